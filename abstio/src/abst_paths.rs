@@ -219,6 +219,16 @@ pub fn path_all_saves(name: &MapName, edits_name: &str, run_name: &str) -> Strin
     ))
 }
 
+pub fn path_trajectories(name: &MapName, edits_name: &str, run_name: &str) -> String {
+    path(format!(
+        "player/trajectories/{}/{}/{}_{}.bin",
+        name.city, name.map, edits_name, run_name
+    ))
+}
+pub fn path_all_trajectories(name: &MapName) -> String {
+    path(format!("player/trajectories/{}/{}", name.city, name.map))
+}
+
 // Input data (For developers to build maps, not needed at runtime)
 
 pub fn path_popdat() -> String {
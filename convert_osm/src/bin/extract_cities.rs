@@ -1,17 +1,16 @@
-//! Extracts all cities from a large .osm file.
+//! Finds all cities in a large .osm or .osm.pbf file.
 //!
-//! 1) Reads a large .osm file
+//! 1) Reads a large .osm or .osm.pbf file
 //! 2) Finds all boundary relations representing cities
 //! 3) Calculates the polygon covering that city
-//! 4) Uses osmconvert to clip the large .osm to a smaller one with just the city
+//! 4) Writes that polygon to a .poly file
 //!
-//! This tool writes all output files (.poly boundaries and .osm extracts) in the current
-//! directory!
+//! The importer clips the large file to each .poly while reading it, so there's no need to write
+//! smaller extracts. This tool writes all output files in the current directory!
 
 use abstutil::{CmdArgs, Timer};
 use geom::{Circle, Distance, GPSBounds, LonLat, Polygon};
 use map_model::osm::OsmID;
-use std::process::Command;
 
 fn main() {
     let mut args = CmdArgs::new();
@@ -24,7 +23,11 @@ fn main() {
     let mut timer = Timer::new(format!("extract cities from {}", input));
 
     // Infer the boundary of the input from the <bounds> tag
-    let doc = convert_osm::reader::read(&input, &GPSBounds::new(), &mut timer).unwrap();
+    let doc = if input.ends_with(".pbf") {
+        convert_osm::pbf::read(&input, &GPSBounds::new(), None, &mut timer).unwrap()
+    } else {
+        convert_osm::reader::read(&input, &GPSBounds::new(), &mut timer).unwrap()
+    };
     for (id, rel) in &doc.relations {
         if !rel.tags.is("border_type", "city") && !rel.tags.is("place", "city") {
             continue;
@@ -72,19 +75,10 @@ fn main() {
             Polygon::convex_hull(polygons)
         };
 
-        let clipping_polygon = format!("{}.poly", name);
         LonLat::write_osmosis_polygon(
-            &clipping_polygon,
+            &format!("{}.poly", name),
             &doc.gps_bounds.convert_back(clip.points()),
         )
         .unwrap();
-
-        abstutil::must_run_cmd(
-            Command::new("osmconvert")
-                .arg(&input)
-                .arg(format!("-B={}", clipping_polygon))
-                .arg("--complete-ways")
-                .arg(format!("-o={}.osm", name)),
-        );
     }
 }
@@ -22,12 +22,19 @@ pub struct OsmExtract {
     pub simple_turn_restrictions: Vec<(RestrictionType, WayID, NodeID, WayID)>,
     /// (relation ID, from way ID, via way ID, to way ID)
     pub complicated_turn_restrictions: Vec<(RelationID, WayID, WayID, WayID)>,
+    /// (from way ID, via node ID, to way ID, pairs of connected (from lane, to lane))
+    pub lane_connectivity: Vec<(WayID, NodeID, WayID, BTreeSet<(usize, usize)>)>,
     /// (location, amenity)
     pub amenities: Vec<(Pt2D, Amenity)>,
 }
 
 pub fn extract_osm(map: &mut RawMap, opts: &Options, timer: &mut Timer) -> OsmExtract {
-    let mut doc = crate::reader::read(&opts.osm_input, &map.gps_bounds, timer).unwrap();
+    let mut doc = if opts.osm_input.ends_with(".pbf") || opts.osm_input.ends_with(".bin") {
+        let clip = opts.clip.as_ref().map(|_| &map.boundary_polygon);
+        crate::pbf::read(&opts.osm_input, &map.gps_bounds, clip, timer).unwrap()
+    } else {
+        crate::reader::read(&opts.osm_input, &map.gps_bounds, timer).unwrap()
+    };
 
     // Use this to quickly test overrides to some ways before upstreaming in OSM.
     if false {
@@ -60,6 +67,7 @@ pub fn extract_osm(map: &mut RawMap, opts: &Options, timer: &mut Timer) -> OsmEx
         osm_node_ids: HashMap::new(),
         simple_turn_restrictions: Vec::new(),
         complicated_turn_restrictions: Vec::new(),
+        lane_connectivity: Vec::new(),
         amenities: Vec::new(),
     };
 
@@ -106,6 +114,7 @@ pub fn extract_osm(map: &mut RawMap, opts: &Options, timer: &mut Timer) -> OsmEx
                     osm_tags: way.tags.clone(),
                     turn_restrictions: Vec::new(),
                     complicated_turn_restrictions: Vec::new(),
+                    lane_connectivity: Vec::new(),
                 },
             ));
             continue;
@@ -258,6 +267,40 @@ pub fn extract_osm(map: &mut RawMap, opts: &Options, timer: &mut Timer) -> OsmEx
                     }
                 }
             }
+        } else if rel.tags.is("type", "connectivity") {
+            let mut from_way_id: Option<WayID> = None;
+            let mut via_node_id: Option<NodeID> = None;
+            let mut to_way_id: Option<WayID> = None;
+            for (role, member) in &rel.members {
+                match member {
+                    OsmID::Way(w) => {
+                        if role == "from" {
+                            from_way_id = Some(*w);
+                        } else if role == "to" {
+                            to_way_id = Some(*w);
+                        }
+                    }
+                    OsmID::Node(n) => {
+                        if role == "via" {
+                            via_node_id = Some(*n);
+                        }
+                    }
+                    OsmID::Relation(_) => {}
+                }
+            }
+            // TODO Handle connectivity via a way
+            if let (Some(from), Some(via), Some(to), Some(lanes)) = (
+                from_way_id,
+                via_node_id,
+                to_way_id,
+                rel.tags
+                    .get("connectivity")
+                    .and_then(|x| parse_lane_connectivity(x)),
+            ) {
+                out.lane_connectivity.push((from, via, to, lanes));
+            } else {
+                warn!("Skipping connectivity relation {}", id);
+            }
         } else if is_bldg(&rel.tags) {
             match multipoly_geometry(id, rel, &doc) {
                 Ok(polygon) => {
@@ -387,6 +430,31 @@ pub fn extract_osm(map: &mut RawMap, opts: &Options, timer: &mut Timer) -> OsmEx
     out
 }
 
+/// Parses something like "1:1|2:2,3|3:(3)" from
+/// <https://wiki.openstreetmap.org/wiki/Relation:connectivity>. Lanes are numbered from 1 in OSM,
+/// but the result counts from 0. Optional lanes (in parentheses) are treated like any other.
+fn parse_lane_connectivity(value: &str) -> Option<BTreeSet<(usize, usize)>> {
+    let mut pairs = BTreeSet::new();
+    for group in value.split('|') {
+        let mut parts = group.split(':');
+        let from = parts.next()?.trim().parse::<usize>().ok()?.checked_sub(1)?;
+        for to in parts.next()?.split(',') {
+            let to = to
+                .trim()
+                .trim_start_matches('(')
+                .trim_end_matches(')')
+                .parse::<usize>()
+                .ok()?
+                .checked_sub(1)?;
+            pairs.insert((from, to));
+        }
+        if parts.next().is_some() {
+            return None;
+        }
+    }
+    Some(pairs)
+}
+
 fn is_road(tags: &mut Tags, opts: &Options) -> bool {
     if tags.is("area", "yes") {
         return false;
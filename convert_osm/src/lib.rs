@@ -14,6 +14,7 @@ mod clip;
 mod extract;
 pub mod osm_geom;
 mod parking;
+pub mod pbf;
 pub mod reader;
 mod snappy;
 mod split_ways;
@@ -21,6 +22,8 @@ mod srtm;
 mod transit;
 
 pub struct Options {
+    /// An .osm or .osm.pbf file, or a .bin written by `pbf::clip_to_file`. A .pbf covering a much
+    /// larger region is fine; objects outside the `clip` polygon are skipped while reading.
     pub osm_input: String,
     pub name: MapName,
 
@@ -0,0 +1,845 @@
+//! Reads the .osm.pbf format (<https://wiki.openstreetmap.org/wiki/PBF_Format>) directly, so a
+//! regional extract from Geofabrik can be imported without first converting it to XML.
+//!
+//! The input is streamed once. Nodes inside the clipping polygon are kept, along with every way
+//! touching one of them, and relations with any member kept. Like `osmconvert --complete-ways`,
+//! ways crossing the boundary keep their nodes outside it, as long as those are within
+//! `NEARBY_DEGREES` of the clipping polygon's bounds. Nodes come before the ways referring to them
+//! in the file, so remembering the nearby nodes avoids a second pass, without holding onto the
+//! whole input.
+//!
+//! Importing many maps from one large file still streams all of it for each map. `clip_to_file`
+//! clips it to a region covering all of them once, and `read` accepts the result instead.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read};
+
+use anyhow::Result;
+use flate2::read::ZlibDecoder;
+use serde::{Deserialize, Serialize};
+
+use abstutil::{prettyprint_usize, Tags, Timer};
+use geom::{GPSBounds, LonLat, Polygon};
+use map_model::osm::{NodeID, OsmID, RelationID, WayID};
+
+use crate::reader::{keep_tag, Document, Node, Relation, Way};
+
+/// How far past the clipping polygon to keep nodes, in case ways crossing the boundary need them.
+/// Ways with a node even further out are cut short there, but still reach past the boundary.
+const NEARBY_DEGREES: f64 = 0.05;
+
+/// Like `reader::read`, but for .osm.pbf files, or a file written by `clip_to_file`. If `clip` is
+/// provided, only objects inside it (in the coordinate space of `input_gps_bounds`) are kept.
+pub fn read(
+    path: &str,
+    input_gps_bounds: &GPSBounds,
+    clip: Option<&Polygon>,
+    timer: &mut Timer,
+) -> Result<Document> {
+    let extract = clip_elements(path, input_gps_bounds, clip, timer)?;
+
+    let mut doc = Document {
+        gps_bounds: input_gps_bounds.clone(),
+        nodes: BTreeMap::new(),
+        ways: BTreeMap::new(),
+        relations: BTreeMap::new(),
+    };
+    // If we weren't provided with GPSBounds, use the file's, or as a last resort, figure it out.
+    if doc.gps_bounds == GPSBounds::new() {
+        if let Some(bounds) = extract.bounds {
+            doc.gps_bounds = bounds;
+        } else {
+            warn!(
+                "No clipping polygon provided and the .osm.pbf is missing a bounding box, so \
+                 figuring out the bounds manually."
+            );
+            for (pt, _) in extract.nodes.values() {
+                doc.gps_bounds.update(*pt);
+            }
+        }
+    }
+
+    for (id, (pt, tags)) in extract.nodes {
+        doc.nodes.insert(
+            id,
+            Node {
+                pt: pt.to_pt(&doc.gps_bounds),
+                tags,
+            },
+        );
+    }
+    for (id, refs, tags) in extract.ways {
+        if doc.ways.contains_key(&id) {
+            bail!("Duplicate {}, your .osm.pbf is corrupt", id);
+        }
+        let mut nodes = Vec::new();
+        let mut pts = Vec::new();
+        // Just skip missing nodes, which're far outside the boundary
+        for n in refs {
+            if let Some(node) = doc.nodes.get(&n) {
+                nodes.push(n);
+                pts.push(node.pt);
+            }
+        }
+        if !nodes.is_empty() {
+            doc.ways.insert(id, Way { nodes, pts, tags });
+        }
+    }
+    // Only keep relations that refer to something kept, possibly through other relations.
+    let mut kept_relations: HashSet<RelationID> = HashSet::new();
+    loop {
+        let before = kept_relations.len();
+        for (id, members, _) in &extract.relations {
+            if kept_relations.contains(id) {
+                continue;
+            }
+            if members.iter().any(|(_, member)| match member {
+                OsmID::Node(n) => doc.nodes.contains_key(n),
+                OsmID::Way(w) => doc.ways.contains_key(w),
+                OsmID::Relation(r) => kept_relations.contains(r),
+            }) {
+                kept_relations.insert(*id);
+            }
+        }
+        if kept_relations.len() == before {
+            break;
+        }
+    }
+    for (id, members, tags) in extract.relations {
+        if !kept_relations.contains(&id) {
+            continue;
+        }
+        let members = members
+            .into_iter()
+            .filter(|(_, member)| match member {
+                OsmID::Node(n) => doc.nodes.contains_key(n),
+                OsmID::Way(w) => doc.ways.contains_key(w),
+                OsmID::Relation(r) => kept_relations.contains(r),
+            })
+            .collect();
+        doc.relations.insert(id, Relation { tags, members });
+    }
+
+    info!(
+        "Found {} nodes, {} ways, {} relations",
+        prettyprint_usize(doc.nodes.len()),
+        prettyprint_usize(doc.ways.len()),
+        prettyprint_usize(doc.relations.len())
+    );
+
+    Ok(doc)
+}
+
+/// Clips a large .osm.pbf file (or a previous output of this) to a region, and writes what's kept
+/// to `output`, which must end in `.bin`. Reading a map inside the region from `output` gives the
+/// same result as reading it from `input`, without streaming the whole input again.
+pub fn clip_to_file(
+    input: &str,
+    region: &GPSBounds,
+    output: String,
+    timer: &mut Timer,
+) -> Result<()> {
+    let clip = region.to_bounds().get_rectangle();
+    let mut extract = clip_elements(input, region, Some(&clip), timer)?;
+    extract.bounds = Some(region.clone());
+    info!(
+        "Clipped {} to {} nodes, {} ways, {} relations",
+        input,
+        prettyprint_usize(extract.nodes.len()),
+        prettyprint_usize(extract.ways.len()),
+        prettyprint_usize(extract.relations.len())
+    );
+    abstio::write_binary(output, &extract);
+    Ok(())
+}
+
+/// The elements of a file kept by clipping, still in GPS coordinates and in the file's order.
+/// Relations haven't been filtered yet, since they can refer to later relations.
+#[derive(Serialize, Deserialize)]
+struct Extract {
+    /// The bounding box of the input, if it has one
+    bounds: Option<GPSBounds>,
+    nodes: BTreeMap<NodeID, (LonLat, Tags)>,
+    ways: Vec<(WayID, Vec<NodeID>, Tags)>,
+    relations: Vec<(RelationID, Vec<(String, OsmID)>, Tags)>,
+}
+
+fn clip_elements(
+    path: &str,
+    input_gps_bounds: &GPSBounds,
+    clip: Option<&Polygon>,
+    timer: &mut Timer,
+) -> Result<Extract> {
+    let is_inside = |pt: LonLat| match clip {
+        Some(polygon) => {
+            input_gps_bounds.contains(pt) && polygon.contains_pt(pt.to_pt(input_gps_bounds))
+        }
+        None => true,
+    };
+    let nearby_bounds = clip.map(|polygon| {
+        let gps = input_gps_bounds.convert_back(polygon.points());
+        let mut b = GPSBounds::from(gps);
+        b.min_lon -= NEARBY_DEGREES;
+        b.min_lat -= NEARBY_DEGREES;
+        b.max_lon += NEARBY_DEGREES;
+        b.max_lat += NEARBY_DEGREES;
+        b
+    });
+
+    timer.start(format!("read {}", path));
+    let mut nodes: BTreeMap<NodeID, (LonLat, Tags)> = BTreeMap::new();
+    let mut nearby_nodes: HashMap<NodeID, (LonLat, Tags)> = HashMap::new();
+    let mut ways: Vec<(WayID, Vec<NodeID>, Tags)> = Vec::new();
+    let mut kept_ways: HashSet<WayID> = HashSet::new();
+    let mut relations: Vec<(RelationID, Vec<(String, OsmID)>, Tags)> = Vec::new();
+    let bounds = for_each_element(path, timer, |elem| {
+        match elem {
+            Element::Node(id, pt, tags) => {
+                if nodes.contains_key(&id) || nearby_nodes.contains_key(&id) {
+                    bail!("Duplicate {}, your .osm.pbf is corrupt", id);
+                }
+                if is_inside(pt) {
+                    nodes.insert(id, (pt, tags));
+                } else if nearby_bounds
+                    .as_ref()
+                    .map(|b| b.contains(pt))
+                    .unwrap_or(false)
+                {
+                    nearby_nodes.insert(id, (pt, tags));
+                }
+            }
+            Element::Way(id, refs, tags) => {
+                if refs.iter().any(|n| nodes.contains_key(n)) {
+                    kept_ways.insert(id);
+                    ways.push((id, refs, tags));
+                }
+            }
+            Element::Relation(id, members, tags) => {
+                // Relations can refer to later relations, so keep all of those for now
+                if members.iter().any(|(_, member)| match member {
+                    OsmID::Node(n) => nodes.contains_key(n) || nearby_nodes.contains_key(n),
+                    OsmID::Way(w) => kept_ways.contains(w),
+                    OsmID::Relation(_) => true,
+                }) {
+                    relations.push((id, members, tags));
+                }
+            }
+        }
+        Ok(())
+    })?;
+    timer.stop(format!("read {}", path));
+
+    // Pull in the nodes outside the boundary that kept ways need
+    for (_, refs, _) in &ways {
+        for n in refs {
+            if let Some(node) = nearby_nodes.remove(n) {
+                nodes.insert(*n, node);
+            }
+        }
+    }
+    drop(nearby_nodes);
+
+    Ok(Extract {
+        bounds,
+        nodes,
+        ways,
+        relations,
+    })
+}
+
+enum Element {
+    Node(NodeID, LonLat, Tags),
+    Way(WayID, Vec<NodeID>, Tags),
+    Relation(RelationID, Vec<(String, OsmID)>, Tags),
+}
+
+/// Calls `cb` with every element of a .osm.pbf file or a file written by `clip_to_file`, in order.
+/// Returns the bounding box of the input, if there is one.
+fn for_each_element<F: FnMut(Element) -> Result<()>>(
+    path: &str,
+    timer: &mut Timer,
+    mut cb: F,
+) -> Result<Option<GPSBounds>> {
+    if !path.ends_with(".bin") {
+        return read_elements(path, cb);
+    }
+    let extract: Extract = abstio::maybe_read_binary(path.to_string(), timer)?;
+    for (id, (pt, tags)) in extract.nodes {
+        cb(Element::Node(id, pt, tags))?;
+    }
+    for (id, refs, tags) in extract.ways {
+        cb(Element::Way(id, refs, tags))?;
+    }
+    for (id, members, tags) in extract.relations {
+        cb(Element::Relation(id, members, tags))?;
+    }
+    Ok(extract.bounds)
+}
+
+/// Streams every element from the file, in order. Returns the bounding box from the header, if
+/// there is one.
+fn read_elements<F: FnMut(Element) -> Result<()>>(
+    path: &str,
+    mut cb: F,
+) -> Result<Option<GPSBounds>> {
+    let mut file = BufReader::new(File::open(path)?);
+    let mut bounds = None;
+    loop {
+        let mut len = [0; 4];
+        match file.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.into()),
+        }
+        let mut header = vec![0; u32::from_be_bytes(len) as usize];
+        file.read_exact(&mut header)?;
+
+        let mut blob_type = String::new();
+        let mut blob_size = 0;
+        for (field, value) in fields(&header)? {
+            match field {
+                1 => blob_type = String::from_utf8(value.bytes()?.to_vec())?,
+                3 => blob_size = value.varint()? as usize,
+                _ => {}
+            }
+        }
+        let mut blob = vec![0; blob_size];
+        file.read_exact(&mut blob)?;
+        let data = decompress(&blob)?;
+
+        match blob_type.as_ref() {
+            "OSMHeader" => {
+                bounds = read_header(&data)?;
+            }
+            "OSMData" => {
+                read_primitive_block(&data, &mut cb)?;
+            }
+            // Unknown blobs are meant to be skipped
+            _ => {}
+        }
+    }
+    Ok(bounds)
+}
+
+fn decompress(blob: &[u8]) -> Result<Vec<u8>> {
+    let mut raw_size = 0;
+    for (field, value) in fields(blob)? {
+        match field {
+            1 => {
+                return Ok(value.bytes()?.to_vec());
+            }
+            2 => {
+                raw_size = value.varint()? as usize;
+            }
+            3 => {
+                let mut out = Vec::with_capacity(raw_size);
+                ZlibDecoder::new(value.bytes()?).read_to_end(&mut out)?;
+                return Ok(out);
+            }
+            4 | 5 | 6 | 7 => {
+                bail!("Only zlib-compressed .osm.pbf files are supported");
+            }
+            _ => {}
+        }
+    }
+    bail!("Blob has no data")
+}
+
+fn read_header(data: &[u8]) -> Result<Option<GPSBounds>> {
+    let mut bounds = None;
+    for (field, value) in fields(data)? {
+        match field {
+            1 => {
+                let (mut left, mut right, mut top, mut bottom) = (0, 0, 0, 0);
+                for (field, value) in fields(value.bytes()?)? {
+                    match field {
+                        1 => left = value.sint()?,
+                        2 => right = value.sint()?,
+                        3 => top = value.sint()?,
+                        4 => bottom = value.sint()?,
+                        _ => {}
+                    }
+                }
+                let mut b = GPSBounds::new();
+                b.update(LonLat::new(left as f64 * 1e-9, bottom as f64 * 1e-9));
+                b.update(LonLat::new(right as f64 * 1e-9, top as f64 * 1e-9));
+                bounds = Some(b);
+            }
+            4 => {
+                let feature = std::str::from_utf8(value.bytes()?)?;
+                if feature != "OsmSchema-V0.6" && feature != "DenseNodes" {
+                    bail!("The .osm.pbf requires an unsupported feature: {}", feature);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(bounds)
+}
+
+fn read_primitive_block<F: FnMut(Element) -> Result<()>>(data: &[u8], cb: &mut F) -> Result<()> {
+    let mut strings: Vec<String> = Vec::new();
+    let mut groups = Vec::new();
+    let mut granularity = 100;
+    let mut lat_offset = 0;
+    let mut lon_offset = 0;
+    for (field, value) in fields(data)? {
+        match field {
+            1 => {
+                for (field, value) in fields(value.bytes()?)? {
+                    if field == 1 {
+                        strings.push(String::from_utf8_lossy(value.bytes()?).into_owned());
+                    }
+                }
+            }
+            2 => groups.push(value.bytes()?),
+            17 => granularity = value.varint()? as i64,
+            19 => lat_offset = value.varint()? as i64,
+            20 => lon_offset = value.varint()? as i64,
+            _ => {}
+        }
+    }
+    let block = Block {
+        strings,
+        granularity,
+        lat_offset,
+        lon_offset,
+    };
+
+    for group in groups {
+        for (field, value) in fields(group)? {
+            match field {
+                1 => block.read_node(value.bytes()?, cb)?,
+                2 => block.read_dense_nodes(value.bytes()?, cb)?,
+                3 => block.read_way(value.bytes()?, cb)?,
+                4 => block.read_relation(value.bytes()?, cb)?,
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+struct Block {
+    strings: Vec<String>,
+    granularity: i64,
+    lat_offset: i64,
+    lon_offset: i64,
+}
+
+impl Block {
+    fn string(&self, idx: u64) -> Result<&str> {
+        self.strings
+            .get(idx as usize)
+            .map(|s| s.as_str())
+            .ok_or_else(|| anyhow!("String table doesn't have {}", idx))
+    }
+
+    fn pt(&self, lon: i64, lat: i64) -> LonLat {
+        LonLat::new(
+            1e-9 * (self.lon_offset + self.granularity * lon) as f64,
+            1e-9 * (self.lat_offset + self.granularity * lat) as f64,
+        )
+    }
+
+    fn tags(&self, keys: Vec<u64>, vals: Vec<u64>) -> Result<Tags> {
+        let mut tags = Tags::new(BTreeMap::new());
+        for (k, v) in keys.into_iter().zip(vals) {
+            let key = self.string(k)?;
+            if keep_tag(key) {
+                tags.insert(key, self.string(v)?);
+            }
+        }
+        Ok(tags)
+    }
+
+    fn read_node<F: FnMut(Element) -> Result<()>>(&self, data: &[u8], cb: &mut F) -> Result<()> {
+        let (mut id, mut lon, mut lat) = (0, 0, 0);
+        let mut keys = Vec::new();
+        let mut vals = Vec::new();
+        for (field, value) in fields(data)? {
+            match field {
+                1 => id = value.sint()?,
+                2 => keys.extend(value.packed()?),
+                3 => vals.extend(value.packed()?),
+                8 => lat = value.sint()?,
+                9 => lon = value.sint()?,
+                _ => {}
+            }
+        }
+        cb(Element::Node(
+            NodeID(id),
+            self.pt(lon, lat),
+            self.tags(keys, vals)?,
+        ))
+    }
+
+    fn read_dense_nodes<F: FnMut(Element) -> Result<()>>(
+        &self,
+        data: &[u8],
+        cb: &mut F,
+    ) -> Result<()> {
+        let mut ids = Vec::new();
+        let mut lats = Vec::new();
+        let mut lons = Vec::new();
+        let mut keys_vals = Vec::new();
+        for (field, value) in fields(data)? {
+            match field {
+                1 => ids.extend(value.packed()?),
+                8 => lats.extend(value.packed()?),
+                9 => lons.extend(value.packed()?),
+                10 => keys_vals.extend(value.packed()?),
+                _ => {}
+            }
+        }
+        if ids.len() != lats.len() || ids.len() != lons.len() {
+            bail!("DenseNodes has mismatched ids and coordinates");
+        }
+
+        // IDs and coordinates are delta-encoded. Tags for all nodes are packed together, with a 0
+        // ending each node's tags.
+        let (mut id, mut lat, mut lon) = (0, 0, 0);
+        let mut kv = keys_vals.into_iter();
+        for idx in 0..ids.len() {
+            id += zigzag(ids[idx]);
+            lat += zigzag(lats[idx]);
+            lon += zigzag(lons[idx]);
+            let mut tags = Tags::new(BTreeMap::new());
+            while let Some(k) = kv.next() {
+                if k == 0 {
+                    break;
+                }
+                let v = kv
+                    .next()
+                    .ok_or_else(|| anyhow!("DenseNodes tag key without a value"))?;
+                let key = self.string(k)?;
+                if keep_tag(key) {
+                    tags.insert(key, self.string(v)?);
+                }
+            }
+            cb(Element::Node(NodeID(id), self.pt(lon, lat), tags))?;
+        }
+        Ok(())
+    }
+
+    fn read_way<F: FnMut(Element) -> Result<()>>(&self, data: &[u8], cb: &mut F) -> Result<()> {
+        let mut id = 0;
+        let mut keys = Vec::new();
+        let mut vals = Vec::new();
+        let mut refs = Vec::new();
+        for (field, value) in fields(data)? {
+            match field {
+                1 => id = value.varint()? as i64,
+                2 => keys.extend(value.packed()?),
+                3 => vals.extend(value.packed()?),
+                8 => {
+                    let mut n = 0;
+                    for delta in value.packed()? {
+                        n += zigzag(delta);
+                        refs.push(NodeID(n));
+                    }
+                }
+                _ => {}
+            }
+        }
+        cb(Element::Way(WayID(id), refs, self.tags(keys, vals)?))
+    }
+
+    fn read_relation<F: FnMut(Element) -> Result<()>>(
+        &self,
+        data: &[u8],
+        cb: &mut F,
+    ) -> Result<()> {
+        let mut id = 0;
+        let mut keys = Vec::new();
+        let mut vals = Vec::new();
+        let mut roles = Vec::new();
+        let mut member_ids = Vec::new();
+        let mut types = Vec::new();
+        for (field, value) in fields(data)? {
+            match field {
+                1 => id = value.varint()? as i64,
+                2 => keys.extend(value.packed()?),
+                3 => vals.extend(value.packed()?),
+                8 => roles.extend(value.packed()?),
+                9 => member_ids.extend(value.packed()?),
+                10 => types.extend(value.packed()?),
+                _ => {}
+            }
+        }
+        if roles.len() != member_ids.len() || roles.len() != types.len() {
+            bail!("Relation {} has mismatched members", id);
+        }
+
+        let mut members = Vec::new();
+        let mut member = 0;
+        for idx in 0..roles.len() {
+            member += zigzag(member_ids[idx]);
+            let role = self.string(roles[idx])?.to_string();
+            match types[idx] {
+                0 => members.push((role, OsmID::Node(NodeID(member)))),
+                1 => members.push((role, OsmID::Way(WayID(member)))),
+                2 => members.push((role, OsmID::Relation(RelationID(member)))),
+                x => bail!("Relation {} has a member of unknown type {}", id, x),
+            }
+        }
+        cb(Element::Relation(
+            RelationID(id),
+            members,
+            self.tags(keys, vals)?,
+        ))
+    }
+}
+
+// Just enough of the protobuf wire format (https://developers.google.com/protocol-buffers/docs/encoding)
+// to read OSM data.
+
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    /// 32 or 64-bit values aren't used by the OSM format
+    Fixed,
+}
+
+impl<'a> Value<'a> {
+    fn varint(&self) -> Result<u64> {
+        match self {
+            Value::Varint(x) => Ok(*x),
+            _ => bail!("Expected a varint"),
+        }
+    }
+
+    fn sint(&self) -> Result<i64> {
+        Ok(zigzag(self.varint()?))
+    }
+
+    fn bytes(&self) -> Result<&'a [u8]> {
+        match self {
+            Value::Bytes(x) => Ok(*x),
+            _ => bail!("Expected bytes"),
+        }
+    }
+
+    /// Repeated numbers are usually packed together, but may be specified one at a time.
+    fn packed(&self) -> Result<Vec<u64>> {
+        match self {
+            Value::Varint(x) => Ok(vec![*x]),
+            Value::Bytes(buf) => {
+                let mut result = Vec::new();
+                let mut pos = 0;
+                while pos < buf.len() {
+                    result.push(read_varint(buf, &mut pos)?);
+                }
+                Ok(result)
+            }
+            Value::Fixed => bail!("Expected packed varints"),
+        }
+    }
+}
+
+fn fields(buf: &[u8]) -> Result<Vec<(u32, Value)>> {
+    let mut result = Vec::new();
+    let mut pos = 0;
+    while pos < buf.len() {
+        let key = read_varint(buf, &mut pos)?;
+        let field = (key >> 3) as u32;
+        let value = match key & 7 {
+            0 => Value::Varint(read_varint(buf, &mut pos)?),
+            1 => {
+                pos += 8;
+                Value::Fixed
+            }
+            2 => {
+                let len = read_varint(buf, &mut pos)? as usize;
+                if pos + len > buf.len() {
+                    bail!("Truncated protobuf message");
+                }
+                pos += len;
+                Value::Bytes(&buf[pos - len..pos])
+            }
+            5 => {
+                pos += 4;
+                Value::Fixed
+            }
+            wire_type => bail!("Unknown protobuf wire type {}", wire_type),
+        };
+        result.push((field, value));
+    }
+    Ok(result)
+}
+
+fn read_varint(buf: &[u8], pos: &mut usize) -> Result<u64> {
+    let mut result = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *buf
+            .get(*pos)
+            .ok_or_else(|| anyhow!("Truncated protobuf varint"))?;
+        *pos += 1;
+        result |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+        shift += 7;
+        if shift >= 64 {
+            bail!("Protobuf varint is too long");
+        }
+    }
+}
+
+fn zigzag(x: u64) -> i64 {
+    ((x >> 1) as i64) ^ -((x & 1) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// tests/tiny.osm.pbf has:
+    /// - nodes 1 and 2 (dense) and 5 (not dense) inside a 0.01 degree square at the origin
+    /// - node 3 just outside the square and node 4 far away
+    /// - way 10 crossing the boundary through 1, 2, 3, way 11 from 3 to 4, and way 12 from 2 to 4
+    /// - relation 20 with ways 10 and 11, 21 with just way 11, and 22 and 23 referring to 20 and
+    ///   21
+    fn fixture() -> String {
+        format!("{}/tests/tiny.osm.pbf", env!("CARGO_MANIFEST_DIR"))
+    }
+
+    #[test]
+    fn test_read_clipped() {
+        let gps_bounds = GPSBounds::from(vec![LonLat::new(0.0, 0.0), LonLat::new(0.01, 0.01)]);
+        let clip = gps_bounds.to_bounds().get_rectangle();
+        let doc = read(
+            &fixture(),
+            &gps_bounds,
+            Some(&clip),
+            &mut Timer::throwaway(),
+        )
+        .unwrap();
+
+        // Node 3 is outside, but way 10 needs it
+        assert_eq!(
+            doc.nodes.keys().cloned().collect::<Vec<_>>(),
+            vec![NodeID(1), NodeID(2), NodeID(3), NodeID(5)]
+        );
+        assert!(doc.nodes[&NodeID(1)].tags.is("highway", "traffic_signals"));
+        assert!(doc.nodes[&NodeID(5)].tags.is("name", "Corner"));
+        assert!(!doc.nodes[&NodeID(5)].tags.contains_key("tiger:cfcc"));
+        let pt = gps_bounds.convert_back(&vec![doc.nodes[&NodeID(2)].pt])[0];
+        assert!((pt.x() - 0.004).abs() < 1e-7 && (pt.y() - 0.004).abs() < 1e-7);
+
+        assert_eq!(
+            doc.ways.keys().cloned().collect::<Vec<_>>(),
+            vec![WayID(10), WayID(12)]
+        );
+        let way = &doc.ways[&WayID(10)];
+        assert_eq!(way.nodes, vec![NodeID(1), NodeID(2), NodeID(3)]);
+        assert_eq!(way.pts.len(), 3);
+        assert!(way.tags.is("highway", "residential"));
+        assert!(!way.tags.contains_key("tiger:cfcc"));
+        // Node 4 is too far away to keep
+        assert_eq!(doc.ways[&WayID(12)].nodes, vec![NodeID(2)]);
+
+        assert_eq!(
+            doc.relations.keys().cloned().collect::<Vec<_>>(),
+            vec![RelationID(20), RelationID(22)]
+        );
+        assert_eq!(
+            doc.relations[&RelationID(20)].members,
+            vec![("outer".to_string(), OsmID::Way(WayID(10)))]
+        );
+        assert_eq!(
+            doc.relations[&RelationID(22)].members,
+            vec![("".to_string(), OsmID::Relation(RelationID(20)))]
+        );
+    }
+
+    #[test]
+    fn test_read_everything() {
+        let doc = read(&fixture(), &GPSBounds::new(), None, &mut Timer::throwaway()).unwrap();
+
+        // The bounds come from the header
+        assert_eq!(
+            doc.gps_bounds,
+            GPSBounds::from(vec![LonLat::new(0.0, 0.0), LonLat::new(10.0, 10.0)])
+        );
+        assert_eq!(doc.nodes.len(), 5);
+        assert_eq!(doc.ways.len(), 3);
+        assert_eq!(doc.ways[&WayID(11)].nodes, vec![NodeID(3), NodeID(4)]);
+        assert_eq!(doc.relations.len(), 4);
+    }
+
+    #[test]
+    fn test_clip_to_file() {
+        let gps_bounds = GPSBounds::from(vec![LonLat::new(0.0, 0.0), LonLat::new(0.01, 0.01)]);
+        let clip = gps_bounds.to_bounds().get_rectangle();
+        let direct = read(
+            &fixture(),
+            &gps_bounds,
+            Some(&clip),
+            &mut Timer::throwaway(),
+        )
+        .unwrap();
+
+        // Clip to a region around the map first, then read the map from that
+        let region = GPSBounds::from(vec![LonLat::new(-0.01, -0.01), LonLat::new(0.02, 0.02)]);
+        let path = std::env::temp_dir()
+            .join(format!("clip_to_file_{}.bin", std::process::id()))
+            .display()
+            .to_string();
+        clip_to_file(&fixture(), &region, path.clone(), &mut Timer::throwaway()).unwrap();
+        let clipped = read(&path, &gps_bounds, Some(&clip), &mut Timer::throwaway()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            clipped.nodes.keys().collect::<Vec<_>>(),
+            direct.nodes.keys().collect::<Vec<_>>()
+        );
+        for (id, node) in &direct.nodes {
+            let pt = clipped.nodes[id].pt;
+            assert_eq!((pt.x(), pt.y()), (node.pt.x(), node.pt.y()));
+            assert_eq!(clipped.nodes[id].tags, node.tags);
+        }
+        assert_eq!(
+            clipped.ways.keys().collect::<Vec<_>>(),
+            direct.ways.keys().collect::<Vec<_>>()
+        );
+        for (id, way) in &direct.ways {
+            assert_eq!(clipped.ways[id].nodes, way.nodes);
+        }
+        assert_eq!(
+            clipped.relations.keys().collect::<Vec<_>>(),
+            direct.relations.keys().collect::<Vec<_>>()
+        );
+        for (id, rel) in &direct.relations {
+            assert_eq!(clipped.relations[id].members, rel.members);
+        }
+    }
+
+    #[test]
+    fn test_varints() {
+        let buf = [0x96, 0x01, 0x03];
+        let mut pos = 0;
+        assert_eq!(read_varint(&buf, &mut pos).unwrap(), 150);
+        assert_eq!(read_varint(&buf, &mut pos).unwrap(), 3);
+        assert!(read_varint(&buf, &mut pos).is_err());
+
+        assert_eq!(zigzag(0), 0);
+        assert_eq!(zigzag(1), -1);
+        assert_eq!(zigzag(2), 1);
+        assert_eq!(zigzag(3), -2);
+    }
+
+    #[test]
+    fn test_fields() {
+        // Field 1 = varint 150, field 2 = packed [3, 270]
+        let buf = [0x08, 0x96, 0x01, 0x12, 0x03, 0x03, 0x8e, 0x02];
+        let parsed = fields(&buf).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].0, 1);
+        assert_eq!(parsed[0].1.varint().unwrap(), 150);
+        assert_eq!(parsed[1].0, 2);
+        assert_eq!(parsed[1].1.packed().unwrap(), vec![3, 270]);
+    }
+}
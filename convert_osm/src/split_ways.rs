@@ -167,6 +167,25 @@ pub fn split_up_roads(
             .push((via, to));
     }
 
+    // Resolve lane connectivity (via a node)
+    let mut connectivity = Vec::new();
+    for (from_osm, via_osm, to_osm, lanes) in input.lane_connectivity {
+        let roads = map.roads_per_intersection(via_osm);
+        if let (Some(from), Some(to)) = (
+            roads.iter().find(|r| r.osm_way_id == from_osm),
+            roads.iter().find(|r| r.osm_way_id == to_osm),
+        ) {
+            connectivity.push((*from, *to, lanes));
+        }
+    }
+    for (from, to, lanes) in connectivity {
+        map.roads
+            .get_mut(&from)
+            .unwrap()
+            .lane_connectivity
+            .push((to, lanes));
+    }
+
     timer.start("match traffic signals to intersections");
     // Handle traffic signals tagged on incoming ways and not at intersections
     // (https://wiki.openstreetmap.org/wiki/Tag:highway=traffic%20signals?uselang=en#Tag_all_incoming_ways).
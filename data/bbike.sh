@@ -10,8 +10,6 @@ for city in Aachen Aarhus Adelaide Albuquerque Alexandria Amsterdam Antwerpen Ar
 	echo $city
 	cd bbike_extracts
 	#wget -c https://download.bbbike.org/osm/bbbike/$city/$city.osm.pbf
-	#osmconvert $city.osm.pbf -o=$city.osm
-	#rm -f $city.osm.pbf
 	cd ..
-	#./import.sh --oneshot=`pwd`/bbike_extracts/$city.osm --skip_ch > log_$city
+	#./import.sh --oneshot=`pwd`/bbike_extracts/$city.osm.pbf --skip_ch > log_$city
 done
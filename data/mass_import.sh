@@ -11,19 +11,19 @@ function extract {
 	mkdir -p mass_import
 	cd mass_import
 
-	# First extract all "cities" from the huge bbike files. If two names collide,
-	# the .osm and .poly might mix between the two arbitrarily!
-	# Don't parallelize (-j1); I think osmconvert must eat CPUs, because my system
-	# lags heavily with -j4 here.
+	# First find all "cities" in the huge bbike files. Each gets a directory, so
+	# the import knows which huge file to clip from. Don't parallelize (-j1);
+	# reading a whole huge file takes lots of memory.
 	for raw_extract in `ls ~/bbike_extracts`; do
-		raw_extract=`basename -s .osm $raw_extract`
-		echo "cargo run --release --bin extract_cities -- /home/$USER/bbike_extracts/$raw_extract.osm --radius_around_label_miles=6 > extract_$raw_extract.log 2>&1"
+		raw_extract=`basename -s .osm.pbf $raw_extract`
+		mkdir -p $raw_extract
+		echo "cd $raw_extract && cargo run --release --bin extract_cities -- /home/$USER/bbike_extracts/$raw_extract.osm.pbf --radius_around_label_miles=6 > ../extract_$raw_extract.log 2>&1"
 	done | parallel --bar -j1
 
 	# Spaces in filenames will mess stuff up
 	# If no files have spaces, the loop fails, so temporarily set +e
 	set +e
-	for f in *\ *; do
+	for f in */*\ *; do
 		mv "$f" "${f// /_}"
 	done
 	cd ..
@@ -32,10 +32,11 @@ function extract {
 function import {
 	set -e
 
-	# Then import each smaller .osm
-	for name in `ls mass_import/*.osm`; do
-		name=`basename -s .osm $name`
-		echo "./import.sh --oneshot=mass_import/$name.osm --oneshot_clip=mass_import/$name.poly --skip_ch --oneshot_dont_infer_sidewalks > mass_import/import_$name.log 2>&1"
+	# Then import each city, clipping from the huge file
+	for poly in `ls mass_import/*/*.poly`; do
+		raw_extract=`basename $(dirname $poly)`
+		name=`basename -s .poly $poly`
+		echo "./import.sh --oneshot=/home/$USER/bbike_extracts/$raw_extract.osm.pbf --oneshot_clip=$poly --skip_ch --oneshot_dont_infer_sidewalks > mass_import/import_$name.log 2>&1"
 	done | parallel --bar -j4
 }
 
@@ -277,6 +277,7 @@ fn build_panel(ctx: &mut EventCtx, app: &App, start: &Building, isochrone: &Isoc
                 "system/assets/tools/map.svg",
                 nice_map_name(app.map.get_name()),
             )
+            .untranslated()
             .hotkey(lctrl(Key::L))
             .build_widget(ctx, "change map"),
     );
@@ -13,7 +13,7 @@ use map_gui::render::{unzoomed_agent_radius, AgentCache, DrawMap, DrawOptions, R
 use map_gui::tools::CameraState;
 use map_gui::ID;
 use map_model::{IntersectionID, LaneID, Map, Traversable};
-use sim::{AgentID, Analytics, Scenario, Sim, SimCallback, SimFlags};
+use sim::{AgentID, Analytics, ObservedCount, Scenario, Sim, SimCallback, SimFlags};
 use widgetry::{Canvas, EventCtx, GfxCtx, Prerender, SharedAppState, State};
 
 use crate::challenges::HighScore;
@@ -602,6 +602,8 @@ pub struct PerMap {
     /// Storing this may cost some memory, but otherwise resetting to midnight would require
     /// loading it again from a file. This is particularly painful on the web!
     pub scenario: Option<Scenario>,
+    /// Real-world counts loaded for validation, along with the file they came from
+    pub observed_counts: Option<(String, Vec<ObservedCount>)>,
 }
 
 impl PerMap {
@@ -634,6 +636,7 @@ impl PerMap {
             suspended_sim: None,
             prebaked: None,
             scenario: None,
+            observed_counts: None,
         }
     }
 
@@ -772,8 +775,9 @@ impl SimCallback for FindDelayedIntersections {
 }
 
 impl SharedAppState for App {
-    fn before_event(&mut self) {
+    fn before_event(&mut self, ctx: &mut EventCtx) {
         self.per_obj.reset();
+        map_gui::AppLike::reload_color_scheme(self, ctx);
     }
 
     fn draw_default(&self, g: &mut GfxCtx) {
@@ -827,3 +831,58 @@ impl SharedAppState for App {
         CameraState::save(canvas, self.primary.map.get_name());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use map_gui::options::OptionsPanel;
+    use widgetry::{Harness, ScreenDims, Settings};
+
+    use super::*;
+
+    /// Like the app before any map is loaded
+    fn blank_app(ctx: &mut EventCtx) -> App {
+        let opts = Options::default();
+        let cs = ColorScheme::new(ctx, opts.color_scheme.clone());
+        let flags = Flags {
+            sim_flags: SimFlags::for_test("test"),
+            live_map_edits: false,
+        };
+        let map = Map::blank();
+        let sim = Sim::new(&map, flags.sim_flags.opts.clone());
+        let primary = PerMap::map_loaded(map, sim, flags, &opts, &cs, ctx, &mut Timer::throwaway());
+        App {
+            primary,
+            cs,
+            opts,
+            per_obj: PerObjectActions::new(),
+            session: SessionState::empty(),
+        }
+    }
+
+    #[test]
+    fn test_options_panel() {
+        let mut harness = Harness::new(
+            Settings::new("test").scale_factor(1.0),
+            ScreenDims::new(1920.0, 2000.0),
+            blank_app,
+        );
+
+        // Closing the panel doesn't change anything
+        let mut state = harness.with_ctx(|ctx, app| OptionsPanel::new(ctx, app));
+        harness.click_button(state.as_mut(), "Enable developer mode");
+        assert!(matches!(
+            harness.click_button(state.as_mut(), "close"),
+            Transition::Pop
+        ));
+        assert!(!harness.app.opts.dev);
+
+        // Applying does
+        let mut state = harness.with_ctx(|ctx, app| OptionsPanel::new(ctx, app));
+        harness.click_button(state.as_mut(), "Enable developer mode");
+        assert!(matches!(
+            harness.click_button(state.as_mut(), "Apply"),
+            Transition::Pop
+        ));
+        assert!(harness.app.opts.dev);
+    }
+}
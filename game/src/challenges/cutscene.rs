@@ -235,7 +235,7 @@ fn make_panel(
                 .btn_light_back("Home")
                 .build_widget(ctx, "quit")
                 .margin_right(100),
-            Line(name).big_heading_styled().draw(ctx),
+            Line(name).big_heading_styled().verbatim().draw(ctx),
         ])
         .margin_below(40),
         inner
@@ -47,7 +47,9 @@ fn prebake(map: &Map, scenario: Scenario, time_limit: Option<Duration>, timer: &
     let mut sim = Sim::new(&map, opts);
     // Bit of an abuse of this, but just need to fix the rng seed.
     let mut rng = SimFlags::for_test("prebaked").make_rng();
-    scenario.instantiate(&mut sim, &map, &mut rng, timer);
+    scenario
+        .instantiate(&mut sim, &map, &mut rng, timer)
+        .unwrap();
     if let Some(dt) = time_limit {
         sim.timed_step(&map, dt, &mut None, timer);
     } else {
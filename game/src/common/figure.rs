@@ -0,0 +1,151 @@
+use map_gui::render::DrawMap;
+use map_gui::tools::grey_out_map;
+use widgetry::{
+    tr, Choice, DrawBaselayer, EventCtx, GfxCtx, Key, Line, Outcome, Panel, Spinner, State,
+    StyledButtons, TextExt, UpdateType, Widget,
+};
+
+use crate::app::{App, Transition};
+
+/// Exports everything on screen as an SVG or PDF, for print-quality figures.
+pub struct ExportFigure {
+    panel: Panel,
+    // If so, stop keeping vector geometry when this is done
+    just_prepared: bool,
+}
+
+impl ExportFigure {
+    /// Only geometry uploaded after this is called appears in figures, so start keeping it and
+    /// render the map again. Returns false if this was already done.
+    pub fn prepare(ctx: &mut EventCtx, app: &mut App) -> bool {
+        if ctx.prerender.is_keeping_vector_geometry() {
+            return false;
+        }
+        ctx.prerender.keep_vector_geometry(true);
+        ctx.loading_screen("prepare to export figures", |ctx, timer| {
+            app.primary.draw_map = DrawMap::new(ctx, &app.primary.map, &app.opts, &app.cs, timer);
+        });
+        app.primary.agents.borrow_mut().invalidate();
+        true
+    }
+
+    /// Undoes `prepare`, rendering the map again without the copies of its geometry.
+    fn finish(ctx: &mut EventCtx, app: &mut App) {
+        ctx.prerender.keep_vector_geometry(false);
+        ctx.loading_screen("finish exporting figures", |ctx, timer| {
+            app.primary.draw_map = DrawMap::new(ctx, &app.primary.map, &app.opts, &app.cs, timer);
+        });
+        app.primary.agents.borrow_mut().invalidate();
+    }
+
+    pub fn new(ctx: &mut EventCtx, app: &App, just_prepared: bool) -> Box<dyn State<App>> {
+        let name = app.primary.map.get_name();
+        Box::new(ExportFigure {
+            panel: Panel::new(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Export figure").small_heading().draw(ctx),
+                    ctx.style().btn_close_widget(ctx),
+                ]),
+                ctx.tr("Everything on screen, except for this panel, is saved as a vector image.")
+                    .draw_text(ctx),
+                if just_prepared && app.primary.layer.is_some() {
+                    ctx.tr("Open the current layer again to include it.")
+                        .draw_text(ctx)
+                } else {
+                    Widget::nothing()
+                },
+                Widget::row(vec![
+                    ctx.tr("Format:").draw_text(ctx).centered_vert(),
+                    Widget::dropdown(
+                        ctx,
+                        "format",
+                        "svg",
+                        vec![Choice::new("SVG", "svg"), Choice::new("PDF", "pdf")],
+                    ),
+                ]),
+                Widget::row(vec![
+                    ctx.tr("Scale:").draw_text(ctx).centered_vert(),
+                    Spinner::new(ctx, (1, 10), 1).named("scale"),
+                ]),
+                tr!(
+                    ctx,
+                    "Figures are saved in {}",
+                    abstio::path_player(format!("figures/{}/{}", name.city, name.map))
+                )
+                .draw_text(ctx),
+                ctx.style()
+                    .btn_solid_dark_text("Export")
+                    .hotkey(Key::Enter)
+                    .build_def(ctx),
+            ]))
+            .build(ctx),
+            just_prepared,
+        })
+    }
+}
+
+impl State<App> for ExportFigure {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    if self.just_prepared {
+                        ExportFigure::finish(ctx, app);
+                    }
+                    Transition::Pop
+                }
+                "Export" => {
+                    let name = app.primary.map.get_name();
+                    let format: &str = self.panel.dropdown_value("format");
+                    let path = abstio::path_player(format!(
+                        "figures/{}/{}/{}.{}",
+                        name.city,
+                        name.map,
+                        app.primary.sim.time().as_filename(),
+                        format
+                    ));
+                    // Once this state is gone, the screen is drawn again and exported
+                    ctx.request_update(UpdateType::ExportVectorImage {
+                        path,
+                        scale: self.panel.spinner("scale") as f64,
+                    });
+                    if self.just_prepared {
+                        // Clean up right after exporting
+                        ctx.request_update(UpdateType::Game);
+                        Transition::Replace(Box::new(FinishExport))
+                    } else {
+                        Transition::Pop
+                    }
+                }
+                _ => unreachable!(),
+            },
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+        self.panel.draw(g);
+    }
+}
+
+/// Draws nothing itself, so the export sees just the states underneath. The first event after
+/// exporting undoes `ExportFigure::prepare`.
+struct FinishExport;
+
+impl State<App> for FinishExport {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ExportFigure::finish(ctx, app);
+        Transition::Pop
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, _: &mut GfxCtx, _: &App) {}
+}
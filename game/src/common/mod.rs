@@ -10,12 +10,14 @@ use widgetry::{
     Widget,
 };
 
+pub use self::figure::ExportFigure;
 pub use self::minimap::MinimapController;
 pub use self::warp::Warping;
 use crate::app::App;
 use crate::app::Transition;
 use crate::info::{ContextualActions, InfoPanel, Tab};
 
+mod figure;
 mod minimap;
 mod warp;
 
@@ -290,7 +292,31 @@ impl CommonState {
 
 // TODO Kinda misnomer
 pub fn tool_panel(ctx: &mut EventCtx) -> Panel {
-    Panel::new(Widget::row(vec![
+    Panel::new(Widget::row(tool_buttons(ctx)))
+        .aligned(HorizontalAlignment::Left, VerticalAlignment::BottomAboveOSD)
+        .build(ctx)
+}
+
+/// Like `tool_panel`, plus ways to save what's on screen.
+pub fn sandbox_tool_panel(ctx: &mut EventCtx) -> Panel {
+    let mut buttons = tool_buttons(ctx);
+    buttons.push(
+        ctx.style()
+            .btn_plain_light_icon("system/assets/tools/save.svg")
+            .build_widget(ctx, "export figure"),
+    );
+    buttons.push(
+        ctx.style()
+            .btn_plain_light_icon("system/assets/tools/record.svg")
+            .build_widget(ctx, "record time-lapse"),
+    );
+    Panel::new(Widget::row(buttons))
+        .aligned(HorizontalAlignment::Left, VerticalAlignment::BottomAboveOSD)
+        .build(ctx)
+}
+
+fn tool_buttons(ctx: &mut EventCtx) -> Vec<Widget> {
+    vec![
         ctx.style()
             .btn_plain_light_icon("system/assets/tools/home.svg")
             .hotkey(Key::Escape)
@@ -298,9 +324,7 @@ pub fn tool_panel(ctx: &mut EventCtx) -> Panel {
         ctx.style()
             .btn_plain_light_icon("system/assets/tools/settings.svg")
             .build_widget(ctx, "settings"),
-    ]))
-    .aligned(HorizontalAlignment::Left, VerticalAlignment::BottomAboveOSD)
-    .build(ctx)
+    ]
 }
 
 pub fn list_names<F: Fn(TextSpan) -> TextSpan>(txt: &mut Text, styler: F, names: BTreeSet<String>) {
@@ -369,6 +393,7 @@ pub fn color_for_trip_phase(app: &App, tpt: TripPhaseType) -> Color {
         TripPhaseType::RidingBus(_, _, _) => app.cs.bus_trip,
         TripPhaseType::Cancelled | TripPhaseType::Finished => unreachable!(),
         TripPhaseType::DelayedStart => Color::YELLOW,
+        TripPhaseType::Escorted => Color::PURPLE,
     }
 }
 
@@ -4,7 +4,7 @@
 use abstutil::Timer;
 use geom::{LonLat, Percent};
 use map_gui::colors::ColorSchemeChoice;
-use map_gui::tools::{nice_map_name, ChooseSomething, CityPicker};
+use map_gui::tools::{nice_map_name, ChooseSomething, CityPicker, PopupMsg};
 use map_gui::AppLike;
 use widgetry::{
     lctrl, Choice, DrawBaselayer, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel,
@@ -17,6 +17,7 @@ mod collisions;
 mod destinations;
 mod kml;
 mod polygon;
+mod replay;
 mod scenario;
 mod story;
 
@@ -38,6 +39,7 @@ impl DevToolsMode {
                     "Change map:".draw_text(ctx),
                     ctx.style()
                         .btn_outline_light_popup(nice_map_name(app.primary.map.get_name()))
+                        .untranslated()
                         .hotkey(lctrl(Key::L))
                         .build_widget(ctx, "change map"),
                 ]),
@@ -62,6 +64,10 @@ impl DevToolsMode {
                         .btn_outline_light_text("story maps")
                         .hotkey(Key::S)
                         .build_def(ctx),
+                    ctx.style()
+                        .btn_outline_light_text("replay trajectories")
+                        .hotkey(Key::R)
+                        .build_def(ctx),
                     if abstio::file_exists(abstio::path(format!(
                         "input/{}/collisions.bin",
                         app.primary.map.get_city_name()
@@ -143,6 +149,49 @@ impl State<App> for DevToolsMode {
                 "view KML" => {
                     return Transition::Push(kml::ViewKML::new(ctx, app, None));
                 }
+                "replay trajectories" => {
+                    return Transition::Push(ChooseSomething::new(
+                        ctx,
+                        "Choose recorded trajectories",
+                        abstio::list_dir(abstio::path_all_trajectories(app.primary.map.get_name()))
+                            .into_iter()
+                            .map(|path| Choice::new(abstutil::basename(&path), path))
+                            .collect(),
+                        Box::new(|path, ctx, app| {
+                            let result = ctx.loading_screen("load trajectories", |_, timer| {
+                                sim::Trajectories::load(path.clone(), timer)
+                            });
+                            let map = &app.primary.map;
+                            match result {
+                                Ok(trajectories)
+                                    if &trajectories.map_name != map.get_name()
+                                        || trajectories.edits_name
+                                            != map.get_edits().edits_name =>
+                                {
+                                    Transition::Replace(PopupMsg::new(
+                                        ctx,
+                                        "Error",
+                                        vec![format!(
+                                            "{} was recorded on {} with edits \"{}\". Load that \
+                                             map and those edits first.",
+                                            path,
+                                            trajectories.map_name.describe(),
+                                            trajectories.edits_name
+                                        )],
+                                    ))
+                                }
+                                Ok(trajectories) => Transition::Replace(
+                                    replay::TrajectoryReplay::new(ctx, app, trajectories),
+                                ),
+                                Err(err) => Transition::Replace(PopupMsg::new(
+                                    ctx,
+                                    "Error",
+                                    vec![format!("Couldn't load {}: {}", path, err)],
+                                )),
+                            }
+                        }),
+                    ));
+                }
                 "story maps" => {
                     return Transition::Push(story::StoryMapEditor::new(ctx));
                 }
@@ -0,0 +1,167 @@
+use abstutil::prettyprint_usize;
+use geom::{Circle, Distance, Time};
+use sim::{AgentType, Trajectories};
+use widgetry::{
+    Choice, Drawable, EventCtx, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel,
+    Slider, State, StyledButtons, Text, TextExt, UpdateType, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+
+/// Plays back trajectories recorded from an earlier simulation. Nothing is simulated; agents are
+/// just drawn where they were recorded, so it's possible to jump to any time instantly.
+pub struct TrajectoryReplay {
+    trajectories: Trajectories,
+    time: Time,
+    playing: bool,
+    draw_agents: Drawable,
+    panel: Panel,
+}
+
+impl TrajectoryReplay {
+    pub fn new(ctx: &mut EventCtx, app: &App, trajectories: Trajectories) -> Box<dyn State<App>> {
+        let panel = Panel::new(Widget::col(vec![
+            Widget::row(vec![
+                Line("Replay trajectories").small_heading().draw(ctx),
+                ctx.style().btn_close_widget(ctx),
+            ]),
+            format!(
+                "{} frames, one every {}",
+                prettyprint_usize(trajectories.num_frames()),
+                trajectories.resolution
+            )
+            .draw_text(ctx),
+            Text::new().draw(ctx).named("time"),
+            Slider::horizontal(ctx, 0.2 * ctx.canvas.window_width, 25.0, 0.0).named("time slider"),
+            Widget::row(vec![
+                ctx.style()
+                    .btn_solid_dark_text("play")
+                    .hotkey(Key::Space)
+                    .build_widget(ctx, "play/pause")
+                    .named("play/pause"),
+                Widget::dropdown(
+                    ctx,
+                    "speed",
+                    60.0,
+                    vec![
+                        Choice::new("1x", 1.0),
+                        Choice::new("10x", 10.0),
+                        Choice::new("60x", 60.0),
+                        Choice::new("600x", 600.0),
+                    ],
+                ),
+            ]),
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
+        .build(ctx);
+
+        let mut state = TrajectoryReplay {
+            trajectories,
+            time: Time::START_OF_DAY,
+            playing: false,
+            draw_agents: Drawable::empty(ctx),
+            panel,
+        };
+        state.change_time(ctx, app, Time::START_OF_DAY, false);
+        Box::new(state)
+    }
+
+    fn change_time(&mut self, ctx: &mut EventCtx, app: &App, time: Time, move_slider: bool) {
+        let end = self.trajectories.end_time();
+        self.time = if time > end { end } else { time };
+
+        let mut batch = GeomBatch::new();
+        for (id, pt, _) in self.trajectories.agents_at(self.time, &app.primary.map) {
+            let (color, radius) = match id.to_type() {
+                AgentType::Car => (app.cs.unzoomed_car, 2.5),
+                AgentType::Bike => (app.cs.unzoomed_bike, 1.5),
+                AgentType::Bus | AgentType::Train => (app.cs.unzoomed_bus, 3.0),
+                AgentType::Pedestrian | AgentType::TransitRider => {
+                    (app.cs.unzoomed_pedestrian, 1.0)
+                }
+            };
+            batch.push(
+                color,
+                Circle::new(pt, Distance::meters(radius)).to_polygon(),
+            );
+        }
+        self.draw_agents = ctx.upload(batch);
+
+        let label = Text::from(Line(self.time.ampm_tostring())).draw(ctx);
+        self.panel.replace(ctx, "time", label);
+        if move_slider {
+            let pct = if end == Time::START_OF_DAY {
+                0.0
+            } else {
+                (self.time - Time::START_OF_DAY) / (end - Time::START_OF_DAY)
+            };
+            let slider = Slider::horizontal(ctx, 0.2 * ctx.canvas.window_width, 25.0, pct.min(1.0))
+                .named("time slider");
+            self.panel.replace(ctx, "time slider", slider);
+        }
+    }
+
+    fn set_playing(&mut self, ctx: &mut EventCtx, playing: bool) {
+        self.playing = playing;
+        let btn = ctx
+            .style()
+            .btn_solid_dark_text(if playing { "pause" } else { "play" })
+            .hotkey(Key::Space)
+            .build_widget(ctx, "play/pause")
+            .named("play/pause");
+        self.panel.replace(ctx, "play/pause", btn);
+    }
+}
+
+impl State<App> for TrajectoryReplay {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        let old_pct = self.panel.slider("time slider").get_percent();
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "play/pause" => {
+                    let playing = !self.playing;
+                    self.set_playing(ctx, playing);
+                }
+                _ => unreachable!(),
+            },
+            _ => {}
+        }
+
+        // TODO Should fiddling with sliders produce Outcome::Changed?
+        let pct = self.panel.slider("time slider").get_percent();
+        if pct != old_pct {
+            // Scrubbing takes over from playback
+            if self.playing {
+                self.set_playing(ctx, false);
+            }
+            let end = self.trajectories.end_time();
+            let time = Time::START_OF_DAY + (end - Time::START_OF_DAY) * pct;
+            self.change_time(ctx, app, time, false);
+        } else if self.playing {
+            if let Some(real_dt) = ctx.input.nonblocking_is_update_event() {
+                ctx.input.use_update_event();
+                let multiplier: f64 = self.panel.dropdown_value("speed");
+                let time = self.time + real_dt * multiplier;
+                self.change_time(ctx, app, time, true);
+                if self.time == self.trajectories.end_time() {
+                    self.set_playing(ctx, false);
+                }
+            }
+            if self.playing {
+                ctx.request_update(UpdateType::Game);
+            }
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        g.redraw(&self.draw_agents);
+        self.panel.draw(g);
+    }
+}
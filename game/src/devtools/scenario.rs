@@ -51,6 +51,7 @@ impl ScenarioManager {
                 Widget::row(vec![
                     Line(format!("Scenario {}", scenario.scenario_name))
                         .small_heading()
+                        .verbatim()
                         .draw(ctx),
                     ctx.style().btn_close_widget(ctx),
                 ]),
@@ -314,6 +314,7 @@ fn make_panel(ctx: &mut EventCtx, story: &StoryMap, mode: &Mode, dirty: bool) ->
             Widget::vert_separator(ctx, 30.0),
             ctx.style()
                 .btn_outline_light_popup(&story.name)
+                .untranslated()
                 .hotkey(lctrl(Key::L))
                 .build_widget(ctx, "load"),
             ctx.style()
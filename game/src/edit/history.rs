@@ -0,0 +1,289 @@
+use geom::Percent;
+use map_gui::tools::{ChooseSomething, ColorDiscrete, PopupMsg, PromptInput};
+use map_gui::ID;
+use widgetry::{
+    Choice, ControlState, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Line, Outcome, Panel,
+    State, StyledButtons, Text, TextExt, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::common::Warping;
+use crate::edit::apply_map_edits;
+
+/// Lets the player save and restore checkpoints, and create, switch between, compare, and delete
+/// branches of the current proposal.
+pub fn manage_history(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+    let edits = app.primary.map.get_edits();
+    let mut choices = vec![Choice::string("save a checkpoint")];
+    if !edits.checkpoints.is_empty() {
+        choices.push(Choice::string("restore a checkpoint"));
+    }
+    choices.push(Choice::string("create a branch"));
+    if !edits.checkpoints.is_empty() {
+        choices.push(Choice::string("create a branch from a checkpoint"));
+    }
+    if !edits.other_branches.is_empty() {
+        choices.push(Choice::string("switch to another branch"));
+        choices.push(Choice::string("compare with another branch"));
+        choices.push(Choice::string("delete another branch"));
+    }
+
+    ChooseSomething::new(
+        ctx,
+        &format!("Manage history of branch \"{}\"", edits.branch),
+        choices,
+        Box::new(|choice, ctx, app| match choice.as_ref() {
+            "save a checkpoint" => Transition::Replace(PromptInput::new(
+                ctx,
+                "Name this checkpoint",
+                Box::new(|name, ctx, app| {
+                    let mut edits = app.primary.map.get_edits().clone();
+                    edits.add_checkpoint(name);
+                    apply_map_edits(ctx, app, edits);
+                    Transition::Pop
+                }),
+            )),
+            "restore a checkpoint" => Transition::Replace(choose_checkpoint(
+                ctx,
+                app,
+                "Restore which checkpoint?",
+                Box::new(|name, ctx, app| {
+                    let mut edits = app.primary.map.get_edits().clone();
+                    match edits.restore_checkpoint(&name) {
+                        Ok(()) => {
+                            apply_map_edits(ctx, app, edits);
+                            Transition::Pop
+                        }
+                        Err(err) => Transition::Replace(PopupMsg::new(
+                            ctx,
+                            "Error",
+                            vec![format!("Can't restore the checkpoint: {}", err)],
+                        )),
+                    }
+                }),
+            )),
+            "create a branch" => Transition::Replace(create_branch(ctx, None)),
+            "create a branch from a checkpoint" => Transition::Replace(choose_checkpoint(
+                ctx,
+                app,
+                "Start the new branch from which checkpoint?",
+                Box::new(|name, ctx, _| Transition::Replace(create_branch(ctx, Some(name)))),
+            )),
+            "switch to another branch" => Transition::Replace(choose_other_branch(
+                ctx,
+                app,
+                "Switch to which branch?",
+                Box::new(|name, ctx, app| {
+                    let mut edits = app.primary.map.get_edits().clone();
+                    match edits.switch_branch(&name) {
+                        Ok(()) => {
+                            apply_map_edits(ctx, app, edits);
+                            Transition::Pop
+                        }
+                        Err(err) => Transition::Replace(PopupMsg::new(
+                            ctx,
+                            "Error",
+                            vec![format!("Can't switch branches: {}", err)],
+                        )),
+                    }
+                }),
+            )),
+            "compare with another branch" => Transition::Replace(choose_other_branch(
+                ctx,
+                app,
+                "Compare with which branch?",
+                Box::new(|name, ctx, app| {
+                    Transition::Replace(CompareBranches::new(ctx, app, name))
+                }),
+            )),
+            "delete another branch" => Transition::Replace(choose_other_branch(
+                ctx,
+                app,
+                "Delete which branch?",
+                Box::new(|name, ctx, app| {
+                    let mut edits = app.primary.map.get_edits().clone();
+                    match edits.delete_branch(&name) {
+                        Ok(()) => {
+                            apply_map_edits(ctx, app, edits);
+                            Transition::Pop
+                        }
+                        Err(err) => Transition::Replace(PopupMsg::new(
+                            ctx,
+                            "Error",
+                            vec![format!("Can't delete the branch: {}", err)],
+                        )),
+                    }
+                }),
+            )),
+            _ => unreachable!(),
+        }),
+    )
+}
+
+fn create_branch(ctx: &mut EventCtx, from_checkpoint: Option<String>) -> Box<dyn State<App>> {
+    PromptInput::new(
+        ctx,
+        "Name the new branch",
+        Box::new(move |name, ctx, app| {
+            let mut edits = app.primary.map.get_edits().clone();
+            match edits.create_branch(name, from_checkpoint.as_deref()) {
+                Ok(()) => {
+                    apply_map_edits(ctx, app, edits);
+                    Transition::Pop
+                }
+                Err(err) => Transition::Replace(PopupMsg::new(
+                    ctx,
+                    "Error",
+                    vec![format!("Can't create the branch: {}", err)],
+                )),
+            }
+        }),
+    )
+}
+
+fn choose_checkpoint(
+    ctx: &mut EventCtx,
+    app: &App,
+    query: &str,
+    cb: Box<dyn Fn(String, &mut EventCtx, &mut App) -> Transition>,
+) -> Box<dyn State<App>> {
+    ChooseSomething::new(
+        ctx,
+        query,
+        app.primary
+            .map
+            .get_edits()
+            .checkpoints
+            .iter()
+            .map(|cp| {
+                Choice::new(
+                    format!("{} (after {} changes)", cp.name, cp.num_commands),
+                    cp.name.clone(),
+                )
+            })
+            .collect(),
+        cb,
+    )
+}
+
+fn choose_other_branch(
+    ctx: &mut EventCtx,
+    app: &App,
+    query: &str,
+    cb: Box<dyn Fn(String, &mut EventCtx, &mut App) -> Transition>,
+) -> Box<dyn State<App>> {
+    ChooseSomething::new(
+        ctx,
+        query,
+        app.primary
+            .map
+            .get_edits()
+            .other_branches
+            .keys()
+            .map(|name| Choice::new(name.clone(), name.clone()))
+            .collect(),
+        cb,
+    )
+}
+
+/// Lists and highlights everything that winds up different between the current branch and
+/// another.
+struct CompareBranches {
+    panel: Panel,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+    // Indexed by the number in "difference #N"
+    ids: Vec<ID>,
+}
+
+impl CompareBranches {
+    fn new(ctx: &mut EventCtx, app: &App, other: String) -> Box<dyn State<App>> {
+        let map = &app.primary.map;
+        let edits = map.get_edits();
+        let diff = edits.diff_branches(&edits.branch, &other).unwrap();
+
+        let mut colorer = ColorDiscrete::new(app, vec![("different", app.cs.edits_layer)]);
+        let mut ids = Vec::new();
+        let mut col = vec![Widget::row(vec![
+            Line(format!("Comparing \"{}\" with \"{}\"", edits.branch, other))
+                .small_heading()
+                .verbatim()
+                .draw(ctx),
+            ctx.style().btn_close_widget(ctx),
+        ])];
+
+        for (r, details) in diff.roads {
+            colorer.add_r(r, "different");
+            let mut txt = Text::from(Line(format!("road #{}", r.0)));
+            txt.append(Line(format!(" {}", map.get_r(r).get_name(None))).secondary());
+            for line in details {
+                txt.add(Line(line).secondary());
+            }
+            col.push(difference_btn(ctx, txt, ids.len()));
+            ids.push(ID::Road(r));
+        }
+        for i in diff.intersections {
+            colorer.add_i(i, "different");
+            let txt = Text::from(Line(format!("intersection #{}", i.0)));
+            col.push(difference_btn(ctx, txt, ids.len()));
+            ids.push(ID::Intersection(i));
+        }
+        for r in diff.routes {
+            col.push(format!("schedule of route {}", map.get_br(r).short_name).draw_text(ctx));
+        }
+        if col.len() == 1 {
+            col.push("These branches make the same changes".draw_text(ctx));
+        }
+
+        let (unzoomed, zoomed, _) = colorer.build(ctx);
+        Box::new(CompareBranches {
+            panel: Panel::new(Widget::col(col))
+                .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+                .max_size(Percent::int(30), Percent::int(70))
+                .build(ctx),
+            unzoomed,
+            zoomed,
+            ids,
+        })
+    }
+}
+
+fn difference_btn(ctx: &mut EventCtx, txt: Text, idx: usize) -> Widget {
+    ctx.style()
+        .btn_plain_light()
+        .label_styled_text(txt, ControlState::Default)
+        .build_widget(ctx, &format!("difference #{}", idx))
+}
+
+impl State<App> for CompareBranches {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => Transition::Pop,
+                x => {
+                    let idx = x["difference #".len()..].parse::<usize>().unwrap();
+                    let id = self.ids[idx].clone();
+                    Transition::Push(Warping::new(
+                        ctx,
+                        app.primary.canonical_point(id.clone()).unwrap(),
+                        Some(10.0),
+                        Some(id),
+                        &mut app.primary,
+                    ))
+                }
+            },
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+        self.panel.draw(g);
+    }
+}
@@ -1,8 +1,11 @@
+use std::collections::BTreeSet;
+
 use map_gui::render::Renderable;
+use map_gui::tools::PopupMsg;
 use map_gui::ID;
-use map_model::{EditCmd, LaneID, LaneType, Map};
+use map_model::{EditCmd, LaneID, LaneType, Map, TurnType};
 use widgetry::{
-    Choice, Color, ControlState, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Panel,
+    Checkbox, Choice, Color, ControlState, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Panel,
     SimpleState, State, StyledButtons, TextExt, VerticalAlignment, Widget,
 };
 
@@ -20,6 +23,14 @@ pub struct LaneEditor {
     mode: GameplayMode,
 }
 
+/// The movements that can be painted as turn arrows, and their checkbox labels
+const TURN_ARROWS: [(TurnType, &str); 4] = [
+    (TurnType::Left, "left"),
+    (TurnType::Straight, "straight"),
+    (TurnType::Right, "right"),
+    (TurnType::UTurn, "U-turn"),
+];
+
 impl LaneEditor {
     pub fn new(
         ctx: &mut EventCtx,
@@ -75,7 +86,7 @@ impl LaneEditor {
         }
 
         let parent = app.primary.map.get_parent(l);
-        let col = vec![
+        let mut col = vec![
             Widget::row(vec![
                 Line(format!("Editing {}", l)).small_heading().draw(ctx),
                 ctx.style()
@@ -90,6 +101,25 @@ impl LaneEditor {
                 .btn_outline_light_text("reverse direction")
                 .hotkey(Key::F)
                 .build_def(ctx),
+        ];
+        let lane = app.primary.map.get_l(l);
+        if lane.is_driving() || lane.is_bus() {
+            col.push("Turn arrows".draw_text(ctx));
+            col.push(Widget::custom_row(
+                TURN_ARROWS
+                    .iter()
+                    .map(|(turn_type, label)| {
+                        let enabled = lane
+                            .allowed_turns
+                            .as_ref()
+                            .map(|types| types.contains(turn_type))
+                            .unwrap_or(true);
+                        Checkbox::checkbox(ctx, label, None, enabled).margin_right(10)
+                    })
+                    .collect(),
+            ));
+        }
+        col.extend(vec![
             {
                 let mut choices = speed_limit_choices(app);
                 if !choices.iter().any(|c| c.data == parent.speed_limit) {
@@ -111,7 +141,7 @@ impl LaneEditor {
                 .btn_solid_dark_text("Finish")
                 .hotkey(Key::Escape)
                 .build_def(ctx),
-        ];
+        ]);
         let panel = Panel::new(Widget::col(col))
             .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
             .build(ctx);
@@ -173,13 +203,38 @@ impl SimpleState<App> for LaneEditor {
         app: &mut App,
         panel: &Panel,
     ) -> Option<Transition> {
-        let mut edits = app.primary.map.get_edits().clone();
-        edits.commands.push(app.primary.map.edit_road_cmd(
-            app.primary.map.get_l(self.l).parent,
-            |new| {
-                new.speed_limit = panel.dropdown_value("speed limit");
-            },
-        ));
+        let map = &app.primary.map;
+        let lane = map.get_l(self.l);
+        let mut allowed_turns = lane.allowed_turns.clone();
+        if lane.is_driving() || lane.is_bus() {
+            let types: BTreeSet<TurnType> = TURN_ARROWS
+                .iter()
+                .filter(|(_, label)| panel.is_checked(label))
+                .map(|(turn_type, _)| *turn_type)
+                .collect();
+            if types.is_empty() {
+                return Some(Transition::Multi(vec![
+                    Transition::Replace(LaneEditor::new(ctx, app, self.l, self.mode.clone())),
+                    Transition::Push(PopupMsg::new(
+                        ctx,
+                        "Error",
+                        vec!["A lane needs to allow at least one movement"],
+                    )),
+                ]));
+            }
+            // Don't add arrows to an unmarked lane if everything is still allowed
+            if allowed_turns.is_some() || types.len() < TURN_ARROWS.len() {
+                allowed_turns = Some(types);
+            }
+        }
+
+        let r = map.get_parent(self.l);
+        let idx = r.offset(self.l);
+        let mut edits = map.get_edits().clone();
+        edits.commands.push(map.edit_road_cmd(r.id, |new| {
+            new.speed_limit = panel.dropdown_value("speed limit");
+            new.allowed_turns[idx] = allowed_turns.clone();
+        }));
         apply_map_edits(ctx, app, edits);
         Some(Transition::Replace(LaneEditor::new(
             ctx,
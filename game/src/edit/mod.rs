@@ -27,6 +27,7 @@ use crate::sandbox::{GameplayMode, SandboxMode, TimeWarpScreen};
 
 mod bulk;
 mod cluster_traffic_signals;
+mod history;
 mod lanes;
 mod routes;
 mod select;
@@ -45,8 +46,8 @@ pub struct EditMode {
     // Retained state from the SandboxMode that spawned us
     mode: GameplayMode,
 
-    // edits name, number of commands
-    changelist_key: (String, usize),
+    // edits name, branch, number of commands, undone commands, and checkpoints
+    changelist_key: (String, String, usize, usize, usize),
 
     unzoomed: Drawable,
     zoomed: Drawable,
@@ -66,7 +67,7 @@ impl EditMode {
             orig_edits: edits.clone(),
             orig_dirty,
             mode,
-            changelist_key: (edits.edits_name.clone(), edits.commands.len()),
+            changelist_key: changelist_key(edits),
             unzoomed: layer.unzoomed,
             zoomed: layer.zoomed,
         })
@@ -143,7 +144,7 @@ impl State<App> for EditMode {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
         {
             let edits = app.primary.map.get_edits();
-            let changelist_key = (edits.edits_name.clone(), edits.commands.len());
+            let changelist_key = changelist_key(edits);
             if self.changelist_key != changelist_key {
                 self.changelist_key = changelist_key;
                 self.changelist = make_changelist(ctx, app);
@@ -273,9 +274,17 @@ impl State<App> for EditMode {
                     ));
                 }
                 "load proposal" => {}
-                "undo" => {
+                "manage history" => {
+                    return Transition::Push(history::manage_history(ctx, app));
+                }
+                "undo" | "redo" => {
                     let mut edits = app.primary.map.get_edits().clone();
-                    let maybe_id = cmd_to_id(&edits.commands.pop().unwrap());
+                    let cmd = if x == "undo" {
+                        edits.undo()
+                    } else {
+                        edits.redo()
+                    };
+                    let maybe_id = cmd_to_id(&cmd.unwrap());
                     apply_map_edits(ctx, app, edits);
                     if let Some(id) = maybe_id {
                         return Transition::Push(Warping::new(
@@ -548,6 +557,7 @@ impl LoadEdits {
                 proposals.push(
                     ctx.style()
                         .btn_outline_light_text(&name)
+                        .untranslated()
                         .build_widget(ctx, &path),
                 );
             }
@@ -773,14 +783,23 @@ pub fn maybe_edit_intersection(
     None
 }
 
+fn changelist_key(edits: &MapEdits) -> (String, String, usize, usize, usize) {
+    (
+        edits.edits_name.clone(),
+        edits.branch.clone(),
+        edits.commands.len(),
+        edits.redo_stack.len(),
+        edits.checkpoints.len(),
+    )
+}
+
 fn make_changelist(ctx: &mut EventCtx, app: &App) -> Panel {
-    // TODO Support redo. Bit harder here to reset the redo_stack when the edits
-    // change, because nested other places modify it too.
     let edits = app.primary.map.get_edits();
     let mut col = vec![
         Widget::row(vec![
             ctx.style()
                 .btn_outline_light_popup(&edits.edits_name)
+                .untranslated()
                 .hotkey(lctrl(Key::P))
                 .build_widget(ctx, "manage proposals"),
             "autosaved"
@@ -789,6 +808,21 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Panel {
                 .padding(10)
                 .bg(Color::hex("#5D9630")),
         ]),
+        Widget::row(vec![
+            ctx.style()
+                .btn_outline_light_popup(&format!("branch: {}", edits.branch))
+                .untranslated()
+                .hotkey(lctrl(Key::B))
+                .build_widget(ctx, "manage history"),
+            if let Some(cmd) = edits.redo_stack.last() {
+                ctx.style()
+                    .btn_outline_light_text(&format!("redo {}", cmd.describe(&app.primary.map).0))
+                    .hotkey(lctrl(Key::Y))
+                    .build_widget(ctx, "redo")
+            } else {
+                Widget::nothing()
+            },
+        ]),
         ColorLegend::row(
             ctx,
             app.cs.edits_layer,
@@ -804,6 +838,11 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Panel {
         col.push(format!("{} more...", edits.commands.len() - 5).draw_text(ctx));
     }
     for idx in edits.commands.len().max(5) - 5..edits.commands.len() {
+        for cp in &edits.checkpoints {
+            if cp.num_commands == idx {
+                col.push(format!("checkpoint \"{}\"", cp.name).draw_text(ctx));
+            }
+        }
         let (summary, details) = edits.commands[idx].describe(&app.primary.map);
         let mut txt = Text::from(Line(format!("{}) {}", idx + 1, summary)));
         for line in details {
@@ -830,6 +869,11 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Panel {
             col.push(btn);
         }
     }
+    for cp in &edits.checkpoints {
+        if cp.num_commands == edits.commands.len() {
+            col.push(format!("checkpoint \"{}\"", cp.name).draw_text(ctx));
+        }
+    }
 
     Panel::new(Widget::col(col))
         .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
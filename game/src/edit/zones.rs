@@ -3,12 +3,13 @@ use std::collections::BTreeSet;
 use enumset::EnumSet;
 use maplit::btreeset;
 
+use geom::{Duration, Time};
 use map_gui::tools::ColorDiscrete;
-use map_model::{AccessRestrictions, PathConstraints, RoadID};
+use map_model::{AccessRestrictions, PathConstraints, RoadID, TimeWindow};
 use sim::TripMode;
 use widgetry::{
-    Color, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, Spinner,
-    State, StyledButtons, Text, TextExt, VerticalAlignment, Widget,
+    Checkbox, Color, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel,
+    Spinner, State, StyledButtons, Text, TextExt, VerticalAlignment, Widget,
 };
 
 use crate::app::{App, Transition};
@@ -24,6 +25,8 @@ pub struct ZoneEditor {
     zoomed: Drawable,
 
     orig_members: BTreeSet<RoadID>,
+    // Only the first time window can be edited here; the rest are preserved.
+    other_time_windows: Vec<(TimeWindow, EnumSet<PathConstraints>)>,
 }
 
 impl ZoneEditor {
@@ -42,6 +45,14 @@ impl ZoneEditor {
             .map(|c| TripMode::from_constraints(c))
             .collect();
         let cap_vehicles_per_hour = start.access_restrictions.cap_vehicles_per_hour;
+        let time_window = start.access_restrictions.time_windows.get(0).cloned();
+        let other_time_windows = start
+            .access_restrictions
+            .time_windows
+            .iter()
+            .skip(1)
+            .cloned()
+            .collect();
 
         let (unzoomed, zoomed, legend) = draw_zone(ctx, app, &members);
         let orig_members = members.clone();
@@ -62,6 +73,7 @@ impl ZoneEditor {
                     Spinner::new(ctx, (0, 1000), cap_vehicles_per_hour.unwrap_or(0) as isize)
                         .named("cap_vehicles"),
                 ]),
+                make_time_window_controls(ctx, time_window),
                 Widget::custom_row(vec![
                     ctx.style()
                         .btn_outline_light_text("Apply")
@@ -81,10 +93,38 @@ impl ZoneEditor {
             allow_through_traffic,
             unzoomed,
             zoomed,
+            other_time_windows,
         })
     }
 }
 
+impl ZoneEditor {
+    fn get_time_windows(&self) -> Vec<(TimeWindow, EnumSet<PathConstraints>)> {
+        let mut windows = Vec::new();
+        if self
+            .panel
+            .is_checked("Restrict through-traffic at certain times")
+        {
+            let window = TimeWindow {
+                start: Time::START_OF_DAY
+                    + Duration::hours(self.panel.spinner("window start") as usize),
+                end: Time::START_OF_DAY
+                    + Duration::hours(self.panel.spinner("window end") as usize),
+            };
+            let banned = TripMode::all()
+                .into_iter()
+                .filter(|m| self.panel.is_checked(&format!("no {}", m.ongoing_verb())))
+                .map(|m| m.to_constraints())
+                .collect::<EnumSet<_>>();
+            if window.start != window.end && !banned.is_empty() {
+                windows.push((window, banned));
+            }
+        }
+        windows.extend(self.other_time_windows.clone());
+        windows
+    }
+}
+
 // TODO Handle splitting/merging zones.
 impl State<App> for ZoneEditor {
     fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
@@ -120,6 +160,7 @@ impl State<App> for ZoneEditor {
                                 Some(n)
                             }
                         },
+                        time_windows: self.get_time_windows(),
                     };
                     for r in &self.selector.roads {
                         let old_access_restrictions =
@@ -228,3 +269,49 @@ fn make_instructions(ctx: &mut EventCtx, allow_through_traffic: &BTreeSet<TripMo
             .draw(ctx)
     }
 }
+
+fn make_time_window_controls(
+    ctx: &mut EventCtx,
+    window: Option<(TimeWindow, EnumSet<PathConstraints>)>,
+) -> Widget {
+    let (start, end, banned) = if let Some((window, banned)) = window {
+        (
+            window.start.get_parts().0,
+            window.end.get_parts().0,
+            banned
+                .into_iter()
+                .map(|c| TripMode::from_constraints(c))
+                .collect(),
+        )
+    } else {
+        (8, 9, btreeset! { TripMode::Drive })
+    };
+    Widget::col(vec![
+        Checkbox::checkbox(
+            ctx,
+            "Restrict through-traffic at certain times",
+            None,
+            window.is_some(),
+        ),
+        Widget::row(vec![
+            "Every day from hour".draw_text(ctx),
+            Spinner::new(ctx, (0, 23), start as isize).named("window start"),
+            "to hour".draw_text(ctx),
+            Spinner::new(ctx, (0, 24), end as isize).named("window end"),
+        ]),
+        Widget::custom_row(
+            TripMode::all()
+                .into_iter()
+                .map(|m| {
+                    Checkbox::checkbox(
+                        ctx,
+                        &format!("no {}", m.ongoing_verb()),
+                        None,
+                        banned.contains(&m),
+                    )
+                    .margin_right(24)
+                })
+                .collect(),
+        ),
+    ])
+}
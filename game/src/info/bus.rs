@@ -28,6 +28,7 @@ pub fn stop(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BusStopID)
         rows.push(
             ctx.style()
                 .btn_outline_light_text(&format!("Route {}", r.short_name))
+                .untranslated()
                 .build_widget(ctx, &label),
         );
         details.hyperlinks.insert(label, Tab::BusRoute(r.id));
@@ -108,6 +109,7 @@ pub fn bus_status(ctx: &mut EventCtx, app: &App, details: &mut Details, id: CarI
     rows.push(
         ctx.style()
             .btn_outline_light_text(&format!("Serves route {}", route.short_name))
+            .untranslated()
             .build_def(ctx),
     );
     details.hyperlinks.insert(
@@ -151,6 +153,7 @@ fn bus_header(
             app.primary.map.get_br(route).short_name
         ))
         .small_heading()
+        .verbatim()
         .draw(ctx),
         header_btns(ctx),
     ]));
@@ -172,6 +175,7 @@ pub fn route(ctx: &mut EventCtx, app: &App, details: &mut Details, id: BusRouteI
     rows.push(Widget::row(vec![
         Line(format!("Route {}", route.short_name))
             .small_heading()
+            .verbatim()
             .draw(ctx),
         header_btns(ctx),
     ]));
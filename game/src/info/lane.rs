@@ -20,6 +20,12 @@ pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Vec
     if !l.is_walkable() {
         kv.push(("Type", l.lane_type.describe().to_string()));
     }
+    for (window, lt) in &l.lane_type_windows {
+        kv.push((
+            "Changes type",
+            format!("{} from {}", lt.short_name(), window),
+        ));
+    }
     if r.is_private() {
         let mut ban = Vec::new();
         for p in PathConstraints::all() {
@@ -30,6 +36,16 @@ pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Vec
         if !ban.is_empty() {
             kv.push(("No through-traffic for", ban.join(", ")));
         }
+        for (window, banned) in &r.access_restrictions.time_windows {
+            let ban: Vec<String> = banned
+                .iter()
+                .map(|p| format!("{:?}", p).to_ascii_lowercase())
+                .collect();
+            kv.push((
+                "No through-traffic for",
+                format!("{} from {}", ban.join(", "), window),
+            ));
+        }
         if let Some(cap) = r.access_restrictions.cap_vehicles_per_hour {
             kv.push((
                 "Cap for vehicles this hour",
@@ -118,10 +134,10 @@ pub fn debug(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Ve
         ));
     }
 
-    if let Some(types) = l.get_turn_restrictions(r) {
+    if let Some(types) = l.get_turn_restrictions() {
         kv.push((
             "Turn restrictions".to_string(),
-            format!("{:?}", types.into_iter().collect::<Vec<_>>()),
+            format!("{:?}", types.iter().collect::<Vec<_>>()),
         ));
     }
     for (restriction, to) in &r.turn_restrictions {
@@ -559,6 +559,7 @@ fn make_timeline(
                     TripPhaseType::RidingBus(_, _, _) => "system/assets/timeline/riding_bus.svg",
                     TripPhaseType::Cancelled | TripPhaseType::Finished => unreachable!(),
                     TripPhaseType::DelayedStart => "system/assets/timeline/delayed_start.svg",
+                    TripPhaseType::Escorted => "system/assets/timeline/walking.svg",
                 },
             )
             .centered_on(Pt2D::new(x1 + phase_width / 2.0, icon_height / 2.0)),
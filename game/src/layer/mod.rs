@@ -15,6 +15,7 @@ mod parking;
 mod population;
 pub mod traffic;
 pub mod transit;
+pub mod validation;
 
 // TODO Good ideas in
 // https://towardsdatascience.com/top-10-map-types-in-data-visualization-b3a80898ea70
@@ -142,6 +143,11 @@ impl PickLayer {
                     "Data".draw_text(ctx),
                     btn("traffic signal demand", Key::M),
                     btn("commuter patterns", Key::R),
+                    if app.primary.observed_counts.is_some() {
+                        btn("observed counts", Key::K)
+                    } else {
+                        Widget::nothing()
+                    },
                 ]),
             ])
             .evenly_spaced(),
@@ -237,6 +243,9 @@ impl State<App> for PickLayer {
                 "commuter patterns" => {
                     return Transition::Replace(dashboards::CommuterPatterns::new(ctx, app));
                 }
+                "observed counts" => {
+                    app.primary.layer = Some(Box::new(validation::CountsValidation::new(ctx, app)));
+                }
                 _ => unreachable!(),
             },
             _ => {
@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+
+use geom::Time;
+use map_gui::tools::{ColorNetwork, DivergingScale};
+use sim::CountLocation;
+use widgetry::{
+    Color, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Line, Panel, Text, VerticalAlignment,
+    Widget,
+};
+
+use crate::app::App;
+use crate::layer::{header, Layer, LayerOutcome};
+use crate::sandbox::dashboards::compare_finished_hours;
+
+/// Highlights places where the simulation doesn't match real-world counts.
+pub struct CountsValidation {
+    time: Time,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+    panel: Panel,
+}
+
+impl Layer for CountsValidation {
+    fn name(&self) -> Option<&'static str> {
+        Some("observed counts")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Panel,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = CountsValidation::new(ctx, app);
+        }
+
+        Layer::simple_event(ctx, minimap, &mut self.panel)
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.panel.draw(g);
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.unzoomed);
+    }
+}
+
+impl CountsValidation {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> CountsValidation {
+        // Per location, the total (observed, simulated) counts, and whether any hour is a bad
+        // match
+        let mut per_location: BTreeMap<CountLocation, (usize, usize, bool)> = BTreeMap::new();
+        for c in compare_finished_hours(app) {
+            let entry = per_location
+                .entry(c.observed.location.clone())
+                .or_insert((0, 0, false));
+            entry.0 += c.observed.count;
+            entry.1 += c.simulated;
+            entry.2 |= c.geh() >= 5.0;
+        }
+
+        let mut colorer = ColorNetwork::new(app);
+        let scale = DivergingScale::new(Color::hex("#4A90E2"), Color::WHITE, Color::hex("#EB3223"))
+            .range(0.0, 2.0);
+        let mut num_bad = 0;
+        for (loc, (observed, simulated, bad)) in &per_location {
+            if !bad {
+                continue;
+            }
+            num_bad += 1;
+            let ratio = if *observed == 0 {
+                2.0
+            } else {
+                (*simulated as f64) / (*observed as f64)
+            };
+            let color = scale.eval(ratio).unwrap();
+            match loc {
+                CountLocation::Road(r) => {
+                    colorer.add_r(*r, color);
+                }
+                CountLocation::Intersection(i) | CountLocation::Movement { i, .. } => {
+                    colorer.add_i(*i, color);
+                }
+                CountLocation::TransitStop(name) => {
+                    for bs in app.primary.map.all_bus_stops().values() {
+                        if &bs.name == name {
+                            colorer.add_bs(bs.id, color);
+                        }
+                    }
+                }
+            }
+        }
+        let (unzoomed, zoomed) = colorer.build(ctx);
+
+        let panel = Panel::new(Widget::col(vec![
+            header(ctx, "Observed counts"),
+            Text::from(
+                Line(format!(
+                    "{} of {} counted places have an hour with GEH of at least 5",
+                    num_bad,
+                    per_location.len()
+                ))
+                .secondary(),
+            )
+            .wrap_to_pct(ctx, 15)
+            .draw(ctx),
+            scale.make_legend(ctx, vec!["under-predicted", "matches", "over-predicted"]),
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        CountsValidation {
+            time: app.primary.sim.time(),
+            unzoomed,
+            zoomed,
+            panel,
+        }
+    }
+}
@@ -120,7 +120,9 @@ fn setup_app(
     if !title {
         opts.color_scheme = map_gui::colors::ColorSchemeChoice::NightMode;
     }
-    let cs = map_gui::colors::ColorScheme::new(ctx, opts.color_scheme);
+    let cs = map_gui::colors::ColorScheme::new(ctx, opts.color_scheme.clone());
+    // update_from_args already checked the language
+    map_gui::tools::set_ui_language(ctx, opts.ui_language.as_deref()).unwrap();
 
     // SimFlags::load doesn't know how to do async IO, which we need on the web. But in the common
     // case, all we're creating there is a map. If so, use the proper async interface.
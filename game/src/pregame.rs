@@ -3,12 +3,14 @@ use std::collections::HashMap;
 use instant::Instant;
 use rand::Rng;
 use rand_xorshift::XorShiftRng;
+use serde::Deserialize;
 
+use abstio::MapName;
 use abstutil::Timer;
 use geom::{Duration, Line, Percent, Pt2D, Speed};
 use map_gui::load::MapLoader;
 use map_gui::tools::{open_browser, PopupMsg};
-use map_model::PermanentMapEdits;
+use map_model::MapEdits;
 use sim::{AlertHandler, ScenarioGenerator, Sim, SimOptions};
 use widgetry::{
     hotkeys, Color, ContentMode, DrawBaselayer, EdgeInsets, EventCtx, Font, GfxCtx, Key, Line,
@@ -37,7 +39,8 @@ impl TitleScreen {
         app.primary.sim = Sim::new(&app.primary.map, opts);
         ScenarioGenerator::small_run(&app.primary.map)
             .generate(&app.primary.map, &mut rng, &mut timer)
-            .instantiate(&mut app.primary.sim, &app.primary.map, &mut rng, &mut timer);
+            .instantiate(&mut app.primary.sim, &app.primary.map, &mut rng, &mut timer)
+            .unwrap();
 
         TitleScreen {
             panel: Panel::new(
@@ -93,8 +96,10 @@ impl MainMenu {
     pub fn new(ctx: &mut EventCtx) -> Box<dyn State<App>> {
         let col = vec![
             {
-                let mut txt = Text::from(Line("A/B STREET").display_title());
-                txt.add(Line("Created by Dustin Carlino, Yuwen Li, & Michael Kirk"));
+                let mut txt = Text::from(Line("A/B STREET").display_title().verbatim());
+                txt.add(Line(
+                    ctx.tr("Created by Dustin Carlino, Yuwen Li, & Michael Kirk"),
+                ));
                 txt.draw(ctx).centered_horiz()
             },
             Widget::row({
@@ -122,7 +127,7 @@ impl MainMenu {
                         .label_text("Tutorial")
                         .tooltip({
                             let mut txt = Text::tooltip(ctx, Key::T, "Tutorial");
-                            txt.add(Line("Learn how to play the game").small());
+                            txt.add(Line(ctx.tr("Learn how to play the game")).small());
                             txt
                         })
                         .hotkey(Key::T)
@@ -133,7 +138,7 @@ impl MainMenu {
                         .label_text("Sandbox")
                         .tooltip({
                             let mut txt = Text::tooltip(ctx, Key::S, "Sandbox");
-                            txt.add(Line("No goals, try out any idea here").small());
+                            txt.add(Line(ctx.tr("No goals, try out any idea here")).small());
                             txt
                         })
                         .hotkey(Key::S)
@@ -144,7 +149,7 @@ impl MainMenu {
                         .label_text("Challenge")
                         .tooltip({
                             let mut txt = Text::tooltip(ctx, Key::C, "Challenges");
-                            txt.add(Line("Fix specific problems").small());
+                            txt.add(Line(ctx.tr("Fix specific problems")).small());
                             txt
                         })
                         .hotkey(Key::C)
@@ -157,7 +162,7 @@ impl MainMenu {
                     .btn_outline_light_text("Community Proposals")
                     .tooltip({
                         let mut txt = Text::tooltip(ctx, Key::P, "Community Proposals");
-                        txt.add(Line("See existing ideas for improving traffic").small());
+                        txt.add(Line(ctx.tr("See existing ideas for improving traffic")).small());
                         txt
                     })
                     .hotkey(Key::P)
@@ -175,7 +180,7 @@ impl MainMenu {
                         .btn_outline_light_text("Feedback")
                         .build_def(ctx),
                 ]),
-                built_info::time().draw(ctx),
+                built_info::time(ctx).draw(ctx),
             ])
             .centered(),
         ];
@@ -261,16 +266,16 @@ impl About {
                 .align_left(),
             {
                 Text::from_multiline(vec![
-                    Line("A/B STREET").display_title(),
-                    Line("Created by Dustin Carlino, Yuwen Li, & Michael Kirk"),
-                    Line("Character art by Holly Hansel"),
+                    Line("A/B STREET").display_title().verbatim(),
+                    Line(ctx.tr("Created by Dustin Carlino, Yuwen Li, & Michael Kirk")),
+                    Line(ctx.tr("Character art by Holly Hansel")),
                     Line(""),
-                    Line(
+                    Line(ctx.tr(
                         "Data from OpenStreetMap, King County GIS, and Puget Sound Regional \
                          Council",
-                    ),
+                    )),
                     Line(""),
-                    Line(
+                    Line(ctx.tr(
                         "Disclaimer: This game is based on imperfect data, heuristics concocted \
                          under the influence of cold brew, a simplified traffic simulation model, \
                          and a deeply flawed understanding of how much articulated buses can bend \
@@ -279,7 +284,7 @@ impl About {
                          characters to real people is probably coincidental, unless of course you \
                          stumble across the elusive \"Dustin Bikelino\". Have the appropriate \
                          amount of fun.",
-                    ),
+                    )),
                 ])
                 .wrap_to_pct(ctx, 50)
                 .draw(ctx)
@@ -332,25 +337,38 @@ impl State<App> for About {
 
 struct Proposals {
     panel: Panel,
-    proposals: HashMap<String, PermanentMapEdits>,
+    proposals: HashMap<String, ProposalInfo>,
     current: Option<String>,
 }
 
+/// Just the parts of a proposal's edits needed to list it. These fields are the same in every
+/// version of the edits format, so proposals that haven't been upgraded yet still show up.
+#[derive(Deserialize)]
+struct ProposalInfo {
+    map_name: MapName,
+    proposal_description: Vec<String>,
+    proposal_link: Option<String>,
+}
+
 impl Proposals {
     fn new(ctx: &mut EventCtx, app: &App, current: Option<String>) -> Box<dyn State<App>> {
         let mut proposals = HashMap::new();
         let mut buttons = Vec::new();
         let mut current_tab = Vec::new();
-        // If a proposal has fallen out of date, it'll be skipped with an error logged. Since these
-        // are under version control, much more likely to notice when they break (or we could add a
-        // step to data/regen.sh).
-        for (name, edits) in
-            abstio::load_all_objects::<PermanentMapEdits>(abstio::path("system/proposals"))
+        // If a proposal is broken, it'll be skipped with an error logged. Since these are under
+        // version control, much more likely to notice when they break, and the tests crate checks
+        // them too.
+        for (name, info) in
+            abstio::load_all_objects::<ProposalInfo>(abstio::path("system/proposals"))
         {
             if current == Some(name.clone()) {
                 let mut txt = Text::new();
-                txt.add(Line(&edits.proposal_description[0]).small_heading());
-                for l in edits.proposal_description.iter().skip(1) {
+                txt.add(
+                    Line(&info.proposal_description[0])
+                        .small_heading()
+                        .verbatim(),
+                );
+                for l in info.proposal_description.iter().skip(1) {
                     txt.add(Line(l));
                 }
                 current_tab.push(
@@ -360,7 +378,7 @@ impl Proposals {
                         .margin_above(15),
                 );
 
-                if edits.proposal_link.is_some() {
+                if info.proposal_link.is_some() {
                     current_tab.push(
                         ctx.style()
                             .btn_solid_dark_text("Read detailed write-up")
@@ -376,7 +394,8 @@ impl Proposals {
 
                 buttons.push(
                     ctx.style()
-                        .btn_solid_dark_text(&edits.proposal_description[0])
+                        .btn_solid_dark_text(&info.proposal_description[0])
+                        .untranslated()
                         .disabled(true)
                         .build_def(ctx)
                         .margin_below(10),
@@ -384,25 +403,28 @@ impl Proposals {
             } else {
                 buttons.push(
                     ctx.style()
-                        .btn_solid_dark_text(&edits.proposal_description[0])
+                        .btn_solid_dark_text(&info.proposal_description[0])
+                        .untranslated()
                         .no_tooltip()
                         .build_widget(ctx, &name)
                         .margin_below(10),
                 );
             }
 
-            proposals.insert(name, edits);
+            proposals.insert(name, info);
         }
 
         let mut col = vec![
             {
-                let mut txt = Text::from(Line("A/B STREET").display_title());
+                let mut txt = Text::from(Line("A/B STREET").display_title().verbatim());
                 txt.add(Line("PROPOSALS").big_heading_styled());
                 txt.add(Line(""));
-                txt.add(Line(
+                txt.add(Line(ctx.tr(
                     "These are proposed changes to Seattle made by community members.",
+                )));
+                txt.add(Line(
+                    ctx.tr("Contact dabreegster@gmail.com to add your idea here!"),
                 ));
-                txt.add(Line("Contact dabreegster@gmail.com to add your idea here!"));
                 txt.draw(ctx).centered_horiz().margin_below(20)
             },
             Widget::custom_row(buttons).flex_wrap(ctx, Percent::int(80)),
@@ -435,16 +457,22 @@ impl State<App> for Proposals {
                     return Transition::Pop;
                 }
                 "Try out this proposal" => {
-                    let edits = self.proposals[self.current.as_ref().unwrap()].clone();
+                    let name = self.current.clone().unwrap();
 
                     return Transition::Push(MapLoader::new(
                         ctx,
                         app,
-                        edits.map_name.clone(),
+                        self.proposals[&name].map_name.clone(),
                         Box::new(move |ctx, app| {
                             // Apply edits before setting up the sandbox, for simplicity
                             let maybe_err = ctx.loading_screen("apply edits", |ctx, mut timer| {
-                                match edits.to_edits(&app.primary.map) {
+                                // The proposal may use an older format, so load it through the
+                                // path that upgrades old edits.
+                                match MapEdits::load(
+                                    &app.primary.map,
+                                    abstio::path(format!("system/proposals/{}.json", name)),
+                                    &mut timer,
+                                ) {
                                     Ok(edits) => {
                                         apply_map_edits(ctx, app, edits);
                                         app.primary
@@ -558,20 +586,27 @@ impl Screensaver {
 #[cfg(not(target_arch = "wasm32"))]
 #[allow(unused)]
 mod built_info {
-    use widgetry::{Color, DrawBaselayer, Line, State, Text};
+    use widgetry::{tr, Color, DrawBaselayer, EventCtx, Line, State, Text};
 
     include!(concat!(env!("OUT_DIR"), "/built.rs"));
 
-    pub fn time() -> Text {
+    pub fn time(ctx: &EventCtx) -> Text {
         let t = built::util::strptime(BUILT_TIME_UTC);
 
-        let mut txt = Text::from(Line(format!(
+        let mut txt = Text::from(Line(tr!(
+            ctx,
             "This version built on {}",
             t.date().naive_local()
         )));
         // Releases every Sunday
         if (chrono::Utc::now() - t).num_days() > 8 {
-            txt.append(Line(format!(" (get the new release from abstreet.org)")).fg(Color::RED));
+            txt.append(
+                Line(format!(
+                    " ({})",
+                    ctx.tr("get the new release from abstreet.org")
+                ))
+                .fg(Color::RED),
+            );
         }
         txt
     }
@@ -579,7 +614,7 @@ mod built_info {
 
 #[cfg(target_arch = "wasm32")]
 mod built_info {
-    pub fn time() -> widgetry::Text {
+    pub fn time(_: &widgetry::EventCtx) -> widgetry::Text {
         widgetry::Text::new()
     }
 }
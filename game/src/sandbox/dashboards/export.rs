@@ -0,0 +1,114 @@
+use std::fs::File;
+use std::io::Write;
+
+use anyhow::Result;
+
+use widgetry::table::{ExportValues, Table};
+
+use crate::app::App;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Csv,
+    Parquet,
+}
+
+/// Writes every row in the table matching its current filters, in the current order, to a new
+/// file in the player's data directory. Returns the path.
+pub fn export_table<T, F>(
+    app: &App,
+    table: &Table<App, T, F>,
+    name: &str,
+    format: Format,
+) -> Result<String> {
+    let map_name = app.primary.map.get_name();
+    let path = abstio::path_player(format!(
+        "exports/{}/{}/{}_{}.{}",
+        map_name.city,
+        map_name.map,
+        name,
+        app.primary.sim.time().as_filename(),
+        match format {
+            Format::Csv => "csv",
+            Format::Parquet => "parquet",
+        }
+    ));
+    std::fs::create_dir_all(std::path::Path::new(&path).parent().unwrap())?;
+    match format {
+        Format::Csv => {
+            let mut f = File::create(&path)?;
+            f.write_all(table.to_csv().as_bytes())?;
+        }
+        Format::Parquet => {
+            write_parquet(&path, table.export())?;
+        }
+    }
+    Ok(path)
+}
+
+/// Parquet keeps numbers as numbers and compresses well, so it's much better than CSV for tables
+/// with every trip in a scenario.
+#[cfg(feature = "parquet")]
+fn write_parquet(path: &str, columns: Vec<(String, ExportValues)>) -> Result<()> {
+    use std::sync::Arc;
+
+    use parquet::column::writer::ColumnWriter;
+    use parquet::data_type::ByteArray;
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::{FileWriter, RowGroupWriter, SerializedFileWriter};
+    use parquet::schema::parser::parse_message_type;
+
+    let mut schema = "message table {\n".to_string();
+    for (name, values) in &columns {
+        schema.push_str(&match values {
+            ExportValues::Text(_) => {
+                format!("REQUIRED BYTE_ARRAY {} (UTF8);\n", field_name(name))
+            }
+            ExportValues::Number(_) => format!("REQUIRED DOUBLE {};\n", field_name(name)),
+        });
+    }
+    schema.push('}');
+
+    let mut writer = SerializedFileWriter::new(
+        File::create(path)?,
+        Arc::new(parse_message_type(&schema)?),
+        Arc::new(WriterProperties::builder().build()),
+    )?;
+    let mut row_group = writer.next_row_group()?;
+    for (name, values) in columns {
+        let mut column = row_group
+            .next_column()?
+            .ok_or_else(|| anyhow!("no Parquet column for {}", name))?;
+        match (&mut column, values) {
+            (ColumnWriter::ByteArrayColumnWriter(ref mut w), ExportValues::Text(values)) => {
+                let values: Vec<ByteArray> =
+                    values.into_iter().map(|x| x.into_bytes().into()).collect();
+                w.write_batch(&values, None, None)?;
+            }
+            (ColumnWriter::DoubleColumnWriter(ref mut w), ExportValues::Number(values)) => {
+                w.write_batch(&values, None, None)?;
+            }
+            _ => unreachable!(),
+        }
+        row_group.close_column(column)?;
+    }
+    writer.close_row_group(row_group)?;
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(not(feature = "parquet"))]
+fn write_parquet(_: &str, _: Vec<(String, ExportValues)>) -> Result<()> {
+    bail!("This build can't write Parquet files; try CSV instead")
+}
+
+/// Parquet field names can't have spaces or punctuation, so "Duration (seconds)" becomes
+/// "duration_seconds".
+#[cfg(feature = "parquet")]
+fn field_name(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
@@ -1,14 +1,16 @@
 use geom::{Distance, Pt2D};
+use map_gui::tools::PopupMsg;
 use sim::{TripEndpoint, TripID};
 use widgetry::table::Table;
 use widgetry::{
     Color, DrawBaselayer, EventCtx, GeomBatch, GfxCtx, Outcome, Panel, RewriteColor, ScreenPt,
-    State,
+    State, StyledButtons, Widget,
 };
 
 use crate::app::{App, Transition};
 use crate::common::color_for_trip_phase;
 use crate::info::{OpenTrip, Tab};
+use crate::sandbox::dashboards::export::{export_table, Format};
 use crate::sandbox::dashboards::trip_table;
 use crate::sandbox::dashboards::DashTab;
 use crate::sandbox::SandboxMode;
@@ -73,6 +75,27 @@ impl<T: 'static, F: 'static, P: 'static + Fn(&mut EventCtx, &App, &Table<App, T,
                     ]);
                 } else if x == "close" {
                     return Transition::Pop;
+                } else if x == "Export to CSV" || x == "Export to Parquet" {
+                    let format = if x == "Export to CSV" {
+                        Format::Csv
+                    } else {
+                        Format::Parquet
+                    };
+                    let name = match self.tab {
+                        DashTab::FinishedTripTable => "finished_trips",
+                        DashTab::CancelledTripTable => "cancelled_trips",
+                        DashTab::UnfinishedTripTable => "unfinished_trips",
+                        DashTab::ParkingOverhead => "parking_overhead",
+                        _ => unreachable!(),
+                    };
+                    return Transition::Push(match export_table(app, &self.table, name, format) {
+                        Ok(path) => PopupMsg::new(
+                            ctx,
+                            "Data exported",
+                            vec![format!("Data exported to {}", path)],
+                        ),
+                        Err(err) => PopupMsg::new(ctx, "Export failed", vec![err.to_string()]),
+                    });
                 } else if x == "finished trips" {
                     return Transition::Replace(trip_table::FinishedTripTable::new(ctx, app));
                 } else if x == "cancelled trips" {
@@ -108,6 +131,18 @@ impl<T: 'static, F: 'static, P: 'static + Fn(&mut EventCtx, &App, &Table<App, T,
     }
 }
 
+/// Export every row matching the current filters, not just the page shown
+pub fn export_buttons(ctx: &mut EventCtx) -> Widget {
+    Widget::row(vec![
+        ctx.style()
+            .btn_plain_light_text("Export to CSV")
+            .build_def(ctx),
+        ctx.style()
+            .btn_plain_light_text("Export to Parquet")
+            .build_def(ctx),
+    ])
+}
+
 fn preview_trip(g: &mut GfxCtx, app: &App, panel: &Panel) {
     let inner_rect = panel.rect_of("preview").clone();
     let map_bounds = app.primary.map.get_bounds().clone();
@@ -174,6 +174,7 @@ impl TransitRoutes {
                         Widget::row(vec![
                             ctx.style()
                                 .btn_outline_light_text(&name)
+                                .untranslated()
                                 .build_widget(ctx, &id.to_string()),
                             format!(
                                 "{} boardings, {} alightings, {} currently waiting",
@@ -1,6 +1,7 @@
 pub use commuter::CommuterPatterns;
 pub use traffic_signals::TrafficSignalDemand;
 pub use trip_table::FinishedTripTable;
+pub use validation::{compare_finished_hours, Validation};
 
 use widgetry::{Choice, EventCtx, Line, Panel, StyledButtons, TextExt, Widget};
 
@@ -8,12 +9,14 @@ use crate::app::App;
 use crate::app::Transition;
 
 mod commuter;
+mod export;
 mod generic_trip_table;
 mod misc;
 mod parking_overhead;
 mod summaries;
 mod traffic_signals;
 mod trip_table;
+mod validation;
 
 // Oh the dashboards melted, but we still had the radio
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -27,6 +30,7 @@ pub enum DashTab {
     TransitRoutes,
     CommuterPatterns,
     TrafficSignals,
+    Validation,
 }
 
 impl DashTab {
@@ -39,6 +43,7 @@ impl DashTab {
             Choice::new("Transit Routes", DashTab::TransitRoutes),
             Choice::new("Commuter Patterns", DashTab::CommuterPatterns),
             Choice::new("Traffic Signal Demand", DashTab::TrafficSignals),
+            Choice::new("Validation", DashTab::Validation),
         ];
         if app.has_prebaked().is_none() {
             choices.remove(1);
@@ -81,6 +86,7 @@ impl DashTab {
             DashTab::TransitRoutes => misc::TransitRoutes::new(ctx, app),
             DashTab::CommuterPatterns => CommuterPatterns::new(ctx, app),
             DashTab::TrafficSignals => TrafficSignalDemand::new(ctx, app),
+            DashTab::Validation => Validation::new(ctx, app),
             DashTab::CancelledTripTable | DashTab::UnfinishedTripTable => unreachable!(),
         }))
     }
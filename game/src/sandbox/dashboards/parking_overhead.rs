@@ -1,10 +1,11 @@
+use abstutil::prettyprint_usize;
 use geom::Duration;
 use sim::{TripEndpoint, TripID, TripPhaseType};
 use widgetry::table::{Col, Filter, Table};
-use widgetry::{Checkbox, EventCtx, Filler, Line, Panel, State, Text, Widget};
+use widgetry::{Checkbox, EventCtx, Filler, Line, Panel, State, Text, TextSpan, Widget};
 
 use crate::app::App;
-use crate::sandbox::dashboards::generic_trip_table::GenericTripTable;
+use crate::sandbox::dashboards::generic_trip_table::{export_buttons, GenericTripTable};
 use crate::sandbox::dashboards::DashTab;
 
 // TODO Compare all of these things before/after
@@ -23,6 +24,7 @@ struct Entry {
     total_duration: Duration,
     driving_duration: Duration,
     parking_duration: Duration,
+    cruising_duration: Duration,
     walking_duration: Duration,
     percent_overhead: usize,
     starts_off_map: bool,
@@ -37,7 +39,8 @@ struct Filters {
 fn produce_raw_data(app: &App) -> Vec<Entry> {
     // Gather raw data
     let mut data = Vec::new();
-    for (id, phases) in app.primary.sim.get_analytics().get_all_trip_phases() {
+    let analytics = app.primary.sim.get_analytics();
+    for (id, phases) in analytics.get_all_trip_phases() {
         let trip = app.primary.sim.trip_info(id);
         let starts_off_map = match trip.start {
             TripEndpoint::Border(_) => true,
@@ -83,6 +86,11 @@ fn produce_raw_data(app: &App) -> Vec<Entry> {
             total_duration,
             driving_duration,
             parking_duration,
+            cruising_duration: analytics
+                .parking_cruising
+                .get(&id)
+                .cloned()
+                .unwrap_or(Duration::ZERO),
             walking_duration,
             percent_overhead: (100.0 * (1.0 - (driving_duration / total_duration))) as usize,
             starts_off_map,
@@ -133,6 +141,10 @@ fn make_table(app: &App) -> Table<App, Entry, Filters> {
         }),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.total_duration))),
     );
+    table.export_number(
+        "Total duration (seconds)",
+        Box::new(|x| x.total_duration.inner_seconds()),
+    );
     table.column(
         "Driving duration",
         Box::new(|ctx, app, x| {
@@ -140,6 +152,10 @@ fn make_table(app: &App) -> Table<App, Entry, Filters> {
         }),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.driving_duration))),
     );
+    table.export_number(
+        "Driving duration (seconds)",
+        Box::new(|x| x.driving_duration.inner_seconds()),
+    );
     table.column(
         "Parking duration",
         Box::new(|ctx, app, x| {
@@ -147,6 +163,21 @@ fn make_table(app: &App) -> Table<App, Entry, Filters> {
         }),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.parking_duration))),
     );
+    table.export_number(
+        "Parking duration (seconds)",
+        Box::new(|x| x.parking_duration.inner_seconds()),
+    );
+    table.column(
+        "Cruising duration",
+        Box::new(|ctx, app, x| {
+            Text::from(Line(x.cruising_duration.to_string(&app.opts.units))).render(ctx)
+        }),
+        Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.cruising_duration))),
+    );
+    table.export_number(
+        "Cruising duration (seconds)",
+        Box::new(|x| x.cruising_duration.inner_seconds()),
+    );
     table.column(
         "Walking duration",
         Box::new(|ctx, app, x| {
@@ -154,11 +185,18 @@ fn make_table(app: &App) -> Table<App, Entry, Filters> {
         }),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.walking_duration))),
     );
+    table.export_number(
+        "Walking duration (seconds)",
+        Box::new(|x| x.walking_duration.inner_seconds()),
+    );
     table.column(
         "Percent overhead",
         Box::new(|ctx, _, x| Text::from(Line(format!("{}%", x.percent_overhead))).render(ctx)),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.percent_overhead))),
     );
+    table.export_number("Percent overhead", Box::new(|x| x.percent_overhead as f64));
+    table.export_text("Starts off-map", Box::new(|x| x.starts_off_map.to_string()));
+    table.export_text("Ends off-map", Box::new(|x| x.ends_off_map.to_string()));
 
     table
 }
@@ -185,6 +223,8 @@ fn make_panel(ctx: &mut EventCtx, app: &App, table: &Table<App, Entry, Filters>)
                      overhead,",
                 ),
                 Line("since the time spent driving off-map isn't shown here."),
+                Line(""),
+                parking_summary(app),
             ])
             .draw(ctx),
             Filler::square_width(ctx, 0.15).named("preview"),
@@ -192,8 +232,29 @@ fn make_panel(ctx: &mut EventCtx, app: &App, table: &Table<App, Entry, Filters>)
         .evenly_spaced(),
     );
     col.push(table.render(ctx, app));
+    col.push(export_buttons(ctx));
 
     Panel::new(Widget::col(col))
         .exact_size_percent(90, 90)
         .build(ctx)
 }
+
+fn parking_summary(app: &App) -> TextSpan {
+    let analytics = app.primary.sim.get_analytics();
+    let revenue: f64 = analytics.parking_fees.iter().map(|(_, _, fee)| *fee).sum();
+    let num_cruising = analytics.parking_cruising.len();
+    let total_cruising: Duration = analytics.parking_cruising.values().cloned().sum();
+    if num_cruising == 0 {
+        Line(format!(
+            "${:.2} collected in parking fees. Nobody has had to cruise for parking.",
+            revenue
+        ))
+    } else {
+        Line(format!(
+            "${:.2} collected in parking fees. {} trips cruised for parking, {} on average.",
+            revenue,
+            prettyprint_usize(num_cruising),
+            (total_cruising / (num_cruising as f64)).to_string(&app.opts.units)
+        ))
+    }
+}
@@ -10,7 +10,7 @@ use widgetry::{
 
 use crate::app::App;
 use crate::common::{checkbox_per_mode, cmp_duration_shorter, color_for_mode};
-use crate::sandbox::dashboards::generic_trip_table::GenericTripTable;
+use crate::sandbox::dashboards::generic_trip_table::{export_buttons, GenericTripTable};
 use crate::sandbox::dashboards::DashTab;
 
 pub struct FinishedTripTable;
@@ -329,6 +329,11 @@ fn make_table_finished_trips(app: &App) -> Table<App, FinishedTrip, Filters> {
         Box::new(|ctx, _, x| Text::from(Line(x.departure.ampm_tostring())).render(ctx)),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.departure))),
     );
+    table.export_text("Type", Box::new(|x| x.mode.ongoing_verb().to_string()));
+    table.export_number(
+        "Departure (seconds after midnight)",
+        Box::new(|x| x.departure.inner_seconds()),
+    );
     table.column(
         "Duration",
         Box::new(|ctx, app, x| {
@@ -336,6 +341,10 @@ fn make_table_finished_trips(app: &App) -> Table<App, FinishedTrip, Filters> {
         }),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.duration_after))),
     );
+    table.export_number(
+        "Duration (seconds)",
+        Box::new(|x| x.duration_after.inner_seconds()),
+    );
 
     if app.has_prebaked().is_some() {
         table.column(
@@ -352,6 +361,10 @@ fn make_table_finished_trips(app: &App) -> Table<App, FinishedTrip, Filters> {
                 rows.sort_by_key(|x| x.duration_after - x.duration_before)
             })),
         );
+        table.export_number(
+            "Duration before changes (seconds)",
+            Box::new(|x| x.duration_before.inner_seconds()),
+        );
         table.column(
             "Normalized",
             Box::new(|ctx, _, x| {
@@ -386,6 +399,11 @@ fn make_table_finished_trips(app: &App) -> Table<App, FinishedTrip, Filters> {
         Box::new(|ctx, _, x| Text::from(Line(x.percent_waiting.to_string())).render(ctx)),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.percent_waiting))),
     );
+    table.export_number(
+        "Time spent waiting (seconds)",
+        Box::new(|x| x.waiting.inner_seconds()),
+    );
+    table.export_number("Percent waiting", Box::new(|x| x.percent_waiting as f64));
 
     table
 }
@@ -462,6 +480,11 @@ fn make_table_cancelled_trips(app: &App) -> Table<App, CancelledTrip, Filters> {
         Box::new(|ctx, _, x| Text::from(Line(x.departure.ampm_tostring())).render(ctx)),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.departure))),
     );
+    table.export_text("Type", Box::new(|x| x.mode.ongoing_verb().to_string()));
+    table.export_number(
+        "Departure (seconds after midnight)",
+        Box::new(|x| x.departure.inner_seconds()),
+    );
     if app.has_prebaked().is_some() {
         table.column(
             "Estimated duration",
@@ -470,6 +493,10 @@ fn make_table_cancelled_trips(app: &App) -> Table<App, CancelledTrip, Filters> {
             }),
             Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.duration_before))),
         );
+        table.export_number(
+            "Estimated duration (seconds)",
+            Box::new(|x| x.duration_before.inner_seconds()),
+        );
     }
     table.static_col("Reason", Box::new(|x| x.reason.clone()));
 
@@ -562,6 +589,11 @@ fn make_table_unfinished_trips(app: &App) -> Table<App, UnfinishedTrip, Filters>
         Box::new(|ctx, _, x| Text::from(Line(x.departure.ampm_tostring())).render(ctx)),
         Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.departure))),
     );
+    table.export_text("Type", Box::new(|x| x.mode.ongoing_verb().to_string()));
+    table.export_number(
+        "Departure (seconds after midnight)",
+        Box::new(|x| x.departure.inner_seconds()),
+    );
     if app.has_prebaked().is_some() {
         table.column(
             "Estimated duration",
@@ -570,6 +602,10 @@ fn make_table_unfinished_trips(app: &App) -> Table<App, UnfinishedTrip, Filters>
             }),
             Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.duration_before))),
         );
+        table.export_number(
+            "Estimated duration (seconds)",
+            Box::new(|x| x.duration_before.inner_seconds()),
+        );
     }
 
     table
@@ -643,6 +679,7 @@ fn make_panel_finished_trips(
         DashTab::FinishedTripTable.picker(ctx, app),
         trip_category_selector(ctx, app, DashTab::FinishedTripTable),
         table.render(ctx, app),
+        export_buttons(ctx),
         Filler::square_width(ctx, 0.15)
             .named("preview")
             .centered_horiz(),
@@ -661,6 +698,7 @@ fn make_panel_cancelled_trips(
         DashTab::FinishedTripTable.picker(ctx, app),
         trip_category_selector(ctx, app, DashTab::CancelledTripTable),
         table.render(ctx, app),
+        export_buttons(ctx),
         Filler::square_width(ctx, 0.15)
             .named("preview")
             .centered_horiz(),
@@ -678,6 +716,7 @@ fn make_panel_unfinished_trips(
         DashTab::FinishedTripTable.picker(ctx, app),
         trip_category_selector(ctx, app, DashTab::UnfinishedTripTable),
         table.render(ctx, app),
+        export_buttons(ctx),
         Filler::square_width(ctx, 0.15)
             .named("preview")
             .centered_horiz(),
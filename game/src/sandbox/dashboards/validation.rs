@@ -0,0 +1,216 @@
+use abstutil::prettyprint_usize;
+use map_gui::tools::{ChooseSomething, PopupMsg};
+use sim::{AgentType, CountComparison, CountsSummary, ObservedCount};
+use widgetry::{
+    Choice, CompareCounts, DrawBaselayer, EventCtx, GfxCtx, Line, Outcome, Panel, State,
+    StyledButtons, Text, TextExt, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::layer::validation::CountsValidation;
+use crate::sandbox::dashboards::DashTab;
+
+/// Compares the simulation with real-world counts, like traffic counters, bike counters, and
+/// transit boardings.
+pub struct Validation {
+    panel: Panel,
+}
+
+impl Validation {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let mut col = vec![
+            DashTab::Validation.picker(ctx, app),
+            Widget::row(vec![
+                Line("Compare with observed counts")
+                    .small_heading()
+                    .draw(ctx)
+                    .centered_vert(),
+                ctx.style()
+                    .btn_outline_light_text("load counts")
+                    .build_def(ctx),
+            ]),
+        ];
+
+        match app.primary.observed_counts {
+            None => {
+                col.push(
+                    Text::from(Line(format!(
+                        "Choose a CSV file from data/input/{}/ with columns \
+                         name,road,intersection,from_road,to_road,bus_stop,agent_type,hour,count",
+                        app.primary.map.get_city_name()
+                    )))
+                    .wrap_to_pct(ctx, 80)
+                    .draw(ctx),
+                );
+            }
+            Some((ref path, _)) => {
+                col.push(format!("Counts from {}", path).draw_text(ctx));
+                let comparisons = compare_finished_hours(app);
+                if comparisons.is_empty() {
+                    col.push(
+                        "None of the counts cover hours that have finished simulating yet"
+                            .draw_text(ctx),
+                    );
+                } else {
+                    col.push(summarize(ctx, &comparisons));
+                    col.push(Widget::row(vec![
+                        CompareCounts::new(
+                            ctx,
+                            "Observed",
+                            "Simulated",
+                            comparisons
+                                .iter()
+                                .map(|c| (c.observed.count, c.simulated))
+                                .collect(),
+                        ),
+                        worst_matches(ctx, &comparisons),
+                    ]));
+                    col.push(
+                        ctx.style()
+                            .btn_solid_dark_text("show on map")
+                            .build_def(ctx),
+                    );
+                }
+            }
+        }
+
+        Box::new(Validation {
+            panel: Panel::new(Widget::col(col))
+                .exact_size_percent(90, 90)
+                .build(ctx),
+        })
+    }
+}
+
+impl State<App> for Validation {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => Transition::Pop,
+                "load counts" => Transition::Push(ChooseSomething::new(
+                    ctx,
+                    "Load counts",
+                    Choice::strings(
+                        abstio::list_dir(abstio::path(format!(
+                            "input/{}/",
+                            app.primary.map.get_city_name()
+                        )))
+                        .into_iter()
+                        .filter(|x| x.ends_with(".csv"))
+                        .collect(),
+                    ),
+                    Box::new(|path, ctx, app| {
+                        match ObservedCount::load_csv(&path, &app.primary.map) {
+                            Ok(counts) => {
+                                app.primary.observed_counts = Some((path, counts));
+                                Transition::Multi(vec![
+                                    Transition::Pop,
+                                    Transition::Replace(Validation::new(ctx, app)),
+                                ])
+                            }
+                            Err(err) => Transition::Replace(PopupMsg::new(
+                                ctx,
+                                "Error",
+                                vec![format!("Couldn't load {}: {}", path, err)],
+                            )),
+                        }
+                    }),
+                )),
+                "show on map" => {
+                    app.primary.layer = Some(Box::new(CountsValidation::new(ctx, app)));
+                    Transition::Pop
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed => DashTab::Validation
+                .transition(ctx, app, &self.panel)
+                .unwrap(),
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::Custom
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.clear(app.cs.dialog_bg);
+        self.panel.draw(g);
+    }
+}
+
+/// Compares the loaded counts with the simulation, but only for hours that have completely
+/// finished. Otherwise everything would look under-predicted in the middle of the day.
+pub fn compare_finished_hours(app: &App) -> Vec<CountComparison> {
+    let (_, counts) = match app.primary.observed_counts {
+        Some(ref pair) => pair,
+        None => {
+            return Vec::new();
+        }
+    };
+    let current_hour = app.primary.sim.time().get_parts().0;
+    let finished: Vec<ObservedCount> = counts
+        .iter()
+        .filter(|c| c.hour < current_hour)
+        .cloned()
+        .collect();
+    CountComparison::compare_all(&finished, app.primary.sim.get_analytics(), &app.primary.map)
+}
+
+fn summarize(ctx: &mut EventCtx, comparisons: &[CountComparison]) -> Widget {
+    let mut txt = Text::new();
+    describe(&mut txt, "All counts", &CountsSummary::new(comparisons));
+    for agent_type in AgentType::all() {
+        let subset: Vec<CountComparison> = comparisons
+            .iter()
+            .filter(|c| c.observed.agent_type == agent_type)
+            .cloned()
+            .collect();
+        if !subset.is_empty() && subset.len() != comparisons.len() {
+            describe(&mut txt, agent_type.noun(), &CountsSummary::new(&subset));
+        }
+    }
+    txt.draw(ctx)
+}
+
+fn describe(txt: &mut Text, label: &str, summary: &CountsSummary) {
+    txt.add(Line(label).small_heading());
+    txt.add(Line(format!(
+        "{} counts, {:.1}% with GEH under 5 (the usual target is 85%)",
+        prettyprint_usize(summary.num_counts),
+        summary.pct_geh_under_5
+    )));
+    txt.add(
+        Line(format!(
+            "Mean GEH {:.2}, RMSE {:.1} ({:.1}% of the mean count)",
+            summary.mean_geh, summary.rmse, summary.pct_rmse
+        ))
+        .secondary(),
+    );
+}
+
+fn worst_matches(ctx: &mut EventCtx, comparisons: &[CountComparison]) -> Widget {
+    let mut sorted: Vec<&CountComparison> = comparisons.iter().collect();
+    sorted.sort_by(|a, b| b.geh().partial_cmp(&a.geh()).unwrap());
+
+    let mut txt = Text::from(Line("Worst matches").small_heading());
+    for c in sorted.into_iter().take(10) {
+        txt.add(Line(format!(
+            "{} ({}) at {}:00",
+            c.observed.name,
+            c.observed.location.describe(),
+            c.observed.hour
+        )));
+        txt.add(
+            Line(format!(
+                "  {}: observed {}, simulated {}, GEH {:.1}",
+                c.observed.agent_type.noun(),
+                prettyprint_usize(c.observed.count),
+                prettyprint_usize(c.simulated),
+                c.geh()
+            ))
+            .secondary(),
+        );
+    }
+    txt.draw(ctx).margin_left(20)
+}
@@ -110,6 +110,7 @@ impl GameplayState for Freeform {
                         "system/assets/tools/map.svg",
                         nice_map_name(app.primary.map.get_name()),
                     )
+                    .untranslated()
                     .hotkey(lctrl(Key::L))
                     .build_widget(ctx, "change map"),
                 ctx.style()
@@ -347,12 +348,18 @@ impl State<App> for AgentSpawner {
                         });
                     }
                     let mut rng = app.primary.current_flags.sim_flags.make_rng();
-                    scenario.instantiate(
+                    if let Err(err) = scenario.instantiate(
                         &mut app.primary.sim,
                         map,
                         &mut rng,
                         &mut Timer::new("spawn trip"),
-                    );
+                    ) {
+                        return Transition::Replace(PopupMsg::new(
+                            ctx,
+                            "Error",
+                            vec![format!("Couldn't start these trips: {}", err)],
+                        ));
+                    }
                     app.primary.sim.tiny_step(map, &mut app.primary.sim_cb);
                     app.recalculate_current_selection(ctx);
                     return Transition::Pop;
@@ -566,13 +573,15 @@ pub fn spawn_agents_around(i: IntersectionID, app: &mut App) {
     }
 
     let retry_if_no_room = false;
-    scenario.instantiate_without_retries(
-        &mut app.primary.sim,
-        map,
-        &mut rng,
-        retry_if_no_room,
-        &mut timer,
-    );
+    scenario
+        .instantiate_without_retries(
+            &mut app.primary.sim,
+            map,
+            &mut rng,
+            retry_if_no_room,
+            &mut timer,
+        )
+        .unwrap();
     app.primary.sim.tiny_step(map, &mut app.primary.sim_cb);
 }
 
@@ -132,9 +132,18 @@ impl GameplayMode {
             let map_area = map.get_boundary_polygon().clone();
             let map_bounds = map.get_gps_bounds().clone();
             let mut rng = sim::fork_rng(&mut rng);
+            // Cities can point to local census data instead of the global file
+            let census_path = abstio::path(format!("input/{}/census.json", map.get_city_name()));
+            let census_config = if abstio::file_exists(&census_path) {
+                abstio::read_json::<popdat::CensusConfig>(census_path, timer)
+            } else {
+                popdat::CensusConfig::default()
+            };
 
             LoadScenario::Future(Box::pin(async move {
-                let areas = popdat::CensusArea::fetch_all_for_map(&map_area, &map_bounds).await?;
+                let areas =
+                    popdat::CensusArea::fetch_from_source(&census_config, &map_area, &map_bounds)
+                        .await?;
 
                 let scenario_from_app: Box<dyn Send + FnOnce(&App) -> Scenario> =
                     Box::new(move |app: &App| {
@@ -119,6 +119,7 @@ impl GameplayState for PlayScenario {
                         "system/assets/tools/map.svg",
                         nice_map_name(app.primary.map.get_name()),
                     )
+                    .untranslated()
                     .hotkey(lctrl(Key::L))
                     .build_widget(ctx, "change map"),
                 ctx.style()
@@ -126,6 +127,7 @@ impl GameplayState for PlayScenario {
                         "system/assets/tools/calendar.svg",
                         &self.scenario_name,
                     )
+                    .untranslated()
                     .hotkey(Key::S)
                     .build_widget(ctx, "change scenario"),
                 ctx.style()
@@ -211,6 +213,12 @@ impl EditScenarioModifiers {
                 .btn_solid_dark_text("Repeat schedule multiple days")
                 .build_def(ctx),
         ]));
+        rows.push(Widget::row(vec![
+            Spinner::new(ctx, (1, 100), 10).named("pct_households"),
+            ctx.style()
+                .btn_solid_dark_text("Households give up a car")
+                .build_def(ctx),
+        ]));
         rows.push(Widget::horiz_separator(ctx, 0.5));
         rows.push(
             Widget::row(vec![
@@ -306,6 +314,16 @@ impl State<App> for EditScenarioModifiers {
                         self.modifiers.clone(),
                     ));
                 }
+                "Households give up a car" => {
+                    self.modifiers.push(ScenarioModifier::GiveUpCars {
+                        pct_households: self.panel.spinner("pct_households") as usize,
+                    });
+                    return Transition::Replace(EditScenarioModifiers::new(
+                        ctx,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
                 x => {
                     if let Some(x) = x.strip_prefix("delete modifier ") {
                         self.modifiers.remove(x.parse::<usize>().unwrap() - 1);
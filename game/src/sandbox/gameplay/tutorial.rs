@@ -1,5 +1,7 @@
 use std::collections::BTreeSet;
 
+use anyhow::Result;
+
 use abstutil::Timer;
 use geom::{ArrowCap, Distance, Duration, PolyLine, Pt2D, Time};
 use map_gui::tools::{grey_out_map, Minimap, PopupMsg};
@@ -17,7 +19,7 @@ use widgetry::{
 
 use crate::app::{App, Transition};
 use crate::challenges::cutscene::CutsceneBuilder;
-use crate::common::{tool_panel, MinimapController, Warping};
+use crate::common::{sandbox_tool_panel, MinimapController, Warping};
 use crate::edit::EditMode;
 use crate::sandbox::gameplay::{GameplayMode, GameplayState};
 use crate::sandbox::{
@@ -34,6 +36,8 @@ pub struct Tutorial {
 
     msg_panel: Option<Panel>,
     warped: bool,
+    // If this stage's custom_spawn failed, tell the player on the first event
+    spawn_error: Option<String>,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -103,6 +107,17 @@ impl Tutorial {
         controls: &mut SandboxControls,
         tut: &mut TutorialState,
     ) -> Option<Transition> {
+        if let Some(err) = self.spawn_error.take() {
+            return Some(Transition::Push(PopupMsg::new(
+                ctx,
+                "Error",
+                vec![format!(
+                    "Couldn't set up this part of the tutorial: {}",
+                    err
+                )],
+            )));
+        }
+
         // First of all, might need to initiate warping
         if !self.warped {
             if let Some((ref id, zoom)) = tut.stage().warp_to {
@@ -551,7 +566,7 @@ struct Stage {
     )>,
     task: Task,
     warp_to: Option<(ID, f64)>,
-    custom_spawn: Option<Box<dyn Fn(&mut App)>>,
+    custom_spawn: Option<Box<dyn Fn(&mut App) -> Result<()>>>,
     make_scenario: Option<ScenarioGenerator>,
 }
 
@@ -601,7 +616,7 @@ impl Stage {
         self
     }
 
-    fn custom_spawn(mut self, cb: Box<dyn Fn(&mut App)>) -> Stage {
+    fn custom_spawn(mut self, cb: Box<dyn Fn(&mut App) -> Result<()>>) -> Stage {
         assert!(self.custom_spawn.is_none());
         self.custom_spawn = Some(cb);
         self
@@ -788,8 +803,11 @@ impl TutorialState {
             app.primary.current_selection = None;
         }
 
+        let mut spawn_error = None;
         if let Some(ref cb) = self.stage().custom_spawn {
-            (cb)(app);
+            if let Err(err) = (cb)(app) {
+                spawn_error = Some(err.to_string());
+            }
             app.primary
                 .sim
                 .tiny_step(&app.primary.map, &mut app.primary.sim_cb);
@@ -852,6 +870,7 @@ impl TutorialState {
                 None
             },
             warped: false,
+            spawn_error,
         })
     }
 
@@ -876,7 +895,7 @@ impl TutorialState {
             fire_station: app.primary.map.find_b_by_osm_id(bldg(731238736)).unwrap(),
         };
 
-        let tool_panel = tool_panel(ctx);
+        let tool_panel = sandbox_tool_panel(ctx);
         let time = TimePanel::new(ctx, app);
         let speed = SpeedControls::new(ctx, app);
         let agent_meter = AgentMeter::new(ctx, app);
@@ -1073,7 +1092,7 @@ impl TutorialState {
                         map,
                         &mut rng,
                         &mut Timer::new("spawn trip"),
-                    );
+                    )?;
                     app.primary.sim.tiny_step(map, &mut app.primary.sim_cb);
 
                     // And add some noise
@@ -1084,6 +1103,7 @@ impl TutorialState {
                             .unwrap(),
                         app,
                     );
+                    Ok(())
                 }))
                 .msg(
                     vec!["Alright alright, no need to wear out your spacebar."],
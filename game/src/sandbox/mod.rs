@@ -18,9 +18,10 @@ use widgetry::{
 pub use self::gameplay::{spawn_agents_around, GameplayMode, TutorialPointer, TutorialState};
 use self::misc_tools::{RoutePreview, TrafficRecorder};
 pub use self::speed::{SpeedControls, TimePanel};
+use self::time_lapse::{TimeLapse, TimeLapseSetup};
 pub use self::time_warp::TimeWarpScreen;
 use crate::app::{App, Transition};
-use crate::common::{tool_panel, CommonState, MinimapController};
+use crate::common::{sandbox_tool_panel, CommonState, ExportFigure, MinimapController};
 use crate::debug::DebugMode;
 use crate::edit::{
     can_edit_lane, EditMode, LaneEditor, SaveEdits, StopSignEditor, TrafficSignalEditor,
@@ -34,6 +35,7 @@ pub mod dashboards;
 pub mod gameplay;
 mod misc_tools;
 mod speed;
+mod time_lapse;
 mod time_warp;
 mod uber_turns;
 
@@ -42,9 +44,13 @@ pub struct SandboxMode {
     pub gameplay_mode: GameplayMode,
 
     pub controls: SandboxControls,
+    /// While recording, the usual controls are hidden and the simulation only steps for the
+    /// recording
+    time_lapse: Option<TimeLapse>,
 
     recalc_unzoomed_agent: Option<Time>,
     last_cs: ColorSchemeChoice,
+    last_ui_language: Option<String>,
 }
 
 pub struct SandboxControls {
@@ -113,10 +119,30 @@ impl State<App> for SandboxMode {
         }
 
         if app.opts.color_scheme != self.last_cs {
-            self.last_cs = app.opts.color_scheme;
+            self.last_cs = app.opts.color_scheme.clone();
             self.controls.recreate_panels(ctx, app);
             self.gameplay.recreate_panels(ctx, app);
         }
+        if app.opts.ui_language != self.last_ui_language {
+            self.last_ui_language = app.opts.ui_language.clone();
+            self.controls.recreate_panels(ctx, app);
+            self.gameplay.recreate_panels(ctx, app);
+        }
+
+        if let Some(ref mut recorder) = self.time_lapse {
+            let maybe_t = recorder.event(ctx, app);
+            // Layers depend on the time, so update them before the frame is captured
+            if let Some(ref m) = self.controls.minimap {
+                if let Some(t) = PickLayer::update(ctx, app, m.get_panel()) {
+                    return t;
+                }
+            }
+            if let Some(t) = maybe_t {
+                self.time_lapse = None;
+                return t;
+            }
+            return Transition::Keep;
+        }
 
         // Do this before gameplay
         if self.gameplay.can_move_canvas() {
@@ -197,6 +223,17 @@ impl State<App> for SandboxMode {
                     "settings" => {
                         return Transition::Push(OptionsPanel::new(ctx, app));
                     }
+                    "export figure" => {
+                        let just_prepared = ExportFigure::prepare(ctx, app);
+                        if just_prepared {
+                            self.controls.recreate_panels(ctx, app);
+                            self.gameplay.recreate_panels(ctx, app);
+                        }
+                        return Transition::Push(ExportFigure::new(ctx, app, just_prepared));
+                    }
+                    "record time-lapse" => {
+                        return Transition::Push(TimeLapseSetup::new(ctx, app));
+                    }
                     _ => unreachable!(),
                 },
                 _ => {}
@@ -226,6 +263,10 @@ impl State<App> for SandboxMode {
         if let Some(ref l) = app.primary.layer {
             l.draw(g, app);
         }
+        if let Some(ref r) = self.time_lapse {
+            r.draw(g);
+            return;
+        }
 
         if let Some(ref c) = self.controls.common {
             c.draw(g, app);
@@ -460,6 +501,17 @@ impl AgentMeter {
                     .align_right(),
             ]));
         }
+        if let Some(n) = app.primary.sim.num_trajectory_frames() {
+            rows.push(Widget::row(vec![
+                format!("{} frames of trajectories recorded", prettyprint_usize(n))
+                    .draw_text(ctx)
+                    .centered_vert(),
+                ctx.style()
+                    .btn_solid_dark_text("Where are trajectories?")
+                    .build_def(ctx)
+                    .align_right(),
+            ]));
+        }
 
         let panel = Panel::new(Widget::col(rows))
             .aligned(HorizontalAlignment::Right, VerticalAlignment::Top)
@@ -499,6 +551,17 @@ impl AgentMeter {
                 "Stop" => {
                     app.primary.sim.save_recorded_traffic(&app.primary.map);
                 }
+                "Where are trajectories?" => {
+                    let path = app.primary.sim.trajectories_path().unwrap();
+                    return Some(Transition::Push(PopupMsg::new(
+                        ctx,
+                        "Trajectories",
+                        vec![
+                            format!("Every frame is written to {} as it's recorded.", path),
+                            "Replay them from the internal dev tools.".to_string(),
+                        ],
+                    )));
+                }
                 _ => unreachable!(),
             },
             _ => {}
@@ -758,23 +821,32 @@ impl State<App> for SandboxLoader {
                 }
                 LoadStage::GotScenario(mut scenario) => {
                     let scenario_name = scenario.scenario_name.clone();
-                    ctx.loading_screen("instantiate scenario", |_, mut timer| {
-                        if let GameplayMode::PlayScenario(_, _, ref modifiers) = self.mode {
-                            for m in modifiers {
-                                scenario = m.apply(&app.primary.map, scenario);
+                    let result =
+                        ctx.loading_screen("instantiate scenario", |_, mut timer| -> Result<()> {
+                            if let GameplayMode::PlayScenario(_, _, ref modifiers) = self.mode {
+                                for m in modifiers {
+                                    scenario = m.apply(&app.primary.map, scenario);
+                                }
                             }
-                        }
 
-                        scenario.instantiate(
-                            &mut app.primary.sim,
-                            &app.primary.map,
-                            &mut app.primary.current_flags.sim_flags.make_rng(),
-                            &mut timer,
-                        );
-                        app.primary
-                            .sim
-                            .tiny_step(&app.primary.map, &mut app.primary.sim_cb);
-                    });
+                            scenario.instantiate(
+                                &mut app.primary.sim,
+                                &app.primary.map,
+                                &mut app.primary.current_flags.sim_flags.make_rng(),
+                                &mut timer,
+                            )?;
+                            app.primary
+                                .sim
+                                .tiny_step(&app.primary.map, &mut app.primary.sim_cb);
+                            Ok(())
+                        });
+                    if let Err(err) = result {
+                        return Transition::Replace(PopupMsg::new(
+                            ctx,
+                            "Error",
+                            vec![format!("Couldn't start {}: {}", scenario_name, err)],
+                        ));
+                    }
 
                     self.stage = Some(LoadStage::LoadingPrebaked(scenario_name));
                     continue;
@@ -836,8 +908,10 @@ impl State<App> for SandboxLoader {
                         controls: SandboxControls::new(ctx, app, &gameplay),
                         gameplay,
                         gameplay_mode: self.mode.clone(),
+                        time_lapse: None,
                         recalc_unzoomed_agent: None,
-                        last_cs: app.opts.color_scheme,
+                        last_cs: app.opts.color_scheme.clone(),
+                        last_ui_language: app.opts.ui_language.clone(),
                     });
 
                     let mut transitions = vec![Transition::Replace(sandbox)];
@@ -904,7 +978,7 @@ impl SandboxControls {
                 None
             },
             tool_panel: if gameplay.has_tool_panel() {
-                Some(tool_panel(ctx))
+                Some(sandbox_tool_panel(ctx))
             } else {
                 None
             },
@@ -933,7 +1007,7 @@ impl SandboxControls {
 
     fn recreate_panels(&mut self, ctx: &mut EventCtx, app: &App) {
         if self.tool_panel.is_some() {
-            self.tool_panel = Some(tool_panel(ctx));
+            self.tool_panel = Some(sandbox_tool_panel(ctx));
         }
         if let Some(ref mut speed) = self.speed {
             speed.recreate_panel(ctx, app);
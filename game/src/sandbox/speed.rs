@@ -71,7 +71,7 @@ impl SpeedControls {
                 ]
                 .into_iter()
                 .map(|(s, label)| {
-                    let mut txt = Text::from(Line(label).small());
+                    let mut txt = Text::from(Line(ctx.tr(label)).small());
                     txt.extend(Text::tooltip(ctx, Key::LeftArrow, "slow down"));
                     txt.extend(Text::tooltip(ctx, Key::RightArrow, "speed up"));
 
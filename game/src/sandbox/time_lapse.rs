@@ -0,0 +1,514 @@
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use anyhow::Result;
+
+use abstutil::Timer;
+use geom::{Duration, Time};
+use map_gui::tools::{grey_out_map, PopupMsg};
+use widgetry::{
+    Choice, DrawBaselayer, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome, Panel, State,
+    StyledButtons, Text, TextExt, UpdateType, VerticalAlignment, Widget,
+};
+
+use crate::app::{App, Transition};
+use crate::sandbox::SandboxMode;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Gif,
+    Apng,
+    /// Leave the frames as numbered PNG files, to stitch together some other way
+    Frames,
+}
+
+/// Asks how to record a time-lapse of the current view.
+pub struct TimeLapseSetup {
+    panel: Panel,
+}
+
+impl TimeLapseSetup {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State<App>> {
+        let name = app.primary.map.get_name();
+        Box::new(TimeLapseSetup {
+            panel: Panel::new(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Record time-lapse").small_heading().draw(ctx),
+                    ctx.style().btn_close_widget(ctx),
+                ]),
+                "The simulation runs forward, capturing the map as it's shown in the window now."
+                    .draw_text(ctx),
+                if app.primary.layer.is_some() {
+                    "The current layer is included.".draw_text(ctx)
+                } else {
+                    "Open a layer first to record something like delay or throughput."
+                        .draw_text(ctx)
+                },
+                Widget::row(vec![
+                    "Simulated time between frames:"
+                        .draw_text(ctx)
+                        .centered_vert(),
+                    Widget::dropdown(
+                        ctx,
+                        "interval",
+                        Duration::minutes(5),
+                        vec![1, 5, 10, 15, 30, 60]
+                            .into_iter()
+                            .map(|m| {
+                                Choice::new(Duration::minutes(m).to_string(), Duration::minutes(m))
+                            })
+                            .collect(),
+                    ),
+                ]),
+                Widget::row(vec![
+                    "Record for:".draw_text(ctx).centered_vert(),
+                    Widget::dropdown(
+                        ctx,
+                        "duration",
+                        Duration::hours(4),
+                        vec![1, 2, 4, 8, 12, 24]
+                            .into_iter()
+                            .map(|h| {
+                                Choice::new(Duration::hours(h).to_string(), Duration::hours(h))
+                            })
+                            .collect(),
+                    ),
+                ]),
+                Widget::row(vec![
+                    "Frames per second:".draw_text(ctx).centered_vert(),
+                    Widget::dropdown(
+                        ctx,
+                        "fps",
+                        10,
+                        vec![5, 10, 15, 25]
+                            .into_iter()
+                            .map(|x| Choice::new(x.to_string(), x))
+                            .collect(),
+                    ),
+                ]),
+                Widget::row(vec![
+                    "Format:".draw_text(ctx).centered_vert(),
+                    Widget::dropdown(
+                        ctx,
+                        "format",
+                        Format::Gif,
+                        vec![
+                            Choice::new("GIF", Format::Gif),
+                            Choice::new("APNG", Format::Apng),
+                            Choice::new("numbered PNG frames", Format::Frames),
+                        ],
+                    ),
+                ]),
+                format!(
+                    "Time-lapses are saved in {}",
+                    abstio::path_player(format!("time_lapses/{}/{}", name.city, name.map))
+                )
+                .draw_text(ctx),
+                ctx.style()
+                    .btn_solid_dark_text("Start recording")
+                    .hotkey(Key::Enter)
+                    .build_def(ctx),
+            ]))
+            .build(ctx),
+        })
+    }
+}
+
+impl State<App> for TimeLapseSetup {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.panel.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => Transition::Pop,
+                "Start recording" => {
+                    let recorder = TimeLapse::new(
+                        ctx,
+                        app,
+                        self.panel.dropdown_value("interval"),
+                        self.panel.dropdown_value("duration"),
+                        self.panel.dropdown_value("fps"),
+                        self.panel.dropdown_value("format"),
+                    );
+                    Transition::Multi(vec![
+                        Transition::Pop,
+                        Transition::ModifyState(Box::new(move |state, _, _| {
+                            let sandbox = state.downcast_mut::<SandboxMode>().unwrap();
+                            sandbox.time_lapse = Some(recorder);
+                        })),
+                    ])
+                }
+                _ => unreachable!(),
+            },
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::PreviousState
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        grey_out_map(g, app);
+        self.panel.draw(g);
+    }
+}
+
+/// Steps the simulation and captures the screen at a fixed interval of simulated time. Lives in
+/// `SandboxMode`, so the current layer keeps updating as time passes. The runner writes each frame
+/// after the event that requests it, so frames are only combined on the next event.
+pub struct TimeLapse {
+    dir: String,
+    interval: Duration,
+    fps: usize,
+    format: Format,
+    end: Time,
+    next_frame: Time,
+    frames: Vec<String>,
+    done: bool,
+
+    time_overlay: Panel,
+    controls: Panel,
+}
+
+impl TimeLapse {
+    fn new(
+        ctx: &mut EventCtx,
+        app: &mut App,
+        interval: Duration,
+        duration: Duration,
+        fps: usize,
+        format: Format,
+    ) -> TimeLapse {
+        // Don't capture the mouseover highlighting
+        app.primary.current_selection = None;
+
+        let name = app.primary.map.get_name();
+        let now = app.primary.sim.time();
+        TimeLapse {
+            dir: abstio::path_player(format!(
+                "time_lapses/{}/{}/{}",
+                name.city,
+                name.map,
+                now.as_filename()
+            )),
+            interval,
+            fps,
+            format,
+            end: now + duration,
+            next_frame: now,
+            frames: Vec::new(),
+            done: false,
+
+            time_overlay: make_time_overlay(ctx, app),
+            controls: Panel::new(Widget::col(vec![
+                Text::new().draw(ctx).named("progress"),
+                ctx.style()
+                    .btn_solid_dark_text("stop recording")
+                    .hotkey(Key::Escape)
+                    .build_def(ctx),
+            ]))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Bottom)
+            .build(ctx),
+        }
+    }
+
+    /// Returns a transition once recording is over.
+    pub fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Option<Transition> {
+        if self.done {
+            return Some(self.finish(ctx));
+        }
+
+        if let Outcome::Clicked(x) = self.controls.event(ctx) {
+            match x.as_ref() {
+                "stop recording" => {
+                    return Some(self.finish(ctx));
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        if ctx.input.nonblocking_is_update_event().is_some() {
+            ctx.input.use_update_event();
+            if app.primary.sim.time() < self.next_frame {
+                app.primary.sim.time_limited_step(
+                    &app.primary.map,
+                    self.next_frame - app.primary.sim.time(),
+                    Duration::seconds(0.033),
+                    &mut app.primary.sim_cb,
+                );
+            }
+            if app.primary.sim.time() >= self.next_frame {
+                let path = format!("{}/frame_{:04}.png", self.dir, self.frames.len() + 1);
+                ctx.request_update(UpdateType::ScreenCapture { path: path.clone() });
+                self.frames.push(path);
+                self.time_overlay = make_time_overlay(ctx, app);
+                self.controls.replace(
+                    ctx,
+                    "progress",
+                    Text::from(Line(format!(
+                        "Recorded {} frames, until {}",
+                        self.frames.len(),
+                        self.end.ampm_tostring()
+                    )))
+                    .draw(ctx),
+                );
+
+                if self.next_frame >= self.end {
+                    self.done = true;
+                } else if self.next_frame + self.interval > self.end {
+                    self.next_frame = self.end;
+                } else {
+                    self.next_frame = self.next_frame + self.interval;
+                }
+            }
+        }
+
+        ctx.request_update(UpdateType::Game);
+        None
+    }
+
+    /// Only the time is captured in frames; the controls are just for watching.
+    pub fn draw(&self, g: &mut GfxCtx) {
+        self.time_overlay.draw(g);
+        if !g.is_screencap() {
+            self.controls.draw(g);
+        }
+    }
+
+    fn finish(&self, ctx: &mut EventCtx) -> Transition {
+        if self.frames.is_empty() {
+            return Transition::Push(PopupMsg::new(
+                ctx,
+                "Recording stopped",
+                vec!["No frames were captured"],
+            ));
+        }
+        let result = match self.format {
+            Format::Gif => {
+                let path = format!("{}.gif", self.dir);
+                ctx.loading_screen("encode GIF", |_, timer| {
+                    encode_gif(&self.frames, self.fps, &path, timer)
+                })
+                .map(|_| path)
+            }
+            Format::Apng => {
+                let path = format!("{}.png", self.dir);
+                encode_apng(&self.frames, self.fps, &path).map(|_| path)
+            }
+            Format::Frames => Ok(self.dir.clone()),
+        };
+        match result {
+            Ok(path) => {
+                if self.format != Format::Frames {
+                    if let Err(err) = std::fs::remove_dir_all(&self.dir) {
+                        warn!("Couldn't clean up {}: {}", self.dir, err);
+                    }
+                }
+                Transition::Push(PopupMsg::new(
+                    ctx,
+                    "Time-lapse recorded",
+                    vec![format!("{} frames saved to {}", self.frames.len(), path)],
+                ))
+            }
+            Err(err) => Transition::Push(PopupMsg::new(
+                ctx,
+                "Recording failed",
+                vec![format!("Frames are still in {}: {}", self.dir, err)],
+            )),
+        }
+    }
+}
+
+fn make_time_overlay(ctx: &mut EventCtx, app: &App) -> Panel {
+    Panel::new(
+        Text::from_multiline(vec![
+            Line(app.primary.sim.time().ampm_tostring()).big_heading_plain(),
+            Line(app.primary.map.get_edits().edits_name.clone()),
+        ])
+        .draw(ctx),
+    )
+    .aligned(HorizontalAlignment::Left, VerticalAlignment::Top)
+    .build(ctx)
+}
+
+fn encode_gif(frames: &[String], fps: usize, path: &str, timer: &mut Timer) -> Result<()> {
+    let (width, height) = image::open(&frames[0])?.to_rgba8().dimensions();
+    let mut encoder = gif::Encoder::new(
+        BufWriter::new(File::create(path)?),
+        width.try_into()?,
+        height.try_into()?,
+        &[],
+    )?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+
+    timer.start_iter("quantize frames", frames.len());
+    for frame_path in frames {
+        timer.next();
+        let mut pixels = image::open(frame_path)?.to_rgba8();
+        if pixels.dimensions() != (width, height) {
+            bail!("{} isn't the same size as the first frame", frame_path);
+        }
+        // Speed 10 is the default tradeoff between quality and time
+        let mut frame = gif::Frame::from_rgba_speed(width as u16, height as u16, &mut pixels, 10);
+        // In hundredths of a second
+        frame.delay = (100.0 / fps as f64).round() as u16;
+        encoder.write_frame(&frame)?;
+    }
+    Ok(())
+}
+
+const PNG_SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// Animated PNGs are regular PNGs with a few extra chunks, so each frame's compressed image data
+/// can be copied over directly. See https://wiki.mozilla.org/APNG_Specification.
+fn encode_apng(frames: &[String], fps: usize, path: &str) -> Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(&PNG_SIGNATURE)?;
+
+    let mut first_header: Option<Vec<u8>> = None;
+    let mut sequence: u32 = 0;
+    for (idx, frame_path) in frames.iter().enumerate() {
+        let bytes = std::fs::read(frame_path)?;
+        let chunks = png_chunks(&bytes)?;
+        let header = chunks
+            .iter()
+            .find(|(kind, _)| *kind == b"IHDR")
+            .ok_or_else(|| anyhow!("{} has no header", frame_path))?
+            .1;
+
+        if let Some(ref first) = first_header {
+            if first.as_slice() != header {
+                bail!("{} isn't the same size as the first frame", frame_path);
+            }
+        } else {
+            write_chunk(&mut out, b"IHDR", header)?;
+            let mut animation_control = Vec::new();
+            animation_control.extend_from_slice(&(frames.len() as u32).to_be_bytes());
+            // Loop forever
+            animation_control.extend_from_slice(&0u32.to_be_bytes());
+            write_chunk(&mut out, b"acTL", &animation_control)?;
+            first_header = Some(header.to_vec());
+        }
+
+        let mut frame_control = Vec::new();
+        frame_control.extend_from_slice(&sequence.to_be_bytes());
+        sequence += 1;
+        // The width and height are the first 8 bytes of the header
+        frame_control.extend_from_slice(&header[0..8]);
+        // No offset
+        frame_control.extend_from_slice(&0u32.to_be_bytes());
+        frame_control.extend_from_slice(&0u32.to_be_bytes());
+        // Each frame lasts 1/fps seconds
+        frame_control.extend_from_slice(&1u16.to_be_bytes());
+        frame_control.extend_from_slice(&(fps as u16).to_be_bytes());
+        // Don't dispose or blend; every frame covers the whole image
+        frame_control.push(0);
+        frame_control.push(0);
+        write_chunk(&mut out, b"fcTL", &frame_control)?;
+
+        for (kind, data) in chunks {
+            if kind != b"IDAT" {
+                continue;
+            }
+            // The first frame doubles as the static image for viewers that don't understand APNG
+            if idx == 0 {
+                write_chunk(&mut out, b"IDAT", data)?;
+            } else {
+                let mut frame_data = Vec::with_capacity(4 + data.len());
+                frame_data.extend_from_slice(&sequence.to_be_bytes());
+                sequence += 1;
+                frame_data.extend_from_slice(data);
+                write_chunk(&mut out, b"fdAT", &frame_data)?;
+            }
+        }
+    }
+
+    write_chunk(&mut out, b"IEND", &[])?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Splits a PNG file into (type, data) chunks.
+fn png_chunks(bytes: &[u8]) -> Result<Vec<(&[u8], &[u8])>> {
+    if !bytes.starts_with(&PNG_SIGNATURE) {
+        bail!("not a PNG file");
+    }
+    let mut chunks = Vec::new();
+    let mut idx = PNG_SIGNATURE.len();
+    while idx + 8 <= bytes.len() {
+        let len = u32::from_be_bytes(bytes[idx..idx + 4].try_into()?) as usize;
+        let data_start = idx + 8;
+        let data_end = data_start + len;
+        // Skip the CRC at the end
+        if data_end + 4 > bytes.len() {
+            bail!("truncated PNG file");
+        }
+        chunks.push((&bytes[idx + 4..data_start], &bytes[data_start..data_end]));
+        idx = data_end + 4;
+    }
+    Ok(chunks)
+}
+
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(kind);
+    crc.update(data);
+    out.write_all(&crc.finalize().to_be_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_apng() {
+        let dir = std::env::temp_dir().join(format!("time_lapse_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut frames = Vec::new();
+        for (idx, color) in vec![[255, 0, 0, 255], [0, 0, 255, 255]]
+            .into_iter()
+            .enumerate()
+        {
+            let path = dir
+                .join(format!("frame_{}.png", idx))
+                .to_str()
+                .unwrap()
+                .to_string();
+            image::RgbaImage::from_pixel(3, 2, image::Rgba(color))
+                .save(&path)
+                .unwrap();
+            frames.push(path);
+        }
+        let path = dir.join("animated.png").to_str().unwrap().to_string();
+        encode_apng(&frames, 10, &path).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        let chunks = png_chunks(&bytes).unwrap();
+        let kinds: Vec<&[u8]> = chunks.iter().map(|(kind, _)| *kind).collect();
+        assert_eq!(
+            kinds,
+            vec![b"IHDR", b"acTL", b"fcTL", b"IDAT", b"fcTL", b"fdAT", b"IEND"]
+        );
+        let sequence = |data: &[u8]| u32::from_be_bytes(data[0..4].try_into().unwrap());
+        // 2 frames, looping forever
+        assert_eq!(chunks[1].1, &[0, 0, 0, 2, 0, 0, 0, 0]);
+        // fcTL and fdAT chunks share one sequence
+        assert_eq!(sequence(chunks[2].1), 0);
+        assert_eq!(sequence(chunks[4].1), 1);
+        assert_eq!(sequence(chunks[5].1), 2);
+        // Every frame is 3x2 and lasts 1/10 of a second
+        assert_eq!(&chunks[4].1[4..12], &[0, 0, 0, 3, 0, 0, 0, 2]);
+        assert_eq!(&chunks[4].1[20..24], &[0, 1, 0, 10]);
+
+        // Viewers that don't understand APNG see the first frame
+        let still = image::open(&path).unwrap().to_rgba8();
+        assert_eq!(still.dimensions(), (3, 2));
+        assert_eq!(still.get_pixel(0, 0).0, [255, 0, 0, 255]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -8,15 +8,19 @@
 // it's now 01:01:00.0
 // > curl http://localhost:1234/data/get-road-thruput
 // ... huge JSON blob
+// > curl http://localhost:1234/map/tiles/16/10487/22889.mvt
+// ... a Mapbox Vector Tile
 
 #[macro_use]
 extern crate anyhow;
 #[macro_use]
 extern crate log;
 
+mod tiles;
+
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryFrom;
-use std::sync::RwLock;
+use std::sync::{Mutex, RwLock};
 
 use anyhow::Result;
 use hyper::{Body, Request, Response, Server, StatusCode};
@@ -48,6 +52,7 @@ lazy_static::lazy_static! {
             opts: SimOptions::default(),
         }
     });
+    static ref TILES: Mutex<tiles::TileCache> = Mutex::new(tiles::TileCache::default());
 }
 
 #[tokio::main]
@@ -92,25 +97,56 @@ async fn serve_req(req: Request<Body>) -> Result<Response<Body>, hyper::Error> {
             .collect();
     let body = hyper::body::to_bytes(req).await?.to_vec();
     info!("Handling {}", path);
-    Ok(
-        match handle_command(
+
+    // Vector tiles are binary and only need to read the map and simulation
+    if path.starts_with("/map/tiles/") || path.starts_with("/data/tiles/") {
+        return Ok(
+            match tiles::handle(&path, &SIM.read().unwrap(), &MAP.read().unwrap(), &TILES) {
+                Ok(tile) => Response::builder()
+                    .header("Content-Type", "application/vnd.mapbox-vector-tile")
+                    .header("Access-Control-Allow-Origin", "*")
+                    .body(Body::from(tile))
+                    .unwrap(),
+                Err(err) => {
+                    error!("{}: {}", path, err);
+                    Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(Body::from(format!("Bad tile {}: {}", path, err)))
+                        .unwrap()
+                }
+            },
+        );
+    }
+
+    let result = {
+        let mut sim = SIM.write().unwrap();
+        let mut map = MAP.write().unwrap();
+        let result = handle_command(
             &path,
             &params,
             &body,
-            &mut SIM.write().unwrap(),
-            &mut MAP.write().unwrap(),
+            &mut sim,
+            &mut map,
             &mut LOAD.write().unwrap(),
-        ) {
-            Ok(resp) => Response::new(Body::from(resp)),
-            Err(err) => {
-                error!("{}: {}", path, err);
-                Response::builder()
-                    .status(StatusCode::BAD_REQUEST)
-                    .body(Body::from(format!("Bad command {}: {}", path, err)))
-                    .unwrap()
-            }
-        },
-    )
+        );
+        // Any command might change the simulation, and these replace the map. Do this before
+        // anyone can read the new state.
+        TILES
+            .lock()
+            .unwrap()
+            .invalidate(path == "/sim/reset" || path == "/sim/load");
+        result
+    };
+    Ok(match result {
+        Ok(resp) => Response::new(Body::from(resp)),
+        Err(err) => {
+            error!("{}: {}", path, err);
+            Response::builder()
+                .status(StatusCode::BAD_REQUEST)
+                .body(Body::from(format!("Bad command {}: {}", path, err)))
+                .unwrap()
+        }
+    })
 }
 
 fn handle_command(
@@ -175,7 +211,7 @@ fn handle_command(
             let mut scenario = Scenario::empty(map, "one-shot");
             scenario.people = ExternalPerson::import(map, vec![input])?;
             let mut rng = XorShiftRng::seed_from_u64(load.rng_seed);
-            scenario.instantiate(sim, map, &mut rng, &mut Timer::throwaway());
+            scenario.instantiate(sim, map, &mut rng, &mut Timer::throwaway())?;
             Ok(format!(
                 "{} created",
                 sim.get_all_people().last().unwrap().id
@@ -366,7 +402,11 @@ fn handle_command(
         // Controlling the map
         "/map/get-edits" => {
             let mut edits = map.get_edits().clone();
+            // The history refers to positions in the full list of commands, so it's meaningless
+            // once compressed
             edits.commands.clear();
+            edits.redo_stack.clear();
+            edits.checkpoints.clear();
             edits.compress(map);
             Ok(abstutil::to_json(&edits.to_permanent(map)))
         }
@@ -495,7 +535,9 @@ impl LoadSim {
 
         let mut rng = XorShiftRng::seed_from_u64(self.rng_seed);
         let mut sim = Sim::new(&map, self.opts.clone());
-        scenario.instantiate(&mut sim, &map, &mut rng, timer);
+        scenario
+            .instantiate(&mut sim, &map, &mut rng, timer)
+            .unwrap();
 
         (map, sim)
     }
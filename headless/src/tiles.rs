@@ -0,0 +1,814 @@
+//! Serves the map and the live simulation as Mapbox Vector Tiles
+//! (https://github.com/mapbox/vector-tile-spec/tree/master/2.1), so web maps like MapLibre can show
+//! them directly. Tiles use the usual z/x/y scheme in Web Mercator.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use aabb_quadtree::QuadTree;
+use anyhow::Result;
+
+use geom::{Bounds, Duration, LonLat, Polygon, Pt2D, Time};
+use map_model::osm::RoadRank;
+use map_model::{BuildingID, BuildingType, BusStopID, IntersectionID, Map, Road, RoadID};
+use sim::{AgentID, CarStatus, Sim, UnzoomedAgent};
+
+/// The size of a tile's coordinate space
+const EXTENT: u32 = 4096;
+/// How far past the edges of a tile to keep geometry, in the tile's coordinate space, so lines and
+/// points along the edges aren't cut off
+const BUFFER: i64 = 64;
+/// Buildings make low-zoom tiles huge, so leave them out until zoomed in this far
+const MIN_ZOOM_FOR_BUILDINGS: u32 = 14;
+
+/// Handles `/map/tiles/{z}/{x}/{y}.mvt` and `/data/tiles/{z}/{x}/{y}.mvt`.
+pub fn handle(path: &str, sim: &Sim, map: &Map, cache: &Mutex<TileCache>) -> Result<Vec<u8>> {
+    if let Some(rest) = path.strip_prefix("/map/tiles/") {
+        let tile = TileID::parse(rest)?;
+        let index = cache.lock().unwrap().map_index(map);
+        Ok(map_tile(map, &index, tile))
+    } else if let Some(rest) = path.strip_prefix("/data/tiles/") {
+        let tile = TileID::parse(rest)?;
+        let index = cache.lock().unwrap().map_index(map);
+        let data = cache.lock().unwrap().sim_data(sim, map);
+        Ok(sim_tile(sim, map, &index, &data, tile))
+    } else {
+        bail!("Unknown tiles")
+    }
+}
+
+/// Web maps request many tiles at once, so the work they share is kept until the map or the
+/// simulation changes.
+#[derive(Default)]
+pub struct TileCache {
+    map_index: Option<Arc<MapIndex>>,
+    sim_data: Option<Arc<SimData>>,
+}
+
+impl TileCache {
+    /// Call after anything that might change the simulation, and say if the map changed too.
+    pub fn invalidate(&mut self, map_changed: bool) {
+        self.sim_data = None;
+        if map_changed {
+            self.map_index = None;
+        }
+    }
+
+    fn map_index(&mut self, map: &Map) -> Arc<MapIndex> {
+        self.map_index
+            .get_or_insert_with(|| Arc::new(MapIndex::new(map)))
+            .clone()
+    }
+
+    fn sim_data(&mut self, sim: &Sim, map: &Map) -> Arc<SimData> {
+        if self.sim_data.as_ref().map(|data| data.time) != Some(sim.time()) {
+            self.sim_data = Some(Arc::new(SimData::new(sim, map)));
+        }
+        self.sim_data.clone().unwrap()
+    }
+}
+
+/// Where everything on the map is, to quickly find what's in a tile
+struct MapIndex {
+    roads: QuadTree<RoadID>,
+    intersections: QuadTree<IntersectionID>,
+    buildings: QuadTree<BuildingID>,
+    bus_stops: QuadTree<BusStopID>,
+}
+
+impl MapIndex {
+    fn new(map: &Map) -> MapIndex {
+        let bbox = map.get_bounds().as_bbox();
+        let mut index = MapIndex {
+            roads: QuadTree::default(bbox),
+            intersections: QuadTree::default(bbox),
+            buildings: QuadTree::default(bbox),
+            bus_stops: QuadTree::default(bbox),
+        };
+        for r in map.all_roads() {
+            index
+                .roads
+                .insert_with_box(r.id, r.center_pts.get_bounds().as_bbox());
+        }
+        for i in map.all_intersections() {
+            index
+                .intersections
+                .insert_with_box(i.id, i.polygon.get_bounds().as_bbox());
+        }
+        for b in map.all_buildings() {
+            index
+                .buildings
+                .insert_with_box(b.id, b.polygon.get_bounds().as_bbox());
+        }
+        for stop in map.all_bus_stops().values() {
+            let pt = stop.sidewalk_pos.pt(map);
+            index
+                .bus_stops
+                .insert_with_box(stop.id, Bounds::from(&vec![pt]).as_bbox());
+        }
+        index
+    }
+}
+
+/// Everything from the simulation that tiles need, at one time
+struct SimData {
+    time: Time,
+    agents: Vec<UnzoomedAgent>,
+    /// Indices into `agents`
+    agents_quadtree: QuadTree<usize>,
+    /// How long each agent has been stuck in one place
+    delays: BTreeMap<AgentID, Duration>,
+    /// The delay of every vehicle currently moving along each road
+    vehicles_per_road: HashMap<RoadID, Vec<Duration>>,
+}
+
+impl SimData {
+    fn new(sim: &Sim, map: &Map) -> SimData {
+        let delays: BTreeMap<AgentID, Duration> = sim
+            .get_blocked_by_graph(map)
+            .into_iter()
+            .map(|(id, (delay, _))| (id, delay))
+            .collect();
+
+        let agents = sim.get_unzoomed_agents(map);
+        let mut agents_quadtree = QuadTree::default(map.get_bounds().as_bbox());
+        for (idx, a) in agents.iter().enumerate() {
+            agents_quadtree.insert_with_box(idx, Bounds::from(&vec![a.pos]).as_bbox());
+        }
+
+        let mut vehicles_per_road: HashMap<RoadID, Vec<Duration>> = HashMap::new();
+        for car in sim.get_all_draw_cars(map) {
+            if car.status != CarStatus::Moving {
+                continue;
+            }
+            if let Some(l) = car.on.maybe_lane() {
+                vehicles_per_road
+                    .entry(map.get_l(l).parent)
+                    .or_insert_with(Vec::new)
+                    .push(
+                        delays
+                            .get(&AgentID::Car(car.id))
+                            .cloned()
+                            .unwrap_or(Duration::ZERO),
+                    );
+            }
+        }
+
+        SimData {
+            time: sim.time(),
+            agents,
+            agents_quadtree,
+            delays,
+            vehicles_per_road,
+        }
+    }
+}
+
+/// The static map: roads, intersections, buildings, and bus stops.
+fn map_tile(map: &Map, index: &MapIndex, tile: TileID) -> Vec<u8> {
+    let bounds = tile.map_bounds(map);
+    let mut layers = Vec::new();
+
+    let mut roads = Layer::new("roads");
+    for (r, _, _) in index.roads.query(bounds.as_bbox()) {
+        let r = map.get_r(*r);
+        let lanes = r.lanes_ltr();
+        roads.add_feature(
+            Some(r.id.0 as u64),
+            tile.line_string(map, r.center_pts.points()),
+            vec![
+                ("osm_way_id", Value::Int(r.orig_id.osm_way_id.0)),
+                ("name", Value::String(r.get_name(None))),
+                ("rank", Value::String(rank(r).to_string())),
+                ("num_lanes", Value::Int(lanes.len() as i64)),
+                // Left to right, like "Sidewalk,Parking,Driving,Driving,Parking,Sidewalk"
+                (
+                    "lane_types",
+                    Value::String(
+                        lanes
+                            .iter()
+                            .map(|(_, _, lt)| format!("{:?}", lt))
+                            .collect::<Vec<_>>()
+                            .join(","),
+                    ),
+                ),
+                (
+                    "width_meters",
+                    Value::Double(2.0 * r.get_half_width(map).inner_meters()),
+                ),
+            ],
+        );
+    }
+    layers.push(roads);
+
+    let mut intersections = Layer::new("intersections");
+    for (i, _, _) in index.intersections.query(bounds.as_bbox()) {
+        let i = map.get_i(*i);
+        intersections.add_feature(
+            Some(i.id.0 as u64),
+            tile.polygon(map, &i.polygon),
+            vec![
+                ("osm_node_id", Value::Int(i.orig_id.0)),
+                ("type", Value::String(format!("{:?}", i.intersection_type))),
+            ],
+        );
+    }
+    layers.push(intersections);
+
+    if tile.z >= MIN_ZOOM_FOR_BUILDINGS {
+        let mut buildings = Layer::new("buildings");
+        for (b, _, _) in index.buildings.query(bounds.as_bbox()) {
+            let b = map.get_b(*b);
+            let mut props = vec![
+                ("osm_id", Value::String(b.orig_id.to_string())),
+                ("address", Value::String(b.address.clone())),
+                (
+                    "type",
+                    Value::String(
+                        match b.bldg_type {
+                            BuildingType::Residential { .. } => "residential",
+                            BuildingType::ResidentialCommercial(_, _) => "residential_commercial",
+                            BuildingType::Commercial(_) => "commercial",
+                            BuildingType::Empty => "empty",
+                        }
+                        .to_string(),
+                    ),
+                ),
+                ("levels", Value::Double(b.levels)),
+            ];
+            if let Some(ref name) = b.name {
+                props.push(("name", Value::String(name.get(None).clone())));
+            }
+            buildings.add_feature(Some(b.id.0 as u64), tile.polygon(map, &b.polygon), props);
+        }
+        layers.push(buildings);
+    }
+
+    let mut bus_stops = Layer::new("bus_stops");
+    for (stop, _, _) in index.bus_stops.query(bounds.as_bbox()) {
+        let stop = map.get_bs(*stop);
+        let pt = stop.sidewalk_pos.pt(map);
+        if !bounds.contains(pt) {
+            continue;
+        }
+        bus_stops.add_feature(
+            None,
+            tile.point(map, pt),
+            vec![
+                ("name", Value::String(stop.name.clone())),
+                ("train", Value::Bool(stop.is_train_stop)),
+                (
+                    "routes",
+                    Value::String(
+                        map.get_routes_serving_stop(stop.id)
+                            .into_iter()
+                            .map(|r| r.short_name.clone())
+                            .collect::<Vec<_>>()
+                            .join(","),
+                    ),
+                ),
+            ],
+        );
+    }
+    layers.push(bus_stops);
+
+    encode_tile(layers)
+}
+
+/// The live simulation: the position of every agent, and delay and throughput per road.
+fn sim_tile(sim: &Sim, map: &Map, index: &MapIndex, data: &SimData, tile: TileID) -> Vec<u8> {
+    let bounds = tile.map_bounds(map);
+    let mut layers = Vec::new();
+
+    let mut agents = Layer::new("agents");
+    for (idx, _, _) in data.agents_quadtree.query(bounds.as_bbox()) {
+        let a = &data.agents[*idx];
+        if !bounds.contains(a.pos) {
+            continue;
+        }
+        let mut props = vec![
+            ("id", Value::String(a.id.to_string())),
+            (
+                "type",
+                Value::String(match a.id.to_vehicle_type() {
+                    Some(vt) => format!("{:?}", vt),
+                    None => "Pedestrian".to_string(),
+                }),
+            ),
+            (
+                "delay_seconds",
+                Value::Double(
+                    data.delays
+                        .get(&a.id)
+                        .cloned()
+                        .unwrap_or(Duration::ZERO)
+                        .inner_seconds(),
+                ),
+            ),
+        ];
+        if let Some(p) = a.person {
+            props.push(("person", Value::Int(p.0 as i64)));
+        }
+        if let Some(t) = sim.agent_to_trip(a.id) {
+            props.push(("trip", Value::Int(t.0 as i64)));
+        }
+        agents.add_feature(None, tile.point(map, a.pos), props);
+    }
+    layers.push(agents);
+
+    let mut road_delay = Layer::new("road_delay");
+    for (r, _, _) in index.roads.query(bounds.as_bbox()) {
+        let r = map.get_r(*r);
+        let delays: &[Duration] = data
+            .vehicles_per_road
+            .get(&r.id)
+            .map(|x| x.as_slice())
+            .unwrap_or(&[]);
+        let max_delay = delays.iter().max().cloned().unwrap_or(Duration::ZERO);
+        let mean_delay = if delays.is_empty() {
+            Duration::ZERO
+        } else {
+            delays.iter().fold(Duration::ZERO, |sum, x| sum + *x) / (delays.len() as f64)
+        };
+        road_delay.add_feature(
+            Some(r.id.0 as u64),
+            tile.line_string(map, r.center_pts.points()),
+            vec![
+                ("vehicles", Value::Int(delays.len() as i64)),
+                (
+                    "max_delay_seconds",
+                    Value::Double(max_delay.inner_seconds()),
+                ),
+                (
+                    "mean_delay_seconds",
+                    Value::Double(mean_delay.inner_seconds()),
+                ),
+                // Since midnight, for all types of agents
+                (
+                    "throughput",
+                    Value::Int(sim.get_analytics().road_thruput.total_for(r.id) as i64),
+                ),
+            ],
+        );
+    }
+    layers.push(road_delay);
+
+    encode_tile(layers)
+}
+
+fn rank(r: &Road) -> &'static str {
+    match r.get_rank() {
+        RoadRank::Highway => "highway",
+        RoadRank::Arterial => "arterial",
+        RoadRank::Local => "local",
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct TileID {
+    z: u32,
+    x: u32,
+    y: u32,
+}
+
+impl TileID {
+    /// Parses "{z}/{x}/{y}.mvt"
+    fn parse(raw: &str) -> Result<TileID> {
+        let parts: Vec<&str> = raw.trim_end_matches(".mvt").split('/').collect();
+        if parts.len() != 3 {
+            bail!("Tiles are requested as z/x/y.mvt, not {}", raw);
+        }
+        let tile = TileID {
+            z: parts[0].parse()?,
+            x: parts[1].parse()?,
+            y: parts[2].parse()?,
+        };
+        if tile.z > 24 || tile.x >= (1 << tile.z) || tile.y >= (1 << tile.z) {
+            bail!("{:?} doesn't exist", tile);
+        }
+        Ok(tile)
+    }
+
+    /// Transforms a point into this tile's coordinate space, which goes from 0 to EXTENT, with the
+    /// Y axis pointing down.
+    fn project(&self, pt: LonLat) -> (i64, i64) {
+        let n = 2.0_f64.powi(self.z as i32);
+        let x = (pt.x() + 180.0) / 360.0 * n;
+        let lat = pt.y().to_radians();
+        let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / std::f64::consts::PI) / 2.0 * n;
+        (
+            ((x - self.x as f64) * EXTENT as f64).round() as i64,
+            ((y - self.y as f64) * EXTENT as f64).round() as i64,
+        )
+    }
+
+    /// The inverse of `project`
+    fn unproject(&self, x: f64, y: f64) -> LonLat {
+        let n = 2.0_f64.powi(self.z as i32);
+        let x = (self.x as f64 + x / EXTENT as f64) / n;
+        let y = (self.y as f64 + y / EXTENT as f64) / n;
+        let lat = (std::f64::consts::PI * (1.0 - 2.0 * y)).sinh().atan();
+        LonLat::new(x * 360.0 - 180.0, lat.to_degrees())
+    }
+
+    /// The area covered by this tile in map space, plus the buffer.
+    fn map_bounds(&self, map: &Map) -> Bounds {
+        let buffer = BUFFER as f64;
+        let mut bounds = Bounds::new();
+        for (x, y) in vec![
+            (-buffer, -buffer),
+            (EXTENT as f64 + buffer, EXTENT as f64 + buffer),
+        ] {
+            bounds.update(self.unproject(x, y).to_pt(map.get_gps_bounds()));
+        }
+        bounds
+    }
+
+    fn point(&self, map: &Map, pt: Pt2D) -> Geometry {
+        Geometry::Point(self.project(pt.to_gps(map.get_gps_bounds())))
+    }
+
+    /// Long roads can go far past the tile, so lines are clipped to it, plus the buffer.
+    fn line_string(&self, map: &Map, pts: &Vec<Pt2D>) -> Geometry {
+        Geometry::LineString(clip_line_string(&self.project_all(map, pts)))
+    }
+
+    /// Only the outer ring of the polygon is used.
+    fn polygon(&self, map: &Map, polygon: &Polygon) -> Geometry {
+        let mut pts = self.project_all(map, polygon.points());
+        if pts.len() > 1 && pts[0] == pts[pts.len() - 1] {
+            pts.pop();
+        }
+        Geometry::Polygon(pts)
+    }
+
+    /// Also removes points that wind up in the same place
+    fn project_all(&self, map: &Map, pts: &Vec<Pt2D>) -> Vec<(i64, i64)> {
+        let mut result = Vec::new();
+        for pt in map.get_gps_bounds().convert_back(pts) {
+            let pt = self.project(pt);
+            if result.last() != Some(&pt) {
+                result.push(pt);
+            }
+        }
+        result
+    }
+}
+
+/// Splits a line into the pieces inside the tile, plus the buffer.
+fn clip_line_string(pts: &[(i64, i64)]) -> Vec<Vec<(i64, i64)>> {
+    let mut parts = Vec::new();
+    let mut current: Vec<(i64, i64)> = Vec::new();
+    for pair in pts.windows(2) {
+        if let Some((pt1, pt2)) = clip_segment(pair[0], pair[1]) {
+            if current.last() != Some(&pt1) {
+                parts.push(std::mem::replace(&mut current, vec![pt1]));
+            }
+            current.push(pt2);
+        }
+    }
+    parts.push(current);
+    parts.retain(|part| part.len() > 1);
+    parts
+}
+
+/// Clips one segment to the tile, plus the buffer, using the Liang-Barsky algorithm. Returns None
+/// if nothing is left.
+fn clip_segment(pt1: (i64, i64), pt2: (i64, i64)) -> Option<((i64, i64), (i64, i64))> {
+    let (min, max) = (-BUFFER as f64, (EXTENT as i64 + BUFFER) as f64);
+    let (x, y) = (pt1.0 as f64, pt1.1 as f64);
+    let (dx, dy) = ((pt2.0 - pt1.0) as f64, (pt2.1 - pt1.1) as f64);
+    // The fractions along the segment where it enters and leaves
+    let mut enter: f64 = 0.0;
+    let mut leave: f64 = 1.0;
+    for (p, q) in vec![(-dx, x - min), (dx, max - x), (-dy, y - min), (dy, max - y)] {
+        if p == 0.0 {
+            // Parallel to this edge, so either entirely inside or outside of it
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 {
+            enter = enter.max(q / p);
+        } else {
+            leave = leave.min(q / p);
+        }
+    }
+    if enter > leave {
+        return None;
+    }
+    let at = |t: f64| ((x + t * dx).round() as i64, (y + t * dy).round() as i64);
+    let (pt1, pt2) = (at(enter), at(leave));
+    if pt1 == pt2 {
+        return None;
+    }
+    Some((pt1, pt2))
+}
+
+/// In tile coordinates
+enum Geometry {
+    Point((i64, i64)),
+    /// Possibly split into several pieces
+    LineString(Vec<Vec<(i64, i64)>>),
+    Polygon(Vec<(i64, i64)>),
+}
+
+impl Geometry {
+    /// Returns the feature type and the encoded commands, or None if the geometry collapsed to
+    /// nothing at this zoom level.
+    fn encode(self) -> Option<(u64, Vec<u32>)> {
+        let mut cmds = Vec::new();
+        let mut cursor = (0, 0);
+        let mut move_cursor = |cmds: &mut Vec<u32>, pt: (i64, i64)| {
+            cmds.push(zigzag(pt.0 - cursor.0) as u32);
+            cmds.push(zigzag(pt.1 - cursor.1) as u32);
+            cursor = pt;
+        };
+
+        match self {
+            Geometry::Point(pt) => {
+                cmds.push(command(MOVE_TO, 1));
+                move_cursor(&mut cmds, pt);
+                Some((POINT, cmds))
+            }
+            Geometry::LineString(parts) => {
+                for pts in parts {
+                    if pts.len() < 2 {
+                        continue;
+                    }
+                    cmds.push(command(MOVE_TO, 1));
+                    move_cursor(&mut cmds, pts[0]);
+                    cmds.push(command(LINE_TO, pts.len() - 1));
+                    for pt in pts.into_iter().skip(1) {
+                        move_cursor(&mut cmds, pt);
+                    }
+                }
+                if cmds.is_empty() {
+                    return None;
+                }
+                Some((LINE_STRING, cmds))
+            }
+            Geometry::Polygon(mut pts) => {
+                if pts.len() < 3 {
+                    return None;
+                }
+                // Exterior rings must wind clockwise, with the Y axis pointing down
+                let mut area = 0;
+                for (idx, pt1) in pts.iter().enumerate() {
+                    let pt2 = pts[(idx + 1) % pts.len()];
+                    area += pt1.0 * pt2.1 - pt2.0 * pt1.1;
+                }
+                if area == 0 {
+                    return None;
+                }
+                if area < 0 {
+                    pts.reverse();
+                }
+                cmds.push(command(MOVE_TO, 1));
+                move_cursor(&mut cmds, pts[0]);
+                cmds.push(command(LINE_TO, pts.len() - 1));
+                for pt in pts.into_iter().skip(1) {
+                    move_cursor(&mut cmds, pt);
+                }
+                cmds.push(command(CLOSE_PATH, 1));
+                Some((POLYGON, cmds))
+            }
+        }
+    }
+}
+
+const MOVE_TO: u32 = 1;
+const LINE_TO: u32 = 2;
+const CLOSE_PATH: u32 = 7;
+
+const POINT: u64 = 1;
+const LINE_STRING: u64 = 2;
+const POLYGON: u64 = 3;
+
+fn command(id: u32, count: usize) -> u32 {
+    (id & 0x7) | ((count as u32) << 3)
+}
+
+fn zigzag(x: i64) -> u64 {
+    ((x << 1) ^ (x >> 63)) as u64
+}
+
+enum Value {
+    String(String),
+    Double(f64),
+    Int(i64),
+    Bool(bool),
+}
+
+impl Value {
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        match self {
+            Value::String(x) => write_bytes(&mut buf, 1, x.as_bytes()),
+            Value::Double(x) => {
+                write_key(&mut buf, 3, WIRE_FIXED64);
+                buf.extend_from_slice(&x.to_le_bytes());
+            }
+            Value::Int(x) => write_uint(&mut buf, 6, zigzag(*x)),
+            Value::Bool(x) => write_uint(&mut buf, 7, *x as u64),
+        }
+        buf
+    }
+}
+
+/// One named layer of a tile. The format stores each distinct property key and value once per
+/// layer, and features refer to them by index.
+struct Layer {
+    name: &'static str,
+    features: Vec<Vec<u8>>,
+    keys: Vec<&'static str>,
+    values: Vec<Vec<u8>>,
+    key_indices: HashMap<&'static str, u32>,
+    value_indices: HashMap<Vec<u8>, u32>,
+}
+
+impl Layer {
+    fn new(name: &'static str) -> Layer {
+        Layer {
+            name,
+            features: Vec::new(),
+            keys: Vec::new(),
+            values: Vec::new(),
+            key_indices: HashMap::new(),
+            value_indices: HashMap::new(),
+        }
+    }
+
+    fn add_feature(
+        &mut self,
+        id: Option<u64>,
+        geometry: Geometry,
+        props: Vec<(&'static str, Value)>,
+    ) {
+        let (geom_type, cmds) = match geometry.encode() {
+            Some(x) => x,
+            None => {
+                return;
+            }
+        };
+
+        let mut tags = Vec::new();
+        for (key, value) in props {
+            let next_key = self.keys.len() as u32;
+            let keys = &mut self.keys;
+            tags.push(*self.key_indices.entry(key).or_insert_with(|| {
+                keys.push(key);
+                next_key
+            }));
+
+            let next_value = self.values.len() as u32;
+            let values = &mut self.values;
+            let value = value.encode();
+            tags.push(*self.value_indices.entry(value.clone()).or_insert_with(|| {
+                values.push(value);
+                next_value
+            }));
+        }
+
+        let mut feature = Vec::new();
+        if let Some(id) = id {
+            write_uint(&mut feature, 1, id);
+        }
+        write_packed(&mut feature, 2, &tags);
+        write_uint(&mut feature, 3, geom_type);
+        write_packed(&mut feature, 4, &cmds);
+        self.features.push(feature);
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        write_uint(&mut buf, 15, 2);
+        write_bytes(&mut buf, 1, self.name.as_bytes());
+        for feature in &self.features {
+            write_bytes(&mut buf, 2, feature);
+        }
+        for key in &self.keys {
+            write_bytes(&mut buf, 3, key.as_bytes());
+        }
+        for value in &self.values {
+            write_bytes(&mut buf, 4, value);
+        }
+        write_uint(&mut buf, 5, EXTENT as u64);
+        buf
+    }
+}
+
+fn encode_tile(layers: Vec<Layer>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for layer in layers {
+        if !layer.features.is_empty() {
+            write_bytes(&mut buf, 3, &layer.encode());
+        }
+    }
+    buf
+}
+
+// Just enough of the protobuf wire format to write tiles
+
+const WIRE_VARINT: u64 = 0;
+const WIRE_FIXED64: u64 = 1;
+const WIRE_LENGTH_DELIMITED: u64 = 2;
+
+fn write_varint(buf: &mut Vec<u8>, mut x: u64) {
+    while x >= 0x80 {
+        buf.push((x as u8) | 0x80);
+        x >>= 7;
+    }
+    buf.push(x as u8);
+}
+
+fn write_key(buf: &mut Vec<u8>, field: u64, wire_type: u64) {
+    write_varint(buf, (field << 3) | wire_type);
+}
+
+fn write_uint(buf: &mut Vec<u8>, field: u64, x: u64) {
+    write_key(buf, field, WIRE_VARINT);
+    write_varint(buf, x);
+}
+
+fn write_bytes(buf: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    write_key(buf, field, WIRE_LENGTH_DELIMITED);
+    write_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+fn write_packed(buf: &mut Vec<u8>, field: u64, values: &[u32]) {
+    let mut packed = Vec::new();
+    for x in values {
+        write_varint(&mut packed, *x as u64);
+    }
+    write_bytes(buf, field, &packed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project() {
+        let tile = TileID::parse("14/2625/5719.mvt").unwrap();
+        let (x, y) = tile.project(LonLat::new(-122.3, 47.64));
+        assert!(x >= 0 && x < EXTENT as i64 && y >= 0 && y < EXTENT as i64);
+        let pt = tile.unproject(x as f64, y as f64);
+        assert!((pt.x() - -122.3).abs() < 1e-5);
+        assert!((pt.y() - 47.64).abs() < 1e-5);
+
+        assert!(TileID::parse("2/4/0.mvt").is_err());
+        assert!(TileID::parse("14/2622").is_err());
+    }
+
+    #[test]
+    fn test_encode_geometry() {
+        // The example from the spec
+        assert_eq!(
+            Geometry::LineString(vec![vec![(2, 2), (2, 10), (10, 10)]])
+                .encode()
+                .unwrap(),
+            (LINE_STRING, vec![9, 4, 4, 18, 0, 16, 16, 0])
+        );
+        // Also from the spec, a line in two pieces
+        assert_eq!(
+            Geometry::LineString(vec![vec![(2, 2), (2, 10), (10, 10)], vec![(1, 1), (3, 5)]])
+                .encode()
+                .unwrap(),
+            (
+                LINE_STRING,
+                vec![9, 4, 4, 18, 0, 16, 16, 0, 9, 17, 17, 10, 4, 8]
+            )
+        );
+        // Counter-clockwise rings get flipped
+        let (_, cmds) = Geometry::Polygon(vec![(3, 6), (3, 12), (8, 12)])
+            .encode()
+            .unwrap();
+        assert_eq!(cmds, vec![9, 16, 24, 18, 9, 0, 0, 11, 15]);
+        assert!(Geometry::Polygon(vec![(0, 0), (1, 1), (2, 2)])
+            .encode()
+            .is_none());
+    }
+
+    #[test]
+    fn test_clip_line_string() {
+        let max = EXTENT as i64 + BUFFER;
+        // Inside
+        assert_eq!(
+            clip_line_string(&[(0, 0), (100, 100), (200, 50)]),
+            vec![vec![(0, 0), (100, 100), (200, 50)]]
+        );
+        // Leaving the tile and coming back splits the line
+        assert_eq!(
+            clip_line_string(&[(0, 0), (5000, 0), (5000, 100), (0, 100)]),
+            vec![vec![(0, 0), (max, 0)], vec![(max, 100), (0, 100)]]
+        );
+        // Passing through the tile
+        assert_eq!(
+            clip_line_string(&[(-1000, 10), (9000, 10)]),
+            vec![vec![(-BUFFER, 10), (max, 10)]]
+        );
+        // Entirely outside
+        assert!(clip_line_string(&[(-1000, -1000), (-1000, 9000), (9000, 9000)]).is_empty());
+    }
+}
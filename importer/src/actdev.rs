@@ -7,20 +7,16 @@ use rand_xorshift::XorShiftRng;
 
 use geom::{Duration, LonLat, Time};
 use map_model::Map;
-use sim::{ExternalPerson, ExternalTrip, ExternalTripEndpoint, Scenario, TripMode};
+use sim::{ExternalPerson, ExternalTrip, ExternalTripEndpoint, Scenario, TripMode, TripPurpose};
 
 use crate::configuration::ImporterConfiguration;
 use crate::utils::download;
 
-pub fn import_scenarios(map: &Map, config: &ImporterConfiguration) -> Result<()> {
-    // TODO This hardcodes for one city right now; generalize.
-    download(
-        config,
-        "input/cambridge/desire_lines_disag.geojson",
-        "https://raw.githubusercontent.com/cyipt/actdev/main/data-small/great-kneighton/desire_lines_disag.geojson",
-    );
+pub fn import_scenarios(map: &Map, url: &str, config: &ImporterConfiguration) -> Result<()> {
+    let path = format!("input/{}/desire_lines_disag.geojson", map.get_city_name());
+    download(config, &path, url);
 
-    let bytes = abstio::slurp_file(abstio::path("input/cambridge/desire_lines_disag.geojson"))?;
+    let bytes = abstio::slurp_file(abstio::path(path))?;
     let raw_string = std::str::from_utf8(&bytes)?;
     let geojson = raw_string.parse::<GeoJson>()?;
     let mut baseline = Vec::new();
@@ -101,11 +97,13 @@ fn generate_scenario(name: &str, input: Vec<ODSummary>, map: &Map) {
                         departure: leave_time,
                         destination: ExternalTripEndpoint::Position(od.work),
                         mode: od.mode,
+                        purpose: Some(TripPurpose::Work),
                     },
                     ExternalTrip {
                         departure: return_time,
                         destination: ExternalTripEndpoint::Position(od.home),
                         mode: od.mode,
+                        purpose: Some(TripPurpose::Home),
                     },
                 ],
             });
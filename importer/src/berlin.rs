@@ -7,41 +7,10 @@ use serde::Deserialize;
 use abstutil::Timer;
 use geom::Ring;
 use kml::ExtraShapes;
-use map_model::raw::RawMap;
 use map_model::BuildingType;
 
-use crate::configuration::ImporterConfiguration;
-use crate::utils::{download, download_kml};
-
-pub fn import_extra_data(map: &RawMap, config: &ImporterConfiguration, timer: &mut Timer) {
-    // From https://data.technologiestiftung-berlin.de/dataset/lor_planungsgraeume/en
-    download_kml(
-        "input/berlin/planning_areas.bin",
-        "https://tsb-opendata.s3.eu-central-1.amazonaws.com/lor_planungsgraeume/lor_planungsraeume.kml",
-        &map.gps_bounds,
-        // Keep partly out-of-bounds polygons
-        false,
-        timer
-    );
-
-    // From
-    // https://daten.berlin.de/datensaetze/einwohnerinnen-und-einwohner-berlin-lor-planungsr%C3%A4umen-am-31122018
-    download(
-        config,
-        "input/berlin/EWR201812E_Matrix.csv",
-        "https://www.statistik-berlin-brandenburg.de/opendata/EWR201812E_Matrix.csv",
-    );
-
-    // Always do this, it's idempotent and fast
-    correlate_population(
-        "data/input/berlin/planning_areas.bin",
-        "data/input/berlin/EWR201812E_Matrix.csv",
-        timer,
-    );
-}
-
 // Modify the filtered KML of planning areas with the number of residents from a different dataset.
-fn correlate_population(kml_path: &str, csv_path: &str, timer: &mut Timer) {
+pub fn correlate_population(kml_path: &str, csv_path: &str, timer: &mut Timer) {
     let mut shapes = abstio::read_binary::<ExtraShapes>(kml_path.to_string(), timer);
     for rec in csv::ReaderBuilder::new()
         .delimiter(b';')
@@ -71,13 +40,8 @@ struct Record {
     e_e: String,
 }
 
-pub fn distribute_residents(map: &mut map_model::Map, timer: &mut Timer) {
-    for shape in abstio::read_binary::<ExtraShapes>(
-        "data/input/berlin/planning_areas.bin".to_string(),
-        timer,
-    )
-    .shapes
-    {
+pub fn distribute_residents(map: &mut map_model::Map, planning_areas: &str, timer: &mut Timer) {
+    for shape in abstio::read_binary::<ExtraShapes>(planning_areas.to_string(), timer).shapes {
         let pts = map.get_gps_bounds().convert(&shape.points);
         if pts
             .iter()
@@ -0,0 +1,519 @@
+//! Imports a [MATSim](https://www.matsim.org) population into a scenario. Each person's selected
+//! plan (or their first, if none is selected) becomes their daily schedule: every leg between two
+//! activities is one trip, departing when the previous activity ends.
+//!
+//! Usage: `import_matsim_plans --map=data/system/seattle/maps/montlake.bin
+//! --input=plans.xml --scenario_name=matsim`
+//!
+//! Coordinates are assumed to be longitude and latitude. Many published populations use a UTM
+//! projection instead; pass `--utm_zone=32N` in that case. Other projections aren't supported;
+//! reproject the plans first. Stages like "car interaction" are merged into the surrounding trip,
+//! which uses its most significant mode. People who never come near the map are skipped, and so
+//! are people riding as a passenger, since plans don't say whose car they're in.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use log::{info, warn};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use abstutil::{prettyprint_usize, CmdArgs, Timer};
+use geom::{Duration, LonLat, Time};
+use map_model::Map;
+use sim::{ExternalPerson, ExternalTrip, ExternalTripEndpoint, Scenario, TripMode, TripPurpose};
+
+fn main() -> Result<()> {
+    let mut args = CmdArgs::new();
+    let map = args.required("--map");
+    let input = args.required("--input");
+    let scenario_name = args.required("--scenario_name");
+    let utm_zone = args.optional_parse("--utm_zone", |s| UtmZone::parse(&s));
+    args.done();
+
+    let mut timer = Timer::new("import MATSim plans");
+    let map = Map::new(map, &mut timer);
+
+    timer.start(format!("read {}", input));
+    let plans = read_plans(&input)?;
+    timer.stop(format!("read {}", input));
+
+    let boundary = map.get_boundary_polygon();
+    let gps_bounds = map.get_gps_bounds();
+    let mut people = Vec::new();
+    let mut skipped = 0;
+    // How many plans couldn't be imported, and the first problem
+    let mut bad_plans = 0;
+    let mut first_error = None;
+    for (id, activities) in plans {
+        let to_gps = |x: f64, y: f64| match utm_zone {
+            Some(ref zone) => zone.to_gps(x, y),
+            None => LonLat::new(x, y),
+        };
+        match to_person(&id, activities, to_gps) {
+            Ok(person) => {
+                let mut pts = std::iter::once(&person.origin)
+                    .chain(person.trips.iter().map(|t| &t.destination))
+                    .filter_map(|endpt| match endpt {
+                        ExternalTripEndpoint::Position(gps) => Some(gps.to_pt(gps_bounds)),
+                        ExternalTripEndpoint::TripEndpoint(_) => None,
+                    });
+                if pts.any(|pt| boundary.contains_pt(pt)) {
+                    people.push(person);
+                } else {
+                    skipped += 1;
+                }
+            }
+            Err(err) => {
+                if first_error.is_none() {
+                    first_error = Some(format!("{}: {}", id, err));
+                }
+                bad_plans += 1;
+            }
+        }
+    }
+    if let Some(err) = first_error {
+        warn!(
+            "Skipped {} people with plans that couldn't be imported. The first was {}",
+            prettyprint_usize(bad_plans),
+            err
+        );
+    }
+
+    let mut s = Scenario::empty(&map, &scenario_name);
+    // Include all buses/trains
+    s.only_seed_buses = None;
+    let (specs, failed) = ExternalPerson::import_skipping_failures(&map, people);
+    s.people = specs;
+    s = s.remove_weird_schedules();
+    info!(
+        "Imported {} people with {} trips. Skipped {} people entirely off the map, and {} without \
+         a building near one of their activities.",
+        prettyprint_usize(s.people.len()),
+        prettyprint_usize(s.people.iter().map(|p| p.trips.len()).sum()),
+        prettyprint_usize(skipped),
+        prettyprint_usize(failed)
+    );
+    s.save();
+    Ok(())
+}
+
+enum PlanElement {
+    Activity {
+        activity_type: String,
+        x: f64,
+        y: f64,
+        end_time: Option<Time>,
+        max_dur: Option<Duration>,
+    },
+    Leg {
+        mode: String,
+        dep_time: Option<Time>,
+    },
+}
+
+/// Returns the chosen plan of every person, in the order they appear.
+fn read_plans(path: &str) -> Result<Vec<(String, Vec<PlanElement>)>> {
+    let bytes = abstio::slurp_file(path)?;
+    let raw_string = std::str::from_utf8(&bytes)?;
+    let mut reader = Reader::from_str(raw_string);
+    reader.trim_text(true);
+
+    let mut results = Vec::new();
+    let mut person: Option<String> = None;
+    // Every plan of the current person, and whether it's selected
+    let mut plans: Vec<(bool, Vec<PlanElement>)> = Vec::new();
+
+    let mut buf = Vec::new();
+    loop {
+        let mut person_done = false;
+        let parsed = match reader.read_event(&mut buf)? {
+            Event::Start(e) | Event::Empty(e) => Some(parse_element(&e, &reader)?),
+            Event::End(e) => {
+                person_done = e.name() == b"person";
+                None
+            }
+            Event::Eof => break,
+            _ => None,
+        };
+        buf.clear();
+        if person_done {
+            if let Some(id) = person.take() {
+                let mut all = std::mem::take(&mut plans);
+                let idx = all.iter().position(|(selected, _)| *selected).unwrap_or(0);
+                if idx < all.len() {
+                    results.push((id, all.remove(idx).1));
+                }
+            }
+        }
+        let (name, attribs) = match parsed {
+            Some(x) => x,
+            None => {
+                continue;
+            }
+        };
+
+        match name.as_ref() {
+            "person" => {
+                person = attribs.get("id").cloned();
+                plans.clear();
+            }
+            "plan" => {
+                plans.push((
+                    attribs.get("selected").map(|x| x == "yes") == Some(true),
+                    Vec::new(),
+                ));
+            }
+            "activity" | "act" => {
+                if let Some(plan) = plans.last_mut() {
+                    plan.1.push(PlanElement::Activity {
+                        activity_type: attribs.get("type").cloned().unwrap_or_else(String::new),
+                        x: parse_coord(&attribs, "x")?,
+                        y: parse_coord(&attribs, "y")?,
+                        end_time: parse_time(&attribs, "end_time")?,
+                        max_dur: parse_time(&attribs, "max_dur")?.map(|t| t - Time::START_OF_DAY),
+                    });
+                }
+            }
+            "leg" => {
+                if let Some(plan) = plans.last_mut() {
+                    plan.1.push(PlanElement::Leg {
+                        mode: attribs.get("mode").cloned().unwrap_or_else(String::new),
+                        dep_time: parse_time(&attribs, "dep_time")?,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(results)
+}
+
+/// Turns a plan into a person. Between two real activities, there may be several legs and
+/// "interaction" activities, like walking to a parked car; they form a single trip.
+fn to_person<F: Fn(f64, f64) -> LonLat>(
+    id: &str,
+    plan: Vec<PlanElement>,
+    to_gps: F,
+) -> Result<ExternalPerson> {
+    let mut origin = None;
+    let mut trips = Vec::new();
+    // When the previous activity ends, or the last known time
+    let mut last_time = Time::START_OF_DAY;
+    let mut departure: Option<Time> = None;
+    let mut modes: Vec<TripMode> = Vec::new();
+
+    for element in plan {
+        match element {
+            PlanElement::Activity {
+                activity_type,
+                x,
+                y,
+                end_time,
+                max_dur,
+            } => {
+                if activity_type.ends_with(" interaction") {
+                    continue;
+                }
+                let pos = ExternalTripEndpoint::Position(to_gps(x, y));
+                if origin.is_none() {
+                    origin = Some(pos);
+                } else {
+                    let mode = modes
+                        .drain(..)
+                        .max_by_key(|m| mode_priority(*m))
+                        .ok_or_else(|| anyhow::anyhow!("two activities without a leg between"))?;
+                    trips.push(ExternalTrip {
+                        departure: departure.take().unwrap_or(last_time),
+                        destination: pos,
+                        mode,
+                        purpose: Some(purpose(&activity_type)),
+                    });
+                }
+                if let Some(t) = end_time {
+                    last_time = t;
+                } else if let Some(d) = max_dur {
+                    last_time = last_time + d;
+                }
+            }
+            PlanElement::Leg { mode, dep_time } => {
+                if mode == "ride" {
+                    anyhow::bail!("riding as a passenger isn't supported");
+                }
+                if departure.is_none() {
+                    departure = dep_time;
+                }
+                modes.push(
+                    parse_mode(&mode).ok_or_else(|| anyhow::anyhow!("unknown mode {}", mode))?,
+                );
+            }
+        }
+    }
+
+    if trips.is_empty() {
+        anyhow::bail!("{} has no trips", id);
+    }
+    Ok(ExternalPerson {
+        origin: origin.unwrap(),
+        trips,
+    })
+}
+
+fn parse_mode(mode: &str) -> Option<TripMode> {
+    match mode {
+        "car" => Some(TripMode::Drive),
+        "bike" | "bicycle" => Some(TripMode::Bike),
+        "walk" | "transit_walk" | "non_network_walk" | "access_walk" | "egress_walk" => {
+            Some(TripMode::Walk)
+        }
+        "pt" | "bus" | "train" | "tram" | "subway" | "rail" => Some(TripMode::Transit),
+        _ => None,
+    }
+}
+
+/// Walking to a bus stop shouldn't turn a transit trip into a walking trip.
+fn mode_priority(mode: TripMode) -> usize {
+    match mode {
+        TripMode::Walk => 0,
+        TripMode::Bike => 1,
+        TripMode::Drive => 2,
+        TripMode::Transit => 3,
+    }
+}
+
+/// MATSim activity types aren't standardized, but most populations use these names or their
+/// first letter.
+fn purpose(activity_type: &str) -> TripPurpose {
+    let lower = activity_type.to_lowercase();
+    let base = lower.split('_').next().unwrap();
+    match base {
+        "home" | "h" => TripPurpose::Home,
+        "work" | "w" => TripPurpose::Work,
+        "education" | "school" | "university" | "e" => TripPurpose::School,
+        "leisure" | "l" => TripPurpose::Recreation,
+        _ => TripPurpose::Shopping,
+    }
+}
+
+fn parse_element(
+    e: &BytesStart,
+    reader: &Reader<&[u8]>,
+) -> Result<(String, BTreeMap<String, String>)> {
+    let name = std::str::from_utf8(e.name())?.to_string();
+    let mut attribs = BTreeMap::new();
+    for attr in e.attributes() {
+        let attr = attr?;
+        attribs.insert(
+            std::str::from_utf8(attr.key)?.to_string(),
+            attr.unescape_and_decode_value(reader)?,
+        );
+    }
+    Ok((name, attribs))
+}
+
+fn parse_coord(attribs: &BTreeMap<String, String>, key: &str) -> Result<f64> {
+    let value = attribs
+        .get(key)
+        .ok_or_else(|| anyhow::anyhow!("activity missing {}", key))?;
+    Ok(value.parse::<f64>()?)
+}
+
+/// MATSim writes "undefined" for times that aren't set.
+fn parse_time(attribs: &BTreeMap<String, String>, key: &str) -> Result<Option<Time>> {
+    match attribs.get(key) {
+        Some(x) if x != "undefined" => Ok(Some(Time::parse(x)?)),
+        _ => Ok(None),
+    }
+}
+
+struct UtmZone {
+    number: usize,
+    north: bool,
+}
+
+impl UtmZone {
+    fn parse(x: &str) -> Result<UtmZone> {
+        let (number, hemisphere) = x.split_at(x.len().saturating_sub(1));
+        let north = match hemisphere {
+            "N" | "n" => true,
+            "S" | "s" => false,
+            _ => anyhow::bail!("UTM zone {} should end with N or S", x),
+        };
+        let number = number.parse::<usize>()?;
+        if !(1..=60).contains(&number) {
+            anyhow::bail!("UTM zone {} must be between 1 and 60", x);
+        }
+        Ok(UtmZone { number, north })
+    }
+
+    /// Converts WGS84 UTM coordinates to longitude and latitude.
+    fn to_gps(&self, easting: f64, northing: f64) -> LonLat {
+        let k0 = 0.9996;
+        let a = 6_378_137.0;
+        let e2: f64 = 0.006_694_38;
+        let ep2 = e2 / (1.0 - e2);
+        let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+
+        let x = easting - 500_000.0;
+        let y = if self.north {
+            northing
+        } else {
+            northing - 10_000_000.0
+        };
+        let m = y / k0;
+        let mu = m / (a * (1.0 - e2 / 4.0 - 3.0 * e2.powi(2) / 64.0 - 5.0 * e2.powi(3) / 256.0));
+        let phi1 = mu
+            + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+            + (21.0 * e1.powi(2) / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+            + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+            + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+
+        let sin1 = phi1.sin();
+        let cos1 = phi1.cos();
+        let n1 = a / (1.0 - e2 * sin1.powi(2)).sqrt();
+        let t1 = phi1.tan().powi(2);
+        let c1 = ep2 * cos1.powi(2);
+        let r1 = a * (1.0 - e2) / (1.0 - e2 * sin1.powi(2)).powf(1.5);
+        let d = x / (n1 * k0);
+
+        let lat = phi1
+            - (n1 * phi1.tan() / r1)
+                * (d.powi(2) / 2.0
+                    - (5.0 + 3.0 * t1 + 10.0 * c1 - 4.0 * c1.powi(2) - 9.0 * ep2) * d.powi(4)
+                        / 24.0
+                    + (61.0 + 90.0 * t1 + 298.0 * c1 + 45.0 * t1.powi(2)
+                        - 252.0 * ep2
+                        - 3.0 * c1.powi(2))
+                        * d.powi(6)
+                        / 720.0);
+        let lon = (d - (1.0 + 2.0 * t1 + c1) * d.powi(3) / 6.0
+            + (5.0 - 2.0 * c1 + 28.0 * t1 - 3.0 * c1.powi(2) + 8.0 * ep2 + 24.0 * t1.powi(2))
+                * d.powi(5)
+                / 120.0)
+            / cos1;
+        let lon0 = (self.number as f64 - 1.0) * 6.0 - 180.0 + 3.0;
+        LonLat::new(lon0 + lon.to_degrees(), lat.to_degrees())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The forward projection, from Snyder's "Map Projections: A Working Manual"
+    fn to_utm(zone: &UtmZone, gps: LonLat) -> (f64, f64) {
+        let k0 = 0.9996;
+        let a = 6_378_137.0;
+        let e2: f64 = 0.006_694_38;
+        let ep2 = e2 / (1.0 - e2);
+
+        let lon0 = (zone.number as f64 - 1.0) * 6.0 - 180.0 + 3.0;
+        let phi = gps.y().to_radians();
+        let n = a / (1.0 - e2 * phi.sin().powi(2)).sqrt();
+        let t = phi.tan().powi(2);
+        let c = ep2 * phi.cos().powi(2);
+        let big_a = phi.cos() * (gps.x() - lon0).to_radians();
+        let m = a
+            * ((1.0 - e2 / 4.0 - 3.0 * e2.powi(2) / 64.0 - 5.0 * e2.powi(3) / 256.0) * phi
+                - (3.0 * e2 / 8.0 + 3.0 * e2.powi(2) / 32.0 + 45.0 * e2.powi(3) / 1024.0)
+                    * (2.0 * phi).sin()
+                + (15.0 * e2.powi(2) / 256.0 + 45.0 * e2.powi(3) / 1024.0) * (4.0 * phi).sin()
+                - (35.0 * e2.powi(3) / 3072.0) * (6.0 * phi).sin());
+
+        let easting = k0
+            * n
+            * (big_a
+                + (1.0 - t + c) * big_a.powi(3) / 6.0
+                + (5.0 - 18.0 * t + t.powi(2) + 72.0 * c - 58.0 * ep2) * big_a.powi(5) / 120.0)
+            + 500_000.0;
+        let mut northing = k0
+            * (m + n
+                * phi.tan()
+                * (big_a.powi(2) / 2.0
+                    + (5.0 - t + 9.0 * c + 4.0 * c.powi(2)) * big_a.powi(4) / 24.0
+                    + (61.0 - 58.0 * t + t.powi(2) + 600.0 * c - 330.0 * ep2) * big_a.powi(6)
+                        / 720.0));
+        if !zone.north {
+            northing += 10_000_000.0;
+        }
+        (easting, northing)
+    }
+
+    #[test]
+    fn test_utm_round_trip() {
+        for (zone, lon, lat) in &[
+            ("10N", -122.3, 47.65),
+            ("10N", -124.9, 49.1),
+            ("32N", 8.54, 47.37),
+            ("31N", 3.0, 0.0),
+            ("56S", 151.2, -33.87),
+            ("23S", -46.63, -23.55),
+        ] {
+            let zone = UtmZone::parse(zone).unwrap();
+            let (easting, northing) = to_utm(&zone, LonLat::new(*lon, *lat));
+            let gps = zone.to_gps(easting, northing);
+            assert!(
+                (gps.x() - lon).abs() < 1e-6 && (gps.y() - lat).abs() < 1e-6,
+                "{}, {} became {}",
+                lon,
+                lat,
+                gps
+            );
+        }
+
+        // The central meridian on the equator
+        let gps = UtmZone::parse("31N").unwrap().to_gps(500_000.0, 0.0);
+        assert!((gps.x() - 3.0).abs() < 1e-9 && gps.y().abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_utm_zone() {
+        assert!(UtmZone::parse("32n").unwrap().north);
+        assert!(!UtmZone::parse("56S").unwrap().north);
+        for bad in &["", "N", "32", "0N", "61S", "32X"] {
+            assert!(UtmZone::parse(bad).is_err(), "{} accepted", bad);
+        }
+    }
+
+    #[test]
+    fn test_passengers_skipped() {
+        let activity = |activity_type: &str, x: f64| PlanElement::Activity {
+            activity_type: activity_type.to_string(),
+            x,
+            y: 47.6,
+            end_time: Some(Time::START_OF_DAY + Duration::hours(8)),
+            max_dur: None,
+        };
+        let leg = |mode: &str| PlanElement::Leg {
+            mode: mode.to_string(),
+            dep_time: None,
+        };
+        let to_gps = |x, y| LonLat::new(x, y);
+
+        let person = to_person(
+            "driver",
+            vec![
+                activity("home", -122.3),
+                leg("walk"),
+                activity("car interaction", -122.3),
+                leg("car"),
+                activity("work", -122.2),
+            ],
+            to_gps,
+        )
+        .unwrap();
+        assert_eq!(person.trips.len(), 1);
+        assert_eq!(person.trips[0].mode, TripMode::Drive);
+
+        assert!(to_person(
+            "passenger",
+            vec![
+                activity("home", -122.3),
+                leg("ride"),
+                activity("work", -122.2)
+            ],
+            to_gps,
+        )
+        .is_err());
+    }
+}
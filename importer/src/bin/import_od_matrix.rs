@@ -0,0 +1,359 @@
+//! Imports a zone-based origin-destination matrix, like most transportation agencies publish, into
+//! a scenario. Each trip between two zones is assigned to a random building in each zone and a
+//! departure time drawn from a profile.
+//!
+//! Inputs:
+//! - `--zones`: GeoJSON with a polygon or multipolygon per zone. The `--zone_id` property
+//!   (default "id") names each zone.
+//! - `--od`: CSV with columns `origin,destination,mode,count`, where `mode` is one of `Walk`,
+//!   `Bike`, `Transit`, or `Drive`. `count` may be fractional, but not negative; the remainder is
+//!   rounded randomly.
+//! - `--departure_profile`: CSV with columns `hour,weight`, for hours 0 through 23. The weights
+//!   don't have to sum to anything in particular.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+
+use anyhow::Result;
+use geojson::{GeoJson, Value};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use serde::Deserialize;
+
+use abstutil::{prettyprint_usize, CmdArgs, Timer};
+use geom::{Duration, LonLat, Ring, Time};
+use map_model::{BuildingID, BuildingType, Map};
+use sim::{ExternalPerson, ExternalTrip, ExternalTripEndpoint, Scenario, TripEndpoint, TripMode};
+
+fn main() -> Result<()> {
+    let mut args = CmdArgs::new();
+    let map = args.required("--map");
+    let zones_path = args.required("--zones");
+    let zone_id = args
+        .optional("--zone_id")
+        .unwrap_or_else(|| "id".to_string());
+    let od_path = args.required("--od");
+    let profile_path = args.required("--departure_profile");
+    let scenario_name = args.required("--scenario_name");
+    let rng_seed = args
+        .optional_parse("--rng_seed", |s| s.parse::<u64>())
+        .unwrap_or(42);
+    args.done();
+
+    let mut timer = Timer::new("import origin-destination matrix");
+    let map = Map::new(map, &mut timer);
+    let mut rng = XorShiftRng::seed_from_u64(rng_seed);
+
+    let zones = load_zones(&map, &zones_path, &zone_id)?;
+    let profile = load_departure_profile(&profile_path)?;
+
+    let mut people = Vec::new();
+    let mut skipped = 0;
+    for rec in read_od(File::open(&od_path)?)? {
+        let (from, to) = match (zones.get(&rec.origin), zones.get(&rec.destination)) {
+            (Some(from), Some(to)) => (from, to),
+            _ => {
+                anyhow::bail!(
+                    "{} -> {} refers to a zone not in {}",
+                    rec.origin,
+                    rec.destination,
+                    zones_path
+                );
+            }
+        };
+        if !from.usable() || !to.usable() {
+            skipped += rec.count.round() as usize;
+            continue;
+        }
+
+        for _ in 0..num_trips(rec.count, &mut rng) {
+            let origin = from.pick(&mut rng, None);
+            // Try not to start and end at the same building for trips within a zone
+            let destination = to.pick(&mut rng, Some(&origin));
+            let hour = profile.choose_weighted(&mut rng, |(_, weight)| *weight)?.0;
+            let departure = Time::START_OF_DAY
+                + Duration::hours(hour)
+                + Duration::seconds(rng.gen_range(0.0..3600.0));
+            people.push(ExternalPerson {
+                origin,
+                trips: vec![ExternalTrip {
+                    departure,
+                    destination,
+                    mode: rec.mode,
+                    purpose: None,
+                }],
+            });
+        }
+    }
+    if skipped > 0 {
+        println!(
+            "Skipped {} trips involving zones inside the map without any buildings",
+            prettyprint_usize(skipped)
+        );
+    }
+
+    let mut s = Scenario::empty(&map, &scenario_name);
+    // Include all buses/trains
+    s.only_seed_buses = None;
+    s.people = ExternalPerson::import(&map, people)?;
+    println!(
+        "Imported {} people from the OD matrix",
+        prettyprint_usize(s.people.len())
+    );
+    s.save();
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct ODRecord {
+    origin: String,
+    destination: String,
+    mode: TripMode,
+    count: f64,
+}
+
+#[derive(Deserialize)]
+struct ProfileRecord {
+    hour: usize,
+    weight: f64,
+}
+
+struct Zone {
+    /// Buildings inside the zone, weighted by how many people live or work there
+    buildings: Vec<(BuildingID, usize)>,
+    /// Only used when there are no buildings
+    center: LonLat,
+    outside_map: bool,
+}
+
+impl Zone {
+    /// Zones entirely off the map are represented by the nearest border. Zones inside the map
+    /// without any buildings can't be used.
+    fn usable(&self) -> bool {
+        !self.buildings.is_empty() || self.outside_map
+    }
+
+    fn pick(
+        &self,
+        rng: &mut XorShiftRng,
+        avoid: Option<&ExternalTripEndpoint>,
+    ) -> ExternalTripEndpoint {
+        if self.buildings.is_empty() {
+            return ExternalTripEndpoint::Position(self.center);
+        }
+        let avoid = match avoid {
+            Some(ExternalTripEndpoint::TripEndpoint(TripEndpoint::Bldg(b))) => Some(*b),
+            _ => None,
+        };
+        let mut b = self.choose(rng);
+        // A few retries is plenty; zones with one building just get a trip to itself.
+        for _ in 0..3 {
+            if Some(b) != avoid {
+                break;
+            }
+            b = self.choose(rng);
+        }
+        ExternalTripEndpoint::TripEndpoint(TripEndpoint::Bldg(b))
+    }
+
+    fn choose(&self, rng: &mut XorShiftRng) -> BuildingID {
+        // If every building has zero weight, pick uniformly
+        self.buildings
+            .choose_weighted(rng, |(_, weight)| *weight)
+            .unwrap_or_else(|_| self.buildings.choose(rng).unwrap())
+            .0
+    }
+}
+
+fn read_od<R: Read>(reader: R) -> Result<Vec<ODRecord>> {
+    let mut records = Vec::new();
+    for rec in csv::Reader::from_reader(reader).deserialize() {
+        let rec: ODRecord = rec?;
+        if !rec.count.is_finite() || rec.count < 0.0 {
+            anyhow::bail!(
+                "{} -> {} has a bad count {}",
+                rec.origin,
+                rec.destination,
+                rec.count
+            );
+        }
+        records.push(rec);
+    }
+    Ok(records)
+}
+
+/// Rounds a fractional number of trips up or down randomly, so the expected total is preserved.
+/// The count must be finite and non-negative.
+fn num_trips(count: f64, rng: &mut XorShiftRng) -> usize {
+    let mut num = count.floor() as usize;
+    if rng.gen_bool(count.fract()) {
+        num += 1;
+    }
+    num
+}
+
+fn load_zones(map: &Map, path: &str, id_key: &str) -> Result<BTreeMap<String, Zone>> {
+    let bytes = abstio::slurp_file(path)?;
+    let geojson = std::str::from_utf8(&bytes)?.parse::<GeoJson>()?;
+    let features = match geojson {
+        GeoJson::FeatureCollection(collection) => collection.features,
+        _ => anyhow::bail!("{} isn't a FeatureCollection", path),
+    };
+
+    let gps_bounds = map.get_gps_bounds();
+    let mut zones = BTreeMap::new();
+    for feature in features {
+        let id = match feature.property(id_key) {
+            Some(serde_json::Value::String(s)) => s.clone(),
+            Some(serde_json::Value::Number(n)) => n.to_string(),
+            _ => anyhow::bail!("A zone is missing the {} property", id_key),
+        };
+        let polygons = match feature.geometry.as_ref().map(|g| &g.value) {
+            Some(value) => zone_polygons(value),
+            None => None,
+        }
+        .ok_or_else(|| anyhow::anyhow!("Zone {} isn't a polygon", id))?;
+        let all_pts: Vec<&LonLat> = polygons.iter().flatten().collect();
+        let center = LonLat::new(
+            all_pts.iter().map(|pt| pt.x()).sum::<f64>() / (all_pts.len() as f64),
+            all_pts.iter().map(|pt| pt.y()).sum::<f64>() / (all_pts.len() as f64),
+        );
+        let mut rings = Vec::new();
+        for gps in polygons {
+            rings.push(Ring::new(
+                gps.iter().map(|pt| pt.to_pt(gps_bounds)).collect(),
+            )?);
+        }
+
+        let buildings: Vec<(BuildingID, usize)> = map
+            .all_buildings()
+            .iter()
+            .filter(|b| {
+                let pt = b.polygon.center();
+                rings.iter().any(|ring| ring.contains_pt(pt))
+            })
+            .map(|b| (b.id, activity(&b.bldg_type)))
+            .collect();
+        let outside_map = !map
+            .get_boundary_polygon()
+            .contains_pt(center.to_pt(gps_bounds));
+        zones.insert(
+            id,
+            Zone {
+                buildings,
+                center,
+                outside_map,
+            },
+        );
+    }
+    Ok(zones)
+}
+
+/// The outer ring of every polygon making up a zone. Holes are ignored.
+fn zone_polygons(geometry: &Value) -> Option<Vec<Vec<LonLat>>> {
+    let outer_rings: Vec<&Vec<Vec<f64>>> = match geometry {
+        Value::Polygon(polygon) => polygon.iter().take(1).collect(),
+        Value::MultiPolygon(multi_polygon) => multi_polygon
+            .iter()
+            .filter_map(|polygon| polygon.get(0))
+            .collect(),
+        _ => return None,
+    };
+    if outer_rings.is_empty() {
+        return None;
+    }
+    Some(
+        outer_rings
+            .into_iter()
+            .map(|ring| ring.iter().map(|pt| LonLat::new(pt[0], pt[1])).collect())
+            .collect(),
+    )
+}
+
+/// How many people live or work in a building
+fn activity(bldg_type: &BuildingType) -> usize {
+    match bldg_type {
+        BuildingType::Residential { num_residents, .. } => *num_residents,
+        BuildingType::ResidentialCommercial(residents, workers) => residents + workers,
+        BuildingType::Commercial(workers) => *workers,
+        BuildingType::Empty => 0,
+    }
+}
+
+fn load_departure_profile(path: &str) -> Result<Vec<(usize, f64)>> {
+    let mut profile = Vec::new();
+    for rec in csv::Reader::from_reader(File::open(path)?).deserialize() {
+        let rec: ProfileRecord = rec?;
+        if rec.hour > 23 {
+            anyhow::bail!("Departure profile has hour {}", rec.hour);
+        }
+        profile.push((rec.hour, rec.weight));
+    }
+    if profile.is_empty() {
+        anyhow::bail!("{} is empty", path);
+    }
+    Ok(profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_od() {
+        let records = read_od("origin,destination,mode,count\na,b,Drive,2.5\n".as_bytes()).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].mode, TripMode::Drive);
+
+        for bad in vec!["-1", "NaN", "inf"] {
+            let input = format!("origin,destination,mode,count\na,b,Walk,{}\n", bad);
+            assert!(read_od(input.as_bytes()).is_err(), "count {} accepted", bad);
+        }
+    }
+
+    #[test]
+    fn test_num_trips() {
+        let mut rng = XorShiftRng::seed_from_u64(42);
+        assert_eq!(num_trips(0.0, &mut rng), 0);
+        assert_eq!(num_trips(3.0, &mut rng), 3);
+
+        let mut total = 0;
+        for _ in 0..1000 {
+            let n = num_trips(1.25, &mut rng);
+            assert!(n == 1 || n == 2);
+            total += n;
+        }
+        // The expected total is 1250
+        assert!(total > 1150 && total < 1350, "total {}", total);
+    }
+
+    #[test]
+    fn test_zone_polygons() {
+        let square = |x: f64| {
+            vec![
+                vec![x, 0.0],
+                vec![x + 1.0, 0.0],
+                vec![x + 1.0, 1.0],
+                vec![x, 1.0],
+                vec![x, 0.0],
+            ]
+        };
+
+        let polygon = zone_polygons(&Value::Polygon(vec![square(0.0), square(0.25)])).unwrap();
+        assert_eq!(polygon.len(), 1);
+        assert_eq!(polygon[0][1], LonLat::new(1.0, 0.0));
+
+        let multi = zone_polygons(&Value::MultiPolygon(vec![
+            vec![square(0.0)],
+            vec![square(10.0), square(10.25)],
+        ]))
+        .unwrap();
+        assert_eq!(multi.len(), 2);
+        assert_eq!(multi[1][0], LonLat::new(10.0, 0.0));
+
+        assert!(zone_polygons(&Value::Point(vec![0.0, 0.0])).is_none());
+        assert!(zone_polygons(&Value::MultiPolygon(Vec::new())).is_none());
+    }
+}
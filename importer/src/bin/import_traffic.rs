@@ -2,7 +2,7 @@ use serde::Deserialize;
 
 use abstutil::{CmdArgs, Timer};
 use map_model::Map;
-use sim::{ExternalPerson, Scenario};
+use sim::{ExternalHousehold, ExternalPerson, Scenario};
 
 fn main() {
     let mut args = CmdArgs::new();
@@ -18,6 +18,7 @@ fn main() {
     // Include all buses/trains
     s.only_seed_buses = None;
     s.people = ExternalPerson::import(&map, input.people).unwrap();
+    s.households = ExternalHousehold::import(&s.people, input.households).unwrap();
     s.save();
 }
 
@@ -25,4 +26,6 @@ fn main() {
 struct Input {
     scenario_name: String,
     people: Vec<ExternalPerson>,
+    #[serde(default)]
+    households: Vec<ExternalHousehold>,
 }
@@ -0,0 +1,165 @@
+//! Imports a table of trips, like the output of a household travel survey or an activity-based
+//! model, into a scenario. Each row is one trip; rows with the same person form that person's
+//! daily schedule, or activity chain.
+//!
+//! The CSV needs columns
+//! `person,departure,origin_lon,origin_lat,destination_lon,destination_lat,mode,purpose`.
+//! `departure` is `HH:MM:SS` or seconds since midnight, `mode` is one of `Walk`, `Bike`,
+//! `Transit`, or `Drive`, and `purpose` is a variant of `TripPurpose`, like `Work` or `Home`. The
+//! purpose may be left blank.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+
+use anyhow::Result;
+use serde::Deserialize;
+
+use abstutil::{prettyprint_usize, CmdArgs, Timer};
+use geom::{Distance, LonLat, Time};
+use map_model::Map;
+use sim::{ExternalPerson, ExternalTrip, ExternalTripEndpoint, Scenario, TripMode, TripPurpose};
+
+fn main() -> Result<()> {
+    let mut args = CmdArgs::new();
+    let map = args.required("--map");
+    let input = args.required("--input");
+    let scenario_name = args.required("--scenario_name");
+    args.done();
+
+    let mut timer = Timer::new("import trips CSV");
+    let map = Map::new(map, &mut timer);
+
+    let mut people = Vec::new();
+    for (id, sorted) in read_schedules(File::open(&input)?)? {
+        // Each trip should start where the previous one ended. The simulation can't teleport
+        // people, so the origin of everything except the first trip is ignored.
+        let gps_bounds = map.get_gps_bounds();
+        for pair in sorted.windows(2) {
+            let prev_end = pair[0].1.destination().to_pt(gps_bounds);
+            let next_start = pair[1].1.origin().to_pt(gps_bounds);
+            if prev_end.dist_to(next_start) > Distance::meters(100.0) {
+                println!(
+                    "Person {} doesn't start a trip at {} where their previous trip ended; using \
+                     the previous destination",
+                    id, pair[1].1.departure
+                );
+            }
+        }
+
+        people.push(ExternalPerson {
+            origin: ExternalTripEndpoint::Position(sorted[0].1.origin()),
+            trips: sorted
+                .into_iter()
+                .map(|(departure, trip)| ExternalTrip {
+                    departure,
+                    destination: ExternalTripEndpoint::Position(trip.destination()),
+                    mode: trip.mode,
+                    purpose: trip.purpose,
+                })
+                .collect(),
+        });
+    }
+
+    let mut s = Scenario::empty(&map, &scenario_name);
+    // Include all buses/trains
+    s.only_seed_buses = None;
+    s.people = ExternalPerson::import(&map, people)?;
+    println!(
+        "Imported {} people with {} trips",
+        prettyprint_usize(s.people.len()),
+        prettyprint_usize(s.people.iter().map(|p| p.trips.len()).sum())
+    );
+    s.save();
+    Ok(())
+}
+
+/// Groups trips by person, in the order people first appear, and sorts each person's trips by
+/// departure time.
+fn read_schedules<R: Read>(reader: R) -> Result<Vec<(String, Vec<(Time, TripRecord)>)>> {
+    // Keep the order people first appear in, so the output is stable
+    let mut order: Vec<String> = Vec::new();
+    let mut trips_per_person: BTreeMap<String, Vec<TripRecord>> = BTreeMap::new();
+    for rec in csv::Reader::from_reader(reader).deserialize() {
+        let rec: TripRecord = rec?;
+        if !trips_per_person.contains_key(&rec.person) {
+            order.push(rec.person.clone());
+        }
+        trips_per_person
+            .entry(rec.person.clone())
+            .or_insert_with(Vec::new)
+            .push(rec);
+    }
+
+    let mut schedules = Vec::new();
+    for id in order {
+        let mut sorted = Vec::new();
+        for trip in trips_per_person.remove(&id).unwrap() {
+            let departure = Time::parse(&trip.departure).map_err(|err| {
+                anyhow::anyhow!("Person {} has a bad departure time: {}", id, err)
+            })?;
+            sorted.push((departure, trip));
+        }
+        sorted.sort_by_key(|(t, _)| *t);
+        schedules.push((id, sorted));
+    }
+    Ok(schedules)
+}
+
+#[derive(Deserialize)]
+struct TripRecord {
+    person: String,
+    departure: String,
+    origin_lon: f64,
+    origin_lat: f64,
+    destination_lon: f64,
+    destination_lat: f64,
+    mode: TripMode,
+    purpose: Option<TripPurpose>,
+}
+
+impl TripRecord {
+    fn origin(&self) -> LonLat {
+        LonLat::new(self.origin_lon, self.origin_lat)
+    }
+
+    fn destination(&self) -> LonLat {
+        LonLat::new(self.destination_lon, self.destination_lat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str =
+        "person,departure,origin_lon,origin_lat,destination_lon,destination_lat,mode,purpose\n";
+
+    #[test]
+    fn test_read_schedules() {
+        let input = format!(
+            "{}b,08:00:00,1,2,3,4,Bike,Work\na,17:30:00,3,4,1,2,Walk,\na,07:00:00,1,2,3,4,Transit,\
+             Work\n",
+            HEADER
+        );
+        let schedules = read_schedules(input.as_bytes()).unwrap();
+
+        assert_eq!(schedules.len(), 2);
+        assert_eq!(schedules[0].0, "b");
+        assert_eq!(schedules[1].0, "a");
+
+        let a = &schedules[1].1;
+        assert_eq!(a[0].0, Time::START_OF_DAY + geom::Duration::hours(7));
+        assert_eq!(a[0].1.mode, TripMode::Transit);
+        assert!(matches!(a[0].1.purpose, Some(TripPurpose::Work)));
+        assert_eq!(a[0].1.destination(), LonLat::new(3.0, 4.0));
+        assert_eq!(a[1].1.mode, TripMode::Walk);
+        assert!(a[1].1.purpose.is_none());
+    }
+
+    #[test]
+    fn test_bad_departure() {
+        let input = format!("{}a,noon,1,2,3,4,Walk,\n", HEADER);
+        assert!(read_schedules(input.as_bytes()).is_err());
+    }
+}
@@ -1,84 +1,414 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 use abstio::MapName;
+use abstutil::Timer;
+use geom::{GPSBounds, LonLat};
 use map_model::raw::RawMap;
+use map_model::Map;
 
 use crate::configuration::ImporterConfiguration;
-use crate::utils::{download, osmconvert};
+use crate::utils::{download, download_kml};
 
-/// Importing a new city can be done just by filling out this config file and specifying some
-/// polygon boundaries. Most fields are directly from `convert_osm::Options`.
+/// Importing a new city can be done just by filling out this config file in
+/// `importer/config/$city/cfg.json` and specifying some polygon boundaries, one `$map.poly` per
+/// map. Most fields are directly from `convert_osm::Options`.
 ///
-/// If any extra data is imported for a city (like collisions or population), then for now, don't
-/// use this.
+/// Paths to input files are relative to the `data/` directory, like `input/seattle/blockface.bin`.
 #[derive(Serialize, Deserialize)]
 pub struct GenericCityImporter {
     /// The URL to a .osm or .osm.pbf file containing the entire city.
     /// http://download.geofabrik.de/ is recommended.
-    ///
-    /// You can also put a path like `input/seattle/osm/washington-latest.osm.pbf` in here,
-    /// and instead that file will be used. This is kind of a hack, because it'll assume the cities
-    /// are imported in the proper order, but it prevents having to download duplicate large files.
     pub osm_url: String,
+    /// Where to keep the downloaded `osm_url`, relative to `data/`. Defaults to
+    /// `input/$city/osm/$filename`. Cities covered by the same large file can share one path, so
+    /// it's only downloaded once, by whichever city is imported first.
+    #[serde(default)]
+    pub osm_file: Option<String>,
 
     pub map_config: map_model::MapConfig,
     pub onstreet_parking: convert_osm::OnstreetParking,
     pub public_offstreet_parking: convert_osm::PublicOffstreetParking,
     pub private_offstreet_parking: convert_osm::PrivateOffstreetParking,
+    /// Overrides `private_offstreet_parking` for some maps, keyed by map name.
+    #[serde(default)]
+    pub private_offstreet_parking_per_map: BTreeMap<String, convert_osm::PrivateOffstreetParking>,
     /// If provided, pull elevation data from this SRTM file. The SRTM parser is incorrect, so the
     /// results will be nonsense.
     pub elevation: Option<String>,
     /// OSM railway=rail will be included as light rail if so. Cosmetic only.
     pub include_railroads: bool,
+
+    /// Extra input files to download before importing any map. .zip and .gz files are
+    /// uncompressed.
+    #[serde(default)]
+    pub extra_files: Vec<ExtraFile>,
+    /// KML layers to download, clip, and convert to `ExtraShapes`.
+    #[serde(default)]
+    pub kml_layers: Vec<KmlLayer>,
+    /// Produces `input/$city/collisions.bin`.
+    #[serde(default)]
+    pub collisions: Option<CollisionsSource>,
+    /// Travel demand to import with `--scenario`.
+    #[serde(default)]
+    pub scenarios: Vec<ScenarioSource>,
+    /// Steps for this city that haven't been generalized.
+    #[serde(default)]
+    pub extra_steps: Vec<ExtraStep>,
+    /// Produce a city overview from all of the individual maps when regenerating everything.
+    #[serde(default)]
+    pub city_overview: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExtraFile {
+    pub url: String,
+    /// Relative to `data/`. Use a trailing `/` to unzip into a directory.
+    pub output: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct KmlLayer {
+    /// Must end with `.kml`
+    pub url: String,
+    /// Relative to `data/`, ending with `.bin`
+    pub output: String,
+    /// The name of a map in this city. Shapes outside its polygon are dropped.
+    pub boundary: String,
+    /// If false, shapes partly out of bounds are kept.
+    pub require_all_pts_in_bounds: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum CollisionsSource {
+    /// UK STATS19 data, as a .zip containing a CSV
+    Stats19 {
+        url: String,
+        /// Where the CSV winds up after unzipping, relative to `data/`
+        csv: String,
+        /// Only import while importing this map, using its boundary. If not specified, use every
+        /// map.
+        only_map: Option<String>,
+    },
+    /// From https://data-seattlecitygis.opendata.arcgis.com/datasets/5b5c745e0f1f48e7a53acec63a0022ab_0
+    SeattleGIS {
+        /// A KML export of the dataset
+        url: String,
+        /// Recorded as the source of each collision. The dataset's page, rather than the export.
+        source: String,
+        /// Where to download the KML, relative to `data/`
+        kml: String,
+        /// The name of a map in this city, defining which collisions are kept
+        boundary: String,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum ScenarioSource {
+    /// Seattle's travel demand model, https://github.com/psrc/soundcast. Needs the `scenarios`
+    /// feature.
+    Soundcast {
+        /// The name of a map covering the entire region. Trips are clipped from there.
+        huge_map: String,
+        /// After generating the scenario, give buildings in these maps enough private parking for
+        /// the cars that start there.
+        adjust_private_parking: Vec<String>,
+    },
+    /// Desire lines from https://github.com/cyipt/actdev, as GeoJSON
+    Actdev { url: String },
+    /// The same JSON format accepted by the `import_traffic` tool, relative to `data/`
+    ExternalPeople { path: String },
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum ExtraStep {
+    /// Distribute residents from Berlin's planning areas to buildings
+    BerlinPopulation {
+        /// The planning areas, as `ExtraShapes` from one of the `kml_layers`, relative to `data/`
+        planning_areas: String,
+        /// Residents per planning area, relative to `data/`
+        population_csv: String,
+    },
+    /// Add schedules to bus routes from King County Metro's GTFS
+    SeattleGTFS {
+        /// The unzipped GTFS directory, relative to `data/`
+        gtfs_dir: String,
+    },
 }
 
 impl GenericCityImporter {
-    pub fn osm_to_raw(
-        &self,
-        name: MapName,
-        timer: &mut abstutil::Timer,
-        config: &ImporterConfiguration,
-    ) -> RawMap {
-        let local_osm_file = if self.osm_url.starts_with("http") {
-            let file = format!(
+    /// Reads `importer/config/$city/cfg.json`.
+    pub fn load(city: &str, timer: &mut Timer) -> GenericCityImporter {
+        match abstio::maybe_read_json::<GenericCityImporter>(
+            format!("importer/config/{}/cfg.json", city),
+            timer,
+        ) {
+            Ok(city_cfg) => city_cfg,
+            Err(err) => {
+                panic!("Can't import city {}: {}", city, err);
+            }
+        }
+    }
+
+    /// Downloads the OSM input, if needed. Returns its path, relative to `data/`.
+    pub fn download_osm(&self, city: &str, config: &ImporterConfiguration) -> String {
+        let local_osm_file = self.osm_file.clone().unwrap_or_else(|| {
+            format!(
                 "input/{}/osm/{}",
-                name.city,
+                city,
                 std::path::Path::new(&self.osm_url)
                     .file_name()
                     .unwrap()
                     .to_os_string()
                     .into_string()
                     .unwrap()
-            );
-            download(config, &file, &self.osm_url);
-            file
-        } else {
-            self.osm_url.clone()
-        };
-
-        osmconvert(
-            &local_osm_file,
-            format!("importer/config/{}/{}.poly", name.city, name.map),
-            format!("input/{}/osm/{}.osm", name.city, name.map),
-            config,
-        );
+            )
+        });
+        download(config, &local_osm_file, &self.osm_url);
+        local_osm_file
+    }
 
+    /// A .osm.pbf input usually covers much more than the city, and reading each map streams all
+    /// of it. When importing several maps, clip the input once to a region covering all of them,
+    /// and read each map from that instead. Returns the path to read maps from, relative to
+    /// `data/`.
+    pub fn clip_osm(
+        &self,
+        city: &str,
+        osm_input: String,
+        maps: &[String],
+        timer: &mut Timer,
+    ) -> String {
+        if !osm_input.ends_with(".pbf") || maps.len() < 2 {
+            return osm_input;
+        }
+        let mut region = GPSBounds::new();
+        for map in maps {
+            for pt in LonLat::read_osmosis_polygon(&boundary_path(city, map)).unwrap() {
+                region.update(pt);
+            }
+        }
+        let output = format!("input/{}/osm/clipped.bin", city);
+        convert_osm::pbf::clip_to_file(
+            &abstio::path(&osm_input),
+            &region,
+            abstio::path(&output),
+            timer,
+        )
+        .unwrap();
+        output
+    }
+
+    /// Reads one map from `osm_input`, a path relative to `data/` returned by `download_osm` or
+    /// `clip_osm`.
+    pub fn osm_to_raw(
+        &self,
+        name: MapName,
+        osm_input: &str,
+        timer: &mut Timer,
+        config: &ImporterConfiguration,
+    ) -> RawMap {
+        self.download_inputs(&name.city, config, timer);
+
+        // convert_osm reads .osm.pbf files directly, clipping to the boundary while reading, so
+        // there's no need to produce a smaller .osm first.
         let map = convert_osm::convert(
             convert_osm::Options {
-                osm_input: abstio::path(format!("input/{}/osm/{}.osm", name.city, name.map)),
+                osm_input: abstio::path(osm_input),
                 name: name.clone(),
 
-                clip: Some(format!("importer/config/{}/{}.poly", name.city, name.map)),
+                clip: Some(boundary_path(&name.city, &name.map)),
                 map_config: self.map_config.clone(),
-                onstreet_parking: self.onstreet_parking.clone(),
-                public_offstreet_parking: self.public_offstreet_parking.clone(),
-                private_offstreet_parking: self.private_offstreet_parking.clone(),
-                elevation: self.elevation.clone(),
+                onstreet_parking: match self.onstreet_parking {
+                    convert_osm::OnstreetParking::Blockface(ref path) => {
+                        convert_osm::OnstreetParking::Blockface(abstio::path(path))
+                    }
+                    ref x => x.clone(),
+                },
+                public_offstreet_parking: match self.public_offstreet_parking {
+                    convert_osm::PublicOffstreetParking::GIS(ref path) => {
+                        convert_osm::PublicOffstreetParking::GIS(abstio::path(path))
+                    }
+                    ref x => x.clone(),
+                },
+                private_offstreet_parking: self
+                    .private_offstreet_parking_per_map
+                    .get(&name.map)
+                    .unwrap_or(&self.private_offstreet_parking)
+                    .clone(),
+                elevation: self.elevation.as_ref().map(abstio::path),
                 include_railroads: self.include_railroads,
             },
             timer,
         );
         map.save();
+
+        self.import_collisions(&map, config, timer);
+        for step in &self.extra_steps {
+            if let ExtraStep::BerlinPopulation {
+                planning_areas,
+                population_csv,
+            } = step
+            {
+                // Always do this, it's idempotent and fast
+                crate::berlin::correlate_population(
+                    &abstio::path(planning_areas),
+                    &abstio::path(population_csv),
+                    timer,
+                );
+            }
+        }
+
         map
     }
+
+    /// Downloads everything except the OSM input. Files that already exist are skipped.
+    fn download_inputs(&self, city: &str, config: &ImporterConfiguration, timer: &mut Timer) {
+        for file in &self.extra_files {
+            download(config, &file.output, &file.url);
+        }
+        for layer in &self.kml_layers {
+            download_kml(
+                &layer.output,
+                &layer.url,
+                &load_boundary(city, &layer.boundary),
+                layer.require_all_pts_in_bounds,
+                timer,
+            );
+        }
+    }
+
+    fn import_collisions(&self, map: &RawMap, config: &ImporterConfiguration, timer: &mut Timer) {
+        let city = &map.name.city;
+        let output = format!("data/input/{}/collisions.bin", city);
+        match self.collisions {
+            Some(CollisionsSource::Stats19 {
+                ref url,
+                ref csv,
+                ref only_map,
+            }) => {
+                if only_map
+                    .as_ref()
+                    .map(|m| m != &map.name.map)
+                    .unwrap_or(false)
+                {
+                    return;
+                }
+                download(config, csv, url);
+
+                // Always do this, it's idempotent and fast
+                let shapes =
+                    kml::ExtraShapes::load_csv(&abstio::path(csv), &map.gps_bounds, timer).unwrap();
+                let collisions = collisions::import_stats19(shapes, url);
+                abstio::write_binary(output, &collisions);
+            }
+            Some(CollisionsSource::SeattleGIS {
+                ref url,
+                ref source,
+                ref kml,
+                ref boundary,
+            }) => {
+                download(config, kml, url);
+
+                // This is a little expensive, so delete the output to regenerate this.
+                if !abstio::file_exists(&output) {
+                    let shapes = kml::load(
+                        &abstio::path(kml),
+                        &load_boundary(city, boundary),
+                        true,
+                        timer,
+                    )
+                    .unwrap();
+                    let collisions = collisions::import_seattle(shapes, source);
+                    abstio::write_binary(output, &collisions);
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Run any extra steps on a freshly built map.
+    pub fn after_raw_to_map(&self, map: &mut Map, timer: &mut Timer) {
+        for step in &self.extra_steps {
+            match step {
+                ExtraStep::BerlinPopulation { planning_areas, .. } => {
+                    timer.start(format!(
+                        "distribute residents from planning areas for {}",
+                        map.get_name().describe()
+                    ));
+                    crate::berlin::distribute_residents(map, &abstio::path(planning_areas), timer);
+                    timer.stop(format!(
+                        "distribute residents from planning areas for {}",
+                        map.get_name().describe()
+                    ));
+                }
+                ExtraStep::SeattleGTFS { gtfs_dir } => {
+                    timer.start(format!(
+                        "add GTFS schedules for {}",
+                        map.get_name().describe()
+                    ));
+                    crate::seattle::add_gtfs_schedules(map, &abstio::path(gtfs_dir));
+                    timer.stop(format!(
+                        "add GTFS schedules for {}",
+                        map.get_name().describe()
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Import every scenario source that doesn't need Soundcast.
+    pub fn import_simple_scenarios(
+        &self,
+        map: &Map,
+        config: &ImporterConfiguration,
+        timer: &mut Timer,
+    ) {
+        for source in &self.scenarios {
+            match source {
+                ScenarioSource::Soundcast { .. } => {}
+                ScenarioSource::Actdev { url } => {
+                    crate::actdev::import_scenarios(map, url, config).unwrap();
+                }
+                ScenarioSource::ExternalPeople { path } => {
+                    let input: ExternalInput = abstio::read_json(abstio::path(path), timer);
+                    let mut s = sim::Scenario::empty(map, &input.scenario_name);
+                    // Include all buses/trains
+                    s.only_seed_buses = None;
+                    s.people = sim::ExternalPerson::import(map, input.people).unwrap();
+                    s.households =
+                        sim::ExternalHousehold::import(&s.people, input.households).unwrap();
+                    s.save();
+                }
+            }
+        }
+    }
+
+    /// If this city uses Soundcast, returns the huge map and which maps need parking adjusted.
+    pub fn soundcast(&self) -> Option<(&String, &Vec<String>)> {
+        self.scenarios.iter().find_map(|source| match source {
+            ScenarioSource::Soundcast {
+                huge_map,
+                adjust_private_parking,
+            } => Some((huge_map, adjust_private_parking)),
+            _ => None,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct ExternalInput {
+    scenario_name: String,
+    people: Vec<sim::ExternalPerson>,
+    #[serde(default)]
+    households: Vec<sim::ExternalHousehold>,
+}
+
+fn boundary_path(city: &str, map: &str) -> String {
+    format!("importer/config/{}/{}.poly", city, map)
+}
+
+fn load_boundary(city: &str, map: &str) -> GPSBounds {
+    GPSBounds::from(LonLat::read_osmosis_polygon(&boundary_path(city, map)).unwrap())
 }
@@ -11,14 +11,13 @@ use abstutil::{basename, Timer};
 
 use configuration::{load_configuration, ImporterConfiguration};
 use dependencies::are_dependencies_callable;
+use generic::GenericCityImporter;
 
 mod actdev;
 mod berlin;
 mod configuration;
 mod dependencies;
 mod generic;
-mod leeds;
-mod london;
 mod seattle;
 #[cfg(feature = "scenarios")]
 mod soundcast;
@@ -89,37 +88,23 @@ fn main() {
 
 fn regenerate_everything(config: ImporterConfiguration) {
     let mut timer = Timer::new("regenerate all maps");
-    for city in vec![
-        "seattle",
-        "bellevue",
-        "berlin",
-        "cambridge",
-        "cheshire",
-        "detroit",
-        "krakow",
-        "leeds",
-        "london",
-        "nyc",
-        "paris",
-        "salzburg",
-        "tel_aviv",
-        "warsaw",
-    ] {
-        let mut job = Job {
-            city: city.to_string(),
+    // Every directory in importer/config is a city. Each city downloads its own input, so the
+    // order doesn't matter.
+    for city in abstio::list_dir("importer/config".to_string())
+        .into_iter()
+        .filter(|path| std::path::Path::new(path).is_dir())
+        .map(basename)
+    {
+        let city_cfg = GenericCityImporter::load(&city, &mut timer);
+        let job = Job {
+            city,
             osm_to_raw: true,
             raw_to_map: true,
-            scenario: false,
-            city_overview: false,
+            // Only some maps run extra tasks
+            scenario: !city_cfg.scenarios.is_empty(),
+            city_overview: city_cfg.city_overview,
             only_map: None,
         };
-        // Only some maps run extra tasks
-        if city == "seattle" || city == "cambridge" {
-            job.scenario = true;
-        }
-        if city == "nyc" || city == "paris" || city == "salzburg" {
-            job.city_overview = true;
-        }
 
         let skip_ch = false;
         let keep_bldg_tags = false;
@@ -158,16 +143,18 @@ impl Job {
                 .collect()
         };
 
-        let (maybe_popdat, maybe_huge_map) = if self.scenario {
-            // TODO This is getting messy!
-            if self.city == "cambridge" {
-                (None, None)
-            } else {
-                assert_eq!(self.city, "seattle");
+        let city_cfg = GenericCityImporter::load(&self.city, timer);
 
+        let (maybe_popdat, maybe_huge_map) = match city_cfg.soundcast() {
+            Some((_huge_map, _)) if self.scenario => {
                 #[cfg(feature = "scenarios")]
                 {
-                    let (popdat, huge_map) = seattle::ensure_popdat_exists(timer, config);
+                    let (popdat, huge_map) = seattle::ensure_popdat_exists(
+                        &city_cfg,
+                        MapName::new(&self.city, _huge_map),
+                        timer,
+                        config,
+                    );
                     (Some(popdat), Some(huge_map))
                 }
 
@@ -178,62 +165,25 @@ impl Job {
                     (Some(true), Some(true))
                 }
             }
+            _ => (None, None),
+        };
+
+        let osm_input = if self.osm_to_raw {
+            let osm_input = city_cfg.download_osm(&self.city, config);
+            Some(city_cfg.clip_osm(&self.city, osm_input, &names, timer))
         } else {
-            (None, None)
+            None
         };
 
         for name in names {
-            if self.osm_to_raw {
-                // Still special-cased
-                if self.city == "seattle" {
-                    seattle::osm_to_raw(&name, timer, config);
-                } else {
-                    let raw = match abstio::maybe_read_json::<generic::GenericCityImporter>(
-                        format!("importer/config/{}/cfg.json", self.city),
-                        timer,
-                    ) {
-                        Ok(city_cfg) => {
-                            city_cfg.osm_to_raw(MapName::new(&self.city, &name), timer, config)
-                        }
-                        Err(err) => {
-                            panic!("Can't import city {}: {}", self.city, err);
-                        }
-                    };
-
-                    match self.city.as_ref() {
-                        "berlin" => berlin::import_extra_data(&raw, config, timer),
-                        "leeds" => {
-                            if name == "huge" {
-                                leeds::import_extra_data(&raw, config, timer);
-                            }
-                        }
-                        "london" => london::import_extra_data(&raw, config, timer),
-                        _ => {}
-                    }
-                }
-            }
             let name = MapName::new(&self.city, &name);
+            if let Some(ref osm_input) = osm_input {
+                city_cfg.osm_to_raw(name.clone(), osm_input, timer, config);
+            }
 
             let mut maybe_map = if self.raw_to_map {
                 let mut map = utils::raw_to_map(&name, !skip_ch, keep_bldg_tags, timer);
-
-                // Another strange step in the pipeline.
-                if name == MapName::new("berlin", "center") {
-                    timer.start(format!(
-                        "distribute residents from planning areas for {}",
-                        name.describe()
-                    ));
-                    berlin::distribute_residents(&mut map, timer);
-                    timer.stop(format!(
-                        "distribute residents from planning areas for {}",
-                        name.describe()
-                    ));
-                } else if name.city == "seattle" {
-                    timer.start(format!("add GTFS schedules for {}", name.describe()));
-                    seattle::add_gtfs_schedules(&mut map);
-                    timer.stop(format!("add GTFS schedules for {}", name.describe()));
-                }
-
+                city_cfg.after_raw_to_map(&mut map, timer);
                 Some(map)
             } else if self.scenario {
                 Some(map_model::Map::new(name.path(), timer))
@@ -243,7 +193,7 @@ impl Job {
 
             if self.scenario {
                 #[cfg(feature = "scenarios")]
-                if self.city == "seattle" {
+                if let Some((_, adjust_private_parking)) = city_cfg.soundcast() {
                     timer.start(format!("scenario for {}", name.describe()));
                     let scenario = soundcast::make_weekday_scenario(
                         maybe_map.as_ref().unwrap(),
@@ -255,7 +205,7 @@ impl Job {
                     timer.stop(format!("scenario for {}", name.describe()));
 
                     // This is a strange ordering.
-                    if name.map == "downtown" || name.map == "south_seattle" {
+                    if adjust_private_parking.contains(&name.map) {
                         timer.start(format!("adjust parking for {}", name.describe()));
                         seattle::adjust_private_parking(maybe_map.as_mut().unwrap(), &scenario);
                         timer.stop(format!("adjust parking for {}", name.describe()));
@@ -266,9 +216,7 @@ impl Job {
                     timer.stop("match parcels to buildings");
                 }
 
-                if self.city == "cambridge" {
-                    actdev::import_scenarios(maybe_map.as_ref().unwrap(), config).unwrap();
-                }
+                city_cfg.import_simple_scenarios(maybe_map.as_ref().unwrap(), config, timer);
             }
         }
 
@@ -294,7 +242,10 @@ fn oneshot(
 ) {
     let mut timer = abstutil::Timer::new("oneshot");
     println!("- Running convert_osm on {}", osm_path);
-    let name = abstutil::basename(&osm_path);
+    // A clipped map is usually one small piece of a much larger input, so name it after the clip
+    let name = abstutil::basename(clip.as_ref().unwrap_or(&osm_path))
+        .trim_end_matches(".osm")
+        .to_string();
     let raw = convert_osm::convert(
         convert_osm::Options {
             osm_input: osm_path,
@@ -242,6 +242,7 @@ pub fn make_weekday_scenario(
         map_name: map.get_name().clone(),
         people,
         only_seed_buses: None,
+        households: Vec::new(),
     }
     .remove_weird_schedules()
 }
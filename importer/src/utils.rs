@@ -96,33 +96,6 @@ pub fn download_kml(
     std::fs::rename(tmp, output.replace(".bin", ".kml")).unwrap();
 }
 
-// Uses osmconvert to clip the input .osm (or .pbf) against a polygon and produce some output.
-// Skips if the output exists.
-pub fn osmconvert(
-    input: &str,
-    clipping_polygon: String,
-    output: String,
-    config: &ImporterConfiguration,
-) {
-    let input = abstio::path(input);
-    let clipping_polygon = clipping_polygon;
-    let output = abstio::path(output);
-
-    if Path::new(&output).exists() {
-        println!("- {} already exists", output);
-        return;
-    }
-    println!("- Clipping {} to {}", input, clipping_polygon);
-
-    must_run_cmd(
-        Command::new(&config.osmconvert)
-            .arg(input)
-            .arg(format!("-B={}", clipping_polygon))
-            .arg("--complete-ways")
-            .arg(format!("-o={}", output)),
-    );
-}
-
 // Converts a RawMap to a Map.
 pub fn raw_to_map(
     name: &MapName,
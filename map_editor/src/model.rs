@@ -294,6 +294,7 @@ impl Model {
                 osm_tags,
                 turn_restrictions: Vec::new(),
                 complicated_turn_restrictions: Vec::new(),
+                lane_connectivity: Vec::new(),
             },
         );
         self.road_added(id, ctx);
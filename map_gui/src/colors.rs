@@ -1,6 +1,13 @@
 //! A color scheme groups colors used for different map, dynamic, and UI elements in one place, to
 //! encourage deduplication. The player can also switch between different color schemes.
 
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+use anyhow::Result;
+use instant::Instant;
+use serde::Deserialize;
+
 use map_model::osm::RoadRank;
 use map_model::LaneType;
 use widgetry::{Choice, Color, EventCtx, Fill, Style, Texture};
@@ -18,7 +25,7 @@ use crate::tools::{loading_tips, ColorScale};
 //
 // TODO There are plenty of colors left that aren't captured here. :(
 
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum ColorSchemeChoice {
     DayMode,
     NightMode,
@@ -32,10 +39,21 @@ pub enum ColorSchemeChoice {
     MapboxDark,
     FadedZoom,
     NegativeSpace,
+    /// Loaded from a file in `data/player/color_schemes/`, by name. See `CustomColorScheme`.
+    Custom(String),
 }
 
 impl ColorSchemeChoice {
+    /// The built-in schemes, then any custom ones.
     pub fn choices() -> Vec<Choice<ColorSchemeChoice>> {
+        let mut choices = ColorSchemeChoice::builtin_choices();
+        for name in abstio::list_all_objects(abstio::path_player("color_schemes")) {
+            choices.push(Choice::new(name.clone(), ColorSchemeChoice::Custom(name)));
+        }
+        choices
+    }
+
+    fn builtin_choices() -> Vec<Choice<ColorSchemeChoice>> {
         vec![
             Choice::new("day mode", ColorSchemeChoice::DayMode),
             Choice::new("night mode", ColorSchemeChoice::NightMode),
@@ -55,6 +73,8 @@ impl ColorSchemeChoice {
 
 pub struct ColorScheme {
     scheme: ColorSchemeChoice,
+    /// For custom schemes, the file to watch for changes
+    file: Option<WatchedFile>,
 
     // UI
     pub panel_bg: Color,
@@ -157,6 +177,23 @@ pub struct ColorScheme {
 
 impl ColorScheme {
     pub fn new(ctx: &mut EventCtx, scheme: ColorSchemeChoice) -> ColorScheme {
+        let cs = match scheme {
+            ColorSchemeChoice::Custom(ref name) => {
+                ColorScheme::custom(name).unwrap_or_else(|err| {
+                    error!("Couldn't load the {} color scheme: {}", name, err);
+                    // Keep watching the file, so the scheme shows up once it's fixed
+                    let mut cs = ColorScheme::builtin(ColorSchemeChoice::DayMode);
+                    cs.file = Some(WatchedFile::new(ColorScheme::custom_path(name)));
+                    cs
+                })
+            }
+            _ => ColorScheme::builtin(scheme),
+        };
+        ctx.set_style(cs.gui_style.clone());
+        cs
+    }
+
+    fn builtin(scheme: ColorSchemeChoice) -> ColorScheme {
         let mut cs = match scheme {
             ColorSchemeChoice::DayMode => ColorScheme::day_mode(),
             ColorSchemeChoice::NightMode => ColorScheme::night_mode(),
@@ -170,9 +207,9 @@ impl ColorScheme {
             ColorSchemeChoice::MapboxDark => ColorScheme::mapbox_dark(),
             ColorSchemeChoice::FadedZoom => ColorScheme::faded_zoom(),
             ColorSchemeChoice::NegativeSpace => ColorScheme::negative_space(),
+            ColorSchemeChoice::Custom(_) => unreachable!(),
         };
         cs.scheme = scheme;
-        ctx.set_style(cs.gui_style.clone());
         cs
     }
 
@@ -181,6 +218,7 @@ impl ColorScheme {
         gui_style.loading_tips = loading_tips();
         ColorScheme {
             scheme: ColorSchemeChoice::DayMode,
+            file: None,
 
             // UI
             panel_bg: gui_style.panel_bg,
@@ -584,3 +622,329 @@ impl ColorScheme {
         cs
     }
 }
+
+/// A color scheme loaded from `data/player/color_schemes/<name>.json`. It starts from one of the
+/// built-in schemes, then overrides colors by the name of the field in `ColorScheme`, like
+/// `unzoomed_highway`, `bus_lane`, `commercial_building`, `unzoomed_bike`, or `good_to_bad_red`.
+/// Colors are written like "#RRGGBB" or "#RRGGBBAA". `agent_colors` and the color scales are
+/// lists of colors. While the scheme is in use, edits to the file show up immediately.
+#[derive(Deserialize)]
+pub struct CustomColorScheme {
+    /// The name of a built-in scheme, like "night mode". Defaults to day mode.
+    #[serde(default)]
+    pub base: Option<String>,
+    pub colors: BTreeMap<String, serde_json::Value>,
+}
+
+struct WatchedFile {
+    path: String,
+    modified: Option<SystemTime>,
+    last_checked: Instant,
+}
+
+impl WatchedFile {
+    /// Call before reading the file, so edits made while it's read aren't missed.
+    fn new(path: String) -> WatchedFile {
+        WatchedFile {
+            modified: last_modified(&path),
+            path,
+            last_checked: Instant::now(),
+        }
+    }
+}
+
+impl ColorScheme {
+    fn custom_path(name: &str) -> String {
+        abstio::path_player(format!("color_schemes/{}.json", name))
+    }
+
+    fn custom(name: &str) -> Result<ColorScheme> {
+        let file = WatchedFile::new(ColorScheme::custom_path(name));
+        let custom: CustomColorScheme = serde_json::from_slice(&abstio::slurp_file(&file.path)?)?;
+
+        let base = match custom.base {
+            Some(base) => {
+                ColorSchemeChoice::builtin_choices()
+                    .into_iter()
+                    .find(|c| c.label == base)
+                    .ok_or_else(|| anyhow!("unknown base color scheme {}", base))?
+                    .data
+            }
+            None => ColorSchemeChoice::DayMode,
+        };
+        let mut cs = ColorScheme::builtin(base);
+        for (key, value) in custom.colors {
+            cs.override_color(&key, &value)
+                .map_err(|err| anyhow!("{}: {}", key, err))?;
+        }
+        cs.file = Some(file);
+        Ok(cs)
+    }
+
+    /// If this scheme was loaded from a file that's been edited since, load it again. The file is
+    /// checked at most once a second. Returns true if the scheme changed.
+    pub fn reload_if_changed(&mut self, ctx: &mut EventCtx) -> bool {
+        let file = if let Some(ref mut file) = self.file {
+            file
+        } else {
+            return false;
+        };
+        if file.last_checked.elapsed() < std::time::Duration::from_secs(1) {
+            return false;
+        }
+        file.last_checked = Instant::now();
+        let modified = last_modified(&file.path);
+        if modified == file.modified {
+            return false;
+        }
+        // Even if the file is broken, don't try to load it again until it changes
+        file.modified = modified;
+
+        let name = abstutil::basename(&file.path);
+        match ColorScheme::custom(&name) {
+            Ok(cs) => {
+                ctx.set_style(cs.gui_style.clone());
+                *self = cs;
+                true
+            }
+            Err(err) => {
+                error!("Couldn't reload the {} color scheme: {}", name, err);
+                false
+            }
+        }
+    }
+
+    fn override_color(&mut self, key: &str, value: &serde_json::Value) -> Result<()> {
+        match key {
+            // UI
+            "panel_bg" => {
+                self.panel_bg = parse_color(value)?;
+                self.gui_style.panel_bg = self.panel_bg;
+            }
+            "section_bg" => self.section_bg = parse_color(value)?,
+            "inner_panel" => self.inner_panel = parse_color(value)?,
+            "day_time_slider" => self.day_time_slider = parse_color(value)?,
+            "night_time_slider" => self.night_time_slider = parse_color(value)?,
+            "selected" => self.selected = parse_color(value)?,
+            "current_object" => self.current_object = parse_color(value)?,
+            "perma_selected_object" => self.perma_selected_object = parse_color(value)?,
+            "bottom_bar_id" => self.bottom_bar_id = parse_color(value)?,
+            "bottom_bar_name" => self.bottom_bar_name = parse_color(value)?,
+            "fade_map_dark" => self.fade_map_dark = parse_color(value)?,
+            "dialog_bg" => self.dialog_bg = parse_color(value)?,
+            "minimap_cursor_border" => self.minimap_cursor_border = parse_color(value)?,
+            "minimap_cursor_bg" => self.minimap_cursor_bg = parse_optional_color(value)?,
+            "minimap_selected_zoom" => self.minimap_selected_zoom = parse_color(value)?,
+            "minimap_unselected_zoom" => self.minimap_unselected_zoom = parse_color(value)?,
+
+            // Roads
+            "driving_lane" => self.driving_lane = parse_color(value)?,
+            "bus_lane" => self.bus_lane = parse_color(value)?,
+            "parking_lane" => self.parking_lane = parse_color(value)?,
+            "bike_lane" => self.bike_lane = parse_color(value)?,
+            "sidewalk" => self.sidewalk = parse_color(value)?,
+            "sidewalk_lines" => self.sidewalk_lines = parse_optional_color(value)?,
+            "general_road_marking" => self.general_road_marking = parse_color(value)?,
+            "road_center_line" => self.road_center_line = parse_color(value)?,
+            "light_rail_track" => self.light_rail_track = parse_color(value)?,
+            "private_road" => self.private_road = parse_color(value)?,
+            "unzoomed_highway" => self.unzoomed_highway = parse_color(value)?,
+            "unzoomed_arterial" => self.unzoomed_arterial = parse_color(value)?,
+            "unzoomed_residential" => self.unzoomed_residential = parse_color(value)?,
+            "unzoomed_trail" => self.unzoomed_trail = parse_color(value)?,
+
+            // Intersections
+            "normal_intersection" => self.normal_intersection = parse_color(value)?,
+            "stop_sign" => self.stop_sign = parse_color(value)?,
+            "stop_sign_pole" => self.stop_sign_pole = parse_color(value)?,
+            "signal_protected_turn" => self.signal_protected_turn = parse_color(value)?,
+            "signal_permitted_turn" => self.signal_permitted_turn = parse_color(value)?,
+            "signal_banned_turn" => self.signal_banned_turn = parse_color(value)?,
+            "signal_box" => self.signal_box = parse_color(value)?,
+            "signal_spinner" => self.signal_spinner = parse_color(value)?,
+            "signal_turn_block_bg" => self.signal_turn_block_bg = parse_color(value)?,
+
+            // Timeline delay highlighting
+            "very_slow_intersection" => self.very_slow_intersection = parse_color(value)?,
+            "slow_intersection" => self.slow_intersection = parse_color(value)?,
+            "normal_slow_intersection" => self.normal_slow_intersection = parse_color(value)?,
+
+            // Other static elements
+            "void_background" => self.void_background = parse_color(value)?,
+            "map_background" => self.map_background = parse_color(value)?.into(),
+            "unzoomed_interesting_intersection" => {
+                self.unzoomed_interesting_intersection = parse_color(value)?
+            }
+            "residential_building" => self.residential_building = parse_color(value)?,
+            "commercial_building" => self.commercial_building = parse_color(value)?,
+            "building_outline" => self.building_outline = parse_color(value)?,
+            "parking_lot" => self.parking_lot = parse_color(value)?,
+            "grass" => self.grass = parse_color(value)?.into(),
+            "water" => self.water = parse_color(value)?.into(),
+            "median_strip" => self.median_strip = parse_color(value)?.into(),
+            "pedestrian_plaza" => self.pedestrian_plaza = parse_color(value)?.into(),
+
+            // Unzoomed dynamic elements
+            "unzoomed_car" => self.unzoomed_car = parse_color(value)?,
+            "unzoomed_bike" => self.unzoomed_bike = parse_color(value)?,
+            "unzoomed_bus" => self.unzoomed_bus = parse_color(value)?,
+            "unzoomed_pedestrian" => self.unzoomed_pedestrian = parse_color(value)?,
+
+            // Agents
+            "agent_colors" => self.agent_colors = parse_colors(value, 1)?,
+            "route" => self.route = parse_color(value)?,
+            "turn_arrow" => self.turn_arrow = parse_color(value)?,
+            "brake_light" => self.brake_light = parse_color(value)?,
+            "bus_body" => self.bus_body = parse_color(value)?,
+            "bus_label" => self.bus_label = parse_color(value)?,
+            "train_body" => self.train_body = parse_color(value)?,
+            "ped_head" => self.ped_head = parse_color(value)?,
+            "ped_foot" => self.ped_foot = parse_color(value)?,
+            "ped_preparing_bike_body" => self.ped_preparing_bike_body = parse_color(value)?,
+            "ped_crowd" => self.ped_crowd = parse_color(value)?,
+            "bike_frame" => self.bike_frame = parse_color(value)?,
+            "parked_car" => self.parked_car = parse_color(value)?,
+
+            // Layers
+            "good_to_bad_red" => self.good_to_bad_red = ColorScale(parse_colors(value, 2)?),
+            "good_to_bad_green" => self.good_to_bad_green = ColorScale(parse_colors(value, 2)?),
+            "bus_layer" => self.bus_layer = parse_color(value)?,
+            "edits_layer" => self.edits_layer = parse_color(value)?,
+
+            // Misc
+            "parking_trip" => self.parking_trip = parse_color(value)?,
+            "bike_trip" => self.bike_trip = parse_color(value)?,
+            "bus_trip" => self.bus_trip = parse_color(value)?,
+            "before_changes" => self.before_changes = parse_color(value)?,
+            "after_changes" => self.after_changes = parse_color(value)?,
+
+            _ => bail!("not a color in the scheme"),
+        }
+        Ok(())
+    }
+}
+
+// Not available on the web
+fn last_modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn parse_color(value: &serde_json::Value) -> Result<Color> {
+    let raw = value
+        .as_str()
+        .ok_or_else(|| anyhow!("expected a color like \"#RRGGBB\", not {}", value))?;
+    if !raw.starts_with('#')
+        || !(raw.len() == 7 || raw.len() == 9)
+        || !raw[1..].chars().all(|c| c.is_ascii_hexdigit())
+    {
+        bail!(
+            "expected a color like \"#RRGGBB\" or \"#RRGGBBAA\", not {}",
+            raw
+        );
+    }
+    let color = Color::hex(&raw[0..7]);
+    if raw.len() == 9 {
+        let alpha = u8::from_str_radix(&raw[7..9], 16)?;
+        return Ok(color.alpha((alpha as f32) / 255.0));
+    }
+    Ok(color)
+}
+
+/// null means no color
+fn parse_optional_color(value: &serde_json::Value) -> Result<Option<Color>> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    parse_color(value).map(Some)
+}
+
+fn parse_colors(value: &serde_json::Value, min_len: usize) -> Result<Vec<Color>> {
+    let colors = value
+        .as_array()
+        .ok_or_else(|| anyhow!("expected a list of colors, not {}", value))?
+        .iter()
+        .map(parse_color)
+        .collect::<Result<Vec<_>>>()?;
+    if colors.len() < min_len {
+        bail!("expected at least {} colors", min_len);
+    }
+    Ok(colors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_override_color() {
+        let mut cs = ColorScheme::builtin(ColorSchemeChoice::NightMode);
+        let custom: CustomColorScheme = serde_json::from_str(
+            r##"{
+                "colors": {
+                    "unzoomed_highway": "#E69F00",
+                    "bike_lane": "#009E7380",
+                    "sidewalk_lines": null,
+                    "good_to_bad_red": ["#F0E442", "#D55E00"]
+                }
+            }"##,
+        )
+        .unwrap();
+        for (key, value) in custom.colors {
+            cs.override_color(&key, &value).unwrap();
+        }
+        assert_eq!(
+            cs.unzoomed_road_surface(RoadRank::Highway),
+            Color::hex("#E69F00")
+        );
+        assert_eq!(
+            cs.zoomed_road_surface(LaneType::Biking, RoadRank::Local),
+            Color::hex("#009E73").alpha(128.0 / 255.0)
+        );
+        assert_eq!(cs.sidewalk_lines, None);
+        assert_eq!(cs.good_to_bad_red.0.len(), 2);
+
+        assert!(cs
+            .override_color("driving_lane", &serde_json::json!("red"))
+            .is_err());
+        assert!(cs
+            .override_color("good_to_bad_green", &serde_json::json!(["#FFFFFF"]))
+            .is_err());
+        assert!(cs
+            .override_color("no_such_color", &serde_json::json!("#FFFFFF"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_every_field_can_be_overridden() {
+        // Read the fields of ColorScheme from this file, so new ones can't be forgotten
+        let src = include_str!("colors.rs");
+        let start = src.find("pub struct ColorScheme {").unwrap();
+        let end = start + src[start..].find("\n}\n").unwrap();
+        let mut cs = ColorScheme::builtin(ColorSchemeChoice::DayMode);
+        let mut num_fields = 0;
+        for line in src[start..end].lines().skip(1) {
+            let line = line.trim().trim_start_matches("pub ");
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            let mut parts = line.trim_end_matches(',').split(": ");
+            let (field, field_type) = (parts.next().unwrap(), parts.next().unwrap());
+            let value = match field_type {
+                "Color" | "Fill" => serde_json::json!("#123456"),
+                "Option<Color>" => serde_json::json!(null),
+                "Vec<Color>" | "ColorScale" => serde_json::json!(["#123456", "#654321"]),
+                // Not colors
+                "ColorSchemeChoice" | "Option<WatchedFile>" | "Style" => {
+                    continue;
+                }
+                _ => panic!("{} has an unexpected type {}", field, field_type),
+            };
+            if let Err(err) = cs.override_color(field, &value) {
+                panic!("Can't override {} in a custom color scheme: {}", field, err);
+            }
+            num_fields += 1;
+        }
+        // Make sure the fields were found at all
+        assert!(num_fields > 50);
+    }
+}
@@ -64,7 +64,21 @@ pub trait AppLike {
             return false;
         }
         self.mut_opts().color_scheme = cs;
-        *self.mut_cs() = ColorScheme::new(ctx, self.opts().color_scheme);
+        *self.mut_cs() = ColorScheme::new(ctx, self.opts().color_scheme.clone());
+
+        ctx.loading_screen("rerendering map colors", |ctx, timer| {
+            *self.mut_draw_map() = DrawMap::new(ctx, self.map(), self.opts(), self.cs(), timer);
+        });
+
+        true
+    }
+
+    /// If the color scheme is loaded from a file that's been edited, load it again. Return true if
+    /// there was a change.
+    fn reload_color_scheme(&mut self, ctx: &mut EventCtx) -> bool {
+        if !self.mut_cs().reload_if_changed(ctx) {
+            return false;
+        }
 
         ctx.loading_screen("rerendering map colors", |ctx, timer| {
             *self.mut_draw_map() = DrawMap::new(ctx, self.map(), self.opts(), self.cs(), timer);
@@ -7,7 +7,7 @@ use widgetry::{
 
 use crate::colors::ColorSchemeChoice;
 use crate::render::DrawBuilding;
-use crate::tools::grey_out_map;
+use crate::tools::{grey_out_map, set_ui_language, ui_language_choices, ui_translations};
 use crate::AppLike;
 
 /// Options controlling the UI. Some of the options are common to all map-based apps, and some are
@@ -44,6 +44,8 @@ pub struct Options {
     /// Display roads and buildings in an alternate language, if possible. None means to use the
     /// OSM native name.
     pub language: Option<String>,
+    /// The language of the UI, by language code. None means English.
+    pub ui_language: Option<String>,
     /// How to render geometric units
     pub units: UnitFmt,
 }
@@ -65,6 +67,7 @@ impl Options {
             jump_to_delay: Duration::minutes(5),
 
             language: None,
+            ui_language: None,
             units: UnitFmt {
                 round_durations: true,
                 // TODO Should default be based on the map?
@@ -98,6 +101,12 @@ impl Options {
                 );
             }
         }
+        if let Some(x) = args.optional("--ui_language") {
+            if let Err(err) = ui_translations(Some(&x)) {
+                panic!("Invalid --ui_language={}: {}", x, err);
+            }
+            self.ui_language = Some(x);
+        }
     }
 }
 
@@ -131,7 +140,7 @@ impl OptionsPanel {
                     Line("Settings").small_heading().draw(ctx),
                     ctx.style().btn_close_widget(ctx),
                 ]),
-                "Camera controls".draw_text(ctx),
+                ctx.tr("Camera controls").draw_text(ctx),
                 Widget::col(vec![
                     Checkbox::checkbox(
                         ctx,
@@ -159,58 +168,79 @@ impl OptionsPanel {
                         ctx.canvas.keys_to_pan,
                     ),
                     Widget::row(vec![
-                        "Scroll speed for menus".draw_text(ctx).centered_vert(),
+                        ctx.tr("Scroll speed for menus")
+                            .draw_text(ctx)
+                            .centered_vert(),
                         Spinner::new(ctx, (1, 50), ctx.canvas.gui_scroll_speed as isize)
                             .named("gui_scroll_speed"),
                     ]),
                 ])
                 .bg(app.cs().section_bg)
                 .padding(8),
-                "Appearance".draw_text(ctx),
+                ctx.tr("Appearance").draw_text(ctx),
                 Widget::col(vec![
                     Widget::row(vec![
-                        "Traffic signal rendering:".draw_text(ctx),
+                        ctx.tr("Traffic signal rendering:").draw_text(ctx),
                         Widget::dropdown(
                             ctx,
                             "Traffic signal rendering",
                             app.opts().traffic_signal_style.clone(),
                             vec![
-                                Choice::new("Default (Brian's style)", TrafficSignalStyle::BAP),
-                                Choice::new("Yuwen's style", TrafficSignalStyle::Yuwen),
                                 Choice::new(
-                                    "arrows showing individual turns (to debug)",
+                                    ctx.tr("Default (Brian's style)"),
+                                    TrafficSignalStyle::BAP,
+                                ),
+                                Choice::new(ctx.tr("Yuwen's style"), TrafficSignalStyle::Yuwen),
+                                Choice::new(
+                                    ctx.tr("arrows showing individual turns (to debug)"),
                                     TrafficSignalStyle::IndividualTurnArrows,
                                 ),
                             ],
                         ),
                     ]),
                     Widget::row(vec![
-                        "Camera angle:".draw_text(ctx),
+                        ctx.tr("Camera angle:").draw_text(ctx),
                         Widget::dropdown(
                             ctx,
                             "Camera angle",
                             app.opts().camera_angle.clone(),
                             vec![
-                                Choice::new("Top-down", CameraAngle::TopDown),
-                                Choice::new("Isometric (northeast)", CameraAngle::IsometricNE),
-                                Choice::new("Isometric (northwest)", CameraAngle::IsometricNW),
-                                Choice::new("Isometric (southeast)", CameraAngle::IsometricSE),
-                                Choice::new("Isometric (southwest)", CameraAngle::IsometricSW),
-                                Choice::new("Abstract (just symbols)", CameraAngle::Abstract),
+                                Choice::new(ctx.tr("Top-down"), CameraAngle::TopDown),
+                                Choice::new(
+                                    ctx.tr("Isometric (northeast)"),
+                                    CameraAngle::IsometricNE,
+                                ),
+                                Choice::new(
+                                    ctx.tr("Isometric (northwest)"),
+                                    CameraAngle::IsometricNW,
+                                ),
+                                Choice::new(
+                                    ctx.tr("Isometric (southeast)"),
+                                    CameraAngle::IsometricSE,
+                                ),
+                                Choice::new(
+                                    ctx.tr("Isometric (southwest)"),
+                                    CameraAngle::IsometricSW,
+                                ),
+                                Choice::new(
+                                    ctx.tr("Abstract (just symbols)"),
+                                    CameraAngle::Abstract,
+                                ),
                             ],
                         ),
                     ]),
                     Widget::row(vec![
-                        "Color scheme:".draw_text(ctx),
+                        ctx.tr("Color scheme:").draw_text(ctx),
                         Widget::dropdown(
                             ctx,
                             "Color scheme",
-                            app.opts().color_scheme,
+                            app.opts().color_scheme.clone(),
                             ColorSchemeChoice::choices(),
                         ),
                     ]),
                     Widget::row(vec![
-                        "Camera zoom to switch to unzoomed view".draw_text(ctx),
+                        ctx.tr("Camera zoom to switch to unzoomed view")
+                            .draw_text(ctx),
                         Widget::dropdown(
                             ctx,
                             "min zoom",
@@ -226,10 +256,19 @@ impl OptionsPanel {
                         ),
                     ]),
                     Widget::row(vec![
-                        "Language".draw_text(ctx),
+                        ctx.tr("Language of the interface").draw_text(ctx),
+                        Widget::dropdown(
+                            ctx,
+                            "ui_language",
+                            app.opts().ui_language.clone(),
+                            ui_language_choices(),
+                        ),
+                    ]),
+                    Widget::row(vec![
+                        ctx.tr("Language of map labels").draw_text(ctx),
                         Widget::dropdown(ctx, "language", app.opts().language.clone(), {
                             let mut choices = Vec::new();
-                            choices.push(Choice::new("Map native language", None));
+                            choices.push(Choice::new(ctx.tr("Map native language"), None));
                             for lang in app.map().get_languages() {
                                 choices.push(Choice::new(lang, Some(lang.to_string())));
                             }
@@ -247,7 +286,7 @@ impl OptionsPanel {
                 ])
                 .bg(app.cs().section_bg)
                 .padding(8),
-                "Debug".draw_text(ctx),
+                ctx.tr("Debug").draw_text(ctx),
                 Widget::col(vec![
                     Checkbox::checkbox(ctx, "Enable developer mode", None, app.opts().dev),
                     Checkbox::checkbox(
@@ -339,7 +378,7 @@ impl<A: AppLike> State<A> for OptionsPanel {
 
                     if app.change_color_scheme(ctx, self.panel.dropdown_value("Color scheme")) {
                         // change_color_scheme doesn't modify our local copy of Options!
-                        opts.color_scheme = app.opts().color_scheme;
+                        opts.color_scheme = app.opts().color_scheme.clone();
                         // If the player picks a different scheme, don't undo it later.
                         opts.toggle_day_night_colors = false;
                     }
@@ -355,6 +394,18 @@ impl<A: AppLike> State<A> for OptionsPanel {
                         }
                     }
 
+                    let ui_language = self.panel.dropdown_value("ui_language");
+                    if ui_language != opts.ui_language {
+                        match set_ui_language(ctx, ui_language.as_deref()) {
+                            Ok(()) => {
+                                opts.ui_language = ui_language;
+                            }
+                            Err(err) => {
+                                error!("Couldn't change the UI language: {}", err);
+                            }
+                        }
+                    }
+
                     *app.mut_opts() = opts;
 
                     return widgetry::Transition::Pop;
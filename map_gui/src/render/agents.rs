@@ -36,6 +36,13 @@ impl AgentCache {
         }
     }
 
+    /// Forget everything cached, so all agents are uploaded again the next time they're drawn.
+    pub fn invalidate(&mut self) {
+        self.time = None;
+        self.agents_per_on.clear();
+        self.unzoomed = None;
+    }
+
     pub fn get(&self, on: Traversable) -> Vec<&dyn Renderable> {
         self.agents_per_on[&on]
             .iter()
@@ -10,7 +10,7 @@ use crate::load::MapLoader;
 use crate::options::Options;
 use crate::render::DrawMap;
 use crate::render::{DrawOptions, Renderable};
-use crate::tools::CameraState;
+use crate::tools::{set_ui_language, CameraState};
 use crate::{AppLike, ID};
 
 /// Simple app state that just renders a static map, without any dynamic agents on the map.
@@ -43,7 +43,9 @@ impl<T: 'static> SimpleApp<T> {
             .unwrap_or(MapName::seattle("montlake"));
         args.done();
 
-        let cs = ColorScheme::new(ctx, opts.color_scheme);
+        let cs = ColorScheme::new(ctx, opts.color_scheme.clone());
+        // update_from_args already checked the language
+        set_ui_language(ctx, opts.ui_language.as_deref()).unwrap();
         // Start with a blank map
         let map = Map::blank();
         let draw_map = DrawMap::new(ctx, &map, &opts, &cs, &mut Timer::throwaway());
@@ -288,6 +290,10 @@ impl<T: 'static> AppLike for SimpleApp<T> {
 }
 
 impl<T: 'static> SharedAppState for SimpleApp<T> {
+    fn before_event(&mut self, ctx: &mut EventCtx) {
+        self.reload_color_scheme(ctx);
+    }
+
     fn draw_default(&self, g: &mut GfxCtx) {
         self.draw_with_opts(g, DrawOptions::new());
     }
@@ -63,6 +63,7 @@ impl<A: AppLike + 'static> CityPicker<A> {
                         let btn = ctx
                             .style()
                             .btn_outline_light_text(nice_map_name(&name))
+                            .untranslated()
                             .label_color(color, ControlState::Default)
                             .no_tooltip();
 
@@ -87,7 +88,7 @@ impl<A: AppLike + 'static> CityPicker<A> {
                         continue;
                     }
                     // If there's only one map in the city, make the button directly load it.
-                    let button = ctx.style().btn_outline_light_text(&city);
+                    let button = ctx.style().btn_outline_light_text(&city).untranslated();
                     let maps = MapName::list_all_maps_in_city(&city);
                     if maps.len() == 1 {
                         other_cities.push(button.build_widget(ctx, &maps[0].path()));
@@ -273,6 +274,7 @@ impl<A: AppLike + 'static> AllCityPicker<A> {
             buttons.push(
                 ctx.style()
                     .btn_outline_light_text(&name.describe())
+                    .untranslated()
                     .build_widget(ctx, &name.path())
                     .margin_right(10)
                     .margin_below(10),
@@ -311,6 +311,16 @@ impl<'a> ColorNetwork<'a> {
             .push(color.alpha(0.4), self.map.get_pl(pl).polygon.clone());
     }
 
+    pub fn add_bs(&mut self, bs: BusStopID, color: Color) {
+        let pt = self.map.get_bs(bs).sidewalk_pos.pt(self.map);
+        self.zoomed.push(
+            color.alpha(0.4),
+            Circle::new(pt, Distance::meters(5.0)).to_polygon(),
+        );
+        self.unzoomed
+            .push(color, Circle::new(pt, Distance::meters(15.0)).to_polygon());
+    }
+
     // Order the roads by count, then interpolate a color based on position in that ordering.
     pub fn ranked_roads(&mut self, counter: Counter<RoadID>, scale: &ColorScale) {
         let roads = counter.sorted_asc();
@@ -10,6 +10,7 @@ pub use self::colors::{ColorDiscrete, ColorLegend, ColorNetwork, ColorScale, Div
 pub use self::heatmap::{make_heatmap, Grid, HeatmapOptions};
 pub use self::minimap::{Minimap, MinimapControls};
 pub use self::navigate::Navigator;
+pub use self::translations::{set_ui_language, ui_language_choices, ui_translations};
 pub use self::turn_explorer::TurnExplorer;
 pub use self::ui::{ChooseSomething, PopupMsg, PromptInput};
 use crate::AppLike;
@@ -20,6 +21,7 @@ mod colors;
 mod heatmap;
 mod minimap;
 mod navigate;
+mod translations;
 mod turn_explorer;
 mod ui;
 #[cfg(not(target_arch = "wasm32"))]
@@ -0,0 +1,78 @@
+use anyhow::Result;
+
+use widgetry::{Choice, EventCtx, Translations};
+
+/// Message catalogs for the UI, besides English: the language code, the name of the language in
+/// that language, and the catalog itself. See `map_gui/translations/README.md`.
+const CATALOGS: [(&str, &str, &str); 1] =
+    [("pl", "Polski", include_str!("../../translations/pl.json"))];
+
+/// Not a real language; used to find text that isn't translated yet
+const PSEUDO_LOCALE: &str = "pseudo";
+
+/// Every language the UI can be shown in, by language code. None means English.
+pub fn ui_language_choices() -> Vec<Choice<Option<String>>> {
+    let mut choices = vec![Choice::new("English", None)];
+    for (code, name, _) in &CATALOGS {
+        choices.push(Choice::new(*name, Some(code.to_string())));
+    }
+    choices.push(Choice::new(
+        "Pseudo-locale (for testing)",
+        Some(PSEUDO_LOCALE.to_string()),
+    ));
+    choices
+}
+
+/// Loads the translations for a language, by code. None means English.
+pub fn ui_translations(language: Option<&str>) -> Result<Translations> {
+    match language {
+        None => Ok(Translations::English),
+        Some(PSEUDO_LOCALE) => Ok(Translations::Pseudo),
+        Some(code) => {
+            let (_, _, raw) = CATALOGS
+                .iter()
+                .find(|(x, _, _)| *x == code)
+                .ok_or_else(|| anyhow!("no UI translations for {}", code))?;
+            Translations::from_json(raw)
+        }
+    }
+}
+
+/// Translates all UI text created from now on into a language, by code. None means English.
+pub fn set_ui_language(ctx: &EventCtx, language: Option<&str>) -> Result<()> {
+    ctx.set_translations(ui_translations(language)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalogs() {
+        for (code, _, _) in &CATALOGS {
+            let translations = ui_translations(Some(code)).unwrap();
+            if let Translations::Catalog(messages) = translations {
+                for (english, translated) in messages {
+                    // A translation has to use every parameter
+                    let args: Vec<String> = (0..english.matches("{}").count())
+                        .map(|i| format!("<param {}>", i))
+                        .collect();
+                    let filled = widgetry::fill_parameters(&translated, &args);
+                    for arg in &args {
+                        assert!(
+                            filled.contains(arg),
+                            "{} translation of {:?} is missing {}",
+                            code,
+                            english,
+                            arg
+                        );
+                    }
+                }
+            } else {
+                panic!("{} isn't a message catalog", code);
+            }
+        }
+        assert!(ui_translations(Some("xx")).is_err());
+    }
+}
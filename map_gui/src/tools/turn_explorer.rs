@@ -115,7 +115,8 @@ impl TurnExplorer {
                         .get_parent(l)
                         .get_name(app.opts().language.as_ref())
                 ))
-                .small_heading(),
+                .small_heading()
+                .verbatim(),
             )
             .draw(ctx),
             Widget::vert_separator(ctx, 50.0),
@@ -9,7 +9,8 @@ use geom::Speed;
 
 use crate::raw::OriginalRoad;
 use crate::{
-    osm, AccessRestrictions, Direction, EditCmd, EditRoad, LaneType, Map, PermanentMapEdits, RoadID,
+    osm, AccessRestrictions, Direction, EditCmd, EditRoad, LaneType, Map, PermanentMapEdits,
+    RoadID, MAIN_BRANCH,
 };
 
 /// When the PermanentMapEdits format changes, add a transformation here to automatically convert
@@ -82,6 +83,27 @@ pub fn upgrade(mut value: Value, map: &Map) -> Result<PermanentMapEdits> {
             .unwrap()
             .insert("version".to_string(), Value::Number(7.into()));
     }
+    if value["version"] == Value::Number(7.into()) {
+        fix_turn_arrows(&mut value, map)?;
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(8.into()));
+    }
+    if value["version"] == Value::Number(8.into()) {
+        fix_time_windows(&mut value, map)?;
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(9.into()));
+    }
+    if value["version"] == Value::Number(9.into()) {
+        fix_history(&mut value);
+        value
+            .as_object_mut()
+            .unwrap()
+            .insert("version".to_string(), Value::Number(10.into()));
+    }
 
     abstutil::from_json(&value.to_string().into_bytes())
 }
@@ -211,7 +233,14 @@ fn fix_old_lane_cmds(value: &mut Value, map: &Map) -> Result<()> {
                 bail!("{:?} speed limit has changed", obj);
             }
             road.speed_limit = obj.new;
-        } else if let Some(obj) = cmd.remove("ChangeAccessRestrictions") {
+        } else if let Some(mut obj) = cmd.remove("ChangeAccessRestrictions") {
+            // time_windows came later, in fix_time_windows
+            for key in vec!["old", "new"] {
+                obj[key]
+                    .as_object_mut()
+                    .unwrap()
+                    .insert("time_windows".to_string(), Value::Array(Vec::new()));
+            }
             let obj: ChangeAccessRestrictions = serde_json::from_value(obj).unwrap();
             let r = map.find_r_by_osm_id(obj.id)?;
             let road = modified.entry(r).or_insert_with(|| map.get_r_edit(r));
@@ -311,6 +340,56 @@ fn fix_plans(value: &mut Value) {
     })
 }
 
+// Turn arrows were added to EditRoad. Older edits couldn't change them, so use the original arrows
+// from OSM.
+fn fix_turn_arrows(value: &mut Value, map: &Map) -> Result<()> {
+    for cmd in value.as_object_mut().unwrap()["commands"]
+        .as_array_mut()
+        .unwrap()
+    {
+        if let Some(obj) = cmd.as_object_mut().unwrap().get_mut("ChangeRoad") {
+            let obj = obj.as_object_mut().unwrap();
+            let r: OriginalRoad = serde_json::from_value(obj["r"].clone())?;
+            let road = map.get_r(map.find_r_by_osm_id(r)?);
+            let allowed_turns = serde_json::to_value(
+                EditRoad::get_orig_from_osm(road, map.get_config()).allowed_turns,
+            )?;
+            for key in vec!["old", "new"] {
+                obj.get_mut(key)
+                    .unwrap()
+                    .as_object_mut()
+                    .unwrap()
+                    .insert("allowed_turns".to_string(), allowed_turns.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
+// Time windows were added to AccessRestrictions. Fill in whatever OSM says for old edits that
+// didn't have them yet.
+fn fix_time_windows(value: &mut Value, map: &Map) -> Result<()> {
+    for cmd in value.as_object_mut().unwrap()["commands"]
+        .as_array_mut()
+        .unwrap()
+    {
+        if let Some(obj) = cmd.as_object_mut().unwrap().get_mut("ChangeRoad") {
+            let obj = obj.as_object_mut().unwrap();
+            let r: OriginalRoad = serde_json::from_value(obj["r"].clone())?;
+            let road = map.get_r(map.find_r_by_osm_id(r)?);
+            let time_windows =
+                serde_json::to_value(road.access_restrictions_from_osm().time_windows)?;
+            for key in vec!["old", "new"] {
+                obj.get_mut(key).unwrap()["access_restrictions"]
+                    .as_object_mut()
+                    .unwrap()
+                    .insert("time_windows".to_string(), time_windows.clone());
+            }
+        }
+    }
+    Ok(())
+}
+
 // These're old structs used in fix_old_lane_cmds.
 #[derive(Debug, Deserialize)]
 struct OriginalLane {
@@ -368,3 +447,16 @@ impl OriginalLane {
         Ok((r.id, r.offset(l)))
     }
 }
+
+// The redo stack, checkpoints, and branches were added. Old edits just have one branch with no
+// history.
+fn fix_history(value: &mut Value) {
+    let obj = value.as_object_mut().unwrap();
+    obj.insert("redo_stack".to_string(), Value::Array(Vec::new()));
+    obj.insert("checkpoints".to_string(), Value::Array(Vec::new()));
+    obj.insert("branch".to_string(), Value::String(MAIN_BRANCH.to_string()));
+    obj.insert(
+        "other_branches".to_string(),
+        Value::Object(serde_json::Map::new()),
+    );
+}
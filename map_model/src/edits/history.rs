@@ -0,0 +1,322 @@
+//! Besides the stack of commands, a proposal keeps some history: commands that were undone and
+//! could be redone, named checkpoints, and alternate branches. Branches let one proposal hold a few
+//! variations of the same idea, like "option A" and "option B" for one corridor. Only one branch is
+//! applied to the map at a time.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use geom::Time;
+
+use crate::edits::{EditCmd, EditIntersection, EditRoad, MapEdits};
+use crate::{BusRouteID, IntersectionID, RoadID};
+
+/// Every proposal starts with a branch named this.
+pub const MAIN_BRANCH: &str = "main";
+
+/// A named point in the history of one branch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub name: String,
+    /// How many commands had been applied at the time
+    pub num_commands: usize,
+}
+
+/// A branch of a proposal that isn't currently applied to the map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditBranch {
+    pub commands: Vec<EditCmd>,
+    pub redo_stack: Vec<EditCmd>,
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+/// Everything that winds up different between two branches.
+pub struct BranchDiff {
+    /// Each road, with a description of what's different about it
+    pub roads: Vec<(RoadID, Vec<String>)>,
+    pub intersections: Vec<IntersectionID>,
+    pub routes: Vec<BusRouteID>,
+}
+
+impl MapEdits {
+    /// Undoes the most recent command, returning it. It can be redone until something else is
+    /// edited.
+    pub fn undo(&mut self) -> Option<EditCmd> {
+        let cmd = self.commands.pop()?;
+        self.redo_stack.push(cmd.clone());
+        Some(cmd)
+    }
+
+    /// Redoes the most recently undone command, returning it.
+    pub fn redo(&mut self) -> Option<EditCmd> {
+        let cmd = self.redo_stack.pop()?;
+        self.commands.push(cmd.clone());
+        Some(cmd)
+    }
+
+    /// Any change to the commands besides undo and redo starts a new line of history, so the redo
+    /// stack and checkpoints from the abandoned line have to go. `prev` are the edits before the
+    /// change.
+    pub(crate) fn forget_stale_history(&mut self, prev: &MapEdits) {
+        if self.edits_name != prev.edits_name
+            || self.branch != prev.branch
+            || self.redo_stack != prev.redo_stack
+        {
+            return;
+        }
+        let common_prefix = self
+            .commands
+            .iter()
+            .zip(prev.commands.iter())
+            .take_while(|(cmd1, cmd2)| cmd1 == cmd2)
+            .count();
+        if common_prefix == self.commands.len() && common_prefix == prev.commands.len() {
+            return;
+        }
+        self.redo_stack.clear();
+        self.checkpoints
+            .retain(|cp| cp.num_commands <= common_prefix);
+    }
+
+    /// Remembers the current point in history, replacing any checkpoint with the same name.
+    pub fn add_checkpoint(&mut self, name: String) {
+        self.checkpoints.retain(|cp| cp.name != name);
+        self.checkpoints.push(Checkpoint {
+            name,
+            num_commands: self.commands.len(),
+        });
+    }
+
+    /// Undoes or redoes commands to get back to a checkpoint.
+    pub fn restore_checkpoint(&mut self, name: &str) -> Result<()> {
+        let goal = self.get_checkpoint(name)?.num_commands;
+        if goal > self.commands.len() + self.redo_stack.len() {
+            bail!("Checkpoint {} is past the end of the history", name);
+        }
+        while self.commands.len() > goal {
+            self.undo();
+        }
+        while self.commands.len() < goal {
+            self.redo();
+        }
+        Ok(())
+    }
+
+    fn get_checkpoint(&self, name: &str) -> Result<&Checkpoint> {
+        self.checkpoints
+            .iter()
+            .find(|cp| cp.name == name)
+            .ok_or_else(|| anyhow!("No checkpoint named {}", name))
+    }
+
+    /// All branches, including the current one
+    pub fn all_branches(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.other_branches.keys().cloned().collect();
+        names.push(self.branch.clone());
+        names.sort();
+        names
+    }
+
+    /// Starts a new branch and switches to it, leaving the current branch as it is. The new branch
+    /// starts with the current commands, or the commands at some checkpoint.
+    pub fn create_branch(&mut self, name: String, from_checkpoint: Option<&str>) -> Result<()> {
+        if name == self.branch || self.other_branches.contains_key(&name) {
+            bail!("There's already a branch called {}", name);
+        }
+        let num_commands = match from_checkpoint {
+            Some(cp) => self.get_checkpoint(cp)?.num_commands,
+            None => self.commands.len(),
+        };
+        // The checkpoint might be in the redo stack
+        let mut commands = self.commands.clone();
+        commands.extend(self.redo_stack.iter().rev().cloned());
+        commands.truncate(num_commands);
+        let branch = EditBranch {
+            commands,
+            redo_stack: Vec::new(),
+            checkpoints: self
+                .checkpoints
+                .iter()
+                .filter(|cp| cp.num_commands <= num_commands)
+                .cloned()
+                .collect(),
+        };
+
+        self.other_branches.insert(name.clone(), branch);
+        self.switch_branch(&name)
+    }
+
+    /// Makes a different branch the current one. The caller has to apply the edits to the map.
+    pub fn switch_branch(&mut self, name: &str) -> Result<()> {
+        let branch = self
+            .other_branches
+            .remove(name)
+            .ok_or_else(|| anyhow!("No branch named {}", name))?;
+        let old_name = std::mem::replace(&mut self.branch, name.to_string());
+        let old_branch = EditBranch {
+            commands: std::mem::replace(&mut self.commands, branch.commands),
+            redo_stack: std::mem::replace(&mut self.redo_stack, branch.redo_stack),
+            checkpoints: std::mem::replace(&mut self.checkpoints, branch.checkpoints),
+        };
+        self.other_branches.insert(old_name, old_branch);
+        Ok(())
+    }
+
+    /// Deletes a branch that isn't the current one.
+    pub fn delete_branch(&mut self, name: &str) -> Result<()> {
+        if name == self.branch {
+            bail!("Can't delete the current branch");
+        }
+        self.other_branches
+            .remove(name)
+            .ok_or_else(|| anyhow!("No branch named {}", name))?;
+        Ok(())
+    }
+
+    /// Compares the final state of two branches, without applying either to the map.
+    pub fn diff_branches(&self, branch1: &str, branch2: &str) -> Result<BranchDiff> {
+        let changes1 = Changes::new(self.branch_commands(branch1)?);
+        let changes2 = Changes::new(self.branch_commands(branch2)?);
+        Ok(BranchDiff {
+            roads: differences(&changes1.roads, &changes2.roads)
+                .into_iter()
+                .map(|(r, road1, road2)| (r, road2.diff(road1)))
+                .collect(),
+            intersections: differences(&changes1.intersections, &changes2.intersections)
+                .into_iter()
+                .map(|(i, _, _)| i)
+                .collect(),
+            routes: differences(&changes1.routes, &changes2.routes)
+                .into_iter()
+                .map(|(r, _, _)| r)
+                .collect(),
+        })
+    }
+
+    fn branch_commands(&self, name: &str) -> Result<&Vec<EditCmd>> {
+        if name == self.branch {
+            return Ok(&self.commands);
+        }
+        self.other_branches
+            .get(name)
+            .map(|branch| &branch.commands)
+            .ok_or_else(|| anyhow!("No branch named {}", name))
+    }
+}
+
+/// For everything touched by some commands, the state before and after all of them
+struct Changes {
+    roads: BTreeMap<RoadID, (EditRoad, EditRoad)>,
+    intersections: BTreeMap<IntersectionID, (EditIntersection, EditIntersection)>,
+    routes: BTreeMap<BusRouteID, (Vec<Time>, Vec<Time>)>,
+}
+
+impl Changes {
+    fn new(commands: &Vec<EditCmd>) -> Changes {
+        let mut changes = Changes {
+            roads: BTreeMap::new(),
+            intersections: BTreeMap::new(),
+            routes: BTreeMap::new(),
+        };
+        for cmd in commands {
+            match cmd {
+                EditCmd::ChangeRoad { r, old, new } => {
+                    changes
+                        .roads
+                        .entry(*r)
+                        .or_insert_with(|| (old.clone(), new.clone()))
+                        .1 = new.clone();
+                }
+                EditCmd::ChangeIntersection { i, old, new } => {
+                    changes
+                        .intersections
+                        .entry(*i)
+                        .or_insert_with(|| (old.clone(), new.clone()))
+                        .1 = new.clone();
+                }
+                EditCmd::ChangeRouteSchedule { id, old, new } => {
+                    changes
+                        .routes
+                        .entry(*id)
+                        .or_insert_with(|| (old.clone(), new.clone()))
+                        .1 = new.clone();
+                }
+            }
+        }
+        changes
+    }
+}
+
+/// Both branches start from the same map, so something only changed in one branch still has its
+/// original state in the other.
+fn differences<'a, K: Copy + Ord, V: PartialEq>(
+    changes1: &'a BTreeMap<K, (V, V)>,
+    changes2: &'a BTreeMap<K, (V, V)>,
+) -> Vec<(K, &'a V, &'a V)> {
+    let keys: BTreeSet<K> = changes1.keys().chain(changes2.keys()).cloned().collect();
+    let mut result = Vec::new();
+    for key in keys {
+        let (value1, value2) = match (changes1.get(&key), changes2.get(&key)) {
+            (Some((_, value1)), Some((_, value2))) => (value1, value2),
+            (Some((_, value1)), None) => (value1, &changes1[&key].0),
+            (None, Some((_, value2))) => (&changes2[&key].0, value2),
+            (None, None) => unreachable!(),
+        };
+        if value1 != value2 {
+            result.push((key, value1, value2));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(id: usize, old: usize, new: usize) -> EditCmd {
+        EditCmd::ChangeRouteSchedule {
+            id: BusRouteID(id),
+            old: vec![Time::START_OF_DAY; old],
+            new: vec![Time::START_OF_DAY; new],
+        }
+    }
+
+    #[test]
+    fn test_history() {
+        let mut edits = MapEdits::new();
+        edits.commands = vec![schedule(0, 1, 2), schedule(1, 1, 2)];
+        edits.add_checkpoint("first".to_string());
+        edits.commands.push(schedule(0, 2, 3));
+
+        // Undo, then redo
+        let prev = edits.clone();
+        assert_eq!(edits.undo(), Some(schedule(0, 2, 3)));
+        edits.forget_stale_history(&prev);
+        assert_eq!(edits.redo_stack.len(), 1);
+        edits.restore_checkpoint("first").unwrap();
+        assert_eq!(edits.commands.len(), 2);
+
+        // Branch from the checkpoint, then compare
+        edits.redo();
+        edits
+            .create_branch("option B".to_string(), Some("first"))
+            .unwrap();
+        assert_eq!(edits.all_branches(), vec!["main", "option B"]);
+        assert_eq!(edits.commands.len(), 2);
+        edits.commands[1] = schedule(1, 1, 5);
+        let diff = edits.diff_branches(MAIN_BRANCH, "option B").unwrap();
+        assert_eq!(diff.routes, vec![BusRouteID(0), BusRouteID(1)]);
+
+        // Editing after an undo loses the redo stack and later checkpoints
+        edits.switch_branch(MAIN_BRANCH).unwrap();
+        edits.undo();
+        edits.undo();
+        let prev = edits.clone();
+        edits.commands.push(schedule(1, 1, 3));
+        edits.forget_stale_history(&prev);
+        assert!(edits.redo_stack.is_empty());
+        assert!(edits.checkpoints.is_empty());
+    }
+}
@@ -10,15 +10,17 @@ use serde::{Deserialize, Serialize};
 use abstutil::{retain_btreemap, retain_btreeset, Timer};
 use geom::{Speed, Time};
 
+pub use self::history::{BranchDiff, Checkpoint, EditBranch, MAIN_BRANCH};
 pub use self::perma::PermanentMapEdits;
-use crate::make::initial::lane_specs::get_lane_specs_ltr;
+use crate::make::initial::lane_specs::{get_lane_specs_ltr, get_lane_turns_ltr};
 use crate::{
     connectivity, AccessRestrictions, BusRouteID, ControlStopSign, ControlTrafficSignal, Direction,
     IntersectionID, IntersectionType, LaneID, LaneType, Map, MapConfig, PathConstraints,
-    Pathfinder, Road, RoadID, TurnID, Zone,
+    Pathfinder, Road, RoadID, TurnID, TurnType, Zone,
 };
 
 mod compat;
+mod history;
 mod perma;
 
 /// Represents changes to a map. Note this isn't serializable -- that's what `PermanentMapEdits`
@@ -29,6 +31,14 @@ pub struct MapEdits {
     /// A stack, oldest edit is first. The same intersection may be edited multiple times in this
     /// stack, until compress() happens.
     pub commands: Vec<EditCmd>,
+    /// Commands that were undone, with the most recently undone last. Any other edit clears this.
+    pub redo_stack: Vec<EditCmd>,
+    /// Named points in `commands`
+    pub checkpoints: Vec<Checkpoint>,
+    /// The name of the branch described by the fields above, which is applied to the map
+    pub branch: String,
+    /// Alternatives to the current branch, kept in the same proposal
+    pub other_branches: BTreeMap<String, EditBranch>,
     /// If false, adjacent roads with the same AccessRestrictions will not be merged into the same
     /// Zone; every Road will be its own Zone. This is used to experiment with a per-road cap. Note
     /// this is a map-wide setting.
@@ -57,17 +67,21 @@ pub enum EditIntersection {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EditRoad {
     pub lanes_ltr: Vec<(LaneType, Direction)>,
+    /// Turn arrows for each lane, matching up with lanes_ltr. See `Lane::allowed_turns`.
+    pub allowed_turns: Vec<Option<BTreeSet<TurnType>>>,
     pub speed_limit: Speed,
     pub access_restrictions: AccessRestrictions,
 }
 
 impl EditRoad {
     pub fn get_orig_from_osm(r: &Road, cfg: &MapConfig) -> EditRoad {
+        let lanes_ltr: Vec<(LaneType, Direction)> = get_lane_specs_ltr(&r.osm_tags, cfg)
+            .into_iter()
+            .map(|spec| (spec.lt, spec.dir))
+            .collect();
         EditRoad {
-            lanes_ltr: get_lane_specs_ltr(&r.osm_tags, cfg)
-                .into_iter()
-                .map(|spec| (spec.lt, spec.dir))
-                .collect(),
+            allowed_turns: get_lane_turns_ltr(&r.osm_tags, &lanes_ltr),
+            lanes_ltr,
             speed_limit: r.speed_limit_from_osm(),
             access_restrictions: r.access_restrictions_from_osm(),
         }
@@ -84,6 +98,12 @@ impl EditRoad {
                 dir += 1;
             }
         }
+        let turns = self
+            .allowed_turns
+            .iter()
+            .zip(other.allowed_turns.iter())
+            .filter(|(x, y)| x != y)
+            .count();
 
         let mut changes = Vec::new();
         if lt == 1 {
@@ -96,6 +116,11 @@ impl EditRoad {
         } else if dir > 1 {
             changes.push(format!("{} lane reversal", dir));
         }
+        if turns == 1 {
+            changes.push(format!("1 lane's turn arrows"));
+        } else if turns > 1 {
+            changes.push(format!("{} lanes' turn arrows", turns));
+        }
         if self.speed_limit != other.speed_limit {
             changes.push(format!("speed limit"));
         }
@@ -139,6 +164,10 @@ impl MapEdits {
             proposal_description: Vec::new(),
             proposal_link: None,
             commands: Vec::new(),
+            redo_stack: Vec::new(),
+            checkpoints: Vec::new(),
+            branch: MAIN_BRANCH.to_string(),
+            other_branches: BTreeMap::new(),
             merge_zones: true,
 
             changed_roads: BTreeSet::new(),
@@ -163,7 +192,10 @@ impl MapEdits {
 
     fn save(&self, map: &Map) {
         // If untitled and empty, don't actually save anything.
-        if self.edits_name.starts_with("Untitled Proposal") && self.commands.is_empty() {
+        if self.edits_name.starts_with("Untitled Proposal")
+            && self.commands.is_empty()
+            && self.other_branches.is_empty()
+        {
             return;
         }
 
@@ -246,9 +278,16 @@ impl MapEdits {
                 roads.insert(r.id);
             } else {
                 let lanes_ltr = r.lanes_ltr();
-                for (idx, (lt, dir)) in orig.lanes_ltr.into_iter().enumerate() {
-                    if lanes_ltr[idx].1 != dir || lanes_ltr[idx].2 != lt {
-                        lanes.insert(lanes_ltr[idx].0);
+                for (idx, ((lt, dir), allowed_turns)) in orig
+                    .lanes_ltr
+                    .into_iter()
+                    .zip(orig.allowed_turns)
+                    .enumerate()
+                {
+                    let (l, cur_dir, cur_lt) = lanes_ltr[idx];
+                    if cur_dir != dir || cur_lt != lt || map.get_l(l).allowed_turns != allowed_turns
+                    {
+                        lanes.insert(l);
                     }
                 }
             }
@@ -308,10 +347,12 @@ impl EditCmd {
                 road.speed_limit = new.speed_limit;
                 road.access_restrictions = new.access_restrictions.clone();
                 assert_eq!(road.lanes_ltr.len(), new.lanes_ltr.len());
+                assert_eq!(road.lanes_ltr.len(), new.allowed_turns.len());
                 for (idx, (lt, dir)) in new.lanes_ltr.clone().into_iter().enumerate() {
                     let lane = &mut map.lanes[(road.lanes_ltr[idx].0).0];
                     road.lanes_ltr[idx].2 = lt;
                     lane.lane_type = lt;
+                    lane.allowed_turns = new.allowed_turns[idx].clone();
 
                     // Direction change?
                     if road.lanes_ltr[idx].1 != dir {
@@ -474,7 +515,8 @@ impl Map {
     }
 
     pub fn unsaved_edits(&self) -> bool {
-        self.edits.edits_name.starts_with("Untitled Proposal") && !self.edits.commands.is_empty()
+        self.edits.edits_name.starts_with("Untitled Proposal")
+            && (!self.edits.commands.is_empty() || !self.edits.other_branches.is_empty())
     }
 
     pub fn get_r_edit(&self, r: RoadID) -> EditRoad {
@@ -485,6 +527,11 @@ impl Map {
                 .into_iter()
                 .map(|(_, dir, lt)| (lt, dir))
                 .collect(),
+            allowed_turns: r
+                .all_lanes()
+                .into_iter()
+                .map(|l| self.get_l(l).allowed_turns.clone())
+                .collect(),
             speed_limit: r.speed_limit,
             access_restrictions: r.access_restrictions.clone(),
         }
@@ -513,8 +560,12 @@ impl Map {
         // Don't overwrite the current edits with the compressed first. Otherwise, undo/redo order
         // in the UI gets messed up.
         let mut edits = self.edits.clone();
-        edits.commands.clear();
-        edits.compress(self);
+        // Checkpoints refer to positions in the full history, so keep it all if there are any
+        if edits.checkpoints.is_empty() {
+            edits.commands.clear();
+            edits.redo_stack.clear();
+            edits.compress(self);
+        }
         edits.save(self);
     }
 
@@ -557,6 +608,8 @@ impl Map {
             );
         }
 
+        new_edits.forget_stale_history(&self.edits);
+
         let mut effects = EditEffects::new();
 
         // We need to undo() all of the current commands in reverse order, then apply() all of the
@@ -613,6 +666,8 @@ impl Map {
         new_edits.update_derived(self);
         self.edits = new_edits;
         self.pathfinder_dirty = true;
+        // Lanes, turns, and access restrictions may have changed
+        self.time_window_pathfinder.clear();
 
         // Update zones after setting the new edits, since it'll pull merge_zones from there
         if !effects.changed_roads.is_empty() || merge_zones_changed {
@@ -7,9 +7,9 @@ use abstio::MapName;
 use abstutil::{deserialize_btreemap, serialize_btreemap};
 use geom::Time;
 
-use crate::edits::{EditCmd, EditIntersection, EditRoad, MapEdits};
+use crate::edits::{Checkpoint, EditBranch, EditCmd, EditIntersection, EditRoad, MapEdits};
 use crate::raw::OriginalRoad;
-use crate::{osm, ControlStopSign, IntersectionID, Map};
+use crate::{osm, ControlStopSign, IntersectionID, Map, MAIN_BRANCH};
 
 /// MapEdits are converted to this before serializing. Referencing things like LaneID in a Map won't
 /// work if the basemap is rebuilt from new OSM data, so instead we use stabler OSM IDs that're less
@@ -20,6 +20,15 @@ pub struct PermanentMapEdits {
     pub edits_name: String,
     pub version: usize,
     commands: Vec<PermanentEditCmd>,
+    // Most edits don't use the history, so leave it out of the file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    redo_stack: Vec<PermanentEditCmd>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    checkpoints: Vec<Checkpoint>,
+    #[serde(default = "main_branch", skip_serializing_if = "is_main_branch")]
+    branch: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    other_branches: BTreeMap<String, PermanentEditBranch>,
     /// If false, adjacent roads with the same AccessRestrictions will not be merged into the same
     /// Zone; every Road will be its own Zone. This is used to experiment with a per-road cap. Note
     /// this is a map-wide setting.
@@ -31,6 +40,24 @@ pub struct PermanentMapEdits {
     pub proposal_link: Option<String>,
 }
 
+/// The same as `EditBranch`, but with stable IDs.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PermanentEditBranch {
+    commands: Vec<PermanentEditCmd>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    redo_stack: Vec<PermanentEditCmd>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    checkpoints: Vec<Checkpoint>,
+}
+
+fn main_branch() -> String {
+    MAIN_BRANCH.to_string()
+}
+
+fn is_main_branch(branch: &str) -> bool {
+    branch == MAIN_BRANCH
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub enum PermanentEditIntersection {
     StopSign {
@@ -103,6 +130,9 @@ impl PermanentEditCmd {
                         new.lanes_ltr.len()
                     );
                 }
+                if new.allowed_turns.len() != new.lanes_ltr.len() {
+                    bail!("turn arrows in {} don't match the lanes", r);
+                }
                 Ok(EditCmd::ChangeRoad { r: id, new, old })
             }
             PermanentEditCmd::ChangeIntersection { i, new, old } => {
@@ -138,50 +168,105 @@ impl MapEdits {
             map_name: map.get_name().clone(),
             edits_name: self.edits_name.clone(),
             // Increase this every time there's a schema change
-            version: 7,
+            version: 10,
             proposal_description: self.proposal_description.clone(),
             proposal_link: self.proposal_link.clone(),
             commands: self.commands.iter().map(|cmd| cmd.to_perma(map)).collect(),
+            redo_stack: self
+                .redo_stack
+                .iter()
+                .map(|cmd| cmd.to_perma(map))
+                .collect(),
+            checkpoints: self.checkpoints.clone(),
+            branch: self.branch.clone(),
+            other_branches: self
+                .other_branches
+                .iter()
+                .map(|(name, branch)| (name.clone(), branch.to_permanent(map)))
+                .collect(),
             merge_zones: self.merge_zones,
         }
     }
 }
 
+impl EditBranch {
+    fn to_permanent(&self, map: &Map) -> PermanentEditBranch {
+        PermanentEditBranch {
+            commands: self.commands.iter().map(|cmd| cmd.to_perma(map)).collect(),
+            redo_stack: self
+                .redo_stack
+                .iter()
+                .map(|cmd| cmd.to_perma(map))
+                .collect(),
+            checkpoints: self.checkpoints.clone(),
+        }
+    }
+}
+
+impl PermanentEditBranch {
+    /// If `permissive`, strip out commands that're broken, instead of failing.
+    fn to_branch(self, map: &Map, permissive: bool) -> Result<EditBranch> {
+        let num_commands = self.commands.len() + self.redo_stack.len();
+        let convert = |cmds: Vec<PermanentEditCmd>| -> Result<Vec<EditCmd>> {
+            if permissive {
+                Ok(cmds
+                    .into_iter()
+                    .filter_map(|cmd| cmd.to_cmd(map).ok())
+                    .collect())
+            } else {
+                cmds.into_iter().map(|cmd| cmd.to_cmd(map)).collect()
+            }
+        };
+        let commands = convert(self.commands)?;
+        let redo_stack = convert(self.redo_stack)?;
+        // If any commands were dropped, the checkpoints don't point to the right places anymore
+        let checkpoints = if commands.len() + redo_stack.len() == num_commands {
+            self.checkpoints
+        } else {
+            Vec::new()
+        };
+        Ok(EditBranch {
+            commands,
+            redo_stack,
+            checkpoints,
+        })
+    }
+}
+
 impl PermanentMapEdits {
     /// Transform permanent edits to MapEdits, looking up the map IDs by the hopefully stabler OSM
     /// IDs. Validate that the basemap hasn't changed in important ways.
     pub fn to_edits(self, map: &Map) -> Result<MapEdits> {
-        let mut edits = MapEdits {
-            edits_name: self.edits_name,
-            proposal_description: self.proposal_description,
-            proposal_link: self.proposal_link,
-            commands: self
-                .commands
-                .into_iter()
-                .map(|cmd| cmd.to_cmd(map))
-                .collect::<Result<Vec<EditCmd>>>()?,
-            merge_zones: self.merge_zones,
-
-            changed_roads: BTreeSet::new(),
-            original_intersections: BTreeMap::new(),
-            changed_routes: BTreeSet::new(),
-        };
-        edits.update_derived(map);
-        Ok(edits)
+        self.convert(map, false)
     }
 
     /// Transform permanent edits to MapEdits, looking up the map IDs by the hopefully stabler OSM
     /// IDs. Strip out commands that're broken.
     pub fn to_edits_permissive(self, map: &Map) -> MapEdits {
+        self.convert(map, true).unwrap()
+    }
+
+    fn convert(self, map: &Map, permissive: bool) -> Result<MapEdits> {
+        let current = PermanentEditBranch {
+            commands: self.commands,
+            redo_stack: self.redo_stack,
+            checkpoints: self.checkpoints,
+        }
+        .to_branch(map, permissive)?;
+        let mut other_branches = BTreeMap::new();
+        for (name, branch) in self.other_branches {
+            other_branches.insert(name, branch.to_branch(map, permissive)?);
+        }
+
         let mut edits = MapEdits {
             edits_name: self.edits_name,
             proposal_description: self.proposal_description,
             proposal_link: self.proposal_link,
-            commands: self
-                .commands
-                .into_iter()
-                .filter_map(|cmd| cmd.to_cmd(map).ok())
-                .collect(),
+            commands: current.commands,
+            redo_stack: current.redo_stack,
+            checkpoints: current.checkpoints,
+            branch: self.branch,
+            other_branches,
             merge_zones: self.merge_zones,
 
             changed_roads: BTreeSet::new(),
@@ -189,7 +274,7 @@ impl PermanentMapEdits {
             changed_routes: BTreeSet::new(),
         };
         edits.update_derived(map);
-        edits
+        Ok(edits)
     }
 }
 
@@ -244,3 +329,22 @@ impl PermanentEditIntersection {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_is_optional() {
+        let map = Map::blank();
+        let json = abstutil::to_json(&MapEdits::new().to_permanent(&map));
+        assert!(!json.contains("redo_stack"));
+        assert!(!json.contains("checkpoints"));
+        assert!(!json.contains("branch"));
+
+        let perma: PermanentMapEdits = abstutil::from_json(&json.into_bytes()).unwrap();
+        let edits = perma.to_edits(&map).unwrap();
+        assert_eq!(edits.branch, MAIN_BRANCH);
+        assert!(edits.other_branches.is_empty());
+    }
+}
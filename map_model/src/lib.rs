@@ -26,9 +26,11 @@ extern crate anyhow;
 #[macro_use]
 extern crate log;
 
+use std::cell::RefCell;
 use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
+use thread_local::ThreadLocal;
 
 use abstio::MapName;
 use abstutil::{deserialize_btreemap, serialize_btreemap};
@@ -36,7 +38,8 @@ use geom::{Bounds, Distance, GPSBounds, Polygon};
 
 pub use crate::city::City;
 pub use crate::edits::{
-    EditCmd, EditEffects, EditIntersection, EditRoad, MapEdits, PermanentMapEdits,
+    BranchDiff, Checkpoint, EditBranch, EditCmd, EditEffects, EditIntersection, EditRoad, MapEdits,
+    PermanentMapEdits, MAIN_BRANCH,
 };
 pub use crate::map::{DrivingSide, MapConfig};
 pub use crate::objects::area::{Area, AreaID, AreaType};
@@ -55,10 +58,10 @@ pub use crate::objects::traffic_signals::{ControlTrafficSignal, Stage, StageType
 pub use crate::objects::turn::{
     CompressedMovementID, Movement, MovementID, Turn, TurnID, TurnPriority, TurnType,
 };
-pub use crate::objects::zone::{AccessRestrictions, Zone};
+pub use crate::objects::zone::{AccessRestrictions, TimeWindow, Zone};
 pub use crate::pathfind::uber_turns::{IntersectionCluster, UberTurn, UberTurnGroup};
-use crate::pathfind::Pathfinder;
 pub use crate::pathfind::{Path, PathConstraints, PathRequest, PathStep};
+use crate::pathfind::{Pathfinder, TimeWindowPathfinder};
 pub use crate::traversable::{Position, Traversable};
 
 mod city;
@@ -113,6 +116,8 @@ pub struct Map {
     pathfinder_dirty: bool,
     // Not the source of truth, just cached.
     zones: Vec<Zone>,
+    #[serde(skip_serializing, skip_deserializing)]
+    time_window_pathfinder: ThreadLocal<RefCell<TimeWindowPathfinder>>,
 
     name: MapName,
     #[serde(skip_serializing, skip_deserializing)]
@@ -1,11 +1,12 @@
 /// Purely from OSM tags, determine the lanes that a road segment has.
+use std::collections::BTreeSet;
 use std::iter;
 
 use abstutil::Tags;
 use geom::Distance;
 
 use crate::{
-    osm, Direction, DrivingSide, LaneType, MapConfig, NORMAL_LANE_THICKNESS,
+    osm, Direction, DrivingSide, LaneType, MapConfig, TimeWindow, TurnType, NORMAL_LANE_THICKNESS,
     SERVICE_ROAD_LANE_THICKNESS, SHOULDER_THICKNESS, SIDEWALK_THICKNESS,
 };
 
@@ -350,6 +351,200 @@ fn assemble_ltr(
     }
 }
 
+/// Determine the turn arrows painted on each lane from turn:lanes tags. The result matches up
+/// with `lanes_ltr`. None means there are no arrows, so any turn is allowed.
+pub fn get_lane_turns_ltr(
+    tags: &Tags,
+    lanes_ltr: &[(LaneType, Direction)],
+) -> Vec<Option<BTreeSet<TurnType>>> {
+    let mut result = vec![None; lanes_ltr.len()];
+    for dir in vec![Direction::Fwd, Direction::Back] {
+        // The tags describe the end of the original OSM way, so only the last piece of a split way
+        // has turn lanes.
+        let all = if dir == Direction::Fwd && tags.contains_key(osm::ENDPT_FWD) {
+            tags.get("turn:lanes:forward")
+                .or_else(|| tags.get("turn:lanes"))
+        } else if dir == Direction::Back && tags.contains_key(osm::ENDPT_BACK) {
+            tags.get("turn:lanes:backward")
+        } else {
+            None
+        };
+        let all = if let Some(all) = all {
+            all
+        } else {
+            continue;
+        };
+
+        // OSM lists lanes from the left in the direction of travel.
+        let mut indices: Vec<usize> = lanes_ltr
+            .iter()
+            .enumerate()
+            .filter(|(_, (lt, d))| *d == dir && (*lt == LaneType::Driving || *lt == LaneType::Bus))
+            .map(|(idx, _)| idx)
+            .collect();
+        if dir == Direction::Back {
+            indices.reverse();
+        }
+        let parts: Vec<&str> = all.split('|').collect();
+        if parts.len() != indices.len() {
+            warn!(
+                "turn:lanes on way {:?} don't match the lanes",
+                tags.get(osm::OSM_WAY_ID)
+            );
+            continue;
+        }
+        for (idx, part) in indices.into_iter().zip(parts) {
+            result[idx] = parse_turn_arrows(part);
+        }
+    }
+    result
+}
+
+fn parse_turn_arrows(part: &str) -> Option<BTreeSet<TurnType>> {
+    // TODO Probably the target lane should get marked as LaneType::Bus
+    if part == "no" || part == "none" || part == "yes" || part == "psv" || part == "bus" {
+        return None;
+    }
+    // Empty means no restrictions
+    if part.is_empty() {
+        return None;
+    }
+    let types: BTreeSet<TurnType> = part
+        .split(';')
+        .flat_map(|s| match s {
+            "left" | "left\\left" => vec![TurnType::Left],
+            "right" => vec![TurnType::Right],
+            // TODO What is blank supposed to mean? From few observed cases, same as through
+            "through" | "" => vec![TurnType::Straight],
+            // TODO Check this more carefully
+            "slight_right" | "slight right" | "merge_to_right" | "sharp_right" => {
+                vec![TurnType::Straight, TurnType::Right]
+            }
+            "slight_left" | "slight left" | "merge_to_left" | "sharp_left" => {
+                vec![TurnType::Straight, TurnType::Left]
+            }
+            // Geometrically, u-turns are often classified as left turns.
+            "reverse" => vec![TurnType::UTurn, TurnType::Left],
+            s => {
+                warn!("Unknown turn restriction {}", s);
+                vec![]
+            }
+        })
+        .collect();
+    if types.is_empty() {
+        None
+    } else {
+        Some(types)
+    }
+}
+
+/// Some lanes change type during part of the day. Peak-hour bus lanes come from
+/// `bus:lanes:conditional` (and the `psv`, `:forward`, `:backward` variants), and parking lanes
+/// that're cleared for traffic come from `parking:lane:{left,right,both}:conditional`.
+pub fn get_lane_type_windows_ltr(
+    tags: &Tags,
+    lanes_ltr: &[(LaneType, Direction)],
+) -> Vec<Vec<(TimeWindow, LaneType)>> {
+    let mut result = vec![Vec::new(); lanes_ltr.len()];
+
+    for dir in vec![Direction::Fwd, Direction::Back] {
+        let keys = if dir == Direction::Fwd {
+            vec![
+                "bus:lanes:forward:conditional",
+                "psv:lanes:forward:conditional",
+                "bus:lanes:conditional",
+                "psv:lanes:conditional",
+            ]
+        } else {
+            vec![
+                "bus:lanes:backward:conditional",
+                "psv:lanes:backward:conditional",
+            ]
+        };
+        let all = if let Some(all) = keys.into_iter().find_map(|k| tags.get(k)) {
+            all
+        } else {
+            continue;
+        };
+
+        // OSM lists lanes from the left in the direction of travel.
+        let mut indices: Vec<usize> = lanes_ltr
+            .iter()
+            .enumerate()
+            .filter(|(_, (lt, d))| *d == dir && (*lt == LaneType::Driving || *lt == LaneType::Bus))
+            .map(|(idx, _)| idx)
+            .collect();
+        if dir == Direction::Back {
+            indices.reverse();
+        }
+        let parts: Vec<&str> = all.split('|').collect();
+        if parts.len() != indices.len() {
+            warn!(
+                "bus:lanes:conditional on way {:?} don't match the lanes",
+                tags.get(osm::OSM_WAY_ID)
+            );
+            continue;
+        }
+        for (idx, part) in indices.into_iter().zip(parts) {
+            if lanes_ltr[idx].0 != LaneType::Driving {
+                continue;
+            }
+            for (value, windows) in parse_conditional(part) {
+                if value == "designated" || value == "yes" {
+                    for window in windows {
+                        result[idx].push((window, LaneType::Bus));
+                    }
+                }
+            }
+        }
+    }
+
+    let parking: Vec<usize> = lanes_ltr
+        .iter()
+        .enumerate()
+        .filter(|(_, (lt, _))| *lt == LaneType::Parking)
+        .map(|(idx, _)| idx)
+        .collect();
+    if let (Some(first), Some(last)) = (parking.first(), parking.last()) {
+        for (side, indices) in vec![
+            ("left", vec![*first]),
+            ("right", vec![*last]),
+            ("both", vec![*first, *last]),
+        ] {
+            if let Some(value) = tags.get(&format!("parking:lane:{}:conditional", side)) {
+                for (value, windows) in parse_conditional(value) {
+                    if value == "no_parking" || value == "no_stopping" || value == "no_standing" {
+                        for idx in &indices {
+                            for window in &windows {
+                                if !result[*idx].contains(&(*window, LaneType::Driving)) {
+                                    result[*idx].push((*window, LaneType::Driving));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// Splits "value @ (cond); value2 @ (cond2)" into each value and the time windows from its
+/// condition.
+fn parse_conditional(value: &str) -> Vec<(&str, Vec<TimeWindow>)> {
+    value
+        .split(';')
+        .filter_map(|rule| {
+            let parts: Vec<&str> = rule.split('@').collect();
+            if parts.len() != 2 {
+                return None;
+            }
+            Some((parts[0].trim(), TimeWindow::parse_osm(parts[1])))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -522,4 +717,44 @@ mod tests {
         }
         assert!(ok);
     }
+
+    #[test]
+    fn test_turn_lanes() {
+        let mut input = tags(vec![
+            "lanes=4",
+            "lanes:forward=3",
+            "turn:lanes:forward=left|through|through;right",
+        ]);
+        input.insert(osm::ENDPT_FWD, "true");
+        let lanes_ltr = vec![
+            (LaneType::Sidewalk, Direction::Back),
+            (LaneType::Driving, Direction::Back),
+            (LaneType::Driving, Direction::Fwd),
+            (LaneType::Driving, Direction::Fwd),
+            (LaneType::Driving, Direction::Fwd),
+            (LaneType::Sidewalk, Direction::Fwd),
+        ];
+        let actual = get_lane_turns_ltr(&input, &lanes_ltr);
+        assert_eq!(
+            actual,
+            vec![
+                None,
+                None,
+                Some(vec![TurnType::Left].into_iter().collect()),
+                Some(vec![TurnType::Straight].into_iter().collect()),
+                Some(
+                    vec![TurnType::Straight, TurnType::Right]
+                        .into_iter()
+                        .collect()
+                ),
+                None,
+            ]
+        );
+
+        // Without the endpoint marker, this is the middle of a split way.
+        input.remove(osm::ENDPT_FWD);
+        assert!(get_lane_turns_ltr(&input, &lanes_ltr)
+            .into_iter()
+            .all(|x| x.is_none()));
+    }
 }
@@ -3,16 +3,19 @@
 
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
+use thread_local::ThreadLocal;
+
 use abstio::MapName;
 use abstutil::{Parallelism, Tags, Timer};
 use geom::{Bounds, Distance, FindClosest, GPSBounds, HashablePt2D, Speed, EPSILON_DIST};
 
+use crate::make::initial::lane_specs::{get_lane_turns_ltr, get_lane_type_windows_ltr};
 use crate::pathfind::Pathfinder;
 use crate::raw::{OriginalRoad, RawMap};
 use crate::{
     connectivity, osm, AccessRestrictions, Area, AreaID, AreaType, ControlStopSign,
     ControlTrafficSignal, Direction, Intersection, IntersectionID, IntersectionType, Lane, LaneID,
-    Map, MapEdits, Movement, PathConstraints, Position, Road, RoadID, Turn, Zone,
+    LaneType, Map, MapEdits, Movement, PathConstraints, Position, Road, RoadID, Turn, Zone,
 };
 
 mod bridges;
@@ -64,6 +67,7 @@ impl Map {
             config: raw.config.clone(),
             pathfinder: Pathfinder::Dijkstra,
             pathfinder_dirty: false,
+            time_window_pathfinder: ThreadLocal::new(),
             name: raw.name.clone(),
             edits: MapEdits::new(),
         };
@@ -130,6 +134,13 @@ impl Map {
                         }
                     })
                     .collect(),
+                lane_connectivity: raw.roads[&r.id]
+                    .lane_connectivity
+                    .iter()
+                    .filter_map(|(to, lanes)| {
+                        road_id_mapping.get(to).map(|to| (*to, lanes.clone()))
+                    })
+                    .collect(),
                 orig_id: r.id,
                 lanes_ltr: Vec::new(),
                 center_pts: r.trimmed_center_pts.clone(),
@@ -166,8 +177,21 @@ impl Map {
                 .shift_left(r.half_width)
                 .unwrap_or_else(|_| road.center_pts.clone());
 
+            let lanes_ltr: Vec<(LaneType, Direction)> = r
+                .lane_specs_ltr
+                .iter()
+                .map(|spec| (spec.lt, spec.dir))
+                .collect();
+            let allowed_turns = get_lane_turns_ltr(&road.osm_tags, &lanes_ltr);
+            let lane_type_windows = get_lane_type_windows_ltr(&road.osm_tags, &lanes_ltr);
+
             let mut width_so_far = Distance::ZERO;
-            for lane in &r.lane_specs_ltr {
+            for ((lane, allowed_turns), lane_type_windows) in r
+                .lane_specs_ltr
+                .iter()
+                .zip(allowed_turns)
+                .zip(lane_type_windows)
+            {
                 let id = LaneID(map.lanes.len());
 
                 let (src_i, dst_i) = if lane.dir == Direction::Fwd {
@@ -203,6 +227,8 @@ impl Map {
                     lane_type: lane.lt,
                     parent: road_id,
                     bus_stops: BTreeSet::new(),
+                    allowed_turns,
+                    lane_type_windows,
                     driving_blackhole: false,
                     biking_blackhole: false,
                 });
@@ -141,14 +141,33 @@ fn ensure_unique(turns: Vec<Turn>) -> Vec<Turn> {
 }
 
 fn is_turn_allowed(turn: &Turn, map: &Map) -> bool {
-    if let Some(types) = map
-        .get_l(turn.id.src)
-        .get_turn_restrictions(map.get_parent(turn.id.src))
-    {
-        types.contains(&turn.turn_type)
-    } else {
-        true
+    if let Some(types) = map.get_l(turn.id.src).get_turn_restrictions() {
+        if !types.contains(&turn.turn_type) {
+            return false;
+        }
+    }
+    passes_lane_connectivity(turn, map)
+}
+
+/// If an OSM connectivity relation describes the movement between two roads, only the listed
+/// lane-to-lane turns are allowed.
+fn passes_lane_connectivity(turn: &Turn, map: &Map) -> bool {
+    let src = map.get_parent(turn.id.src);
+    let dst = map.get_parent(turn.id.dst);
+    for (to, lanes) in &src.lane_connectivity {
+        if *to != dst.id {
+            continue;
+        }
+        return match (
+            src.motor_lane_idx(turn.id.src),
+            dst.motor_lane_idx(turn.id.dst),
+        ) {
+            (Some(from_idx), Some(to_idx)) => lanes.contains(&(from_idx, to_idx)),
+            // Bike lanes and such aren't described by the relation
+            _ => true,
+        };
     }
+    true
 }
 
 fn does_turn_pass_restrictions(turn: &Turn, i: &Intersection, map: &Map) -> bool {
@@ -188,12 +207,12 @@ fn make_vehicle_turns(i: &Intersection, map: &Map) -> Vec<Turn> {
     let is_deadend = i.roads.len() == 1;
     for src in &i.incoming_lanes {
         let src = map.get_l(*src);
-        if !src.lane_type.is_for_moving_vehicles() {
+        if !src.is_ever_for_moving_vehicles() {
             continue;
         }
         for dst in &i.outgoing_lanes {
             let dst = map.get_l(*dst);
-            if !dst.lane_type.is_for_moving_vehicles() {
+            if !dst.is_ever_for_moving_vehicles() {
                 continue;
             }
             // Only allow U-turns at deadends
@@ -343,10 +362,20 @@ fn remove_merging_turns(map: &Map, input: Vec<Turn>, turn_type: TurnType) -> Vec
             continue;
         }
 
+        // If turn arrows explicitly mark multiple source lanes, like "left|left|through", then
+        // trust them.
+        if group.iter().all(|t| {
+            map.get_l(t.id.src)
+                .get_turn_restrictions()
+                .map(|types| types.contains(&turn_type))
+                .unwrap_or(false)
+        }) {
+            turns.extend(group);
+            continue;
+        }
+
         // We have multiple lanes all with a turn to the same destination road. Most likely, only
         // the rightmost or leftmost can actually make the turn.
-        // TODO If OSM turn restrictions explicitly have something like "left|left|", then there
-        // are multiple source lanes!
         let road = map.get_parent(group[0].id.src);
         let src = if turn_type == TurnType::Right {
             group
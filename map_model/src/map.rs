@@ -1,15 +1,18 @@
 //! A bunch of (mostly read-only) queries on a Map.
 
+use std::cell::RefCell;
 use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
 
 use anyhow::Result;
 use petgraph::graphmap::UnGraphMap;
 use serde::{Deserialize, Serialize};
+use thread_local::ThreadLocal;
 
 use abstio::MapName;
 use abstutil::Timer;
 use geom::{Bounds, Distance, GPSBounds, Polygon, Pt2D, Ring, Time};
 
+use crate::pathfind::TimeWindowPathfinder;
 use crate::raw::{OriginalRoad, RawMap};
 use crate::{
     osm, Area, AreaID, Building, BuildingID, BuildingType, BusRoute, BusRouteID, BusStop,
@@ -168,6 +171,7 @@ impl Map {
             },
             pathfinder: Pathfinder::Dijkstra,
             pathfinder_dirty: false,
+            time_window_pathfinder: ThreadLocal::new(),
             name: MapName {
                 city: "blank city".to_string(),
                 map: "blank".to_string(),
@@ -389,6 +393,22 @@ impl Map {
         turns
     }
 
+    /// Like `get_turns_for`, but accounts for lanes that change type at certain times of day. Only
+    /// for vehicles.
+    pub fn get_turns_for_at(
+        &self,
+        from: LaneID,
+        constraints: PathConstraints,
+        now: Time,
+    ) -> Vec<&Turn> {
+        assert_ne!(constraints, PathConstraints::Pedestrian);
+        self.get_next_turns_and_lanes(from, self.get_l(from).dst_i)
+            .into_iter()
+            .filter(|(_, l)| constraints.can_use_at(l, self, now))
+            .map(|(t, _)| t)
+            .collect()
+    }
+
     pub fn get_next_roads(&self, from: RoadID) -> BTreeSet<RoadID> {
         let mut roads: BTreeSet<RoadID> = BTreeSet::new();
         let r = self.get_r(from);
@@ -556,6 +576,35 @@ impl Map {
         assert!(!self.pathfinder_dirty);
         self.pathfinder.pathfind_avoiding_lanes(req, avoid, self)
     }
+    /// Like `pathfind`, but accounts for lanes that change type and roads that ban through-traffic
+    /// at certain times of day. The usual path is tried first; only if it crosses something closed
+    /// right now is a slower path calculated.
+    pub fn pathfind_at(&self, req: PathRequest, now: Time) -> Result<Path> {
+        let path = self.pathfind(req.clone())?;
+        self.reroute_at(path, now)
+            .ok_or_else(|| anyhow!("can't fulfill {} at {}", req, now))
+    }
+    /// Like `pathfind_avoiding_lanes`, but also accounts for time windows.
+    pub fn pathfind_avoiding_lanes_at(
+        &self,
+        req: PathRequest,
+        avoid: BTreeSet<LaneID>,
+        now: Time,
+    ) -> Option<Path> {
+        let path = self.pathfind_avoiding_lanes(req, avoid.clone())?;
+        self.time_window_pathfinder
+            .get_or(|| RefCell::new(TimeWindowPathfinder::default()))
+            .borrow_mut()
+            .pathfind(path, &avoid, now, self)
+    }
+    /// If a path calculated earlier crosses something closed at this time, find a different one.
+    pub fn reroute_at(&self, path: Path, now: Time) -> Option<Path> {
+        assert!(!self.pathfinder_dirty);
+        self.time_window_pathfinder
+            .get_or(|| RefCell::new(TimeWindowPathfinder::default()))
+            .borrow_mut()
+            .pathfind(path, &BTreeSet::new(), now, self)
+    }
 
     pub fn should_use_transit(
         &self,
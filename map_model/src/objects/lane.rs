@@ -4,11 +4,9 @@ use std::fmt;
 use serde::{Deserialize, Serialize};
 
 use abstutil::{deserialize_usize, serialize_usize, wraparound_get};
-use geom::{Distance, Line, PolyLine, Polygon, Pt2D, Ring};
+use geom::{Distance, Line, PolyLine, Polygon, Pt2D, Ring, Time};
 
-use crate::{
-    osm, BusStopID, DirectedRoadID, Direction, IntersectionID, Map, Road, RoadID, TurnType,
-};
+use crate::{BusStopID, DirectedRoadID, IntersectionID, Map, RoadID, TimeWindow, TurnType};
 
 /// Bit longer than the longest car.
 pub const PARKING_SPOT_LENGTH: Distance = Distance::const_meters(8.0);
@@ -118,6 +116,14 @@ pub struct Lane {
 
     /// Meaningless order
     pub bus_stops: BTreeSet<BusStopID>,
+    /// The movements painted as turn arrows on this lane, either from OSM turn:lanes or edits.
+    /// None means any turn that's geometrically possible is allowed. This only applies to driving
+    /// and bus lanes.
+    pub allowed_turns: Option<BTreeSet<TurnType>>,
+    /// During each window, the lane acts as a different type -- like a driving lane reserved for
+    /// buses at peak hour, or a parking lane cleared for traffic. `lane_type` still determines the
+    /// geometry, but turns also exist for parking lanes that're sometimes used for driving.
+    pub lane_type_windows: Vec<(TimeWindow, LaneType)>,
 
     /// {Cars, bikes} trying to start or end here might not be able to reach most lanes in the
     /// graph, because this is near a border.
@@ -221,78 +227,47 @@ impl Lane {
         self.lane_type == LaneType::LightRail
     }
 
-    // TODO Store this natively if this winds up being useful.
-    pub fn get_directed_parent(&self, map: &Map) -> DirectedRoadID {
-        let r = map.get_r(self.parent);
-        DirectedRoadID {
-            id: r.id,
-            dir: r.dir(self.id),
-        }
+    /// True if vehicles move along this lane at any time of day, even if it's usually for parking.
+    pub fn is_ever_for_moving_vehicles(&self) -> bool {
+        self.lane_type.is_for_moving_vehicles()
+            || self
+                .lane_type_windows
+                .iter()
+                .any(|(window, _)| self.lane_type_at(window.start).is_for_moving_vehicles())
     }
 
-    pub fn get_turn_restrictions(&self, road: &Road) -> Option<BTreeSet<TurnType>> {
-        if !self.is_driving() {
-            return None;
+    /// The type of this lane at a certain time, accounting for any `lane_type_windows`.
+    pub fn lane_type_at(&self, now: Time) -> LaneType {
+        // The windows are derived from the lane's original type. If edits have changed it, they
+        // don't apply anymore.
+        for (window, lt) in &self.lane_type_windows {
+            let expected = match lt {
+                LaneType::Bus => LaneType::Driving,
+                LaneType::Driving => LaneType::Parking,
+                _ => continue,
+            };
+            if self.lane_type == expected && window.contains(now) {
+                return *lt;
+            }
         }
+        self.lane_type
+    }
 
-        let dir = road.dir(self.id);
-        let all = if dir == Direction::Fwd && road.osm_tags.contains_key(osm::ENDPT_FWD) {
-            road.osm_tags
-                .get("turn:lanes:forward")
-                .or_else(|| road.osm_tags.get("turn:lanes"))?
-        } else if dir == Direction::Back && road.osm_tags.contains_key(osm::ENDPT_BACK) {
-            road.osm_tags.get("turn:lanes:backward")?
-        } else {
-            return None;
-        };
-        let parts: Vec<&str> = all.split('|').collect();
-        // Verify the number of parts matches the road's lanes
-        let lanes: Vec<LaneID> = road
-            .children(dir)
-            .into_iter()
-            .filter(|(_, lt)| *lt == LaneType::Driving || *lt == LaneType::Bus)
-            .map(|(id, _)| id)
-            .collect();
-        if parts.len() != lanes.len() {
-            warn!("{}'s turn restrictions don't match the lanes", road.orig_id);
-            return None;
-        }
-        // TODO More warnings if this fails
-        let part = parts[lanes.iter().position(|l| *l == self.id)?];
-        // TODO Probably the target lane should get marked as LaneType::Bus
-        if part == "no" || part == "none" || part == "yes" || part == "psv" || part == "bus" {
+    /// The turn arrows that restrict which movements may start from this lane, if any.
+    pub fn get_turn_restrictions(&self) -> Option<&BTreeSet<TurnType>> {
+        if !self.is_driving() && !self.is_bus() {
             return None;
         }
-        // Empty means no restrictions
-        if part == "" {
-            return None;
+        self.allowed_turns.as_ref()
+    }
+
+    // TODO Store this natively if this winds up being useful.
+    pub fn get_directed_parent(&self, map: &Map) -> DirectedRoadID {
+        let r = map.get_r(self.parent);
+        DirectedRoadID {
+            id: r.id,
+            dir: r.dir(self.id),
         }
-        Some(
-            part.split(';')
-                .flat_map(|s| match s {
-                    "left" | "left\\left" => vec![TurnType::Left],
-                    "right" => vec![TurnType::Right],
-                    // TODO What is blank supposed to mean? From few observed cases, same as through
-                    "through" | "" => vec![TurnType::Straight],
-                    // TODO Check this more carefully
-                    "slight_right" | "slight right" | "merge_to_right" | "sharp_right" => {
-                        vec![TurnType::Straight, TurnType::Right]
-                    }
-                    "slight_left" | "slight left" | "merge_to_left" | "sharp_left" => {
-                        vec![TurnType::Straight, TurnType::Left]
-                    }
-                    "reverse" => {
-                        // TODO We need TurnType::UTurn. Until then, u-turns usually show up as
-                        // left turns.
-                        vec![TurnType::Left]
-                    }
-                    s => {
-                        warn!("Unknown turn restriction {}", s);
-                        vec![]
-                    }
-                })
-                .collect(),
-        )
     }
 
     /// Starting from this lane, follow the lane's left edge to the intersection, continuing to
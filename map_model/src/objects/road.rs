@@ -1,3 +1,4 @@
+use std::collections::BTreeSet;
 use std::fmt;
 
 use enumset::EnumSet;
@@ -9,7 +10,7 @@ use geom::{Distance, PolyLine, Polygon, Speed};
 use crate::raw::{OriginalRoad, RestrictionType};
 use crate::{
     osm, AccessRestrictions, BusStopID, IntersectionID, Lane, LaneID, LaneType, Map,
-    PathConstraints, Zone,
+    PathConstraints, TimeWindow, Zone,
 };
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -98,6 +99,10 @@ pub struct Road {
     pub turn_restrictions: Vec<(RestrictionType, RoadID)>,
     /// self is 'from'. (via, to). Only BanTurns.
     pub complicated_turn_restrictions: Vec<(RoadID, RoadID)>,
+    /// self is 'from'. (to, pairs of (from lane, to lane)). Lanes are counted from 0,
+    /// left-to-right in the direction of travel, and only include driving and bus lanes. If there's
+    /// an entry for a destination road, only those lane-to-lane turns are allowed.
+    pub lane_connectivity: Vec<(RoadID, BTreeSet<(usize, usize)>)>,
     pub orig_id: OriginalRoad,
     pub speed_limit: Speed,
     pub access_restrictions: AccessRestrictions,
@@ -146,6 +151,16 @@ impl Road {
         panic!("{} doesn't contain {}", self.id, lane);
     }
 
+    /// Counting only driving and bus lanes from the left in the direction of travel, what's the
+    /// index of this lane? This is how OSM numbers lanes in turn:lanes and connectivity
+    /// relations.
+    pub fn motor_lane_idx(&self, lane: LaneID) -> Option<usize> {
+        self.children(self.dir(lane))
+            .into_iter()
+            .filter(|(_, lt)| *lt == LaneType::Driving || *lt == LaneType::Bus)
+            .position(|(l, _)| l == lane)
+    }
+
     pub fn parking_to_driving(&self, parking: LaneID, map: &Map) -> Option<LaneID> {
         self.find_closest_lane(parking, |l| l.is_driving(), map)
     }
@@ -378,6 +393,7 @@ impl Road {
         AccessRestrictions {
             allow_through_traffic,
             cap_vehicles_per_hour: None,
+            time_windows: conditional_access_from_osm(&self.osm_tags),
         }
     }
 
@@ -402,6 +418,51 @@ impl Road {
     }
 }
 
+/// Interprets tags like `motor_vehicle:conditional = no @ (Mo-Fr 07:00-09:00)`. Only conditions
+/// that are purely time ranges are understood.
+fn conditional_access_from_osm(tags: &Tags) -> Vec<(TimeWindow, EnumSet<PathConstraints>)> {
+    let mut result = Vec::new();
+    for (key, banned) in vec![
+        (
+            "access:conditional",
+            PathConstraints::Pedestrian
+                | PathConstraints::Bike
+                | PathConstraints::Car
+                | PathConstraints::Bus,
+        ),
+        (
+            "vehicle:conditional",
+            PathConstraints::Bike | PathConstraints::Car | PathConstraints::Bus,
+        ),
+        (
+            "motor_vehicle:conditional",
+            PathConstraints::Car | PathConstraints::Bus,
+        ),
+        ("motorcar:conditional", EnumSet::only(PathConstraints::Car)),
+        ("bicycle:conditional", EnumSet::only(PathConstraints::Bike)),
+        (
+            "foot:conditional",
+            EnumSet::only(PathConstraints::Pedestrian),
+        ),
+    ] {
+        if let Some(value) = tags.get(key) {
+            for rule in value.split(';') {
+                let parts: Vec<&str> = rule.split('@').collect();
+                if parts.len() != 2 {
+                    continue;
+                }
+                if !vec!["no", "private", "destination"].contains(&parts[0].trim()) {
+                    continue;
+                }
+                for window in TimeWindow::parse_osm(parts[1]) {
+                    result.push((window, banned));
+                }
+            }
+        }
+    }
+    result
+}
+
 // TODO All of this is kind of deprecated? During the transiton towards lanes_ltr, some pieces
 // seemed to really need to still handle lanes going outward from the "center" line. Should keep
 // whittling this down, probably. These very much don't handle multiple direction changes.
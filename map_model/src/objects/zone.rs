@@ -4,6 +4,8 @@
 //! 2) Stay Healthy Streets, where most car traffic is banned, except for trips beginning/ending in
 //!    the zone
 //! 3) Congestion capping, where only so many cars per hour can enter the zone
+//! 4) Time-of-day restrictions, like school streets closed to cars during drop-off, or delivery
+//!    windows
 
 use std::collections::BTreeSet;
 
@@ -11,6 +13,8 @@ use enumset::EnumSet;
 use petgraph::graphmap::DiGraphMap;
 use serde::{Deserialize, Serialize};
 
+use geom::{Duration, Time};
+
 use crate::pathfind::{driving_cost, walking_cost, WalkingNode};
 use crate::{
     IntersectionID, LaneID, Map, Path, PathConstraints, PathRequest, PathStep, RoadID, TurnID,
@@ -20,6 +24,9 @@ use crate::{
 pub struct AccessRestrictions {
     pub allow_through_traffic: EnumSet<PathConstraints>,
     pub cap_vehicles_per_hour: Option<usize>,
+    /// During each of these windows, through-traffic is additionally banned for some constraints.
+    /// Trips starting or ending in the zone are still allowed.
+    pub time_windows: Vec<(TimeWindow, EnumSet<PathConstraints>)>,
 }
 
 impl AccessRestrictions {
@@ -27,8 +34,77 @@ impl AccessRestrictions {
         AccessRestrictions {
             allow_through_traffic: EnumSet::all(),
             cap_vehicles_per_hour: None,
+            time_windows: Vec::new(),
+        }
+    }
+
+    /// Who may pass through at a certain time, accounting for any time windows.
+    pub fn allow_through_traffic_at(&self, now: Time) -> EnumSet<PathConstraints> {
+        let mut allow = self.allow_through_traffic;
+        for (window, banned) in &self.time_windows {
+            if window.contains(now) {
+                allow -= *banned;
+            }
+        }
+        allow
+    }
+}
+
+/// A daily recurring period, like 8am to 9am. If `end` is before `start`, the window wraps around
+/// midnight. Every day is treated the same; there's no notion of weekdays in the simulation.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub struct TimeWindow {
+    pub start: Time,
+    pub end: Time,
+}
+
+impl TimeWindow {
+    pub fn contains(&self, now: Time) -> bool {
+        let t = time_of_day(now);
+        if self.start <= self.end {
+            t >= self.start && t < self.end
+        } else {
+            t >= self.start || t < self.end
         }
     }
+
+    /// Parses OpenStreetMap opening_hours-style ranges like "07:00-09:00,16:00-18:30". Weekday
+    /// prefixes like "Mo-Fr" are ignored.
+    pub fn parse_osm(value: &str) -> Vec<TimeWindow> {
+        let mut windows = Vec::new();
+        for range in value.split(|c| c == ',' || c == ';' || c == ' ') {
+            let parts: Vec<&str> = range
+                .trim_matches(|c| c == '(' || c == ')')
+                .split('-')
+                .collect();
+            if parts.len() != 2 || !parts[0].contains(':') {
+                continue;
+            }
+            if let (Ok(start), Ok(end)) = (Time::parse(parts[0]), Time::parse(parts[1])) {
+                windows.push(TimeWindow {
+                    start: time_of_day(start),
+                    end: time_of_day(end),
+                });
+            }
+        }
+        windows
+    }
+}
+
+impl std::fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} - {}",
+            self.start.ampm_tostring(),
+            self.end.ampm_tostring()
+        )
+    }
+}
+
+fn time_of_day(t: Time) -> Time {
+    let day = Duration::hours(24).inner_seconds();
+    Time::START_OF_DAY + Duration::seconds(t.inner_seconds() % day)
 }
 
 /// A contiguous set of roads with access restrictions. This is derived from all the map's roads and
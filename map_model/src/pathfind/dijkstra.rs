@@ -35,7 +35,7 @@ pub fn pathfind_avoiding_lanes(
     avoid: BTreeSet<LaneID>,
     map: &Map,
 ) -> Option<Path> {
-    assert_eq!(req.constraints, PathConstraints::Car);
+    assert_ne!(req.constraints, PathConstraints::Pedestrian);
     let mut graph: DiGraphMap<LaneID, TurnID> = DiGraphMap::new();
     for l in map.all_lanes() {
         if req.constraints.can_use(l, map) && !avoid.contains(&l.id) {
@@ -56,8 +56,13 @@ fn calc_path(graph: DiGraphMap<LaneID, TurnID>, req: &PathRequest, map: &Map) ->
         |(_, _, turn)| driving_cost(map.get_l(turn.src), map.get_t(*turn), req.constraints, map),
         |_| 0.0,
     )?;
+    Some(lanes_to_path(path, req, map))
+}
+
+/// Expands a sequence of lanes found by A* into a full path.
+pub(crate) fn lanes_to_path(lanes: Vec<LaneID>, req: &PathRequest, map: &Map) -> Path {
     let mut steps = Vec::new();
-    for pair in path.windows(2) {
+    for pair in lanes.windows(2) {
         steps.push(PathStep::Lane(pair[0]));
         // We don't need to look for this turn in the map; we know it exists.
         steps.push(PathStep::Turn(TurnID {
@@ -68,7 +73,7 @@ fn calc_path(graph: DiGraphMap<LaneID, TurnID>, req: &PathRequest, map: &Map) ->
     }
     steps.push(PathStep::Lane(req.end.lane()));
     assert_eq!(steps[0], PathStep::Lane(req.start.lane()));
-    Some(Path::new(map, steps, req.clone(), Vec::new()))
+    Path::new(map, steps, req.clone(), Vec::new())
 }
 
 pub fn build_graph_for_pedestrians(map: &Map) -> DiGraphMap<WalkingNode, usize> {
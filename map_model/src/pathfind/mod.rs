@@ -7,12 +7,13 @@ use anyhow::Result;
 use enumset::EnumSetType;
 use serde::{Deserialize, Serialize};
 
-use geom::{Distance, Duration, PolyLine, Speed, EPSILON_DIST};
+use geom::{Distance, Duration, PolyLine, Speed, Time, EPSILON_DIST};
 
 pub use self::ch::ContractionHierarchyPathfinder;
 pub use self::dijkstra::{build_graph_for_pedestrians, build_graph_for_vehicles};
 pub use self::driving::driving_cost;
 pub use self::pathfinder::Pathfinder;
+pub(crate) use self::time_windows::TimeWindowPathfinder;
 pub use self::walking::{walking_cost, WalkingNode};
 use crate::{
     osm, BuildingID, Lane, LaneID, LaneType, Map, Position, Traversable, TurnID, UberTurn,
@@ -23,6 +24,7 @@ mod dijkstra;
 mod driving;
 mod node_map;
 mod pathfinder;
+mod time_windows;
 // TODO tmp
 pub mod uber_turns;
 mod walking;
@@ -485,13 +487,32 @@ impl PathConstraints {
 
     // TODO Handle private zones here?
     pub fn can_use(self, l: &Lane, map: &Map) -> bool {
+        self.can_use_lane_type(l, l.lane_type, map)
+    }
+
+    /// Like `can_use`, but accounts for lanes that change type at certain times of day.
+    pub fn can_use_at(self, l: &Lane, map: &Map, now: Time) -> bool {
+        self.can_use_lane_type(l, l.lane_type_at(now), map)
+    }
+
+    /// Like `can_use`, but also true if the lane is usable during any of its time windows.
+    pub fn can_ever_use(self, l: &Lane, map: &Map) -> bool {
+        self.can_use(l, map)
+            || l.lane_type_windows
+                .iter()
+                .any(|(window, _)| self.can_use_at(l, map, window.start))
+    }
+
+    fn can_use_lane_type(self, l: &Lane, lt: LaneType, map: &Map) -> bool {
         match self {
-            PathConstraints::Pedestrian => l.is_walkable(),
-            PathConstraints::Car => l.is_driving(),
+            PathConstraints::Pedestrian => lt == LaneType::Sidewalk || lt == LaneType::Shoulder,
+            PathConstraints::Car => lt == LaneType::Driving,
             PathConstraints::Bike => {
-                if l.is_biking() {
+                if lt == LaneType::Biking {
                     true
-                } else if l.is_driving() || (l.is_bus() && map.config.bikes_can_use_bus_lanes) {
+                } else if lt == LaneType::Driving
+                    || (lt == LaneType::Bus && map.config.bikes_can_use_bus_lanes)
+                {
                     let road = map.get_r(l.parent);
                     !road.osm_tags.is("bicycle", "no")
                         && !road
@@ -501,8 +522,8 @@ impl PathConstraints {
                     false
                 }
             }
-            PathConstraints::Bus => l.is_driving() || l.is_bus(),
-            PathConstraints::Train => l.is_light_rail(),
+            PathConstraints::Bus => lt == LaneType::Driving || lt == LaneType::Bus,
+            PathConstraints::Train => lt == LaneType::LightRail,
         }
     }
 
@@ -0,0 +1,182 @@
+//! Some lanes change type and some roads ban through-traffic during certain times of day. The
+//! contraction hierarchies don't know about any of this, so paths crossing something closed at the
+//! time are recalculated here with slower A*. The set of active windows only changes a few times
+//! per day, so the graphs and closures are cached.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use petgraph::graphmap::DiGraphMap;
+use petgraph::visit::{EdgeFiltered, EdgeRef};
+
+use geom::Time;
+
+use crate::pathfind::dijkstra::{build_graph_for_pedestrians, lanes_to_path};
+use crate::pathfind::driving::driving_cost;
+use crate::pathfind::walking::{walking_path_to_steps, WalkingNode};
+use crate::{LaneID, Map, Path, PathConstraints, PathStep, RoadID, TimeWindow, TurnID};
+
+#[derive(Default)]
+pub struct TimeWindowPathfinder {
+    /// Every distinct window in the map. Which of these contain the current time determines what's
+    /// closed.
+    windows: Option<Vec<TimeWindow>>,
+    /// Includes lanes that're only usable during some windows
+    vehicle_graphs: BTreeMap<PathConstraints, DiGraphMap<LaneID, TurnID>>,
+    walking_graph: Option<DiGraphMap<WalkingNode, usize>>,
+    closures: BTreeMap<(PathConstraints, Vec<bool>), Closures>,
+}
+
+struct Closures {
+    /// Lanes that're sometimes usable, but not right now
+    lanes: BTreeSet<LaneID>,
+    /// Roads banning through-traffic right now
+    roads: BTreeSet<RoadID>,
+}
+
+impl TimeWindowPathfinder {
+    /// Returns the path unchanged if it doesn't cross anything closed at this time. Otherwise finds
+    /// a different path, also avoiding some extra lanes. Trips starting or ending in a zone may use
+    /// all of it.
+    pub fn pathfind(
+        &mut self,
+        path: Path,
+        avoid: &BTreeSet<LaneID>,
+        now: Time,
+        map: &Map,
+    ) -> Option<Path> {
+        let req = path.get_req().clone();
+        let windows = self.windows.get_or_insert_with(|| all_windows(map));
+        let active: Vec<bool> = windows.iter().map(|w| w.contains(now)).collect();
+        if !active.contains(&true) {
+            return Some(path);
+        }
+
+        if req.constraints == PathConstraints::Pedestrian {
+            if self.walking_graph.is_none() {
+                self.walking_graph = Some(build_graph_for_pedestrians(map));
+            }
+        } else if !self.vehicle_graphs.contains_key(&req.constraints) {
+            self.vehicle_graphs.insert(
+                req.constraints,
+                build_graph_for_vehicles(map, req.constraints),
+            );
+        }
+        let closures = self
+            .closures
+            .entry((req.constraints, active))
+            .or_insert_with(|| Closures::new(map, req.constraints, now));
+
+        let mut exempt = BTreeSet::new();
+        for pos in vec![req.start, req.end] {
+            if let Some(zone) = map.get_parent(pos.lane()).get_zone(map) {
+                exempt.extend(zone.members.iter().cloned());
+            }
+        }
+        let closed = |l: LaneID| {
+            if l == req.start.lane() || l == req.end.lane() {
+                return false;
+            }
+            let r = map.get_l(l).parent;
+            closures.lanes.contains(&l) || (closures.roads.contains(&r) && !exempt.contains(&r))
+        };
+
+        if !path.get_steps().iter().any(|step| match step {
+            PathStep::Lane(l) | PathStep::ContraflowLane(l) => closed(*l),
+            PathStep::Turn(_) => false,
+        }) {
+            return Some(path);
+        }
+
+        if req.constraints == PathConstraints::Pedestrian {
+            let graph = EdgeFiltered::from_fn(
+                self.walking_graph.as_ref().unwrap(),
+                |e: (WalkingNode, WalkingNode, &usize)| match e.target() {
+                    WalkingNode::SidewalkEndpoint(l, _) => !closed(l),
+                    _ => true,
+                },
+            );
+            let closest_end = WalkingNode::closest(req.end, map);
+            let (_, nodes) = petgraph::algo::astar(
+                &graph,
+                WalkingNode::closest(req.start, map),
+                |n| n == closest_end,
+                |e| *e.weight(),
+                |_| 0,
+            )?;
+            let steps = walking_path_to_steps(nodes, map);
+            return Some(Path::new(map, steps, req, Vec::new()));
+        }
+
+        let graph = EdgeFiltered::from_fn(
+            &self.vehicle_graphs[&req.constraints],
+            |e: (LaneID, LaneID, &TurnID)| !closed(e.target()) && !avoid.contains(&e.target()),
+        );
+        let (_, lanes) = petgraph::algo::astar(
+            &graph,
+            req.start.lane(),
+            |l| l == req.end.lane(),
+            |e| {
+                let turn = *e.weight();
+                driving_cost(map.get_l(turn.src), map.get_t(turn), req.constraints, map)
+            },
+            |_| 0.0,
+        )?;
+        Some(lanes_to_path(lanes, &req, map))
+    }
+}
+
+impl Closures {
+    fn new(map: &Map, constraints: PathConstraints, now: Time) -> Closures {
+        Closures {
+            lanes: map
+                .all_lanes()
+                .iter()
+                .filter(|l| {
+                    constraints.can_ever_use(l, map) && !constraints.can_use_at(l, map, now)
+                })
+                .map(|l| l.id)
+                .collect(),
+            roads: map
+                .all_roads()
+                .iter()
+                .filter(|r| {
+                    !r.access_restrictions
+                        .allow_through_traffic_at(now)
+                        .contains(constraints)
+                })
+                .map(|r| r.id)
+                .collect(),
+        }
+    }
+}
+
+fn all_windows(map: &Map) -> Vec<TimeWindow> {
+    let mut windows = Vec::new();
+    for l in map.all_lanes() {
+        windows.extend(l.lane_type_windows.iter().map(|(w, _)| *w));
+    }
+    for r in map.all_roads() {
+        windows.extend(r.access_restrictions.time_windows.iter().map(|(w, _)| *w));
+    }
+    let mut distinct = Vec::new();
+    for w in windows {
+        if !distinct.contains(&w) {
+            distinct.push(w);
+        }
+    }
+    distinct
+}
+
+fn build_graph_for_vehicles(map: &Map, constraints: PathConstraints) -> DiGraphMap<LaneID, TurnID> {
+    let mut graph: DiGraphMap<LaneID, TurnID> = DiGraphMap::new();
+    for l in map.all_lanes() {
+        if constraints.can_ever_use(l, map) {
+            for (turn, dst) in map.get_next_turns_and_lanes(l.id, l.dst_i) {
+                if constraints.can_ever_use(dst, map) {
+                    graph.add_edge(turn.id.src, turn.id.dst, turn.id);
+                }
+            }
+        }
+    }
+    graph
+}
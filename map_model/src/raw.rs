@@ -2,7 +2,7 @@
 //! structure is useful to iterate quickly on parts of the map importing pipeline without having to
 //! constantly read .osm files, and to visualize the intermediate state with map_editor.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
 use crate::anyhow::Context;
@@ -310,6 +310,7 @@ impl RawMap {
         // [ ] road we're deleting is the target of a complicated restriction
         // [X] road we're deleting is the 'via' of a complicated restriction
         // [ ] road we're deleting has turn lanes that wind up orphaning something
+        // [X] road we're deleting is the target of lane connectivity
 
         let (i1, i2) = (short.i1, short.i2);
         if i1 == i2 {
@@ -400,6 +401,17 @@ impl RawMap {
             road.turn_restrictions.extend(add);
         }
 
+        // Lane connectivity can't be transferred to the successors of the deleted road; the lanes
+        // probably don't line up anymore.
+        for road in self.roads.values_mut() {
+            road.lane_connectivity.retain(|(to, _)| *to != short);
+            for (to, _) in &mut road.lane_connectivity {
+                if let Some(new_id) = remapped_road_ids.get(to) {
+                    *to = *new_id;
+                }
+            }
+        }
+
         Ok((i1, i2, deleted, created))
     }
 }
@@ -414,6 +426,10 @@ pub struct RawRoad {
     pub turn_restrictions: Vec<(RestrictionType, OriginalRoad)>,
     /// (via, to). For turn restrictions where 'via' is an entire road. Only BanTurns.
     pub complicated_turn_restrictions: Vec<(OriginalRoad, OriginalRoad)>,
+    /// (to, lanes). From OSM connectivity relations, the pairs of (from lane, to lane) that connect
+    /// to a road. Lanes are counted from 0, left-to-right in the direction of travel, and only
+    /// include general-purpose driving and bus lanes.
+    pub lane_connectivity: Vec<(OriginalRoad, BTreeSet<(usize, usize)>)>,
 }
 
 impl RawRoad {
@@ -54,6 +54,7 @@ impl Viewer {
                 "Change map:".draw_text(ctx),
                 ctx.style()
                     .btn_outline_light_popup(nice_map_name(app.map.get_name()))
+                    .untranslated()
                     .hotkey(lctrl(Key::L))
                     .build_widget(ctx, "change map"),
             ]),
@@ -132,10 +133,13 @@ impl Viewer {
                         continue;
                     }
                     col.push(Widget::row(vec![
-                        ctx.style().btn_plain_light_text(k).build_widget(
-                            ctx,
-                            &format!("open https://wiki.openstreetmap.org/wiki/Key:{}", k),
-                        ),
+                        ctx.style()
+                            .btn_plain_light_text(k)
+                            .untranslated()
+                            .build_widget(
+                                ctx,
+                                &format!("open https://wiki.openstreetmap.org/wiki/Key:{}", k),
+                            ),
                         Line(v).draw(ctx).align_right(),
                     ]));
                 }
@@ -187,10 +191,13 @@ impl Viewer {
                             continue;
                         }
                         col.push(Widget::row(vec![
-                            ctx.style().btn_plain_light_text(k).build_widget(
-                                ctx,
-                                &format!("open https://wiki.openstreetmap.org/wiki/Key:{}", k),
-                            ),
+                            ctx.style()
+                                .btn_plain_light_text(k)
+                                .untranslated()
+                                .build_widget(
+                                    ctx,
+                                    &format!("open https://wiki.openstreetmap.org/wiki/Key:{}", k),
+                                ),
                             Line(v).draw(ctx).align_right(),
                         ]));
                     }
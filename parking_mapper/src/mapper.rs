@@ -136,6 +136,7 @@ impl ParkingMapper {
                     "Change map:".draw_text(ctx),
                     ctx.style()
                         .btn_outline_light_popup(nice_map_name(map.get_name()))
+                        .untranslated()
                         .build_widget(ctx, "change map"),
                 ]),
                 format!(
@@ -7,8 +7,10 @@ use crate::{Activity, CensusPerson, Config, PersonType, Schedule};
 
 impl CensusPerson {
     pub fn generate_schedule(&self, _config: &Config, rng: &mut XorShiftRng) -> Schedule {
-        // TODO How do we pick these categories based on census data?
-        let person_type = if rng.gen_bool(0.5) {
+        // TODO We need more categories, like retirees or people staying at home
+        let person_type = if self.employed {
+            PersonType::Worker
+        } else if self.age < 25 || rng.gen_bool(0.5) {
             PersonType::Student
         } else {
             PersonType::Worker
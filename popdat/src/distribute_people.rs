@@ -1,4 +1,5 @@
 use geo::algorithm::{area::Area, contains::Contains};
+use rand::seq::SliceRandom;
 use rand::Rng;
 use rand_xorshift::XorShiftRng;
 
@@ -15,16 +16,35 @@ pub fn assign_people_to_houses(
 ) -> Vec<CensusPerson> {
     let mut people = Vec::new();
     for area in areas {
+        let pct_employed = area
+            .workers
+            .map(|workers| (workers as f64 / area.population.max(1) as f64).min(1.0))
+            .unwrap_or(0.7);
+        // Treating vehicles as Poisson-distributed among households, this is the share of
+        // households with at least one.
+        let pct_owns_car = area
+            .vehicles_per_household
+            .map(|vehicles| 1.0 - (-vehicles).exp())
+            .unwrap_or(0.5);
+        let total_in_age_bands: usize = area.age_bands.iter().map(|(_, _, n)| *n).sum();
+
         for (home, n) in distribute_population_to_homes(area.polygon, area.population, map, rng) {
             for _ in 0..n {
+                let age = if total_in_age_bands > 0 {
+                    let (min, max, _) =
+                        area.age_bands.choose_weighted(rng, |(_, _, n)| *n).unwrap();
+                    rng.gen_range(*min..(*max).max(*min + 1))
+                } else {
+                    // TODO Making this up when the census doesn't say. We could move this to
+                    // Config.
+                    rng.gen_range(5..95)
+                };
                 people.push(CensusPerson {
                     home,
-                    // TODO Making this up for now. We can either move this to Config or see if we
-                    // can extract it from the census. Also, not even sure which of these
-                    // attributes are useful later in the pipeline.
-                    age: rng.gen_range(5..95),
-                    employed: rng.gen_bool(0.7),
-                    owns_car: rng.gen_bool(0.5),
+                    age,
+                    employed: rng.gen_bool(pct_employed),
+                    owns_car: rng.gen_bool(pct_owns_car),
+                    income: area.median_income,
                 });
             }
         }
@@ -1,94 +1,326 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
 use geo::algorithm::intersects::Intersects;
+use geo::algorithm::{bounding_rect::BoundingRect, map_coords::MapCoordsInplace};
+use geo::{LineString, MultiPolygon, Polygon as GeoPolygon, Rect};
+use serde::{Deserialize, Serialize};
 
 use geom::{GPSBounds, Polygon};
 
 use crate::CensusArea;
 
+/// Where to find census areas. All coordinates must be WGS84.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum CensusSource {
+    /// A FlatGeobuf served over HTTP. Only areas overlapping the map are downloaded.
+    RemoteFlatGeobuf(String),
+    /// A path to a local .fgb file
+    FlatGeobuf(String),
+    /// A path to a local GeoJSON FeatureCollection
+    GeoJSON(String),
+    /// A path to a local .shp file, with attributes in the .dbf file beside it
+    Shapefile(String),
+}
+
+impl CensusSource {
+    /// The global file shared across cities. See the import handbook for how to prepare it.
+    pub fn default() -> CensusSource {
+        CensusSource::RemoteFlatGeobuf(
+            "https://abstreet.s3.amazonaws.com/population_areas.fgb".to_string(),
+        )
+    }
+}
+
+/// Which properties of each census area hold the attributes we care about. Only `population` is
+/// required; areas missing the other attributes fall back to made-up distributions.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AttributeMapping {
+    pub population: String,
+    /// Each property counts the people within some age range.
+    #[serde(default)]
+    pub age_bands: Vec<AgeBandMapping>,
+    /// The number of employed people
+    #[serde(default)]
+    pub workers: Option<String>,
+    /// The average number of vehicles available to each household
+    #[serde(default)]
+    pub vehicles_per_household: Option<String>,
+    /// Median household income, in whatever currency the data uses
+    #[serde(default)]
+    pub income: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AgeBandMapping {
+    pub property: String,
+    pub min_age: usize,
+    /// Exclusive
+    pub max_age: usize,
+}
+
+impl AttributeMapping {
+    /// Matches the global `population_areas` file, which only has population.
+    pub fn default() -> AttributeMapping {
+        AttributeMapping {
+            population: "population".to_string(),
+            age_bands: Vec::new(),
+            workers: None,
+            vehicles_per_household: None,
+            income: None,
+        }
+    }
+}
+
+/// Describes where census data for a city lives, and how to interpret it. Cities can put this in
+/// `data/input/$city/census.json` to use local data instead of the global file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CensusConfig {
+    pub source: CensusSource,
+    pub attributes: AttributeMapping,
+}
+
+impl CensusConfig {
+    pub fn default() -> CensusConfig {
+        CensusConfig {
+            source: CensusSource::default(),
+            attributes: AttributeMapping::default(),
+        }
+    }
+}
+
 impl CensusArea {
+    /// Fetches areas from the global `population_areas` file.
     pub async fn fetch_all_for_map(
         map_area: &Polygon,
         bounds: &GPSBounds,
     ) -> Result<Vec<CensusArea>> {
-        use flatgeobuf::HttpFgbReader;
-        use geozero_core::geo_types::Geo;
+        CensusArea::fetch_from_source(&CensusConfig::default(), map_area, bounds).await
+    }
 
-        use geo::algorithm::{bounding_rect::BoundingRect, map_coords::MapCoordsInplace};
-        let mut geo_map_area: geo::Polygon<_> = map_area.clone().into();
+    /// Finds all areas overlapping the map from any source. Only the remote source actually
+    /// blocks; local files are read immediately.
+    pub async fn fetch_from_source(
+        config: &CensusConfig,
+        map_area: &Polygon,
+        bounds: &GPSBounds,
+    ) -> Result<Vec<CensusArea>> {
+        let mut geo_map_area: GeoPolygon<_> = map_area.clone().into();
         geo_map_area.map_coords_inplace(|c| {
             let projected = geom::Pt2D::new(c.0, c.1).to_gps(bounds);
             (projected.x(), projected.y())
         });
-
-        // See the import handbook for how to prepare this file.
-        let mut fgb =
-            HttpFgbReader::open("https://abstreet.s3.amazonaws.com/population_areas.fgb").await?;
-
         let bounding_rect = geo_map_area
             .bounding_rect()
             .ok_or(anyhow!("missing bound rect"))?;
-        fgb.select_bbox(
-            bounding_rect.min().x,
-            bounding_rect.min().y,
-            bounding_rect.max().x,
-            bounding_rect.max().y,
-        )
-        .await?;
+
+        let features = match config.source {
+            CensusSource::RemoteFlatGeobuf(ref url) => read_remote_fgb(url, &bounding_rect).await?,
+            CensusSource::FlatGeobuf(ref path) => read_local_fgb(path, &bounding_rect)?,
+            CensusSource::GeoJSON(ref path) => read_geojson(path)?,
+            CensusSource::Shapefile(ref path) => crate::shapefile::read(path)?,
+        };
 
         let mut results = vec![];
-        while let Some(feature) = fgb.next().await? {
-            // PERF TODO: how to parse into usize directly? And avoid parsing entire props dict?
-            let props = feature.properties()?;
-            if !props.contains_key("population") {
-                warn!("skipping feature with missing population");
+        for (props, multi_poly) in features {
+            let geo_polygon = match multi_poly.0.first() {
+                Some(p) => p,
+                None => {
+                    warn!("skipping feature with empty geometry");
+                    continue;
+                }
+            };
+            if multi_poly.0.len() > 1 {
+                warn!(
+                    "dropping {} extra polygons from census area: {:?}",
+                    multi_poly.0.len() - 1,
+                    props
+                );
+            }
+
+            if !geo_polygon.intersects(&geo_map_area) {
+                debug!(
+                    "skipping polygon outside of map area. polygon: {:?}, map_area: {:?}",
+                    geo_polygon, geo_map_area
+                );
                 continue;
             }
-            let population: usize = props["population"].parse()?;
-            let geometry = match feature.geometry() {
-                Some(g) => g,
+
+            let area = match config.attributes.extract(geo_polygon.clone(), &props) {
+                Some(area) => area,
                 None => {
-                    warn!("skipping feature with missing geometry");
+                    warn!("skipping feature with missing population: {:?}", props);
                     continue;
                 }
             };
-            let mut geo = Geo::new();
-            geometry.process(&mut geo, flatgeobuf::GeometryType::MultiPolygon)?;
-            if let geo::Geometry::MultiPolygon(multi_poly) = geo.geometry() {
-                let geo_polygon = multi_poly
-                    .0
-                    .first()
-                    .ok_or(anyhow!("multipolygon was unexpectedly empty"))?;
-                if multi_poly.0.len() > 1 {
-                    warn!(
-                        "dropping {} extra polygons from census area: {:?}",
-                        multi_poly.0.len() - 1,
-                        props
-                    );
-                }
+            results.push(area.into_map_coords(bounds));
+        }
 
-                if !geo_polygon.intersects(&geo_map_area) {
-                    debug!(
-                        "skipping polygon outside of map area. polygon: {:?}, map_area: {:?}",
-                        geo_polygon, geo_map_area
-                    );
-                    continue;
+        Ok(results)
+    }
+
+    fn into_map_coords(mut self, bounds: &GPSBounds) -> CensusArea {
+        self.polygon.map_coords_inplace(|(x, y)| {
+            let point = geom::LonLat::new(*x, *y).to_pt(bounds);
+            (point.x(), point.y())
+        });
+        self
+    }
+}
+
+impl AttributeMapping {
+    /// Returns None if the population is missing.
+    fn extract(
+        &self,
+        polygon: GeoPolygon<f64>,
+        props: &HashMap<String, String>,
+    ) -> Option<CensusArea> {
+        let population = parse_number(props, &self.population)?.round() as usize;
+        let mut age_bands = Vec::new();
+        for band in &self.age_bands {
+            if let Some(count) = parse_number(props, &band.property) {
+                age_bands.push((band.min_age, band.max_age, count.round() as usize));
+            }
+        }
+        Some(CensusArea {
+            polygon,
+            population,
+            age_bands,
+            workers: self
+                .workers
+                .as_ref()
+                .and_then(|key| parse_number(props, key))
+                .map(|x| x.round() as usize),
+            vehicles_per_household: self
+                .vehicles_per_household
+                .as_ref()
+                .and_then(|key| parse_number(props, key)),
+            median_income: self
+                .income
+                .as_ref()
+                .and_then(|key| parse_number(props, key)),
+        })
+    }
+}
+
+fn parse_number(props: &HashMap<String, String>, key: &str) -> Option<f64> {
+    let value = props.get(key)?;
+    match value.parse::<f64>() {
+        Ok(x) if x.is_finite() && x >= 0.0 => Some(x),
+        _ => {
+            warn!("{} has a bad value {:?}", key, value);
+            None
+        }
+    }
+}
+
+type Feature = (HashMap<String, String>, MultiPolygon<f64>);
+
+async fn read_remote_fgb(url: &str, bbox: &Rect<f64>) -> Result<Vec<Feature>> {
+    use flatgeobuf::HttpFgbReader;
+
+    let mut fgb = HttpFgbReader::open(url).await?;
+    fgb.select_bbox(bbox.min().x, bbox.min().y, bbox.max().x, bbox.max().y)
+        .await?;
+
+    let mut results = Vec::new();
+    while let Some(feature) = fgb.next().await? {
+        // PERF TODO: how to parse into usize directly? And avoid parsing entire props dict?
+        let props = feature.properties()?;
+        match feature.geometry() {
+            Some(geometry) => {
+                if let Some(multi_poly) = fgb_multipolygon(geometry)? {
+                    results.push((props, multi_poly));
                 }
+            }
+            None => {
+                warn!("skipping feature with missing geometry");
+            }
+        }
+    }
+    Ok(results)
+}
 
-                let mut polygon = geo_polygon.clone();
-                polygon.map_coords_inplace(|(x, y)| {
-                    let point = geom::LonLat::new(*x, *y).to_pt(bounds);
-                    (point.x(), point.y())
-                });
-                results.push(CensusArea {
-                    polygon,
-                    population,
-                });
-            } else {
-                warn!("skipping unexpected geometry");
-                continue;
+fn read_local_fgb(path: &str, bbox: &Rect<f64>) -> Result<Vec<Feature>> {
+    use flatgeobuf::FgbReader;
+
+    let mut file = std::io::BufReader::new(std::fs::File::open(path)?);
+    let mut fgb = FgbReader::open(&mut file)?;
+    fgb.select_bbox(bbox.min().x, bbox.min().y, bbox.max().x, bbox.max().y)?;
+
+    let mut results = Vec::new();
+    while let Some(feature) = fgb.next()? {
+        let props = feature.properties()?;
+        match feature.geometry() {
+            Some(geometry) => {
+                if let Some(multi_poly) = fgb_multipolygon(geometry)? {
+                    results.push((props, multi_poly));
+                }
+            }
+            None => {
+                warn!("skipping feature with missing geometry");
             }
         }
+    }
+    Ok(results)
+}
 
-        Ok(results)
+fn fgb_multipolygon(geometry: flatgeobuf::Geometry) -> Result<Option<MultiPolygon<f64>>> {
+    use geozero_core::geo_types::Geo;
+
+    let mut geo = Geo::new();
+    geometry.process(&mut geo, flatgeobuf::GeometryType::MultiPolygon)?;
+    if let geo::Geometry::MultiPolygon(multi_poly) = geo.geometry() {
+        Ok(Some(multi_poly.clone()))
+    } else {
+        warn!("skipping unexpected geometry");
+        Ok(None)
     }
 }
+
+fn read_geojson(path: &str) -> Result<Vec<Feature>> {
+    use geojson::{GeoJson, Value};
+
+    let geojson = std::fs::read_to_string(path)?.parse::<GeoJson>()?;
+    let features = match geojson {
+        GeoJson::FeatureCollection(collection) => collection.features,
+        _ => bail!("{} isn't a FeatureCollection", path),
+    };
+
+    let mut results = Vec::new();
+    for feature in features {
+        let mut props = HashMap::new();
+        for (key, value) in feature.properties.unwrap_or_default() {
+            let value = match value {
+                serde_json::Value::String(s) => s,
+                serde_json::Value::Null => continue,
+                x => x.to_string(),
+            };
+            props.insert(key, value);
+        }
+        let polygons = match feature.geometry.map(|g| g.value) {
+            Some(Value::Polygon(polygon)) => vec![polygon],
+            Some(Value::MultiPolygon(multi_polygon)) => multi_polygon,
+            _ => {
+                warn!("skipping feature that isn't a polygon");
+                continue;
+            }
+        };
+        let multi_poly = MultiPolygon(
+            polygons
+                .into_iter()
+                .filter(|rings| !rings.is_empty())
+                .map(|mut rings| {
+                    let exterior = to_line_string(rings.remove(0));
+                    GeoPolygon::new(exterior, rings.into_iter().map(to_line_string).collect())
+                })
+                .collect(),
+        );
+        results.push((props, multi_poly));
+    }
+    Ok(results)
+}
+
+fn to_line_string(pts: Vec<Vec<f64>>) -> LineString<f64> {
+    LineString::from(pts.into_iter().map(|pt| (pt[0], pt[1])).collect::<Vec<_>>())
+}
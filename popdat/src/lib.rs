@@ -30,11 +30,13 @@ use map_model::{BuildingID, Map};
 use sim::Scenario;
 
 pub use self::distribute_people::distribute_population_to_homes;
+pub use self::import_census::{AgeBandMapping, AttributeMapping, CensusConfig, CensusSource};
 
 mod activities;
 mod distribute_people;
 mod import_census;
 mod make_person;
+mod shapefile;
 
 /// Represents aggregate demographic data for some part of a city. These could be census tracts or
 /// blocks, depending what data we find. All of the areas should roughly partition the map -- we
@@ -44,7 +46,12 @@ mod make_person;
 pub struct CensusArea {
     pub polygon: geo::Polygon<f64>,
     pub population: usize,
-    // TODO Not sure what goes here, whatever census data actually has that could be useful
+    /// (min age, max age exclusive, number of people). Empty if the source doesn't have ages.
+    pub age_bands: Vec<(usize, usize, usize)>,
+    /// The number of employed people
+    pub workers: Option<usize>,
+    pub vehicles_per_household: Option<f64>,
+    pub median_income: Option<f64>,
 }
 
 /// Demographic information for a single person
@@ -53,6 +60,8 @@ pub struct CensusPerson {
     pub age: usize,
     pub employed: bool,
     pub owns_car: bool,
+    /// The median household income of the area this person lives in, if known
+    pub income: Option<f64>,
 }
 
 /// It might be useful to classify a CensusPerson into different categories to figure out their
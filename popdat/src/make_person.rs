@@ -203,7 +203,7 @@ impl PersonFactory {
                 TripEndpoint::Border(*commuter_borders.choose(rng).unwrap())
             };
 
-            let mode = pick_mode(current_location, goto, map, rng, config);
+            let mode = pick_mode(current_location, goto, person.owns_car, map, rng, config);
             output
                 .trips
                 .push(IndividTrip::new(departure_time, purpose, goto, mode));
@@ -218,6 +218,7 @@ impl PersonFactory {
 fn pick_mode(
     from: TripEndpoint,
     to: TripEndpoint,
+    owns_car: bool,
     map: &Map,
     rng: &mut XorShiftRng,
     config: &Config,
@@ -278,8 +279,16 @@ fn pick_mode(
     if rng.gen_bool(0.005) {
         return TripMode::Bike;
     }
-    // Try transit if available, or fallback to walking
-    if rng.gen_bool(0.3) {
+    // Try transit if available. Without it, people who don't own a car have to bike.
+    let has_transit = !map.all_bus_routes().is_empty();
+    if !owns_car {
+        return if has_transit {
+            TripMode::Transit
+        } else {
+            TripMode::Bike
+        };
+    }
+    if has_transit && rng.gen_bool(0.3) {
         return TripMode::Transit;
     }
 
@@ -0,0 +1,413 @@
+//! A minimal reader for ESRI Shapefiles, just enough to pull polygons and their attributes out of
+//! census data. Only polygon shapes are supported, and coordinates must already be WGS84; the
+//! `.prj` file is ignored. Attributes come from the `.dbf` file alongside the `.shp`.
+//!
+//! The format is documented at
+//! https://www.esri.com/library/whitepapers/pdfs/shapefile.pdf and
+//! http://www.dbase.com/Knowledgebase/INT/db7_file_fmt.htm.
+
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+
+use anyhow::Result;
+use geo::{Coordinate, LineString, MultiPolygon, Polygon};
+
+/// Returns every polygon in the shapefile with its attributes, in the order they appear.
+pub fn read(shp_path: &str) -> Result<Vec<(HashMap<String, String>, MultiPolygon<f64>)>> {
+    let shapes = read_shapes(&std::fs::read(shp_path)?)?;
+    let dbf_path = format!("{}.dbf", shp_path.trim_end_matches(".shp"));
+    let records = read_dbf(&std::fs::read(&dbf_path)?)?;
+    if shapes.len() != records.len() {
+        bail!(
+            "{} has {} shapes, but {} has {} records",
+            shp_path,
+            shapes.len(),
+            dbf_path,
+            records.len()
+        );
+    }
+
+    let mut results = Vec::new();
+    for (shape, record) in shapes.into_iter().zip(records) {
+        // Skip null shapes and records marked as deleted
+        if let (Some(polygon), Some(props)) = (shape, record) {
+            results.push((props, polygon));
+        }
+    }
+    Ok(results)
+}
+
+fn read_shapes(bytes: &[u8]) -> Result<Vec<Option<MultiPolygon<f64>>>> {
+    if bytes.len() < 100 || be_i32(bytes, 0)? != 9994 {
+        bail!("not a .shp file");
+    }
+
+    let mut shapes = Vec::new();
+    let mut pos = 100;
+    while pos + 8 <= bytes.len() {
+        // Lengths are measured in 16-bit words
+        let content_len = count(be_i32(bytes, pos + 4)?, "record length")?
+            .checked_mul(2)
+            .ok_or_else(|| anyhow!("record at byte {} is too long", pos))?;
+        let end = (pos + 8)
+            .checked_add(content_len)
+            .ok_or_else(|| anyhow!("record at byte {} is too long", pos))?;
+        let content = bytes
+            .get(pos + 8..end)
+            .ok_or_else(|| anyhow!("truncated record at byte {}", pos))?;
+        pos = end;
+
+        match le_i32(content, 0)? {
+            0 => {
+                shapes.push(None);
+            }
+            // Polygon, PolygonZ, PolygonM. The extra Z and M values come after the points.
+            5 | 15 | 25 => {
+                shapes.push(Some(read_polygon(content)?));
+            }
+            x => bail!("unsupported shape type {}; only polygons are supported", x),
+        }
+    }
+    Ok(shapes)
+}
+
+fn read_polygon(content: &[u8]) -> Result<MultiPolygon<f64>> {
+    // Skip the shape type and bounding box
+    let num_parts = count(le_i32(content, 36)?, "number of parts")?;
+    let num_points = count(le_i32(content, 40)?, "number of points")?;
+    // Make sure everything fits in the record before trusting these
+    let points_start = num_parts
+        .checked_mul(4)
+        .and_then(|x| x.checked_add(44))
+        .filter(|x| *x <= content.len())
+        .ok_or_else(|| anyhow!("{} parts don't fit in the record", num_parts))?;
+    num_points
+        .checked_mul(16)
+        .and_then(|x| x.checked_add(points_start))
+        .filter(|x| *x <= content.len())
+        .ok_or_else(|| anyhow!("{} points don't fit in the record", num_points))?;
+
+    let mut part_starts = Vec::new();
+    for i in 0..num_parts {
+        let start = count(le_i32(content, 44 + 4 * i)?, "part start")?;
+        if start > num_points {
+            bail!(
+                "part starts at point {}, but there are {}",
+                start,
+                num_points
+            );
+        }
+        part_starts.push(start);
+    }
+    part_starts.push(num_points);
+
+    let mut rings = Vec::new();
+    for pair in part_starts.windows(2) {
+        let mut pts = Vec::new();
+        for i in pair[0]..pair[1] {
+            let offset = points_start + 16 * i;
+            pts.push(Coordinate {
+                x: le_f64(content, offset)?,
+                y: le_f64(content, offset + 8)?,
+            });
+        }
+        rings.push(LineString(pts));
+    }
+
+    // Outer rings are clockwise, and any counter-clockwise holes belong to the outer ring before
+    // them.
+    let mut polygons: Vec<(LineString<f64>, Vec<LineString<f64>>)> = Vec::new();
+    for ring in rings {
+        if signed_area(&ring) <= 0.0 || polygons.is_empty() {
+            polygons.push((ring, Vec::new()));
+        } else {
+            polygons.last_mut().unwrap().1.push(ring);
+        }
+    }
+    Ok(MultiPolygon(
+        polygons
+            .into_iter()
+            .map(|(exterior, interiors)| Polygon::new(exterior, interiors))
+            .collect(),
+    ))
+}
+
+/// Positive for counter-clockwise rings
+fn signed_area(ring: &LineString<f64>) -> f64 {
+    ring.0
+        .windows(2)
+        .map(|pair| pair[0].x * pair[1].y - pair[1].x * pair[0].y)
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Deleted records are None.
+fn read_dbf(bytes: &[u8]) -> Result<Vec<Option<HashMap<String, String>>>> {
+    if bytes.len() < 32 {
+        bail!("not a .dbf file");
+    }
+    let num_records = le_u32(bytes, 4)? as usize;
+    let header_len = le_u16(bytes, 8)? as usize;
+    let record_len = le_u16(bytes, 10)? as usize;
+    if record_len == 0 {
+        bail!("records have length 0");
+    }
+
+    // (name, length)
+    let mut fields: Vec<(String, usize)> = Vec::new();
+    let mut pos = 32;
+    while pos < header_len && bytes.get(pos) != Some(&0x0D) {
+        let descriptor = bytes
+            .get(pos..pos + 32)
+            .ok_or_else(|| anyhow!("truncated field descriptor"))?;
+        let name = String::from_utf8_lossy(&descriptor[0..11])
+            .trim_end_matches(char::from(0))
+            .to_string();
+        fields.push((name, descriptor[16] as usize));
+        pos += 32;
+    }
+
+    let mut records = Vec::new();
+    for i in 0..num_records {
+        let start = header_len + i * record_len;
+        let record = bytes
+            .get(start..start + record_len)
+            .ok_or_else(|| anyhow!("truncated record {}", i))?;
+        if record[0] == b'*' {
+            records.push(None);
+            continue;
+        }
+        let mut props = HashMap::new();
+        let mut offset = 1;
+        for (name, len) in &fields {
+            let value = record
+                .get(offset..offset + len)
+                .ok_or_else(|| anyhow!("record {} is too short", i))?;
+            props.insert(
+                name.clone(),
+                String::from_utf8_lossy(value).trim().to_string(),
+            );
+            offset += len;
+        }
+        records.push(Some(props));
+    }
+    Ok(records)
+}
+
+/// Lengths and counts are stored as signed integers, but can't be negative.
+fn count(x: i32, name: &str) -> Result<usize> {
+    usize::try_from(x).map_err(|_| anyhow!("negative {}: {}", name, x))
+}
+
+fn take(bytes: &[u8], offset: usize, len: usize) -> Result<&[u8]> {
+    offset
+        .checked_add(len)
+        .and_then(|end| bytes.get(offset..end))
+        .ok_or_else(|| anyhow!("unexpected end of file"))
+}
+
+fn be_i32(bytes: &[u8], offset: usize) -> Result<i32> {
+    Ok(i32::from_be_bytes(
+        take(bytes, offset, 4)?.try_into().unwrap(),
+    ))
+}
+
+fn le_i32(bytes: &[u8], offset: usize) -> Result<i32> {
+    Ok(i32::from_le_bytes(
+        take(bytes, offset, 4)?.try_into().unwrap(),
+    ))
+}
+
+fn le_u32(bytes: &[u8], offset: usize) -> Result<u32> {
+    Ok(u32::from_le_bytes(
+        take(bytes, offset, 4)?.try_into().unwrap(),
+    ))
+}
+
+fn le_u16(bytes: &[u8], offset: usize) -> Result<u16> {
+    Ok(u16::from_le_bytes(
+        take(bytes, offset, 2)?.try_into().unwrap(),
+    ))
+}
+
+fn le_f64(bytes: &[u8], offset: usize) -> Result<f64> {
+    Ok(f64::from_le_bytes(
+        take(bytes, offset, 8)?.try_into().unwrap(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A .shp with one square containing a hole, then a null shape
+    fn shp() -> Vec<u8> {
+        let rings: Vec<Vec<(f64, f64)>> = vec![
+            // Clockwise
+            vec![(0.0, 0.0), (0.0, 4.0), (4.0, 4.0), (4.0, 0.0), (0.0, 0.0)],
+            // Counter-clockwise
+            vec![(1.0, 1.0), (2.0, 1.0), (2.0, 2.0), (1.0, 2.0), (1.0, 1.0)],
+        ];
+        let mut polygon = Vec::new();
+        polygon.extend(5_i32.to_le_bytes().iter());
+        // The bounding box isn't used
+        polygon.extend(vec![0; 32]);
+        polygon.extend((rings.len() as i32).to_le_bytes().iter());
+        let num_points: usize = rings.iter().map(|r| r.len()).sum();
+        polygon.extend((num_points as i32).to_le_bytes().iter());
+        let mut start = 0;
+        for ring in &rings {
+            polygon.extend((start as i32).to_le_bytes().iter());
+            start += ring.len();
+        }
+        for (x, y) in rings.into_iter().flatten() {
+            polygon.extend(x.to_le_bytes().iter());
+            polygon.extend(y.to_le_bytes().iter());
+        }
+
+        let mut bytes = vec![0; 100];
+        bytes[0..4].copy_from_slice(&9994_i32.to_be_bytes());
+        for (idx, content) in vec![polygon, 0_i32.to_le_bytes().to_vec()]
+            .into_iter()
+            .enumerate()
+        {
+            bytes.extend(((idx + 1) as i32).to_be_bytes().iter());
+            bytes.extend(((content.len() / 2) as i32).to_be_bytes().iter());
+            bytes.extend(content);
+        }
+        bytes
+    }
+
+    /// A .dbf with fields NAME (6 chars) and POP (4 chars), and records (rows, deleted)
+    fn dbf(rows: Vec<(&str, &str, bool)>, record_len: u16) -> Vec<u8> {
+        let fields = vec![("NAME", 6), ("POP", 4)];
+        let header_len = 32 + 32 * fields.len() + 1;
+
+        let mut bytes = vec![0; 32];
+        bytes[0] = 3;
+        bytes[4..8].copy_from_slice(&(rows.len() as u32).to_le_bytes());
+        bytes[8..10].copy_from_slice(&(header_len as u16).to_le_bytes());
+        bytes[10..12].copy_from_slice(&record_len.to_le_bytes());
+        for (name, len) in fields {
+            let mut descriptor = vec![0; 32];
+            descriptor[0..name.len()].copy_from_slice(name.as_bytes());
+            descriptor[11] = b'C';
+            descriptor[16] = len;
+            bytes.extend(descriptor);
+        }
+        bytes.push(0x0D);
+        for (name, pop, deleted) in rows {
+            bytes.push(if deleted { b'*' } else { b' ' });
+            bytes.extend(format!("{:<6}{:>4}", name, pop).as_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_read_shapes() {
+        let shapes = read_shapes(&shp()).unwrap();
+        assert_eq!(shapes.len(), 2);
+        assert!(shapes[1].is_none());
+
+        let multipolygon = shapes[0].as_ref().unwrap();
+        assert_eq!(multipolygon.0.len(), 1);
+        let polygon = &multipolygon.0[0];
+        assert_eq!(polygon.exterior().0.len(), 5);
+        assert_eq!(polygon.interiors().len(), 1);
+        assert_eq!(polygon.interiors()[0].0[1], Coordinate { x: 2.0, y: 1.0 });
+
+        assert!(read_shapes(&[0; 100]).is_err());
+        let mut truncated = shp();
+        truncated.truncate(150);
+        assert!(read_shapes(&truncated).is_err());
+    }
+
+    #[test]
+    fn test_bad_lengths() {
+        // The polygon's record length is at byte 104, and its content starts at 108
+        for len in vec![-4, i32::MAX] {
+            let mut bytes = shp();
+            bytes[104..108].copy_from_slice(&len.to_be_bytes());
+            assert!(read_shapes(&bytes).is_err());
+        }
+        // The number of parts, then points
+        for (offset, value) in vec![(144, -1), (144, i32::MAX), (148, -1), (148, i32::MAX)] {
+            let mut bytes = shp();
+            bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+            assert!(read_shapes(&bytes).is_err());
+        }
+        // The first part starts past the last point
+        for start in vec![-1_i32, 100] {
+            let mut bytes = shp();
+            bytes[152..156].copy_from_slice(&start.to_le_bytes());
+            assert!(read_shapes(&bytes).is_err());
+        }
+    }
+
+    #[test]
+    fn test_signed_area() {
+        let ccw = LineString(vec![
+            Coordinate { x: 0.0, y: 0.0 },
+            Coordinate { x: 2.0, y: 0.0 },
+            Coordinate { x: 2.0, y: 3.0 },
+            Coordinate { x: 0.0, y: 0.0 },
+        ]);
+        assert!((signed_area(&ccw) - 3.0).abs() < 1e-9);
+        let mut cw = ccw;
+        cw.0.reverse();
+        assert!((signed_area(&cw) + 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_read_dbf() {
+        let records = read_dbf(&dbf(
+            vec![
+                ("north", "120", false),
+                ("gone", "7", true),
+                ("south", "45", false),
+            ],
+            11,
+        ))
+        .unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].as_ref().unwrap()["NAME"], "north");
+        assert_eq!(records[0].as_ref().unwrap()["POP"], "120");
+        assert!(records[1].is_none());
+        assert_eq!(records[2].as_ref().unwrap()["POP"], "45");
+
+        // Fields that don't fit in the record
+        assert!(read_dbf(&dbf(vec![("north", "120", false)], 5)).is_err());
+        // Malformed records
+        assert!(read_dbf(&dbf(vec![("north", "120", false)], 0)).is_err());
+        assert!(read_dbf(&[0; 10]).is_err());
+    }
+
+    #[test]
+    fn test_read() {
+        let dir = std::env::temp_dir().join(format!("popdat_shapefile_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let shp_path = dir.join("areas.shp").to_str().unwrap().to_string();
+        std::fs::write(&shp_path, shp()).unwrap();
+        std::fs::write(
+            dir.join("areas.dbf"),
+            dbf(vec![("north", "120", false), ("south", "45", false)], 11),
+        )
+        .unwrap();
+
+        // The null shape is skipped
+        let results = read(&shp_path).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0["NAME"], "north");
+        assert_eq!(results[0].1 .0.len(), 1);
+
+        // The number of shapes and records must match
+        std::fs::write(
+            dir.join("areas.dbf"),
+            dbf(vec![("north", "120", false)], 11),
+        )
+        .unwrap();
+        assert!(read(&shp_path).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
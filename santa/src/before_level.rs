@@ -269,7 +269,7 @@ fn make_vehicle_panel(ctx: &mut EventCtx, app: &App) -> Panel {
     Panel::new(Widget::col(vec![
         Line("Pick Santa's vehicle").small_heading().draw(ctx),
         Widget::row(buttons),
-        Line(&vehicle.name).small_heading().draw(ctx),
+        Line(&vehicle.name).small_heading().verbatim().draw(ctx),
         Widget::row(vec![
             "Speed:".draw_text(ctx),
             custom_bar(
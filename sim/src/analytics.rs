@@ -2,7 +2,7 @@ use std::collections::{BTreeMap, VecDeque};
 
 use serde::{Deserialize, Serialize};
 
-use abstutil::Counter;
+use abstutil::{retain_btreemap, Counter};
 use geom::{Duration, Time};
 use map_model::{
     BusRouteID, BusStopID, CompressedMovementID, IntersectionID, LaneID, Map, MovementID,
@@ -63,6 +63,13 @@ pub struct Analytics {
     /// Per parking lane or lot, when does a spot become filled (true) or free (false)
     pub parking_lane_changes: BTreeMap<LaneID, Vec<(Time, bool)>>,
     pub parking_lot_changes: BTreeMap<ParkingLotID, Vec<(Time, bool)>>,
+    /// When a car left a spot, and the fee in dollars it paid
+    pub parking_fees: Vec<(Time, ParkingSpot, f64)>,
+    /// Per trip, how long the driver spent cruising for parking after not finding a spot by their
+    /// destination
+    pub parking_cruising: BTreeMap<TripID, Duration>,
+    // Cars currently cruising, and when they started
+    cruising_since: BTreeMap<CarID, (TripID, Time)>,
 
     pub(crate) alerts: Vec<(Time, AlertLocation, String)>,
 
@@ -88,6 +95,9 @@ impl Analytics {
             intersection_delays: BTreeMap::new(),
             parking_lane_changes: BTreeMap::new(),
             parking_lot_changes: BTreeMap::new(),
+            parking_fees: Vec::new(),
+            parking_cruising: BTreeMap::new(),
+            cruising_since: BTreeMap::new(),
             alerts: Vec::new(),
             record_anything,
         }
@@ -232,6 +242,21 @@ impl Analytics {
         }
 
         // Parking spot changes
+        if let Event::CarReachedParkingSpot(car, _) = ev {
+            if let Some((trip, start)) = self.cruising_since.remove(&car) {
+                *self.parking_cruising.entry(trip).or_insert(Duration::ZERO) += time - start;
+            }
+        }
+        if let Event::CarStartedCruising(car, trip) = ev {
+            self.cruising_since.entry(car).or_insert((trip, time));
+        }
+        if let Event::TripCancelled(id, _) = ev {
+            // The car may have been deleted mid-search, so it'll never reach a spot
+            retain_btreemap(&mut self.cruising_since, |_, (trip, _)| *trip != id);
+        }
+        if let Event::CarPaidForParking(_, spot, fee) = ev {
+            self.parking_fees.push((time, spot, fee));
+        }
         if let Event::CarReachedParkingSpot(_, spot) = ev {
             if let ParkingSpot::Onstreet(l, _) = spot {
                 self.parking_lane_changes
@@ -648,3 +673,42 @@ impl Window {
         self.times.len()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VehicleType;
+
+    #[test]
+    fn test_cruising_cleared_on_cancel() {
+        let map = Map::blank();
+        let mut analytics = Analytics::new(true);
+        let car = CarID(0, VehicleType::Car);
+        let t = Time::START_OF_DAY;
+
+        analytics.event(Event::CarStartedCruising(car, TripID(0)), t, &map);
+        analytics.event(
+            Event::TripCancelled(TripID(0), TripMode::Drive),
+            t + Duration::minutes(1),
+            &map,
+        );
+        assert!(analytics.cruising_since.is_empty());
+
+        // The same car searches for parking again on a later trip
+        analytics.event(
+            Event::CarStartedCruising(car, TripID(1)),
+            t + Duration::minutes(10),
+            &map,
+        );
+        analytics.event(
+            Event::CarReachedParkingSpot(car, ParkingSpot::Onstreet(LaneID(0), 0)),
+            t + Duration::minutes(13),
+            &map,
+        );
+        assert_eq!(
+            analytics.parking_cruising.get(&TripID(1)),
+            Some(&Duration::minutes(3))
+        );
+        assert!(!analytics.parking_cruising.contains_key(&TripID(0)));
+    }
+}
@@ -0,0 +1,533 @@
+//! Runs a matrix of experiments -- each some map, edits, scenario, and scenario modifiers -- once
+//! per RNG seed, using many processes at the same time. Afterwards, writes CSV tables summarizing
+//! trip times, road throughput, and intersection delays, with 95% confidence intervals across the
+//! seeds and the difference from the first (baseline) experiment.
+//!
+//! Usage: `batch_runner --config=batch.json`. Each run is just this tool re-invoked with
+//! `--worker`, writing its own results to `{output_dir}/runs/`.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::process::{Child, Command};
+
+use anyhow::Result;
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+use abstio::MapName;
+use abstutil::{CmdArgs, Timer};
+use geom::Duration;
+use map_model::raw::OriginalRoad;
+use map_model::{osm, Map, MapEdits};
+use sim::{Scenario, ScenarioModifier, Sim, SimOptions, TripMode};
+
+#[derive(Serialize, Deserialize)]
+struct BatchConfig {
+    /// The first is the baseline that the others are compared against. Experiments may use
+    /// different maps; results are matched up by OSM IDs.
+    experiments: Vec<Experiment>,
+    /// Every experiment is run once per seed.
+    rng_seeds: Vec<u64>,
+    /// How long to simulate. Use `RepeatDays` and more than 24 hours for multi-day runs.
+    hours: usize,
+    /// How many simulations to run at the same time
+    parallelism: usize,
+    output_dir: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Experiment {
+    /// Must be unique; used to label results
+    name: String,
+    map: MapName,
+    /// The name of proposed edits for the map. If missing, the map is used as-is.
+    #[serde(default)]
+    edits: Option<String>,
+    scenario: String,
+    #[serde(default)]
+    modifiers: Vec<ScenarioModifier>,
+}
+
+/// Everything measured from one simulation run
+#[derive(Serialize, Deserialize)]
+struct RunResults {
+    experiment: String,
+    rng_seed: u64,
+    finished_trips: Vec<(TripMode, Duration)>,
+    cancelled_trips: usize,
+    road_thruput: Vec<(OriginalRoad, usize)>,
+    /// Per intersection, the number of agents delayed and the total delay
+    intersection_delays: Vec<(osm::NodeID, usize, Duration)>,
+}
+
+fn main() -> Result<()> {
+    let mut args = CmdArgs::new();
+    let config_path = args.required("--config");
+    let worker = args.enabled("--worker");
+    let experiment = args.optional_parse("--experiment", |s| s.parse::<usize>());
+    let rng_seed = args.optional_parse("--rng_seed", |s| s.parse::<u64>());
+    args.done();
+
+    let config: BatchConfig = abstio::read_json(config_path.clone(), &mut Timer::throwaway());
+    if config.experiments.is_empty() {
+        anyhow::bail!("{} has no experiments", config_path);
+    }
+    if config.rng_seeds.is_empty() {
+        anyhow::bail!("{} has no rng_seeds", config_path);
+    }
+    if worker {
+        let idx = experiment.expect("--worker needs --experiment");
+        let rng_seed = rng_seed.expect("--worker needs --rng_seed");
+        let results = run(&config.experiments[idx], rng_seed, config.hours);
+        abstio::write_json(
+            results_path(&config, &results.experiment, rng_seed),
+            &results,
+        );
+    } else {
+        run_all(&config, &config_path);
+        summarize(&config);
+    }
+    Ok(())
+}
+
+fn results_path(config: &BatchConfig, experiment: &str, rng_seed: u64) -> String {
+    format!(
+        "{}/runs/{}_{}.json",
+        config.output_dir, experiment, rng_seed
+    )
+}
+
+/// Run every experiment with every seed, never more than `parallelism` at once.
+fn run_all(config: &BatchConfig, config_path: &str) {
+    let exe = std::env::current_exe().unwrap();
+    let mut queue = Vec::new();
+    for idx in 0..config.experiments.len() {
+        for seed in &config.rng_seeds {
+            queue.push((idx, *seed));
+        }
+    }
+    queue.reverse();
+    let total = queue.len();
+
+    let mut running: Vec<(String, Child)> = Vec::new();
+    let mut failures = Vec::new();
+    while !queue.is_empty() || !running.is_empty() {
+        while running.len() < config.parallelism.max(1) && !queue.is_empty() {
+            let (idx, seed) = queue.pop().unwrap();
+            let label = format!("{} with seed {}", config.experiments[idx].name, seed);
+            println!("Starting {}", label);
+            let child = Command::new(&exe)
+                .arg(format!("--config={}", config_path))
+                .arg("--worker")
+                .arg(format!("--experiment={}", idx))
+                .arg(format!("--rng_seed={}", seed))
+                .spawn()
+                .unwrap_or_else(|err| panic!("Couldn't start {}: {}", label, err));
+            running.push((label, child));
+        }
+
+        let mut still_running = Vec::new();
+        for (label, mut child) in running {
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    if !status.success() {
+                        failures.push(label.clone());
+                    }
+                    println!("Finished {} ({} runs left)", label, queue.len());
+                }
+                Ok(None) => {
+                    still_running.push((label, child));
+                }
+                Err(err) => panic!("Couldn't wait for {}: {}", label, err),
+            }
+        }
+        running = still_running;
+        std::thread::sleep(std::time::Duration::from_millis(500));
+    }
+
+    if !failures.is_empty() {
+        println!("{} of {} runs failed:", failures.len(), total);
+        for label in failures {
+            println!("- {}", label);
+        }
+    }
+}
+
+fn run(experiment: &Experiment, rng_seed: u64, hours: usize) -> RunResults {
+    let mut timer = Timer::new(format!("run {} with seed {}", experiment.name, rng_seed));
+    let mut map = Map::new(experiment.map.path(), &mut timer);
+    if let Some(ref name) = experiment.edits {
+        match MapEdits::load(&map, abstio::path_edits(map.get_name(), name), &mut timer) {
+            Ok(edits) => {
+                map.must_apply_edits(edits);
+                map.recalculate_pathfinding_after_edits(&mut timer);
+            }
+            Err(err) => {
+                panic!("Couldn't load edits \"{}\": {}", name, err);
+            }
+        }
+    }
+
+    let mut scenario: Scenario = abstio::must_read_object(
+        abstio::path_scenario(map.get_name(), &experiment.scenario),
+        &mut timer,
+    );
+    for m in &experiment.modifiers {
+        scenario = m.apply(&map, scenario);
+    }
+
+    let mut sim = Sim::new(
+        &map,
+        SimOptions::new(&format!("{}_{}", experiment.name, rng_seed)),
+    );
+    let mut rng = XorShiftRng::seed_from_u64(rng_seed);
+    scenario
+        .instantiate(&mut sim, &map, &mut rng, &mut timer)
+        .unwrap();
+    sim.timed_step(&map, Duration::hours(hours), &mut None, &mut timer);
+
+    let analytics = sim.get_analytics();
+    let mut finished_trips = Vec::new();
+    let mut cancelled_trips = 0;
+    for (_, _, mode, maybe_dt) in &analytics.finished_trips {
+        match maybe_dt {
+            Some(dt) => finished_trips.push((*mode, *dt)),
+            None => cancelled_trips += 1,
+        }
+    }
+    let mut intersection_delays = Vec::new();
+    for (i, delays) in &analytics.intersection_delays {
+        let total = delays
+            .iter()
+            .fold(Duration::ZERO, |sum, (_, _, dt, _)| sum + *dt);
+        intersection_delays.push((map.get_i(*i).orig_id, delays.len(), total));
+    }
+
+    RunResults {
+        experiment: experiment.name.clone(),
+        rng_seed,
+        finished_trips,
+        cancelled_trips,
+        road_thruput: analytics
+            .road_thruput
+            .all_total_counts()
+            .consume()
+            .into_iter()
+            .map(|(r, count)| (map.get_r(r).orig_id, count))
+            .collect(),
+        intersection_delays,
+    }
+}
+
+/// One metric measured once per seed
+#[derive(Default)]
+struct Samples(Vec<f64>);
+
+impl Samples {
+    fn mean(&self) -> f64 {
+        self.0.iter().sum::<f64>() / (self.0.len() as f64)
+    }
+
+    fn variance(&self) -> f64 {
+        if self.0.len() < 2 {
+            return 0.0;
+        }
+        let mean = self.mean();
+        self.0.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / ((self.0.len() - 1) as f64)
+    }
+
+    /// Half the width of the 95% confidence interval around the mean
+    fn ci95(&self) -> f64 {
+        t_critical(self.0.len()) * (self.variance() / (self.0.len() as f64)).sqrt()
+    }
+}
+
+/// The two-sided 95% critical value of Student's t-distribution for a sample this large. Zero if
+/// there's no spread to estimate.
+fn t_critical(n: usize) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+        2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+        2.052, 2.048, 2.045, 2.042,
+    ];
+    if n < 2 {
+        0.0
+    } else {
+        TABLE.get(n - 2).cloned().unwrap_or(1.960)
+    }
+}
+
+/// Mean and CI of a metric for one experiment, plus the difference from the baseline
+fn describe(samples: &Samples, baseline: Option<&Samples>) -> String {
+    let mut row = format!(
+        "{:.2},{:.2},{}",
+        samples.mean(),
+        samples.ci95(),
+        samples.0.len()
+    );
+    if let Some(base) = baseline {
+        // Welch's approximation, using the smaller sample to pick the critical value
+        let n = samples.0.len().min(base.0.len());
+        let se = (samples.variance() / (samples.0.len() as f64)
+            + base.variance() / (base.0.len() as f64))
+            .sqrt();
+        row.push_str(&format!(
+            ",{:.2},{:.2}",
+            samples.mean() - base.mean(),
+            t_critical(n) * se
+        ));
+    } else {
+        row.push_str(",,");
+    }
+    row
+}
+
+fn percentile(sorted: &[f64], pct: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let idx = ((pct / 100.0) * (sorted.len() - 1) as f64).round() as usize;
+    sorted[idx]
+}
+
+/// Per experiment, the samples for each metric keyed by some label
+type Table<K> = BTreeMap<K, BTreeMap<String, Samples>>;
+
+fn summarize(config: &BatchConfig) {
+    let mut timer = Timer::new("summarize batch results");
+    let mut trip_times: Table<(String, String)> = BTreeMap::new();
+    let mut trip_counts: Vec<(String, BTreeMap<String, f64>)> = Vec::new();
+    let mut roads: Vec<(String, BTreeMap<OriginalRoad, f64>)> = Vec::new();
+    let mut intersections: Vec<(String, BTreeMap<osm::NodeID, f64>)> = Vec::new();
+
+    for experiment in &config.experiments {
+        for seed in &config.rng_seeds {
+            let path = results_path(config, &experiment.name, *seed);
+            let results: RunResults = match abstio::maybe_read_json(path.clone(), &mut timer) {
+                Ok(results) => results,
+                Err(err) => {
+                    println!("Skipping {}: {}", path, err);
+                    continue;
+                }
+            };
+
+            let mut per_mode: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+            for (mode, dt) in &results.finished_trips {
+                per_mode
+                    .entry(mode.ongoing_verb().to_string())
+                    .or_insert_with(Vec::new)
+                    .push(dt.inner_seconds());
+                per_mode
+                    .entry("all".to_string())
+                    .or_insert_with(Vec::new)
+                    .push(dt.inner_seconds());
+            }
+            trip_counts.push((
+                experiment.name.clone(),
+                per_mode
+                    .iter()
+                    .map(|(mode, times)| (mode.clone(), times.len() as f64))
+                    .collect(),
+            ));
+            for (mode, mut times) in per_mode {
+                times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let mean = times.iter().sum::<f64>() / (times.len() as f64);
+                let mut add = |metric: &str, value: f64| {
+                    trip_times
+                        .entry((mode.clone(), metric.to_string()))
+                        .or_insert_with(BTreeMap::new)
+                        .entry(experiment.name.clone())
+                        .or_insert_with(Samples::default)
+                        .0
+                        .push(value);
+                };
+                add("mean", mean);
+                add("p50", percentile(&times, 50.0));
+                add("p90", percentile(&times, 90.0));
+                add("p99", percentile(&times, 99.0));
+            }
+            trip_times
+                .entry(("all".to_string(), "cancelled".to_string()))
+                .or_insert_with(BTreeMap::new)
+                .entry(experiment.name.clone())
+                .or_insert_with(Samples::default)
+                .0
+                .push(results.cancelled_trips as f64);
+
+            roads.push((
+                experiment.name.clone(),
+                results
+                    .road_thruput
+                    .into_iter()
+                    .map(|(r, count)| (r, count as f64))
+                    .collect(),
+            ));
+            intersections.push((
+                experiment.name.clone(),
+                results
+                    .intersection_delays
+                    .into_iter()
+                    .map(|(i, count, total)| (i, total.inner_seconds() / (count.max(1) as f64)))
+                    .collect(),
+            ));
+        }
+    }
+    // Percentiles of trip times don't make sense for a mode without any trips, but the count does
+    for (mode, per_experiment) in fill_zeros(trip_counts) {
+        trip_times.insert((mode, "count".to_string()), per_experiment);
+    }
+
+    let baseline = &config.experiments[0].name;
+    let header = format!("experiment,mean,ci95,seeds,diff_vs_{},diff_ci95", baseline);
+    write_table(
+        config,
+        "trip_times.csv",
+        format!("mode,metric_seconds,{}", header),
+        trip_times,
+        |(mode, metric)| format!("{},{}", mode, metric),
+    );
+    write_table(
+        config,
+        "road_throughput.csv",
+        format!("osm_way_id,osm_node1,osm_node2,{}", header),
+        fill_zeros(roads),
+        |r| format!("{},{},{}", r.osm_way_id.0, r.i1.0, r.i2.0),
+    );
+    write_table(
+        config,
+        "intersection_delays.csv",
+        format!("osm_node_id,{}", header),
+        fill_zeros(intersections),
+        |i| i.0.to_string(),
+    );
+}
+
+/// Roads without any traffic and intersections without any delay are missing from a run's output,
+/// so count anything seen in some other run as zero.
+fn fill_zeros<K: Ord + Clone>(runs: Vec<(String, BTreeMap<K, f64>)>) -> Table<K> {
+    let keys: BTreeSet<K> = runs
+        .iter()
+        .flat_map(|(_, values)| values.keys().cloned())
+        .collect();
+    let mut table: Table<K> = BTreeMap::new();
+    for (experiment, values) in runs {
+        for key in &keys {
+            table
+                .entry(key.clone())
+                .or_insert_with(BTreeMap::new)
+                .entry(experiment.clone())
+                .or_insert_with(Samples::default)
+                .0
+                .push(values.get(key).cloned().unwrap_or(0.0));
+        }
+    }
+    table
+}
+
+fn write_table<K: Ord, F: Fn(&K) -> String>(
+    config: &BatchConfig,
+    filename: &str,
+    header: String,
+    table: Table<K>,
+    describe_key: F,
+) {
+    let baseline = &config.experiments[0].name;
+    let mut lines = vec![header];
+    for (key, per_experiment) in table {
+        let key = describe_key(&key);
+        for experiment in &config.experiments {
+            if let Some(samples) = per_experiment.get(&experiment.name) {
+                let base = if &experiment.name == baseline {
+                    None
+                } else {
+                    per_experiment.get(baseline)
+                };
+                lines.push(format!(
+                    "{},{},{}",
+                    key,
+                    experiment.name,
+                    describe(samples, base)
+                ));
+            }
+        }
+    }
+
+    let path = format!("{}/{}", config.output_dir, filename);
+    std::fs::create_dir_all(&config.output_dir).unwrap();
+    std::fs::write(&path, lines.join("\n")).unwrap();
+    println!("Wrote {}", path);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_eq(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn test_samples() {
+        let samples = Samples(vec![2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]);
+        assert!(approx_eq(samples.mean(), 5.0));
+        assert!(approx_eq(samples.variance(), 32.0 / 7.0));
+        assert!(approx_eq(
+            samples.ci95(),
+            2.365 * (32.0 / 7.0 / 8.0 as f64).sqrt()
+        ));
+
+        // No spread to estimate from one sample
+        let one = Samples(vec![3.0]);
+        assert!(approx_eq(one.variance(), 0.0));
+        assert!(approx_eq(one.ci95(), 0.0));
+    }
+
+    #[test]
+    fn test_t_critical() {
+        assert!(approx_eq(t_critical(0), 0.0));
+        assert!(approx_eq(t_critical(1), 0.0));
+        assert!(approx_eq(t_critical(2), 12.706));
+        assert!(approx_eq(t_critical(31), 2.042));
+        assert!(approx_eq(t_critical(1000), 1.960));
+    }
+
+    #[test]
+    fn test_describe() {
+        let base = Samples(vec![10.0, 12.0]);
+        assert_eq!(describe(&base, None), "11.00,12.71,2,,");
+
+        // Both have variance 2; the standard error of the difference is sqrt(2/2 + 2/2)
+        let samples = Samples(vec![14.0, 16.0]);
+        assert_eq!(
+            describe(&samples, Some(&base)),
+            format!("15.00,12.71,2,4.00,{:.2}", 12.706 * 2.0_f64.sqrt())
+        );
+    }
+
+    #[test]
+    fn test_percentile() {
+        assert!(approx_eq(percentile(&[], 50.0), 0.0));
+        let sorted = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        assert!(approx_eq(percentile(&sorted, 0.0), 1.0));
+        assert!(approx_eq(percentile(&sorted, 50.0), 3.0));
+        assert!(approx_eq(percentile(&sorted, 100.0), 5.0));
+    }
+
+    #[test]
+    fn test_fill_zeros() {
+        let mut run1 = BTreeMap::new();
+        run1.insert("a", 1.0);
+        let mut run2 = BTreeMap::new();
+        run2.insert("b", 2.0);
+        let table = fill_zeros(vec![
+            ("base".to_string(), run1.clone()),
+            ("base".to_string(), run2),
+            ("other".to_string(), run1),
+        ]);
+
+        assert_eq!(table["a"]["base"].0, vec![1.0, 0.0]);
+        assert_eq!(table["a"]["other"].0, vec![1.0]);
+        assert_eq!(table["b"]["base"].0, vec![0.0, 2.0]);
+        assert_eq!(table["b"]["other"].0, vec![0.0]);
+    }
+}
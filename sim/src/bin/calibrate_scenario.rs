@@ -0,0 +1,135 @@
+//! Adjusts a scenario until simulated traffic matches observed counts, then saves the result as a
+//! new scenario. Each round simulates the scenario, compares with the counts, and nudges demand by
+//! shifting departure times and scaling the number of people passing through each count. Stops
+//! once enough counts have a GEH statistic under 5, or after `--max_rounds`.
+//!
+//! Usage: `calibrate_scenario --map=data/system/seattle/maps/montlake.bin --scenario=weekday
+//! --counts=counts.csv --output_scenario=weekday_calibrated`. The counts CSV has columns
+//! `name,road,intersection,from_road,to_road,bus_stop,agent_type,hour,count`; see
+//! `ObservedCount::load_csv`.
+//! A CSV report with the GEH of every count after each round is written to `--report`.
+
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use serde::Serialize;
+
+use abstutil::{CmdArgs, Timer};
+use geom::Duration;
+use map_model::Map;
+use sim::{CountComparison, CountsSummary, ObservedCount, Scenario, Sim, SimOptions};
+
+fn main() -> Result<()> {
+    let mut args = CmdArgs::new();
+    let map_path = args.required("--map");
+    let scenario_name = args.required("--scenario");
+    let counts_path = args.required("--counts");
+    let output_scenario = args.required("--output_scenario");
+    let report_path = args
+        .optional("--report")
+        .unwrap_or_else(|| "calibration.csv".to_string());
+    let hours = args
+        .optional_parse("--hours", |s| s.parse::<usize>())
+        .unwrap_or(24);
+    let max_rounds = args
+        .optional_parse("--max_rounds", |s| s.parse::<usize>())
+        .unwrap_or(10);
+    // The percent of counts that must have a GEH under 5
+    let target_pct = args
+        .optional_parse("--target_pct", |s| s.parse::<f64>())
+        .unwrap_or(85.0);
+    let rng_seed = args
+        .optional_parse("--rng_seed", |s| s.parse::<u64>())
+        .unwrap_or(42);
+    args.done();
+
+    let mut timer = Timer::new("calibrate scenario");
+    let map = Map::new(map_path, &mut timer);
+    let mut scenario: Scenario = abstio::must_read_object(
+        abstio::path_scenario(map.get_name(), &scenario_name),
+        &mut timer,
+    );
+    let counts = ObservedCount::load_csv(&counts_path, &map)?;
+    let locations: BTreeSet<_> = counts.iter().map(|c| c.location.clone()).collect();
+    println!(
+        "Calibrating against {} counts at {} locations",
+        counts.len(),
+        locations.len()
+    );
+
+    let mut report = csv::Writer::from_path(&report_path)?;
+    // Adjustments are random, but reproducible
+    let mut rng = XorShiftRng::seed_from_u64(rng_seed);
+
+    for round in 0..=max_rounds {
+        let mut sim = Sim::new(&map, SimOptions::new("calibration"));
+        sim.track_trips_through(locations.clone());
+        // Use the same seed every round, so only the changes to demand matter
+        scenario.instantiate(
+            &mut sim,
+            &map,
+            &mut XorShiftRng::seed_from_u64(rng_seed),
+            &mut timer,
+        )?;
+        sim.timed_step(&map, Duration::hours(hours), &mut None, &mut timer);
+
+        let comparisons = CountComparison::compare_all(&counts, sim.get_analytics(), &map);
+        for c in &comparisons {
+            report.serialize(ReportRow {
+                round,
+                name: c.observed.name.clone(),
+                location: c.observed.location.describe(),
+                agent_type: format!("{:?}", c.observed.agent_type),
+                hour: c.observed.hour,
+                observed: c.observed.count,
+                simulated: c.simulated,
+                geh: format!("{:.2}", c.geh()),
+            })?;
+        }
+        report.flush()?;
+        let summary = CountsSummary::new(&comparisons);
+        println!(
+            "Round {}: {} people, {:.1}% of counts have GEH < 5, mean GEH {:.2}, RMSE {:.1} \
+             ({:.1}%)",
+            round,
+            scenario.people.len(),
+            summary.pct_geh_under_5,
+            summary.mean_geh,
+            summary.rmse,
+            summary.pct_rmse
+        );
+
+        if summary.pct_geh_under_5 >= target_pct {
+            println!("Calibration target reached");
+            break;
+        }
+        if round == max_rounds {
+            println!(
+                "Didn't reach the target of {}% after {} rounds",
+                target_pct, max_rounds
+            );
+            break;
+        }
+        scenario =
+            scenario.adjust_to_counts(&comparisons, sim.get_tracked_crossings().unwrap(), &mut rng);
+    }
+
+    scenario.scenario_name = output_scenario;
+    scenario.save();
+    println!("Wrote {}", report_path);
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct ReportRow {
+    round: usize,
+    name: String,
+    location: String,
+    agent_type: String,
+    hour: usize,
+    observed: usize,
+    simulated: usize,
+    geh: String,
+}
@@ -0,0 +1,54 @@
+//! Simulates a scenario and writes the map and everything that happened in MATSim's formats, so
+//! MATSim's analysis and visualization tools (like Simunto Via) can be used on A/B Street results.
+//!
+//! Usage: `export_matsim --map=data/system/seattle/maps/montlake.bin --scenario=weekday
+//! --output=montlake`. This writes `montlake_network.xml` and `montlake_events.xml`.
+
+use anyhow::Result;
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
+use abstutil::{CmdArgs, Timer};
+use geom::Duration;
+use map_model::Map;
+use sim::{Scenario, Sim, SimOptions};
+
+fn main() -> Result<()> {
+    let mut args = CmdArgs::new();
+    let map_path = args.required("--map");
+    let scenario_name = args.required("--scenario");
+    let output = args.required("--output");
+    let hours = args
+        .optional_parse("--hours", |s| s.parse::<usize>())
+        .unwrap_or(24);
+    let rng_seed = args
+        .optional_parse("--rng_seed", |s| s.parse::<u64>())
+        .unwrap_or(42);
+    args.done();
+
+    let mut timer = Timer::new("export to MATSim");
+    let map = Map::new(map_path, &mut timer);
+    let scenario: Scenario = abstio::must_read_object(
+        abstio::path_scenario(map.get_name(), &scenario_name),
+        &mut timer,
+    );
+
+    let network_path = format!("{}_network.xml", output);
+    sim::write_matsim_network(&map, &network_path)?;
+    println!("Wrote {}", network_path);
+
+    let mut sim = Sim::new(&map, SimOptions::new("matsim"));
+    sim.record_matsim_events();
+    scenario.instantiate(
+        &mut sim,
+        &map,
+        &mut XorShiftRng::seed_from_u64(rng_seed),
+        &mut timer,
+    )?;
+    sim.timed_step(&map, Duration::hours(hours), &mut None, &mut timer);
+
+    let events_path = format!("{}_events.xml", output);
+    sim.save_matsim_events(&events_path)?;
+    println!("Wrote {}", events_path);
+    Ok(())
+}
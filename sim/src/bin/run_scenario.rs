@@ -1,5 +1,5 @@
 //! A simple tool that just runs a simulation for the specified number of hours. Use for profiling
-//! and benchmarking.
+//! and benchmarking, or with `--record_trajectories=5` to record a run for replaying.
 
 fn main() {
     let mut args = abstutil::CmdArgs::new();
@@ -38,11 +38,13 @@ fn main() {
                 &mut None,
             );
             if sim.time() == goal_time {
+                print_trajectories_path(&sim);
                 return;
             }
         }
         println!("\n\nInterrupting at {}", sim.time());
         sim.save();
+        print_trajectories_path(&sim);
         println!("{}", sim.describe_scheduler_stats());
     } else {
         sim.timed_step(
@@ -51,5 +53,13 @@ fn main() {
             &mut None,
             &mut abstutil::Timer::new("run simulation"),
         );
+        print_trajectories_path(&sim);
+    }
+}
+
+/// If `--record_trajectories` was passed, say where they are for replaying later.
+fn print_trajectories_path(sim: &sim::Sim) {
+    if let Some(path) = sim.trajectories_path() {
+        println!("Recorded trajectories to {}", path);
     }
 }
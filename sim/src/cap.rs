@@ -111,7 +111,7 @@ impl CapSimState {
                 avoid_lanes.insert(*l);
             }
         }
-        match map.pathfind_avoiding_lanes(path.get_req().clone(), avoid_lanes) {
+        match map.pathfind_avoiding_lanes_at(path.get_req().clone(), avoid_lanes, now) {
             Some(path) => CapResult::Reroute(path),
             None => {
                 if let Some(delay) = self.delay_trips_instead_of_cancelling {
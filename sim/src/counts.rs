@@ -0,0 +1,404 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::Read;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use geom::Time;
+use map_model::{CompressedMovementID, IntersectionID, Map, RoadID, Traversable};
+
+use crate::{AgentType, Analytics, Event, PersonState, TripID, TripManager};
+
+/// Somewhere traffic was counted.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum CountLocation {
+    /// Everything entering any lane of the road, in either direction
+    Road(RoadID),
+    /// Everything entering the intersection, including agents appearing or leaving at borders.
+    /// Calibration only sees agents turning through the intersection, so trips that start or end
+    /// at a border aren't adjusted to match this count.
+    Intersection(IntersectionID),
+    /// Agents turning from one road onto another. Only traffic signals keep track of these.
+    Movement {
+        i: IntersectionID,
+        from: RoadID,
+        to: RoadID,
+    },
+    /// Passengers boarding transit at every stop with this name. Stops for both directions of a
+    /// route often share a name.
+    TransitStop(String),
+}
+
+impl CountLocation {
+    pub fn describe(&self) -> String {
+        match self {
+            CountLocation::Road(r) => format!("road #{}", r.0),
+            CountLocation::Intersection(i) => format!("intersection #{}", i.0),
+            CountLocation::Movement { i, from, to } => format!(
+                "from road #{} to road #{} at intersection #{}",
+                from.0, to.0, i.0
+            ),
+            CountLocation::TransitStop(name) => format!("transit stop {}", name),
+        }
+    }
+}
+
+/// The number of one type of agent passing some location during one hour, as measured by
+/// something in the real world.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ObservedCount {
+    /// Usually the name or ID of the counter
+    pub name: String,
+    pub location: CountLocation,
+    pub agent_type: AgentType,
+    /// Counts everything from this hour until the next, starting from midnight of the first day
+    pub hour: usize,
+    pub count: usize,
+}
+
+/// One row of the CSV input. Exactly one of `road`, `intersection`, `bus_stop`, or both
+/// `from_road` and `to_road` must be filled out. IDs match the map's RoadIDs and IntersectionIDs;
+/// transit stops are matched by name.
+#[derive(Deserialize)]
+struct CountRecord {
+    name: String,
+    road: Option<usize>,
+    intersection: Option<usize>,
+    from_road: Option<usize>,
+    to_road: Option<usize>,
+    bus_stop: Option<String>,
+    /// Defaults to cars, or transit riders for stops
+    agent_type: Option<AgentType>,
+    hour: usize,
+    count: usize,
+}
+
+impl ObservedCount {
+    /// Reads counts from a CSV file with columns
+    /// `name,road,intersection,from_road,to_road,bus_stop,agent_type,hour,count`.
+    pub fn load_csv(path: &str, map: &Map) -> Result<Vec<ObservedCount>> {
+        read_csv(File::open(path)?, map)
+    }
+
+    /// How many agents the simulation counted here.
+    pub fn simulated(&self, analytics: &Analytics, map: &Map) -> usize {
+        match self.location {
+            CountLocation::Road(r) => self.lookup(&analytics.road_thruput.counts, r),
+            CountLocation::Intersection(i) => {
+                self.lookup(&analytics.intersection_thruput.counts, i)
+            }
+            CountLocation::TransitStop(ref name) => {
+                // Only boardings are recorded per stop, so that's what gets compared
+                let mut total = 0;
+                for bs in map.all_bus_stops().values() {
+                    if &bs.name != name {
+                        continue;
+                    }
+                    if let Some(list) = analytics.passengers_boarding.get(&bs.id) {
+                        total += list
+                            .iter()
+                            .filter(|(t, _, _)| t.get_parts().0 == self.hour)
+                            .count();
+                    }
+                }
+                total
+            }
+            CountLocation::Movement { i, from, to } => {
+                let mut total = 0;
+                for (idx, id) in map.get_traffic_signal(i).movements.keys().enumerate() {
+                    if id.from.id == from && id.to.id == to && !id.crosswalk {
+                        let m = CompressedMovementID { i, idx: idx as u8 };
+                        total += self.lookup(&analytics.traffic_signal_thruput.counts, m);
+                    }
+                }
+                total
+            }
+        }
+    }
+
+    fn lookup<X: Ord>(&self, counts: &BTreeMap<(X, AgentType, usize), usize>, id: X) -> usize {
+        counts
+            .get(&(id, self.agent_type, self.hour))
+            .cloned()
+            .unwrap_or(0)
+    }
+}
+
+fn read_csv<R: Read>(reader: R, map: &Map) -> Result<Vec<ObservedCount>> {
+    let mut counts = Vec::new();
+    for rec in csv::Reader::from_reader(reader).deserialize() {
+        let rec: CountRecord = rec?;
+        let location = match (
+            rec.road,
+            rec.intersection,
+            rec.from_road,
+            rec.to_road,
+            rec.bus_stop,
+        ) {
+            (Some(r), None, None, None, None) => {
+                if map.maybe_get_r(RoadID(r)).is_none() {
+                    bail!("{} refers to road #{}, which isn't in the map", rec.name, r);
+                }
+                CountLocation::Road(RoadID(r))
+            }
+            (None, Some(i), None, None, None) => {
+                if map.maybe_get_i(IntersectionID(i)).is_none() {
+                    bail!(
+                        "{} refers to intersection #{}, which isn't in the map",
+                        rec.name,
+                        i
+                    );
+                }
+                CountLocation::Intersection(IntersectionID(i))
+            }
+            (None, None, Some(from), Some(to), None) => movement(map, RoadID(from), RoadID(to))
+                .map_err(|err| anyhow!("{}: {}", rec.name, err))?,
+            (None, None, None, None, Some(stop)) => {
+                if !map.all_bus_stops().values().any(|bs| bs.name == stop) {
+                    bail!(
+                        "{} refers to transit stop {}, which isn't in the map",
+                        rec.name,
+                        stop
+                    );
+                }
+                CountLocation::TransitStop(stop)
+            }
+            _ => bail!(
+                "{} must specify a road, an intersection, a bus_stop, or from_road and to_road",
+                rec.name
+            ),
+        };
+        let default_agent_type = match location {
+            CountLocation::TransitStop(_) => AgentType::TransitRider,
+            _ => AgentType::Car,
+        };
+        counts.push(ObservedCount {
+            name: rec.name,
+            location,
+            agent_type: rec.agent_type.unwrap_or(default_agent_type),
+            hour: rec.hour,
+            count: rec.count,
+        });
+    }
+    Ok(counts)
+}
+
+fn movement(map: &Map, from: RoadID, to: RoadID) -> Result<CountLocation> {
+    let (from_road, to_road) = match (map.maybe_get_r(from), map.maybe_get_r(to)) {
+        (Some(r1), Some(r2)) => (r1, r2),
+        _ => bail!("road #{} or #{} isn't in the map", from.0, to.0),
+    };
+    let i = if from_road.dst_i == to_road.src_i || from_road.dst_i == to_road.dst_i {
+        from_road.dst_i
+    } else if from_road.src_i == to_road.src_i || from_road.src_i == to_road.dst_i {
+        from_road.src_i
+    } else {
+        bail!("roads #{} and #{} don't meet", from.0, to.0);
+    };
+    if map.maybe_get_traffic_signal(i).is_none() {
+        bail!(
+            "turn movements are only counted at traffic signals, and intersection #{} isn't one",
+            i.0
+        );
+    }
+    Ok(CountLocation::Movement { i, from, to })
+}
+
+/// An observed count paired with what the simulation measured.
+#[derive(Clone, Debug)]
+pub struct CountComparison {
+    pub observed: ObservedCount,
+    pub simulated: usize,
+}
+
+impl CountComparison {
+    pub fn compare_all(
+        counts: &[ObservedCount],
+        analytics: &Analytics,
+        map: &Map,
+    ) -> Vec<CountComparison> {
+        counts
+            .iter()
+            .map(|c| CountComparison {
+                observed: c.clone(),
+                simulated: c.simulated(analytics, map),
+            })
+            .collect()
+    }
+
+    /// The GEH statistic, commonly used to judge hourly traffic volumes. Under 5 is considered a
+    /// good match.
+    pub fn geh(&self) -> f64 {
+        geh(self.observed.count as f64, self.simulated as f64)
+    }
+}
+
+/// `sqrt(2 * (simulated - observed)^2 / (simulated + observed))`
+pub fn geh(observed: f64, simulated: f64) -> f64 {
+    if observed + simulated == 0.0 {
+        return 0.0;
+    }
+    (2.0 * (simulated - observed).powi(2) / (simulated + observed)).sqrt()
+}
+
+/// Overall statistics about how well the simulation matches all counts.
+#[derive(Clone, Debug)]
+pub struct CountsSummary {
+    pub num_counts: usize,
+    /// The percent of counts with a GEH under 5. The usual target is 85%.
+    pub pct_geh_under_5: f64,
+    pub mean_geh: f64,
+    /// Root mean squared error
+    pub rmse: f64,
+    /// RMSE as a percent of the mean observed count
+    pub pct_rmse: f64,
+}
+
+impl CountsSummary {
+    pub fn new(comparisons: &[CountComparison]) -> CountsSummary {
+        let n = comparisons.len().max(1) as f64;
+        let mut under_5 = 0;
+        let mut sum_geh = 0.0;
+        let mut sum_sq_error = 0.0;
+        let mut sum_observed = 0.0;
+        for c in comparisons {
+            let geh = c.geh();
+            if geh < 5.0 {
+                under_5 += 1;
+            }
+            sum_geh += geh;
+            sum_sq_error += (c.simulated as f64 - c.observed.count as f64).powi(2);
+            sum_observed += c.observed.count as f64;
+        }
+        let rmse = (sum_sq_error / n).sqrt();
+        CountsSummary {
+            num_counts: comparisons.len(),
+            pct_geh_under_5: 100.0 * (under_5 as f64) / n,
+            mean_geh: sum_geh / n,
+            rmse,
+            pct_rmse: if sum_observed == 0.0 {
+                0.0
+            } else {
+                100.0 * rmse / (sum_observed / n)
+            },
+        }
+    }
+}
+
+/// Records which trips pass through count locations, so that the trips contributing to a count can
+/// be adjusted.
+#[derive(Clone)]
+pub(crate) struct CountTracker {
+    locations: BTreeSet<CountLocation>,
+    /// Per trip, every location passed, by which type of agent, and during what hour
+    crossings: BTreeMap<TripID, Vec<(CountLocation, AgentType, usize)>>,
+}
+
+impl CountTracker {
+    pub fn new(locations: BTreeSet<CountLocation>) -> CountTracker {
+        CountTracker {
+            locations,
+            crossings: BTreeMap::new(),
+        }
+    }
+
+    pub fn handle_event(&mut self, time: Time, ev: &Event, map: &Map, trips: &TripManager) {
+        let (trip, agent_type, mut candidates) = match ev {
+            Event::AgentEntersTraversable(a, on, _) => {
+                let candidates = match on {
+                    Traversable::Lane(l) => vec![CountLocation::Road(map.get_l(*l).parent)],
+                    Traversable::Turn(t) => vec![
+                        CountLocation::Intersection(t.parent),
+                        CountLocation::Movement {
+                            i: t.parent,
+                            from: map.get_l(t.src).parent,
+                            to: map.get_l(t.dst).parent,
+                        },
+                    ],
+                };
+                (trips.agent_to_trip(*a), a.to_type(), candidates)
+            }
+            Event::PassengerBoardsTransit(p, _, _, stop, _) => {
+                let trip = match trips.get_person(*p).map(|person| &person.state) {
+                    Some(PersonState::Trip(t)) => Some(*t),
+                    _ => None,
+                };
+                let candidates = vec![CountLocation::TransitStop(map.get_bs(*stop).name.clone())];
+                (trip, AgentType::TransitRider, candidates)
+            }
+            _ => {
+                return;
+            }
+        };
+        candidates.retain(|loc| self.locations.contains(loc));
+        if candidates.is_empty() {
+            return;
+        }
+        if let Some(trip) = trip {
+            let hour = time.get_parts().0;
+            let crossings = self.crossings.entry(trip).or_insert_with(Vec::new);
+            for loc in candidates {
+                crossings.push((loc, agent_type, hour));
+            }
+        }
+    }
+
+    pub fn crossings(&self) -> &BTreeMap<TripID, Vec<(CountLocation, AgentType, usize)>> {
+        &self.crossings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str =
+        "name,road,intersection,from_road,to_road,bus_stop,agent_type,hour,count\n";
+
+    fn read(rows: &str) -> Result<Vec<ObservedCount>> {
+        read_csv(format!("{}{}", HEADER, rows).as_bytes(), &Map::blank())
+    }
+
+    #[test]
+    fn test_read_csv() {
+        assert!(read("").unwrap().is_empty());
+
+        // The blank map has nothing to refer to
+        for (row, err) in &[
+            ("a,3,,,,,,8,10", "road #3, which isn't in the map"),
+            (
+                "b,,5,,,,Bike,8,10",
+                "intersection #5, which isn't in the map",
+            ),
+            ("c,,,1,2,,,8,10", "road #1 or #2 isn't in the map"),
+            (
+                "d,,,,,Main St,,8,10",
+                "transit stop Main St, which isn't in the map",
+            ),
+        ] {
+            let msg = read(row).unwrap_err().to_string();
+            assert!(msg.contains(err), "{} gave {}", row, msg);
+        }
+    }
+
+    #[test]
+    fn test_read_csv_bad_rows() {
+        // Nothing or too much specified
+        assert!(read("a,,,,,,,8,10").is_err());
+        assert!(read("a,1,2,,,,,8,10").is_err());
+        assert!(read("a,,,1,,,,8,10").is_err());
+        // Unknown agent types and negative counts
+        assert!(read("a,1,,,,,Spaceship,8,10").is_err());
+        assert!(read("a,1,,,,,,8,-3").is_err());
+    }
+
+    #[test]
+    fn test_geh() {
+        assert_eq!(geh(0.0, 0.0), 0.0);
+        assert_eq!(geh(100.0, 100.0), 0.0);
+        // A textbook example: 1000 observed, 1100 simulated
+        assert!((geh(1000.0, 1100.0) - 3.086).abs() < 0.001);
+        assert_eq!(geh(50.0, 150.0), geh(150.0, 50.0));
+    }
+}
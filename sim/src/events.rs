@@ -20,6 +20,11 @@ use crate::{AgentID, CarID, ParkingSpot, PedestrianID, PersonID, TripID, TripMod
 pub enum Event {
     CarReachedParkingSpot(CarID, ParkingSpot),
     CarLeftParkingSpot(CarID, ParkingSpot),
+    /// The fee in dollars, charged when the car leaves the spot
+    CarPaidForParking(CarID, ParkingSpot, f64),
+    /// A driver couldn't find parking on the lane closest to their destination and started
+    /// searching further away.
+    CarStartedCruising(CarID, TripID),
 
     BusArrivedAtStop(CarID, BusRouteID, BusStopID),
     BusDepartedFromStop(CarID, BusRouteID, BusStopID),
@@ -82,6 +87,8 @@ pub enum TripPhaseType {
     Cancelled,
     Finished,
     DelayedStart,
+    /// Riding along with somebody else's trip
+    Escorted,
 }
 
 impl TripPhaseType {
@@ -98,6 +105,7 @@ impl TripPhaseType {
             TripPhaseType::Cancelled => "Trip was cancelled due to some bug".to_string(),
             TripPhaseType::Finished => "Trip finished".to_string(),
             TripPhaseType::DelayedStart => "Delayed by a previous trip taking too long".to_string(),
+            TripPhaseType::Escorted => "Escorted by someone in their household".to_string(),
         }
     }
 }
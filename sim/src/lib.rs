@@ -32,22 +32,29 @@ pub use crate::render::{
 
 pub use self::analytics::{Analytics, TripPhase};
 pub(crate) use self::cap::CapSimState;
+pub(crate) use self::counts::CountTracker;
+pub use self::counts::{geh, CountComparison, CountLocation, CountsSummary, ObservedCount};
 pub(crate) use self::events::Event;
 pub use self::events::{AlertLocation, TripPhaseType};
 pub use self::make::{
-    fork_rng, BorderSpawnOverTime, ExternalPerson, ExternalTrip, ExternalTripEndpoint, IndividTrip,
-    MapBorders, PersonSpec, Scenario, ScenarioGenerator, ScenarioModifier, SimFlags, SpawnOverTime,
-    TripEndpoint, TripPurpose,
+    fork_rng, BorderSpawnOverTime, Escort, ExternalHousehold, ExternalPerson, ExternalTrip,
+    ExternalTripEndpoint, HouseholdSpec, IndividTrip, MapBorders, PersonSpec, Scenario,
+    ScenarioGenerator, ScenarioModifier, SimFlags, SpawnOverTime, TripEndpoint, TripPurpose,
 };
 pub(crate) use self::make::{StartTripArgs, TripSpec};
+pub use self::matsim::write_matsim_network;
+pub(crate) use self::matsim::MatsimEvents;
 pub(crate) use self::mechanics::{
     DrivingSimState, IntersectionSimState, ParkingSim, ParkingSimState, WalkingSimState,
 };
 pub(crate) use self::pandemic::PandemicModel;
+pub use self::parking_policy::{ParkingPeriod, ParkingPolicy, ParkingRate};
 pub(crate) use self::recorder::TrafficRecorder;
 pub(crate) use self::router::{ActionAtEnd, Router};
 pub(crate) use self::scheduler::{Command, Scheduler};
 pub use self::sim::{AgentProperties, AlertHandler, DelayCause, Sim, SimCallback, SimOptions};
+pub(crate) use self::trajectories::TrajectoryRecorder;
+pub use self::trajectories::{AgentPosition, Trajectories};
 pub(crate) use self::transit::TransitSimState;
 pub use self::trips::TripMode;
 pub use self::trips::{CommutersVehiclesCounts, Person, PersonState, TripInfo, TripResult};
@@ -55,15 +62,19 @@ pub(crate) use self::trips::{TripLeg, TripManager};
 
 mod analytics;
 mod cap;
+mod counts;
 mod events;
 mod make;
+mod matsim;
 mod mechanics;
 mod pandemic;
+mod parking_policy;
 mod recorder;
 mod render;
 mod router;
 mod scheduler;
 mod sim;
+mod trajectories;
 mod transit;
 mod trips;
 
@@ -352,6 +363,9 @@ pub struct ParkedCar {
     pub vehicle: Vehicle,
     pub spot: ParkingSpot,
     pub parked_since: Time,
+    /// When a trip ending here parked the car. Parking fees are charged from this time; cars
+    /// seeded at the start of the simulation or warped after a cancelled trip don't pay.
+    pub trip_arrival: Option<Time>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -0,0 +1,281 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+
+use geom::{Duration, Time};
+
+use crate::{AgentType, CountComparison, CountLocation, Scenario, TripID};
+
+// Don't let a single count change demand too drastically in one round; other counts along the
+// same trips need a chance to pull back.
+const MIN_RATIO: f64 = 0.5;
+const MAX_RATIO: f64 = 2.0;
+
+impl Scenario {
+    /// Nudges demand one step towards matching observed counts, given how the last simulation of
+    /// this scenario compared. Trips through over-counted hours are moved to an adjacent
+    /// under-counted hour at the same location when possible. Otherwise, people whose trips pass
+    /// mostly through over-counted locations may be removed, and people through under-counted
+    /// locations may be duplicated.
+    ///
+    /// `crossings` comes from `Sim::get_tracked_crossings`, after instantiating this scenario
+    /// unmodified.
+    pub fn adjust_to_counts(
+        mut self,
+        comparisons: &[CountComparison],
+        crossings: &BTreeMap<TripID, Vec<(CountLocation, AgentType, usize)>>,
+        rng: &mut XorShiftRng,
+    ) -> Scenario {
+        // (observed, simulated) for everything counted
+        let mut counts: BTreeMap<(CountLocation, AgentType, usize), (f64, f64)> = BTreeMap::new();
+        for c in comparisons {
+            let key = (
+                c.observed.location.clone(),
+                c.observed.agent_type,
+                c.observed.hour,
+            );
+            let entry = counts.entry(key).or_insert((0.0, 0.0));
+            entry.0 += c.observed.count as f64;
+            entry.1 += c.simulated as f64;
+        }
+
+        // Trips are created in the same order as the scenario lists them.
+        let mut trip_indices = BTreeMap::new();
+        for (p, person) in self.people.iter().enumerate() {
+            for idx in 0..person.trips.len() {
+                trip_indices.insert(TripID(trip_indices.len()), (p, idx));
+            }
+        }
+        // Moving an escorted trip would separate the parent and child.
+        let escorted: BTreeSet<(usize, usize)> = self
+            .households
+            .iter()
+            .flat_map(|h| h.escorts.iter().flat_map(|e| vec![e.parent, e.child]))
+            .collect();
+
+        // Per person, the log of the ratio between observed and simulated counts for every
+        // crossing of a trip that wasn't shifted
+        let mut log_ratios: BTreeMap<usize, Vec<f64>> = BTreeMap::new();
+        let mut num_shifted = 0;
+        for (trip, trip_crossings) in crossings {
+            let (p, idx) = match trip_indices.get(trip) {
+                Some(pair) => *pair,
+                None => continue,
+            };
+            let mut shifted = false;
+            if !escorted.contains(&(p, idx)) {
+                for (loc, agent_type, hour) in trip_crossings {
+                    // Locations are tracked for every hour, but maybe only counted during some
+                    let (observed, simulated) = match counts.get(&(loc.clone(), *agent_type, *hour))
+                    {
+                        Some(pair) => *pair,
+                        None => continue,
+                    };
+                    if simulated <= observed || !rng.gen_bool((simulated - observed) / simulated) {
+                        continue;
+                    }
+                    // Would an hour before or after absorb this trip?
+                    let mut options = Vec::new();
+                    if *hour > 0 {
+                        options.push((*hour - 1, false));
+                    }
+                    options.push((*hour + 1, true));
+                    for (other_hour, later) in options {
+                        if let Some((other_observed, other_simulated)) =
+                            counts.get(&(loc.clone(), *agent_type, other_hour)).cloned()
+                        {
+                            if other_simulated < other_observed
+                                && self.shift_trip(p, idx, later, rng)
+                            {
+                                // Keep the counts up-to-date, so later trips don't overshoot
+                                counts
+                                    .get_mut(&(loc.clone(), *agent_type, *hour))
+                                    .unwrap()
+                                    .1 -= 1.0;
+                                counts
+                                    .get_mut(&(loc.clone(), *agent_type, other_hour))
+                                    .unwrap()
+                                    .1 += 1.0;
+                                shifted = true;
+                                break;
+                            }
+                        }
+                    }
+                    if shifted {
+                        break;
+                    }
+                }
+            }
+            if shifted {
+                num_shifted += 1;
+                continue;
+            }
+
+            for (loc, agent_type, hour) in trip_crossings {
+                let (observed, simulated) = match counts.get(&(loc.clone(), *agent_type, *hour)) {
+                    Some(pair) => *pair,
+                    None => continue,
+                };
+                if simulated > 0.0 {
+                    log_ratios
+                        .entry(p)
+                        .or_insert_with(Vec::new)
+                        .push((observed / simulated).max(MIN_RATIO).min(MAX_RATIO).ln());
+                }
+            }
+        }
+
+        // Scale people by the geometric mean of their ratios
+        let mut keep = Vec::new();
+        let mut clones = Vec::new();
+        for p in 0..self.people.len() {
+            let factor = match log_ratios.get(&p) {
+                Some(logs) => (logs.iter().sum::<f64>() / (logs.len() as f64)).exp(),
+                None => 1.0,
+            };
+            if factor < 1.0 {
+                keep.push(rng.gen_bool(factor));
+                continue;
+            }
+            keep.push(true);
+            let mut extra = factor - 1.0;
+            while extra > 0.0 {
+                if extra >= 1.0 || rng.gen_bool(extra) {
+                    // Copies don't belong to any household, so they get their own vehicles.
+                    let mut person = self.people[p].clone();
+                    person.orig_id = None;
+                    clones.push(person);
+                }
+                extra -= 1.0;
+            }
+        }
+
+        let orig = self.people.len();
+        let num_removed = keep.iter().filter(|x| !**x).count();
+        self.retain_people(keep);
+        let num_added = clones.len();
+        self.people.extend(clones);
+        info!(
+            "Calibration changed the departure time of {} trips, removed {} of {} people, and \
+             duplicated {}",
+            num_shifted, num_removed, orig, num_added
+        );
+        self
+    }
+
+    /// Moves one trip's departure by 30 to 60 minutes. Fails if this would make the person's
+    /// schedule overlap.
+    fn shift_trip(&mut self, p: usize, idx: usize, later: bool, rng: &mut XorShiftRng) -> bool {
+        let person = &mut self.people[p];
+        let orig = person.trips[idx].depart;
+        let dt = Duration::minutes(rng.gen_range(30..=60));
+        person.trips[idx].depart = if later {
+            orig + dt
+        } else if orig - Time::START_OF_DAY >= dt {
+            orig - dt
+        } else {
+            return false;
+        };
+        if person.check_schedule().is_err() {
+            person.trips[idx].depart = orig;
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use map_model::{BuildingID, Map, RoadID};
+
+    use super::*;
+    use crate::{IndividTrip, ObservedCount, PersonSpec, TripEndpoint, TripMode, TripPurpose};
+
+    fn at(hours: usize) -> Time {
+        Time::START_OF_DAY + Duration::hours(hours)
+    }
+
+    fn person(depart: Time) -> PersonSpec {
+        PersonSpec {
+            orig_id: None,
+            origin: TripEndpoint::Bldg(BuildingID(0)),
+            trips: vec![IndividTrip::new(
+                depart,
+                TripPurpose::Shopping,
+                TripEndpoint::Bldg(BuildingID(1)),
+                TripMode::Drive,
+            )],
+        }
+    }
+
+    fn compare(r: usize, hour: usize, observed: usize, simulated: usize) -> CountComparison {
+        CountComparison {
+            observed: ObservedCount {
+                name: format!("road {} at {}", r, hour),
+                location: CountLocation::Road(RoadID(r)),
+                agent_type: AgentType::Car,
+                hour,
+                count: observed,
+            },
+            simulated,
+        }
+    }
+
+    fn crossing(r: usize, hour: usize) -> (CountLocation, AgentType, usize) {
+        (CountLocation::Road(RoadID(r)), AgentType::Car, hour)
+    }
+
+    #[test]
+    fn test_adjust_to_counts() {
+        let mut scenario = Scenario::empty(&Map::blank(), "test");
+        scenario.people = vec![person(at(8)), person(at(10)), person(at(12))];
+
+        let comparisons = vec![
+            // Nobody should use road 0 at 8, but somebody's missing an hour later
+            compare(0, 8, 0, 1),
+            compare(0, 9, 1, 0),
+            // Road 1 should see 4 times as much traffic
+            compare(1, 10, 4, 1),
+        ];
+        let mut crossings = BTreeMap::new();
+        crossings.insert(TripID(0), vec![crossing(0, 8)]);
+        // Road 1 isn't counted at 11, which shouldn't matter
+        crossings.insert(TripID(1), vec![crossing(1, 10), crossing(1, 11)]);
+        // The last person doesn't pass anything counted
+
+        let mut rng = XorShiftRng::seed_from_u64(42);
+        let scenario = scenario.adjust_to_counts(&comparisons, &crossings, &mut rng);
+
+        // The first trip moves later
+        let depart = scenario.people[0].trips[0].depart;
+        assert!(depart >= at(8) + Duration::minutes(30) && depart <= at(9));
+        // The second person is doubled, the most any one round allows
+        assert_eq!(scenario.people.len(), 4);
+        assert_eq!(scenario.people[1].trips[0].depart, at(10));
+        assert_eq!(scenario.people[3].trips[0].depart, at(10));
+        assert_eq!(scenario.people[2].trips[0].depart, at(12));
+    }
+
+    #[test]
+    fn test_adjust_to_counts_removes() {
+        let mut scenario = Scenario::empty(&Map::blank(), "test");
+        scenario.people = (0..100).map(|_| person(at(8))).collect();
+
+        // Far too much traffic, with no nearby hour to move it to
+        let comparisons = vec![compare(0, 8, 10, 100)];
+        let crossings = (0..100)
+            .map(|id| (TripID(id), vec![crossing(0, 8)]))
+            .collect();
+
+        let mut rng = XorShiftRng::seed_from_u64(42);
+        let scenario = scenario.adjust_to_counts(&comparisons, &crossings, &mut rng);
+        // Removing people is capped to half in one round
+        assert!(scenario.people.len() > 30 && scenario.people.len() < 70);
+        for p in &scenario.people {
+            assert_eq!(p.trips[0].depart, at(8));
+        }
+    }
+}
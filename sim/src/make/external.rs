@@ -7,7 +7,7 @@ use serde::Deserialize;
 use geom::{Distance, FindClosest, LonLat, Time};
 use map_model::{IntersectionID, Map, PathConstraints};
 
-use crate::{IndividTrip, PersonSpec, TripEndpoint, TripMode, TripPurpose};
+use crate::{Escort, HouseholdSpec, IndividTrip, PersonSpec, TripEndpoint, TripMode, TripPurpose};
 
 #[derive(Deserialize)]
 pub struct ExternalPerson {
@@ -20,6 +20,8 @@ pub struct ExternalTrip {
     pub departure: Time,
     pub destination: ExternalTripEndpoint,
     pub mode: TripMode,
+    /// If this isn't specified, the trip is assumed to be for shopping.
+    pub purpose: Option<TripPurpose>,
 }
 
 #[derive(Deserialize)]
@@ -28,6 +30,49 @@ pub enum ExternalTripEndpoint {
     Position(LonLat),
 }
 
+/// People living together. Members are indices into the list of `ExternalPerson`s.
+#[derive(Deserialize)]
+pub struct ExternalHousehold {
+    pub members: Vec<usize>,
+    pub num_cars: usize,
+    #[serde(default)]
+    pub escorts: Vec<Escort>,
+}
+
+impl ExternalHousehold {
+    /// The household's cars start wherever the first member begins their day, which must be a
+    /// building.
+    pub fn import(
+        people: &[PersonSpec],
+        input: Vec<ExternalHousehold>,
+    ) -> Result<Vec<HouseholdSpec>> {
+        let mut results = Vec::new();
+        for household in input {
+            let first = household
+                .members
+                .get(0)
+                .and_then(|p| people.get(*p))
+                .ok_or_else(|| anyhow!("A household has no members, or refers to nobody"))?;
+            let home = match first.origin {
+                TripEndpoint::Bldg(b) => b,
+                _ => bail!(
+                    "A household's first member {} doesn't start at home",
+                    household.members[0]
+                ),
+            };
+            let spec = HouseholdSpec {
+                home,
+                members: household.members,
+                num_cars: household.num_cars,
+                escorts: household.escorts,
+            };
+            spec.check(people)?;
+            results.push(spec);
+        }
+        Ok(results)
+    }
+}
+
 impl ExternalPerson {
     /// Import external scenario data. The main difference between `ExternalPerson` and
     /// `PersonSpec` is a way to specify endpoints by a `LonLat`. This is snapped to the nearest
@@ -35,6 +80,34 @@ impl ExternalPerson {
     /// (by Euclidean distance -- the network outside the given map isn't known). Failure happens
     /// if a point is within the map, but not close enough to any buildings.
     pub fn import(map: &Map, input: Vec<ExternalPerson>) -> Result<Vec<PersonSpec>> {
+        ExternalPerson::import_each(map, input)
+            .into_iter()
+            .collect()
+    }
+
+    /// Like `import`, but people who can't be imported are skipped instead of failing
+    /// everything. Large populations covering more than the map often have a few of these. Also
+    /// returns the number of people skipped.
+    pub fn import_skipping_failures(
+        map: &Map,
+        input: Vec<ExternalPerson>,
+    ) -> (Vec<PersonSpec>, usize) {
+        let mut results = Vec::new();
+        let mut skipped = 0;
+        for result in ExternalPerson::import_each(map, input) {
+            match result {
+                Ok(spec) => {
+                    results.push(spec);
+                }
+                Err(_) => {
+                    skipped += 1;
+                }
+            }
+        }
+        (results, skipped)
+    }
+
+    fn import_each(map: &Map, input: Vec<ExternalPerson>) -> Vec<Result<PersonSpec>> {
         let mut closest: FindClosest<TripEndpoint> = FindClosest::new(map.get_bounds());
         for b in map.all_buildings() {
             closest.add(TripEndpoint::Bldg(b.id), b.polygon.points());
@@ -64,27 +137,27 @@ impl ExternalPerson {
             }
         };
 
-        let mut results = Vec::new();
-        for person in input {
-            let mut spec = PersonSpec {
-                orig_id: None,
-                origin: lookup_pt(person.origin, true, person.trips[0].mode)?,
-                trips: Vec::new(),
-            };
-            for trip in person.trips {
-                // TODO Add space in the API to specify purpose, but probably make it optional.
-                spec.trips.push(IndividTrip::new(
-                    trip.departure,
-                    TripPurpose::Shopping,
-                    // TODO Do we handle somebody going off-map via one one-way bridge, and
-                    // re-entering using the other?
-                    lookup_pt(trip.destination, false, trip.mode)?,
-                    trip.mode,
-                ));
-            }
-            results.push(spec);
-        }
-        Ok(results)
+        input
+            .into_iter()
+            .map(|person| -> Result<PersonSpec> {
+                let mut spec = PersonSpec {
+                    orig_id: None,
+                    origin: lookup_pt(person.origin, true, person.trips[0].mode)?,
+                    trips: Vec::new(),
+                };
+                for trip in person.trips {
+                    spec.trips.push(IndividTrip::new(
+                        trip.departure,
+                        trip.purpose.unwrap_or(TripPurpose::Shopping),
+                        // TODO Do we handle somebody going off-map via one one-way bridge, and
+                        // re-entering using the other?
+                        lookup_pt(trip.destination, false, trip.mode)?,
+                        trip.mode,
+                    ));
+                }
+                Ok(spec)
+            })
+            .collect()
     }
 }
 
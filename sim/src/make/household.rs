@@ -0,0 +1,323 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use geom::{Duration, Pt2D, Speed, Time};
+use map_model::{BuildingID, Map};
+
+use crate::{PersonSpec, TripEndpoint, TripMode, TripPurpose};
+
+/// People living together. Instead of every person magically owning whatever cars their trips
+/// need, members share a fixed pool of cars. Children's trips can also be tied to a parent
+/// escorting them.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct HouseholdSpec {
+    /// The household's cars start parked here.
+    pub home: BuildingID,
+    /// Indices into `Scenario::people`. Each person belongs to at most one household.
+    pub members: Vec<usize>,
+    /// Zero means the household is car-free.
+    pub num_cars: usize,
+    pub escorts: Vec<Escort>,
+}
+
+/// A child's trip made together with a parent, like being dropped off at school. Both sides are
+/// a person (an index into `Scenario::people`) and the index of one of their trips. The parent's
+/// trip must have `TripPurpose::Escort`, and both trips must start and end at the same place. The
+/// child rides along for the parent's entire trip.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct Escort {
+    pub parent: (usize, usize),
+    pub child: (usize, usize),
+}
+
+impl HouseholdSpec {
+    /// Verify the members and escorts refer to real people and trips that make sense.
+    pub(crate) fn check(&self, people: &[PersonSpec]) -> Result<()> {
+        for p in &self.members {
+            if *p >= people.len() {
+                bail!(
+                    "Household at {} has member {}, who doesn't exist",
+                    self.home,
+                    p
+                );
+            }
+        }
+        for escort in &self.escorts {
+            for (p, idx) in vec![escort.parent, escort.child] {
+                if !self.members.contains(&p) {
+                    bail!(
+                        "Household at {} has an escort involving person {}, who isn't a member",
+                        self.home,
+                        p
+                    );
+                }
+                if idx >= people[p].trips.len() {
+                    bail!("Person {} doesn't have trip {} to escort", p, idx);
+                }
+            }
+
+            let (parent, parent_trip) = escort.parent;
+            let (child, child_trip) = escort.child;
+            if !matches!(
+                people[parent].trips[parent_trip].purpose,
+                TripPurpose::Escort
+            ) {
+                bail!(
+                    "Person {} escorts someone on trip {}, but that trip's purpose isn't escort",
+                    parent,
+                    parent_trip
+                );
+            }
+            if people[parent].trip_start(parent_trip) != people[child].trip_start(child_trip)
+                || people[parent].trips[parent_trip].destination
+                    != people[child].trips[child_trip].destination
+            {
+                bail!(
+                    "Person {} escorts person {} on trips {} and {}, but they don't go between \
+                     the same places",
+                    parent,
+                    child,
+                    parent_trip,
+                    child_trip
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Decides which of the household's cars each member drives on every trip. Trips are
+    /// considered in order of departure. A car can be taken if it's parked where the trip starts
+    /// and whoever drove it last has probably arrived. Returns, for every member, the index of the
+    /// car used for each of their trips. Driving trips that don't get a car have to use another
+    /// mode.
+    pub(crate) fn assign_cars(
+        &self,
+        people: &[PersonSpec],
+        map: &Map,
+    ) -> BTreeMap<usize, Vec<Option<usize>>> {
+        self.assign_cars_with(people, |from, to| estimate_driving_time(from, to, map))
+    }
+
+    fn assign_cars_with<F: Fn(TripEndpoint, TripEndpoint) -> Duration>(
+        &self,
+        people: &[PersonSpec],
+        driving_time: F,
+    ) -> BTreeMap<usize, Vec<Option<usize>>> {
+        let escorted: BTreeSet<(usize, usize)> = self.escorts.iter().map(|e| e.child).collect();
+
+        let mut result = BTreeMap::new();
+        // (departure, person, trip index)
+        let mut driving_trips = Vec::new();
+        for p in &self.members {
+            let person = &people[*p];
+            result.insert(*p, vec![None; person.trips.len()]);
+            for (idx, trip) in person.trips.iter().enumerate() {
+                if trip.mode == TripMode::Drive && !trip.cancelled && !escorted.contains(&(*p, idx))
+                {
+                    driving_trips.push((trip.depart, *p, idx));
+                }
+            }
+        }
+        driving_trips.sort();
+
+        // Where each car is parked (None means off-map), and when it gets there
+        let mut cars: Vec<(Option<BuildingID>, Time)> =
+            vec![(Some(self.home), Time::START_OF_DAY); self.num_cars];
+        for (depart, p, idx) in driving_trips {
+            let from = people[p].trip_start(idx);
+            let to = people[p].trips[idx].destination;
+            if let Some(car) = cars
+                .iter()
+                .position(|(at, ready)| *at == parked_at(from) && *ready <= depart)
+            {
+                cars[car] = (parked_at(to), depart + driving_time(from, to));
+                result.get_mut(&p).unwrap()[idx] = Some(car);
+            }
+        }
+        result
+    }
+}
+
+impl PersonSpec {
+    fn trip_start(&self, idx: usize) -> TripEndpoint {
+        if idx == 0 {
+            self.origin
+        } else {
+            self.trips[idx - 1].destination
+        }
+    }
+}
+
+fn parked_at(endpt: TripEndpoint) -> Option<BuildingID> {
+    match endpt {
+        TripEndpoint::Bldg(b) => Some(b),
+        TripEndpoint::Border(_) | TripEndpoint::SuddenlyAppear(_) => None,
+    }
+}
+
+/// Without pathfinding, guess pessimistically. If a car isn't back when somebody else wants it,
+/// their trip will be cancelled.
+fn estimate_driving_time(from: TripEndpoint, to: TripEndpoint, map: &Map) -> Duration {
+    let dist = endpoint_pt(from, map).dist_to(endpoint_pt(to, map));
+    dist / Speed::miles_per_hour(10.0) + Duration::minutes(10)
+}
+
+fn endpoint_pt(endpt: TripEndpoint, map: &Map) -> Pt2D {
+    match endpt {
+        TripEndpoint::Bldg(b) => map.get_b(b).polygon.center(),
+        TripEndpoint::Border(i) => map.get_i(i).polygon.center(),
+        TripEndpoint::SuddenlyAppear(pos) => pos.pt(map),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IndividTrip, Scenario, ScenarioModifier};
+
+    fn at(hours: usize, mins: usize) -> Time {
+        Time::START_OF_DAY + Duration::hours(hours) + Duration::minutes(mins)
+    }
+
+    fn bldg(idx: usize) -> TripEndpoint {
+        TripEndpoint::Bldg(BuildingID(idx))
+    }
+
+    fn person(trips: Vec<(Time, usize, TripMode)>) -> PersonSpec {
+        PersonSpec {
+            orig_id: None,
+            origin: bldg(0),
+            trips: trips
+                .into_iter()
+                .map(|(depart, b, mode)| {
+                    IndividTrip::new(depart, TripPurpose::Shopping, bldg(b), mode)
+                })
+                .collect(),
+        }
+    }
+
+    /// A parent driving their child to school, and somebody else trying to use the only car
+    fn household() -> (Vec<PersonSpec>, HouseholdSpec) {
+        let mut parent = person(vec![
+            (at(8, 0), 1, TripMode::Drive),
+            (at(17, 0), 0, TripMode::Drive),
+        ]);
+        parent.trips[0].purpose = TripPurpose::Escort;
+        let other = person(vec![
+            // The car's taken
+            (at(8, 30), 2, TripMode::Drive),
+            (at(12, 0), 0, TripMode::Walk),
+            // The car isn't back yet
+            (at(17, 10), 3, TripMode::Drive),
+            (at(17, 40), 0, TripMode::Walk),
+            (at(18, 0), 4, TripMode::Drive),
+        ]);
+        let child = person(vec![(at(8, 0), 1, TripMode::Drive)]);
+        let household = HouseholdSpec {
+            home: BuildingID(0),
+            members: vec![0, 1, 2],
+            num_cars: 1,
+            escorts: vec![Escort {
+                parent: (0, 0),
+                child: (2, 0),
+            }],
+        };
+        (vec![parent, other, child], household)
+    }
+
+    #[test]
+    fn test_assign_cars() {
+        let (people, household) = household();
+        household.check(&people).unwrap();
+        let cars = household.assign_cars_with(&people, |_, _| Duration::minutes(30));
+
+        assert_eq!(cars[&0], vec![Some(0), Some(0)]);
+        assert_eq!(cars[&1], vec![None, None, None, None, Some(0)]);
+        // Escorted children don't need a car
+        assert_eq!(cars[&2], vec![None]);
+
+        // With another car, the morning trip gets one. That car winds up away from home.
+        let mut two_cars = household;
+        two_cars.num_cars = 2;
+        let cars = two_cars.assign_cars_with(&people, |_, _| Duration::minutes(30));
+        assert_eq!(cars[&0], vec![Some(0), Some(0)]);
+        assert_eq!(cars[&1], vec![Some(1), None, None, None, Some(0)]);
+    }
+
+    #[test]
+    fn test_check() {
+        let (mut people, household) = household();
+
+        let mut missing_member = household.clone();
+        missing_member.members.push(5);
+        assert!(missing_member.check(&people).is_err());
+
+        let mut not_together = household.clone();
+        not_together.escorts[0].child = (1, 0);
+        assert!(not_together.check(&people).is_err());
+
+        people[0].trips[0].purpose = TripPurpose::Work;
+        assert!(household.check(&people).is_err());
+    }
+
+    fn scenario(households: Vec<HouseholdSpec>, num_people: usize) -> Scenario {
+        let mut s = Scenario::empty(&Map::blank(), "test");
+        s.people = (0..num_people)
+            .map(|_| person(vec![(at(8, 0), 1, TripMode::Walk)]))
+            .collect();
+        s.households = households;
+        s
+    }
+
+    #[test]
+    fn test_retain_people() {
+        let (_, household) = household();
+        let mut s = scenario(vec![household], 4);
+
+        s.retain_people(vec![true, false, true, true]);
+        assert_eq!(s.people.len(), 3);
+        assert_eq!(s.households[0].members, vec![0, 1]);
+        assert_eq!(s.households[0].escorts[0].parent, (0, 0));
+        assert_eq!(s.households[0].escorts[0].child, (1, 0));
+
+        // Escorts involving the parent disappear
+        s.retain_people(vec![false, true, true]);
+        assert_eq!(s.households[0].members, vec![0]);
+        assert!(s.households[0].escorts.is_empty());
+
+        // So does the empty household
+        s.retain_people(vec![false, true]);
+        assert_eq!(s.people.len(), 1);
+        assert!(s.households.is_empty());
+    }
+
+    #[test]
+    fn test_give_up_cars() {
+        let households = vec![2, 0, 1]
+            .into_iter()
+            .enumerate()
+            .map(|(idx, num_cars)| HouseholdSpec {
+                home: BuildingID(0),
+                members: vec![idx],
+                num_cars,
+                escorts: Vec::new(),
+            })
+            .collect();
+        let s = scenario(households, 3);
+        let num_cars = |pct_households: usize| -> Vec<usize> {
+            ScenarioModifier::GiveUpCars { pct_households }
+                .apply(&Map::blank(), s.clone())
+                .households
+                .iter()
+                .map(|h| h.num_cars)
+                .collect()
+        };
+
+        assert_eq!(num_cars(0), vec![2, 0, 1]);
+        assert_eq!(num_cars(100), vec![1, 0, 0]);
+        // Households without cars don't count towards the percentage
+        assert_eq!(num_cars(1), vec![1, 0, 1]);
+    }
+}
@@ -97,7 +97,9 @@ impl SimFlags {
                 opts.run_name = scenario.scenario_name.clone();
             }
             let mut sim = Sim::new(&map, opts);
-            scenario.instantiate(&mut sim, &map, &mut rng, timer);
+            scenario
+                .instantiate(&mut sim, &map, &mut rng, timer)
+                .unwrap();
 
             (map, sim, rng)
         } else if self.load.contains("/raw_maps/") || self.load.contains("/maps/") {
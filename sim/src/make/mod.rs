@@ -4,8 +4,11 @@
 use rand::{RngCore, SeedableRng};
 use rand_xorshift::XorShiftRng;
 
-pub use self::external::{ExternalPerson, ExternalTrip, ExternalTripEndpoint, MapBorders};
+pub use self::external::{
+    ExternalHousehold, ExternalPerson, ExternalTrip, ExternalTripEndpoint, MapBorders,
+};
 pub use self::generator::{BorderSpawnOverTime, ScenarioGenerator, SpawnOverTime};
+pub use self::household::{Escort, HouseholdSpec};
 pub use self::load::SimFlags;
 pub use self::modifier::ScenarioModifier;
 pub use self::scenario::{IndividTrip, PersonSpec, Scenario, TripPurpose};
@@ -13,8 +16,10 @@ pub use self::spawner::TripEndpoint;
 pub(crate) use self::spawner::{StartTripArgs, TripSpec};
 
 mod activity_model;
+mod calibrate;
 mod external;
 mod generator;
+mod household;
 mod load;
 mod modifier;
 mod scenario;
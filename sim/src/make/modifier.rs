@@ -21,6 +21,10 @@ pub enum ScenarioModifier {
     },
     /// Scenario name
     AddExtraTrips(String),
+    /// Some percentage of households with at least one car each get rid of one.
+    GiveUpCars {
+        pct_households: usize,
+    },
 }
 
 impl ScenarioModifier {
@@ -75,12 +79,38 @@ impl ScenarioModifier {
                     abstio::path_scenario(map.get_name(), name),
                     &mut Timer::throwaway(),
                 );
+                let offset = s.people.len();
                 for mut p in other.people {
                     for trip in &mut p.trips {
                         trip.modified = true;
                     }
                     s.people.push(p);
                 }
+                for mut household in other.households {
+                    for p in &mut household.members {
+                        *p += offset;
+                    }
+                    for escort in &mut household.escorts {
+                        escort.parent.0 += offset;
+                        escort.child.0 += offset;
+                    }
+                    s.households.push(household);
+                }
+                s
+            }
+            ScenarioModifier::GiveUpCars { pct_households } => {
+                for (idx, household) in s
+                    .households
+                    .iter_mut()
+                    .filter(|h| h.num_cars > 0)
+                    .enumerate()
+                {
+                    // Stable as the percentage increases, like ChangeMode
+                    if idx % 100 >= *pct_households {
+                        continue;
+                    }
+                    household.num_cars -= 1;
+                }
                 s
             }
         }
@@ -104,6 +134,9 @@ impl ScenarioModifier {
                 to_mode.map(|m| m.verb())
             ),
             ScenarioModifier::AddExtraTrips(name) => format!("Add extra trips from {}", name),
+            ScenarioModifier::GiveUpCars { pct_households } => {
+                format!("{}% of households with a car give one up", pct_households)
+            }
         }
     }
 }
@@ -118,6 +151,18 @@ impl ScenarioModifier {
 // needing LOTS of cars.
 fn repeat_days(mut s: Scenario, days: usize) -> Scenario {
     s.scenario_name = format!("{} (repeated {} days)", s.scenario_name, days);
+    for household in &mut s.households {
+        let mut escorts = Vec::new();
+        for day in 0..days {
+            for escort in &household.escorts {
+                let mut new = escort.clone();
+                new.parent.1 += day * s.people[escort.parent.0].trips.len();
+                new.child.1 += day * s.people[escort.child.0].trips.len();
+                escorts.push(new);
+            }
+        }
+        household.escorts = escorts;
+    }
     for person in &mut s.people {
         let mut trips = Vec::new();
         let mut offset = Duration::ZERO;
@@ -14,8 +14,8 @@ use map_model::{BuildingID, Map, OffstreetParking, RoadID};
 
 use crate::make::fork_rng;
 use crate::{
-    OrigPersonID, ParkingSpot, Sim, StartTripArgs, TripEndpoint, TripInfo, TripMode, Vehicle,
-    VehicleSpec, VehicleType, BIKE_LENGTH, MAX_CAR_LENGTH, MIN_CAR_LENGTH,
+    CarID, HouseholdSpec, OrigPersonID, ParkingSpot, Sim, StartTripArgs, TripEndpoint, TripInfo,
+    TripMode, Vehicle, VehicleSpec, VehicleType, BIKE_LENGTH, MAX_CAR_LENGTH, MIN_CAR_LENGTH,
 };
 
 /// A Scenario describes all the input to a simulation. Usually a scenario covers one day.
@@ -27,6 +27,9 @@ pub struct Scenario {
     pub people: Vec<PersonSpec>,
     /// None means seed all buses. Otherwise the route name must be present here.
     pub only_seed_buses: Option<BTreeSet<String>>,
+    /// People who live together and share cars. Anybody not in a household gets whatever
+    /// vehicles their trips need.
+    pub households: Vec<HouseholdSpec>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -82,6 +85,8 @@ pub enum TripPurpose {
     Recreation,
     Medical,
     ParkAndRideTransfer,
+    /// The source data didn't say
+    Unknown,
 }
 
 impl fmt::Display for TripPurpose {
@@ -102,18 +107,27 @@ impl fmt::Display for TripPurpose {
                 TripPurpose::Recreation => "recreation",
                 TripPurpose::Medical => "medical",
                 TripPurpose::ParkAndRideTransfer => "park-and-ride transfer",
+                TripPurpose::Unknown => "unknown",
             }
         )
     }
 }
 
 impl Scenario {
-    pub fn instantiate(&self, sim: &mut Sim, map: &Map, rng: &mut XorShiftRng, timer: &mut Timer) {
-        self.instantiate_without_retries(sim, map, rng, true, timer);
+    /// Fails if any person's schedule or household doesn't make sense.
+    pub fn instantiate(
+        &self,
+        sim: &mut Sim,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> Result<()> {
+        self.instantiate_without_retries(sim, map, rng, true, timer)
     }
 
     /// If retry_if_no_room is false, any vehicles that fail to spawn because of something else in
-    /// the way will just wind up as cancelled trips.
+    /// the way will just wind up as cancelled trips. Fails if any person's schedule or household
+    /// doesn't make sense.
     pub fn instantiate_without_retries(
         &self,
         sim: &mut Sim,
@@ -121,7 +135,15 @@ impl Scenario {
         rng: &mut XorShiftRng,
         retry_if_no_room: bool,
         timer: &mut Timer,
-    ) {
+    ) -> Result<()> {
+        // Validate everything before touching the simulation
+        for p in &self.people {
+            p.check_schedule()?;
+        }
+        for household in &self.households {
+            household.check(&self.people)?;
+        }
+
         // Any case where map edits could change the calls to the RNG, we have to fork.
         sim.set_name(self.scenario_name.clone());
 
@@ -140,29 +162,115 @@ impl Scenario {
             }
         }
 
+        // For every household member, the household and the car they use for each trip
+        let mut household_cars: BTreeMap<usize, (usize, Vec<Option<usize>>)> = BTreeMap::new();
+        // Trips where a child is escorted, pointing to the parent's trip
+        let mut escorted_by: BTreeMap<(usize, usize), (usize, usize)> = BTreeMap::new();
+        for (h, household) in self.households.iter().enumerate() {
+            for (p, cars) in household.assign_cars(&self.people, map) {
+                household_cars.insert(p, (h, cars));
+            }
+            for escort in &household.escorts {
+                escorted_by.insert(escort.child, escort.parent);
+            }
+        }
+        // Driving trips without a household car available use transit instead. Escorted children
+        // take whatever mode their parent uses.
+        let own_mode = |p: usize, idx: usize| {
+            let mode = self.people[p].trips[idx].mode;
+            match household_cars.get(&p) {
+                Some((_, cars)) if mode == TripMode::Drive && cars[idx].is_none() => {
+                    TripMode::Transit
+                }
+                _ => mode,
+            }
+        };
+        let mode_for_trip = |p: usize, idx: usize| match escorted_by.get(&(p, idx)) {
+            Some((parent, parent_trip)) => own_mode(*parent, *parent_trip),
+            None => own_mode(p, idx),
+        };
+        let no_car_available = household_cars
+            .iter()
+            .flat_map(|(p, (_, cars))| {
+                cars.iter()
+                    .enumerate()
+                    .map(move |(idx, car)| (*p, idx, car.is_none()))
+            })
+            .filter(|(p, idx, no_car)| {
+                let trip = &self.people[*p].trips[*idx];
+                *no_car
+                    && trip.mode == TripMode::Drive
+                    && !trip.cancelled
+                    && !escorted_by.contains_key(&(*p, *idx))
+            })
+            .count();
+        if no_car_available > 0 {
+            warn!(
+                "{} driving trips in households without a free car will use transit instead",
+                prettyprint_usize(no_car_available)
+            );
+        }
+
         timer.start_iter("trips for People", self.people.len());
         let mut parked_cars: Vec<(Vehicle, BuildingID)> = Vec::new();
         let mut schedule_trips = Vec::new();
-        for p in &self.people {
+        // The household's cars belong to whichever member is created first, and get shared with
+        // everybody else.
+        let mut shared_cars: BTreeMap<usize, Vec<Vehicle>> = BTreeMap::new();
+        // Where each person's trips start in schedule_trips
+        let mut first_trip: BTreeMap<usize, usize> = BTreeMap::new();
+        for (person_idx, p) in self.people.iter().enumerate() {
             timer.next();
 
-            if let Err(err) = p.check_schedule() {
-                panic!("{}", err);
+            let household = household_cars.get(&person_idx);
+            let escorted: BTreeSet<usize> = (0..p.trips.len())
+                .filter(|idx| escorted_by.contains_key(&(person_idx, *idx)))
+                .collect();
+            let (mut vehicle_specs, cars_initially_parked_at, vehicle_foreach_trip) =
+                p.get_vehicles(rng, household.is_some(), &escorted);
+            let mut owns_household_cars = None;
+            if let Some((h, _)) = household {
+                if !shared_cars.contains_key(h) {
+                    owns_household_cars = Some((*h, vehicle_specs.len()));
+                    for _ in 0..self.households[*h].num_cars {
+                        vehicle_specs.push(Scenario::rand_car(rng));
+                    }
+                }
             }
-
-            let (vehicle_specs, cars_initially_parked_at, vehicle_foreach_trip) =
-                p.get_vehicles(rng);
             let person = sim.new_person(p.orig_id, Scenario::rand_ped_speed(rng), vehicle_specs);
+            let person_id = person.id;
+            let vehicles = person.vehicles.clone();
             for (idx, b) in cars_initially_parked_at {
-                parked_cars.push((person.vehicles[idx].clone(), b));
+                parked_cars.push((vehicles[idx].clone(), b));
+            }
+            let mut use_vehicles: Vec<Option<CarID>> = vehicle_foreach_trip
+                .into_iter()
+                .map(|maybe_idx| maybe_idx.map(|idx| vehicles[idx].id))
+                .collect();
+            if let Some((h, cars)) = household {
+                if let Some((_, offset)) = owns_household_cars {
+                    for vehicle in &vehicles[offset..] {
+                        parked_cars.push((vehicle.clone(), self.households[*h].home));
+                    }
+                    shared_cars.insert(*h, vehicles[offset..].to_vec());
+                } else {
+                    sim.share_vehicles(person_id, shared_cars[h].clone());
+                }
+                for (idx, car) in cars.iter().enumerate() {
+                    if let Some(car) = car {
+                        use_vehicles[idx] = Some(shared_cars[h][*car].id);
+                    }
+                }
             }
+
+            first_trip.insert(person_idx, schedule_trips.len());
             let mut from = p.origin.clone();
-            for (trip, maybe_idx) in p.trips.iter().zip(vehicle_foreach_trip) {
+            for (idx, (trip, use_vehicle)) in p.trips.iter().zip(use_vehicles).enumerate() {
                 schedule_trips.push((
-                    person.id,
+                    person_id,
                     TripInfo {
                         departure: trip.depart,
-                        mode: trip.mode,
+                        mode: mode_for_trip(person_idx, idx),
                         start: from,
                         end: trip.destination.clone(),
                         purpose: trip.purpose,
@@ -176,19 +284,29 @@ impl Scenario {
                     },
                     StartTripArgs {
                         retry_if_no_room,
-                        use_vehicle: maybe_idx.map(|idx| person.vehicles[idx].id),
+                        use_vehicle,
                     },
                 ));
                 from = trip.destination.clone();
             }
         }
+        let escorts = escorted_by
+            .into_iter()
+            .map(|((child, child_trip), (parent, parent_trip))| {
+                (
+                    first_trip[&child] + child_trip,
+                    first_trip[&parent] + parent_trip,
+                )
+            })
+            .collect();
 
         // parked_cars is stable over map edits, so don't fork.
         parked_cars.shuffle(rng);
         seed_parked_cars(parked_cars, sim, map, rng, timer);
 
-        sim.spawn_trips(schedule_trips, map, timer);
+        sim.spawn_trips(schedule_trips, escorts, map, timer);
         timer.stop(format!("Instantiating {}", self.scenario_name));
+        Ok(())
     }
 
     pub fn save(&self) {
@@ -204,6 +322,7 @@ impl Scenario {
             map_name: map.get_name().clone(),
             people: Vec::new(),
             only_seed_buses: Some(BTreeSet::new()),
+            households: Vec::new(),
         }
     }
 
@@ -255,24 +374,38 @@ impl Scenario {
         let mut per_bldg = Counter::new();
         // Pass in a dummy RNG
         let mut rng = XorShiftRng::seed_from_u64(0);
-        for p in &self.people {
-            let (_, cars_initially_parked_at, _) = p.get_vehicles(&mut rng);
+        let members: BTreeSet<usize> = self
+            .households
+            .iter()
+            .flat_map(|h| h.members.clone())
+            .collect();
+        for (idx, p) in self.people.iter().enumerate() {
+            // Escorts only affect bikes, so don't bother with them
+            let (_, cars_initially_parked_at, _) =
+                p.get_vehicles(&mut rng, members.contains(&idx), &BTreeSet::new());
             for (_, b) in cars_initially_parked_at {
                 per_bldg.inc(b);
             }
         }
+        for household in &self.households {
+            per_bldg.add(household.home, household.num_cars);
+        }
         per_bldg
     }
 
     pub fn remove_weird_schedules(mut self) -> Scenario {
         let orig = self.people.len();
-        self.people.retain(|person| match person.check_schedule() {
-            Ok(()) => true,
-            Err(err) => {
-                println!("{}", err);
-                false
-            }
-        });
+        let mut keep = Vec::new();
+        for person in &self.people {
+            keep.push(match person.check_schedule() {
+                Ok(()) => true,
+                Err(err) => {
+                    println!("{}", err);
+                    false
+                }
+            });
+        }
+        self.retain_people(keep);
         warn!(
             "{} of {} people have nonsense schedules",
             prettyprint_usize(orig - self.people.len()),
@@ -280,6 +413,37 @@ impl Scenario {
         );
         self
     }
+
+    /// Removes people, keeping households pointing at the right members. Escorts involving
+    /// somebody removed are dropped.
+    pub(crate) fn retain_people(&mut self, keep: Vec<bool>) {
+        let mut new_idx = Vec::new();
+        let mut next = 0;
+        for keep in &keep {
+            new_idx.push(if *keep { Some(next) } else { None });
+            if *keep {
+                next += 1;
+            }
+        }
+        let mut keep = keep.into_iter();
+        self.people.retain(|_| keep.next().unwrap());
+
+        for household in &mut self.households {
+            household.members = household
+                .members
+                .iter()
+                .filter_map(|p| new_idx[*p])
+                .collect();
+            household
+                .escorts
+                .retain(|e| new_idx[e.parent.0].is_some() && new_idx[e.child.0].is_some());
+            for escort in &mut household.escorts {
+                escort.parent.0 = new_idx[escort.parent.0].unwrap();
+                escort.child.0 = new_idx[escort.child.0].unwrap();
+            }
+        }
+        self.households.retain(|h| !h.members.is_empty());
+    }
 }
 
 fn seed_parked_cars(
@@ -403,7 +567,7 @@ fn find_spot_near_building(
 
 impl PersonSpec {
     /// Verify that a person's trips make sense
-    fn check_schedule(&self) -> Result<()> {
+    pub(crate) fn check_schedule(&self) -> Result<()> {
         for pair in self.trips.windows(2) {
             if pair[0].depart >= pair[1].depart {
                 bail!(
@@ -432,9 +596,13 @@ impl PersonSpec {
         Ok(())
     }
 
+    /// Members of a household share its cars, so this only assigns bikes to them. Escorted trips
+    /// don't need a vehicle.
     fn get_vehicles(
         &self,
         rng: &mut XorShiftRng,
+        household_member: bool,
+        escorted: &BTreeSet<usize>,
     ) -> (
         Vec<VehicleSpec>,
         Vec<(usize, BuildingID)>,
@@ -450,9 +618,11 @@ impl PersonSpec {
 
         // TODO If the trip is cancelled, this should be affected...
         let mut from = self.origin.clone();
-        for trip in &self.trips {
+        for (idx, trip) in self.trips.iter().enumerate() {
             let use_for_trip = match trip.mode {
+                _ if escorted.contains(&idx) => None,
                 TripMode::Walk | TripMode::Transit => None,
+                TripMode::Drive if household_member => None,
                 TripMode::Bike => {
                     if bike_idx.is_none() {
                         bike_idx = Some(vehicle_specs.len());
@@ -0,0 +1,336 @@
+//! Writes a simulation in formats understood by [MATSim](https://www.matsim.org) analysis tools:
+//! a network with one link per direction of each road, and the events that happened on it.
+//!
+//! Link IDs are the `RoadID` going forwards and the negated `RoadID` going backwards. Node IDs
+//! are `IntersectionID`s, and person IDs are `PersonID`s. Coordinates are in meters, with Y
+//! increasing northbound.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use anyhow::Result;
+
+use geom::{Pt2D, Time};
+use map_model::{Direction, LaneID, LaneType, Map, Traversable};
+
+use crate::{AgentID, CarID, Event, PersonID, TripID, TripManager, TripPhaseType, VehicleType};
+
+/// Writes the map as a MATSim network.xml. Capacities assume 1,800 vehicles per hour per driving
+/// lane.
+pub fn write_matsim_network(map: &Map, path: &str) -> Result<()> {
+    let mut f = BufWriter::new(File::create(path)?);
+    writeln!(f, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        f,
+        r#"<!DOCTYPE network SYSTEM "http://www.matsim.org/files/dtd/network_v2.dtd">"#
+    )?;
+    writeln!(f, "<network>")?;
+    writeln!(f, "  <nodes>")?;
+    for i in map.all_intersections() {
+        let pt = flip_y(map, i.polygon.center());
+        writeln!(
+            f,
+            r#"    <node id="{}" x="{:.2}" y="{:.2}"/>"#,
+            i.id.0,
+            pt.x(),
+            pt.y()
+        )?;
+    }
+    writeln!(f, "  </nodes>")?;
+    writeln!(f, r#"  <links capperiod="01:00:00">"#)?;
+    for r in map.all_roads() {
+        for dir in vec![Direction::Fwd, Direction::Back] {
+            let lane_types: Vec<LaneType> = r
+                .lanes_ltr()
+                .into_iter()
+                .filter(|(_, d, _)| *d == dir)
+                .map(|(_, _, lt)| lt)
+                .collect();
+            let num_driving = lane_types
+                .iter()
+                .filter(|lt| **lt == LaneType::Driving || **lt == LaneType::Bus)
+                .count();
+            let mut modes = Vec::new();
+            if num_driving > 0 {
+                modes.push("car");
+            }
+            if lane_types
+                .iter()
+                .any(|lt| *lt == LaneType::Driving || *lt == LaneType::Biking)
+            {
+                modes.push("bike");
+            }
+            if lane_types
+                .iter()
+                .any(|lt| *lt == LaneType::Sidewalk || *lt == LaneType::Shoulder)
+            {
+                modes.push("walk");
+            }
+            if lane_types.contains(&LaneType::Bus) || lane_types.contains(&LaneType::LightRail) {
+                modes.push("pt");
+            }
+            if modes.is_empty() {
+                continue;
+            }
+            let (from, to) = if dir == Direction::Fwd {
+                (r.src_i, r.dst_i)
+            } else {
+                (r.dst_i, r.src_i)
+            };
+            writeln!(
+                f,
+                r#"    <link id="{}" from="{}" to="{}" length="{:.2}" freespeed="{:.2}" capacity="{}" permlanes="{}" oneway="1" modes="{}"/>"#,
+                link_id(r.id.0, dir),
+                from.0,
+                to.0,
+                r.center_pts.length().inner_meters(),
+                r.speed_limit.inner_meters_per_second(),
+                1800 * num_driving.max(1),
+                num_driving.max(1),
+                modes.join(",")
+            )?;
+        }
+    }
+    writeln!(f, "  </links>")?;
+    writeln!(f, "</network>")?;
+    Ok(())
+}
+
+/// Turns simulation events into MATSim events as the simulation runs.
+#[derive(Clone)]
+pub(crate) struct MatsimEvents {
+    lines: Vec<String>,
+    /// The mode of the leg each trip is currently on, and the link it was last seen on
+    open_legs: BTreeMap<TripID, (&'static str, String)>,
+    last_trip: BTreeMap<PersonID, TripID>,
+    /// The link every vehicle in traffic is on. Parked vehicles and ones that left the map
+    /// aren't here.
+    vehicle_links: BTreeMap<CarID, String>,
+}
+
+impl MatsimEvents {
+    pub fn new() -> MatsimEvents {
+        MatsimEvents {
+            lines: Vec::new(),
+            open_legs: BTreeMap::new(),
+            last_trip: BTreeMap::new(),
+            vehicle_links: BTreeMap::new(),
+        }
+    }
+
+    pub fn handle_event(&mut self, time: Time, ev: &Event, map: &Map, trips: &TripManager) {
+        match ev {
+            Event::PersonLeavesBuilding(p, b) => {
+                let act_type = self.activity_type(*p, trips);
+                self.push(
+                    time,
+                    "actend",
+                    vec![
+                        ("person", p.0.to_string()),
+                        ("link", lane_link(map, map.get_b(*b).sidewalk_pos.lane())),
+                        ("actType", act_type),
+                    ],
+                );
+            }
+            Event::PersonEntersBuilding(p, b) => {
+                let act_type = self.activity_type(*p, trips);
+                self.push(
+                    time,
+                    "actstart",
+                    vec![
+                        ("person", p.0.to_string()),
+                        ("link", lane_link(map, map.get_b(*b).sidewalk_pos.lane())),
+                        ("actType", act_type),
+                    ],
+                );
+            }
+            Event::TripPhaseStarting(trip, person, req, phase) => {
+                self.last_trip.insert(*person, *trip);
+                let mode = match phase {
+                    TripPhaseType::Driving | TripPhaseType::Parking => Some("car"),
+                    TripPhaseType::Walking => Some("walk"),
+                    TripPhaseType::Biking => Some("bike"),
+                    TripPhaseType::WaitingForBus(_, _) | TripPhaseType::RidingBus(_, _, _) => {
+                        Some("pt")
+                    }
+                    TripPhaseType::Escorted => Some("ride"),
+                    TripPhaseType::Cancelled
+                    | TripPhaseType::Finished
+                    | TripPhaseType::DelayedStart => None,
+                };
+                let link = req.as_ref().map(|r| lane_link(map, r.start.lane()));
+                if let Some(old_mode) = self.open_legs.get(trip).map(|(m, _)| *m) {
+                    // Waiting for a bus and riding it are the same leg, and so is searching for
+                    // parking after driving
+                    if Some(old_mode) == mode {
+                        return;
+                    }
+                    self.close_leg(time, *trip, *person, link.clone());
+                }
+                if let Some(mode) = mode {
+                    let link = link.unwrap_or_else(String::new);
+                    self.push(
+                        time,
+                        "departure",
+                        vec![
+                            ("person", person.0.to_string()),
+                            ("link", link.clone()),
+                            ("legMode", mode.to_string()),
+                        ],
+                    );
+                    self.open_legs.insert(*trip, (mode, link));
+                }
+            }
+            Event::TripFinished { trip, .. } | Event::TripCancelled(trip, _) => {
+                if let Some(person) = trips.trip_to_person(*trip) {
+                    self.close_leg(time, *trip, person, None);
+                }
+            }
+            Event::AgentEntersTraversable(agent, Traversable::Lane(l), _) => {
+                let link = lane_link(map, *l);
+                if let Some(trip) = trips.agent_to_trip(*agent) {
+                    if let Some(leg) = self.open_legs.get_mut(&trip) {
+                        leg.1 = link.clone();
+                    }
+                }
+                if let AgentID::Car(car) = agent {
+                    let vehicle = vehicle_id(*car);
+                    match self.vehicle_links.insert(*car, link.clone()) {
+                        Some(prev) => {
+                            if prev == link {
+                                return;
+                            }
+                            self.push(
+                                time,
+                                "left link",
+                                vec![("vehicle", vehicle.clone()), ("link", prev)],
+                            );
+                        }
+                        None => {
+                            self.push(
+                                time,
+                                "vehicle enters traffic",
+                                vec![("vehicle", vehicle.clone()), ("link", link.clone())],
+                            );
+                        }
+                    }
+                    self.push(
+                        time,
+                        "entered link",
+                        vec![("vehicle", vehicle), ("link", link)],
+                    );
+                }
+            }
+            Event::CarReachedParkingSpot(car, _)
+            | Event::BikeStoppedAtSidewalk(car, _)
+            | Event::PersonLeavesMap(_, Some(AgentID::Car(car)), _) => {
+                self.vehicle_leaves(time, *car);
+            }
+            Event::PassengerBoardsTransit(person, bus, _, _, _) => {
+                self.push(
+                    time,
+                    "PersonEntersVehicle",
+                    vec![
+                        ("person", person.0.to_string()),
+                        ("vehicle", vehicle_id(*bus)),
+                    ],
+                );
+            }
+            Event::PassengerAlightsTransit(person, bus, _, _) => {
+                self.push(
+                    time,
+                    "PersonLeavesVehicle",
+                    vec![
+                        ("person", person.0.to_string()),
+                        ("vehicle", vehicle_id(*bus)),
+                    ],
+                );
+            }
+            _ => {}
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        let mut f = BufWriter::new(File::create(path)?);
+        writeln!(f, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(f, r#"<events version="1.0">"#)?;
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+        writeln!(f, "</events>")?;
+        Ok(())
+    }
+
+    fn close_leg(&mut self, time: Time, trip: TripID, person: PersonID, link: Option<String>) {
+        if let Some((mode, last_link)) = self.open_legs.remove(&trip) {
+            self.push(
+                time,
+                "arrival",
+                vec![
+                    ("person", person.0.to_string()),
+                    ("link", link.unwrap_or(last_link)),
+                    ("legMode", mode.to_string()),
+                ],
+            );
+        }
+    }
+
+    /// Forgets where the vehicle is, so its next trip starts by entering traffic again.
+    fn vehicle_leaves(&mut self, time: Time, car: CarID) {
+        if let Some(link) = self.vehicle_links.remove(&car) {
+            self.push(
+                time,
+                "vehicle leaves traffic",
+                vec![("vehicle", vehicle_id(car)), ("link", link)],
+            );
+        }
+    }
+
+    /// Activities are named after the purpose of the trip leading to them. Before the first trip,
+    /// people are assumed to be at home.
+    fn activity_type(&self, person: PersonID, trips: &TripManager) -> String {
+        match self.last_trip.get(&person) {
+            Some(trip) => trips.trip_info(*trip).purpose.to_string().to_lowercase(),
+            None => "home".to_string(),
+        }
+    }
+
+    fn push(&mut self, time: Time, event_type: &str, attribs: Vec<(&str, String)>) {
+        let mut line = format!(
+            r#"  <event time="{:.1}" type="{}""#,
+            time.inner_seconds(),
+            event_type
+        );
+        for (k, v) in attribs {
+            line.push_str(&format!(r#" {}="{}""#, k, v));
+        }
+        line.push_str("/>");
+        self.lines.push(line);
+    }
+}
+
+fn link_id(r: usize, dir: Direction) -> String {
+    match dir {
+        Direction::Fwd => format!("{}", r),
+        Direction::Back => format!("-{}", r),
+    }
+}
+
+fn lane_link(map: &Map, l: LaneID) -> String {
+    let r = map.get_parent(l);
+    link_id(r.id.0, r.dir(l))
+}
+
+fn vehicle_id(car: CarID) -> String {
+    match car.1 {
+        VehicleType::Car => format!("car_{}", car.0),
+        VehicleType::Bus => format!("bus_{}", car.0),
+        VehicleType::Train => format!("train_{}", car.0),
+        VehicleType::Bike => format!("bike_{}", car.0),
+    }
+}
+
+fn flip_y(map: &Map, pt: Pt2D) -> Pt2D {
+    Pt2D::new(pt.x(), map.get_bounds().max_y - pt.y())
+}
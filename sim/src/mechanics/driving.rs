@@ -10,10 +10,10 @@ use crate::mechanics::car::{Car, CarState};
 use crate::mechanics::Queue;
 use crate::sim::Ctx;
 use crate::{
-    ActionAtEnd, AgentID, AgentProperties, CarID, Command, CreateCar, DelayCause, DistanceInterval,
-    DrawCarInput, Event, IntersectionSimState, ParkedCar, ParkingSim, ParkingSpot, PersonID,
-    SimOptions, TimeInterval, TransitSimState, TripID, TripManager, UnzoomedAgent, Vehicle,
-    WalkingSimState, FOLLOWING_DISTANCE,
+    ActionAtEnd, AgentID, AgentPosition, AgentProperties, CarID, Command, CreateCar, DelayCause,
+    DistanceInterval, DrawCarInput, Event, IntersectionSimState, ParkedCar, ParkingSim,
+    ParkingSpot, PersonID, SimOptions, TimeInterval, TransitSimState, TripID, TripManager,
+    UnzoomedAgent, Vehicle, WalkingSimState, FOLLOWING_DISTANCE,
 };
 
 const TIME_TO_WAIT_AT_BUS_STOP: Duration = Duration::const_seconds(10.0);
@@ -73,7 +73,7 @@ impl DrivingSimState {
         }
 
         for l in map.all_lanes() {
-            if l.lane_type.is_for_moving_vehicles() {
+            if l.is_ever_for_moving_vehicles() {
                 let q = Queue::new(Traversable::Lane(l.id), map);
                 sim.queues.insert(q.id, q);
             }
@@ -134,6 +134,7 @@ impl DrivingSimState {
                 // Have to do this early
                 if car.router.last_step() {
                     match car.router.maybe_handle_end(
+                        now,
                         start_dist,
                         &car.vehicle,
                         ctx.parking,
@@ -303,6 +304,7 @@ impl DrivingSimState {
                     car.state = CarState::WaitingToAdvance { blocked_since: now };
                     if self.recalc_lanechanging {
                         car.router.opportunistically_lanechange(
+                            now,
                             &self.queues,
                             ctx.map,
                             self.handle_uber_turns,
@@ -318,6 +320,7 @@ impl DrivingSimState {
                     // the next loop will pick that up. Just trigger the side effect of choosing an
                     // end_dist.
                     car.router.maybe_handle_end(
+                        now,
                         front,
                         &car.vehicle,
                         ctx.parking,
@@ -331,7 +334,7 @@ impl DrivingSimState {
                     .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
             }
             CarState::IdlingAtStop(dist, _) => {
-                car.router = transit.bus_departed_from_stop(car.vehicle.id, ctx.map);
+                car.router = transit.bus_departed_from_stop(car.vehicle.id, now, ctx.map);
                 self.events
                     .push(Event::PathAmended(car.router.get_path().clone()));
                 car.state = car.crossing_state(dist, now, ctx.map);
@@ -419,6 +422,7 @@ impl DrivingSimState {
                 // way, until laggy_head is None.
 
                 let last_step = car.router.advance(
+                    now,
                     &car.vehicle,
                     ctx.parking,
                     ctx.map,
@@ -490,6 +494,7 @@ impl DrivingSimState {
             | CarState::WaitingToAdvance { .. } => unreachable!(),
             CarState::Queued { blocked_since } => {
                 match car.router.maybe_handle_end(
+                    now,
                     our_dist,
                     &car.vehicle,
                     ctx.parking,
@@ -611,6 +616,7 @@ impl DrivingSimState {
                     vehicle: car.vehicle.clone(),
                     spot,
                     parked_since: now,
+                    trip_arrival: Some(now),
                 });
                 trips.car_reached_parking_spot(
                     now,
@@ -853,6 +859,7 @@ impl DrivingSimState {
                                 follower.state = CarState::WaitingToAdvance { blocked_since };
                                 if self.recalc_lanechanging && ctx.handling_live_edits.is_none() {
                                     follower.router.opportunistically_lanechange(
+                                        now,
                                         &self.queues,
                                         ctx.map,
                                         self.handle_uber_turns,
@@ -887,7 +894,7 @@ impl DrivingSimState {
         // Calculate all queues that should exist now.
         let mut new_queues = HashSet::new();
         for l in map.all_lanes() {
-            if l.lane_type.is_for_moving_vehicles() {
+            if l.is_ever_for_moving_vehicles() {
                 new_queues.insert(Traversable::Lane(l.id));
             }
         }
@@ -961,6 +968,24 @@ impl DrivingSimState {
         result
     }
 
+    /// Vehicles waiting to spawn aren't included, since they're not on the map yet.
+    pub fn get_all_positions(&self, now: Time) -> Vec<AgentPosition> {
+        let mut result = Vec::new();
+        for queue in self.queues.values() {
+            if queue.cars.is_empty() {
+                continue;
+            }
+            for (c, dist) in queue.get_car_positions(now, &self.cars, &self.queues) {
+                result.push(AgentPosition {
+                    id: AgentID::Car(c),
+                    on: queue.id,
+                    dist,
+                });
+            }
+        }
+        result
+    }
+
     pub fn does_car_exist(&self, id: CarID) -> bool {
         self.cars.contains_key(&id)
     }
@@ -7,13 +7,20 @@ use abstutil::{
     deserialize_btreemap, deserialize_multimap, retain_btreemap, serialize_btreemap,
     serialize_multimap, MultiMap,
 };
-use geom::{Distance, PolyLine, Pt2D};
+use geom::{Distance, PolyLine, Pt2D, Time};
 use map_model::{
     BuildingID, Lane, LaneID, LaneType, Map, OffstreetParking, ParkingLotID, PathConstraints,
     PathStep, Position, Traversable, TurnID,
 };
 
-use crate::{CarID, CarStatus, DrawCarInput, Event, ParkedCar, ParkingSpot, PersonID, Vehicle};
+use crate::{
+    CarID, CarStatus, DrawCarInput, Event, ParkedCar, ParkingPolicy, ParkingSpot, PersonID,
+    SimOptions, Vehicle,
+};
+
+/// When parking has a price, drivers looking for a spot keep searching this much further past the
+/// first free spot, to find something cheaper.
+const EXTRA_PRICED_PARKING_SEARCH: Distance = Distance::const_meters(400.0);
 
 /// Manages the state of parked cars. There are two implementations:
 /// - NormalParkingSimState allows only one vehicle per ParkingSpot defined in the map
@@ -24,13 +31,17 @@ pub trait ParkingSim {
     /// Returns any cars that got very abruptly evicted from existence, and also cars actively
     /// moving into a deleted spot.
     fn handle_live_edits(&mut self, map: &Map) -> (Vec<ParkedCar>, Vec<CarID>);
+    /// Some parking lanes don't allow parking during part of the day. Recalculate which ones are
+    /// closed right now. Cars already parked there stay, but nobody new may park.
+    fn update_time_windows(&mut self, now: Time, map: &Map);
     fn get_free_onstreet_spots(&self, l: LaneID) -> Vec<ParkingSpot>;
     fn get_free_offstreet_spots(&self, b: BuildingID) -> Vec<ParkingSpot>;
     fn get_free_lot_spots(&self, pl: ParkingLotID) -> Vec<ParkingSpot>;
     fn reserve_spot(&mut self, spot: ParkingSpot, car: CarID);
     /// Needed when abruptly deleting a car, in case they're being deleted during their last step.
     fn unreserve_spot(&mut self, car: CarID);
-    fn remove_parked_car(&mut self, p: ParkedCar);
+    /// Also charges the car for its stay, if the spot isn't free.
+    fn remove_parked_car(&mut self, p: ParkedCar, now: Time, map: &Map);
     fn add_parked_car(&mut self, p: ParkedCar);
     fn get_draw_cars(&self, id: LaneID, map: &Map) -> Vec<DrawCarInput>;
     fn get_draw_cars_in_lots(&self, id: LaneID, map: &Map) -> Vec<DrawCarInput>;
@@ -49,9 +60,15 @@ pub trait ParkingSim {
         // Either the building where a seeded car starts or the target of a trip. For filtering
         // private spots.
         target: BuildingID,
+        now: Time,
         map: &Map,
     ) -> Vec<(ParkingSpot, Position)>;
     fn spot_to_driving_pos(&self, spot: ParkingSpot, vehicle: &Vehicle, map: &Map) -> Position;
+    /// How much a driver heading to some target dislikes a spot, accounting for price and walking
+    /// distance. None means parking is free everywhere, so drivers should just pick the closest
+    /// spot.
+    fn spot_cost(&self, spot: ParkingSpot, target: BuildingID, now: Time, map: &Map)
+        -> Option<f64>;
     fn spot_to_sidewalk_pos(&self, spot: ParkingSpot, map: &Map) -> Position;
     fn get_owner_of_car(&self, id: CarID) -> Option<PersonID>;
     fn lookup_parked_car(&self, id: CarID) -> Option<&ParkedCar>;
@@ -66,6 +83,7 @@ pub trait ParkingSim {
         start: LaneID,
         vehicle: &Vehicle,
         target: BuildingID,
+        now: Time,
         map: &Map,
     ) -> Option<(Vec<PathStep>, ParkingSpot, Position)>;
     fn collect_events(&mut self) -> Vec<Event>;
@@ -83,11 +101,11 @@ pub enum ParkingSimState {
 impl ParkingSimState {
     /// Counterintuitive: any spots located in blackholes are just not represented here. If somebody
     /// tries to drive from a blackholed spot, they couldn't reach most places.
-    pub fn new(map: &Map, infinite: bool) -> ParkingSimState {
-        if infinite {
+    pub fn new(map: &Map, opts: &SimOptions) -> ParkingSimState {
+        if opts.infinite_parking {
             ParkingSimState::Infinite(InfiniteParkingSimState::new(map))
         } else {
-            ParkingSimState::Normal(NormalParkingSimState::new(map))
+            ParkingSimState::Normal(NormalParkingSimState::new(map, opts.parking_policy.clone()))
         }
     }
 
@@ -125,6 +143,8 @@ pub struct NormalParkingSimState {
         deserialize_with = "deserialize_multimap"
     )]
     driving_to_parking_lanes: MultiMap<LaneID, LaneID>,
+    // Parking lanes that currently act as a different lane type, due to time windows
+    closed_onstreet_lanes: BTreeSet<LaneID>,
 
     // Off-street
     num_spots_per_offstreet: BTreeMap<BuildingID, usize>,
@@ -143,11 +163,13 @@ pub struct NormalParkingSimState {
     )]
     driving_to_lots: MultiMap<LaneID, ParkingLotID>,
 
+    policy: ParkingPolicy,
+
     events: Vec<Event>,
 }
 
 impl NormalParkingSimState {
-    fn new(map: &Map) -> NormalParkingSimState {
+    fn new(map: &Map, policy: ParkingPolicy) -> NormalParkingSimState {
         let mut sim = NormalParkingSimState {
             parked_cars: BTreeMap::new(),
             occupants: BTreeMap::new(),
@@ -155,11 +177,14 @@ impl NormalParkingSimState {
 
             onstreet_lanes: BTreeMap::new(),
             driving_to_parking_lanes: MultiMap::new(),
+            closed_onstreet_lanes: BTreeSet::new(),
             num_spots_per_offstreet: BTreeMap::new(),
             driving_to_offstreet: MultiMap::new(),
             num_spots_per_lot: BTreeMap::new(),
             driving_to_lots: MultiMap::new(),
 
+            policy,
+
             events: Vec::new(),
         };
         for l in map.all_lanes() {
@@ -168,6 +193,7 @@ impl NormalParkingSimState {
                 sim.onstreet_lanes.insert(lane.parking_lane, lane);
             }
         }
+        sim.update_time_windows(Time::START_OF_DAY, map);
         for b in map.all_buildings() {
             if let Some((pos, _)) = b.driving_connection(map) {
                 if !map.get_l(pos.lane()).driving_blackhole {
@@ -194,7 +220,7 @@ impl NormalParkingSimState {
 impl ParkingSim for NormalParkingSimState {
     fn handle_live_edits(&mut self, map: &Map) -> (Vec<ParkedCar>, Vec<CarID>) {
         let (filled_before, _) = self.get_all_parking_spots();
-        let new = NormalParkingSimState::new(map);
+        let new = NormalParkingSimState::new(map, self.policy.clone());
         let (_, avail_after) = new.get_all_parking_spots();
         let avail_after: BTreeSet<ParkingSpot> = avail_after.into_iter().collect();
 
@@ -232,8 +258,20 @@ impl ParkingSim for NormalParkingSimState {
         (evicted, moving_into_deleted_spot)
     }
 
+    fn update_time_windows(&mut self, now: Time, map: &Map) {
+        self.closed_onstreet_lanes = self
+            .onstreet_lanes
+            .keys()
+            .filter(|l| map.get_l(**l).lane_type_at(now) != LaneType::Parking)
+            .cloned()
+            .collect();
+    }
+
     fn get_free_onstreet_spots(&self, l: LaneID) -> Vec<ParkingSpot> {
         let mut spots: Vec<ParkingSpot> = Vec::new();
+        if self.closed_onstreet_lanes.contains(&l) {
+            return spots;
+        }
         if let Some(lane) = self.onstreet_lanes.get(&l) {
             for spot in lane.spots() {
                 if self.is_free(spot) {
@@ -288,7 +326,7 @@ impl ParkingSim for NormalParkingSimState {
         retain_btreemap(&mut self.reserved_spots, |_, c| car != *c);
     }
 
-    fn remove_parked_car(&mut self, p: ParkedCar) {
+    fn remove_parked_car(&mut self, p: ParkedCar, now: Time, map: &Map) {
         if self.parked_cars.remove(&p.vehicle.id).is_none() {
             panic!("remove_parked_car {:?} missing from parked_cars", p);
         }
@@ -297,6 +335,14 @@ impl ParkingSim for NormalParkingSimState {
         }
         self.events
             .push(Event::CarLeftParkingSpot(p.vehicle.id, p.spot));
+
+        if let Some(arrival) = p.trip_arrival {
+            let fee = self.policy.fee(p.spot, arrival, now, map);
+            if fee > 0.0 {
+                self.events
+                    .push(Event::CarPaidForParking(p.vehicle.id, p.spot, fee));
+            }
+        }
     }
 
     fn add_parked_car(&mut self, p: ParkedCar) {
@@ -421,13 +467,18 @@ impl ParkingSim for NormalParkingSimState {
         // Either the building where a seeded car starts or the target of a trip. For filtering
         // private spots.
         target: BuildingID,
+        now: Time,
         map: &Map,
     ) -> Vec<(ParkingSpot, Position)> {
         let mut candidates = Vec::new();
 
         for l in self.driving_to_parking_lanes.get(driving_pos.lane()) {
+            if self.closed_onstreet_lanes.contains(l) {
+                continue;
+            }
             for spot in self.onstreet_lanes[l].spots() {
                 if self.is_free(spot)
+                    && self.policy.allows_expected_stay(spot, now, map)
                     && driving_pos.dist_along()
                         < self.spot_to_driving_pos(spot, vehicle, map).dist_along()
                 {
@@ -445,7 +496,7 @@ impl ParkingSim for NormalParkingSimState {
             if driving_pos.dist_along() < *bldg_dist {
                 for idx in 0..self.num_spots_per_offstreet[b] {
                     let spot = ParkingSpot::Offstreet(*b, idx);
-                    if self.is_free(spot) {
+                    if self.is_free(spot) && self.policy.allows_expected_stay(spot, now, map) {
                         candidates.push(spot);
                     }
                 }
@@ -457,7 +508,7 @@ impl ParkingSim for NormalParkingSimState {
             if driving_pos.dist_along() < lot_dist {
                 for idx in 0..self.num_spots_per_lot[&pl] {
                     let spot = ParkingSpot::Lot(*pl, idx);
-                    if self.is_free(spot) {
+                    if self.is_free(spot) && self.policy.allows_expected_stay(spot, now, map) {
                         candidates.push(spot);
                     }
                 }
@@ -485,6 +536,26 @@ impl ParkingSim for NormalParkingSimState {
         }
     }
 
+    fn spot_cost(
+        &self,
+        spot: ParkingSpot,
+        target: BuildingID,
+        now: Time,
+        map: &Map,
+    ) -> Option<f64> {
+        if self.policy.is_free() {
+            return None;
+        }
+        let walking_dist = self
+            .spot_to_sidewalk_pos(spot, map)
+            .pt(map)
+            .dist_to(map.get_b(target).sidewalk_pos.pt(map));
+        Some(
+            self.policy
+                .generalized_cost(spot, walking_dist, Distance::ZERO, now, map),
+        )
+    }
+
     fn spot_to_sidewalk_pos(&self, spot: ParkingSpot, map: &Map) -> Position {
         match spot {
             ParkingSpot::Onstreet(l, idx) => {
@@ -541,6 +612,7 @@ impl ParkingSim for NormalParkingSimState {
         start: LaneID,
         vehicle: &Vehicle,
         target: BuildingID,
+        now: Time,
         map: &Map,
     ) -> Option<(Vec<PathStep>, ParkingSpot, Position)> {
         let mut backrefs: HashMap<LaneID, TurnID> = HashMap::new();
@@ -550,35 +622,56 @@ impl ParkingSim for NormalParkingSimState {
         let mut queue: BinaryHeap<(Distance, LaneID)> = BinaryHeap::new();
         queue.push((Distance::ZERO, start));
 
+        // When parking isn't free, don't just take the first spot found. Keep searching a bit
+        // further, then pick the one with the lowest cost.
+        let mut best: Option<(f64, LaneID, ParkingSpot, Position)> = None;
+        let mut search_until: Option<Distance> = None;
+
         while !queue.is_empty() {
             let (dist_so_far, current) = queue.pop().unwrap();
+            if let Some(limit) = search_until {
+                if -dist_so_far > limit {
+                    break;
+                }
+            }
             // If the current lane has a spot open, we wouldn't be asking. This can happen if a spot
             // opens up on the 'start' lane, but behind the car.
             if current != start {
-                // Pick the closest to the start of the lane, since that's closest to where we came
-                // from
-                if let Some((spot, pos)) = self
-                    .get_all_free_spots(Position::start(current), vehicle, target, map)
-                    .into_iter()
-                    .min_by_key(|(_, pos)| pos.dist_along())
-                {
-                    let mut steps = vec![PathStep::Lane(current)];
-                    let mut current = current;
-                    loop {
-                        if current == start {
-                            // Don't include PathStep::Lane(start)
-                            steps.pop();
-                            steps.reverse();
-                            return Some((steps, spot, pos));
+                let candidates =
+                    self.get_all_free_spots(Position::start(current), vehicle, target, now, map);
+                if self.policy.is_free() {
+                    // Pick the closest to the start of the lane, since that's closest to where we
+                    // came from
+                    if let Some((spot, pos)) = candidates
+                        .into_iter()
+                        .min_by_key(|(_, pos)| pos.dist_along())
+                    {
+                        best = Some((0.0, current, spot, pos));
+                        break;
+                    }
+                } else {
+                    for (spot, pos) in candidates {
+                        let walking_dist = self
+                            .spot_to_sidewalk_pos(spot, map)
+                            .pt(map)
+                            .dist_to(map.get_b(target).sidewalk_pos.pt(map));
+                        let cost = self.policy.generalized_cost(
+                            spot,
+                            walking_dist,
+                            -dist_so_far + pos.dist_along(),
+                            now,
+                            map,
+                        );
+                        if best.as_ref().map(|(c, _, _, _)| cost < *c).unwrap_or(true) {
+                            best = Some((cost, current, spot, pos));
                         }
-                        let turn = backrefs[&current];
-                        steps.push(PathStep::Turn(turn));
-                        steps.push(PathStep::Lane(turn.src));
-                        current = turn.src;
+                    }
+                    if best.is_some() && search_until.is_none() {
+                        search_until = Some(-dist_so_far + EXTRA_PRICED_PARKING_SEARCH);
                     }
                 }
             }
-            for turn in map.get_turns_for(current, PathConstraints::Car) {
+            for turn in map.get_turns_for_at(current, PathConstraints::Car, now) {
                 if !backrefs.contains_key(&turn.id.dst) {
                     let dist_this_step = turn.geom.length() + map.get_l(current).length();
                     backrefs.insert(turn.id.dst, turn.id);
@@ -588,7 +681,20 @@ impl ParkingSim for NormalParkingSimState {
             }
         }
 
-        None
+        let (_, mut current, spot, pos) = best?;
+        let mut steps = vec![PathStep::Lane(current)];
+        loop {
+            if current == start {
+                // Don't include PathStep::Lane(start)
+                steps.pop();
+                steps.reverse();
+                return Some((steps, spot, pos));
+            }
+            let turn = backrefs[&current];
+            steps.push(PathStep::Turn(turn));
+            steps.push(PathStep::Lane(turn.src));
+            current = turn.src;
+        }
     }
 
     fn collect_events(&mut self) -> Vec<Event> {
@@ -792,6 +898,8 @@ impl ParkingSim for InfiniteParkingSimState {
         (Vec::new(), Vec::new())
     }
 
+    fn update_time_windows(&mut self, _: Time, _: &Map) {}
+
     fn get_free_onstreet_spots(&self, _: LaneID) -> Vec<ParkingSpot> {
         Vec::new()
     }
@@ -814,7 +922,7 @@ impl ParkingSim for InfiniteParkingSimState {
         retain_btreemap(&mut self.reserved_spots, |_, c| car != *c);
     }
 
-    fn remove_parked_car(&mut self, p: ParkedCar) {
+    fn remove_parked_car(&mut self, p: ParkedCar, _: Time, _: &Map) {
         self.parked_cars
             .remove(&p.vehicle.id)
             .expect("remove_parked_car missing from parked_cars");
@@ -876,6 +984,7 @@ impl ParkingSim for InfiniteParkingSimState {
         driving_pos: Position,
         vehicle: &Vehicle,
         target: BuildingID,
+        _: Time,
         map: &Map,
     ) -> Vec<(ParkingSpot, Position)> {
         // The target building may be blackholed, so fallback to a building on one of the
@@ -908,6 +1017,10 @@ impl ParkingSim for InfiniteParkingSimState {
         }
     }
 
+    fn spot_cost(&self, _: ParkingSpot, _: BuildingID, _: Time, _: &Map) -> Option<f64> {
+        None
+    }
+
     fn spot_to_sidewalk_pos(&self, spot: ParkingSpot, map: &Map) -> Position {
         match spot {
             ParkingSpot::Offstreet(b, _) => map.get_b(b).sidewalk_pos,
@@ -931,6 +1044,7 @@ impl ParkingSim for InfiniteParkingSimState {
         start: LaneID,
         vehicle: &Vehicle,
         target: BuildingID,
+        now: Time,
         map: &Map,
     ) -> Option<(Vec<PathStep>, ParkingSpot, Position)> {
         // TODO This impl is copied from NormalParkingSimState. Instead, we already know the
@@ -950,7 +1064,7 @@ impl ParkingSim for InfiniteParkingSimState {
                 // Pick the closest to the start of the lane, since that's closest to where we came
                 // from
                 if let Some((spot, pos)) = self
-                    .get_all_free_spots(Position::start(current), vehicle, target, map)
+                    .get_all_free_spots(Position::start(current), vehicle, target, now, map)
                     .into_iter()
                     .min_by_key(|(_, pos)| pos.dist_along())
                 {
@@ -970,7 +1084,7 @@ impl ParkingSim for InfiniteParkingSimState {
                     }
                 }
             }
-            for turn in map.get_turns_for(current, PathConstraints::Car) {
+            for turn in map.get_turns_for_at(current, PathConstraints::Car, now) {
                 if !backrefs.contains_key(&turn.id.dst) {
                     let dist_this_step = turn.geom.length() + map.get_l(current).length();
                     backrefs.insert(turn.id.dst, turn.id);
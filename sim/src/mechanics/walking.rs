@@ -11,10 +11,10 @@ use map_model::{
 
 use crate::sim::Ctx;
 use crate::{
-    AgentID, AgentProperties, Command, CommutersVehiclesCounts, CreatePedestrian, DistanceInterval,
-    DrawPedCrowdInput, DrawPedestrianInput, Event, IntersectionSimState, ParkedCar, ParkingSpot,
-    PedCrowdLocation, PedestrianID, PersonID, Scheduler, SidewalkPOI, SidewalkSpot, TimeInterval,
-    TransitSimState, TripID, TripManager, UnzoomedAgent,
+    AgentID, AgentPosition, AgentProperties, Command, CommutersVehiclesCounts, CreatePedestrian,
+    DistanceInterval, DrawPedCrowdInput, DrawPedestrianInput, Event, IntersectionSimState,
+    ParkedCar, ParkingSpot, PedCrowdLocation, PedestrianID, PersonID, Scheduler, SidewalkPOI,
+    SidewalkSpot, TimeInterval, TransitSimState, TripID, TripManager, UnzoomedAgent,
 };
 
 const TIME_TO_START_BIKING: Duration = Duration::const_seconds(30.0);
@@ -426,6 +426,17 @@ impl WalkingSimState {
         peds
     }
 
+    pub fn get_all_positions(&self, now: Time, map: &Map) -> Vec<AgentPosition> {
+        self.peds
+            .values()
+            .map(|ped| AgentPosition {
+                id: AgentID::Pedestrian(ped.id),
+                on: ped.path.current_step().as_traversable(),
+                dist: ped.get_dist_along(now, map),
+            })
+            .collect()
+    }
+
     pub fn get_draw_peds_on(
         &self,
         now: Time,
@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use abstutil::{deserialize_btreemap, serialize_btreemap};
+use geom::{Distance, Duration, Speed, Time};
+use map_model::{LaneID, Map, OffstreetParking, ParkingLotID, TimeWindow};
+
+use crate::ParkingSpot;
+
+/// Prices and time limits for parking, and how drivers weigh them when picking a spot. Private
+/// spots in a building are always free and unlimited for the people that can use them.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ParkingPolicy {
+    pub onstreet: ParkingRate,
+    pub lots: ParkingRate,
+    /// Publicly accessible garages inside buildings
+    pub garages: ParkingRate,
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    pub onstreet_overrides: BTreeMap<LaneID, ParkingRate>,
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    pub lot_overrides: BTreeMap<ParkingLotID, ParkingRate>,
+
+    /// How many dollars a driver values an hour of their time. This trades off walking further
+    /// against paying for a closer spot.
+    pub value_of_time_per_hour: f64,
+    /// Drivers don't know exactly how long they'll stay somewhere, so they assume this much when
+    /// judging prices and time limits.
+    pub expected_stay: Duration,
+    /// Dollars charged when a car leaves after staying past the time limit in effect when it
+    /// arrived.
+    #[serde(default)]
+    pub overstay_fine: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ParkingRate {
+    /// The hourly price outside of any `periods`
+    pub dollars_per_hour: f64,
+    /// The time limit outside of any `periods`. Spots with a time limit shorter than a driver's
+    /// expected stay won't be considered.
+    pub max_stay: Option<Duration>,
+    /// Prices and time limits that only apply during part of the day, like metered parking from
+    /// 8am to 6pm. If windows overlap, the first one wins.
+    #[serde(default)]
+    pub periods: Vec<ParkingPeriod>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ParkingPeriod {
+    pub window: TimeWindow,
+    pub dollars_per_hour: f64,
+    pub max_stay: Option<Duration>,
+}
+
+impl ParkingRate {
+    pub const FREE: ParkingRate = ParkingRate {
+        dollars_per_hour: 0.0,
+        max_stay: None,
+        periods: Vec::new(),
+    };
+
+    /// The hourly price and time limit in effect at some time.
+    pub fn at(&self, now: Time) -> (f64, Option<Duration>) {
+        for p in &self.periods {
+            if p.window.contains(now) {
+                return (p.dollars_per_hour, p.max_stay);
+            }
+        }
+        (self.dollars_per_hour, self.max_stay)
+    }
+
+    /// The price of parking from `start` to `end`, accounting for prices changing during the day.
+    pub fn price(&self, start: Time, end: Time) -> f64 {
+        // Split the stay everywhere the price might change, then charge each piece at its own
+        // price.
+        let day = Duration::hours(24);
+        let mut cuts = vec![start, end];
+        let first_day = (start.inner_seconds() / day.inner_seconds()).floor();
+        let last_day = (end.inner_seconds() / day.inner_seconds()).floor();
+        let mut offset = first_day;
+        while offset <= last_day {
+            for p in &self.periods {
+                for t in vec![p.window.start, p.window.end] {
+                    let t = t + day * offset;
+                    if t > start && t < end {
+                        cuts.push(t);
+                    }
+                }
+            }
+            offset += 1.0;
+        }
+        cuts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        cuts.windows(2)
+            .map(|pair| self.at(pair[0]).0 * (pair[1] - pair[0]).inner_seconds() / 3600.0)
+            .sum()
+    }
+}
+
+// Drivers don't know their exact route to the destination, so use a rough speed for the time spent
+// walking from a spot or driving further to reach it.
+const WALKING_SPEED: Speed = Speed::const_meters_per_second(1.34);
+const CRUISING_SPEED: Speed = Speed::const_meters_per_second(6.7);
+
+impl ParkingPolicy {
+    /// Parking is free and unlimited everywhere.
+    pub fn free() -> ParkingPolicy {
+        ParkingPolicy {
+            onstreet: ParkingRate::FREE,
+            lots: ParkingRate::FREE,
+            garages: ParkingRate::FREE,
+            onstreet_overrides: BTreeMap::new(),
+            lot_overrides: BTreeMap::new(),
+            value_of_time_per_hour: 15.0,
+            expected_stay: Duration::hours(2),
+            overstay_fine: 0.0,
+        }
+    }
+
+    /// If there are no prices or limits anywhere, drivers just take the first spot they find.
+    pub fn is_free(&self) -> bool {
+        self.onstreet == ParkingRate::FREE
+            && self.lots == ParkingRate::FREE
+            && self.garages == ParkingRate::FREE
+            && self
+                .onstreet_overrides
+                .values()
+                .chain(self.lot_overrides.values())
+                .all(|rate| *rate == ParkingRate::FREE)
+            && self.overstay_fine == 0.0
+    }
+
+    pub fn rate(&self, spot: ParkingSpot, map: &Map) -> ParkingRate {
+        match spot {
+            ParkingSpot::Onstreet(l, _) => self
+                .onstreet_overrides
+                .get(&l)
+                .cloned()
+                .unwrap_or_else(|| self.onstreet.clone()),
+            ParkingSpot::Lot(pl, _) => self
+                .lot_overrides
+                .get(&pl)
+                .cloned()
+                .unwrap_or_else(|| self.lots.clone()),
+            ParkingSpot::Offstreet(b, _) => match map.get_b(b).parking {
+                OffstreetParking::PublicGarage(_, _) => self.garages.clone(),
+                OffstreetParking::Private(_, _) => ParkingRate::FREE,
+            },
+        }
+    }
+
+    /// How much somebody who arrived at a spot owes when leaving, including any fine for staying
+    /// past the time limit.
+    pub fn fee(&self, spot: ParkingSpot, arrival: Time, now: Time, map: &Map) -> f64 {
+        let rate = self.rate(spot, map);
+        let mut fee = rate.price(arrival, now);
+        if let Some(max) = rate.at(arrival).1 {
+            if now - arrival > max {
+                fee += self.overstay_fine;
+            }
+        }
+        fee
+    }
+
+    /// Can a driver arriving now and expecting to stay the usual amount of time use this spot?
+    pub fn allows_expected_stay(&self, spot: ParkingSpot, now: Time, map: &Map) -> bool {
+        match self.rate(spot, map).at(now).1 {
+            Some(max) => max >= self.expected_stay,
+            None => true,
+        }
+    }
+
+    /// The generalized cost, in dollars, of parking in a spot: the fee for the expected stay, plus
+    /// the value of time spent walking to the destination and back, plus driving further to reach
+    /// the spot.
+    pub fn generalized_cost(
+        &self,
+        spot: ParkingSpot,
+        walking_dist: Distance,
+        extra_driving_dist: Distance,
+        now: Time,
+        map: &Map,
+    ) -> f64 {
+        let walking = (walking_dist / WALKING_SPEED) * 2.0;
+        let driving = extra_driving_dist / CRUISING_SPEED;
+        self.rate(spot, map).price(now, now + self.expected_stay)
+            + self.value_of_time_per_hour * (walking + driving).inner_seconds() / 3600.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metered() -> ParkingRate {
+        ParkingRate {
+            dollars_per_hour: 0.0,
+            max_stay: None,
+            periods: vec![ParkingPeriod {
+                window: TimeWindow {
+                    start: Time::START_OF_DAY + Duration::hours(8),
+                    end: Time::START_OF_DAY + Duration::hours(18),
+                },
+                dollars_per_hour: 2.0,
+                max_stay: Some(Duration::hours(2)),
+            }],
+        }
+    }
+
+    fn hour(h: f64) -> Time {
+        Time::START_OF_DAY + Duration::minutes(60) * h
+    }
+
+    #[test]
+    fn test_time_of_day_prices() {
+        let rate = metered();
+        assert_eq!(rate.at(hour(7.0)), (0.0, None));
+        assert_eq!(rate.at(hour(8.0)), (2.0, Some(Duration::hours(2))));
+        assert_eq!(rate.at(hour(18.0)), (0.0, None));
+
+        // Entirely overnight
+        assert_eq!(rate.price(hour(19.0), hour(31.0)), 0.0);
+        // Straddling the start and end of metering
+        assert_eq!(rate.price(hour(7.0), hour(9.5)), 3.0);
+        assert_eq!(rate.price(hour(17.0), hour(20.0)), 2.0);
+        // Across a full day and into the next morning's metering
+        assert_eq!(rate.price(hour(7.0), hour(33.0)), 20.0 + 2.0);
+    }
+
+    #[test]
+    fn test_fee_and_time_limits() {
+        let mut policy = ParkingPolicy::free();
+        assert!(policy.is_free());
+        policy.onstreet = metered();
+        policy.overstay_fine = 50.0;
+        assert!(!policy.is_free());
+
+        let spot = ParkingSpot::Onstreet(LaneID(0), 0);
+        let map = Map::blank();
+        // A stay within the limit
+        assert_eq!(policy.fee(spot, hour(9.0), hour(10.5), &map), 3.0);
+        // Overstaying the limit in effect on arrival
+        assert_eq!(policy.fee(spot, hour(9.0), hour(12.0), &map), 6.0 + 50.0);
+        // No limit applies when arriving in the evening
+        assert_eq!(policy.fee(spot, hour(19.0), hour(33.0), &map), 2.0);
+
+        // Drivers expect to stay for 2 hours by default
+        assert!(policy.allows_expected_stay(spot, hour(9.0), &map));
+        policy.expected_stay = Duration::hours(3);
+        assert!(!policy.allows_expected_stay(spot, hour(9.0), &map));
+        assert!(policy.allows_expected_stay(spot, hour(20.0), &map));
+    }
+}
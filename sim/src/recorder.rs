@@ -92,6 +92,7 @@ impl TrafficRecorder {
             map_name: map.get_name().clone(),
             people,
             only_seed_buses: None,
+            households: Vec::new(),
         }
         .save();
     }
@@ -5,7 +5,7 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
-use geom::Distance;
+use geom::{Distance, Time};
 use map_model::{
     BuildingID, IntersectionID, LaneID, Map, Path, PathConstraints, PathRequest, PathStep,
     Position, Traversable, Turn, TurnID,
@@ -158,6 +158,7 @@ impl Router {
     /// Returns the step just finished
     pub fn advance(
         &mut self,
+        now: Time,
         vehicle: &Vehicle,
         parking: &ParkingSimState,
         map: &Map,
@@ -168,6 +169,7 @@ impl Router {
         if self.last_step() {
             // Do this to trigger the side-effect of looking for parking.
             self.maybe_handle_end(
+                now,
                 Distance::ZERO,
                 vehicle,
                 parking,
@@ -180,7 +182,11 @@ impl Router {
         // Sanity check laws haven't been broken
         if let Traversable::Lane(l) = self.head() {
             let lane = map.get_l(l);
-            if !vehicle.vehicle_type.to_constraints().can_use(lane, map) {
+            if !vehicle
+                .vehicle_type
+                .to_constraints()
+                .can_use_at(lane, map, now)
+            {
                 panic!(
                     "{} just wound up on {}, a {:?} (check the OSM tags)",
                     vehicle.id, l, lane.lane_type
@@ -195,6 +201,7 @@ impl Router {
     /// step.
     pub fn maybe_handle_end(
         &mut self,
+        now: Time,
         front: Distance,
         vehicle: &Vehicle,
         parking: &ParkingSimState,
@@ -236,28 +243,40 @@ impl Router {
                         Position::new(current_lane, front),
                         vehicle,
                         target,
+                        now,
                         map,
                     );
-                    let best =
-                        if let Some((driving_pos, _)) = map.get_b(target).driving_connection(map) {
-                            if driving_pos.lane() == current_lane {
-                                let target_dist = driving_pos.dist_along();
-                                // Closest to the building
-                                candidates
-                                    .into_iter()
-                                    .min_by_key(|(_, pos)| (pos.dist_along() - target_dist).abs())
-                            } else {
-                                // Closest to the road endpoint, I guess
-                                candidates
-                                    .into_iter()
-                                    .min_by_key(|(_, pos)| pos.dist_along())
-                            }
+                    let priced = candidates
+                        .first()
+                        .map(|(s, _)| parking.spot_cost(*s, target, now, map).is_some())
+                        .unwrap_or(false);
+                    let best = if priced {
+                        // Weigh the price against walking distance
+                        candidates.into_iter().min_by(|(s1, _), (s2, _)| {
+                            let c1 = parking.spot_cost(*s1, target, now, map).unwrap();
+                            let c2 = parking.spot_cost(*s2, target, now, map).unwrap();
+                            c1.partial_cmp(&c2).unwrap()
+                        })
+                    } else if let Some((driving_pos, _)) = map.get_b(target).driving_connection(map)
+                    {
+                        if driving_pos.lane() == current_lane {
+                            let target_dist = driving_pos.dist_along();
+                            // Closest to the building
+                            candidates
+                                .into_iter()
+                                .min_by_key(|(_, pos)| (pos.dist_along() - target_dist).abs())
                         } else {
                             // Closest to the road endpoint, I guess
                             candidates
                                 .into_iter()
                                 .min_by_key(|(_, pos)| pos.dist_along())
-                        };
+                        }
+                    } else {
+                        // Closest to the road endpoint, I guess
+                        candidates
+                            .into_iter()
+                            .min_by_key(|(_, pos)| pos.dist_along())
+                    };
                     if let Some((new_spot, new_pos)) = best {
                         if let Some((t, p)) = trip_and_person {
                             events.push(Event::TripPhaseStarting(
@@ -275,8 +294,11 @@ impl Router {
                         assert!(new_pos.dist_along() >= front);
                         *spot = Some((new_spot, new_pos.dist_along()));
                     } else {
-                        if let Some((new_path_steps, new_spot, new_pos)) =
-                            parking.path_to_free_parking_spot(current_lane, vehicle, target, map)
+                        if let Some((t, _)) = trip_and_person {
+                            events.push(Event::CarStartedCruising(vehicle.id, t));
+                        }
+                        if let Some((new_path_steps, new_spot, new_pos)) = parking
+                            .path_to_free_parking_spot(current_lane, vehicle, target, now, map)
                         {
                             assert!(!new_path_steps.is_empty());
                             for step in new_path_steps {
@@ -339,6 +361,7 @@ impl Router {
 
     pub fn opportunistically_lanechange(
         &mut self,
+        now: Time,
         queues: &HashMap<Traversable, Queue>,
         map: &Map,
         handle_uber_turns: bool,
@@ -403,7 +426,7 @@ impl Router {
             let best = parent
                 .lanes_ltr()
                 .into_iter()
-                .filter(|(l, d, _)| dir == *d && constraints.can_use(map.get_l(*l), map))
+                .filter(|(l, d, _)| dir == *d && constraints.can_use_at(map.get_l(*l), map, now))
                 .filter_map(|(l, _, _)| {
                     // Make sure we can go from this lane to next_lane.
 
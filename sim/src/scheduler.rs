@@ -27,6 +27,10 @@ pub(crate) enum Command {
     Pandemic(pandemic::Cmd),
     /// The Time is redundant, just used to dedupe commands
     StartBus(BusRouteID, Time),
+    /// Some lane time window starts or ends now. The Time is just used to dedupe commands.
+    UpdateTimeWindows(Time),
+    /// Sample the position of every agent. The Time is just used to dedupe commands.
+    RecordTrajectories(Time),
 }
 
 impl Command {
@@ -50,6 +54,8 @@ impl Command {
             Command::Callback(_) => CommandType::Callback,
             Command::Pandemic(ref p) => CommandType::Pandemic(p.clone()),
             Command::StartBus(r, t) => CommandType::StartBus(*r, *t),
+            Command::UpdateTimeWindows(t) => CommandType::TimeWindows(*t),
+            Command::RecordTrajectories(t) => CommandType::Trajectories(*t),
         }
     }
 
@@ -65,6 +71,8 @@ impl Command {
             Command::Callback(_) => SimpleCommandType::Callback,
             Command::Pandemic(_) => SimpleCommandType::Pandemic,
             Command::StartBus(_, _) => SimpleCommandType::StartBus,
+            Command::UpdateTimeWindows(_) => SimpleCommandType::TimeWindows,
+            Command::RecordTrajectories(_) => SimpleCommandType::Trajectories,
         }
     }
 }
@@ -81,6 +89,8 @@ enum CommandType {
     Callback,
     Pandemic(pandemic::Cmd),
     StartBus(BusRouteID, Time),
+    TimeWindows(Time),
+    Trajectories(Time),
 }
 
 /// A more compressed form of CommandType, just used for keeping stats on event processing.
@@ -94,6 +104,8 @@ enum SimpleCommandType {
     Callback,
     Pandemic,
     StartBus,
+    TimeWindows,
+    Trajectories,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Clone)]
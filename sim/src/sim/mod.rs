@@ -1,6 +1,6 @@
 // This file has a jumbled mess of queries, setup, and mutating methods.
 
-use std::collections::{BTreeSet, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::panic;
 
 use anyhow::Result;
@@ -19,12 +19,13 @@ use map_model::{
 
 pub use self::queries::{AgentProperties, DelayCause};
 use crate::{
-    AgentID, AlertLocation, Analytics, CapSimState, CarID, Command, CreateCar, DrivingSimState,
-    Event, IntersectionSimState, OrigPersonID, PandemicModel, ParkedCar, ParkingSim,
-    ParkingSimState, ParkingSpot, Person, PersonID, Router, Scheduler, SidewalkPOI, SidewalkSpot,
-    StartTripArgs, TrafficRecorder, TransitSimState, TripID, TripInfo, TripManager, TripPhaseType,
-    Vehicle, VehicleSpec, VehicleType, WalkingSimState, BUS_LENGTH, LIGHT_RAIL_LENGTH,
-    MIN_CAR_LENGTH,
+    AgentID, AgentType, AlertLocation, Analytics, CapSimState, CarID, Command, CountLocation,
+    CountTracker, CreateCar, DrivingSimState, Event, IntersectionSimState, MatsimEvents,
+    OrigPersonID, PandemicModel, ParkedCar, ParkingPolicy, ParkingSim, ParkingSimState,
+    ParkingSpot, Person, PersonID, Router, Scheduler, SidewalkPOI, SidewalkSpot, StartTripArgs,
+    TrafficRecorder, TrajectoryRecorder, TransitSimState, TripID, TripInfo, TripManager,
+    TripPhaseType, Vehicle, VehicleSpec, VehicleType, WalkingSimState, BUS_LENGTH,
+    LIGHT_RAIL_LENGTH, MIN_CAR_LENGTH,
 };
 
 mod queries;
@@ -59,6 +60,16 @@ pub struct Sim {
     // This is created interactively, and there's no reason to preserve one for savestates.
     #[serde(skip_serializing, skip_deserializing)]
     recorder: Option<TrafficRecorder>,
+    // Only used while calibrating a scenario against counts.
+    #[serde(skip_serializing, skip_deserializing)]
+    count_tracker: Option<CountTracker>,
+    // Only used while exporting events for MATSim tools.
+    #[serde(skip_serializing, skip_deserializing)]
+    matsim_events: Option<MatsimEvents>,
+    // Trajectories are written to their own file as they're recorded, so they're not part of
+    // savestates.
+    #[serde(skip_serializing, skip_deserializing)]
+    trajectories: Option<TrajectoryRecorder>,
 
     #[serde(skip_serializing, skip_deserializing)]
     alerts: AlertHandler,
@@ -104,6 +115,8 @@ pub struct SimOptions {
     /// Ignore parking data in the map and instead treat every building as if it has unlimited
     /// capacity for vehicles.
     pub infinite_parking: bool,
+    /// Prices and time limits for parking, and how drivers choose spots based on them.
+    pub parking_policy: ParkingPolicy,
     /// Allow all agents to immediately proceed into an intersection, even if they'd hit another
     /// agent. Obviously this destroys realism of the simulation, but can be used to debug
     /// gridlock. Also implies freeform_policy, so vehicles ignore traffic signals.
@@ -118,6 +131,8 @@ pub struct SimOptions {
     /// Don't collect any analytics. Only useful for benchmarking and debugging gridlock more
     /// quickly.
     pub skip_analytics: bool,
+    /// Sample the position of every agent at this interval, so the run can be replayed later.
+    pub record_trajectories: Option<Duration>,
 }
 
 impl std::default::Default for SimOptions {
@@ -152,12 +167,26 @@ impl SimOptions {
                 })
                 .unwrap_or(AlertHandler::Print),
             infinite_parking: args.enabled("--infinite_parking"),
+            parking_policy: args
+                .optional("--parking_policy")
+                .map(|path| abstio::read_json(path, &mut Timer::throwaway()))
+                .unwrap_or_else(ParkingPolicy::free),
             disable_turn_conflicts: args.enabled("--disable_turn_conflicts"),
             cancel_drivers_delay_threshold: args
                 .optional_parse("--cancel_drivers_delay_threshold", Duration::parse),
             delay_trips_instead_of_cancelling: args
                 .optional_parse("--delay_trips_instead_of_cancelling", Duration::parse),
             skip_analytics: args.enabled("--skip_analytics"),
+            record_trajectories: args.optional_parse(
+                "--record_trajectories",
+                |s| -> Result<Duration> {
+                    let resolution = Duration::parse(s)?;
+                    if resolution <= Duration::ZERO {
+                        bail!("--record_trajectories must be positive");
+                    }
+                    Ok(resolution)
+                },
+            ),
         }
     }
 }
@@ -190,10 +219,12 @@ impl SimOptions {
             enable_pandemic_model: None,
             alerts: AlertHandler::Print,
             infinite_parking: false,
+            parking_policy: ParkingPolicy::free(),
             disable_turn_conflicts: false,
             cancel_drivers_delay_threshold: None,
             delay_trips_instead_of_cancelling: None,
             skip_analytics: false,
+            record_trajectories: None,
         }
     }
 }
@@ -202,9 +233,44 @@ impl SimOptions {
 impl Sim {
     pub fn new(map: &Map, opts: SimOptions) -> Sim {
         let mut scheduler = Scheduler::new();
+        // Lanes that change type during the day need to be updated as time passes. Every window
+        // boundary recurs daily.
+        let mut window_boundaries = BTreeSet::new();
+        for l in map.all_lanes() {
+            for (window, _) in &l.lane_type_windows {
+                window_boundaries.insert(window.start);
+                window_boundaries.insert(window.end);
+            }
+        }
+        for t in window_boundaries {
+            scheduler.push(t, Command::UpdateTimeWindows(t));
+        }
+        let trajectories = opts.record_trajectories.and_then(|resolution| {
+            let edits_name = map.get_edits().edits_name.clone();
+            match TrajectoryRecorder::new(
+                abstio::path_trajectories(map.get_name(), &edits_name, &opts.run_name),
+                map.get_name().clone(),
+                edits_name,
+                opts.run_name.clone(),
+                resolution,
+            ) {
+                Ok(recorder) => {
+                    scheduler.push(
+                        Time::START_OF_DAY,
+                        Command::RecordTrajectories(Time::START_OF_DAY),
+                    );
+                    Some(recorder)
+                }
+                Err(err) => {
+                    error!("Not recording trajectories: {}", err);
+                    None
+                }
+            }
+        });
+
         Sim {
             driving: DrivingSimState::new(map, &opts),
-            parking: ParkingSimState::new(map, opts.infinite_parking),
+            parking: ParkingSimState::new(map, &opts),
             walking: WalkingSimState::new(),
             intersections: IntersectionSimState::new(map, &mut scheduler, &opts),
             transit: TransitSimState::new(map),
@@ -226,20 +292,27 @@ impl Sim {
 
             analytics: Analytics::new(!opts.skip_analytics),
             recorder: None,
+            count_tracker: None,
+            matsim_events: None,
+            trajectories,
         }
     }
 
+    /// `escorts` pairs a child's trip with the parent's trip, both as indices into `input`.
     pub(crate) fn spawn_trips(
         &mut self,
         input: Vec<(PersonID, TripInfo, StartTripArgs)>,
+        escorts: Vec<(usize, usize)>,
         map: &Map,
         timer: &mut Timer,
     ) {
         timer.start_iter("spawn trips", input.len());
+        let mut ids = Vec::new();
         for (p, info, args) in input {
             timer.next();
 
             let trip = self.trips.new_trip(p, info.clone());
+            ids.push(trip);
             // This might be immediately true due to ScenarioModifiers
             if let Some(msg) = info.cancellation_reason {
                 self.trips.cancel_unstarted_trip(trip, msg);
@@ -248,6 +321,9 @@ impl Sim {
                     .push(info.departure, Command::StartTrip(trip, args));
             }
         }
+        for (child, parent) in escorts {
+            self.trips.set_escort(ids[child], ids[parent]);
+        }
 
         if let Some(ref mut m) = self.pandemic {
             m.initialize(self.trips.get_all_people(), &mut self.scheduler);
@@ -292,14 +368,18 @@ impl Sim {
         // TODO Refactor the logic in router
         let spot = if let Some((spot, _)) = self
             .parking
-            .get_all_free_spots(Position::start(driving_lane), &vehicle, b, map)
+            .get_all_free_spots(Position::start(driving_lane), &vehicle, b, self.time, map)
             .get(0)
         {
             spot.clone()
         } else {
-            let (_, spot, _) =
-                self.parking
-                    .path_to_free_parking_spot(driving_lane, &vehicle, b, map)?;
+            let (_, spot, _) = self.parking.path_to_free_parking_spot(
+                driving_lane,
+                &vehicle,
+                b,
+                self.time,
+                map,
+            )?;
             spot
         };
 
@@ -321,12 +401,17 @@ impl Sim {
     ) -> &Person {
         self.trips.new_person(orig_id, ped_speed, vehicle_specs)
     }
+    /// Lets somebody else use vehicles owned by another person.
+    pub(crate) fn share_vehicles(&mut self, person: PersonID, vehicles: Vec<Vehicle>) {
+        self.trips.share_vehicles(person, vehicles);
+    }
     pub(crate) fn seed_parked_car(&mut self, vehicle: Vehicle, spot: ParkingSpot) {
         self.parking.reserve_spot(spot, vehicle.id);
         self.parking.add_parked_car(ParkedCar {
             vehicle,
             spot,
             parked_since: self.time,
+            trip_arrival: None,
         });
     }
 
@@ -338,7 +423,7 @@ impl Sim {
 
     fn start_bus(&mut self, route: &BusRoute, map: &Map) {
         // Spawn one bus for the first leg.
-        let path = self.transit.create_empty_route(route, map);
+        let path = self.transit.create_empty_route(route, self.time, map);
 
         // For now, no desire for randomness. Caller can pass in list of specs if that ever
         // changes.
@@ -537,7 +622,7 @@ impl Sim {
                                     b,
                                 ));
                             }
-                            self.parking.remove_parked_car(parked_car);
+                            self.parking.remove_parked_car(parked_car, self.time, map);
                         }
                         if let Some(route) = maybe_route {
                             self.transit.bus_created(id, route);
@@ -627,6 +712,25 @@ impl Sim {
             Command::StartBus(r, _) => {
                 self.start_bus(map.get_br(r), map);
             }
+            Command::UpdateTimeWindows(t) => {
+                self.parking.update_time_windows(self.time, map);
+                let next = t + Duration::hours(24);
+                self.scheduler.push(next, Command::UpdateTimeWindows(next));
+            }
+            Command::RecordTrajectories(t) => {
+                // After loading a savestate, the recording is gone
+                if let Some(ref mut trajectories) = self.trajectories {
+                    let mut frame = self.driving.get_all_positions(self.time);
+                    frame.extend(self.walking.get_all_positions(self.time, map));
+                    if let Err(err) = trajectories.add_frame(frame) {
+                        error!("Stopped recording trajectories: {}", err);
+                        self.trajectories = None;
+                    } else {
+                        let next = t + trajectories.resolution;
+                        self.scheduler.push(next, Command::RecordTrajectories(next));
+                    }
+                }
+            }
         }
 
         // Record events at precisely the time they occur.
@@ -649,6 +753,12 @@ impl Sim {
             if let Some(ref mut r) = self.recorder {
                 r.handle_event(self.time, &ev, map, &self.driving, &self.trips);
             }
+            if let Some(ref mut t) = self.count_tracker {
+                t.handle_event(self.time, &ev, map, &self.trips);
+            }
+            if let Some(ref mut m) = self.matsim_events {
+                m.handle_event(self.time, &ev, map, &self.trips);
+            }
 
             self.analytics.event(ev, self.time, map);
         }
@@ -934,6 +1044,7 @@ impl Sim {
 
         let num_evicted = {
             let (evicted_cars, cars_parking_in_the_void) = self.parking.handle_live_edits(map);
+            self.parking.update_time_windows(self.time, map);
             let num_evicted = evicted_cars.len();
             affected.extend(self.walking.find_trips_to_parking(evicted_cars));
             for car in cars_parking_in_the_void {
@@ -1021,3 +1132,50 @@ impl Sim {
         self.recorder.take().unwrap().save(map);
     }
 }
+
+// Tracking trips through count locations
+impl Sim {
+    /// Start remembering which trips pass through these locations. Call before spawning trips.
+    pub fn track_trips_through(&mut self, locations: BTreeSet<CountLocation>) {
+        assert!(self.count_tracker.is_none());
+        self.count_tracker = Some(CountTracker::new(locations));
+    }
+
+    /// Per trip, every tracked location passed, by which type of agent, and during what hour.
+    pub fn get_tracked_crossings(
+        &self,
+    ) -> Option<&BTreeMap<TripID, Vec<(CountLocation, AgentType, usize)>>> {
+        Some(self.count_tracker.as_ref()?.crossings())
+    }
+}
+
+// Recording trajectories
+impl Sim {
+    /// If trajectories are being recorded, how many frames so far?
+    pub fn num_trajectory_frames(&self) -> Option<usize> {
+        Some(self.trajectories.as_ref()?.num_frames())
+    }
+
+    /// Where trajectories are being written, or None if they're not being recorded. Every frame
+    /// is written as soon as it's recorded.
+    pub fn trajectories_path(&self) -> Option<&str> {
+        Some(self.trajectories.as_ref()?.path())
+    }
+}
+
+// Exporting events for MATSim
+impl Sim {
+    /// Start remembering events to later write in MATSim's format. Call before spawning trips.
+    pub fn record_matsim_events(&mut self) {
+        assert!(self.matsim_events.is_none());
+        self.matsim_events = Some(MatsimEvents::new());
+    }
+
+    /// Writes everything recorded since `record_matsim_events` as a MATSim events.xml.
+    pub fn save_matsim_events(&self, path: &str) -> Result<()> {
+        match self.matsim_events {
+            Some(ref m) => m.save(path),
+            None => bail!("record_matsim_events wasn't called"),
+        }
+    }
+}
@@ -0,0 +1,425 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use abstio::MapName;
+use abstutil::Timer;
+use geom::{Angle, Distance, Duration, Pt2D, Time};
+use map_model::{Map, Traversable};
+
+use crate::AgentID;
+
+/// Where every agent was throughout a simulation, sampled at a fixed interval. This lets an entire
+/// run be replayed later and scrubbed through, without simulating again.
+///
+/// The file is written by `TrajectoryRecorder` while the simulation runs: a header, then one
+/// `FrameDelta` per frame. Distances are rounded to centimeters, and each frame only describes
+/// what changed since the previous one.
+pub struct Trajectories {
+    pub map_name: MapName,
+    pub edits_name: String,
+    pub run_name: String,
+    /// How much time passes between each frame
+    pub resolution: Duration,
+    /// The first frame is at midnight, and every frame holds every agent on the map at that time.
+    frames: Vec<Vec<AgentPosition>>,
+}
+
+/// For cars and bikes, this is the position of the front of the vehicle.
+#[derive(Clone, Copy)]
+pub struct AgentPosition {
+    pub id: AgentID,
+    pub on: Traversable,
+    pub dist: Distance,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    map_name: MapName,
+    edits_name: String,
+    run_name: String,
+    resolution: Duration,
+}
+
+/// Every agent's `Traversable` and distance along it in centimeters
+type Positions = BTreeMap<AgentID, (Traversable, u32)>;
+
+/// How one frame differs from the previous one.
+#[derive(Serialize, Deserialize)]
+struct FrameDelta {
+    /// Agents that appeared or moved onto a different `Traversable`
+    changed: Vec<(AgentID, Traversable, u32)>,
+    /// Agents that disappeared
+    removed: Vec<AgentID>,
+    /// How many centimeters every other agent moved, in order of `AgentID`
+    moved: Vec<i32>,
+}
+
+impl FrameDelta {
+    fn new(prev: &Positions, next: &Positions) -> FrameDelta {
+        let mut changed = Vec::new();
+        let mut moved = Vec::new();
+        for (id, (on, dist)) in next {
+            match prev.get(id) {
+                Some((prev_on, prev_dist)) if prev_on == on => {
+                    moved.push(*dist as i32 - *prev_dist as i32);
+                }
+                _ => {
+                    changed.push((*id, *on, *dist));
+                }
+            }
+        }
+        FrameDelta {
+            changed,
+            removed: prev
+                .keys()
+                .filter(|id| !next.contains_key(id))
+                .cloned()
+                .collect(),
+            moved,
+        }
+    }
+
+    fn apply(self, positions: &mut Positions) -> Result<()> {
+        for id in &self.removed {
+            positions.remove(id);
+        }
+        let changed: BTreeSet<AgentID> = self.changed.iter().map(|(id, _, _)| *id).collect();
+        let mut moved = self.moved.into_iter();
+        for (id, (_, dist)) in positions.iter_mut() {
+            if changed.contains(id) {
+                continue;
+            }
+            let delta = moved
+                .next()
+                .ok_or_else(|| anyhow!("frame doesn't say how far {} moved", id))?;
+            *dist = (*dist as i64 + delta as i64).max(0) as u32;
+        }
+        if moved.next().is_some() {
+            bail!("frame moves more agents than exist");
+        }
+        for (id, on, dist) in self.changed {
+            positions.insert(id, (on, dist));
+        }
+        Ok(())
+    }
+}
+
+impl Trajectories {
+    pub fn load(path: String, timer: &mut Timer) -> Result<Trajectories> {
+        let bytes = abstio::slurp_file(&path)?;
+        let mut reader = &bytes[..];
+        let header: Header = bincode::deserialize_from(&mut reader)?;
+
+        timer.start(format!("decode frames from {}", path));
+        let mut positions = Positions::new();
+        let mut frames = Vec::new();
+        while !reader.is_empty() {
+            let delta: FrameDelta = bincode::deserialize_from(&mut reader)?;
+            delta.apply(&mut positions)?;
+            frames.push(
+                positions
+                    .iter()
+                    .map(|(id, (on, dist))| AgentPosition {
+                        id: *id,
+                        on: *on,
+                        dist: Distance::meters(*dist as f64 / 100.0),
+                    })
+                    .collect(),
+            );
+        }
+        timer.stop(format!("decode frames from {}", path));
+
+        Ok(Trajectories {
+            map_name: header.map_name,
+            edits_name: header.edits_name,
+            run_name: header.run_name,
+            resolution: header.resolution,
+            frames,
+        })
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// The time of the last frame
+    pub fn end_time(&self) -> Time {
+        Time::START_OF_DAY + self.resolution * (self.frames.len().max(1) - 1) as f64
+    }
+
+    /// Where is every agent at some time? Between frames, agents staying on the same `Traversable`
+    /// move smoothly; anybody else jumps to their new position at the next frame.
+    pub fn agents_at(&self, time: Time, map: &Map) -> Vec<(AgentID, Pt2D, Angle)> {
+        if self.frames.is_empty() || time < Time::START_OF_DAY {
+            return Vec::new();
+        }
+        let elapsed = (time - Time::START_OF_DAY) / self.resolution;
+        let idx = (elapsed.floor() as usize).min(self.frames.len() - 1);
+        let percent = elapsed - (idx as f64);
+
+        let next: HashMap<AgentID, &AgentPosition> = self
+            .frames
+            .get(idx + 1)
+            .map(|frame| frame.iter().map(|pos| (pos.id, pos)).collect())
+            .unwrap_or_else(HashMap::new);
+        let mut result = Vec::new();
+        for pos in &self.frames[idx] {
+            let mut dist = pos.dist;
+            if let Some(later) = next.get(&pos.id) {
+                if later.on == pos.on {
+                    dist = pos.dist + (later.dist - pos.dist) * percent;
+                }
+            }
+            if let Ok((pt, angle)) = pos.on.dist_along(dist, map) {
+                result.push((pos.id, pt, angle));
+            }
+        }
+        result
+    }
+}
+
+/// Appends every frame to the trajectories file as soon as it's recorded, so long simulations
+/// don't hold everything in memory. A cloned `Sim` keeps appending to the same file.
+#[derive(Clone)]
+pub(crate) struct TrajectoryRecorder {
+    path: String,
+    pub resolution: Duration,
+    num_frames: usize,
+    last_frame: Positions,
+}
+
+impl TrajectoryRecorder {
+    /// Starts a new file, replacing anything already there.
+    pub fn new(
+        path: String,
+        map_name: MapName,
+        edits_name: String,
+        run_name: String,
+        resolution: Duration,
+    ) -> Result<TrajectoryRecorder> {
+        if resolution <= Duration::ZERO {
+            bail!(
+                "trajectories need a positive resolution, not {}",
+                resolution
+            );
+        }
+        std::fs::create_dir_all(std::path::Path::new(&path).parent().unwrap())?;
+        let mut f = BufWriter::new(File::create(&path)?);
+        bincode::serialize_into(
+            &mut f,
+            &Header {
+                map_name,
+                edits_name,
+                run_name,
+                resolution,
+            },
+        )?;
+        f.flush()?;
+        Ok(TrajectoryRecorder {
+            path,
+            resolution,
+            num_frames: 0,
+            last_frame: Positions::new(),
+        })
+    }
+
+    pub fn add_frame(&mut self, frame: Vec<AgentPosition>) -> Result<()> {
+        let positions: Positions = frame
+            .into_iter()
+            .map(|pos| {
+                let cm = (pos.dist.inner_meters() * 100.0).round() as u32;
+                (pos.id, (pos.on, cm))
+            })
+            .collect();
+        let mut f = BufWriter::new(OpenOptions::new().append(true).open(&self.path)?);
+        bincode::serialize_into(&mut f, &FrameDelta::new(&self.last_frame, &positions))?;
+        f.flush()?;
+        self.last_frame = positions;
+        self.num_frames += 1;
+        Ok(())
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.num_frames
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use abstutil::Tags;
+    use geom::{GPSBounds, LonLat};
+    use map_model::raw::{OriginalRoad, RawIntersection, RawMap, RawRoad};
+    use map_model::{osm, IntersectionType, LaneID};
+
+    use super::*;
+    use crate::{CarID, PedestrianID, VehicleType};
+
+    /// One road between two borders
+    fn tiny_map() -> Map {
+        let mut raw = RawMap::blank(MapName::new("test", "tiny"));
+        raw.gps_bounds = GPSBounds::from(vec![
+            LonLat::new(-122.31, 47.61),
+            LonLat::new(-122.30, 47.62),
+        ]);
+        raw.boundary_polygon = raw.gps_bounds.to_bounds().get_rectangle();
+        for (id, x) in &[(1, 100.0), (2, 400.0)] {
+            raw.intersections.insert(
+                osm::NodeID(*id),
+                RawIntersection {
+                    point: Pt2D::new(*x, 200.0),
+                    intersection_type: IntersectionType::Border,
+                    elevation: Distance::ZERO,
+                },
+            );
+        }
+        let mut osm_tags = Tags::new(BTreeMap::new());
+        osm_tags.insert(osm::HIGHWAY, "residential");
+        raw.roads.insert(
+            OriginalRoad::new(10, (1, 2)),
+            RawRoad {
+                center_points: vec![Pt2D::new(100.0, 200.0), Pt2D::new(400.0, 200.0)],
+                osm_tags,
+                turn_restrictions: Vec::new(),
+                complicated_turn_restrictions: Vec::new(),
+                lane_connectivity: Vec::new(),
+            },
+        );
+        Map::create_from_raw(raw, false, false, &mut Timer::throwaway())
+    }
+
+    fn pos(id: AgentID, lane: LaneID, meters: f64) -> AgentPosition {
+        AgentPosition {
+            id,
+            on: Traversable::Lane(lane),
+            dist: Distance::meters(meters),
+        }
+    }
+
+    fn close(pt1: Pt2D, pt2: Pt2D) -> bool {
+        pt1.dist_to(pt2) < Distance::meters(0.01)
+    }
+
+    #[test]
+    fn test_agents_at() {
+        let map = tiny_map();
+        let lanes: Vec<LaneID> = map.all_lanes().iter().map(|l| l.id).collect();
+        let (l1, l2) = (lanes[0], lanes[1]);
+        let car = AgentID::Car(CarID(0, VehicleType::Car));
+        let switcher = AgentID::Car(CarID(1, VehicleType::Car));
+        let leaver = AgentID::Pedestrian(PedestrianID(2));
+        let trajectories = Trajectories {
+            map_name: map.get_name().clone(),
+            edits_name: "untitled edits".to_string(),
+            run_name: "test".to_string(),
+            resolution: Duration::seconds(10.0),
+            frames: vec![
+                vec![
+                    pos(car, l1, 0.0),
+                    pos(switcher, l1, 10.0),
+                    pos(leaver, l1, 5.0),
+                ],
+                vec![pos(car, l1, 20.0), pos(switcher, l2, 1.0)],
+            ],
+        };
+        let at = |lane: LaneID, meters: f64| {
+            map.get_l(lane)
+                .lane_center_pts
+                .dist_along(Distance::meters(meters))
+                .unwrap()
+                .0
+        };
+        let find = |agents: &[(AgentID, Pt2D, Angle)], id: AgentID| {
+            agents
+                .iter()
+                .find(|(a, _, _)| *a == id)
+                .map(|(_, pt, _)| *pt)
+        };
+
+        // Halfway between frames, the car staying on its lane moves smoothly. Agents changing
+        // lanes or disappearing stay put until the next frame.
+        let agents = trajectories.agents_at(Time::START_OF_DAY + Duration::seconds(5.0), &map);
+        assert_eq!(agents.len(), 3);
+        assert!(close(find(&agents, car).unwrap(), at(l1, 10.0)));
+        assert!(close(find(&agents, switcher).unwrap(), at(l1, 10.0)));
+        assert!(close(find(&agents, leaver).unwrap(), at(l1, 5.0)));
+
+        // Exactly on a frame
+        let agents = trajectories.agents_at(Time::START_OF_DAY + Duration::seconds(10.0), &map);
+        assert_eq!(agents.len(), 2);
+        assert!(close(find(&agents, car).unwrap(), at(l1, 20.0)));
+        assert!(close(find(&agents, switcher).unwrap(), at(l2, 1.0)));
+
+        // After the last frame, everybody stays where they were last seen
+        let agents = trajectories.agents_at(Time::START_OF_DAY + Duration::hours(1), &map);
+        assert!(close(find(&agents, car).unwrap(), at(l1, 20.0)));
+        assert_eq!(
+            trajectories.end_time(),
+            Time::START_OF_DAY + Duration::seconds(10.0)
+        );
+    }
+
+    #[test]
+    fn test_record_and_load() {
+        let path = std::env::temp_dir()
+            .join(format!("trajectories_{}.bin", std::process::id()))
+            .to_str()
+            .unwrap()
+            .to_string();
+        let (l1, l2) = (LaneID(0), LaneID(1));
+        let car = AgentID::Car(CarID(0, VehicleType::Car));
+        let bike = AgentID::Car(CarID(1, VehicleType::Bike));
+        let ped = AgentID::Pedestrian(PedestrianID(2));
+        let frames = vec![
+            vec![pos(car, l1, 0.0), pos(ped, l2, 3.0)],
+            vec![pos(car, l1, 12.345), pos(ped, l2, 2.0), pos(bike, l1, 1.0)],
+            vec![pos(car, l2, 0.5), pos(bike, l1, 1.0)],
+            Vec::new(),
+        ];
+
+        let mut recorder = TrajectoryRecorder::new(
+            path.clone(),
+            MapName::new("test", "tiny"),
+            "untitled edits".to_string(),
+            "test".to_string(),
+            Duration::seconds(5.0),
+        )
+        .unwrap();
+        for frame in &frames {
+            recorder.add_frame(frame.clone()).unwrap();
+        }
+        assert_eq!(recorder.num_frames(), frames.len());
+
+        let loaded = Trajectories::load(path.clone(), &mut Timer::throwaway()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.map_name, MapName::new("test", "tiny"));
+        assert_eq!(loaded.resolution, Duration::seconds(5.0));
+        assert_eq!(loaded.num_frames(), frames.len());
+        for (expected, actual) in frames.iter().zip(loaded.frames.iter()) {
+            let mut expected = expected.clone();
+            expected.sort_by_key(|pos| pos.id);
+            assert_eq!(expected.len(), actual.len());
+            for (pos1, pos2) in expected.iter().zip(actual.iter()) {
+                assert_eq!(pos1.id, pos2.id);
+                assert_eq!(pos1.on, pos2.on);
+                assert!((pos1.dist - pos2.dist).abs() <= Distance::meters(0.005));
+            }
+        }
+
+        assert!(TrajectoryRecorder::new(
+            path,
+            MapName::new("test", "tiny"),
+            "untitled edits".to_string(),
+            "test".to_string(),
+            Duration::ZERO,
+        )
+        .is_err());
+    }
+}
@@ -88,7 +88,7 @@ impl TransitSimState {
     }
 
     /// Returns the path for the first leg.
-    pub fn create_empty_route(&mut self, bus_route: &BusRoute, map: &Map) -> Path {
+    pub fn create_empty_route(&mut self, bus_route: &BusRoute, now: Time, map: &Map) -> Path {
         if !self.routes.contains_key(&bus_route.id) {
             assert!(bus_route.stops.len() > 1);
             let mut stops = Vec::new();
@@ -153,7 +153,7 @@ impl TransitSimState {
             );
         }
 
-        self.routes[&bus_route.id].start.clone()
+        path_at(&self.routes[&bus_route.id].start, now, map)
     }
 
     pub fn bus_created(&mut self, bus: CarID, r: BusRouteID) {
@@ -272,7 +272,7 @@ impl TransitSimState {
         }
     }
 
-    pub fn bus_departed_from_stop(&mut self, id: CarID, map: &Map) -> Router {
+    pub fn bus_departed_from_stop(&mut self, id: CarID, now: Time, map: &Map) -> Router {
         let mut bus = self.buses.get_mut(&id).unwrap();
         let route = self.routes.get_mut(&bus.route).unwrap();
         match bus.state {
@@ -281,13 +281,13 @@ impl TransitSimState {
                 let stop = &route.stops[stop_idx];
                 self.events
                     .push(Event::BusDepartedFromStop(id, bus.route, stop.id));
-                if let Some(path) = stop.next_stop.clone() {
+                if let Some(ref path) = stop.next_stop {
                     bus.state = BusState::DrivingToStop(stop_idx + 1);
-                    Router::follow_bus_route(id, path)
+                    Router::follow_bus_route(id, path_at(path, now, map))
                 } else {
-                    if let Some(path) = route.end_at_border.clone() {
+                    if let Some(ref path) = route.end_at_border {
                         bus.state = BusState::DrivingOffMap;
-                        Router::follow_bus_route(id, path)
+                        Router::follow_bus_route(id, path_at(path, now, map))
                     } else {
                         route.active_vehicles.remove(&id);
                         for (person, stop2) in &bus.passengers {
@@ -421,3 +421,10 @@ impl TransitSimState {
         &self.peds_waiting[&at]
     }
 }
+
+/// Routes are calculated once up-front. When a bus sets off, make sure nothing along the way is
+/// closed at that time of day. If there's no way around, just drive through.
+fn path_at(path: &Path, now: Time, map: &Map) -> Path {
+    map.reroute_at(path.clone(), now)
+        .unwrap_or_else(|| path.clone())
+}
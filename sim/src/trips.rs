@@ -86,6 +86,15 @@ impl TripManager {
         self.get_person(id).unwrap()
     }
 
+    pub fn share_vehicles(&mut self, person: PersonID, vehicles: Vec<Vehicle>) {
+        self.people[person.0].vehicles.extend(vehicles);
+    }
+
+    /// The child's trip will ride along with the parent's trip, if it's still happening.
+    pub fn set_escort(&mut self, child: TripID, parent: TripID) {
+        self.trips[child.0].escorted_by = Some(parent);
+    }
+
     pub fn new_car_id(&mut self) -> usize {
         let id = self.car_id_counter;
         self.car_id_counter += 1;
@@ -103,6 +112,8 @@ impl TripManager {
             total_blocked_time: Duration::ZERO,
             total_distance: Distance::ZERO,
             legs: VecDeque::new(),
+            escorted_by: None,
+            passengers: Vec::new(),
         };
         self.unfinished_trips += 1;
         let person = &mut self.people[trip.person.0];
@@ -154,8 +165,25 @@ impl TripManager {
             ));
             return;
         }
+
+        if let Some(parent) = self.trips[trip.0].escorted_by {
+            let parent_trip = &self.trips[parent.0];
+            if parent_trip.finished_at.is_none() && parent_trip.info.cancellation_reason.is_none() {
+                self.start_escorted_trip(trip, parent);
+                return;
+            }
+            // The parent already left without them, so travel alone.
+            self.trips[trip.0].escorted_by = None;
+        }
         self.trips[trip.0].started = true;
 
+        // Children who were supposed to be escorted don't have their own vehicle.
+        if args.use_vehicle.is_none() {
+            if let TripMode::Drive | TripMode::Bike = self.trips[trip.0].info.mode {
+                self.trips[trip.0].info.mode = TripMode::Transit;
+            }
+        }
+
         let info = &self.trips[trip.0].info;
         let spec = match TripSpec::maybe_new(
             info.start.clone(),
@@ -242,7 +270,7 @@ impl TripManager {
                         end: walking_goal.sidewalk_pos,
                         constraints: PathConstraints::Pedestrian,
                     };
-                    match ctx.map.pathfind(req) {
+                    match ctx.map.pathfind_at(req, now) {
                         Ok(path) => {
                             ctx.scheduler.push(
                                 now,
@@ -259,7 +287,8 @@ impl TripManager {
                         }
                         Err(err) => {
                             // Move the car to the destination
-                            ctx.parking.remove_parked_car(parked_car.clone());
+                            ctx.parking
+                                .remove_parked_car(parked_car.clone(), now, ctx.map);
                             self.cancel_trip(
                                 now,
                                 trip,
@@ -270,15 +299,14 @@ impl TripManager {
                         }
                     }
                 } else {
-                    // This should only happen when a driving trip has been cancelled and there was
-                    // absolutely no room to warp the car.
-                    self.cancel_trip(
-                        now,
-                        trip,
-                        format!("should have {} parked somewhere, but it's unavailable", car),
-                        None,
-                        ctx,
-                    );
+                    let reason = if self.active_trip_mode.contains_key(&AgentID::Car(car)) {
+                        format!("{} is still being driven by someone sharing it", car)
+                    } else {
+                        // This should only happen when a driving trip has been cancelled and
+                        // there was absolutely no room to warp the car.
+                        format!("should have {} parked somewhere, but it's unavailable", car)
+                    };
+                    self.cancel_trip(now, trip, reason, None, ctx);
                 }
             }
             TripSpec::JustWalking { start, goal } => {
@@ -314,7 +342,7 @@ impl TripManager {
                     end: goal.sidewalk_pos,
                     constraints: PathConstraints::Pedestrian,
                 };
-                match ctx.map.pathfind(req) {
+                match ctx.map.pathfind_at(req, now) {
                     Ok(path) => {
                         ctx.scheduler.push(
                             now,
@@ -344,7 +372,7 @@ impl TripManager {
                         end: walk_to.sidewalk_pos,
                         constraints: PathConstraints::Pedestrian,
                     };
-                    match ctx.map.pathfind(req) {
+                    match ctx.map.pathfind_at(req, now) {
                         Ok(path) => {
                             // Where we start biking may have slightly changed due to live map
                             // edits!
@@ -422,7 +450,7 @@ impl TripManager {
                     end: walk_to.sidewalk_pos,
                     constraints: PathConstraints::Pedestrian,
                 };
-                match ctx.map.pathfind(req) {
+                match ctx.map.pathfind_at(req, now) {
                     Ok(path) => {
                         ctx.scheduler.push(
                             now,
@@ -528,14 +556,25 @@ impl TripManager {
         trip.total_distance += distance_crossed;
 
         trip.assert_walking_leg(SidewalkSpot::deferred_parking_spot());
-        let parked_car = ctx.parking.get_car_at_spot(spot).unwrap().clone();
-        let drive_to = match trip.legs[0] {
-            TripLeg::Drive(c, ref to) => {
-                assert_eq!(c, parked_car.vehicle.id);
-                to.clone()
-            }
+        let (car, drive_to) = match trip.legs[0] {
+            TripLeg::Drive(c, ref to) => (c, to.clone()),
             _ => unreachable!(),
         };
+        let parked_car = match ctx.parking.get_car_at_spot(spot) {
+            Some(parked_car) if parked_car.vehicle.id == car => parked_car.clone(),
+            _ => {
+                // Somebody sharing the car drove off with it first
+                let id = trip.id;
+                self.cancel_trip(
+                    now,
+                    id,
+                    format!("{} was taken by someone sharing it", car),
+                    None,
+                    ctx,
+                );
+                return;
+            }
+        };
 
         let mut start =
             ctx.parking
@@ -574,7 +613,8 @@ impl TripManager {
             }
             Err(err) => {
                 // Move the car to the destination...
-                ctx.parking.remove_parked_car(parked_car.clone());
+                ctx.parking
+                    .remove_parked_car(parked_car.clone(), now, ctx.map);
                 self.cancel_trip(now, trip, err.to_string(), Some(parked_car.vehicle), ctx);
             }
         }
@@ -634,7 +674,7 @@ impl TripManager {
             ))
         } else {
             ctx.map
-                .pathfind(req)
+                .pathfind_at(req, now)
                 .map(|path| drive_to.make_router(bike, path, ctx.map))
         };
         match maybe_router {
@@ -926,9 +966,48 @@ impl TripManager {
         });
 
         let person = trip.person;
+        let passengers = std::mem::take(&mut trip.passengers);
+        for passenger in passengers {
+            self.escorted_trip_finished(now, passenger, ctx);
+        }
         self.start_delayed_trip(now, person, ctx);
     }
 
+    fn start_escorted_trip(&mut self, trip: TripID, parent: TripID) {
+        let t = &mut self.trips[trip.0];
+        t.started = true;
+        let person = &mut self.people[t.person.0];
+        if let PersonState::Inside(b) = person.state {
+            self.events.push(Event::PersonLeavesBuilding(person.id, b));
+        }
+        person.state = PersonState::Trip(trip);
+        self.events.push(Event::TripPhaseStarting(
+            trip,
+            person.id,
+            None,
+            TripPhaseType::Escorted,
+        ));
+        self.trips[parent.0].passengers.push(trip);
+    }
+
+    /// The parent's trip finished, so the escorted child arrives too.
+    fn escorted_trip_finished(&mut self, now: Time, id: TripID, ctx: &mut Ctx) {
+        let trip = &self.trips[id.0];
+        let person = trip.person;
+        self.people[person.0].state = match trip.info.end {
+            TripEndpoint::Bldg(b) => {
+                self.events.push(Event::PersonEntersBuilding(person, b));
+                PersonState::Inside(b)
+            }
+            TripEndpoint::Border(i) => {
+                self.events.push(Event::PersonLeavesMap(person, None, i));
+                PersonState::OffMap
+            }
+            TripEndpoint::SuddenlyAppear(_) => unreachable!(),
+        };
+        self.trip_finished(now, id, ctx);
+    }
+
     fn start_delayed_trip(&mut self, now: Time, id: PersonID, ctx: &mut Ctx) {
         let person = &mut self.people[id.0];
         if person.delayed_trips.is_empty() {
@@ -959,7 +1038,7 @@ impl TripManager {
             end: walk_to.sidewalk_pos,
             constraints: PathConstraints::Pedestrian,
         };
-        match ctx.map.pathfind(req) {
+        match ctx.map.pathfind_at(req, now) {
             Ok(path) => {
                 let person = &self.people[trip.person.0];
                 ctx.scheduler.push(
@@ -991,7 +1070,7 @@ impl TripManager {
         req: PathRequest,
         car: CarID,
     ) -> Result<Path> {
-        let path = ctx.map.pathfind(req)?;
+        let path = ctx.map.pathfind_at(req, now)?;
         match ctx
             .cap
             .maybe_cap_path(path, now, car, ctx.intersections, ctx.map)
@@ -1062,21 +1141,27 @@ impl TripManager {
                 // First remove the parked car, if needed. Maybe the trip was cancelled while the
                 // car was parked in the starting building.
                 if let Some(parked_car) = ctx.parking.lookup_parked_car(vehicle.id).cloned() {
-                    ctx.parking.remove_parked_car(parked_car);
+                    ctx.parking.remove_parked_car(parked_car, now, ctx.map);
                 }
 
                 if let TripEndpoint::Bldg(b) = trip.info.end {
                     let driving_lane = ctx.map.find_driving_lane_near_building(b);
                     if let Some(spot) = ctx
                         .parking
-                        .get_all_free_spots(Position::start(driving_lane), &vehicle, b, ctx.map)
+                        .get_all_free_spots(
+                            Position::start(driving_lane),
+                            &vehicle,
+                            b,
+                            now,
+                            ctx.map,
+                        )
                         // TODO Could pick something closer, but meh, cancelled trips are bugs
                         // anyway
                         .get(0)
                         .map(|(spot, _)| spot.clone())
                         .or_else(|| {
                             ctx.parking
-                                .path_to_free_parking_spot(driving_lane, &vehicle, b, ctx.map)
+                                .path_to_free_parking_spot(driving_lane, &vehicle, b, now, ctx.map)
                                 .map(|(_, spot, _)| spot)
                         })
                     {
@@ -1092,6 +1177,7 @@ impl TripManager {
                             vehicle,
                             spot,
                             parked_since: now,
+                            trip_arrival: None,
                         });
                     } else {
                         self.events.push(Event::Alert(
@@ -1106,14 +1192,26 @@ impl TripManager {
             }
         } else {
             // If the trip was cancelled because we'e totally out of parking, don't forget to clean
-            // this up.
-            if let TripLeg::Drive(c, _) = &trip.legs[0] {
-                if let Some(t) = self.active_trip_mode.remove(&AgentID::Car(*c)) {
-                    assert_eq!(t, trip.id);
+            // this up. If somebody else took a shared car, leave their trip alone.
+            if let Some(TripLeg::Drive(c, _)) = trip.legs.front() {
+                if self.active_trip_mode.get(&AgentID::Car(*c)) == Some(&trip.id) {
+                    self.active_trip_mode.remove(&AgentID::Car(*c));
                 }
             }
         }
 
+        // Anybody being escorted is warped along too
+        let passengers = std::mem::take(&mut trip.passengers);
+        for passenger in passengers {
+            self.cancel_trip(
+                now,
+                passenger,
+                format!("the trip escorting them was cancelled: {}", reason),
+                None,
+                ctx,
+            );
+        }
+
         self.start_delayed_trip(now, person, ctx);
     }
 
@@ -1149,6 +1247,10 @@ impl TripManager {
         if !trip.started {
             return TripResult::TripNotStarted;
         }
+        // Escorted children are inside their parent's vehicle or walking alongside
+        if let Some(parent) = trip.escorted_by {
+            return self.trip_to_agent(parent);
+        }
 
         let person = &self.people[trip.person.0];
         let a = match &trip.legs[0] {
@@ -1367,6 +1469,10 @@ struct Trip {
     // Not filled out until the trip starts
     legs: VecDeque<TripLeg>,
     person: PersonID,
+    /// A parent's trip that this child rides along with
+    escorted_by: Option<TripID>,
+    /// Children riding along with this trip right now
+    passengers: Vec<TripID>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
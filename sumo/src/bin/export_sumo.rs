@@ -0,0 +1,36 @@
+//! Converts an A/B Street map, and optionally a scenario for it, to SUMO's formats.
+//!
+//! Usage: `export_sumo --map=data/system/seattle/maps/montlake.bin --scenario=weekday
+//! --output=montlake`. This writes `montlake.net.xml` and `montlake.rou.xml`.
+
+use anyhow::Result;
+
+use abstutil::{CmdArgs, Timer};
+use map_model::Map;
+use sim::Scenario;
+
+fn main() -> Result<()> {
+    let mut args = CmdArgs::new();
+    let map_path = args.required("--map");
+    let scenario_name = args.optional("--scenario");
+    let output = args.required("--output");
+    args.done();
+
+    let mut timer = Timer::new("export to SUMO");
+    let map = Map::new(map_path, &mut timer);
+    let net_path = format!("{}.net.xml", output);
+    sumo::export_map(&map, &net_path, &mut timer)?;
+    println!("Wrote {}", net_path);
+
+    if let Some(name) = scenario_name {
+        let scenario: Scenario =
+            abstio::must_read_object(abstio::path_scenario(map.get_name(), &name), &mut timer);
+        let routes_path = format!("{}.rou.xml", output);
+        let skipped = sumo::export_scenario(&scenario, &map, &routes_path, &mut timer)?;
+        println!("Wrote {}", routes_path);
+        if skipped > 0 {
+            println!("Skipped {} trips that couldn't be exported", skipped);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,31 @@
+//! Imports vehicles, trips, and flows from a SUMO routes file into a scenario for a map that was
+//! converted from a SUMO network.
+//!
+//! Usage: `import_sumo_demand --map=data/system/sumo/maps/montlake.bin --routes=routes.xml
+//! --scenario_name=sumo`
+
+use anyhow::Result;
+
+use abstutil::{CmdArgs, Timer};
+use map_model::Map;
+use sumo::Demand;
+
+fn main() -> Result<()> {
+    let mut args = CmdArgs::new();
+    let map_path = args.required("--map");
+    let routes_path = args.required("--routes");
+    let scenario_name = args.required("--scenario_name");
+    args.done();
+
+    let mut timer = Timer::new("import SUMO demand");
+    let map = Map::new(map_path, &mut timer);
+    let demand = Demand::load(&routes_path, &mut timer)?;
+    let scenario = demand.into_scenario(&map, &scenario_name);
+    println!(
+        "Imported {} people into {}",
+        scenario.people.len(),
+        scenario_name
+    );
+    scenario.save();
+    Ok(())
+}
@@ -0,0 +1,460 @@
+//! Parses SUMO [demand](https://sumo.dlr.de/docs/Definition_of_Vehicles,_Vehicle_Types,_and_Routes.html)
+//! from .rou.xml files and turns it into an A/B Street `Scenario`.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use abstutil::Timer;
+use geom::{Duration, Time};
+use map_model::{BuildingID, Direction, IntersectionID, Map, RoadID};
+use sim::{IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose};
+
+use crate::{EdgeID, VehicleClass};
+
+/// Every vehicle defined by a SUMO routes file. Flows are expanded into individual vehicles.
+pub struct Demand {
+    pub trips: Vec<Trip>,
+}
+
+/// One vehicle traveling between two edges. When SUMO specifies the full route, only the first
+/// and last edge are kept; A/B Street picks its own path.
+pub struct Trip {
+    pub id: String,
+    pub depart: Time,
+    pub vehicle_class: VehicleClass,
+    pub from: EdgeID,
+    pub to: EdgeID,
+}
+
+/// A `vehicle` or `flow` whose route might be nested inside of it
+struct Pending {
+    attribs: BTreeMap<String, String>,
+    is_flow: bool,
+    edges: Option<Vec<EdgeID>>,
+}
+
+impl Demand {
+    /// Reads vehicles, trips, and flows from a .rou.xml file. Persons, containers, and anything
+    /// departing at a non-numeric time (like "triggered") are skipped.
+    pub fn load(path: &str, timer: &mut Timer) -> Result<Demand> {
+        timer.start(format!("read {}", path));
+        let bytes = abstio::slurp_file(path)?;
+        let demand = Demand::parse(std::str::from_utf8(&bytes)?)?;
+        timer.stop(format!("read {}", path));
+        Ok(demand)
+    }
+
+    fn parse(raw_string: &str) -> Result<Demand> {
+        let mut reader = Reader::from_str(raw_string);
+        reader.trim_text(true);
+
+        let mut vehicle_types: BTreeMap<String, VehicleClass> = BTreeMap::new();
+        let mut routes: BTreeMap<String, Vec<EdgeID>> = BTreeMap::new();
+        let mut pending: Option<Pending> = None;
+        let mut trips = Vec::new();
+        let mut skipped = 0;
+
+        let mut buf = Vec::new();
+        loop {
+            let mut closed = false;
+            let parsed = match reader.read_event(&mut buf)? {
+                Event::Start(e) => Some((parse_element(&e, &reader)?, false)),
+                Event::Empty(e) => Some((parse_element(&e, &reader)?, true)),
+                Event::End(e) => {
+                    closed = e.name() == b"vehicle" || e.name() == b"flow";
+                    None
+                }
+                Event::Eof => break,
+                _ => None,
+            };
+            buf.clear();
+            if closed {
+                if let Some(p) = pending.take() {
+                    skipped += finish(p, &vehicle_types, &routes, &mut trips)?;
+                }
+            }
+            let ((name, attribs), is_empty) = match parsed {
+                Some(x) => x,
+                None => {
+                    continue;
+                }
+            };
+
+            match name.as_ref() {
+                "vType" => {
+                    if let Some(id) = attribs.get("id") {
+                        vehicle_types.insert(
+                            id.clone(),
+                            attribs
+                                .get("vClass")
+                                .map(|x| VehicleClass::parse(x))
+                                .unwrap_or_else(|| VehicleClass::Other("passenger".to_string())),
+                        );
+                    }
+                }
+                "route" => {
+                    let edges: Vec<EdgeID> = attribs
+                        .get("edges")
+                        .map(|x| {
+                            x.split_whitespace()
+                                .map(|e| EdgeID(e.to_string()))
+                                .collect()
+                        })
+                        .unwrap_or_else(Vec::new);
+                    if let Some(ref mut p) = pending {
+                        p.edges = Some(edges);
+                    } else if let Some(id) = attribs.get("id") {
+                        routes.insert(id.clone(), edges);
+                    }
+                }
+                "vehicle" | "trip" | "flow" => {
+                    let p = Pending {
+                        attribs,
+                        is_flow: name == "flow",
+                        edges: None,
+                    };
+                    if is_empty || name == "trip" {
+                        skipped += finish(p, &vehicle_types, &routes, &mut trips)?;
+                    } else {
+                        pending = Some(p);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if skipped > 0 {
+            warn!(
+                "Skipped {} vehicles and flows without usable departure times",
+                skipped
+            );
+        }
+        Ok(Demand { trips })
+    }
+
+    /// Creates a scenario with one person per vehicle. Trips starting or ending on an edge that
+    /// connects to a border use that border; otherwise, buildings along the edge's road are used,
+    /// spreading trips out over all of them. Pedestrians and public transit vehicles are skipped,
+    /// as are trips on edges not in the map. Only maps converted from SUMO networks know their
+    /// original edge IDs. SUMO doesn't say why anybody travels, so every trip's purpose is
+    /// unknown.
+    pub fn into_scenario(self, map: &Map, scenario_name: &str) -> Scenario {
+        let mut edges: BTreeMap<String, (RoadID, Direction)> = BTreeMap::new();
+        for r in map.all_roads() {
+            if let Some(id) = r.osm_tags.get(crate::EDGE_ID) {
+                edges.insert(id.clone(), (r.id, Direction::Fwd));
+            }
+            if let Some(id) = r.osm_tags.get(crate::REVERSE_EDGE_ID) {
+                edges.insert(id.clone(), (r.id, Direction::Back));
+            }
+        }
+        let mut buildings: BTreeMap<RoadID, Vec<BuildingID>> = BTreeMap::new();
+        for b in map.all_buildings() {
+            buildings
+                .entry(map.get_l(b.sidewalk_pos.lane()).parent)
+                .or_insert_with(Vec::new)
+                .push(b.id);
+        }
+        // Round-robin through the buildings on each road
+        let mut next_building: BTreeMap<RoadID, usize> = BTreeMap::new();
+        let mut endpoint = |edge: &EdgeID, from: bool| -> Option<TripEndpoint> {
+            let (r, dir) = *edges.get(&edge.0)?;
+            let road = map.get_r(r);
+            let i: IntersectionID = match (dir, from) {
+                (Direction::Fwd, true) | (Direction::Back, false) => road.src_i,
+                (Direction::Fwd, false) | (Direction::Back, true) => road.dst_i,
+            };
+            if map.get_i(i).is_border() {
+                return Some(TripEndpoint::Border(i));
+            }
+            let choices = buildings.get(&r)?;
+            let idx = next_building.entry(r).or_insert(0);
+            let b = choices[*idx % choices.len()];
+            *idx += 1;
+            Some(TripEndpoint::Bldg(b))
+        };
+
+        let mut scenario = Scenario::empty(map, scenario_name);
+        let mut skipped = 0;
+        for trip in self.trips {
+            let mode = match trip.vehicle_class {
+                VehicleClass::Bicycle => TripMode::Bike,
+                VehicleClass::Pedestrian | VehicleClass::RailUrban => {
+                    skipped += 1;
+                    continue;
+                }
+                VehicleClass::Other(ref x)
+                    if x == "bus" || x == "coach" || x == "tram" || x.starts_with("rail") =>
+                {
+                    skipped += 1;
+                    continue;
+                }
+                VehicleClass::Other(_) => TripMode::Drive,
+            };
+            match (endpoint(&trip.from, true), endpoint(&trip.to, false)) {
+                (Some(origin), Some(destination)) => {
+                    scenario.people.push(PersonSpec {
+                        orig_id: None,
+                        origin,
+                        trips: vec![IndividTrip::new(
+                            trip.depart,
+                            TripPurpose::Unknown,
+                            destination,
+                            mode,
+                        )],
+                    });
+                }
+                _ => {
+                    skipped += 1;
+                }
+            }
+        }
+        if skipped > 0 {
+            info!("Skipped {} SUMO trips that couldn't be imported", skipped);
+        }
+        scenario
+    }
+}
+
+fn parse_element(
+    e: &BytesStart,
+    reader: &Reader<&[u8]>,
+) -> Result<(String, BTreeMap<String, String>)> {
+    let name = std::str::from_utf8(e.name())?.to_string();
+    let mut attribs = BTreeMap::new();
+    for attr in e.attributes() {
+        let attr = attr?;
+        attribs.insert(
+            std::str::from_utf8(attr.key)?.to_string(),
+            attr.unescape_and_decode_value(reader)?,
+        );
+    }
+    Ok((name, attribs))
+}
+
+/// Turns a vehicle, trip, or flow into trips. Returns the number of vehicles skipped.
+fn finish(
+    p: Pending,
+    vehicle_types: &BTreeMap<String, VehicleClass>,
+    routes: &BTreeMap<String, Vec<EdgeID>>,
+    trips: &mut Vec<Trip>,
+) -> Result<usize> {
+    let id = p
+        .attribs
+        .get("id")
+        .cloned()
+        .ok_or_else(|| anyhow!("vehicle without an id"))?;
+    let edges = if let Some(edges) = p.edges {
+        edges
+    } else if let Some(route) = p.attribs.get("route") {
+        routes
+            .get(route)
+            .cloned()
+            .ok_or_else(|| anyhow!("{} uses unknown route {}", id, route))?
+    } else {
+        match (p.attribs.get("from"), p.attribs.get("to")) {
+            (Some(from), Some(to)) => vec![EdgeID(from.clone()), EdgeID(to.clone())],
+            _ => bail!("{} has no route and no from/to edges", id),
+        }
+    };
+    if edges.is_empty() {
+        bail!("{} has an empty route", id);
+    }
+    let vehicle_class = p
+        .attribs
+        .get("type")
+        .and_then(|t| vehicle_types.get(t))
+        .cloned()
+        .unwrap_or_else(|| VehicleClass::Other("passenger".to_string()));
+    let from = edges[0].clone();
+    let to = edges.last().unwrap().clone();
+
+    let departures = if p.is_flow {
+        match flow_departures(&p.attribs) {
+            Ok(times) => times,
+            Err(_) => {
+                return Ok(1);
+            }
+        }
+    } else {
+        match p.attribs.get("depart").map(|x| Time::parse(x)) {
+            Some(Ok(t)) => vec![t],
+            _ => {
+                return Ok(1);
+            }
+        }
+    };
+    let num_departures = departures.len();
+    for (idx, depart) in departures.into_iter().enumerate() {
+        trips.push(Trip {
+            // SUMO names vehicles from a flow this way
+            id: if p.is_flow {
+                format!("{}.{}", id, idx)
+            } else {
+                id.clone()
+            },
+            depart,
+            vehicle_class: vehicle_class.clone(),
+            from: from.clone(),
+            to: to.clone(),
+        });
+    }
+    if num_departures == 0 {
+        return Ok(1);
+    }
+    Ok(0)
+}
+
+/// Flows emit vehicles at a fixed rate or a fixed number of vehicles spread evenly between
+/// `begin` and `end`. Random flows given by `probability` are approximated by their average rate.
+fn flow_departures(attribs: &BTreeMap<String, String>) -> Result<Vec<Time>> {
+    let begin = match attribs.get("begin") {
+        Some(x) => Time::parse(x)?,
+        None => Time::START_OF_DAY,
+    };
+    let end = match attribs.get("end") {
+        Some(x) => Time::parse(x)?,
+        None => Time::START_OF_DAY + Duration::hours(24),
+    };
+    if end <= begin {
+        bail!("flow ends before it begins");
+    }
+
+    if let Some(number) = attribs.get("number") {
+        let number = number.parse::<usize>()?;
+        if number == 0 {
+            return Ok(Vec::new());
+        }
+        let spacing = (end - begin) / (number as f64);
+        return Ok((0..number).map(|i| begin + spacing * (i as f64)).collect());
+    }
+
+    // Seconds between each vehicle
+    let period = if let Some(x) = attribs.get("vehsPerHour") {
+        3600.0 / x.parse::<f64>()?
+    } else if let Some(x) = attribs.get("period") {
+        x.parse::<f64>()?
+    } else if let Some(x) = attribs.get("probability") {
+        1.0 / x.parse::<f64>()?
+    } else {
+        bail!("flow has no rate");
+    };
+    if !period.is_finite() || period <= 0.0 {
+        bail!("flow has a bad rate");
+    }
+    let period = Duration::seconds(period);
+    let mut times = Vec::new();
+    let mut t = begin;
+    while t < end {
+        times.push(t);
+        t += period;
+    }
+    Ok(times)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flow(attribs: Vec<(&str, &str)>) -> Result<Vec<Time>> {
+        flow_departures(
+            &attribs
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+
+    fn at(seconds: f64) -> Time {
+        Time::START_OF_DAY + Duration::seconds(seconds)
+    }
+
+    #[test]
+    fn test_flow_departures() {
+        // A fixed number spread evenly
+        assert_eq!(
+            flow(vec![("begin", "0"), ("end", "100"), ("number", "4")]).unwrap(),
+            vec![at(0.0), at(25.0), at(50.0), at(75.0)]
+        );
+        assert!(flow(vec![("begin", "0"), ("end", "100"), ("number", "0")])
+            .unwrap()
+            .is_empty());
+        // Rates
+        assert_eq!(
+            flow(vec![("begin", "10"), ("end", "40"), ("period", "10")]).unwrap(),
+            vec![at(10.0), at(20.0), at(30.0)]
+        );
+        assert_eq!(
+            flow(vec![("begin", "0"), ("end", "3600"), ("vehsPerHour", "2")]).unwrap(),
+            vec![at(0.0), at(1800.0)]
+        );
+        assert_eq!(
+            flow(vec![("begin", "0"), ("end", "10"), ("probability", "0.25")]).unwrap(),
+            vec![at(0.0), at(4.0), at(8.0)]
+        );
+        // Without an end, flows last all day
+        assert_eq!(flow(vec![("period", "3600")]).unwrap().len(), 24);
+
+        assert!(flow(vec![("begin", "10"), ("end", "10"), ("period", "1")]).is_err());
+        assert!(flow(vec![("begin", "0"), ("end", "10")]).is_err());
+        assert!(flow(vec![("begin", "0"), ("end", "10"), ("period", "0")]).is_err());
+        assert!(flow(vec![("begin", "0"), ("end", "10"), ("probability", "0")]).is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        let demand = Demand::parse(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<routes>
+  <vType id="bike" vClass="bicycle"/>
+  <vType id="walker" vClass="pedestrian"/>
+  <route id="main" edges="a b c"/>
+  <vehicle id="v1" depart="10" route="main"/>
+  <vehicle id="v2" type="bike" depart="20">
+    <route edges="d e"/>
+  </vehicle>
+  <trip id="t1" depart="30" from="f" to="g"/>
+  <trip id="t2" depart="triggered" from="f" to="g"/>
+  <flow id="f1" type="walker" begin="0" end="60" number="2" from="h" to="i"/>
+  <person id="p1" depart="5">
+    <walk edges="a b"/>
+  </person>
+</routes>"#,
+        )
+        .unwrap();
+
+        let summary: Vec<(&str, Time, &str, &str)> = demand
+            .trips
+            .iter()
+            .map(|t| (t.id.as_str(), t.depart, t.from.0.as_str(), t.to.0.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("v1", at(10.0), "a", "c"),
+                ("v2", at(20.0), "d", "e"),
+                ("t1", at(30.0), "f", "g"),
+                ("f1.0", at(0.0), "h", "i"),
+                ("f1.1", at(30.0), "h", "i"),
+            ]
+        );
+        assert!(demand.trips[0].vehicle_class == VehicleClass::Other("passenger".to_string()));
+        assert!(demand.trips[1].vehicle_class == VehicleClass::Bicycle);
+        assert!(demand.trips[3].vehicle_class == VehicleClass::Pedestrian);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for bad in &[
+            r#"<routes><vehicle id="v" depart="0" route="missing"/></routes>"#,
+            r#"<routes><trip id="t" depart="0" from="a"/></routes>"#,
+            r#"<routes><trip depart="0" from="a" to="b"/></routes>"#,
+            r#"<routes><vehicle id="v" depart="0"><route edges=""/></vehicle></routes>"#,
+        ] {
+            assert!(Demand::parse(bad).is_err(), "{} should fail", bad);
+        }
+    }
+}
@@ -0,0 +1,958 @@
+//! Writes A/B Street maps and scenarios in SUMO's formats, so the same network and demand can be
+//! simulated with SUMO's car-following and lane-changing models.
+//!
+//! Each `Road` becomes up to two edges: `r` in the forwards direction and `-r` backwards.
+//! Intersections become junctions with the same ID. Parking lanes, shared left-turn lanes, and
+//! lanes under construction aren't exported. Vehicle turns become connections through one
+//! internal lane each, and traffic signal stages become phases of a `tlLogic`. A/B Street has no
+//! yellow time between stages, so neither does the export. Sidewalks meeting at the same corner of
+//! an intersection are joined by a walking area, and crosswalks become crossings between two
+//! walking areas.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use anyhow::Result;
+
+use abstutil::Timer;
+use geom::{Distance, Pt2D, Time};
+use map_model::{
+    osm, Direction, DrivingSide, IntersectionID, IntersectionType, LaneID, LaneType, Map, RoadID,
+    StageType, Turn, TurnID, TurnPriority, TurnType,
+};
+use sim::{Scenario, TripEndpoint, TripMode};
+
+/// Writes the map as a SUMO .net.xml file.
+pub fn export_map(map: &Map, path: &str, timer: &mut Timer) -> Result<()> {
+    timer.start(format!("export {}", path));
+    let lanes = exported_lanes(map);
+    let junctions = all_junctions(map, &lanes);
+    let fix = |pt: Pt2D| flip_y(map, pt);
+
+    let mut f = BufWriter::new(File::create(path)?);
+    writeln!(f, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        f,
+        r#"<net version="1.9" junctionCornerDetail="5" limitTurnSpeed="5.50"{}>"#,
+        if map.get_config().driving_side == DrivingSide::Left {
+            r#" lefthand="true""#
+        } else {
+            ""
+        }
+    )?;
+    let bounds = map.get_bounds();
+    let gps = map.get_gps_bounds();
+    writeln!(
+        f,
+        r#"  <location netOffset="0.00,0.00" convBoundary="{:.2},{:.2},{:.2},{:.2}" origBoundary="{:.6},{:.6},{:.6},{:.6}" projParameter="!"/>"#,
+        bounds.min_x,
+        0.0,
+        bounds.max_x,
+        bounds.max_y - bounds.min_y,
+        gps.min_lon,
+        gps.min_lat,
+        gps.max_lon,
+        gps.max_lat
+    )?;
+
+    // Types are only needed for readers that expect them; every edge overrides the attributes.
+    let mut types: BTreeMap<String, (usize, f64)> = BTreeMap::new();
+    for r in map.all_roads() {
+        types
+            .entry(edge_type(map, r.id))
+            .or_insert((priority(map, r.id), r.speed_limit.inner_meters_per_second()));
+    }
+    for (id, (priority, speed)) in types {
+        writeln!(
+            f,
+            r#"  <type id="{}" priority="{}" speed="{:.2}"/>"#,
+            escape(&id),
+            priority,
+            speed
+        )?;
+    }
+
+    for (i, junction) in &junctions {
+        for (idx, t) in junction.turns.iter().enumerate() {
+            let turn = map.get_t(*t);
+            let speed = map
+                .get_parent(turn.id.src)
+                .speed_limit
+                .min(map.get_parent(turn.id.dst).speed_limit);
+            writeln!(f, r#"  <edge id=":{}_{}" function="internal">"#, i.0, idx)?;
+            writeln!(
+                f,
+                r#"    <lane id=":{}_{}_0" index="0" speed="{:.2}" length="{:.2}" shape="{}"/>"#,
+                i.0,
+                idx,
+                speed.inner_meters_per_second(),
+                turn.geom.length().max(Distance::meters(0.1)).inner_meters(),
+                shape(turn.geom.points().iter().cloned().map(fix))
+            )?;
+            writeln!(f, "  </edge>")?;
+        }
+        for (idx, (t, _, _)) in junction.crossings.iter().enumerate() {
+            let turn = map.get_t(*t);
+            let sidewalk = map.get_l(turn.id.src);
+            let crossed: BTreeSet<&String> = lanes
+                .iter()
+                .filter(|(l, _)| map.get_l(**l).parent == sidewalk.parent)
+                .map(|(_, (edge, _, _))| edge)
+                .collect();
+            writeln!(
+                f,
+                r#"  <edge id=":{}_c{}" function="crossing" crossingEdges="{}">"#,
+                i.0,
+                idx,
+                crossed.into_iter().cloned().collect::<Vec<_>>().join(" ")
+            )?;
+            writeln!(
+                f,
+                r#"    <lane id=":{}_c{}_0" index="0" allow="pedestrian" speed="1.00" length="{:.2}" width="{:.2}" shape="{}"/>"#,
+                i.0,
+                idx,
+                turn.geom.length().max(Distance::meters(0.1)).inner_meters(),
+                sidewalk.width.inner_meters(),
+                shape(turn.geom.points().iter().cloned().map(fix))
+            )?;
+            writeln!(f, "  </edge>")?;
+        }
+        for (idx, corner) in junction.corners.iter().enumerate() {
+            let length = corner
+                .pts
+                .windows(2)
+                .fold(Distance::ZERO, |sum, pair| sum + pair[0].dist_to(pair[1]));
+            let width = corner
+                .sidewalks
+                .iter()
+                .fold(Distance::ZERO, |max, l| max.max(map.get_l(*l).width));
+            writeln!(
+                f,
+                r#"  <edge id=":{}_w{}" function="walkingarea">"#,
+                i.0, idx
+            )?;
+            writeln!(
+                f,
+                r#"    <lane id=":{}_w{}_0" index="0" allow="pedestrian" speed="1.00" length="{:.2}" width="{:.2}" shape="{}"/>"#,
+                i.0,
+                idx,
+                length.max(Distance::meters(0.1)).inner_meters(),
+                width.inner_meters(),
+                shape(corner.pts.iter().cloned().map(fix))
+            )?;
+            writeln!(f, "  </edge>")?;
+        }
+    }
+
+    for r in map.all_roads() {
+        for dir in vec![Direction::Fwd, Direction::Back] {
+            let mut children: Vec<(LaneID, usize)> = Vec::new();
+            for (l, d, _) in r.lanes_ltr() {
+                if let Some((_, _, idx)) = lanes.get(&l) {
+                    if d == dir {
+                        children.push((l, *idx));
+                    }
+                }
+            }
+            if children.is_empty() {
+                continue;
+            }
+            children.sort_by_key(|(_, idx)| *idx);
+            let (from, to) = if dir == Direction::Fwd {
+                (r.src_i, r.dst_i)
+            } else {
+                (r.dst_i, r.src_i)
+            };
+            let name = r.get_name(None);
+            writeln!(
+                f,
+                r#"  <edge id="{}" from="{}" to="{}" priority="{}" type="{}"{}>"#,
+                edge_id(r.id, dir),
+                from.0,
+                to.0,
+                priority(map, r.id),
+                escape(&edge_type(map, r.id)),
+                if name == "???" {
+                    String::new()
+                } else {
+                    format!(r#" name="{}""#, escape(&name))
+                }
+            )?;
+            for (l, idx) in children {
+                let lane = map.get_l(l);
+                writeln!(
+                    f,
+                    r#"    <lane id="{}" index="{}"{} speed="{:.2}" length="{:.2}" width="{:.2}" shape="{}"/>"#,
+                    lane_id(&lanes, l),
+                    idx,
+                    allow(lane.lane_type),
+                    r.speed_limit.inner_meters_per_second(),
+                    lane.length().inner_meters(),
+                    lane.width.inner_meters(),
+                    shape(lane.lane_center_pts.points().iter().cloned().map(fix))
+                )?;
+            }
+            writeln!(f, "  </edge>")?;
+        }
+    }
+
+    for (i, junction) in &junctions {
+        let links = junction.links();
+        if map.get_i(*i).intersection_type != IntersectionType::TrafficSignal || links.is_empty() {
+            continue;
+        }
+        let signal = map.get_traffic_signal(*i);
+        let actuated = signal.stages.iter().any(|s| match s.stage_type {
+            StageType::Fixed(_) => false,
+            StageType::Variable(_, _, _) => true,
+        });
+        writeln!(
+            f,
+            r#"  <tlLogic id="{}" type="{}" programID="0" offset="{:.0}">"#,
+            i.0,
+            if actuated { "actuated" } else { "static" },
+            signal.offset.inner_seconds()
+        )?;
+        for stage in &signal.stages {
+            let state: String = links
+                .iter()
+                .map(|t| match stage.get_priority_of_turn(*t, signal) {
+                    TurnPriority::Protected => 'G',
+                    TurnPriority::Yield => 'g',
+                    TurnPriority::Banned => 'r',
+                })
+                .collect();
+            let duration = stage.stage_type.simple_duration().inner_seconds();
+            match stage.stage_type {
+                StageType::Fixed(_) => {
+                    writeln!(
+                        f,
+                        r#"    <phase duration="{:.0}" state="{}"/>"#,
+                        duration, state
+                    )?;
+                }
+                StageType::Variable(_, _, additional) => {
+                    writeln!(
+                        f,
+                        r#"    <phase duration="{:.0}" minDur="{:.0}" maxDur="{:.0}" state="{}"/>"#,
+                        duration,
+                        duration,
+                        duration + additional.inner_seconds(),
+                        state
+                    )?;
+                }
+            }
+        }
+        writeln!(f, "  </tlLogic>")?;
+    }
+
+    for i in map.all_intersections() {
+        let junction = &junctions[&i.id];
+        let links = junction.links();
+        let mut incoming: Vec<String> = i
+            .incoming_lanes
+            .iter()
+            .filter(|l| lanes.contains_key(l))
+            .map(|l| lane_id(&lanes, *l))
+            .collect();
+        incoming.extend((0..junction.corners.len()).map(|idx| format!(":{}_w{}_0", i.id.0, idx)));
+        let mut internal: Vec<String> = (0..junction.turns.len())
+            .map(|idx| format!(":{}_{}_0", i.id.0, idx))
+            .collect();
+        internal.extend((0..junction.crossings.len()).map(|idx| format!(":{}_c{}_0", i.id.0, idx)));
+        let center = fix(i.polygon.center());
+        writeln!(
+            f,
+            r#"  <junction id="{}" type="{}" x="{:.2}" y="{:.2}" incLanes="{}" intLanes="{}" shape="{}">"#,
+            i.id.0,
+            junction_type(map, i.id, &links),
+            center.x(),
+            center.y(),
+            incoming.join(" "),
+            internal.join(" "),
+            shape(polygon_pts(&i.polygon).into_iter().map(fix))
+        )?;
+        for (idx, t1) in links.iter().enumerate() {
+            let turn1 = map.get_t(*t1);
+            let mut response = String::new();
+            let mut foes = String::new();
+            // SUMO lists the highest link index first
+            for t2 in links.iter().rev() {
+                let turn2 = map.get_t(*t2);
+                let conflict = turn1.conflicts_with(turn2);
+                foes.push(if conflict { '1' } else { '0' });
+                response.push(if conflict && must_yield(map, turn1, turn2) {
+                    '1'
+                } else {
+                    '0'
+                });
+            }
+            writeln!(
+                f,
+                r#"    <request index="{}" response="{}" foes="{}" cont="0"/>"#,
+                idx, response, foes
+            )?;
+        }
+        writeln!(f, "  </junction>")?;
+    }
+
+    for (i, junction) in &junctions {
+        let is_signal = map.get_i(*i).intersection_type == IntersectionType::TrafficSignal;
+        let jtype = junction_type(map, *i, &junction.links());
+        let tl = |idx: usize| {
+            if is_signal {
+                format!(r#" tl="{}" linkIndex="{}""#, i.0, idx)
+            } else {
+                String::new()
+            }
+        };
+        for (idx, t) in junction.turns.iter().enumerate() {
+            let turn = map.get_t(*t);
+            let (from_edge, _, from_lane) = &lanes[&turn.id.src];
+            let (to_edge, _, to_lane) = &lanes[&turn.id.dst];
+            let dir = match turn.turn_type {
+                TurnType::Left => "l",
+                TurnType::Right => "r",
+                TurnType::UTurn => "t",
+                _ => "s",
+            };
+            writeln!(
+                f,
+                r#"  <connection from="{}" to="{}" fromLane="{}" toLane="{}" via=":{}_{}_0"{} dir="{}" state="{}"/>"#,
+                from_edge,
+                to_edge,
+                from_lane,
+                to_lane,
+                i.0,
+                idx,
+                tl(idx),
+                dir,
+                link_state(map, *t, jtype)
+            )?;
+            writeln!(
+                f,
+                r#"  <connection from=":{}_{}" to="{}" fromLane="0" toLane="{}" dir="{}" state="M"/>"#,
+                i.0, idx, to_edge, to_lane, dir
+            )?;
+        }
+
+        for (idx, corner) in junction.corners.iter().enumerate() {
+            for l in &corner.sidewalks {
+                let (edge, _, lane_idx) = &lanes[l];
+                if map.get_l(*l).dst_i == *i {
+                    writeln!(
+                        f,
+                        r#"  <connection from="{}" to=":{}_w{}" fromLane="{}" toLane="0" dir="s" state="M"/>"#,
+                        edge, i.0, idx, lane_idx
+                    )?;
+                } else {
+                    writeln!(
+                        f,
+                        r#"  <connection from=":{}_w{}" to="{}" fromLane="0" toLane="{}" dir="s" state="M"/>"#,
+                        i.0, idx, edge, lane_idx
+                    )?;
+                }
+            }
+        }
+        for (idx, (t, from, to)) in junction.crossings.iter().enumerate() {
+            writeln!(
+                f,
+                r#"  <connection from=":{}_w{}" to=":{}_c{}" fromLane="0" toLane="0"{} dir="s" state="{}"/>"#,
+                i.0,
+                from,
+                i.0,
+                idx,
+                tl(junction.turns.len() + idx),
+                link_state(map, *t, jtype)
+            )?;
+            writeln!(
+                f,
+                r#"  <connection from=":{}_c{}" to=":{}_w{}" fromLane="0" toLane="0" dir="s" state="M"/>"#,
+                i.0, idx, i.0, to
+            )?;
+        }
+    }
+
+    writeln!(f, "</net>")?;
+    timer.stop(format!("export {}", path));
+    Ok(())
+}
+
+/// Writes the scenario as a SUMO .rou.xml file. Every trip becomes its own vehicle or person,
+/// named after the person's index in the scenario and the trip's index in their schedule.
+/// Driving and biking trips start and end on the lane A/B Street would use, but there's no
+/// parking: vehicles appear and vanish. Transit trips become `personTrip`s, leaving SUMO to pick
+/// the route. Cancelled trips and trips that can't be placed on the network are skipped; returns
+/// how many.
+pub fn export_scenario(
+    scenario: &Scenario,
+    map: &Map,
+    path: &str,
+    timer: &mut Timer,
+) -> Result<usize> {
+    timer.start(format!("export {}", path));
+    let lanes = exported_lanes(map);
+
+    // SUMO requires everything to be sorted by departure time
+    let mut entries: Vec<(Time, String)> = Vec::new();
+    let mut skipped = 0;
+    for (person_idx, person) in scenario.people.iter().enumerate() {
+        let mut from = person.origin.clone();
+        for (trip_idx, trip) in person.trips.iter().enumerate() {
+            let to = trip.destination.clone();
+            let id = format!("{}_{}", person_idx, trip_idx);
+            let depart = trip.depart.inner_seconds();
+            let req = if trip.cancelled {
+                None
+            } else {
+                TripEndpoint::path_req(from, to.clone(), trip.mode, map)
+            };
+            from = to;
+            let req = match req {
+                Some(req) => req,
+                None => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+            let (start, end) = match (lanes.get(&req.start.lane()), lanes.get(&req.end.lane())) {
+                (Some(start), Some(end)) => (start, end),
+                _ => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+            let start_pos = req.start.dist_along().inner_meters();
+            let end_pos = req.end.dist_along().inner_meters();
+
+            entries.push((
+                trip.depart,
+                match trip.mode {
+                    TripMode::Drive | TripMode::Bike => format!(
+                        r#"  <trip id="{}" type="{}" depart="{:.2}" from="{}" to="{}" departLane="{}" departPos="{:.2}" arrivalPos="{:.2}"/>"#,
+                        id,
+                        if trip.mode == TripMode::Drive {
+                            "car"
+                        } else {
+                            "bike"
+                        },
+                        depart,
+                        start.0,
+                        end.0,
+                        start.2,
+                        start_pos,
+                        end_pos
+                    ),
+                    TripMode::Walk => format!(
+                        "  <person id=\"{}\" depart=\"{:.2}\" departPos=\"{:.2}\">\n    <walk \
+                         from=\"{}\" to=\"{}\" arrivalPos=\"{:.2}\"/>\n  </person>",
+                        id, depart, start_pos, start.0, end.0, end_pos
+                    ),
+                    TripMode::Transit => format!(
+                        "  <person id=\"{}\" depart=\"{:.2}\" departPos=\"{:.2}\">\n    \
+                         <personTrip from=\"{}\" to=\"{}\" arrivalPos=\"{:.2}\" \
+                         modes=\"public\"/>\n  </person>",
+                        id, depart, start_pos, start.0, end.0, end_pos
+                    ),
+                },
+            ));
+        }
+    }
+    entries.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    let mut f = BufWriter::new(File::create(path)?);
+    writeln!(f, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(f, "<routes>")?;
+    writeln!(f, r#"  <vType id="car" vClass="passenger"/>"#)?;
+    writeln!(f, r#"  <vType id="bike" vClass="bicycle"/>"#)?;
+    for (_, entry) in entries {
+        writeln!(f, "{}", entry)?;
+    }
+    writeln!(f, "</routes>")?;
+    timer.stop(format!("export {}", path));
+    Ok(skipped)
+}
+
+/// For every exported lane, its edge, direction, and index. Index 0 is the lane farthest from the
+/// center of the road.
+fn exported_lanes(map: &Map) -> BTreeMap<LaneID, (String, Direction, usize)> {
+    let mut result = BTreeMap::new();
+    for r in map.all_roads() {
+        for dir in vec![Direction::Fwd, Direction::Back] {
+            let mut children: Vec<LaneID> = r
+                .lanes_ltr()
+                .into_iter()
+                .filter(|(_, d, lt)| *d == dir && allow_lane(*lt))
+                .map(|(l, _, _)| l)
+                .collect();
+            // When driving on the right, forwards lanes are listed from the center out, and
+            // backwards lanes from the outside in. It's the opposite when driving on the left.
+            if (dir == Direction::Fwd) == (map.get_config().driving_side == DrivingSide::Right) {
+                children.reverse();
+            }
+            for (idx, l) in children.into_iter().enumerate() {
+                result.insert(l, (edge_id(r.id, dir), dir, idx));
+            }
+        }
+    }
+    result
+}
+
+/// What gets exported for each intersection
+struct Junction {
+    /// Vehicle turns between exported lanes, in SUMO's link order: grouped by the incoming lane
+    turns: Vec<TurnID>,
+    /// Sidewalks meeting at the same corner, which become walking areas
+    corners: Vec<Corner>,
+    /// One crosswalk between each pair of corners, and the indices of those corners
+    crossings: Vec<(TurnID, usize, usize)>,
+}
+
+struct Corner {
+    sidewalks: Vec<LaneID>,
+    /// Where the sidewalks and crossings touch this corner
+    pts: Vec<Pt2D>,
+}
+
+impl Junction {
+    /// Every link in index order: vehicle turns, then crossings
+    fn links(&self) -> Vec<TurnID> {
+        self.turns
+            .iter()
+            .cloned()
+            .chain(self.crossings.iter().map(|(t, _, _)| *t))
+            .collect()
+    }
+}
+
+fn all_junctions(
+    map: &Map,
+    lanes: &BTreeMap<LaneID, (String, Direction, usize)>,
+) -> BTreeMap<IntersectionID, Junction> {
+    let mut result = BTreeMap::new();
+    for i in map.all_intersections() {
+        let mut junction = Junction {
+            turns: Vec::new(),
+            corners: Vec::new(),
+            crossings: Vec::new(),
+        };
+        if i.is_border() {
+            result.insert(i.id, junction);
+            continue;
+        }
+
+        let order: Vec<LaneID> = i
+            .incoming_lanes
+            .iter()
+            .filter(|l| lanes.contains_key(l))
+            .cloned()
+            .collect();
+        let mut turns: Vec<&Turn> = map
+            .get_turns_in_intersection(i.id)
+            .into_iter()
+            .filter(|t| {
+                !t.between_sidewalks()
+                    && lanes.contains_key(&t.id.src)
+                    && lanes.contains_key(&t.id.dst)
+            })
+            .collect();
+        turns.sort_by_key(|t| {
+            (
+                order.iter().position(|l| *l == t.id.src),
+                lanes[&t.id.dst].2,
+                t.id.dst,
+            )
+        });
+        junction.turns = turns.into_iter().map(|t| t.id).collect();
+
+        // Group sidewalks into corners, joined by the turns between them
+        let mut groups: Vec<Vec<LaneID>> = Vec::new();
+        let mut group_of: BTreeMap<LaneID, usize> = BTreeMap::new();
+        for l in i.incoming_lanes.iter().chain(i.outgoing_lanes.iter()) {
+            if map.get_l(*l).is_walkable() && lanes.contains_key(l) && !group_of.contains_key(l) {
+                group_of.insert(*l, groups.len());
+                groups.push(vec![*l]);
+            }
+        }
+        let mut crossings = Vec::new();
+        let mut crossed = BTreeSet::new();
+        for turn_type in &[TurnType::SharedSidewalkCorner, TurnType::Crosswalk] {
+            for t in map.get_turns_in_intersection(i.id) {
+                if t.turn_type != *turn_type {
+                    continue;
+                }
+                let (g1, g2) = match (group_of.get(&t.id.src), group_of.get(&t.id.dst)) {
+                    (Some(g1), Some(g2)) if g1 != g2 => (*g1, *g2),
+                    _ => continue,
+                };
+                if *turn_type == TurnType::SharedSidewalkCorner {
+                    let moved = std::mem::take(&mut groups[g2]);
+                    for l in &moved {
+                        group_of.insert(*l, g1);
+                    }
+                    groups[g1].extend(moved);
+                } else if crossed.insert((g1.min(g2), g1.max(g2))) {
+                    // Crosswalks go both ways, but one crossing is enough
+                    crossings.push((t.id, g1, g2));
+                }
+            }
+        }
+
+        // Only corners that pedestrians can walk through need a walking area
+        let mut corner_idx = BTreeMap::new();
+        for (idx, sidewalks) in groups.into_iter().enumerate() {
+            if sidewalks.len() > 1 || crossings.iter().any(|(_, g1, g2)| *g1 == idx || *g2 == idx) {
+                corner_idx.insert(idx, junction.corners.len());
+                let mut pts = Vec::new();
+                for l in &sidewalks {
+                    let lane = map.get_l(*l);
+                    push_unique(
+                        &mut pts,
+                        if lane.src_i == i.id {
+                            lane.lane_center_pts.first_pt()
+                        } else {
+                            lane.lane_center_pts.last_pt()
+                        },
+                    );
+                }
+                junction.corners.push(Corner { sidewalks, pts });
+            }
+        }
+        for (t, g1, g2) in crossings {
+            let (c1, c2) = (corner_idx[&g1], corner_idx[&g2]);
+            let geom = &map.get_t(t).geom;
+            push_unique(&mut junction.corners[c1].pts, geom.first_pt());
+            push_unique(&mut junction.corners[c2].pts, geom.last_pt());
+            junction.crossings.push((t, c1, c2));
+        }
+
+        result.insert(i.id, junction);
+    }
+    result
+}
+
+fn push_unique(pts: &mut Vec<Pt2D>, pt: Pt2D) {
+    if !pts.contains(&pt) {
+        pts.push(pt);
+    }
+}
+
+/// Does `t1` have to yield to `t2`, assuming they conflict?
+fn must_yield(map: &Map, t1: &Turn, t2: &Turn) -> bool {
+    let i = t1.id.parent;
+    match map.get_i(i).intersection_type {
+        IntersectionType::StopSign => {
+            let ss = map.get_stop_sign(i);
+            ss.get_priority(t1.id, map) == TurnPriority::Yield
+                && ss.get_priority(t2.id, map) == TurnPriority::Protected
+        }
+        IntersectionType::TrafficSignal => {
+            let signal = map.get_traffic_signal(i);
+            signal.stages.iter().any(|s| {
+                s.get_priority_of_turn(t1.id, signal) == TurnPriority::Yield
+                    && s.get_priority_of_turn(t2.id, signal) == TurnPriority::Protected
+            })
+        }
+        IntersectionType::Border | IntersectionType::Construction => false,
+    }
+}
+
+/// How SUMO should treat a link, given the type of junction
+fn link_state(map: &Map, t: TurnID, jtype: &str) -> &'static str {
+    match jtype {
+        "traffic_light" => "O",
+        "allway_stop" => "=",
+        "priority" | "priority_stop" => {
+            if map.get_stop_sign(t.parent).get_priority(t, map) == TurnPriority::Protected {
+                "M"
+            } else if jtype == "priority_stop" {
+                "s"
+            } else {
+                "m"
+            }
+        }
+        _ => "M",
+    }
+}
+
+fn junction_type(map: &Map, i: IntersectionID, links: &[TurnID]) -> &'static str {
+    if links.is_empty() {
+        return "dead_end";
+    }
+    match map.get_i(i).intersection_type {
+        IntersectionType::TrafficSignal => "traffic_light",
+        IntersectionType::StopSign => {
+            let ss = map.get_stop_sign(i);
+            let stops: BTreeSet<bool> = ss.roads.values().map(|r| r.must_stop).collect();
+            if !stops.contains(&false) {
+                "allway_stop"
+            } else if stops.contains(&true) {
+                "priority_stop"
+            } else {
+                "priority"
+            }
+        }
+        IntersectionType::Border | IntersectionType::Construction => "dead_end",
+    }
+}
+
+fn edge_id(r: RoadID, dir: Direction) -> String {
+    match dir {
+        Direction::Fwd => format!("{}", r.0),
+        Direction::Back => format!("-{}", r.0),
+    }
+}
+
+fn lane_id(lanes: &BTreeMap<LaneID, (String, Direction, usize)>, l: LaneID) -> String {
+    let (edge, _, idx) = &lanes[&l];
+    format!("{}_{}", edge, idx)
+}
+
+fn edge_type(map: &Map, r: RoadID) -> String {
+    let tags = &map.get_r(r).osm_tags;
+    if let Some(hwy) = tags.get(osm::HIGHWAY) {
+        format!("highway.{}", hwy)
+    } else if let Some(railway) = tags.get("railway") {
+        format!("railway.{}", railway)
+    } else {
+        "highway.road".to_string()
+    }
+}
+
+fn priority(map: &Map, r: RoadID) -> usize {
+    match map.get_r(r).get_rank() {
+        osm::RoadRank::Local => 1,
+        osm::RoadRank::Arterial => 2,
+        osm::RoadRank::Highway => 3,
+    }
+}
+
+fn allow_lane(lt: LaneType) -> bool {
+    match lt {
+        LaneType::Parking | LaneType::SharedLeftTurn | LaneType::Construction => false,
+        _ => true,
+    }
+}
+
+fn allow(lt: LaneType) -> &'static str {
+    match lt {
+        LaneType::Sidewalk | LaneType::Shoulder => r#" allow="pedestrian""#,
+        LaneType::Biking => r#" allow="bicycle""#,
+        LaneType::Bus => r#" allow="bus""#,
+        LaneType::LightRail => r#" allow="rail_urban""#,
+        _ => r#" disallow="pedestrian""#,
+    }
+}
+
+/// SUMO's Y axis points north, the opposite of A/B Street's.
+fn flip_y(map: &Map, pt: Pt2D) -> Pt2D {
+    Pt2D::new(pt.x(), map.get_bounds().max_y - pt.y())
+}
+
+fn polygon_pts(polygon: &geom::Polygon) -> Vec<Pt2D> {
+    let mut pts = polygon.points().clone();
+    if pts.len() > 1 && pts[0] == *pts.last().unwrap() {
+        pts.pop();
+    }
+    pts
+}
+
+fn shape<I: Iterator<Item = Pt2D>>(pts: I) -> String {
+    pts.map(|pt| format!("{:.2},{:.2}", pt.x(), pt.y()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn escape(x: &str) -> String {
+    x.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use abstio::MapName;
+    use abstutil::Tags;
+    use geom::{GPSBounds, LonLat};
+    use map_model::raw::{OriginalRoad, RawIntersection, RawMap, RawRoad};
+    use sim::{IndividTrip, PersonSpec, TripPurpose};
+
+    use super::*;
+    use crate::{EdgeID, Network};
+
+    /// A T intersection with a stop sign. The three ends are borders.
+    fn tiny_map() -> Map {
+        let mut raw = RawMap::blank(MapName::new("test", "tiny"));
+        raw.gps_bounds = GPSBounds::from(vec![
+            LonLat::new(-122.31, 47.61),
+            LonLat::new(-122.30, 47.62),
+        ]);
+        raw.boundary_polygon = raw.gps_bounds.to_bounds().get_rectangle();
+        for (id, x, y, intersection_type) in vec![
+            (1, 200.0, 200.0, IntersectionType::StopSign),
+            (2, 50.0, 200.0, IntersectionType::Border),
+            (3, 350.0, 200.0, IntersectionType::Border),
+            (4, 200.0, 350.0, IntersectionType::Border),
+        ] {
+            raw.intersections.insert(
+                osm::NodeID(id),
+                RawIntersection {
+                    point: Pt2D::new(x, y),
+                    intersection_type,
+                    elevation: Distance::ZERO,
+                },
+            );
+        }
+        for (way, end) in vec![(10, 2), (11, 3), (12, 4)] {
+            let mut osm_tags = Tags::new(BTreeMap::new());
+            osm_tags.insert(osm::HIGHWAY, "residential");
+            osm_tags.insert("sidewalk", "both");
+            osm_tags.insert(osm::NAME, format!("Street {}", way));
+            raw.roads.insert(
+                OriginalRoad::new(way, (1, end)),
+                RawRoad {
+                    center_points: vec![
+                        raw.intersections[&osm::NodeID(1)].point,
+                        raw.intersections[&osm::NodeID(end)].point,
+                    ],
+                    osm_tags,
+                    turn_restrictions: Vec::new(),
+                    complicated_turn_restrictions: Vec::new(),
+                    lane_connectivity: Vec::new(),
+                },
+            );
+        }
+        Map::create_from_raw(raw, false, false, &mut Timer::throwaway())
+    }
+
+    fn intersection(map: &Map, id: i64) -> IntersectionID {
+        map.all_intersections()
+            .iter()
+            .find(|i| i.orig_id == osm::NodeID(id))
+            .unwrap()
+            .id
+    }
+
+    fn border(map: &Map, id: i64) -> TripEndpoint {
+        TripEndpoint::Border(intersection(map, id))
+    }
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("sumo_export_{}_{}", std::process::id(), name))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    #[test]
+    fn test_export_map() {
+        let map = tiny_map();
+        let path = temp_path("tiny.net.xml");
+        export_map(&map, &path, &mut Timer::throwaway()).unwrap();
+        let network = Network::load(&path, &mut Timer::throwaway()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(network.junctions.len(), map.all_intersections().len());
+        // Every road is two-way
+        assert_eq!(network.normal_edges.len(), 2 * map.all_roads().len());
+        let lanes = exported_lanes(&map);
+        let num_lanes: usize = network.normal_edges.values().map(|e| e.lanes.len()).sum();
+        assert_eq!(num_lanes, lanes.len());
+
+        let center = intersection(&map, 1);
+        let junction = &all_junctions(&map, &lanes)[&center];
+        // Every vehicle turn, crossing, and walking area is there
+        for idx in 0..junction.turns.len() {
+            assert!(network
+                .internal_edges
+                .contains_key(&EdgeID(format!(":{}_{}", center.0, idx))));
+        }
+        assert!(!junction.crossings.is_empty());
+        for idx in 0..junction.crossings.len() {
+            assert!(network
+                .internal_edges
+                .contains_key(&EdgeID(format!(":{}_c{}", center.0, idx))));
+        }
+        // Sidewalks on both sides of three roads meet at three corners
+        assert_eq!(junction.corners.len(), 3);
+        for idx in 0..junction.corners.len() {
+            assert!(network
+                .internal_edges
+                .contains_key(&EdgeID(format!(":{}_w{}", center.0, idx))));
+        }
+
+        // Pedestrians can reach every sidewalk touching the middle through a walking area
+        let walking_area = format!(":{}_w", center.0);
+        for l in map.get_i(center).incoming_lanes.iter() {
+            if !map.get_l(*l).is_walkable() {
+                continue;
+            }
+            let (edge, _, idx) = &lanes[l];
+            assert!(network.connections.iter().any(|c| c.from.0 == *edge
+                && c.from_lane == *idx
+                && c.to.0.starts_with(&walking_area)));
+        }
+        for l in map.get_i(center).outgoing_lanes.iter() {
+            if !map.get_l(*l).is_walkable() {
+                continue;
+            }
+            let (edge, _, idx) = &lanes[l];
+            assert!(network.connections.iter().any(|c| c.to.0 == *edge
+                && c.to_lane == *idx
+                && c.from.0.starts_with(&walking_area)));
+        }
+        // And every vehicle turn is a connection through its internal lane
+        assert_eq!(
+            network
+                .connections
+                .iter()
+                .filter(|c| c.via.is_some())
+                .count(),
+            junction.turns.len()
+        );
+    }
+
+    #[test]
+    fn test_export_scenario() {
+        let map = tiny_map();
+        let mut scenario = Scenario::empty(&map, "test");
+        let trip = |hours: usize, to: TripEndpoint, mode: TripMode| {
+            IndividTrip::new(
+                Time::START_OF_DAY + geom::Duration::hours(hours),
+                TripPurpose::Work,
+                to,
+                mode,
+            )
+        };
+        scenario.people.push(PersonSpec {
+            orig_id: None,
+            origin: border(&map, 2),
+            trips: vec![
+                trip(9, border(&map, 3), TripMode::Drive),
+                trip(10, border(&map, 4), TripMode::Walk),
+            ],
+        });
+        let mut cancelled = trip(8, border(&map, 4), TripMode::Bike);
+        cancelled.cancelled = true;
+        scenario.people.push(PersonSpec {
+            orig_id: None,
+            origin: border(&map, 3),
+            trips: vec![cancelled],
+        });
+
+        let path = temp_path("tiny.rou.xml");
+        let skipped = export_scenario(&scenario, &map, &path, &mut Timer::throwaway()).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(skipped, 1);
+        let drive = contents.find(r#"<trip id="0_0" type="car" depart="32400.00""#);
+        let walk = contents.find(r#"<person id="0_1" depart="36000.00""#);
+        assert!(drive.is_some());
+        assert!(walk.is_some());
+        assert!(drive < walk);
+        assert!(!contents.contains(r#"id="1_0""#));
+    }
+}
@@ -2,16 +2,28 @@
 
 #[macro_use]
 extern crate anyhow;
+#[macro_use]
+extern crate log;
 
 use std::collections::BTreeMap;
 
 use geom::{Distance, PolyLine, Polygon, Pt2D, Speed};
 
+pub use self::demand::{Demand, Trip};
+pub use self::export::{export_map, export_scenario};
 pub use self::raw::{Connection, Direction, EdgeID, InternalLaneID, LaneID, NodeID};
 
+mod demand;
+mod export;
 mod normalize;
 mod raw;
 
+/// Maps converted from SUMO remember the ID of the edge going forwards along each road in this
+/// tag.
+pub const EDGE_ID: &str = "id";
+/// And the ID of the edge going backwards, if there is one, in this tag.
+pub const REVERSE_EDGE_ID: &str = "reverse_id";
+
 /// A normalized form of a SUMO
 /// [network](https://sumo.dlr.de/docs/Networks/SUMO_Road_Networks.html). A `raw::Network` is a direct representation of a .net.xml file. That's further simplified to produce this structure, which should be easier to work with. The
 /// transformations:
@@ -74,7 +86,7 @@ pub struct Junction {
     pub shape: Polygon,
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, PartialEq)]
 pub enum VehicleClass {
     Pedestrian,
     Bicycle,
@@ -83,3 +95,14 @@ pub enum VehicleClass {
     // https://sumo.dlr.de/docs/Definition_of_Vehicles,_Vehicle_Types,_and_Routes.html#abstract_vehicle_class
     Other(String),
 }
+
+impl VehicleClass {
+    pub fn parse(x: &str) -> VehicleClass {
+        match x {
+            "pedestrian" => VehicleClass::Pedestrian,
+            "bicycle" => VehicleClass::Bicycle,
+            "rail_urban" => VehicleClass::RailUrban,
+            other => VehicleClass::Other(other.to_string()),
+        }
+    }
+}
@@ -85,6 +85,8 @@ fn convert(orig_path: &str, network: Network) -> Result<Map> {
                 dst_i,
 
                 bus_stops: BTreeSet::new(),
+                allowed_turns: None,
+                lane_type_windows: Vec::new(),
 
                 driving_blackhole: false,
                 biking_blackhole: false,
@@ -100,7 +102,7 @@ fn convert(orig_path: &str, network: Network) -> Result<Map> {
             let speed_limit = edge.lanes[0].speed;
 
             let mut tags = BTreeMap::new();
-            tags.insert("id".to_string(), edge.id.0.clone());
+            tags.insert(sumo::EDGE_ID.to_string(), edge.id.0.clone());
             if let Some(name) = &edge.name {
                 tags.insert("name".to_string(), name.clone());
             }
@@ -118,6 +120,7 @@ fn convert(orig_path: &str, network: Network) -> Result<Map> {
                 osm_tags: Tags::new(tags),
                 turn_restrictions: Vec::new(),
                 complicated_turn_restrictions: Vec::new(),
+                lane_connectivity: Vec::new(),
                 // TODO Temporary ID. We could consider squeezing SUMO IDs into this scheme.
                 orig_id: raw::OriginalRoad::new(123, (456, 789)),
                 speed_limit,
@@ -139,6 +142,9 @@ fn convert(orig_path: &str, network: Network) -> Result<Map> {
             lanes_ltr.extend(roads[road_id.0].lanes_ltr.clone());
             // TODO Should we check that the attributes are the same for both directions?
             roads[road_id.0].lanes_ltr = lanes_ltr;
+            roads[road_id.0]
+                .osm_tags
+                .insert(sumo::REVERSE_EDGE_ID, edge.id.0.clone());
         }
     }
 
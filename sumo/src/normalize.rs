@@ -51,7 +51,8 @@ impl Network {
         }
 
         for edge in raw.edges {
-            if edge.function == raw::Function::Internal {
+            // Crossings and walking areas are internal to junctions too
+            if edge.function != raw::Function::Normal {
                 let mut lanes = Vec::new();
                 for lane in edge.lanes {
                     lanes.push(InternalLane {
@@ -87,6 +87,10 @@ pub enum Function {
     Normal,
     #[serde(rename = "internal")]
     Internal,
+    #[serde(rename = "crossing")]
+    Crossing,
+    #[serde(rename = "walkingarea")]
+    WalkingArea,
 }
 impl std::default::Default for Function {
     fn default() -> Function {
@@ -230,12 +234,7 @@ fn parse_list_vehicles<'de, D: serde::Deserializer<'de>>(
     let raw = <String>::deserialize(d)?;
     let mut vehicles = Vec::new();
     for x in raw.split(" ") {
-        vehicles.push(match x {
-            "pedestrian" => VehicleClass::Pedestrian,
-            "bicycle" => VehicleClass::Bicycle,
-            "rail_urban" => VehicleClass::RailUrban,
-            other => VehicleClass::Other(other.to_string()),
-        });
+        vehicles.push(VehicleClass::parse(x));
     }
     Ok(vehicles)
 }
@@ -7,17 +7,24 @@ use anyhow::Result;
 use rand::seq::SliceRandom;
 
 use abstio::MapName;
-use abstutil::Timer;
+use abstutil::{CmdArgs, Timer};
 use geom::{Duration, Time};
 use map_model::{IntersectionID, Map};
 use sim::{IndividTrip, PersonSpec, Scenario, TripEndpoint, TripMode, TripPurpose};
 
 fn main() -> Result<()> {
+    let mut args = CmdArgs::new();
+    let regen_proposals = args.enabled("--regen_proposals");
+    args.done();
+    if regen_proposals {
+        return check_proposals(true);
+    }
+
     test_lane_changing(&import_map(abstio::path(
         "../tests/input/lane_selection.osm",
     )))?;
     test_map_importer()?;
-    check_proposals()?;
+    check_proposals(false)?;
     smoke_test()?;
     Ok(())
 }
@@ -97,7 +104,7 @@ fn smoke_test() -> Result<()> {
         let mut sim = sim::Sim::new(&map, opts);
         // Bit of an abuse of this, but just need to fix the rng seed.
         let mut rng = sim::SimFlags::for_test("smoke_test").make_rng();
-        scenario.instantiate(&mut sim, &map, &mut rng, &mut timer);
+        scenario.instantiate(&mut sim, &map, &mut rng, &mut timer)?;
         sim.timed_step(&map, Duration::hours(1), &mut None, &mut timer);
 
         if (name.city == "seattle"
@@ -135,27 +142,45 @@ fn dump_route_goldenfile(map: &map_model::Map) -> Result<()> {
     Ok(())
 }
 
-/// Verify all edits under version control can be correctly apply to their map.
-fn check_proposals() -> Result<()> {
+/// Verify all edits under version control can be correctly apply to their map and use the
+/// current version of the edits format. Old files are upgraded through the same path the game uses
+/// to load edits, so with `regen`, write the upgraded files back instead of failing. That needs the
+/// maps the proposals are for.
+fn check_proposals(regen: bool) -> Result<()> {
     let mut timer = Timer::new("check all proposals");
     for name in abstio::list_all_objects(abstio::path("system/proposals")) {
-        match abstio::maybe_read_json::<map_model::PermanentMapEdits>(
-            abstio::path(format!("system/proposals/{}.json", name)),
-            &mut timer,
-        ) {
-            Ok(perma) => {
-                let map = map_model::Map::new(perma.map_name.path(), &mut timer);
-                if let Err(err) = perma.clone().to_edits(&map) {
+        let path = abstio::path(format!("system/proposals/{}.json", name));
+        let value: serde_json::Value = serde_json::from_slice(&abstio::slurp_file(path.clone())?)?;
+        let map_name: MapName = serde_json::from_value(value["map_name"].clone())?;
+        let map = map_model::Map::new(map_name.path(), &mut timer);
+        let edits = match map_model::MapEdits::load(&map, path.clone(), &mut timer) {
+            Ok(edits) => edits,
+            Err(err) => {
+                if let Ok(perma) =
+                    abstio::maybe_read_json::<map_model::PermanentMapEdits>(path, &mut timer)
+                {
                     abstio::write_json(
                         "repair_attempt.json".to_string(),
                         &perma.to_edits_permissive(&map).to_permanent(&map),
                     );
-                    anyhow::bail!("{} is out-of-date: {}", name, err);
                 }
+                anyhow::bail!("{} is out-of-date: {}", name, err);
             }
-            Err(err) => {
-                anyhow::bail!("{} JSON is broken: {}", name, err);
+        };
+
+        let perma = edits.to_permanent(&map);
+        if value["version"] != serde_json::Value::from(perma.version) {
+            if !regen {
+                anyhow::bail!(
+                    "{} uses version {} of the edits format, not {}. Upgrade it with `cargo run \
+                     --bin tests -- --regen_proposals`",
+                    name,
+                    value["version"],
+                    perma.version
+                );
             }
+            println!("Upgrading {} to version {}", name, perma.version);
+            abstio::write_json(path, &perma);
         }
     }
     Ok(())
@@ -216,7 +241,7 @@ fn test_lane_changing(map: &Map) -> Result<()> {
     opts.alerts = sim::AlertHandler::Silence;
     let mut sim = sim::Sim::new(&map, opts);
     let mut rng = sim::SimFlags::for_test("test_lane_changing").make_rng();
-    scenario.instantiate(&mut sim, &map, &mut rng, &mut Timer::throwaway());
+    scenario.instantiate(&mut sim, &map, &mut rng, &mut Timer::throwaway())?;
     while !sim.is_done() {
         sim.tiny_step(&map, &mut None);
     }
@@ -183,6 +183,10 @@ fn generate_manifest() -> Manifest {
         if path.contains("system/assets/") || path.contains("system/proposals") {
             continue;
         }
+        // The importer rewrites this every time it imports a whole city
+        if path.ends_with("/osm/clipped.bin") {
+            continue;
+        }
         paths.push((orig_path, path));
     }
 
@@ -11,7 +11,7 @@ use crate::{Canvas, Color, EventCtx, GfxCtx, Outcome, Panel};
 /// implementing this trait.
 pub trait SharedAppState {
     /// Before `State::event` is called, call this.
-    fn before_event(&mut self) {}
+    fn before_event(&mut self, _: &mut EventCtx) {}
     /// When DrawBaselayer::DefaultDraw is called, run this.
     fn draw_default(&self, _: &mut GfxCtx) {}
 
@@ -29,7 +29,7 @@ pub(crate) struct App<A: SharedAppState> {
 
 impl<A: SharedAppState> App<A> {
     pub(crate) fn event(&mut self, ctx: &mut EventCtx) {
-        self.shared_app_state.before_event();
+        self.shared_app_state.before_event(ctx);
 
         let transition = self
             .states
@@ -83,6 +83,14 @@ impl<A: SharedAppState> App<A> {
     /// If true, then the top-most state on the stack needs to be "woken up" with a fake mouseover
     /// event.
     fn execute_transition(&mut self, ctx: &mut EventCtx, transition: Transition<A>) -> bool {
+        // Keyboard focus belonged to a panel from the old state
+        match transition {
+            Transition::Keep | Transition::KeepWithMouseover => {}
+            _ => {
+                ctx.canvas.focused_panel = None;
+            }
+        }
+
         match transition {
             Transition::Keep => false,
             Transition::KeepWithMouseover => true,
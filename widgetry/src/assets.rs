@@ -8,7 +8,7 @@ use usvg::Options;
 use geom::Bounds;
 
 use crate::text::Font;
-use crate::{text, EventCtx, GeomBatch, GfxCtx, Prerender};
+use crate::{text, EventCtx, GeomBatch, GfxCtx, Prerender, Translations};
 
 // TODO We don't need refcell maybe? Can we take &mut Assets?
 pub struct Assets {
@@ -21,6 +21,7 @@ pub struct Assets {
     font_to_id: HashMap<Font, fontdb::ID>,
     pub text_opts: Options,
     pub read_svg: Box<dyn Fn(&str) -> Vec<u8>>,
+    pub(crate) translations: RefCell<Translations>,
 }
 
 impl Assets {
@@ -33,6 +34,7 @@ impl Assets {
             font_to_id: HashMap::new(),
             text_opts: Options::default(),
             read_svg,
+            translations: RefCell::new(Translations::English),
         };
         // All fonts are statically bundled with the library right now, on both native and web.
         // Eventually need to let people specify their own fonts dynamically at runtime.
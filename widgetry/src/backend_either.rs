@@ -0,0 +1,188 @@
+//! When the offscreen backend is built alongside a windowed one, an app could be using either.
+//! These wrappers dispatch to whichever backend set up the app. Anything drawn was uploaded by the
+//! same backend, so mixing them up is a bug.
+
+use abstutil::Timer;
+
+use crate::drawing::Uniforms;
+use crate::{backend_glow, backend_offscreen};
+use crate::{Canvas, Color, GeomBatch, ScreenDims, ScreenRectangle};
+
+pub fn setup(
+    window_title: &str,
+    timer: &mut Timer,
+) -> (PrerenderInnards, winit::event_loop::EventLoop<()>) {
+    let (inner, event_loop) = backend_glow::setup(window_title, timer);
+    (PrerenderInnards::Glow(inner), event_loop)
+}
+
+pub fn setup_offscreen(window_size: ScreenDims, timer: &mut Timer) -> PrerenderInnards {
+    PrerenderInnards::Offscreen(backend_offscreen::setup_offscreen(window_size, timer))
+}
+
+// Represents one frame that's gonna be drawn
+pub enum GfxCtxInnards<'a> {
+    Glow(backend_glow::GfxCtxInnards<'a>),
+    Offscreen(backend_offscreen::GfxCtxInnards<'a>),
+}
+
+impl<'a> GfxCtxInnards<'a> {
+    pub fn clear(&mut self, color: Color) {
+        match self {
+            GfxCtxInnards::Glow(g) => g.clear(color),
+            GfxCtxInnards::Offscreen(g) => g.clear(color),
+        }
+    }
+
+    pub fn redraw(&mut self, obj: &Drawable, uniforms: &Uniforms, prerender: &PrerenderInnards) {
+        match (self, &obj.inner, prerender) {
+            (GfxCtxInnards::Glow(g), DrawableInner::Glow(obj), PrerenderInnards::Glow(p)) => {
+                g.redraw(obj, uniforms, p)
+            }
+            (
+                GfxCtxInnards::Offscreen(g),
+                DrawableInner::Offscreen(obj),
+                PrerenderInnards::Offscreen(p),
+            ) => g.redraw(obj, uniforms, p),
+            _ => unreachable!("drawing something uploaded by a different backend"),
+        }
+    }
+
+    pub fn enable_clipping(&mut self, rect: ScreenRectangle, scale_factor: f64, canvas: &Canvas) {
+        match self {
+            GfxCtxInnards::Glow(g) => g.enable_clipping(rect, scale_factor, canvas),
+            GfxCtxInnards::Offscreen(g) => g.enable_clipping(rect, scale_factor, canvas),
+        }
+    }
+
+    pub fn disable_clipping(&mut self, scale_factor: f64, canvas: &Canvas) {
+        match self {
+            GfxCtxInnards::Glow(g) => g.disable_clipping(scale_factor, canvas),
+            GfxCtxInnards::Offscreen(g) => g.disable_clipping(scale_factor, canvas),
+        }
+    }
+
+    pub fn take_clip(&mut self, scale_factor: f64, canvas: &Canvas) -> Option<[i32; 4]> {
+        match self {
+            GfxCtxInnards::Glow(g) => g.take_clip(scale_factor, canvas),
+            GfxCtxInnards::Offscreen(g) => g.take_clip(scale_factor, canvas),
+        }
+    }
+
+    pub fn restore_clip(&mut self, clip: Option<[i32; 4]>) {
+        match self {
+            GfxCtxInnards::Glow(g) => g.restore_clip(clip),
+            GfxCtxInnards::Offscreen(g) => g.restore_clip(clip),
+        }
+    }
+}
+
+/// Geometry that's been uploaded once and can be quickly redrawn many times. Create by creating a
+/// `GeomBatch` and calling `ctx.upload(batch)`.
+pub struct Drawable {
+    inner: DrawableInner,
+    pub(crate) vector: Option<GeomBatch>,
+}
+
+enum DrawableInner {
+    Glow(backend_glow::Drawable),
+    Offscreen(backend_offscreen::Drawable),
+}
+
+pub enum PrerenderInnards {
+    Glow(backend_glow::PrerenderInnards),
+    Offscreen(backend_offscreen::PrerenderInnards),
+}
+
+impl PrerenderInnards {
+    pub fn actually_upload(&self, permanent: bool, batch: GeomBatch) -> Drawable {
+        let inner = match self {
+            PrerenderInnards::Glow(p) => DrawableInner::Glow(p.actually_upload(permanent, batch)),
+            PrerenderInnards::Offscreen(p) => {
+                DrawableInner::Offscreen(p.actually_upload(permanent, batch))
+            }
+        };
+        Drawable {
+            inner,
+            vector: None,
+        }
+    }
+
+    pub fn total_bytes_uploaded(&self) -> usize {
+        match self {
+            PrerenderInnards::Glow(p) => p.total_bytes_uploaded(),
+            PrerenderInnards::Offscreen(p) => p.total_bytes_uploaded(),
+        }
+    }
+
+    pub fn request_redraw(&self) {
+        match self {
+            PrerenderInnards::Glow(p) => p.request_redraw(),
+            PrerenderInnards::Offscreen(p) => p.request_redraw(),
+        }
+    }
+
+    pub fn set_cursor_icon(&self, icon: winit::window::CursorIcon) {
+        match self {
+            PrerenderInnards::Glow(p) => p.set_cursor_icon(icon),
+            PrerenderInnards::Offscreen(p) => p.set_cursor_icon(icon),
+        }
+    }
+
+    pub fn draw_new_frame(&self) -> GfxCtxInnards {
+        match self {
+            PrerenderInnards::Glow(p) => GfxCtxInnards::Glow(p.draw_new_frame()),
+            PrerenderInnards::Offscreen(p) => GfxCtxInnards::Offscreen(p.draw_new_frame()),
+        }
+    }
+
+    pub fn window_resized(&self, new_size: ScreenDims, scale_factor: f64) {
+        match self {
+            PrerenderInnards::Glow(p) => p.window_resized(new_size, scale_factor),
+            PrerenderInnards::Offscreen(p) => p.window_resized(new_size, scale_factor),
+        }
+    }
+
+    pub fn window_size(&self, scale_factor: f64) -> ScreenDims {
+        match self {
+            PrerenderInnards::Glow(p) => p.window_size(scale_factor),
+            PrerenderInnards::Offscreen(p) => p.window_size(scale_factor),
+        }
+    }
+
+    pub fn set_window_icon(&self, icon: winit::window::Icon) {
+        match self {
+            PrerenderInnards::Glow(p) => p.set_window_icon(icon),
+            PrerenderInnards::Offscreen(_) => {}
+        }
+    }
+
+    pub fn monitor_scale_factor(&self) -> f64 {
+        match self {
+            PrerenderInnards::Glow(p) => p.monitor_scale_factor(),
+            PrerenderInnards::Offscreen(p) => p.monitor_scale_factor(),
+        }
+    }
+
+    pub fn draw_finished(&self, gfc_ctx_innards: GfxCtxInnards) {
+        match (self, gfc_ctx_innards) {
+            (PrerenderInnards::Glow(p), GfxCtxInnards::Glow(g)) => p.draw_finished(g),
+            (PrerenderInnards::Offscreen(p), GfxCtxInnards::Offscreen(g)) => p.draw_finished(g),
+            _ => unreachable!("finished drawing a frame from a different backend"),
+        }
+    }
+
+    pub(crate) fn to_image(&self) -> image::RgbaImage {
+        match self {
+            PrerenderInnards::Glow(_) => unreachable!("only the offscreen backend draws to images"),
+            PrerenderInnards::Offscreen(p) => p.to_image(),
+        }
+    }
+
+    pub(crate) fn screencap(&self, dims: ScreenDims, filename: String) -> anyhow::Result<()> {
+        match self {
+            PrerenderInnards::Glow(p) => p.screencap(dims, filename),
+            PrerenderInnards::Offscreen(p) => p.screencap(dims, filename),
+        }
+    }
+}
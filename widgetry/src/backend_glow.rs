@@ -4,7 +4,7 @@ use std::rc::Rc;
 use glow::HasContext;
 
 use crate::drawing::Uniforms;
-use crate::{Canvas, Color, EventCtx, GeomBatch, ScreenDims, ScreenRectangle};
+use crate::{Canvas, Color, GeomBatch, ScreenDims, ScreenRectangle};
 
 #[cfg(feature = "native-backend")]
 pub use crate::backend_glow_native::setup;
@@ -114,6 +114,9 @@ pub struct Drawable {
     elem_buffer: Buffer,
     num_indices: i32,
     gl: Rc<glow::Context>,
+    // Alongside the offscreen backend, backend_either keeps this instead
+    #[cfg_attr(feature = "offscreen-backend", allow(dead_code))]
+    pub(crate) vector: Option<GeomBatch>,
 }
 
 impl Drop for Drawable {
@@ -125,13 +128,6 @@ impl Drop for Drawable {
     }
 }
 
-impl Drawable {
-    /// This has no effect when drawn.
-    pub fn empty(ctx: &EventCtx) -> Drawable {
-        ctx.upload(GeomBatch::new())
-    }
-}
-
 struct VertexArray {
     id: <glow::Context as glow::HasContext>::VertexArray,
     was_destroyed: bool,
@@ -316,6 +312,7 @@ impl PrerenderInnards {
             elem_buffer,
             num_indices,
             gl: self.gl.clone(),
+            vector: None,
         }
     }
 
@@ -323,6 +320,10 @@ impl PrerenderInnards {
         self.window_adapter.window()
     }
 
+    pub fn total_bytes_uploaded(&self) -> usize {
+        self.total_bytes_uploaded.get()
+    }
+
     pub fn request_redraw(&self) {
         self.window().request_redraw();
     }
@@ -0,0 +1,433 @@
+//! A software rasterizer that draws into an in-memory RGBA image, for machines without a GPU or a
+//! display. It mimics what the shaders and OpenGL state in `backend_glow` do: the same transform
+//! and z-ordering, premultiplied alpha blending, scissor clipping, and texture sampling from the
+//! sprite sheet. There's no multisampling, so edges are aliased; render at a higher scale factor
+//! and downscale if that matters.
+
+use std::cell::{Cell, RefCell};
+
+use image::GenericImageView;
+
+use abstutil::Timer;
+
+use crate::drawing::Uniforms;
+use crate::{Canvas, Color, GeomBatch, ScreenDims, ScreenRectangle};
+
+// Matches the arbitrary texture_scale in the vertex shader
+const TEXTURE_SCALE: f32 = 16.0;
+
+pub fn setup_offscreen(window_size: ScreenDims, timer: &mut Timer) -> PrerenderInnards {
+    timer.start("load textures");
+    let textures = Textures::load(include_bytes!("../textures/spritesheet.png"), 64).unwrap();
+    timer.stop("load textures");
+    PrerenderInnards {
+        framebuffer: RefCell::new(Framebuffer::new(0, 0)),
+        textures,
+        window_size: Cell::new(window_size),
+        scale_factor: Cell::new(1.0),
+        total_bytes_uploaded: Cell::new(0),
+    }
+}
+
+// Represents one frame that's gonna be drawn
+pub struct GfxCtxInnards<'a> {
+    framebuffer: &'a RefCell<Framebuffer>,
+    // (left, top, width, height) in physical pixels
+    current_clip: Option<[i32; 4]>,
+}
+
+impl<'a> GfxCtxInnards<'a> {
+    pub fn clear(&mut self, color: Color) {
+        let mut fb = self.framebuffer.borrow_mut();
+        let [x1, y1, x2, y2] = fb.clip_bounds(self.current_clip);
+        for y in y1..y2 {
+            for x in x1..x2 {
+                let idx = y * fb.width + x;
+                fb.color[idx] = [color.r, color.g, color.b, color.a];
+                fb.depth[idx] = 1.0;
+            }
+        }
+    }
+
+    pub fn redraw(&mut self, obj: &Drawable, uniforms: &Uniforms, prerender: &PrerenderInnards) {
+        let scale_factor = prerender.scale_factor.get() as f32;
+        let [cam_x, cam_y, zoom] = uniforms.transform;
+        let mut fb = self.framebuffer.borrow_mut();
+        let clip = fb.clip_bounds(self.current_clip);
+
+        for tri in obj.indices.chunks_exact(3) {
+            let mut pts = [ScreenVertex::default(); 3];
+            for (pt, idx) in pts.iter_mut().zip(tri) {
+                let v = &obj.vertices[*idx as usize];
+                *pt = ScreenVertex {
+                    x: ((v[0] * zoom) - cam_x) * scale_factor,
+                    y: ((v[1] * zoom) - cam_y) * scale_factor,
+                    // The vertex shader's z, then mapped from normalized device coordinates to
+                    // the depth range
+                    depth: ((v[2] + uniforms.window[2]) / 3.0 + 1.0) / 2.0,
+                    color: [v[3], v[4], v[5], v[6]],
+                    tex: [v[0] / TEXTURE_SCALE, v[1] / TEXTURE_SCALE],
+                };
+            }
+            // All vertices of one polygon share a texture
+            let texture = obj.vertices[tri[0] as usize][7] as usize;
+            fb.fill_triangle(pts, clip, &prerender.textures, texture);
+        }
+    }
+
+    pub fn enable_clipping(&mut self, rect: ScreenRectangle, scale_factor: f64, _: &Canvas) {
+        assert!(self.current_clip.is_none());
+        // Like the scissor rectangle, this is in units of physical pixels. No Y-inversion needed.
+        let left = (rect.x1 * scale_factor) as i32;
+        let top = (rect.y1 * scale_factor) as i32;
+        let width = ((rect.x2 - rect.x1) * scale_factor) as i32;
+        let height = ((rect.y2 - rect.y1) * scale_factor) as i32;
+        self.current_clip = Some([left, top, width, height]);
+    }
+
+    pub fn disable_clipping(&mut self, _: f64, _: &Canvas) {
+        assert!(self.current_clip.is_some());
+        self.current_clip = None;
+    }
+
+    pub fn take_clip(&mut self, scale_factor: f64, canvas: &Canvas) -> Option<[i32; 4]> {
+        let clip = self.current_clip?;
+        self.disable_clipping(scale_factor, canvas);
+        Some(clip)
+    }
+
+    pub fn restore_clip(&mut self, clip: Option<[i32; 4]>) {
+        self.current_clip = clip;
+    }
+}
+
+/// Geometry that's been triangulated once and can be quickly redrawn many times. Create by
+/// creating a `GeomBatch` and calling `ctx.upload(batch)`.
+pub struct Drawable {
+    // Same layout as the glow backend's vertex buffer: position (x, y, z), color (r, g, b, a),
+    // texture ID
+    vertices: Vec<[f32; 8]>,
+    indices: Vec<u32>,
+    // Alongside another backend, backend_either keeps this instead
+    #[cfg_attr(
+        any(feature = "native-backend", feature = "wasm-backend"),
+        allow(dead_code)
+    )]
+    pub(crate) vector: Option<GeomBatch>,
+}
+
+pub struct PrerenderInnards {
+    framebuffer: RefCell<Framebuffer>,
+    textures: Textures,
+    window_size: Cell<ScreenDims>,
+    scale_factor: Cell<f64>,
+
+    pub total_bytes_uploaded: Cell<usize>,
+}
+
+impl PrerenderInnards {
+    pub fn actually_upload(&self, permanent: bool, batch: GeomBatch) -> Drawable {
+        let mut vertices: Vec<[f32; 8]> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+
+        for (color, poly, z) in batch.consume() {
+            let idx_offset = vertices.len() as u32;
+            let (pts, raw_indices) = poly.raw_for_rendering();
+            for pt in pts {
+                let style = color.shader_style(*pt);
+                vertices.push([
+                    pt.x() as f32,
+                    pt.y() as f32,
+                    z as f32,
+                    style[0],
+                    style[1],
+                    style[2],
+                    style[3],
+                    style[4],
+                ]);
+            }
+            for idx in raw_indices {
+                indices.push(idx_offset + (*idx as u32));
+            }
+        }
+
+        if permanent {
+            self.total_bytes_uploaded.set(
+                self.total_bytes_uploaded.get()
+                    + vertices.len() * std::mem::size_of::<[f32; 8]>()
+                    + indices.len() * std::mem::size_of::<u32>(),
+            );
+        }
+
+        Drawable {
+            vertices,
+            indices,
+            vector: None,
+        }
+    }
+
+    pub fn total_bytes_uploaded(&self) -> usize {
+        self.total_bytes_uploaded.get()
+    }
+
+    pub fn request_redraw(&self) {}
+
+    pub fn set_cursor_icon(&self, _: winit::window::CursorIcon) {}
+
+    pub fn draw_new_frame(&self) -> GfxCtxInnards {
+        GfxCtxInnards {
+            framebuffer: &self.framebuffer,
+            current_clip: None,
+        }
+    }
+
+    pub fn window_resized(&self, new_size: ScreenDims, scale_factor: f64) {
+        self.window_size.set(new_size);
+        self.scale_factor.set(scale_factor);
+        *self.framebuffer.borrow_mut() = Framebuffer::new(
+            (new_size.width * scale_factor).round() as usize,
+            (new_size.height * scale_factor).round() as usize,
+        );
+    }
+
+    pub fn window_size(&self, _: f64) -> ScreenDims {
+        self.window_size.get()
+    }
+
+    pub fn monitor_scale_factor(&self) -> f64 {
+        1.0
+    }
+
+    pub fn draw_finished(&self, _: GfxCtxInnards) {}
+
+    /// Copies the current contents of the framebuffer. Like reading pixels from OpenGL, the colors
+    /// have premultiplied alpha.
+    pub(crate) fn to_image(&self) -> image::RgbaImage {
+        let fb = self.framebuffer.borrow();
+        image::RgbaImage::from_fn(fb.width as u32, fb.height as u32, |x, y| {
+            let c = fb.color[(y as usize) * fb.width + (x as usize)];
+            image::Rgba([to_byte(c[0]), to_byte(c[1]), to_byte(c[2]), to_byte(c[3])])
+        })
+    }
+
+    pub(crate) fn screencap(&self, dims: ScreenDims, filename: String) -> anyhow::Result<()> {
+        let mut img = self.to_image();
+        let width = (dims.width as u32).min(img.width());
+        let height = (dims.height as u32).min(img.height());
+        image::imageops::crop(&mut img, 0, 0, width, height)
+            .to_image()
+            .save(&filename)?;
+        Ok(())
+    }
+}
+
+fn to_byte(x: f32) -> u8 {
+    (x.max(0.0).min(1.0) * 255.0).round() as u8
+}
+
+#[derive(Clone, Copy, Default)]
+struct ScreenVertex {
+    // In physical pixels
+    x: f32,
+    y: f32,
+    // From 0 (on top) to 1
+    depth: f32,
+    color: [f32; 4],
+    tex: [f32; 2],
+}
+
+struct Framebuffer {
+    width: usize,
+    height: usize,
+    // Premultiplied RGBA
+    color: Vec<[f32; 4]>,
+    depth: Vec<f32>,
+}
+
+impl Framebuffer {
+    fn new(width: usize, height: usize) -> Framebuffer {
+        Framebuffer {
+            width,
+            height,
+            color: vec![[0.0; 4]; width * height],
+            depth: vec![1.0; width * height],
+        }
+    }
+
+    /// Returns (x1, y1, x2, y2) of pixels that may be touched, with the end exclusive.
+    fn clip_bounds(&self, clip: Option<[i32; 4]>) -> [usize; 4] {
+        match clip {
+            Some([left, top, width, height]) => {
+                let x1 = left.max(0) as usize;
+                let y1 = top.max(0) as usize;
+                let x2 = (left + width).max(0) as usize;
+                let y2 = (top + height).max(0) as usize;
+                [
+                    x1.min(self.width),
+                    y1.min(self.height),
+                    x2.min(self.width),
+                    y2.min(self.height),
+                ]
+            }
+            None => [0, 0, self.width, self.height],
+        }
+    }
+
+    fn fill_triangle(
+        &mut self,
+        mut pts: [ScreenVertex; 3],
+        clip: [usize; 4],
+        textures: &Textures,
+        texture: usize,
+    ) {
+        let mut area = edge(&pts[0], &pts[1], pts[2].x, pts[2].y);
+        if area == 0.0 || !area.is_finite() {
+            return;
+        }
+        // Consistently wind triangles, so the tie-breaking rule below works
+        if area < 0.0 {
+            pts.swap(1, 2);
+            area = -area;
+        }
+
+        let min_x = pts.iter().map(|p| p.x).fold(f32::INFINITY, f32::min);
+        let max_x = pts.iter().map(|p| p.x).fold(f32::NEG_INFINITY, f32::max);
+        let min_y = pts.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+        let max_y = pts.iter().map(|p| p.y).fold(f32::NEG_INFINITY, f32::max);
+        let x1 = (min_x.floor().max(0.0) as usize).max(clip[0]);
+        let y1 = (min_y.floor().max(0.0) as usize).max(clip[1]);
+        let x2 = (max_x.ceil().max(0.0) as usize).min(clip[2]);
+        let y2 = (max_y.ceil().max(0.0) as usize).min(clip[3]);
+
+        // A pixel center exactly on an edge shared by two triangles must only be drawn once, or
+        // translucent polygons get visible seams. Follow the usual "top-left" rule.
+        let owns_edge = |a: &ScreenVertex, b: &ScreenVertex| {
+            let dx = b.x - a.x;
+            let dy = b.y - a.y;
+            (dy == 0.0 && dx > 0.0) || dy < 0.0
+        };
+        let owned = [
+            owns_edge(&pts[1], &pts[2]),
+            owns_edge(&pts[2], &pts[0]),
+            owns_edge(&pts[0], &pts[1]),
+        ];
+
+        for y in y1..y2 {
+            let py = (y as f32) + 0.5;
+            for x in x1..x2 {
+                let px = (x as f32) + 0.5;
+                let w = [
+                    edge(&pts[1], &pts[2], px, py),
+                    edge(&pts[2], &pts[0], px, py),
+                    edge(&pts[0], &pts[1], px, py),
+                ];
+                if w.iter()
+                    .zip(owned.iter())
+                    .any(|(w, owned)| *w < 0.0 || (*w == 0.0 && !owned))
+                {
+                    continue;
+                }
+                let bary = [w[0] / area, w[1] / area, w[2] / area];
+                let interp = |f: &dyn Fn(&ScreenVertex) -> f32| {
+                    bary[0] * f(&pts[0]) + bary[1] * f(&pts[1]) + bary[2] * f(&pts[2])
+                };
+
+                let depth = interp(&|p| p.depth);
+                let idx = y * self.width + x;
+                // Like glDepthFunc(LEQUAL), after clipping to the depth range
+                if depth < 0.0 || depth > 1.0 || depth > self.depth[idx] {
+                    continue;
+                }
+
+                let texel = textures.sample(texture, interp(&|p| p.tex[0]), interp(&|p| p.tex[1]));
+                let r = interp(&|p| p.color[0]) * texel[0];
+                let g = interp(&|p| p.color[1]) * texel[1];
+                let b = interp(&|p| p.color[2]) * texel[2];
+                let a = interp(&|p| p.color[3]) * texel[3];
+
+                // The fragment shader premultiplies alpha, then blending is
+                // (ONE, ONE_MINUS_SRC_ALPHA) for color and (ONE_MINUS_DST_ALPHA, ONE) for alpha.
+                let dst = self.color[idx];
+                self.color[idx] = [
+                    a * r + dst[0] * (1.0 - a),
+                    a * g + dst[1] * (1.0 - a),
+                    a * b + dst[2] * (1.0 - a),
+                    a * (1.0 - dst[3]) + dst[3],
+                ];
+                self.depth[idx] = depth;
+            }
+        }
+    }
+}
+
+// Twice the signed area of the triangle (a, b, (x, y))
+fn edge(a: &ScreenVertex, b: &ScreenVertex, x: f32, y: f32) -> f32 {
+    (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+}
+
+/// The sprite sheet, split into individual textures. See `backend_glow::load_textures` for how
+/// textures are numbered; texture 0 is pure white.
+struct Textures {
+    sprite_length: usize,
+    // Each sprite is a row-major list of RGBA pixels
+    sprites: Vec<Vec<[f32; 4]>>,
+}
+
+impl Textures {
+    fn load(image_bytes: &[u8], sprite_length: u32) -> anyhow::Result<Textures> {
+        let img =
+            if let image::DynamicImage::ImageRgba8(img) = image::load_from_memory(image_bytes)? {
+                img
+            } else {
+                bail!("the sprite sheet must be RGBA");
+            };
+        let (img_width, img_height) = img.dimensions();
+        if img_width % sprite_length != 0 || img_height % sprite_length != 0 {
+            bail!("sprites must align exactly");
+        }
+
+        let mut sprites = vec![vec![[1.0; 4]; (sprite_length * sprite_length) as usize]];
+        for y in 0..img_height / sprite_length {
+            for x in 0..img_width / sprite_length {
+                let cell = img.view(
+                    x * sprite_length,
+                    y * sprite_length,
+                    sprite_length,
+                    sprite_length,
+                );
+                sprites.push(
+                    cell.pixels()
+                        .map(|(_, _, p)| {
+                            [
+                                (p.0[0] as f32) / 255.0,
+                                (p.0[1] as f32) / 255.0,
+                                (p.0[2] as f32) / 255.0,
+                                (p.0[3] as f32) / 255.0,
+                            ]
+                        })
+                        .collect(),
+                );
+            }
+        }
+        Ok(Textures {
+            sprite_length: sprite_length as usize,
+            sprites,
+        })
+    }
+
+    /// Nearest-neighbor sampling, repeating the texture in both directions
+    fn sample(&self, texture: usize, u: f32, v: f32) -> [f32; 4] {
+        if texture == 0 {
+            return [1.0; 4];
+        }
+        let sprite = if let Some(s) = self.sprites.get(texture) {
+            s
+        } else {
+            return [1.0; 4];
+        };
+        let len = self.sprite_length;
+        let x = ((u.rem_euclid(1.0) * len as f32) as usize).min(len - 1);
+        let y = ((v.rem_euclid(1.0) * len as f32) as usize).min(len - 1);
+        sprite[y * len + x]
+    }
+}
@@ -44,6 +44,10 @@ pub struct Canvas {
 
     // Kind of just widgetry state awkwardly stuck here...
     pub(crate) keys_held: HashSet<Key>,
+    /// Which panel has keyboard focus, if any. The panel itself remembers which of its widgets
+    /// has it.
+    pub(crate) focused_panel: Option<usize>,
+    pub(crate) next_panel_id: usize,
 }
 
 impl Canvas {
@@ -72,6 +76,8 @@ impl Canvas {
             covered_areas: RefCell::new(Vec::new()),
 
             keys_held: HashSet::new(),
+            focused_panel: None,
+            next_panel_id: 0,
         }
     }
 
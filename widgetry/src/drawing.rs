@@ -4,6 +4,7 @@ use geom::{Bounds, Polygon, Pt2D};
 
 use crate::assets::Assets;
 use crate::backend::{GfxCtxInnards, PrerenderInnards};
+use crate::tools::vector::VectorCapture;
 use crate::{
     Canvas, Color, Drawable, EventCtx, GeomBatch, Key, ScreenDims, ScreenPt, ScreenRectangle,
     Style, Text,
@@ -58,6 +59,11 @@ pub struct GfxCtx<'a> {
 
     pub(crate) num_draw_calls: usize,
     pub(crate) num_forks: usize,
+
+    // Only set while exporting a vector image
+    pub(crate) vector: Option<VectorCapture>,
+    // Only set while a test harness looks for buttons to click
+    pub(crate) clickables: Option<Vec<(String, ScreenRectangle)>>,
 }
 
 impl<'a> GfxCtx<'a> {
@@ -78,6 +84,8 @@ impl<'a> GfxCtx<'a> {
             num_forks: 0,
             screencap_mode,
             naming_hint: None,
+            vector: None,
+            clickables: None,
         }
     }
 
@@ -135,6 +143,9 @@ impl<'a> GfxCtx<'a> {
         self.inner
             .redraw(obj, &self.uniforms, &self.prerender.inner);
         self.num_draw_calls += 1;
+        if let Some(ref mut v) = self.vector {
+            v.add(obj.vector.as_ref(), &self.uniforms);
+        }
 
         // println!("{:?}", backtrace::Backtrace::new());
     }
@@ -147,11 +158,17 @@ impl<'a> GfxCtx<'a> {
 
     // TODO Stateful API :(
     pub fn enable_clipping(&mut self, rect: ScreenRectangle) {
+        if let Some(ref mut v) = self.vector {
+            v.clip = Some(rect.clone());
+        }
         let scale_factor = self.prerender.get_scale_factor();
         self.inner.enable_clipping(rect, scale_factor, self.canvas);
     }
 
     pub fn disable_clipping(&mut self) {
+        if let Some(ref mut v) = self.vector {
+            v.clip = None;
+        }
         let scale_factor = self.prerender.get_scale_factor();
         self.inner.disable_clipping(scale_factor, self.canvas);
     }
@@ -191,9 +208,13 @@ impl<'a> GfxCtx<'a> {
         let clip = self
             .inner
             .take_clip(self.prerender.get_scale_factor(), self.canvas);
+        let vector_clip = self.vector.as_mut().and_then(|v| v.clip.take());
         batch.draw(self);
         self.unfork();
         self.inner.restore_clip(clip);
+        if let Some(ref mut v) = self.vector {
+            v.clip = vector_clip;
+        }
     }
 
     pub fn get_screen_bounds(&self) -> Bounds {
@@ -240,6 +261,13 @@ impl<'a> GfxCtx<'a> {
     }
 }
 
+impl Drawable {
+    /// This has no effect when drawn.
+    pub fn empty(ctx: &EventCtx) -> Drawable {
+        ctx.upload(GeomBatch::new())
+    }
+}
+
 // TODO Don't expose this directly
 // TODO Rename or something maybe. This actually owns all the permanent state of everything.
 pub struct Prerender {
@@ -247,6 +275,7 @@ pub struct Prerender {
     pub(crate) assets: Assets,
     pub(crate) num_uploads: Cell<usize>,
     pub(crate) scale_factor: RefCell<f64>,
+    pub(crate) keep_vector_geometry: Cell<bool>,
 }
 
 impl Prerender {
@@ -259,12 +288,30 @@ impl Prerender {
     }
 
     pub fn get_total_bytes_uploaded(&self) -> usize {
-        self.inner.total_bytes_uploaded.get()
+        self.inner.total_bytes_uploaded()
+    }
+
+    /// Normally uploaded geometry only lives on the GPU. If this is enabled, anything uploaded
+    /// afterwards also keeps a copy, so it can be exported as a vector image later. This costs
+    /// lots of memory for a large map.
+    pub fn keep_vector_geometry(&self, enabled: bool) {
+        self.keep_vector_geometry.set(enabled);
+    }
+
+    pub fn is_keeping_vector_geometry(&self) -> bool {
+        self.keep_vector_geometry.get()
     }
 
     fn actually_upload(&self, permanent: bool, batch: GeomBatch) -> Drawable {
         self.num_uploads.set(self.num_uploads.get() + 1);
-        self.inner.actually_upload(permanent, batch)
+        let vector = if self.keep_vector_geometry.get() {
+            Some(batch.clone())
+        } else {
+            None
+        };
+        let mut obj = self.inner.actually_upload(permanent, batch);
+        obj.vector = vector;
+        obj
 
         // println!("{:?}", backtrace::Backtrace::new());
     }
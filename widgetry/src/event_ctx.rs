@@ -7,7 +7,7 @@ use geom::{Percent, Polygon};
 
 use crate::{
     svg, text, Canvas, Color, Drawable, Event, GeomBatch, GfxCtx, HorizontalAlignment, Key, Line,
-    Panel, Prerender, ScreenDims, Style, Text, UserInput, VerticalAlignment, Widget,
+    Panel, Prerender, ScreenDims, Style, Text, Translations, UserInput, VerticalAlignment, Widget,
 };
 
 #[derive(Clone, PartialEq, Debug)]
@@ -23,6 +23,18 @@ pub enum UpdateType {
         /// optional drawing suffix returned by the app.
         leaflet_naming: bool,
     },
+    /// Draw the current screen once more, saving it as a PNG file. Any missing parent directories
+    /// are created.
+    ScreenCapture {
+        path: String,
+    },
+    /// Draw the current screen once more, writing it as an SVG or PDF file, depending on the
+    /// extension of the path. `scale` multiplies the size of the output, relative to the window.
+    /// Only geometry uploaded while `Prerender::keep_vector_geometry` is enabled is included.
+    ExportVectorImage {
+        path: String,
+        scale: f64,
+    },
 }
 
 pub struct EventCtx<'a> {
@@ -117,6 +129,21 @@ impl<'a> EventCtx<'a> {
         *self.prerender.assets.default_line_height.borrow()
     }
 
+    /// Translates some English UI text into the current language. Use `tr!` for text with
+    /// parameters.
+    pub fn tr(&self, english: &str) -> String {
+        self.prerender
+            .assets
+            .translations
+            .borrow()
+            .translate(english)
+    }
+
+    /// Changes the language of all text translated afterwards.
+    pub fn set_translations(&self, translations: Translations) {
+        *self.prerender.assets.translations.borrow_mut() = translations;
+    }
+
     // TODO I can't decide which way the API should go.
     pub fn upload(&self, batch: GeomBatch) -> Drawable {
         self.prerender.upload(batch)
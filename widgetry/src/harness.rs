@@ -0,0 +1,551 @@
+use abstutil::Timer;
+use geom::Pt2D;
+
+use crate::runner::{setup_prerender, update_canvas, wrap_keyboard_focus};
+use crate::{
+    Canvas, Event, EventCtx, GfxCtx, Key, Prerender, ScreenDims, ScreenPt, ScreenRectangle,
+    Settings, SharedAppState, State, Style, Transition, UpdateType, UserInput,
+};
+
+/// Drives one `State` from tests, without a window or GPU. Synthetic events are passed to the
+/// state, and the `Transition` it returns is handed back instead of being applied. Between events,
+/// tests can downcast the state or look at its panels to check what happened.
+///
+/// This needs the `offscreen-backend` feature. Apps can enable it just for their tests, as a
+/// dev-dependency, alongside the backend they normally use.
+pub struct Harness<A: SharedAppState> {
+    prerender: Prerender,
+    canvas: Canvas,
+    style: Style,
+    /// The shared app state, for tests to set up and check
+    pub app: A,
+    updates_requested: Vec<UpdateType>,
+}
+
+impl<A: 'static + SharedAppState> Harness<A> {
+    /// Sets up the shared app state, with a fixed window size. The states to test are created
+    /// afterwards with `with_ctx`.
+    pub fn new<F: FnOnce(&mut EventCtx) -> A>(
+        settings: Settings,
+        window_size: ScreenDims,
+        make_app: F,
+    ) -> Harness<A> {
+        let mut timer = Timer::new("setup widgetry test harness");
+        let prerender_innards = crate::backend::setup_offscreen(window_size, &mut timer);
+        let (prerender, mut canvas, mut style) = setup_prerender(prerender_innards, settings);
+        let app = make_app(&mut EventCtx {
+            fake_mouseover: true,
+            input: UserInput::new(Event::NoOp, &canvas),
+            canvas: &mut canvas,
+            prerender: &prerender,
+            style: &mut style,
+            updates_requested: vec![],
+        });
+        timer.done();
+        Harness {
+            prerender,
+            canvas,
+            style,
+            app,
+            updates_requested: Vec::new(),
+        }
+    }
+
+    /// Directly access the app, as if in an event handler. Use this to create the state to test.
+    pub fn with_ctx<T, F: FnOnce(&mut EventCtx, &mut A) -> T>(&mut self, f: F) -> T {
+        let mut ctx = EventCtx {
+            fake_mouseover: true,
+            input: UserInput::new(Event::NoOp, &self.canvas),
+            canvas: &mut self.canvas,
+            prerender: &self.prerender,
+            style: &mut self.style,
+            updates_requested: vec![],
+        };
+        let result = f(&mut ctx, &mut self.app);
+        self.updates_requested.extend(ctx.updates_requested);
+        result
+    }
+
+    /// Passes one event to the state and returns its transition. Like the real event loop, if the
+    /// state asks for `KeepWithMouseover`, it's immediately called again.
+    pub fn event(&mut self, state: &mut dyn State<A>, ev: Event) -> Transition<A> {
+        let input = UserInput::new(ev, &self.canvas);
+        update_canvas(&mut self.canvas, &input, &self.prerender);
+
+        let app = &mut self.app;
+        let mut ctx = EventCtx {
+            fake_mouseover: false,
+            input,
+            canvas: &mut self.canvas,
+            prerender: &self.prerender,
+            style: &mut self.style,
+            updates_requested: vec![],
+        };
+        app.before_event(&mut ctx);
+        let mut transition = state.event(&mut ctx, app);
+        while let Transition::KeepWithMouseover = transition {
+            transition = ctx.no_op_event(true, |ctx| {
+                app.before_event(ctx);
+                state.event(ctx, app)
+            });
+        }
+        wrap_keyboard_focus(&mut ctx);
+        self.updates_requested.extend(ctx.updates_requested);
+        transition
+    }
+
+    /// Moves the mouse to a point on the screen.
+    pub fn move_mouse(&mut self, state: &mut dyn State<A>, pt: ScreenPt) -> Transition<A> {
+        self.event(state, Event::MouseMovedTo(pt))
+    }
+
+    /// Moves the mouse to a point on the map, using the current camera.
+    pub fn move_mouse_to_map(&mut self, state: &mut dyn State<A>, pt: Pt2D) -> Transition<A> {
+        let pt = self.canvas.map_to_screen(pt);
+        self.move_mouse(state, pt)
+    }
+
+    /// Moves the mouse to a point on the screen, then presses and releases the left button.
+    pub fn left_click_at(&mut self, state: &mut dyn State<A>, pt: ScreenPt) -> Transition<A> {
+        self.events(
+            state,
+            vec![
+                Event::MouseMovedTo(pt),
+                Event::LeftMouseButtonDown,
+                Event::LeftMouseButtonUp,
+            ],
+        )
+    }
+
+    /// Clicks the button or checkbox with this action, on any panel the state draws. Panics if
+    /// there's no such button on the screen.
+    pub fn click_button(&mut self, state: &mut dyn State<A>, action: &str) -> Transition<A> {
+        let buttons = self.clickable_buttons(state);
+        // If panels overlap, the last one drawn is on top
+        if let Some((_, rect)) = buttons.iter().rev().find(|(x, _)| x == action) {
+            let pt = rect.center();
+            self.left_click_at(state, pt)
+        } else {
+            panic!(
+                "No button {} on the screen. The choices: {:?}",
+                action,
+                buttons.into_iter().map(|(x, _)| x).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    /// Presses and releases a key.
+    pub fn press_key(&mut self, state: &mut dyn State<A>, key: Key) -> Transition<A> {
+        self.events(state, vec![Event::KeyPress(key), Event::KeyRelease(key)])
+    }
+
+    /// Types some text, one key at a time. Panics if some character can't be typed.
+    pub fn type_text(&mut self, state: &mut dyn State<A>, text: &str) -> Transition<A> {
+        let mut events = Vec::new();
+        for c in text.chars() {
+            let (key, shift) =
+                key_for_char(c).unwrap_or_else(|| panic!("No key types {:?}, in {:?}", c, text));
+            if shift {
+                events.push(Event::KeyPress(Key::LeftShift));
+            }
+            events.push(Event::KeyPress(key));
+            events.push(Event::KeyRelease(key));
+            if shift {
+                events.push(Event::KeyRelease(Key::LeftShift));
+            }
+        }
+        self.events(state, events)
+    }
+
+    /// Passes events to the state in order, stopping early at the first transition besides
+    /// `Keep`.
+    pub fn events(&mut self, state: &mut dyn State<A>, events: Vec<Event>) -> Transition<A> {
+        for ev in events {
+            let transition = self.event(state, ev);
+            if !matches!(transition, Transition::Keep) {
+                return transition;
+            }
+        }
+        Transition::Keep
+    }
+
+    /// Draws the state, then returns the action and location of every button and checkbox on
+    /// its panels, in the order they were drawn.
+    pub fn clickable_buttons(&mut self, state: &dyn State<A>) -> Vec<(String, ScreenRectangle)> {
+        self.canvas.start_drawing();
+        let mut g = GfxCtx::new(&self.prerender, &self.canvas, &self.style, false);
+        g.clickables = Some(Vec::new());
+        state.draw(&mut g, &self.app);
+        let clickables = g.clickables.take().unwrap();
+        self.prerender.inner.draw_finished(g.inner);
+        self.prerender.num_uploads.set(0);
+        clickables
+    }
+
+    /// Draws the state into an image. If the state asks for `DrawBaselayer::DefaultDraw`, that
+    /// happens first. The colors have premultiplied alpha.
+    pub fn draw(&mut self, state: &dyn State<A>) -> image::RgbaImage {
+        self.canvas.start_drawing();
+        let mut g = GfxCtx::new(&self.prerender, &self.canvas, &self.style, false);
+        if let crate::DrawBaselayer::DefaultDraw = state.draw_baselayer() {
+            self.app.draw_default(&mut g);
+        }
+        state.draw(&mut g, &self.app);
+        self.prerender.inner.draw_finished(g.inner);
+        self.prerender.num_uploads.set(0);
+        self.prerender.inner.to_image()
+    }
+
+    /// Returns everything states have asked for with `ctx.request_update` since the last call.
+    pub fn take_updates_requested(&mut self) -> Vec<UpdateType> {
+        std::mem::take(&mut self.updates_requested)
+    }
+
+    /// Position the camera before moving the mouse on the map.
+    pub fn canvas(&mut self) -> &mut Canvas {
+        &mut self.canvas
+    }
+}
+
+/// Which key, and whether shift has to be held, types a character.
+fn key_for_char(c: char) -> Option<(Key, bool)> {
+    const KEYS: [Key; 46] = [
+        Key::A,
+        Key::B,
+        Key::C,
+        Key::D,
+        Key::E,
+        Key::F,
+        Key::G,
+        Key::H,
+        Key::I,
+        Key::J,
+        Key::K,
+        Key::L,
+        Key::M,
+        Key::N,
+        Key::O,
+        Key::P,
+        Key::Q,
+        Key::R,
+        Key::S,
+        Key::T,
+        Key::U,
+        Key::V,
+        Key::W,
+        Key::X,
+        Key::Y,
+        Key::Z,
+        Key::Num1,
+        Key::Num2,
+        Key::Num3,
+        Key::Num4,
+        Key::Num5,
+        Key::Num6,
+        Key::Num7,
+        Key::Num8,
+        Key::Num9,
+        Key::Num0,
+        Key::LeftBracket,
+        Key::RightBracket,
+        Key::Space,
+        Key::Slash,
+        Key::Dot,
+        Key::Comma,
+        Key::Semicolon,
+        Key::Colon,
+        Key::Equals,
+        Key::SingleQuote,
+    ];
+    for key in KEYS.iter().cloned() {
+        if key.to_char(false) == Some(c) {
+            return Some((key, false));
+        }
+        if key.to_char(true) == Some(c) {
+            return Some((key, true));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Harness;
+    use crate::{
+        Color, Event, EventCtx, GfxCtx, Key, Outcome, Panel, ScreenDims, Settings, SharedAppState,
+        Slider, State, StyledButtons, Transition, Widget,
+    };
+
+    struct App {
+        count: usize,
+    }
+
+    impl SharedAppState for App {}
+
+    struct Counter {
+        panel: Panel,
+    }
+
+    impl Counter {
+        fn new(ctx: &mut EventCtx) -> Counter {
+            Counter {
+                panel: Panel::new(Widget::col(vec![
+                    Widget::row(vec![
+                        Widget::text_entry(ctx, String::new(), true).named("name"),
+                        ctx.style().btn_close_widget(ctx),
+                    ]),
+                    ctx.style().btn_solid_dark_text("increment").build_def(ctx),
+                ]))
+                .build(ctx),
+            }
+        }
+    }
+
+    impl State<App> for Counter {
+        fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition<App> {
+            match self.panel.event(ctx) {
+                Outcome::Clicked(x) => match x.as_ref() {
+                    "close" => Transition::Pop,
+                    "increment" => {
+                        app.count += 1;
+                        Transition::Keep
+                    }
+                    _ => unreachable!(),
+                },
+                _ => Transition::Keep,
+            }
+        }
+
+        fn draw(&self, g: &mut GfxCtx, _: &App) {
+            g.clear(Color::BLACK);
+            self.panel.draw(g);
+        }
+    }
+
+    #[test]
+    fn test_panel_interaction() {
+        let mut harness = Harness::new(
+            Settings::new("test").scale_factor(1.0),
+            ScreenDims::new(800.0, 600.0),
+            |_| App { count: 0 },
+        );
+        let mut state = harness.with_ctx(|ctx, _| Counter::new(ctx));
+
+        let actions: Vec<String> = harness
+            .clickable_buttons(&state)
+            .into_iter()
+            .map(|(x, _)| x)
+            .collect();
+        assert_eq!(actions, vec!["close".to_string(), "increment".to_string()]);
+
+        assert!(matches!(
+            harness.click_button(&mut state, "increment"),
+            Transition::Keep
+        ));
+        assert!(matches!(
+            harness.click_button(&mut state, "increment"),
+            Transition::Keep
+        ));
+        assert_eq!(harness.app.count, 2);
+
+        assert!(matches!(
+            harness.type_text(&mut state, "Hi there!"),
+            Transition::Keep
+        ));
+        assert_eq!(state.panel.text_box("name"), "Hi there!");
+
+        // The text box has focus, but passes along keys it doesn't handle
+        assert!(matches!(
+            harness.press_key(&mut state, Key::Escape),
+            Transition::Pop
+        ));
+    }
+
+    #[test]
+    fn test_keyboard_navigation() {
+        let mut harness = Harness::new(
+            Settings::new("test").scale_factor(1.0),
+            ScreenDims::new(800.0, 600.0),
+            |_| App { count: 0 },
+        );
+        let mut state = harness.with_ctx(|ctx, _| Counter::new(ctx));
+
+        // Tab to the increment button, passing the text box and close button
+        for _ in 0..3 {
+            assert!(matches!(
+                harness.press_key(&mut state, Key::Tab),
+                Transition::Keep
+            ));
+        }
+        let tree: Vec<String> = harness.with_ctx(|ctx, _| {
+            state
+                .panel
+                .accessibility_tree(ctx)
+                .into_iter()
+                .map(|node| node.to_string())
+                .collect()
+        });
+        assert_eq!(
+            tree,
+            vec![
+                "text box \"name\"".to_string(),
+                "button \"close\"".to_string(),
+                "button \"increment\", focused".to_string(),
+            ]
+        );
+        assert!(matches!(
+            harness.press_key(&mut state, Key::Enter),
+            Transition::Keep
+        ));
+        assert!(matches!(
+            harness.press_key(&mut state, Key::Space),
+            Transition::Keep
+        ));
+        assert_eq!(harness.app.count, 2);
+
+        // Shift+Tab goes back to close
+        assert!(matches!(
+            harness.events(
+                &mut state,
+                vec![
+                    Event::KeyPress(Key::LeftShift),
+                    Event::KeyPress(Key::Tab),
+                    Event::KeyRelease(Key::Tab),
+                    Event::KeyRelease(Key::LeftShift),
+                ]
+            ),
+            Transition::Keep
+        ));
+        assert!(matches!(
+            harness.press_key(&mut state, Key::Enter),
+            Transition::Pop
+        ));
+    }
+
+    struct TwoPanels {
+        top: Panel,
+        bottom: Panel,
+    }
+
+    impl State<App> for TwoPanels {
+        fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition<App> {
+            if let Outcome::Clicked(x) = self.top.event(ctx) {
+                match x.as_ref() {
+                    "close" => {
+                        return Transition::Keep;
+                    }
+                    "increment" => {
+                        app.count += 1;
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            if let Outcome::Clicked(x) = self.bottom.event(ctx) {
+                assert_eq!(x, "close");
+                return Transition::Pop;
+            }
+            Transition::Keep
+        }
+
+        fn draw(&self, g: &mut GfxCtx, _: &App) {
+            g.clear(Color::BLACK);
+            self.top.draw(g);
+            self.bottom.draw(g);
+        }
+    }
+
+    #[test]
+    fn test_keyboard_focus_per_panel() {
+        let mut harness = Harness::new(
+            Settings::new("test").scale_factor(1.0),
+            ScreenDims::new(800.0, 600.0),
+            |_| App { count: 0 },
+        );
+        let mut state = harness.with_ctx(|ctx, _| TwoPanels {
+            top: Panel::new(Widget::row(vec![
+                ctx.style().btn_close_widget(ctx),
+                ctx.style().btn_solid_dark_text("increment").build_def(ctx),
+            ]))
+            .build(ctx),
+            // The slider has no name
+            bottom: Panel::new(Widget::row(vec![
+                Slider::horizontal(ctx, 100.0, 25.0, 0.0),
+                ctx.style().btn_close_widget(ctx),
+            ]))
+            .build(ctx),
+        });
+        let describe = |harness: &mut Harness<App>, state: &TwoPanels| {
+            harness.with_ctx(|ctx, _| {
+                let mut lines = Vec::new();
+                for panel in &[&state.top, &state.bottom] {
+                    for node in panel.accessibility_tree(ctx) {
+                        lines.push(node.to_string());
+                    }
+                }
+                lines
+            })
+        };
+
+        // Tab moves through the top panel, then the bottom
+        for _ in 0..3 {
+            assert!(matches!(
+                harness.press_key(&mut state, Key::Tab),
+                Transition::Keep
+            ));
+        }
+        assert!(matches!(
+            harness.press_key(&mut state, Key::RightArrow),
+            Transition::Keep
+        ));
+        assert_eq!(
+            describe(&mut harness, &state),
+            vec![
+                "button \"close\"".to_string(),
+                "button \"increment\"".to_string(),
+                "slider \"\": 5%, focused".to_string(),
+                "button \"close\"".to_string(),
+            ]
+        );
+
+        // Both panels have a close button, but only the bottom one has focus
+        assert!(matches!(
+            harness.press_key(&mut state, Key::Tab),
+            Transition::Keep
+        ));
+        assert_eq!(
+            describe(&mut harness, &state),
+            vec![
+                "button \"close\"".to_string(),
+                "button \"increment\"".to_string(),
+                "slider \"\": 5%".to_string(),
+                "button \"close\", focused".to_string(),
+            ]
+        );
+
+        // Past the end of the last panel, nothing has focus, and then Tab starts over
+        assert!(matches!(
+            harness.press_key(&mut state, Key::Tab),
+            Transition::Keep
+        ));
+        assert!(describe(&mut harness, &state)
+            .iter()
+            .all(|line| !line.ends_with("focused")));
+        assert!(matches!(
+            harness.press_key(&mut state, Key::Tab),
+            Transition::Keep
+        ));
+        assert_eq!(
+            describe(&mut harness, &state)[0],
+            "button \"close\", focused"
+        );
+
+        // Rebuilding a panel without restoring it loses focus
+        harness.with_ctx(|ctx, _| {
+            state.top = Panel::new(ctx.style().btn_close_widget(ctx)).build(ctx);
+        });
+        assert!(describe(&mut harness, &state)
+            .iter()
+            .all(|line| !line.ends_with("focused")));
+        assert_eq!(harness.app.count, 0);
+    }
+}
@@ -7,6 +7,7 @@
 //! * [`Autocomplete`] - select predefined value by combining text entry with menus
 //! * [`Button`] - clickable buttons with keybindings and tooltips
 //! * [`Checkbox`] - toggle between two buttons
+//! * [`CompareCounts`] - a scatter plot comparing two counts of the same thing
 //! * [`CompareTimes`] - a scatter plot specialized for comparing times
 //! * [`DrawWithTooltips`] - draw static geometry, with mouse tooltips in certain regions
 //! * [`Dropdown`] - a button that expands into a menu
@@ -38,17 +39,26 @@ pub use crate::drawing::{GfxCtx, Prerender};
 pub use crate::event::{hotkeys, lctrl, Event, Key, MultiKey};
 pub use crate::event_ctx::{EventCtx, UpdateType};
 pub use crate::geom::{GeomBatch, RewriteColor};
+#[cfg(feature = "offscreen-backend")]
+pub use crate::harness::Harness;
 pub use crate::input::UserInput;
-pub use crate::runner::{run, Settings};
+#[cfg(feature = "offscreen-backend")]
+pub use crate::offscreen::{compare_to_goldenfile, OffscreenRenderer};
+#[cfg(any(feature = "native-backend", feature = "wasm-backend"))]
+pub use crate::runner::run;
+pub use crate::runner::Settings;
 pub use crate::screen_geom::{ScreenDims, ScreenPt, ScreenRectangle};
 pub use crate::style::{buttons::StyledButtons, Style};
 pub use crate::text::{Font, Line, Text, TextExt, TextSpan};
 pub use crate::tools::warper::Warper;
 pub use crate::tools::Cached;
+pub use crate::translations::{fill_parameters, Translations};
+pub use crate::widgets::accessibility::{AccessibilityNode, Role};
 pub use crate::widgets::autocomplete::Autocomplete;
 pub(crate) use crate::widgets::button::Button;
 pub use crate::widgets::button::{ButtonBuilder, MultiButton};
 pub use crate::widgets::checkbox::Checkbox;
+pub use crate::widgets::compare_counts::CompareCounts;
 pub use crate::widgets::compare_times::CompareTimes;
 pub(crate) use crate::widgets::dropdown::Dropdown;
 pub use crate::widgets::fan_chart::FanChart;
@@ -69,30 +79,58 @@ pub use crate::widgets::{
 
 mod app_state;
 mod assets;
+#[cfg(all(
+    feature = "offscreen-backend",
+    any(feature = "native-backend", feature = "wasm-backend")
+))]
+mod backend_either;
 #[cfg(any(feature = "native-backend", feature = "wasm-backend"))]
 mod backend_glow;
 #[cfg(feature = "native-backend")]
 mod backend_glow_native;
 #[cfg(feature = "wasm-backend")]
 mod backend_glow_wasm;
+#[cfg(feature = "offscreen-backend")]
+mod backend_offscreen;
 mod canvas;
 mod color;
 mod drawing;
 mod event;
 mod event_ctx;
 mod geom;
+#[cfg(feature = "offscreen-backend")]
+mod harness;
 mod input;
+#[cfg(feature = "offscreen-backend")]
+mod offscreen;
 mod runner;
 mod screen_geom;
 mod style;
 mod svg;
 mod text;
 mod tools;
+mod translations;
 mod widgets;
 
 mod backend {
-    #[cfg(any(feature = "native-backend", feature = "wasm-backend"))]
+    #[cfg(all(
+        any(feature = "native-backend", feature = "wasm-backend"),
+        not(feature = "offscreen-backend")
+    ))]
     pub use crate::backend_glow::*;
+
+    #[cfg(all(
+        feature = "offscreen-backend",
+        not(any(feature = "native-backend", feature = "wasm-backend"))
+    ))]
+    pub use crate::backend_offscreen::*;
+
+    // An app built with both can use either: a window through `run`, or `OffscreenRenderer`.
+    #[cfg(all(
+        feature = "offscreen-backend",
+        any(feature = "native-backend", feature = "wasm-backend")
+    ))]
+    pub use crate::backend_either::*;
 }
 
 /// Like [`std::include_bytes!`], but also returns its argument, the relative path to the bytes
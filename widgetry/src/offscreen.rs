@@ -0,0 +1,179 @@
+use abstutil::Timer;
+
+use crate::runner::{setup_state, State};
+use crate::tools::screenshot::{screenshot_current, screenshot_everything};
+use crate::tools::vector::export_vector_image;
+use crate::{Canvas, Event, EventCtx, Prerender, ScreenDims, Settings, SharedAppState, UserInput};
+
+/// Runs an app without a window or GPU, drawing into an in-memory image. This works just like
+/// `widgetry::run`, except events have to be passed in explicitly. Use it to render map thumbnails
+/// on a server or to write screenshot tests.
+pub struct OffscreenRenderer<A: SharedAppState> {
+    prerender: Prerender,
+    state: State<A>,
+}
+
+impl<A: 'static + SharedAppState> OffscreenRenderer<A> {
+    /// Sets up the app, with a fixed window size.
+    pub fn new<F: FnOnce(&mut EventCtx) -> (A, Vec<Box<dyn crate::app_state::State<A>>>)>(
+        settings: Settings,
+        window_size: ScreenDims,
+        make_app: F,
+    ) -> OffscreenRenderer<A> {
+        let mut timer = Timer::new("setup offscreen widgetry");
+        let prerender_innards = crate::backend::setup_offscreen(window_size, &mut timer);
+        let (prerender, state) = setup_state(prerender_innards, settings, make_app, &mut timer);
+        timer.done();
+        OffscreenRenderer { prerender, state }
+    }
+
+    /// Handles one event, as if it came from a window. Send `Event::Update` to make time pass.
+    pub fn event(&mut self, ev: Event) {
+        self.state.event(ev, &self.prerender);
+    }
+
+    /// Draws the current state of the app. The colors have premultiplied alpha.
+    pub fn draw(&mut self) -> image::RgbaImage {
+        self.state.draw(&self.prerender, false);
+        self.prerender.num_uploads.set(0);
+        self.prerender.inner.to_image()
+    }
+
+    /// Tiles the whole map into images of some size, like the screen capture tool in debug mode.
+    pub fn screenshot_everything(
+        &mut self,
+        dir_path: &str,
+        zoom: f64,
+        dims: ScreenDims,
+        leaflet_naming: bool,
+    ) -> anyhow::Result<()> {
+        screenshot_everything(
+            &mut self.state,
+            dir_path,
+            &self.prerender,
+            zoom,
+            dims,
+            leaflet_naming,
+        )
+    }
+
+    /// Saves what's currently drawn as a PNG file. See `UpdateType::ScreenCapture`.
+    pub fn screenshot_current(&mut self, path: &str) -> anyhow::Result<()> {
+        screenshot_current(&mut self.state, &self.prerender, path)
+    }
+
+    /// Writes what's currently drawn as an SVG or PDF file. See `UpdateType::ExportVectorImage`.
+    pub fn export_vector_image(&mut self, path: &str, scale: f64) -> anyhow::Result<()> {
+        export_vector_image(&mut self.state, &self.prerender, path, scale)
+    }
+
+    /// Position the camera before drawing.
+    pub fn canvas(&mut self) -> &mut Canvas {
+        &mut self.state.canvas
+    }
+
+    /// Directly access the app, as if in an event handler.
+    pub fn with_ctx<T, F: FnOnce(&mut EventCtx, &mut A) -> T>(&mut self, f: F) -> T {
+        let mut ctx = EventCtx {
+            fake_mouseover: true,
+            input: UserInput::new(Event::NoOp, &self.state.canvas),
+            canvas: &mut self.state.canvas,
+            prerender: &self.prerender,
+            style: &mut self.state.style,
+            updates_requested: vec![],
+        };
+        f(&mut ctx, &mut self.state.app.shared_app_state)
+    }
+}
+
+/// Compares an image to a previously saved goldenfile. If the goldenfile doesn't exist yet, it's
+/// created. Otherwise, if more than `max_different_pixels` differ, the new image is written next to
+/// the goldenfile with an `.actual.png` suffix, so the two can be compared.
+pub fn compare_to_goldenfile(
+    actual: &image::RgbaImage,
+    path: &str,
+    max_different_pixels: usize,
+) -> anyhow::Result<()> {
+    if !std::path::Path::new(path).exists() {
+        actual.save(path)?;
+        warn!("{} didn't exist, so created it", path);
+        return Ok(());
+    }
+
+    let expected = image::open(path)?.to_rgba8();
+    if expected.dimensions() != actual.dimensions() {
+        bail!(
+            "{} is {:?}, but the new image is {:?}",
+            path,
+            expected.dimensions(),
+            actual.dimensions()
+        );
+    }
+    let num_different = expected
+        .pixels()
+        .zip(actual.pixels())
+        .filter(|(a, b)| a != b)
+        .count();
+    if num_different > max_different_pixels {
+        let actual_path = format!("{}.actual.png", path.trim_end_matches(".png"));
+        actual.save(&actual_path)?;
+        bail!(
+            "{} pixels differ from {}; see {}",
+            num_different,
+            path,
+            actual_path
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use geom::Polygon;
+
+    use super::OffscreenRenderer;
+    use crate::{Color, EventCtx, GeomBatch, GfxCtx, ScreenDims, Settings, SharedAppState};
+
+    struct App;
+
+    impl SharedAppState for App {}
+
+    struct Squares;
+
+    impl crate::State<App> for Squares {
+        fn event(&mut self, _: &mut EventCtx, _: &mut App) -> crate::Transition<App> {
+            crate::Transition::Keep
+        }
+
+        fn draw(&self, g: &mut GfxCtx, _: &App) {
+            g.clear(Color::BLACK);
+            g.fork_screenspace();
+            let mut batch = GeomBatch::new();
+            batch.push(Color::RED, Polygon::rectangle(50.0, 50.0));
+            batch.push(
+                Color::BLUE.alpha(0.5),
+                Polygon::rectangle(50.0, 50.0).translate(25.0, 25.0),
+            );
+            batch.draw(g);
+            g.unfork();
+        }
+    }
+
+    #[test]
+    fn test_blending() {
+        let mut renderer = OffscreenRenderer::new(
+            Settings::new("test").scale_factor(1.0),
+            ScreenDims::new(100.0, 100.0),
+            |_| (App, vec![Box::new(Squares) as Box<dyn crate::State<App>>]),
+        );
+        let img = renderer.draw();
+        assert_eq!(img.dimensions(), (100, 100));
+        // Just red
+        assert_eq!(img.get_pixel(10, 10).0, [255, 0, 0, 255]);
+        // Translucent blue over red
+        assert_eq!(img.get_pixel(40, 40).0, [128, 0, 128, 255]);
+        // Translucent blue over the black background
+        assert_eq!(img.get_pixel(60, 60).0, [0, 0, 128, 255]);
+        assert_eq!(img.get_pixel(90, 90).0, [0, 0, 0, 255]);
+    }
+}
@@ -1,20 +1,30 @@
 use std::cell::{Cell, RefCell};
 use std::panic;
 
+#[cfg(any(feature = "native-backend", feature = "wasm-backend"))]
 use image::{GenericImageView, Pixel};
 use instant::Instant;
+#[cfg(any(feature = "native-backend", feature = "wasm-backend"))]
 use winit::window::Icon;
 
 use abstutil::{elapsed_seconds, Timer};
+#[cfg(any(feature = "native-backend", feature = "wasm-backend"))]
 use geom::Duration;
 
 use crate::app_state::App;
 use crate::assets::Assets;
-use crate::tools::screenshot::screenshot_everything;
+use crate::backend::PrerenderInnards;
+#[cfg(any(feature = "native-backend", feature = "wasm-backend"))]
+use crate::tools::screenshot::{screenshot_current, screenshot_everything};
+#[cfg(any(feature = "native-backend", feature = "wasm-backend"))]
+use crate::tools::vector::export_vector_image;
+use crate::tools::vector::VectorCapture;
 use crate::{
-    Canvas, Event, EventCtx, GfxCtx, Prerender, SharedAppState, Style, Text, UpdateType, UserInput,
+    Canvas, Event, EventCtx, GfxCtx, Key, Prerender, SharedAppState, Style, Text, UpdateType,
+    UserInput,
 };
 
+#[cfg(any(feature = "native-backend", feature = "wasm-backend"))]
 const UPDATE_FREQUENCY: std::time::Duration = std::time::Duration::from_millis(1000 / 30);
 // Manually enable and then check STDOUT
 const DEBUG_PERFORMANCE: bool = false;
@@ -23,12 +33,16 @@ const DEBUG_PERFORMANCE: bool = false;
 pub(crate) struct State<A: SharedAppState> {
     pub(crate) app: App<A>,
     pub(crate) canvas: Canvas,
-    style: Style,
+    pub(crate) style: Style,
 }
 
 impl<A: SharedAppState> State<A> {
     // The bool indicates if the input was actually used.
-    fn event(&mut self, mut ev: Event, prerender: &Prerender) -> (Vec<UpdateType>, bool) {
+    pub(crate) fn event(
+        &mut self,
+        mut ev: Event,
+        prerender: &Prerender,
+    ) -> (Vec<UpdateType>, bool) {
         if let Event::MouseWheelScroll(dx, dy) = ev {
             if self.canvas.invert_scroll {
                 ev = Event::MouseWheelScroll(-dx, -dy);
@@ -58,40 +72,7 @@ impl<A: SharedAppState> State<A> {
         // start_drawing call.
         let input = UserInput::new(ev, &self.canvas);
 
-        // Update some widgetry state that's stashed in Canvas for sad reasons.
-        {
-            if let Event::WindowResized(new_size) = input.event {
-                let inner_size = prerender.window_size();
-                trace!(
-                    "winit event says the window was resized from {}, {} to {:?}. But inner size \
-                     is {:?}, so using that",
-                    self.canvas.window_width,
-                    self.canvas.window_height,
-                    new_size,
-                    inner_size
-                );
-                prerender.window_resized(new_size);
-                self.canvas.window_width = inner_size.width;
-                self.canvas.window_height = inner_size.height;
-            }
-
-            if let Event::KeyPress(key) = input.event {
-                self.canvas.keys_held.insert(key);
-            } else if let Event::KeyRelease(key) = input.event {
-                self.canvas.keys_held.remove(&key);
-            }
-
-            if let Some(pt) = input.get_moved_mouse() {
-                self.canvas.cursor = pt;
-            }
-
-            if input.event == Event::WindowGainedCursor {
-                self.canvas.window_has_cursor = true;
-            }
-            if input.window_lost_cursor() {
-                self.canvas.window_has_cursor = false;
-            }
-        }
+        update_canvas(&mut self.canvas, &input, prerender);
 
         match panic::catch_unwind(panic::AssertUnwindSafe(|| {
             let mut ctx = EventCtx {
@@ -104,6 +85,7 @@ impl<A: SharedAppState> State<A> {
             };
             let started = Instant::now();
             self.app.event(&mut ctx);
+            wrap_keyboard_focus(&mut ctx);
             if DEBUG_PERFORMANCE {
                 println!("- event() took {}s", elapsed_seconds(started));
             }
@@ -126,7 +108,28 @@ impl<A: SharedAppState> State<A> {
 
     /// Returns naming hint. Logically consumes the number of uploads.
     pub(crate) fn draw(&mut self, prerender: &Prerender, screenshot: bool) -> Option<String> {
+        self.draw_with(prerender, screenshot, None).0
+    }
+
+    /// Draws normally, but also records everything drawn. While drawing, any new uploads keep
+    /// their geometry.
+    pub(crate) fn draw_vector(&mut self, prerender: &Prerender) -> VectorCapture {
+        let was_keeping = prerender.is_keeping_vector_geometry();
+        prerender.keep_vector_geometry(true);
+        let capture = VectorCapture::new(self.canvas.get_window_dims());
+        let capture = self.draw_with(prerender, false, Some(capture)).1.unwrap();
+        prerender.keep_vector_geometry(was_keeping);
+        capture
+    }
+
+    fn draw_with(
+        &mut self,
+        prerender: &Prerender,
+        screenshot: bool,
+        vector: Option<VectorCapture>,
+    ) -> (Option<String>, Option<VectorCapture>) {
         let mut g = GfxCtx::new(prerender, &self.canvas, &self.style, screenshot);
+        g.vector = vector;
 
         self.canvas.start_drawing();
 
@@ -149,12 +152,15 @@ impl<A: SharedAppState> State<A> {
             );
         }
 
+        let vector = g.vector.take();
         prerender.inner.draw_finished(g.inner);
-        naming_hint
+        (naming_hint, vector)
     }
 }
 
 /// Customize how widgetry works. These settings can't be changed after starting.
+// Without a window, a few of these don't apply.
+#[cfg_attr(feature = "offscreen-backend", allow(dead_code))]
 pub struct Settings {
     window_title: String,
     dump_raw_events: bool,
@@ -222,43 +228,85 @@ impl Settings {
     }
 }
 
-pub fn run<
-    A: 'static + SharedAppState,
-    F: FnOnce(&mut EventCtx) -> (A, Vec<Box<dyn crate::app_state::State<A>>>),
->(
-    settings: Settings,
-    make_app: F,
-) -> ! {
-    let mut timer = Timer::new("setup widgetry");
-    let (prerender_innards, event_loop) = crate::backend::setup(&settings.window_title, &mut timer);
+/// Update some widgetry state that's stashed in Canvas for sad reasons.
+pub(crate) fn update_canvas(canvas: &mut Canvas, input: &UserInput, prerender: &Prerender) {
+    if let Event::WindowResized(new_size) = input.event {
+        let inner_size = prerender.window_size();
+        trace!(
+            "winit event says the window was resized from {}, {} to {:?}. But inner size \
+             is {:?}, so using that",
+            canvas.window_width,
+            canvas.window_height,
+            new_size,
+            inner_size
+        );
+        prerender.window_resized(new_size);
+        canvas.window_width = inner_size.width;
+        canvas.window_height = inner_size.height;
+    }
 
-    if let Some(ref path) = settings.window_icon {
-        if !cfg!(target_arch = "wasm32") {
-            let image = image::open(path).unwrap();
-            let (width, height) = image.dimensions();
-            let mut rgba = Vec::with_capacity((width * height) as usize * 4);
-            for (_, _, pixel) in image.pixels() {
-                rgba.extend_from_slice(&pixel.to_rgba().0);
-            }
-            let icon = Icon::from_rgba(rgba, width, height).unwrap();
-            prerender_innards.set_window_icon(icon);
-        }
+    if let Event::KeyPress(key) = input.event {
+        canvas.keys_held.insert(key);
+    } else if let Event::KeyRelease(key) = input.event {
+        canvas.keys_held.remove(&key);
+    }
+
+    if let Some(pt) = input.get_moved_mouse() {
+        canvas.cursor = pt;
+    }
+
+    if input.event == Event::WindowGainedCursor {
+        canvas.window_has_cursor = true;
+    }
+    if input.window_lost_cursor() {
+        canvas.window_has_cursor = false;
+    }
+}
+
+/// Each panel moves keyboard focus through its own widgets, and leaves Tab alone once focus moves
+/// past its last widget. If no panel took it, start over at the beginning next time. This also
+/// recovers if the panel with focus is gone.
+pub(crate) fn wrap_keyboard_focus(ctx: &mut EventCtx) {
+    if ctx.input.event == Event::KeyPress(Key::Tab) && !ctx.input.has_been_consumed() {
+        ctx.canvas.focused_panel = None;
     }
+}
 
+/// Everything needed before creating the app
+pub(crate) fn setup_prerender(
+    prerender_innards: PrerenderInnards,
+    settings: Settings,
+) -> (Prerender, Canvas, Style) {
     let monitor_scale_factor = prerender_innards.monitor_scale_factor();
     let prerender = Prerender {
         assets: Assets::new(settings.read_svg),
         num_uploads: Cell::new(0),
         inner: prerender_innards,
         scale_factor: RefCell::new(settings.scale_factor.unwrap_or(monitor_scale_factor)),
+        keep_vector_geometry: Cell::new(false),
     };
     let mut style = Style::standard();
     style.loading_tips = settings.loading_tips.unwrap_or_else(Text::new);
 
     let initial_size = prerender.window_size();
-    let mut canvas = Canvas::new(initial_size);
+    let canvas = Canvas::new(initial_size);
     prerender.window_resized(initial_size);
 
+    (prerender, canvas, style)
+}
+
+/// Everything needed before handling the first event, whether or not there's a window
+pub(crate) fn setup_state<
+    A: 'static + SharedAppState,
+    F: FnOnce(&mut EventCtx) -> (A, Vec<Box<dyn crate::app_state::State<A>>>),
+>(
+    prerender_innards: PrerenderInnards,
+    settings: Settings,
+    make_app: F,
+    timer: &mut Timer,
+) -> (Prerender, State<A>) {
+    let (prerender, mut canvas, mut style) = setup_prerender(prerender_innards, settings);
+
     timer.start("setup app");
     let (shared_app_state, states) = make_app(&mut EventCtx {
         fake_mouseover: true,
@@ -273,11 +321,37 @@ pub fn run<
         shared_app_state,
         states,
     };
-    timer.done();
 
-    let mut state = State { canvas, app, style };
+    (prerender, State { canvas, app, style })
+}
+
+#[cfg(any(feature = "native-backend", feature = "wasm-backend"))]
+pub fn run<
+    A: 'static + SharedAppState,
+    F: FnOnce(&mut EventCtx) -> (A, Vec<Box<dyn crate::app_state::State<A>>>),
+>(
+    settings: Settings,
+    make_app: F,
+) -> ! {
+    let mut timer = Timer::new("setup widgetry");
+    let (prerender_innards, event_loop) = crate::backend::setup(&settings.window_title, &mut timer);
+
+    if let Some(ref path) = settings.window_icon {
+        if !cfg!(target_arch = "wasm32") {
+            let image = image::open(path).unwrap();
+            let (width, height) = image.dimensions();
+            let mut rgba = Vec::with_capacity((width * height) as usize * 4);
+            for (_, _, pixel) in image.pixels() {
+                rgba.extend_from_slice(&pixel.to_rgba().0);
+            }
+            let icon = Icon::from_rgba(rgba, width, height).unwrap();
+            prerender_innards.set_window_icon(icon);
+        }
+    }
 
     let dump_raw_events = settings.dump_raw_events;
+    let (prerender, mut state) = setup_state(prerender_innards, settings, make_app, &mut timer);
+    timer.done();
 
     let mut running = true;
     let mut last_update = Instant::now();
@@ -375,6 +449,16 @@ pub fn run<
                         error!("Couldn't screenshot everything: {}", err);
                     }
                 }
+                UpdateType::ScreenCapture { path } => {
+                    if let Err(err) = screenshot_current(&mut state, &prerender, &path) {
+                        error!("Couldn't capture {}: {}", path, err);
+                    }
+                }
+                UpdateType::ExportVectorImage { path, scale } => {
+                    if let Err(err) = export_vector_image(&mut state, &prerender, &path, scale) {
+                        error!("Couldn't export {}: {}", path, err);
+                    }
+                }
             }
         }
     });
@@ -288,7 +288,8 @@ impl<'a> Style {
             let mut txt = Text::new();
             let key_txt = Line(key.describe()).fg(button_style.fg_hotkey);
             txt.append(key_txt);
-            let label_text = Line(format!(" - {}", label)).fg(button_style.fg);
+            txt.append(Line(" - ").fg(button_style.fg));
+            let label_text = Line(label).fg(button_style.fg).translatable();
             txt.append(label_text);
             txt
         };
@@ -297,7 +298,8 @@ impl<'a> Style {
             let mut txt = Text::new();
             let key_txt = Line(key.describe()).fg(button_style.fg_hotkey.alpha(0.3));
             txt.append(key_txt);
-            let label_text = Line(format!(" - {}", label)).fg(button_style.fg_disabled);
+            txt.append(Line(" - ").fg(button_style.fg_disabled));
+            let label_text = Line(label).fg(button_style.fg_disabled).translatable();
             txt.append(label_text);
             txt
         };
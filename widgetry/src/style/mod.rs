@@ -8,6 +8,8 @@ pub struct Style {
     pub outline_color: Color,
     pub panel_bg: Color,
     pub hotkey_color: Color,
+    /// Drawn around the widget with keyboard focus
+    pub focus_ring_color: Color,
     pub loading_tips: Text,
     pub btn_solid_dark: ButtonStyle,
     pub btn_outline_dark: ButtonStyle,
@@ -35,6 +37,7 @@ impl Style {
             outline_color: Color::WHITE,
             panel_bg: Color::grey(0.4),
             hotkey_color: Color::GREEN,
+            focus_ring_color: hex("#FFCC00"),
             loading_tips: Text::new(),
 
             // Buttons
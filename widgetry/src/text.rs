@@ -50,6 +50,8 @@ pub struct TextSpan {
     size: usize,
     font: Font,
     underlined: bool,
+    translatable: bool,
+    verbatim: bool,
 }
 
 impl TextSpan {
@@ -75,24 +77,30 @@ impl TextSpan {
 
     // Yuwen's new styles, defined in Figma. Should document them in Github better.
 
+    // Titles and headings are translated into the current language, unless they're `verbatim`.
+
     pub fn display_title(mut self) -> TextSpan {
         self.font = Font::BungeeInlineRegular;
         self.size = 64;
+        self.translatable = true;
         self
     }
     pub fn big_heading_styled(mut self) -> TextSpan {
         self.font = Font::BungeeRegular;
         self.size = 32;
+        self.translatable = true;
         self
     }
     pub fn big_heading_plain(mut self) -> TextSpan {
         self.font = Font::OverpassBold;
         self.size = 32;
+        self.translatable = true;
         self
     }
     pub fn small_heading(mut self) -> TextSpan {
         self.font = Font::OverpassSemiBold;
         self.size = 26;
+        self.translatable = true;
         self
     }
     // The default
@@ -137,6 +145,19 @@ impl TextSpan {
         self.font = font;
         self
     }
+
+    /// Never translate this text, even if it's a heading. Use this for names from the map and
+    /// anything the user typed.
+    pub fn verbatim(mut self) -> TextSpan {
+        self.verbatim = true;
+        self
+    }
+
+    /// Translate this text into the current language when it's rendered.
+    pub(crate) fn translatable(mut self) -> TextSpan {
+        self.translatable = true;
+        self
+    }
 }
 
 // TODO What's the better way of doing this? Also "Line" is a bit of a misnomer
@@ -148,6 +169,8 @@ pub fn Line<S: Into<String>>(text: S) -> TextSpan {
         size: DEFAULT_FONT_SIZE,
         font: DEFAULT_FONT,
         underlined: false,
+        translatable: false,
+        verbatim: false,
     }
 }
 
@@ -207,11 +230,37 @@ impl Text {
         if let Some(ref key) = hotkey.into() {
             Text::from_all(vec![
                 Line(key.describe()).fg(ctx.style().hotkey_color).small(),
-                Line(format!(" - {}", action)).small(),
+                Line(" - ").small(),
+                Line(action).small().translatable(),
             ])
         } else {
-            Text::from(Line(action).small())
+            Text::from(Line(action).small().translatable())
+        }
+    }
+
+    /// Never translate any of this text. See `TextSpan::verbatim`.
+    pub fn verbatim(mut self) -> Text {
+        for (_, spans) in self.lines.iter_mut() {
+            for span in spans {
+                span.verbatim = true;
+            }
         }
+        self
+    }
+
+    /// Translates headings and other text marked as translatable into the current language.
+    fn translate(mut self, assets: &Assets) -> Text {
+        let translations = assets.translations.borrow();
+        for (_, spans) in self.lines.iter_mut() {
+            for span in spans {
+                if span.translatable && !span.verbatim {
+                    span.text = translations.translate(&span.text);
+                    // Don't translate twice
+                    span.verbatim = true;
+                }
+            }
+        }
+        self
     }
 
     pub fn change_fg(mut self, fg: Color) -> Text {
@@ -270,6 +319,15 @@ impl Text {
         self.lines.is_empty()
     }
 
+    /// All of the text without any styling, with one line per line.
+    pub fn to_plain_string(&self) -> String {
+        self.lines
+            .iter()
+            .map(|(_, spans)| spans.iter().map(|s| s.text.as_str()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
     pub fn extend(&mut self, other: Text) {
         self.lines.extend(other.lines);
     }
@@ -285,7 +343,8 @@ impl Text {
     }
 
     pub(crate) fn inner_render(self, assets: &Assets, tolerance: f32) -> GeomBatch {
-        let hash_key = self.hash_key();
+        let txt = self.translate(assets);
+        let hash_key = txt.hash_key();
         if let Some(batch) = assets.get_cached_text(&hash_key) {
             return batch;
         }
@@ -295,7 +354,7 @@ impl Text {
 
         let mut y = 0.0;
         let mut max_width = 0.0_f64;
-        for (line_color, line) in self.lines {
+        for (line_color, line) in txt.lines {
             // Assume size doesn't change mid-line. Always use this fixed line height per font
             // size.
             let line_height = assets.line_height(line[0].font, line[0].size);
@@ -325,7 +384,7 @@ impl Text {
             max_width = max_width.max(line_dims.width);
         }
 
-        if let Some(c) = self.bg_color {
+        if let Some(c) = txt.bg_color {
             output_batch.push(c, Polygon::rectangle(max_width, y));
         }
         output_batch.append(master_batch);
@@ -350,7 +409,8 @@ impl Text {
     }
 
     pub fn draw(self, ctx: &EventCtx) -> Widget {
-        JustDraw::wrap(ctx, self.render(ctx))
+        let label = self.to_plain_string();
+        JustDraw::wrap_text(ctx, self.render(ctx), label)
     }
     pub fn batch(self, ctx: &EventCtx) -> Widget {
         DeferDraw::new(self.render(ctx))
@@ -363,9 +423,10 @@ impl Text {
         )
     }
 
-    pub(crate) fn inner_wrap_to_pct(mut self, limit: f64, assets: &Assets) -> Text {
+    pub(crate) fn inner_wrap_to_pct(self, limit: f64, assets: &Assets) -> Text {
+        let mut txt = self.translate(assets);
         let mut lines = Vec::new();
-        for (bg, spans) in self.lines.drain(..) {
+        for (bg, spans) in txt.lines.drain(..) {
             // First optimistically assume everything just fits.
             if render_line(spans.clone(), svg::LOW_QUALITY, assets)
                 .get_dims()
@@ -391,6 +452,8 @@ impl Text {
                             font: span.font,
                             fg_color: span.fg_color,
                             underlined: false,
+                            translatable: false,
+                            verbatim: true,
                         }],
                         svg::LOW_QUALITY,
                         assets,
@@ -418,8 +481,8 @@ impl Text {
                 lines.push((bg, current_line));
             }
         }
-        self.lines = lines;
-        self
+        txt.lines = lines;
+        txt
     }
 }
 
@@ -65,3 +65,18 @@ pub(crate) fn screenshot_everything<A: SharedAppState>(
     state.canvas.cam_y = orig_y;
     Ok(())
 }
+
+/// Take a screenshot of exactly what's in the window right now.
+pub(crate) fn screenshot_current<A: SharedAppState>(
+    state: &mut State<A>,
+    prerender: &Prerender,
+    path: &str,
+) -> anyhow::Result<()> {
+    if let Some(dir) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    state.draw(prerender, true);
+    prerender
+        .inner
+        .screencap(state.canvas.get_window_dims(), path.to_string())
+}
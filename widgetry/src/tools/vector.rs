@@ -0,0 +1,436 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use geom::{Polygon, Pt2D, Ring};
+
+use crate::drawing::Uniforms;
+use crate::runner::State;
+use crate::{Color, Fill, GeomBatch, Prerender, ScreenDims, ScreenRectangle, SharedAppState};
+
+/// Draw everything once, recording the geometry, and write it as SVG or PDF, depending on the
+/// extension of `path`. `scale` multiplies the size of the output, relative to the window.
+pub(crate) fn export_vector_image<A: SharedAppState>(
+    state: &mut State<A>,
+    prerender: &Prerender,
+    path: &str,
+    scale: f64,
+) -> anyhow::Result<()> {
+    let capture = state.draw_vector(prerender);
+    if capture.num_missing > 0 {
+        warn!(
+            "{} objects were uploaded before keep_vector_geometry was enabled, so they're missing \
+             from {}",
+            capture.num_missing, path
+        );
+    }
+    if let Some(dir) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    if path.ends_with(".svg") {
+        std::fs::write(path, capture.to_svg(scale))?;
+    } else if path.ends_with(".pdf") {
+        std::fs::write(path, capture.to_pdf(scale))?;
+    } else {
+        bail!("Don't know how to write {}; use .svg or .pdf", path);
+    }
+    info!("Exported {} shapes to {}", capture.items.len(), path);
+    Ok(())
+}
+
+/// Everything drawn during one frame, in screen-space.
+pub(crate) struct VectorCapture {
+    window: ScreenDims,
+    items: Vec<Item>,
+    // Applies to anything drawn now
+    pub(crate) clip: Option<ScreenRectangle>,
+    // How many Drawables had no retained geometry
+    num_missing: usize,
+}
+
+struct Item {
+    paint: Paint,
+    // Each path is closed. Together, they fill the shape.
+    paths: Vec<Vec<(f64, f64)>>,
+    // Lower values are on top
+    z: f64,
+    clip: Option<ScreenRectangle>,
+}
+
+enum Paint {
+    Solid(Color),
+    LinearGradient {
+        from: (f64, f64),
+        to: (f64, f64),
+        stops: Vec<(f64, Color)>,
+    },
+}
+
+impl VectorCapture {
+    pub fn new(window: ScreenDims) -> VectorCapture {
+        VectorCapture {
+            window,
+            items: Vec::new(),
+            clip: None,
+            num_missing: 0,
+        }
+    }
+
+    pub fn add(&mut self, batch: Option<&GeomBatch>, uniforms: &Uniforms) {
+        let batch = if let Some(b) = batch {
+            b
+        } else {
+            self.num_missing += 1;
+            return;
+        };
+
+        let cam_x = uniforms.transform[0] as f64;
+        let cam_y = uniforms.transform[1] as f64;
+        let zoom = uniforms.transform[2] as f64;
+        let to_screen = |pt: Pt2D| (pt.x() * zoom - cam_x, pt.y() * zoom - cam_y);
+
+        for (fill, poly, z) in &batch.list {
+            // Skip anything offscreen. The entire map is usually uploaded as just a few objects.
+            let bounds = poly.get_bounds();
+            let (x1, y1) = to_screen(Pt2D::new(bounds.min_x, bounds.min_y));
+            let (x2, y2) = to_screen(Pt2D::new(bounds.max_x, bounds.max_y));
+            if x2 < 0.0 || y2 < 0.0 || x1 > self.window.width || y1 > self.window.height {
+                continue;
+            }
+
+            let paint = match fill {
+                Fill::Color(c) => Paint::Solid(*c),
+                Fill::LinearGradient(lg) => Paint::LinearGradient {
+                    from: to_screen(lg.line.pt1()),
+                    to: to_screen(lg.line.pt2()),
+                    stops: lg.stops.clone(),
+                },
+                // Textures aren't exported, just their color
+                Fill::Texture(_) => Paint::Solid(Color::WHITE),
+                Fill::ColoredTexture(c, _) => Paint::Solid(*c),
+            };
+            self.items.push(Item {
+                paint,
+                paths: to_paths(poly)
+                    .into_iter()
+                    .map(|pts| pts.into_iter().map(to_screen).collect())
+                    .collect(),
+                z: *z + (uniforms.window[2] as f64),
+                clip: self.clip.clone(),
+            });
+        }
+    }
+
+    /// Put everything in the order to paint it, from bottom to top.
+    fn sorted_items(&self) -> Vec<&Item> {
+        let mut items: Vec<&Item> = self.items.iter().collect();
+        // Ties are broken by the order things were drawn, like the depth test does
+        items.sort_by(|a, b| b.z.partial_cmp(&a.z).unwrap());
+        items
+    }
+
+    fn to_svg(&self, scale: f64) -> String {
+        let mut defs = String::new();
+        let mut body = String::new();
+        let mut clip_ids: Vec<String> = Vec::new();
+
+        for (idx, item) in self.sorted_items().into_iter().enumerate() {
+            let fill = match item.paint {
+                Paint::Solid(c) => svg_color(c),
+                Paint::LinearGradient {
+                    from,
+                    to,
+                    ref stops,
+                } => {
+                    writeln!(
+                        defs,
+                        r#"<linearGradient id="gradient{}" gradientUnits="userSpaceOnUse" x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}">"#,
+                        idx, from.0, from.1, to.0, to.1
+                    )
+                    .unwrap();
+                    for (offset, color) in stops {
+                        writeln!(
+                            defs,
+                            r#"<stop offset="{}" stop-color="rgb({},{},{})" stop-opacity="{}"/>"#,
+                            offset,
+                            to_byte(color.r),
+                            to_byte(color.g),
+                            to_byte(color.b),
+                            color.a
+                        )
+                        .unwrap();
+                    }
+                    defs.push_str("</linearGradient>\n");
+                    format!(r#"fill="url(#gradient{})""#, idx)
+                }
+            };
+
+            let clip = if let Some(ref rect) = item.clip {
+                let key = format!(
+                    r#"x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}""#,
+                    rect.x1,
+                    rect.y1,
+                    rect.x2 - rect.x1,
+                    rect.y2 - rect.y1
+                );
+                let id = if let Some(id) = clip_ids.iter().position(|x| x == &key) {
+                    id
+                } else {
+                    writeln!(
+                        defs,
+                        r#"<clipPath id="clip{}"><rect {}/></clipPath>"#,
+                        clip_ids.len(),
+                        key
+                    )
+                    .unwrap();
+                    clip_ids.push(key);
+                    clip_ids.len() - 1
+                };
+                format!(r#" clip-path="url(#clip{})""#, id)
+            } else {
+                String::new()
+            };
+
+            let mut d = String::new();
+            for path in &item.paths {
+                for (i, (x, y)) in path.iter().enumerate() {
+                    write!(d, "{}{:.2} {:.2} ", if i == 0 { "M" } else { "L" }, x, y).unwrap();
+                }
+                d.push_str("Z ");
+            }
+            writeln!(body, r#"<path d="{}" {}{}/>"#, d.trim_end(), fill, clip).unwrap();
+        }
+
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 \
+             {} {}\">\n<defs>\n{}</defs>\n{}</svg>\n",
+            self.window.width * scale,
+            self.window.height * scale,
+            self.window.width,
+            self.window.height,
+            defs,
+            body
+        )
+    }
+
+    fn to_pdf(&self, scale: f64) -> Vec<u8> {
+        let width = self.window.width * scale;
+        let height = self.window.height * scale;
+
+        // Transparency needs a graphics state per distinct alpha value
+        let mut alphas: BTreeMap<u8, usize> = BTreeMap::new();
+        let mut content = String::new();
+        // Flip the y axis, so the rest of the coordinates match the screen
+        writeln!(content, "{} 0 0 {} 0 {:.2} cm", scale, -scale, height).unwrap();
+        for item in self.sorted_items() {
+            // PDF gradients are much more involved. Just use the average color.
+            let color = match item.paint {
+                Paint::Solid(c) => c,
+                Paint::LinearGradient { ref stops, .. } => average_color(stops),
+            };
+            let alpha = to_byte(color.a);
+            let num_alphas = alphas.len();
+            let gs = *alphas.entry(alpha).or_insert(num_alphas);
+
+            content.push_str("q\n");
+            if let Some(ref rect) = item.clip {
+                writeln!(
+                    content,
+                    "{:.2} {:.2} {:.2} {:.2} re W n",
+                    rect.x1,
+                    rect.y1,
+                    rect.x2 - rect.x1,
+                    rect.y2 - rect.y1
+                )
+                .unwrap();
+            }
+            writeln!(
+                content,
+                "/GS{} gs {:.3} {:.3} {:.3} rg",
+                gs, color.r, color.g, color.b
+            )
+            .unwrap();
+            for path in &item.paths {
+                for (i, (x, y)) in path.iter().enumerate() {
+                    writeln!(
+                        content,
+                        "{:.2} {:.2} {}",
+                        x,
+                        y,
+                        if i == 0 { "m" } else { "l" }
+                    )
+                    .unwrap();
+                }
+                content.push_str("h\n");
+            }
+            content.push_str("f\nQ\n");
+        }
+
+        let mut ext_g_states = String::new();
+        for (alpha, idx) in &alphas {
+            write!(
+                ext_g_states,
+                "/GS{} << /Type /ExtGState /ca {:.3} >> ",
+                idx,
+                (*alpha as f64) / 255.0
+            )
+            .unwrap();
+        }
+
+        let objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] /Contents 4 0 R \
+                 /Resources << /ExtGState << {}>> >> >>",
+                width, height, ext_g_states
+            ),
+            format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                content.len(),
+                content
+            ),
+        ];
+
+        let mut pdf = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::new();
+        for (idx, obj) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            write!(pdf, "{} 0 obj\n{}\nendobj\n", idx + 1, obj).unwrap();
+        }
+        let xref = pdf.len();
+        write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).unwrap();
+        for offset in offsets {
+            write!(pdf, "{:010} 00000 n \n", offset).unwrap();
+        }
+        write!(
+            pdf,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .unwrap();
+        pdf.into_bytes()
+    }
+}
+
+/// Polygons with a simple outline become one path. Anything else, like polygons with holes or
+/// tessellated text, becomes one path per triangle.
+fn to_paths(poly: &Polygon) -> Vec<Vec<Pt2D>> {
+    if let Ok(ring) = Ring::new(poly.points().clone()) {
+        return vec![ring.points().clone()];
+    }
+    poly.triangles()
+        .into_iter()
+        .map(|tri| vec![tri.pt1, tri.pt2, tri.pt3])
+        .collect()
+}
+
+fn svg_color(c: Color) -> String {
+    format!(
+        r#"fill="rgb({},{},{})" fill-opacity="{}""#,
+        to_byte(c.r),
+        to_byte(c.g),
+        to_byte(c.b),
+        c.a
+    )
+}
+
+fn average_color(stops: &[(f64, Color)]) -> Color {
+    if stops.is_empty() {
+        return Color::WHITE;
+    }
+    let n = stops.len() as f32;
+    Color::rgba_f(
+        stops.iter().map(|(_, c)| c.r).sum::<f32>() / n,
+        stops.iter().map(|(_, c)| c.g).sum::<f32>() / n,
+        stops.iter().map(|(_, c)| c.b).sum::<f32>() / n,
+        stops.iter().map(|(_, c)| c.a).sum::<f32>() / n,
+    )
+}
+
+fn to_byte(x: f32) -> u8 {
+    (x.max(0.0).min(1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A red square with a translucent blue one partly over it, and one far offscreen
+    fn capture() -> VectorCapture {
+        let mut batch = GeomBatch::new();
+        batch.push(Color::RED, Polygon::rectangle(10.0, 10.0));
+        batch.push(
+            Color::BLUE.alpha(0.5),
+            Polygon::rectangle(10.0, 10.0).translate(5.0, 5.0),
+        );
+        batch.push(
+            Color::GREEN,
+            Polygon::rectangle(10.0, 10.0).translate(500.0, 500.0),
+        );
+        let uniforms = Uniforms {
+            transform: [0.0, 0.0, 1.0],
+            window: [100.0, 100.0, 0.0],
+        };
+
+        let mut capture = VectorCapture::new(ScreenDims::new(100.0, 100.0));
+        capture.add(Some(&batch), &uniforms);
+        capture.add(None, &uniforms);
+        capture
+    }
+
+    #[test]
+    fn test_svg() {
+        let capture = capture();
+        assert_eq!(capture.items.len(), 2);
+        assert_eq!(capture.num_missing, 1);
+
+        let svg = capture.to_svg(2.0);
+        assert!(svg.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="200" viewBox="0 0 100 100">"#
+        ));
+        assert_eq!(svg.matches("<path").count(), 2);
+        // Things drawn first are painted first
+        let red = svg
+            .find(r#"<path d="M0.00 0.00 L10.00 0.00 L10.00 10.00 L0.00 10.00 L0.00 0.00 Z" fill="rgb(255,0,0)" fill-opacity="1"/>"#)
+            .unwrap();
+        let blue = svg
+            .find(r#"<path d="M5.00 5.00 L15.00 5.00 L15.00 15.00 L5.00 15.00 L5.00 5.00 Z" fill="rgb(0,0,255)" fill-opacity="0.5"/>"#)
+            .unwrap();
+        assert!(red < blue);
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn test_pdf() {
+        let pdf = String::from_utf8(capture().to_pdf(2.0)).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.ends_with("%%EOF\n"));
+        assert!(pdf.contains("/MediaBox [0 0 200.00 200.00]"));
+        assert!(pdf.contains("/GS0 << /Type /ExtGState /ca 1.000 >>"));
+        assert!(pdf.contains("/GS1 << /Type /ExtGState /ca 0.502 >>"));
+        let red = pdf.find("/GS0 gs 1.000 0.000 0.000 rg").unwrap();
+        let blue = pdf.find("/GS1 gs 0.000 0.000 1.000 rg").unwrap();
+        assert!(red < blue);
+
+        // Every object has to be where the cross-reference table says
+        let xref = pdf.rfind("xref\n").unwrap();
+        let offsets: Vec<usize> = pdf[xref..]
+            .lines()
+            .filter(|line| line.ends_with(" n "))
+            .map(|line| line[0..10].parse().unwrap())
+            .collect();
+        assert_eq!(offsets.len(), 4);
+        for (idx, offset) in offsets.into_iter().enumerate() {
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj\n", idx + 1)));
+        }
+        let startxref: usize = pdf
+            .lines()
+            .skip_while(|line| *line != "startxref")
+            .nth(1)
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(startxref, xref);
+    }
+}
@@ -0,0 +1,170 @@
+//! UI text is written in English. Button labels, default tooltips, and headings are translated
+//! when they're rendered; opt out with `ButtonBuilder::untranslated` and `TextSpan::verbatim` for
+//! names from the map and anything the user typed. Other text is only translated where it's wrapped
+//! in `ctx.tr(...)`, or `tr!(ctx, ...)` when it has parameters. Text only changes when it's
+//! rendered, so after switching languages, panels have to be built again.
+
+use std::collections::BTreeMap;
+
+/// How to translate English UI text.
+#[derive(Clone)]
+pub enum Translations {
+    /// Leave everything in English.
+    English,
+    /// Look up English strings in a message catalog. Anything missing stays in English.
+    Catalog(BTreeMap<String, String>),
+    /// Don't translate, but accent every letter and make everything about 40% longer, like many
+    /// languages are compared to English. Text that still looks normal isn't translatable yet, and
+    /// panels that overflow will also overflow in real translations.
+    Pseudo,
+}
+
+impl Translations {
+    /// Parses a message catalog, which is a JSON object mapping English strings to their
+    /// translation.
+    pub fn from_json(raw: &str) -> anyhow::Result<Translations> {
+        let messages: BTreeMap<String, String> = serde_json::from_str(raw)?;
+        Ok(Translations::Catalog(messages))
+    }
+
+    /// Translates some English UI text.
+    pub fn translate(&self, english: &str) -> String {
+        match self {
+            Translations::English => english.to_string(),
+            Translations::Catalog(ref messages) => messages
+                .get(english)
+                .cloned()
+                .unwrap_or_else(|| english.to_string()),
+            Translations::Pseudo => pseudo_translate(english),
+        }
+    }
+}
+
+/// Translates some English UI text, then fills in parameters like `format!`. Only `{}`
+/// placeholders are supported, not format specs. Since word order differs between languages, a
+/// translation can also refer to parameters by position, like `{1} ... {0}`.
+///
+/// `tr!(ctx, "Figures are saved in {}", path)`
+#[macro_export]
+macro_rules! tr {
+    ($ctx:expr, $english:expr) => {
+        $ctx.tr($english)
+    };
+    ($ctx:expr, $english:expr, $($arg:expr),+ $(,)?) => {
+        $crate::fill_parameters(&$ctx.tr($english), &[$($arg.to_string()),+])
+    };
+}
+
+/// Used by `tr!`. Placeholders without a matching parameter are left alone, so a mistake in a
+/// catalog shows up on screen instead of crashing.
+#[doc(hidden)]
+pub fn fill_parameters(template: &str, args: &[String]) -> String {
+    let mut result = String::new();
+    let mut next_arg = 0;
+    let mut rest = template;
+    while let Some(idx) = rest.find(|c: char| c == '{' || c == '}') {
+        result.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            result.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+        if rest.starts_with('{') {
+            if let Some(end) = rest.find('}') {
+                let inner = &rest[1..end];
+                let arg = if inner.is_empty() {
+                    next_arg += 1;
+                    args.get(next_arg - 1)
+                } else {
+                    inner.parse::<usize>().ok().and_then(|i| args.get(i))
+                };
+                if let Some(arg) = arg {
+                    result.push_str(arg);
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+        }
+        result.push_str(&rest[..1]);
+        rest = &rest[1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+fn pseudo_translate(english: &str) -> String {
+    let num_letters = english.chars().filter(|c| c.is_alphabetic()).count();
+    // Leave numbers and symbols alone
+    if num_letters == 0 {
+        return english.to_string();
+    }
+
+    let mut result = String::from("[");
+    for c in english.chars() {
+        result.push(match c {
+            'a' => 'á',
+            'c' => 'ç',
+            'e' => 'é',
+            'i' => 'í',
+            'n' => 'ñ',
+            'o' => 'ö',
+            's' => 'š',
+            'u' => 'ü',
+            'y' => 'ý',
+            'z' => 'ž',
+            'A' => 'Å',
+            'C' => 'Ç',
+            'E' => 'É',
+            'I' => 'Î',
+            'N' => 'Ñ',
+            'O' => 'Ö',
+            'S' => 'Š',
+            'U' => 'Ü',
+            'Y' => 'Ý',
+            'Z' => 'Ž',
+            _ => c,
+        });
+    }
+    for _ in 0..(num_letters * 2 / 5).max(1) {
+        result.push('~');
+    }
+    result.push(']');
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate() {
+        let catalog = Translations::from_json(r#"{"Settings": "Ustawienia"}"#).unwrap();
+        assert_eq!(catalog.translate("Settings"), "Ustawienia");
+        assert_eq!(catalog.translate("Apply"), "Apply");
+
+        assert_eq!(Translations::Pseudo.translate("Settings"), "[Šéttíñgš~~~]");
+        assert_eq!(Translations::Pseudo.translate("42 %"), "42 %");
+        assert_eq!(
+            Translations::Pseudo.translate("Saved in {}"),
+            "[Šávéd íñ {}~~]"
+        );
+
+        assert_eq!(Translations::English.translate("Settings"), "Settings");
+    }
+
+    #[test]
+    fn test_fill_parameters() {
+        let args = vec!["3".to_string(), "Seattle".to_string()];
+        assert_eq!(
+            fill_parameters("{} trips in {}", &args),
+            "3 trips in Seattle"
+        );
+        assert_eq!(fill_parameters("{1}: {0} trips", &args), "Seattle: 3 trips");
+        assert_eq!(fill_parameters("{{{}}}", &args), "{3}");
+        // Mistakes in a catalog are left visible
+        assert_eq!(fill_parameters("{} {} {}", &args), "3 Seattle {}");
+        assert_eq!(fill_parameters("{5} {x} {", &args), "{5} {x} {");
+    }
+}
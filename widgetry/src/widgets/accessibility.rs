@@ -0,0 +1,82 @@
+use std::fmt;
+
+/// What kind of widget something is, for people navigating with a keyboard or screen reader.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Role {
+    Button,
+    Checkbox,
+    Dropdown,
+    Slider,
+    TextBox,
+    Text,
+}
+
+/// Describes one widget on a panel for assistive technology. See `Panel::accessibility_tree`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccessibilityNode {
+    pub role: Role,
+    /// What the widget says or does, in the current UI language
+    pub label: String,
+    /// The current state, for checkboxes, dropdowns, sliders, and text boxes
+    pub value: Option<String>,
+    /// The name the panel uses for this widget, if it has one
+    pub id: Option<String>,
+    /// Can this widget be used right now? Disabled buttons can't.
+    pub focusable: bool,
+    /// Does this widget have keyboard focus right now?
+    pub focused: bool,
+}
+
+impl AccessibilityNode {
+    pub fn new<I: Into<String>>(role: Role, label: I) -> AccessibilityNode {
+        AccessibilityNode {
+            role,
+            label: label.into(),
+            value: None,
+            id: None,
+            focusable: role != Role::Text,
+            focused: false,
+        }
+    }
+
+    pub fn value<I: Into<String>>(mut self, value: I) -> AccessibilityNode {
+        self.value = Some(value.into());
+        self
+    }
+
+    pub fn focusable(mut self, focusable: bool) -> AccessibilityNode {
+        self.focusable = focusable;
+        self
+    }
+}
+
+/// One line meant to be read aloud, like `checkbox "Show traffic signals": checked, focused`.
+impl fmt::Display for AccessibilityNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.role == Role::Text {
+            return write!(f, "{}", self.label);
+        }
+        let role = match self.role {
+            Role::Button => "button",
+            Role::Checkbox => "checkbox",
+            Role::Dropdown => "dropdown",
+            Role::Slider => "slider",
+            Role::TextBox => "text box",
+            Role::Text => unreachable!(),
+        };
+        write!(f, "{} {:?}", role, self.label)?;
+        match self.value {
+            Some(ref value) if !value.is_empty() => {
+                write!(f, ": {}", value)?;
+            }
+            _ => {}
+        }
+        if !self.focusable {
+            write!(f, ", unavailable")?;
+        }
+        if self.focused {
+            write!(f, ", focused")?;
+        }
+        Ok(())
+    }
+}
@@ -1,9 +1,9 @@
 use geom::{Distance, Polygon, Pt2D};
 
 use crate::{
-    svg, text::Font, Color, ContentMode, ControlState, CornerRounding, Drawable, EdgeInsets,
-    EventCtx, GeomBatch, GfxCtx, Line, MultiKey, Outcome, RewriteColor, ScreenDims, ScreenPt,
-    ScreenRectangle, Text, Widget, WidgetImpl, WidgetOutput,
+    svg, text::Font, AccessibilityNode, Color, ContentMode, ControlState, CornerRounding, Drawable,
+    EdgeInsets, EventCtx, GeomBatch, GfxCtx, Key, Line, MultiKey, Outcome, RewriteColor, Role,
+    ScreenDims, ScreenPt, ScreenRectangle, Text, Widget, WidgetImpl, WidgetOutput,
 };
 
 pub struct Button {
@@ -120,6 +120,18 @@ impl WidgetImpl for Button {
             g.redraw_at(self.top_left, &self.draw_normal);
         }
     }
+
+    fn accessibility(&self) -> Option<AccessibilityNode> {
+        Some(AccessibilityNode::new(Role::Button, self.action.clone()).focusable(!self.is_disabled))
+    }
+
+    fn focused_key_press(&mut self, _: &mut EventCtx, key: Key, output: &mut WidgetOutput) -> bool {
+        if self.is_disabled || !(key == Key::Enter || key == Key::Space) {
+            return false;
+        }
+        output.outcome = Outcome::Clicked(self.action.clone());
+        true
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -132,6 +144,7 @@ pub struct ButtonBuilder<'a> {
     is_label_before_image: bool,
     corner_rounding: Option<CornerRounding>,
     is_disabled: bool,
+    is_untranslated: bool,
     default_style: ButtonStyle<'a>,
     hover_style: ButtonStyle<'a>,
     disable_style: ButtonStyle<'a>,
@@ -223,6 +236,13 @@ impl<'b, 'a: 'b> ButtonBuilder<'a> {
         self
     }
 
+    /// The label is translated into the current language by default, while the action stays the
+    /// same. Don't translate it if it's a name from the map or something the user typed.
+    pub fn untranslated(mut self) -> Self {
+        self.is_untranslated = true;
+        self
+    }
+
     /// Set the color of the button's label.
     ///
     /// If not specified, a default font color will be used.
@@ -488,6 +508,14 @@ impl<'b, 'a: 'b> ButtonBuilder<'a> {
             "button was empty"
         );
         let hitbox = normal.get_bounds().get_rectangle();
+        let tooltip = match self.tooltip {
+            Some(ref tooltip) => Some(tooltip.clone()),
+            // The default tooltip repeats the action
+            None if self.is_untranslated => {
+                Some(Text::tooltip(ctx, self.hotkey.clone(), action).verbatim())
+            }
+            None => None,
+        };
         Button::new(
             ctx,
             normal,
@@ -495,7 +523,7 @@ impl<'b, 'a: 'b> ButtonBuilder<'a> {
             disabled,
             self.hotkey.clone(),
             action,
-            self.tooltip.clone(),
+            tooltip,
             hitbox,
             self.is_disabled,
         )
@@ -638,7 +666,11 @@ impl<'b, 'a: 'b> ButtonBuilder<'a> {
                     .as_ref()
                     .or(default.and_then(|d| d.styled_text.as_ref()))
                 {
-                    return Some(styled_text.clone().bg(Color::CLEAR).render(ctx));
+                    let mut styled_text = styled_text.clone();
+                    if self.is_untranslated {
+                        styled_text = styled_text.verbatim();
+                    }
+                    return Some(styled_text.bg(Color::CLEAR).render(ctx));
                 }
 
                 let text = label.text.or(default.and_then(|d| d.text));
@@ -654,6 +686,9 @@ impl<'b, 'a: 'b> ButtonBuilder<'a> {
                     .or(default.and_then(|d| d.color))
                     .unwrap_or(ctx.style().outline_color);
                 let mut line = Line(text).fg(color);
+                if !self.is_untranslated {
+                    line = line.translatable();
+                }
 
                 if let Some(font_size) = label.font_size.or(default.and_then(|d| d.font_size)) {
                     line = line.size(font_size);
@@ -1,7 +1,7 @@
 use crate::{
-    include_labeled_bytes, Button, Color, ControlState, EdgeInsets, EventCtx, GfxCtx, MultiKey,
-    Outcome, RewriteColor, ScreenDims, ScreenPt, StyledButtons, Text, TextSpan, Widget, WidgetImpl,
-    WidgetOutput,
+    include_labeled_bytes, AccessibilityNode, Button, Color, ControlState, EdgeInsets, EventCtx,
+    GfxCtx, Key, MultiKey, Outcome, RewriteColor, Role, ScreenDims, ScreenPt, StyledButtons, Text,
+    TextSpan, Widget, WidgetImpl, WidgetOutput,
 };
 
 pub struct Checkbox {
@@ -219,6 +219,18 @@ impl Checkbox {
     }
 }
 
+impl Checkbox {
+    fn toggle_if_clicked(&mut self, output: &mut WidgetOutput) {
+        if let Outcome::Clicked(_) = output.outcome {
+            output.outcome = Outcome::Changed;
+            std::mem::swap(&mut self.btn, &mut self.other_btn);
+            self.btn.set_pos(self.other_btn.top_left);
+            self.enabled = !self.enabled;
+            output.redo_layout = true;
+        }
+    }
+}
+
 impl WidgetImpl for Checkbox {
     fn get_dims(&self) -> ScreenDims {
         self.btn.get_dims()
@@ -230,16 +242,29 @@ impl WidgetImpl for Checkbox {
 
     fn event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) {
         self.btn.event(ctx, output);
-        if let Outcome::Clicked(_) = output.outcome {
-            output.outcome = Outcome::Changed;
-            std::mem::swap(&mut self.btn, &mut self.other_btn);
-            self.btn.set_pos(self.other_btn.top_left);
-            self.enabled = !self.enabled;
-            output.redo_layout = true;
-        }
+        self.toggle_if_clicked(output);
     }
 
     fn draw(&self, g: &mut GfxCtx) {
         self.btn.draw(g);
     }
+
+    fn accessibility(&self) -> Option<AccessibilityNode> {
+        let mut node = self.btn.accessibility()?;
+        node.role = Role::Checkbox;
+        Some(node.value(if self.enabled { "checked" } else { "unchecked" }))
+    }
+
+    fn focused_key_press(
+        &mut self,
+        ctx: &mut EventCtx,
+        key: Key,
+        output: &mut WidgetOutput,
+    ) -> bool {
+        if !self.btn.focused_key_press(ctx, key, output) {
+            return false;
+        }
+        self.toggle_if_clicked(output);
+        true
+    }
 }
@@ -0,0 +1,113 @@
+use abstutil::prettyprint_usize;
+
+use crate::widgets::compare_plot::{draw_crosshairs, draw_plot, with_axes};
+use crate::{
+    Color, Drawable, EventCtx, GfxCtx, Line, ScreenDims, ScreenPt, Text, Widget, WidgetImpl,
+    WidgetOutput,
+};
+
+/// A scatter plot comparing two counts of the same thing, like observed and simulated traffic
+/// volumes. Points above the diagonal have a higher y value.
+pub struct CompareCounts {
+    draw: Drawable,
+
+    max: usize,
+    x_name: String,
+    y_name: String,
+
+    top_left: ScreenPt,
+    dims: ScreenDims,
+}
+
+impl CompareCounts {
+    pub fn new<I: Into<String>>(
+        ctx: &mut EventCtx,
+        x_name: I,
+        y_name: I,
+        points: Vec<(usize, usize)>,
+    ) -> Widget {
+        if points.is_empty() {
+            return Widget::nothing();
+        }
+        let x_name = x_name.into();
+        let y_name = y_name.into();
+
+        let actual_max = points.iter().map(|(x, y)| *x.max(y)).max().unwrap().max(1);
+        // Excluding 0
+        let num_labels = 5;
+        let step = round_up_nicely((actual_max as f64 / num_labels as f64).ceil() as usize);
+        let max = step * num_labels;
+        let labels: Vec<usize> = (0..=num_labels).map(|i| i * step).collect();
+
+        let batch = draw_plot(
+            num_labels,
+            points
+                .into_iter()
+                .map(|(x, y)| {
+                    let color = if x == y {
+                        Color::YELLOW.alpha(0.5)
+                    } else if y < x {
+                        Color::hex("#4A90E2").alpha(0.9)
+                    } else {
+                        Color::hex("#EB3223").alpha(0.9)
+                    };
+                    ((x as f64) / (max as f64), (y as f64) / (max as f64), color)
+                })
+                .collect(),
+        );
+        let plot = Widget::new(Box::new(CompareCounts {
+            dims: batch.get_dims(),
+            draw: ctx.upload(batch),
+            max,
+            x_name: x_name.clone(),
+            y_name: y_name.clone(),
+            top_left: ScreenPt::new(0.0, 0.0),
+        }));
+
+        with_axes(
+            ctx,
+            plot,
+            x_name,
+            y_name,
+            labels.into_iter().map(prettyprint_usize).collect(),
+        )
+    }
+}
+
+/// Rounds up to 1, 2, or 5 times a power of 10.
+fn round_up_nicely(x: usize) -> usize {
+    let mut magnitude = 1;
+    loop {
+        for multiple in &[1, 2, 5] {
+            if x <= multiple * magnitude {
+                return multiple * magnitude;
+            }
+        }
+        magnitude *= 10;
+    }
+}
+
+impl WidgetImpl for CompareCounts {
+    fn get_dims(&self) -> ScreenDims {
+        self.dims
+    }
+
+    fn set_pos(&mut self, top_left: ScreenPt) {
+        self.top_left = top_left;
+    }
+
+    fn event(&mut self, _: &mut EventCtx, _: &mut WidgetOutput) {}
+
+    fn draw(&self, g: &mut GfxCtx) {
+        g.redraw_at(self.top_left, &self.draw);
+
+        draw_crosshairs(g, self.top_left, self.dims, |pct_x, pct_y| {
+            let x = (pct_x * self.max as f64).round() as usize;
+            let y = (pct_y * self.max as f64).round() as usize;
+            Text::from_multiline(vec![
+                Line(format!("{}: {}", self.x_name, prettyprint_usize(x))),
+                Line(format!("{}: {}", self.y_name, prettyprint_usize(y))),
+            ])
+        });
+    }
+}
@@ -0,0 +1,126 @@
+//! Pieces shared by scatter plots comparing two measurements of the same thing on the same scale,
+//! like `CompareTimes` and `CompareCounts`.
+
+use geom::{Angle, Circle, Distance, Pt2D};
+
+use crate::{
+    Color, EventCtx, GeomBatch, GfxCtx, JustDraw, Line, ScreenDims, ScreenPt, ScreenRectangle,
+    Text, TextExt, Widget,
+};
+
+/// Draws a square plot with grid lines and a diagonal marking where both values match. Points are
+/// expressed as a percent of the maximum value on each axis.
+pub(crate) fn draw_plot(num_labels: usize, points: Vec<(f64, f64, Color)>) -> GeomBatch {
+    // We want a nice square so the scales match up.
+    let width = 500.0;
+    let height = width;
+
+    let mut batch = GeomBatch::new();
+    batch.autocrop_dims = false;
+
+    // Grid lines
+    let thickness = Distance::meters(2.0);
+    for i in 1..num_labels {
+        let x = (i as f64) / (num_labels as f64) * width;
+        let y = (i as f64) / (num_labels as f64) * height;
+        // Horizontal
+        batch.push(
+            Color::grey(0.5),
+            geom::Line::new(Pt2D::new(0.0, y), Pt2D::new(width, y))
+                .unwrap()
+                .make_polygons(thickness),
+        );
+        // Vertical
+        batch.push(
+            Color::grey(0.5),
+            geom::Line::new(Pt2D::new(x, 0.0), Pt2D::new(x, height))
+                .unwrap()
+                .make_polygons(thickness),
+        );
+    }
+    // Draw the diagonal, since we're comparing things on the same scale
+    batch.push(
+        Color::grey(0.5),
+        geom::Line::new(Pt2D::new(0.0, height), Pt2D::new(width, 0.0))
+            .unwrap()
+            .make_polygons(thickness),
+    );
+
+    let circle = Circle::new(Pt2D::new(0.0, 0.0), Distance::meters(4.0)).to_polygon();
+    for (pct_x, pct_y, color) in points {
+        let pt = Pt2D::new(pct_x * width, (1.0 - pct_y) * height);
+        batch.push(color, circle.translate(pt.x(), pt.y()));
+    }
+    batch
+}
+
+/// Surrounds the plot with labelled axes. `labels` go from 0 to the maximum value.
+pub(crate) fn with_axes(
+    ctx: &mut EventCtx,
+    plot: Widget,
+    x_name: String,
+    y_name: String,
+    labels: Vec<String>,
+) -> Widget {
+    let y_axis = Widget::custom_col(
+        labels
+            .iter()
+            .rev()
+            .map(|x| Line(x).small().draw(ctx))
+            .collect(),
+    )
+    .evenly_spaced();
+    let y_label = {
+        let label = Text::from(Line(y_name))
+            .render(ctx)
+            .rotate(Angle::degrees(90.0))
+            .autocrop();
+        // The text is already scaled; don't use Widget::draw_batch and scale it again.
+        JustDraw::wrap(ctx, label).centered_vert().margin_right(5)
+    };
+
+    let x_axis = Widget::custom_row(labels.iter().map(|x| Line(x).small().draw(ctx)).collect())
+        .evenly_spaced();
+    let x_label = x_name.draw_text(ctx).centered_horiz();
+
+    // It's a bit of work to make both the x and y axis line up with the plot. :)
+    let plot_width = plot.get_width_for_forcing();
+    Widget::custom_col(vec![
+        Widget::custom_row(vec![y_label, y_axis, plot]),
+        Widget::custom_col(vec![x_axis, x_label])
+            .force_width(plot_width)
+            .align_right(),
+    ])
+    .container()
+}
+
+/// If the cursor is over the plot, draws crosshairs to it and a tooltip. The tooltip is given the
+/// cursor's position as a percent of the maximum value on each axis.
+pub(crate) fn draw_crosshairs<F: Fn(f64, f64) -> Text>(
+    g: &mut GfxCtx,
+    top_left: ScreenPt,
+    dims: ScreenDims,
+    tooltip: F,
+) {
+    if let Some(cursor) = g.canvas.get_cursor_in_screen_space() {
+        let rect = ScreenRectangle::top_left(top_left, dims);
+        if let Some((pct_x, pct_y)) = rect.pt_to_percent(cursor) {
+            let thickness = Distance::meters(2.0);
+            let mut batch = GeomBatch::new();
+            // Horizontal
+            if let Some(l) = geom::Line::new(Pt2D::new(rect.x1, cursor.y), cursor.to_pt()) {
+                batch.push(Color::WHITE, l.make_polygons(thickness));
+            }
+            // Vertical
+            if let Some(l) = geom::Line::new(Pt2D::new(cursor.x, rect.y2), cursor.to_pt()) {
+                batch.push(Color::WHITE, l.make_polygons(thickness));
+            }
+
+            g.fork_screenspace();
+            let draw = g.upload(batch);
+            g.redraw(&draw);
+            g.draw_mouse_tooltip(tooltip(pct_x, 1.0 - pct_y));
+            g.unfork();
+        }
+    }
+}
@@ -1,15 +1,14 @@
-use geom::{Angle, Circle, Distance, Duration, Pt2D};
+use geom::Duration;
 
+use crate::widgets::compare_plot::{draw_crosshairs, draw_plot, with_axes};
 use crate::{
-    Color, Drawable, EventCtx, GeomBatch, GfxCtx, JustDraw, Line, ScreenDims, ScreenPt,
-    ScreenRectangle, Text, TextExt, Widget, WidgetImpl, WidgetOutput,
+    Color, Drawable, EventCtx, GfxCtx, Line, ScreenDims, ScreenPt, Text, Widget, WidgetImpl,
+    WidgetOutput,
 };
 
 // TODO This is tuned for the trip time comparison right now.
-// - Generic types for x and y axis
 // - number of labels
 // - rounding behavior
-// - forcing the x and y axis to be on the same scale, be drawn as a square
 // - coloring the better/worse
 
 pub struct CompareTimes {
@@ -37,54 +36,23 @@ impl CompareTimes {
         let num_labels = 5;
         let (max, labels) = actual_max.make_intervals_for_max(num_labels);
 
-        // We want a nice square so the scales match up.
-        let width = 500.0;
-        let height = width;
-
-        let mut batch = GeomBatch::new();
-        batch.autocrop_dims = false;
-
-        // Grid lines
-        let thickness = Distance::meters(2.0);
-        for i in 1..num_labels {
-            let x = (i as f64) / (num_labels as f64) * width;
-            let y = (i as f64) / (num_labels as f64) * height;
-            // Horizontal
-            batch.push(
-                Color::grey(0.5),
-                geom::Line::new(Pt2D::new(0.0, y), Pt2D::new(width, y))
-                    .unwrap()
-                    .make_polygons(thickness),
-            );
-            // Vertical
-            batch.push(
-                Color::grey(0.5),
-                geom::Line::new(Pt2D::new(x, 0.0), Pt2D::new(x, height))
-                    .unwrap()
-                    .make_polygons(thickness),
-            );
-        }
-        // Draw the diagonal, since we're comparing things on the same scale
-        batch.push(
-            Color::grey(0.5),
-            geom::Line::new(Pt2D::new(0.0, height), Pt2D::new(width, 0.0))
-                .unwrap()
-                .make_polygons(thickness),
+        let batch = draw_plot(
+            num_labels,
+            points
+                .into_iter()
+                .map(|(b, a)| {
+                    // TODO Could color circles by mode
+                    let color = if a == b {
+                        Color::YELLOW.alpha(0.5)
+                    } else if a < b {
+                        Color::GREEN.alpha(0.9)
+                    } else {
+                        Color::RED.alpha(0.9)
+                    };
+                    (b / max, a / max, color)
+                })
+                .collect(),
         );
-
-        let circle = Circle::new(Pt2D::new(0.0, 0.0), Distance::meters(4.0)).to_polygon();
-        for (b, a) in points {
-            let pt = Pt2D::new((b / max) * width, (1.0 - (a / max)) * height);
-            // TODO Could color circles by mode
-            let color = if a == b {
-                Color::YELLOW.alpha(0.5)
-            } else if a < b {
-                Color::GREEN.alpha(0.9)
-            } else {
-                Color::RED.alpha(0.9)
-            };
-            batch.push(color, circle.translate(pt.x(), pt.y()));
-        }
         let plot = Widget::new(Box::new(CompareTimes {
             dims: batch.get_dims(),
             draw: ctx.upload(batch),
@@ -92,43 +60,13 @@ impl CompareTimes {
             top_left: ScreenPt::new(0.0, 0.0),
         }));
 
-        let y_axis = Widget::custom_col(
-            labels
-                .iter()
-                .rev()
-                .map(|x| Line(x.to_string()).small().draw(ctx))
-                .collect(),
+        with_axes(
+            ctx,
+            plot,
+            format!("{} (minutes)", x_name.into()),
+            format!("{} (minutes)", y_name.into()),
+            labels.into_iter().map(|x| x.to_string()).collect(),
         )
-        .evenly_spaced();
-        let y_label = {
-            let label = Text::from(Line(format!("{} (minutes)", y_name.into())))
-                .render(ctx)
-                .rotate(Angle::degrees(90.0))
-                .autocrop();
-            // The text is already scaled; don't use Widget::draw_batch and scale it again.
-            JustDraw::wrap(ctx, label).centered_vert().margin_right(5)
-        };
-
-        let x_axis = Widget::custom_row(
-            labels
-                .iter()
-                .map(|x| Line(x.to_string()).small().draw(ctx))
-                .collect(),
-        )
-        .evenly_spaced();
-        let x_label = format!("{} (minutes)", x_name.into())
-            .draw_text(ctx)
-            .centered_horiz();
-
-        // It's a bit of work to make both the x and y axis line up with the plot. :)
-        let plot_width = plot.get_width_for_forcing();
-        Widget::custom_col(vec![
-            Widget::custom_row(vec![y_label, y_axis, plot]),
-            Widget::custom_col(vec![x_axis, x_label])
-                .force_width(plot_width)
-                .align_right(),
-        ])
-        .container()
     }
 }
 
@@ -146,51 +84,33 @@ impl WidgetImpl for CompareTimes {
     fn draw(&self, g: &mut GfxCtx) {
         g.redraw_at(self.top_left, &self.draw);
 
-        if let Some(cursor) = g.canvas.get_cursor_in_screen_space() {
-            let rect = ScreenRectangle::top_left(self.top_left, self.dims);
-            if let Some((pct_x, pct_y)) = rect.pt_to_percent(cursor) {
-                let thickness = Distance::meters(2.0);
-                let mut batch = GeomBatch::new();
-                // Horizontal
-                if let Some(l) = geom::Line::new(Pt2D::new(rect.x1, cursor.y), cursor.to_pt()) {
-                    batch.push(Color::WHITE, l.make_polygons(thickness));
-                }
-                // Vertical
-                if let Some(l) = geom::Line::new(Pt2D::new(cursor.x, rect.y2), cursor.to_pt()) {
-                    batch.push(Color::WHITE, l.make_polygons(thickness));
-                }
-
-                g.fork_screenspace();
-                let draw = g.upload(batch);
-                g.redraw(&draw);
-                // TODO Quite specialized to the one use right now
-                let before = pct_x * self.max;
-                let after = (1.0 - pct_y) * self.max;
-                if after <= before {
-                    g.draw_mouse_tooltip(Text::from_multiline(vec![
-                        Line(format!("Before: {}", before)),
-                        Line(format!("After: {}", after)),
-                        Line(format!(
-                            "{} faster (-{:.1}%)",
-                            before - after,
-                            100.0 * (1.0 - after / before)
-                        ))
-                        .fg(Color::hex("#72CE36")),
-                    ]));
-                } else {
-                    g.draw_mouse_tooltip(Text::from_multiline(vec![
-                        Line(format!("Before: {}", before)),
-                        Line(format!("After: {}", after)),
-                        Line(format!(
-                            "{} slower (+{:.1}%)",
-                            after - before,
-                            100.0 * (after / before - 1.0)
-                        ))
-                        .fg(Color::hex("#EB3223")),
-                    ]));
-                }
-                g.unfork();
+        draw_crosshairs(g, self.top_left, self.dims, |pct_x, pct_y| {
+            // TODO Quite specialized to the one use right now
+            let before = pct_x * self.max;
+            let after = pct_y * self.max;
+            if after <= before {
+                Text::from_multiline(vec![
+                    Line(format!("Before: {}", before)),
+                    Line(format!("After: {}", after)),
+                    Line(format!(
+                        "{} faster (-{:.1}%)",
+                        before - after,
+                        100.0 * (1.0 - after / before)
+                    ))
+                    .fg(Color::hex("#72CE36")),
+                ])
+            } else {
+                Text::from_multiline(vec![
+                    Line(format!("Before: {}", before)),
+                    Line(format!("After: {}", after)),
+                    Line(format!(
+                        "{} slower (+{:.1}%)",
+                        after - before,
+                        100.0 * (after / before - 1.0)
+                    ))
+                    .fg(Color::hex("#EB3223")),
+                ])
             }
-        }
+        });
     }
 }
@@ -1,8 +1,9 @@
 use geom::{CornerRadii, Distance, Polygon, Pt2D};
 
 use crate::{
-    Button, Choice, Color, ControlState, CornerRounding, EdgeInsets, EventCtx, GeomBatch, GfxCtx,
-    Menu, Outcome, ScreenDims, ScreenPt, ScreenRectangle, StyledButtons, WidgetImpl, WidgetOutput,
+    AccessibilityNode, Button, Choice, Color, ControlState, CornerRounding, EdgeInsets, EventCtx,
+    GeomBatch, GfxCtx, Key, Menu, Outcome, Role, ScreenDims, ScreenPt, ScreenRectangle,
+    StyledButtons, WidgetImpl, WidgetOutput,
 };
 
 pub struct Dropdown<T: Clone> {
@@ -160,6 +161,28 @@ impl<T: 'static + Clone> WidgetImpl for Dropdown<T> {
         }
     }
 
+    fn accessibility(&self) -> Option<AccessibilityNode> {
+        Some(
+            AccessibilityNode::new(Role::Dropdown, self.label.clone())
+                .value(self.choices[self.current_idx].label.clone()),
+        )
+    }
+
+    fn focused_key_press(&mut self, ctx: &mut EventCtx, key: Key, _: &mut WidgetOutput) -> bool {
+        // While the menu is open, it handles the arrow keys and Enter like usual
+        match (self.menu.is_some(), key) {
+            (false, Key::Enter) | (false, Key::Space) | (false, Key::DownArrow) => {
+                self.open_menu(ctx);
+                true
+            }
+            (true, Key::Escape) => {
+                self.menu = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
     fn can_restore(&self) -> bool {
         true
     }
@@ -195,7 +218,8 @@ fn make_btn(ctx: &EventCtx, label: &str, tooltip: &str, is_persisten_split: bool
             }))
             .outline(0.0, Color::CLEAR, ControlState::Default);
     } else {
-        builder = builder.label_text(label);
+        // Like the menu, choices aren't translated here; callers translate them up-front.
+        builder = builder.label_text(label).untranslated();
     }
 
     builder.build(ctx, tooltip)
@@ -1,8 +1,8 @@
 use geom::Polygon;
 
 use crate::{
-    svg, Drawable, EventCtx, GeomBatch, GfxCtx, RewriteColor, ScreenDims, ScreenPt,
-    ScreenRectangle, Text, Widget, WidgetImpl, WidgetOutput,
+    svg, AccessibilityNode, Drawable, EventCtx, GeomBatch, GfxCtx, RewriteColor, Role, ScreenDims,
+    ScreenPt, ScreenRectangle, Text, Widget, WidgetImpl, WidgetOutput,
 };
 
 // Just draw something, no interaction.
@@ -11,6 +11,9 @@ pub struct JustDraw {
 
     pub top_left: ScreenPt,
     pub dims: ScreenDims,
+
+    /// If this is text, what it says
+    label: Option<String>,
 }
 
 impl JustDraw {
@@ -19,6 +22,16 @@ impl JustDraw {
             dims: batch.get_dims(),
             draw: ctx.upload(batch),
             top_left: ScreenPt::new(0.0, 0.0),
+            label: None,
+        }))
+    }
+
+    pub(crate) fn wrap_text(ctx: &EventCtx, batch: GeomBatch, label: String) -> Widget {
+        Widget::new(Box::new(JustDraw {
+            dims: batch.get_dims(),
+            draw: ctx.upload(batch),
+            top_left: ScreenPt::new(0.0, 0.0),
+            label: Some(label),
         }))
     }
 
@@ -29,6 +42,7 @@ impl JustDraw {
             dims: ScreenDims::new(bounds.width(), bounds.height()),
             draw: ctx.upload(batch),
             top_left: ScreenPt::new(0.0, 0.0),
+            label: None,
         }))
     }
     pub fn svg_transform(ctx: &EventCtx, filename: &str, rewrite: RewriteColor) -> Widget {
@@ -39,6 +53,7 @@ impl JustDraw {
             dims: ScreenDims::new(bounds.width(), bounds.height()),
             draw: ctx.upload(batch),
             top_left: ScreenPt::new(0.0, 0.0),
+            label: None,
         }))
     }
 }
@@ -57,6 +72,15 @@ impl WidgetImpl for JustDraw {
     fn draw(&self, g: &mut GfxCtx) {
         g.redraw_at(self.top_left, &self.draw);
     }
+
+    fn accessibility(&self) -> Option<AccessibilityNode> {
+        match self.label {
+            Some(ref label) if !label.is_empty() => {
+                Some(AccessibilityNode::new(Role::Text, label.clone()))
+            }
+            _ => None,
+        }
+    }
 }
 
 pub struct DrawWithTooltips {
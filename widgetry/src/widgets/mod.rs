@@ -12,14 +12,17 @@ use geom::{CornerRadii, Distance, Percent, Polygon};
 use crate::widgets::containers::{Container, Nothing};
 pub use crate::widgets::panel::Panel;
 use crate::{
-    Button, Checkbox, Choice, Color, DeferDraw, DrawWithTooltips, Drawable, Dropdown, EventCtx,
-    GeomBatch, GfxCtx, JustDraw, Menu, RewriteColor, ScreenDims, ScreenPt, ScreenRectangle, Text,
-    TextBox,
+    AccessibilityNode, Button, Checkbox, Choice, Color, DeferDraw, DrawWithTooltips, Drawable,
+    Dropdown, EventCtx, GeomBatch, GfxCtx, JustDraw, Key, Menu, RewriteColor, ScreenDims, ScreenPt,
+    ScreenRectangle, Text, TextBox,
 };
 
+pub mod accessibility;
 pub mod autocomplete;
 pub mod button;
 pub mod checkbox;
+pub mod compare_counts;
+mod compare_plot;
 pub mod compare_times;
 pub mod containers;
 pub mod dropdown;
@@ -59,6 +62,16 @@ pub trait WidgetImpl: downcast_rs::Downcast {
     fn restore(&mut self, _: &mut EventCtx, _prev: &Box<dyn WidgetImpl>) {
         unreachable!()
     }
+    /// Describe this widget for keyboard and screen reader users. Widgets returning nothing are
+    /// left out of the accessibility tree and can't get keyboard focus.
+    fn accessibility(&self) -> Option<AccessibilityNode> {
+        None
+    }
+    /// This widget has keyboard focus and a key was pressed. Return true if the widget used the
+    /// key; otherwise the panel handles it normally.
+    fn focused_key_press(&mut self, _: &mut EventCtx, _: Key, _: &mut WidgetOutput) -> bool {
+        false
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -653,6 +666,80 @@ impl Widget {
         }
     }
 
+    fn get_all_click_rects(&self, rects: &mut Vec<(String, ScreenRectangle)>) {
+        if let Some(btn) = self.widget.downcast_ref::<Button>() {
+            rects.push((btn.action.clone(), self.rect.clone()));
+        } else if let Some(checkbox) = self.widget.downcast_ref::<Checkbox>() {
+            rects.push((checkbox.btn.action.clone(), self.rect.clone()));
+        } else if let Some(container) = self.widget.downcast_ref::<Container>() {
+            for w in &container.members {
+                w.get_all_click_rects(rects);
+            }
+        }
+    }
+
+    /// `focus` indexes into `focusable_widgets`.
+    fn accessibility_tree(
+        &self,
+        focus: Option<usize>,
+        num_focusable: &mut usize,
+        nodes: &mut Vec<AccessibilityNode>,
+    ) {
+        if let Some(container) = self.widget.downcast_ref::<Container>() {
+            for w in &container.members {
+                w.accessibility_tree(focus, num_focusable, nodes);
+            }
+        } else if let Some(mut node) = self.widget.accessibility() {
+            // Widgets without any text, like sliders, are described by their name
+            if node.label.is_empty() {
+                if let Some(ref id) = self.id {
+                    node.label = id.clone();
+                }
+            }
+            node.id = self.id.clone();
+            if self.is_focusable() {
+                node.focused = focus == Some(*num_focusable);
+                *num_focusable += 1;
+            }
+            nodes.push(node);
+        }
+    }
+
+    fn is_focusable(&self) -> bool {
+        // Moving focus scrolls the panel already
+        if self.id == Some("horiz scrollbar".to_string())
+            || self.id == Some("vert scrollbar".to_string())
+        {
+            return false;
+        }
+        self.widget
+            .accessibility()
+            .map(|node| node.focusable)
+            .unwrap_or(false)
+    }
+
+    /// Everything that Tab can move to, in reading order
+    fn focusable_widgets<'a>(&'a self, widgets: &mut Vec<&'a Widget>) {
+        if let Some(container) = self.widget.downcast_ref::<Container>() {
+            for w in &container.members {
+                w.focusable_widgets(widgets);
+            }
+        } else if self.is_focusable() {
+            widgets.push(self);
+        }
+    }
+
+    fn focusable_widgets_mut<'a>(&'a mut self, widgets: &mut Vec<&'a mut Widget>) {
+        if self.widget.is::<Container>() {
+            let container = self.widget.downcast_mut::<Container>().unwrap();
+            for w in &mut container.members {
+                w.focusable_widgets_mut(widgets);
+            }
+        } else if self.is_focusable() {
+            widgets.push(self);
+        }
+    }
+
     fn currently_hovering(&self) -> Option<&String> {
         if let Some(btn) = self.widget.downcast_ref::<Button>() {
             if btn.hovering {
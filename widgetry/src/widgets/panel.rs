@@ -5,13 +5,13 @@ use stretch::node::Stretch;
 use stretch::number::Number;
 use stretch::style::{Dimension, Style};
 
-use geom::{Percent, Polygon};
+use geom::{Distance, Percent, Polygon};
 
 use crate::widgets::Container;
 use crate::{
-    Autocomplete, Checkbox, Color, Dropdown, EventCtx, GfxCtx, HorizontalAlignment, Menu, Outcome,
-    PersistentSplit, ScreenDims, ScreenPt, ScreenRectangle, Slider, Spinner, TextBox,
-    VerticalAlignment, Widget, WidgetImpl, WidgetOutput,
+    AccessibilityNode, Autocomplete, Canvas, Checkbox, Color, Dropdown, Event, EventCtx, GfxCtx,
+    HorizontalAlignment, Key, Menu, Outcome, PersistentSplit, ScreenDims, ScreenPt,
+    ScreenRectangle, Slider, Spinner, TextBox, VerticalAlignment, Widget, WidgetImpl, WidgetOutput,
 };
 
 pub struct Panel {
@@ -25,6 +25,10 @@ pub struct Panel {
     contents_dims: ScreenDims,
     container_dims: ScreenDims,
     clip_rect: Option<ScreenRectangle>,
+
+    // Identifies this panel to the canvas, which tracks which panel has keyboard focus
+    panel_id: usize,
+    keyboard_focus: Option<usize>,
 }
 
 impl Panel {
@@ -266,7 +270,10 @@ impl Panel {
 
         let before = self.scroll_offset();
         let mut output = WidgetOutput::new();
-        self.top_level.widget.event(ctx, &mut output);
+        if !self.focused_widget_event(ctx, &mut output) {
+            self.top_level.widget.event(ctx, &mut output);
+            self.move_keyboard_focus(ctx);
+        }
         if self.scroll_offset() != before || output.redo_layout {
             self.recompute_layout(ctx, true);
         }
@@ -274,6 +281,99 @@ impl Panel {
         output.outcome
     }
 
+    /// The index into `Widget::focusable_widgets` of the widget with keyboard focus, if this
+    /// panel has it
+    fn focused_widget_idx(&self, canvas: &Canvas) -> Option<usize> {
+        if canvas.focused_panel == Some(self.panel_id) {
+            self.keyboard_focus
+        } else {
+            None
+        }
+    }
+
+    /// If a widget on this panel has keyboard focus, it gets the first chance to handle a key
+    /// press. Returns true if it did.
+    fn focused_widget_event(&mut self, ctx: &mut EventCtx, output: &mut WidgetOutput) -> bool {
+        let key = match ctx.input.event {
+            Event::KeyPress(key) if !ctx.input.has_been_consumed() => key,
+            _ => {
+                return false;
+            }
+        };
+        let idx = match self.focused_widget_idx(ctx.canvas) {
+            Some(idx) => idx,
+            None => {
+                return false;
+            }
+        };
+        let mut widgets = Vec::new();
+        self.top_level.focusable_widgets_mut(&mut widgets);
+        if let Some(w) = widgets.into_iter().nth(idx) {
+            if w.widget.focused_key_press(ctx, key, output) {
+                ctx.input.consume_event();
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Tab moves keyboard focus to the next widget, and shift+Tab to the previous one. Past either
+    /// end of this panel, the key is left for the next panel.
+    fn move_keyboard_focus(&mut self, ctx: &mut EventCtx) {
+        if ctx.input.event != Event::KeyPress(Key::Tab) || ctx.input.has_been_consumed() {
+            return;
+        }
+        let mut widgets = Vec::new();
+        self.top_level.focusable_widgets(&mut widgets);
+        if widgets.is_empty() {
+            return;
+        }
+        let backwards = ctx.is_key_down(Key::LeftShift);
+        let next = match self.focused_widget_idx(ctx.canvas) {
+            Some(idx) => {
+                if backwards {
+                    idx.checked_sub(1)
+                } else if idx + 1 < widgets.len() {
+                    Some(idx + 1)
+                } else {
+                    None
+                }
+            }
+            None => {
+                if ctx.canvas.focused_panel.is_some()
+                    && ctx.canvas.focused_panel != Some(self.panel_id)
+                {
+                    // Some other panel has focus
+                    return;
+                }
+                Some(if backwards { widgets.len() - 1 } else { 0 })
+            }
+        };
+
+        self.keyboard_focus = next;
+        if let Some(idx) = next {
+            ctx.canvas.focused_panel = Some(self.panel_id);
+            ctx.input.consume_event();
+            let rect = widgets[idx].rect.clone();
+            if let Some(ref clip) = self.clip_rect {
+                if !clip.contains(rect.center()) {
+                    self.set_scroll_offset(ctx, (0.0, rect.y1));
+                }
+            }
+        } else {
+            ctx.canvas.focused_panel = None;
+        }
+    }
+
+    /// Describes every widget on this panel for assistive technology, in reading order. Each
+    /// node's `Display` is a line of text suitable for a screen reader.
+    pub fn accessibility_tree(&self, ctx: &EventCtx) -> Vec<AccessibilityNode> {
+        let mut nodes = Vec::new();
+        self.top_level
+            .accessibility_tree(self.focused_widget_idx(ctx.canvas), &mut 0, &mut nodes);
+        nodes
+    }
+
     pub fn draw(&self, g: &mut GfxCtx) {
         if let Some(ref rect) = self.clip_rect {
             g.enable_clipping(rect.clone());
@@ -281,6 +381,9 @@ impl Panel {
         } else {
             g.canvas.mark_covered_area(self.top_level.rect.clone());
         }
+        if let Some(ref mut clickables) = g.clickables {
+            clickables.extend(self.clickable_rects());
+        }
 
         // Debugging
         if false {
@@ -299,6 +402,18 @@ impl Panel {
         }
 
         self.top_level.draw(g);
+        if let Some(idx) = self.focused_widget_idx(g.canvas) {
+            let mut widgets = Vec::new();
+            self.top_level.focusable_widgets(&mut widgets);
+            if let Some(w) = widgets.get(idx) {
+                let color = g.style().focus_ring_color;
+                if let Ok(ring) = w.rect.to_polygon().to_outline(Distance::meters(3.0)) {
+                    g.fork_screenspace();
+                    g.draw_polygon(color, ring);
+                    g.unfork();
+                }
+            }
+        }
         if self.scrollable_x || self.scrollable_y {
             g.disable_clipping();
 
@@ -319,8 +434,22 @@ impl Panel {
         actions
     }
 
+    /// Every button and checkbox, with its action and where it is on the screen. Buttons scrolled
+    /// out of view are skipped.
+    pub(crate) fn clickable_rects(&self) -> Vec<(String, ScreenRectangle)> {
+        let mut rects = Vec::new();
+        self.top_level.get_all_click_rects(&mut rects);
+        if let Some(ref clip) = self.clip_rect {
+            rects.retain(|(_, rect)| clip.contains(rect.center()));
+        }
+        rects
+    }
+
     pub fn restore(&mut self, ctx: &mut EventCtx, prev: &Panel) {
         self.set_scroll_offset(ctx, prev.scroll_offset());
+        // Keep keyboard focus on the same widget
+        self.panel_id = prev.panel_id;
+        self.keyboard_focus = prev.keyboard_focus;
 
         self.top_level.restore(ctx, &prev);
 
@@ -517,7 +646,11 @@ impl PanelBuilder {
             contents_dims: ScreenDims::new(0.0, 0.0),
             container_dims: ScreenDims::new(0.0, 0.0),
             clip_rect: None,
+
+            panel_id: ctx.canvas.next_panel_id,
+            keyboard_focus: None,
         };
+        ctx.canvas.next_panel_id += 1;
         if let Dims::ExactPercent(w, h) = panel.dims {
             // Don't set size, because then scrolling breaks -- the actual size has to be based on
             // the contents.
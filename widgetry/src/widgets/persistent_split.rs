@@ -42,7 +42,9 @@ impl<T: 'static + PartialEq + Clone + std::fmt::Debug> PersistentSplit<T> {
         choices: Vec<Choice<T>>,
     ) -> PersistentSplit<T> {
         let dropdown = Dropdown::new(ctx, "change", default_value, choices, true);
-        let mut btn = button_builder(ctx).label_text(dropdown.current_value_label());
+        let mut btn = button_builder(ctx)
+            .label_text(dropdown.current_value_label())
+            .untranslated();
 
         if let Some(multikey) = hotkey.into() {
             btn = btn.hotkey(multikey)
@@ -113,8 +115,9 @@ impl<T: 'static + Clone + PartialEq> WidgetImpl for PersistentSplit<T> {
         if new_value != self.current_value {
             self.current_value = new_value;
             let label = self.btn.action.clone();
-            let mut button_builder =
-                button_builder(ctx).label_text(self.dropdown.current_value_label());
+            let mut button_builder = button_builder(ctx)
+                .label_text(self.dropdown.current_value_label())
+                .untranslated();
             if let Some(multikey) = self.btn.hotkey.take() {
                 button_builder = button_builder.hotkey(multikey)
             }
@@ -1,8 +1,8 @@
 use geom::{Circle, Distance, Polygon, Pt2D};
 
 use crate::{
-    Color, Drawable, EventCtx, GeomBatch, GfxCtx, ScreenDims, ScreenPt, ScreenRectangle, Widget,
-    WidgetImpl, WidgetOutput,
+    AccessibilityNode, Color, Drawable, EventCtx, GeomBatch, GfxCtx, Key, Role, ScreenDims,
+    ScreenPt, ScreenRectangle, Widget, WidgetImpl, WidgetOutput,
 };
 
 pub struct Slider {
@@ -270,4 +270,30 @@ impl WidgetImpl for Slider {
         g.canvas
             .mark_covered_area(ScreenRectangle::top_left(self.top_left, self.dims));
     }
+
+    fn accessibility(&self) -> Option<AccessibilityNode> {
+        // The panel fills in the label from the slider's name
+        Some(
+            AccessibilityNode::new(Role::Slider, "")
+                .value(format!("{}%", (self.current_percent * 100.0).round())),
+        )
+    }
+
+    fn focused_key_press(&mut self, ctx: &mut EventCtx, key: Key, _: &mut WidgetOutput) -> bool {
+        let step =
+            match (&self.style, key) {
+                (Style::Vertical { .. }, Key::UpArrow) => -0.05,
+                (Style::Vertical { .. }, Key::DownArrow) => 0.05,
+                (Style::Horizontal { .. }, Key::LeftArrow)
+                | (Style::Area { .. }, Key::LeftArrow) => -0.05,
+                (Style::Horizontal { .. }, Key::RightArrow)
+                | (Style::Area { .. }, Key::RightArrow) => 0.05,
+                _ => {
+                    return false;
+                }
+            };
+        self.current_percent = abstutil::clamp(self.current_percent + step, 0.0, 1.0);
+        self.recalc(ctx);
+        true
+    }
 }
@@ -1,3 +1,5 @@
+use std::rc::Rc;
+
 use abstutil::prettyprint_usize;
 use geom::Polygon;
 
@@ -13,6 +15,7 @@ pub struct Table<A, T, F> {
     label_per_row: Box<dyn Fn(&T) -> String>,
     columns: Vec<Column<A, T>>,
     filter: Filter<A, T, F>,
+    exports: Vec<(String, Export<T>)>,
 
     sort_by: String,
     descending: bool,
@@ -30,6 +33,18 @@ struct Column<A, T> {
     col: Col<T>,
 }
 
+/// How to write one column when exporting a table
+enum Export<T> {
+    Text(Box<dyn Fn(&T) -> String>),
+    Number(Box<dyn Fn(&T) -> f64>),
+}
+
+/// One column of an exported table, with a value per row
+pub enum ExportValues {
+    Text(Vec<String>),
+    Number(Vec<f64>),
+}
+
 pub struct Filter<A, T, F> {
     pub state: F,
     pub to_controls: Box<dyn Fn(&mut EventCtx, &A, &F) -> Widget>,
@@ -49,6 +64,7 @@ impl<A, T, F> Table<A, T, F> {
             label_per_row,
             columns: Vec::new(),
             filter,
+            exports: Vec::new(),
 
             sort_by: default_sort_by.to_string(),
             descending: true,
@@ -69,7 +85,19 @@ impl<A, T, F> Table<A, T, F> {
         });
     }
 
-    pub fn render(&self, ctx: &mut EventCtx, app: &A) -> Widget {
+    /// Include a column of text when exporting the table. `static_col` does this already.
+    pub fn export_text(&mut self, name: &str, value: Box<dyn Fn(&T) -> String>) {
+        self.exports.push((name.to_string(), Export::Text(value)));
+    }
+
+    /// Include a column of numbers when exporting the table. Prefer raw values, like durations in
+    /// seconds, over the text shown in the table.
+    pub fn export_number(&mut self, name: &str, value: Box<dyn Fn(&T) -> f64>) {
+        self.exports.push((name.to_string(), Export::Number(value)));
+    }
+
+    /// Rows that pass the current filter, in the current order
+    fn filtered_and_sorted(&self) -> Vec<&T> {
         let mut data: Vec<&T> = Vec::new();
 
         // Filter
@@ -78,7 +106,6 @@ impl<A, T, F> Table<A, T, F> {
                 data.push(row);
             }
         }
-        let num_filtered = data.len();
 
         // Sort
         for col in &self.columns {
@@ -93,6 +120,12 @@ impl<A, T, F> Table<A, T, F> {
         if self.descending {
             data.reverse();
         }
+        data
+    }
+
+    pub fn render(&self, ctx: &mut EventCtx, app: &A) -> Widget {
+        let data = self.filtered_and_sorted();
+        let num_filtered = data.len();
 
         // Render the headers
         let headers = self
@@ -166,6 +199,61 @@ impl<A, T, F> Table<A, T, F> {
         self.filter.state = (self.filter.from_controls)(panel);
         self.skip = 0;
     }
+
+    /// Every exported column, with values for all rows matching the current filter, in the current
+    /// order. Unlike `render`, this isn't limited to one page.
+    pub fn export(&self) -> Vec<(String, ExportValues)> {
+        let data = self.filtered_and_sorted();
+        self.exports
+            .iter()
+            .map(|(name, export)| {
+                let values = match export {
+                    Export::Text(f) => ExportValues::Text(data.iter().map(|x| f(x)).collect()),
+                    Export::Number(f) => ExportValues::Number(data.iter().map(|x| f(x)).collect()),
+                };
+                (name.clone(), values)
+            })
+            .collect()
+    }
+
+    /// Exports the table as CSV, with a header row.
+    pub fn to_csv(&self) -> String {
+        let columns = self.export();
+        let num_rows = match columns.get(0) {
+            Some((_, ExportValues::Text(x))) => x.len(),
+            Some((_, ExportValues::Number(x))) => x.len(),
+            None => 0,
+        };
+
+        let mut out = columns
+            .iter()
+            .map(|(name, _)| csv_escape(name))
+            .collect::<Vec<_>>()
+            .join(",");
+        out.push('\n');
+        for idx in 0..num_rows {
+            out.push_str(
+                &columns
+                    .iter()
+                    .map(|(_, values)| match values {
+                        ExportValues::Text(x) => csv_escape(&x[idx]),
+                        ExportValues::Number(x) => x[idx].to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn csv_escape(value: &str) -> String {
+    if value.contains(|c| c == ',' || c == '"' || c == '\n') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
 }
 
 impl<A, T> Filter<A, T, ()> {
@@ -183,11 +271,14 @@ impl<A, T> Filter<A, T, ()> {
 // things.
 impl<A, T: 'static, F> Table<A, T, F> {
     pub fn static_col(&mut self, name: &str, to_str: Box<dyn Fn(&T) -> String>) {
+        let to_str = Rc::new(to_str);
+        let render_str = to_str.clone();
         self.column(
             name,
-            Box::new(move |ctx, _, x| Text::from(Line((to_str)(x))).render(ctx)),
+            Box::new(move |ctx, _, x| Text::from(Line((render_str)(x))).render(ctx)),
             Col::Static,
         );
+        self.export_text(name, Box::new(move |x| (to_str)(x)));
     }
 }
 
@@ -280,3 +371,34 @@ fn make_table(
 
     Widget::custom_col(col)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_csv() {
+        let mut table: Table<(), (usize, &'static str), ()> = Table::new(
+            vec![(3, "three"), (1, "one, \"uno\""), (2, "two")],
+            Box::new(|x| x.0.to_string()),
+            "id",
+            Filter {
+                state: (),
+                to_controls: Box::new(|_, _, _| Widget::nothing()),
+                from_controls: Box::new(|_| ()),
+                apply: Box::new(|_, x| x.0 != 2),
+            },
+        );
+        table.column(
+            "id",
+            Box::new(|_, _, _| GeomBatch::new()),
+            Col::Sortable(Box::new(|rows| rows.sort_by_key(|x| x.0))),
+        );
+        table.export_number("id", Box::new(|x| x.0 as f64));
+        table.static_col("name", Box::new(|x| x.1.to_string()));
+
+        assert_eq!(table.to_csv(), "id,name\n3,three\n1,\"one, \"\"uno\"\"\"\n");
+        assert!(table.clicked("id"));
+        assert_eq!(table.to_csv(), "id,name\n1,\"one, \"\"uno\"\"\"\n3,three\n");
+    }
+}
@@ -1,8 +1,8 @@
 use geom::Polygon;
 
 use crate::{
-    text, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, ScreenDims, ScreenPt, ScreenRectangle,
-    Text, WidgetImpl, WidgetOutput,
+    text, AccessibilityNode, EventCtx, GeomBatch, GfxCtx, Key, Line, Outcome, Role, ScreenDims,
+    ScreenPt, ScreenRectangle, Text, WidgetImpl, WidgetOutput,
 };
 
 // TODO right now, only a single line
@@ -53,6 +53,37 @@ impl TextBox {
     pub fn get_line(&self) -> String {
         self.line.clone()
     }
+
+    /// Returns false if the key doesn't type anything.
+    fn type_key(&mut self, ctx: &EventCtx, key: Key, output: &mut WidgetOutput) -> bool {
+        match key {
+            Key::LeftArrow => {
+                if self.cursor_x > 0 {
+                    self.cursor_x -= 1;
+                }
+            }
+            Key::RightArrow => {
+                self.cursor_x = (self.cursor_x + 1).min(self.line.len());
+            }
+            Key::Backspace => {
+                if self.cursor_x > 0 {
+                    output.outcome = Outcome::Changed;
+                    self.line.remove(self.cursor_x - 1);
+                    self.cursor_x -= 1;
+                }
+            }
+            _ => {
+                if let Some(c) = key.to_char(ctx.is_key_down(Key::LeftShift)) {
+                    output.outcome = Outcome::Changed;
+                    self.line.insert(self.cursor_x, c);
+                    self.cursor_x += 1;
+                } else {
+                    return false;
+                }
+            }
+        }
+        true
+    }
 }
 
 impl WidgetImpl for TextBox {
@@ -84,32 +115,9 @@ impl WidgetImpl for TextBox {
             return;
         }
         if let Some(key) = ctx.input.any_pressed() {
-            match key {
-                Key::LeftArrow => {
-                    if self.cursor_x > 0 {
-                        self.cursor_x -= 1;
-                    }
-                }
-                Key::RightArrow => {
-                    self.cursor_x = (self.cursor_x + 1).min(self.line.len());
-                }
-                Key::Backspace => {
-                    if self.cursor_x > 0 {
-                        output.outcome = Outcome::Changed;
-                        self.line.remove(self.cursor_x - 1);
-                        self.cursor_x -= 1;
-                    }
-                }
-                _ => {
-                    if let Some(c) = key.to_char(ctx.is_key_down(Key::LeftShift)) {
-                        output.outcome = Outcome::Changed;
-                        self.line.insert(self.cursor_x, c);
-                        self.cursor_x += 1;
-                    } else {
-                        ctx.input.unconsume_event();
-                    }
-                }
-            };
+            if !self.type_key(ctx, key, output) {
+                ctx.input.unconsume_event();
+            }
         }
     }
 
@@ -123,4 +131,18 @@ impl WidgetImpl for TextBox {
         let draw = g.upload(batch);
         g.redraw_at(self.top_left, &draw);
     }
+
+    fn accessibility(&self) -> Option<AccessibilityNode> {
+        // The panel fills in the label from the text box's name
+        Some(AccessibilityNode::new(Role::TextBox, "").value(self.line.clone()))
+    }
+
+    fn focused_key_press(
+        &mut self,
+        ctx: &mut EventCtx,
+        key: Key,
+        output: &mut WidgetOutput,
+    ) -> bool {
+        self.type_key(ctx, key, output)
+    }
 }